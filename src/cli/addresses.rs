@@ -0,0 +1,127 @@
+use anyhow::{Context, Result};
+use argh::FromArgs;
+
+use super::CliContext;
+use crate::config::{AddressBook, AddressBookEntry};
+use crate::util::*;
+
+#[derive(FromArgs)]
+/// Address book with named recipients
+#[argh(subcommand, name = "addresses")]
+pub struct Cmd {
+    #[argh(subcommand)]
+    subcommand: SubCmd,
+}
+
+impl Cmd {
+    pub async fn run(self, ctx: CliContext) -> Result<()> {
+        let path = &ctx.dirs().address_book;
+        let mut book = AddressBook::load(path)?;
+
+        let response = match self.subcommand {
+            SubCmd::Add(cmd) => {
+                anyhow::ensure!(
+                    !cmd.name.is_empty()
+                        && cmd
+                            .name
+                            .chars()
+                            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'),
+                    "name must consist of letters, digits, `-` or `_`"
+                );
+                anyhow::ensure!(
+                    cmd.force || !book.entries.contains_key(&cmd.name),
+                    "`{}` already exists, use `--force` to overwrite it",
+                    cmd.name
+                );
+
+                let address = parse_address(&cmd.address)?;
+                if let Some(name) = book.label(&address).filter(|name| *name != cmd.name) {
+                    print_warning(format!("address is already known as `{name}`"));
+                }
+
+                book.entries.insert(
+                    cmd.name.clone(),
+                    AddressBookEntry {
+                        address: address.clone(),
+                        note: cmd.note,
+                    },
+                );
+                book.store(path)?;
+                serde_json::json!({
+                    "name": cmd.name,
+                    "address": address.to_string(),
+                })
+            }
+            SubCmd::List(_) => serde_json::json!({
+                "entries": book
+                    .entries
+                    .iter()
+                    .map(|(name, entry)| {
+                        serde_json::json!({
+                            "name": name,
+                            "address": entry.address.to_string(),
+                            "note": entry.note,
+                        })
+                    })
+                    .collect::<Vec<_>>(),
+            }),
+            SubCmd::Remove(cmd) => {
+                let entry = book
+                    .entries
+                    .remove(&cmd.name)
+                    .with_context(|| format!("`{}` not found", cmd.name))?;
+                book.store(path)?;
+                serde_json::json!({
+                    "name": cmd.name,
+                    "address": entry.address.to_string(),
+                })
+            }
+        };
+
+        print_output(response);
+        Ok(())
+    }
+}
+
+#[derive(FromArgs)]
+#[argh(subcommand)]
+enum SubCmd {
+    Add(CmdAdd),
+    List(CmdList),
+    Remove(CmdRemove),
+}
+
+#[derive(FromArgs)]
+/// Adds a named address
+#[argh(subcommand, name = "add")]
+struct CmdAdd {
+    /// entry name
+    #[argh(positional)]
+    name: String,
+
+    /// account address
+    #[argh(positional)]
+    address: String,
+
+    /// optional description
+    #[argh(option)]
+    note: Option<String>,
+
+    /// overwrite an existing entry
+    #[argh(switch, short = 'f')]
+    force: bool,
+}
+
+#[derive(FromArgs)]
+/// Lists named addresses
+#[argh(subcommand, name = "list")]
+struct CmdList {}
+
+#[derive(FromArgs)]
+/// Removes a named address
+#[argh(subcommand, name = "remove")]
+struct CmdRemove {
+    /// entry name
+    #[argh(positional)]
+    name: String,
+}
@@ -0,0 +1,73 @@
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use argh::FromArgs;
+use dialoguer::console::style;
+use everscale_crypto::ed25519;
+
+use crate::config::StoredKeys;
+use crate::util::*;
+
+#[derive(FromArgs)]
+/// Signs the approval request of the dangerous operation (two-man rule)
+#[argh(subcommand, name = "approve")]
+pub struct Cmd {
+    /// approval request printed by the operator
+    #[argh(positional)]
+    request: String,
+
+    /// path to the approver keys
+    #[argh(option)]
+    keys: PathBuf,
+
+    /// never prompt
+    #[argh(switch, short = 'f')]
+    force: bool,
+}
+
+impl Cmd {
+    pub fn run(self) -> Result<()> {
+        let request = ApprovalRequest::decode(&self.request)?;
+        anyhow::ensure!(!request.is_expired(), "approval request expired");
+
+        let keys = StoredKeys::load(&self.keys).context("failed to load approver keys")?;
+        let secret = ed25519::SecretKey::from_bytes(keys.secret);
+
+        if is_terminal() {
+            let operation = match &request.operation {
+                ApprovalOperation::Transfer {
+                    wallet,
+                    destination,
+                    amount,
+                } => format!(
+                    "Transfer {:#}\nfrom {wallet}\nto {destination}",
+                    Tokens(*amount)
+                ),
+                ApprovalOperation::KeyRotation { keys } => format!("Rotation of {keys} keys"),
+            };
+            eprintln!(
+                "{}\n{}\n",
+                style("Operation:").green().bold(),
+                style(operation).bold()
+            );
+        }
+
+        if is_terminal()
+            && !self.force
+            && !confirm(
+                &dialoguer::theme::ColorfulTheme::default(),
+                false,
+                "Do you really want to approve this operation?",
+            )?
+        {
+            return Err(Declined.into());
+        }
+
+        let signature = sign_approval(&self.request, &secret);
+        print_output(serde_json::json!({
+            "approver": hex::encode(ed25519::PublicKey::from(&secret).as_bytes()),
+            "signature": hex::encode(signature),
+        }));
+        Ok(())
+    }
+}
@@ -0,0 +1,131 @@
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use argh::FromArgs;
+
+use super::CliContext;
+use crate::util::*;
+use crate::validator::{decrypt, make_local_backup, make_remote_backup};
+
+#[derive(FromArgs)]
+/// Backups of keys and configs
+#[argh(subcommand, name = "backup")]
+pub struct Cmd {
+    #[argh(subcommand)]
+    subcommand: SubCmd,
+}
+
+impl Cmd {
+    pub async fn run(self, ctx: CliContext) -> Result<()> {
+        match self.subcommand {
+            SubCmd::Run(cmd) => cmd.run(ctx).await,
+            SubCmd::Decrypt(cmd) => cmd.run(ctx),
+        }
+    }
+}
+
+#[derive(FromArgs)]
+#[argh(subcommand)]
+enum SubCmd {
+    Run(CmdRun),
+    Decrypt(CmdDecrypt),
+}
+
+#[derive(FromArgs)]
+/// Creates a backup now
+#[argh(subcommand, name = "run")]
+struct CmdRun {
+    /// local backups directory (`backups` in the root directory by default)
+    #[argh(option)]
+    dir: Option<PathBuf>,
+
+    /// name of the remote destination from the `backup` section
+    #[argh(option)]
+    destination: Option<String>,
+
+    /// how many backups to keep
+    #[argh(option, default = "7")]
+    keep: usize,
+}
+
+impl CmdRun {
+    async fn run(self, ctx: CliContext) -> Result<()> {
+        let dirs = ctx.dirs();
+
+        match self.destination {
+            Some(destination) => {
+                anyhow::ensure!(
+                    self.dir.is_none(),
+                    "`--dir` and `--destination` are exclusive"
+                );
+                let config = ctx
+                    .load_config()?
+                    .backup
+                    .context("backup section not found in the app config")?;
+                let name = make_remote_backup(dirs, &config, &destination, self.keep).await?;
+                print_output(serde_json::json!({
+                    "destination": destination,
+                    "name": name,
+                }));
+            }
+            None => {
+                let dir = self.dir.unwrap_or_else(|| dirs.root.join("backups"));
+                let path = make_local_backup(dirs, &dir, self.keep)?;
+                print_output(serde_json::json!({
+                    "path": path,
+                }));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(FromArgs)]
+/// Decrypts the backup downloaded from the remote destination
+#[argh(subcommand, name = "decrypt")]
+struct CmdDecrypt {
+    /// path to the encrypted backup
+    #[argh(positional)]
+    input: PathBuf,
+
+    /// path to the decrypted archive (input path without `.enc` by default)
+    #[argh(option, short = 'o')]
+    output: Option<PathBuf>,
+}
+
+impl CmdDecrypt {
+    fn run(self, ctx: CliContext) -> Result<()> {
+        let passphrase = match ctx
+            .load_config()
+            .ok()
+            .and_then(|config| config.backup?.passphrase)
+        {
+            Some(passphrase) => passphrase.as_str().to_owned(),
+            None if is_terminal() => dialoguer::Password::new()
+                .with_prompt("Backup passphrase")
+                .interact()?,
+            None => anyhow::bail!("backup passphrase not found in the app config"),
+        };
+
+        let output = match self.output {
+            Some(output) => output,
+            None => match self
+                .input
+                .to_str()
+                .and_then(|path| path.strip_suffix(".enc"))
+            {
+                Some(path) => PathBuf::from(path),
+                None => anyhow::bail!("output path is required"),
+            },
+        };
+
+        let data = std::fs::read(&self.input).context("failed to read encrypted backup")?;
+        let data = decrypt(&data, &passphrase)?;
+        std::fs::write(&output, data).context("failed to write decrypted backup")?;
+
+        print_output(serde_json::json!({
+            "path": output,
+        }));
+        Ok(())
+    }
+}
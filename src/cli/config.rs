@@ -0,0 +1,660 @@
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use argh::FromArgs;
+use everscale_crypto::ed25519;
+use tokio_util::sync::CancellationToken;
+
+use super::{check_key_rotation_policy, CliContext};
+use crate::config::{
+    merge_global_config, ApiRole, AppConfig, AppConfigApiToken, AppConfigControl,
+    AppConfigValidator, GlobalConfig, NetworksIndex, NodeConfig, NodeConfigAdnl, NodeConsoleConfig,
+    NodeConsoleControl, Secret,
+};
+use crate::contracts::Elector;
+use crate::dirs::VALIDATOR_SERVICE;
+use crate::network::{self, NodeTcpRpc, NodeUdpRpc, Subscription};
+use crate::util::*;
+use crate::validator::{Journal, DEFAULT_STAKE_FACTOR};
+
+#[derive(FromArgs)]
+/// Config management
+#[argh(subcommand, name = "config")]
+pub struct Cmd {
+    #[argh(subcommand)]
+    subcommand: SubCmd,
+}
+
+impl Cmd {
+    pub async fn run(self, ctx: CliContext) -> Result<()> {
+        let response = match self.subcommand {
+            SubCmd::RotateControlKeys(cmd) => cmd.run(ctx).await?,
+            SubCmd::RotateAdnlKeys(cmd) => cmd.run(ctx).await?,
+            SubCmd::UpdateGlobalConfig(cmd) => cmd.run(ctx).await?,
+            SubCmd::ApiToken(cmd) => cmd.run(ctx)?,
+            SubCmd::ExportConsole(cmd) => cmd.run(ctx)?,
+            SubCmd::Audit(cmd) => cmd.run(ctx).await?,
+        };
+
+        print_output(response);
+        Ok(())
+    }
+}
+
+#[derive(FromArgs)]
+#[argh(subcommand)]
+enum SubCmd {
+    RotateControlKeys(CmdRotateControlKeys),
+    RotateAdnlKeys(CmdRotateAdnlKeys),
+    UpdateGlobalConfig(CmdUpdateGlobalConfig),
+    ApiToken(CmdApiToken),
+    ExportConsole(CmdExportConsole),
+    Audit(CmdAudit),
+}
+
+#[derive(FromArgs)]
+/// Replaces the control client keypair
+#[argh(subcommand, name = "rotate-control-keys")]
+struct CmdRotateControlKeys {
+    /// do not restart the node service (it must be restarted manually)
+    #[argh(switch)]
+    no_restart: bool,
+
+    /// how long to wait for the node to accept the new key (in seconds). 120 seconds default
+    #[argh(option, default = "120")]
+    timeout: u64,
+}
+
+impl CmdRotateControlKeys {
+    async fn run(self, ctx: CliContext) -> Result<serde_json::Value> {
+        let dirs = ctx.dirs();
+
+        // NOTE: env overrides must not be stored back
+        let mut app_config = AppConfig::load_file(&dirs.app_config)?;
+        let mut node_config = NodeConfig::load(&dirs.node_config)?;
+
+        check_key_rotation_policy(&ctx, &app_config, "control")?;
+
+        let control = app_config
+            .control
+            .as_ref()
+            .context("control server is not configured")?;
+        anyhow::ensure!(
+            !control.client_secret.is_reference(),
+            "control client secret is stored outside of the app config"
+        );
+        let mut server = node_config
+            .get_control_server()?
+            .context("node control server is not configured")?;
+
+        let old_pubkey = ed25519::PublicKey::from(&*control.client_secret);
+        let new_secret = ed25519::SecretKey::generate(&mut rand::thread_rng());
+        let new_pubkey = ed25519::PublicKey::from(&new_secret);
+
+        let new_control = AppConfigControl {
+            client_secret: Secret::new(new_secret),
+            ..control.clone()
+        };
+
+        // Allow both keys until the new one is verified
+        if let Some(clients) = &mut server.clients {
+            clients.push(new_pubkey);
+            node_config.set_control_server(&server)?;
+            node_config.store(&dirs.node_config)?;
+        }
+
+        let restart = server.clients.is_some();
+        let wait = self.wait_for_new_key(&new_control, restart, ctx.cancellation());
+        if let Err(e) = wait.await {
+            // Revert node config
+            if let Some(clients) = &mut server.clients {
+                clients.retain(|key| key != &new_pubkey);
+                node_config.set_control_server(&server)?;
+                node_config.store(&dirs.node_config)?;
+            }
+            return Err(e);
+        }
+
+        // Remove the old key from the node config and use the new one
+        app_config.control = Some(new_control);
+        app_config.store(&dirs.app_config)?;
+        if let Some(clients) = &mut server.clients {
+            clients.retain(|key| key != &old_pubkey);
+            node_config.set_control_server(&server)?;
+            node_config.store(&dirs.node_config)?;
+        }
+
+        Ok(serde_json::json!({
+            "old_client_pubkey": hex::encode(old_pubkey.as_bytes()),
+            "new_client_pubkey": hex::encode(new_pubkey.as_bytes()),
+            // NOTE: the node still accepts the old key until the next restart
+            "restart_required": restart,
+        }))
+    }
+
+    async fn wait_for_new_key(
+        &self,
+        control: &AppConfigControl,
+        restart: bool,
+        cancellation: CancellationToken,
+    ) -> Result<()> {
+        const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+        // NOTE: node config is not changed when any clients are allowed
+        if restart {
+            if self.no_restart {
+                eprintln!("Restart the node to apply the new control key");
+            } else {
+                system::systemd_restart_service(VALIDATOR_SERVICE).await?;
+            }
+        }
+
+        let deadline = Instant::now() + Duration::from_secs(self.timeout);
+        loop {
+            let res = match NodeTcpRpc::new(control).await {
+                Ok(rpc_node) => rpc_node
+                    .with_cancellation(cancellation.clone())
+                    .get_stats()
+                    .await
+                    .map(|_| ()),
+                Err(e) => Err(e),
+            };
+
+            match res {
+                Ok(()) => return Ok(()),
+                Err(e) if Instant::now() >= deadline || cancellation.is_cancelled() => {
+                    return Err(e).context("node didn't accept the new control key");
+                }
+                Err(e) => {
+                    tracing::debug!("new control key is not accepted yet: {e:?}");
+                    tokio::select! {
+                        _ = tokio::time::sleep(POLL_INTERVAL) => {}
+                        _ = cancellation.cancelled() => {}
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[derive(FromArgs)]
+/// Replaces the node ADNL keys, previous keys are served until `--finish`
+#[argh(subcommand, name = "rotate-adnl-keys")]
+struct CmdRotateAdnlKeys {
+    /// remove the previous keys after the grace period
+    #[argh(switch)]
+    finish: bool,
+
+    /// do not restart the node service (it must be restarted manually)
+    #[argh(switch)]
+    no_restart: bool,
+}
+
+impl CmdRotateAdnlKeys {
+    async fn run(self, ctx: CliContext) -> Result<serde_json::Value> {
+        let dirs = ctx.dirs();
+
+        // NOTE: env overrides must not be stored back
+        let mut app_config = AppConfig::load_file(&dirs.app_config)?;
+        let mut node_config = NodeConfig::load(&dirs.node_config)?;
+
+        let mut adnl_node = node_config
+            .get_adnl_node()?
+            .context("node ADNL config not found")?;
+
+        if self.finish {
+            let removed = adnl_node.remove_retired_keys();
+            if removed {
+                node_config.set_adnl_node(&adnl_node)?;
+                node_config.store(&dirs.node_config)?;
+                self.restart_node().await?;
+            }
+
+            return Ok(serde_json::json!({
+                "retired_keys_removed": removed,
+            }));
+        }
+
+        check_key_rotation_policy(&ctx, &app_config, "adnl")?;
+
+        let adnl_client = app_config
+            .adnl
+            .as_mut()
+            .context("ADNL client is not configured")?;
+        anyhow::ensure!(
+            !adnl_node.has_retired_keys(),
+            "previous keys are still served, run with `--finish` first"
+        );
+
+        // Keep previous keys for the grace period
+        adnl_node.rotate_keys();
+        node_config.set_adnl_node(&adnl_node)?;
+        node_config.store(&dirs.node_config)?;
+
+        let server_pubkey = adnl_node.overlay_pubkey()?;
+        adnl_client.server_pubkey = server_pubkey;
+        adnl_client.server_address = adnl_node.ip_address;
+        app_config.store(&dirs.app_config)?;
+
+        self.restart_node().await?;
+
+        // Publish the new address record without waiting for the node
+        let overlay_key = &adnl_node.keys[&NodeConfigAdnl::OVERLAY_TAG];
+        let announced =
+            match network::announce_address(dirs, overlay_key, adnl_node.ip_address).await {
+                Ok(announced) => announced,
+                Err(e) => {
+                    print_warning(format!("failed to announce new address: {e:?}"));
+                    false
+                }
+            };
+
+        Ok(serde_json::json!({
+            "server_pubkey": hex::encode(server_pubkey.as_bytes()),
+            "announced": announced,
+        }))
+    }
+
+    async fn restart_node(&self) -> Result<()> {
+        if self.no_restart {
+            eprintln!("Restart the node to apply the new ADNL keys");
+            Ok(())
+        } else {
+            system::systemd_restart_service(VALIDATOR_SERVICE).await
+        }
+    }
+}
+
+#[derive(FromArgs)]
+/// Updates the stored global config from the network preset
+#[argh(subcommand, name = "update-global-config")]
+struct CmdUpdateGlobalConfig {
+    /// URL of the latest global config (network preset URL by default)
+    #[argh(option)]
+    url: Option<reqwest::Url>,
+
+    /// only print changes
+    #[argh(switch)]
+    dry_run: bool,
+
+    /// skip confirmation
+    #[argh(switch, short = 'f')]
+    force: bool,
+}
+
+impl CmdUpdateGlobalConfig {
+    async fn run(self, ctx: CliContext) -> Result<serde_json::Value> {
+        let dirs = ctx.dirs();
+
+        let current =
+            std::fs::read_to_string(&dirs.global_config).context("failed to read global config")?;
+
+        let url = match self.url {
+            Some(url) => url,
+            None => {
+                let global_config = serde_json::from_str::<GlobalConfig>(&current)?;
+                NetworksIndex::load(&dirs.networks_index)?
+                    .find_by_zerostate(global_config.zero_state.file_hash.as_array())
+                    .context("unknown network, global config URL must be specified")?
+                    .global_config_url
+                    .clone()
+            }
+        };
+
+        let latest = reqwest::get(url)
+            .await
+            .context("failed to download global config")?
+            .error_for_status()?
+            .text()
+            .await
+            .context("failed to download global config")?;
+
+        let (merged, update) = merge_global_config(&current, &latest)?;
+        let mut output = serde_json::to_value(&update)?;
+
+        let apply = !self.dry_run && !update.is_empty();
+        if apply
+            && is_terminal()
+            && !self.force
+            && !confirm(
+                &dialoguer::theme::ColorfulTheme::default(),
+                true,
+                "Apply global config changes?",
+            )?
+        {
+            output["applied"] = false.into();
+            return Ok(output);
+        }
+
+        if apply {
+            std::fs::write(&dirs.global_config, merged).context("failed to write global config")?;
+        }
+        output["applied"] = apply.into();
+        Ok(output)
+    }
+}
+
+#[derive(FromArgs)]
+/// Issues or revokes a named management API token
+#[argh(subcommand, name = "api-token")]
+struct CmdApiToken {
+    /// unique token name
+    #[argh(positional)]
+    name: String,
+
+    /// token role: `read_only` (default) or `operator`
+    #[argh(option, default = "ApiRole::ReadOnly")]
+    role: ApiRole,
+
+    /// remove the token instead
+    #[argh(switch)]
+    revoke: bool,
+}
+
+impl CmdApiToken {
+    fn run(self, ctx: CliContext) -> Result<serde_json::Value> {
+        let dirs = ctx.dirs();
+
+        // NOTE: env overrides must not be stored back
+        let mut app_config = AppConfig::load_file(&dirs.app_config)?;
+        let api = app_config
+            .api
+            .as_mut()
+            .context("management API is not configured")?;
+        anyhow::ensure!(self.name != "default", "`default` token name is reserved");
+
+        let existing = api.tokens.iter().position(|item| item.name == self.name);
+        let output = if self.revoke {
+            let index = existing.context("API token not found")?;
+            api.tokens.remove(index);
+            serde_json::json!({ "name": self.name, "revoked": true })
+        } else {
+            anyhow::ensure!(existing.is_none(), "API token already exists");
+
+            let token = hex::encode(rand::random::<[u8; 32]>());
+            api.tokens.push(AppConfigApiToken {
+                name: self.name.clone(),
+                token: Secret::new(token.clone()),
+                role: self.role,
+            });
+            serde_json::json!({ "name": self.name, "role": self.role, "token": token })
+        };
+
+        app_config.store(&dirs.app_config)?;
+        Ok(output)
+    }
+}
+
+#[derive(FromArgs)]
+/// Writes the config for the node console tool with the same control server keys
+#[argh(subcommand, name = "export-console")]
+struct CmdExportConsole {
+    /// path to the console config. `$ROOT/console.json` default
+    #[argh(positional)]
+    path: Option<PathBuf>,
+}
+
+impl CmdExportConsole {
+    fn run(self, ctx: CliContext) -> Result<serde_json::Value> {
+        const STAKE_FACTOR_ONE: f64 = 65536.0;
+
+        let config = ctx.load_config()?;
+        let control = config.control()?;
+
+        let stake_factor = match &config.validator {
+            Some(AppConfigValidator::Single(single)) => single.stake_factor,
+            Some(AppConfigValidator::DePool(depool)) => depool.stake_factor,
+            None => None,
+        };
+
+        let console_config = NodeConsoleConfig {
+            config: NodeConsoleControl {
+                server_address: control.server_address,
+                server_key: control.server_pubkey,
+                client_key: ed25519::SecretKey::from_bytes(*control.client_secret.as_bytes()),
+            },
+            wallet_id: config
+                .validator
+                .as_ref()
+                .map(|validator| validator.wallet_address().to_string()),
+            max_factor: stake_factor.map(|factor| factor as f64 / STAKE_FACTOR_ONE),
+        };
+
+        let path = self
+            .path
+            .unwrap_or_else(|| ctx.dirs().root.join("console.json"));
+        console_config.store(&path)?;
+
+        let client_pubkey = ed25519::PublicKey::from(&*control.client_secret);
+        Ok(serde_json::json!({
+            "path": path,
+            "server_address": control.server_address,
+            "client_pubkey": hex::encode(client_pubkey.as_bytes()),
+        }))
+    }
+}
+
+#[derive(FromArgs)]
+/// Checks the validator setup against the current blockchain config
+#[argh(subcommand, name = "audit")]
+struct CmdAudit {}
+
+impl CmdAudit {
+    async fn run(self, ctx: CliContext) -> Result<serde_json::Value> {
+        const STAKE_FACTOR_ONE: u32 = 65536;
+
+        let mut config = ctx.load_config()?;
+        let validator = config
+            .validator
+            .take()
+            .context("validator is not configured")?;
+
+        let node_tcp_rpc = NodeTcpRpc::new(config.control()?)
+            .await
+            .context("failed to build node TCP client")?
+            .with_cancellation(ctx.cancellation());
+        let node_udp_rpc = NodeUdpRpc::new(config.adnl()?, ctx.dirs())
+            .await
+            .context("failed to build node UDP client")?
+            .with_cancellation(ctx.cancellation());
+        let subscription = Subscription::new(node_tcp_rpc, node_udp_rpc);
+        if let Some(network) = config.network.take() {
+            subscription.set_network_params(network);
+        }
+        subscription.ensure_ready().await?;
+
+        // Fetch everything at once
+        let elector = Elector::new(
+            subscription.get_system_addresses().await?.elector,
+            subscription.clone(),
+        );
+        let wallet_address = validator.wallet_address();
+        let (blockchain_config, elector_data, wallet_state) = futures_util::future::try_join3(
+            subscription.get_blockchain_config(),
+            elector.get_data(),
+            subscription.get_account_state(wallet_address),
+        )
+        .await?;
+        let blockchain_config = &blockchain_config.config;
+        let stakes_config = blockchain_config
+            .stakes_config()
+            .context("invalid stakes config")?;
+        let timings = blockchain_config
+            .elector_params()
+            .context("invalid elector params")?;
+
+        let mut findings = Findings::default();
+
+        // Stake
+        let (stake_factor, participant) = match &validator {
+            AppConfigValidator::Single(single) => {
+                let stake = single.stake_per_round as u128;
+                let min_stake = stakes_config.min_stake.as_u128();
+                let max_stake = stakes_config.max_stake.as_u128();
+                if stake < min_stake {
+                    findings.error(
+                        "stake",
+                        format!(
+                            "stake per round {} is less than the min stake {}, \
+                            increase `validator.stake_per_round`",
+                            Tokens(stake),
+                            Tokens(min_stake)
+                        ),
+                    );
+                } else if stake > max_stake {
+                    findings.warning(
+                        "stake",
+                        format!(
+                            "stake per round {} exceeds the max stake {}, \
+                            the rest will be returned by the elector",
+                            Tokens(stake),
+                            Tokens(max_stake)
+                        ),
+                    );
+                } else {
+                    findings.ok("stake", "stake per round is within the allowed range");
+                }
+                (single.stake_factor, Some(&single.address))
+            }
+            AppConfigValidator::DePool(depool) => {
+                findings.ok("stake", "stake is managed by the DePool");
+                (depool.stake_factor, None)
+            }
+        };
+
+        // Max factor
+        let stake_factor = stake_factor.unwrap_or(DEFAULT_STAKE_FACTOR);
+        let max_stake_factor = stakes_config.max_stake_factor;
+        let factor = |value: u32| value as f64 / STAKE_FACTOR_ONE as f64;
+        if stake_factor < STAKE_FACTOR_ONE {
+            findings.error(
+                "max_factor",
+                format!(
+                    "max factor {} is less than 1.0, elector rejects such requests",
+                    factor(stake_factor)
+                ),
+            );
+        } else if stake_factor > max_stake_factor {
+            findings.error(
+                "max_factor",
+                format!(
+                    "max factor {} exceeds the network limit {}, \
+                    decrease `validator.stake_factor`",
+                    factor(stake_factor),
+                    factor(max_stake_factor)
+                ),
+            );
+        } else {
+            findings.ok("max_factor", format!("max factor {}", factor(stake_factor)));
+        }
+
+        // Validator keys and ADNL address
+        let journal = Journal::load(ctx.dirs().validation_journal.clone());
+        let state = journal.state();
+        match (elector_data.election_id(), state.election_id, &state.keys) {
+            (Some(election_id), Some(id), Some(keys)) if id == election_id => {
+                // NOTE: keys are registered until the stake of this round is unfrozen
+                let ttl = election_id
+                    + timings.validators_elected_for
+                    + timings.elections_start_before
+                    + timings.elections_end_before
+                    + timings.stake_held_for;
+
+                let public_key = subscription
+                    .tcp_rpc()?
+                    .export_public_key(&keys.permanent_key_hash)
+                    .await
+                    .context("failed to export validator public key")?;
+                let conflicting_bid = participant.and_then(|address| {
+                    elector_data.find_conflicting_bid(
+                        address,
+                        &ton_types::UInt256::from(public_key.to_bytes()),
+                        &ton_types::UInt256::from(keys.adnl_addr),
+                    )
+                });
+                match conflicting_bid {
+                    Some(bid) => findings.error(
+                        "adnl",
+                        format!(
+                            "elector has a bid with other keys (adnl {}), \
+                            the node will not participate with them",
+                            bid.adnl_addr.to_hex_string()
+                        ),
+                    ),
+                    None => findings.ok(
+                        "adnl",
+                        format!(
+                            "ADNL address {} must be kept until {ttl}",
+                            hex::encode(keys.adnl_addr)
+                        ),
+                    ),
+                }
+            }
+            (Some(election_id), _, _) => findings.warning(
+                "adnl",
+                format!(
+                    "no validator keys registered for elections {election_id} yet, \
+                    the manager generates them when it sends the request"
+                ),
+            ),
+            (None, ..) => findings.ok("adnl", "elections are not open"),
+        }
+
+        // Wallet workchain
+        match &validator {
+            AppConfigValidator::Single(single) if !single.address.is_masterchain() => findings
+                .error(
+                    "wallet",
+                    "validator wallet must be in the masterchain to send election requests",
+                ),
+            AppConfigValidator::DePool(depool) if depool.depool.workchain_id() != 0 => {
+                findings.error("wallet", "DePool must be deployed in the basechain")
+            }
+            _ if wallet_state.is_none() => {
+                findings.error("wallet", "validator wallet not deployed")
+            }
+            _ => findings.ok("wallet", "wallet is deployed in the expected workchain"),
+        }
+
+        Ok(serde_json::json!({
+            "errors": findings.errors,
+            "warnings": findings.warnings,
+            "findings": findings.items,
+        }))
+    }
+}
+
+#[derive(Default)]
+struct Findings {
+    items: Vec<serde_json::Value>,
+    errors: usize,
+    warnings: usize,
+}
+
+impl Findings {
+    fn ok(&mut self, check: &str, message: impl Into<String>) {
+        self.push("ok", check, message.into());
+    }
+
+    fn warning(&mut self, check: &str, message: impl Into<String>) {
+        self.warnings += 1;
+        self.push("warning", check, message.into());
+    }
+
+    fn error(&mut self, check: &str, message: impl Into<String>) {
+        self.errors += 1;
+        self.push("error", check, message.into());
+    }
+
+    fn push(&mut self, severity: &str, check: &str, message: String) {
+        if severity != "ok" {
+            print_warning(format!("{check}: {message}"));
+        }
+        self.items.push(serde_json::json!({
+            "check": check,
+            "severity": severity,
+            "message": message,
+        }));
+    }
+}
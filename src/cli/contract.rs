@@ -5,10 +5,10 @@ use argh::FromArgs;
 use nekoton_abi::FunctionExt;
 use ton_block::{Deserializable, Serializable};
 
-use super::CliContext;
+use super::{check_spending_policy, estimate_transfer, CliContext};
 use crate::config::{AppConfigValidator, StoredKeys};
-use crate::contracts::{wallet, InternalMessage, ONE_EVER};
-use crate::network::{NodeTcpRpc, NodeUdpRpc, Subscription};
+use crate::contracts::{wallet, InternalMessage};
+use crate::network::{connect_subscription, NodeTcpRpc};
 use crate::util::*;
 
 #[derive(FromArgs)]
@@ -24,6 +24,7 @@ impl Cmd {
         let response = match self.subcommand {
             SubCmd::StateInit(cmd) => cmd.run()?,
             SubCmd::Call(cmd) => cmd.run(ctx).await?,
+            SubCmd::Run(cmd) => cmd.run(ctx).await?,
             SubCmd::Sendx(cmd) => cmd.run(ctx).await?,
             SubCmd::Send(cmd) => cmd.run(ctx).await?,
         };
@@ -56,6 +57,7 @@ async fn get_account_stuff(
 enum SubCmd {
     StateInit(CmdStateInit),
     Call(CmdCall),
+    Run(CmdRun),
     Sendx(CmdSendx),
     Send(CmdSend),
 }
@@ -153,7 +155,7 @@ struct CmdCall {
     #[argh(option, short = 'a')]
     abi: PathBuf,
 
-    /// contract address
+    /// contract address or name from the address book
     #[argh(option, short = 'd', long = "addr")]
     address: String,
 
@@ -166,11 +168,13 @@ impl CmdCall {
     async fn run(self, ctx: CliContext) -> Result<serde_json::Value> {
         let config = ctx.load_config()?;
 
-        let node_rpc = NodeTcpRpc::new(config.control()?).await?;
+        let node_rpc = NodeTcpRpc::new(config.control()?)
+            .await?
+            .with_cancellation(ctx.cancellation());
 
         let clock = nekoton_utils::SimpleClock;
 
-        let address = parse_address(&self.address)?;
+        let address = ctx.resolve_address(&self.address)?;
         let method = parse_contract_method(&self.abi, &self.method)?;
         let input = nekoton_abi::parse_abi_tokens(&method.inputs, self.args)?;
 
@@ -196,6 +200,76 @@ impl CmdCall {
     }
 }
 
+#[derive(FromArgs)]
+/// Executes the specified method locally using the provided ABI
+#[argh(subcommand, name = "run")]
+struct CmdRun {
+    /// path to the JSON ABI file
+    #[argh(positional)]
+    abi: PathBuf,
+
+    /// contract address or name from the address book
+    #[argh(positional)]
+    address: String,
+
+    /// method name
+    #[argh(positional)]
+    method: String,
+
+    /// method args
+    #[argh(positional, default = "default_args()")]
+    args: serde_json::Value,
+}
+
+impl CmdRun {
+    async fn run(self, ctx: CliContext) -> Result<serde_json::Value> {
+        let config = ctx.load_config()?;
+
+        let node_rpc = NodeTcpRpc::new(config.control()?)
+            .await?
+            .with_cancellation(ctx.cancellation());
+
+        let clock = nekoton_utils::SimpleClock;
+
+        let address = ctx.resolve_address(&self.address)?;
+        let method = parse_contract_method(&self.abi, &self.method)?;
+
+        // Responsible methods have `answerId` as the first input
+        let responsible = matches!(
+            method.inputs.first(),
+            Some(param) if param.name == "answerId" && param.kind == ton_abi::ParamType::Uint(32)
+        );
+
+        let mut args = self.args;
+        if responsible {
+            if let serde_json::Value::Object(args) = &mut args {
+                args.entry("answerId").or_insert(serde_json::json!(0));
+            }
+        }
+        let input = nekoton_abi::parse_abi_tokens(&method.inputs, args)?;
+
+        let account_stuff = get_account_stuff(&node_rpc, &address).await?;
+
+        let nekoton_abi::ExecutionOutput {
+            result_code,
+            tokens,
+        } = match responsible {
+            false => method.run_local(&clock, account_stuff, &input)?,
+            true => method.run_local_responsible(&clock, account_stuff, &input)?,
+        };
+
+        let output = tokens
+            .as_deref()
+            .map(nekoton_abi::make_abi_tokens)
+            .transpose()?;
+
+        Ok(serde_json::json!({
+            "code": result_code,
+            "output": output,
+        }))
+    }
+}
+
 #[derive(FromArgs)]
 /// Sends an external message
 #[argh(subcommand, name = "sendx")]
@@ -212,7 +286,7 @@ struct CmdSendx {
     #[argh(option, short = 'a', long = "abi")]
     abi: PathBuf,
 
-    /// destination address
+    /// destination address or name from the address book
     #[argh(option, short = 'd', long = "dest")]
     destination: String,
 
@@ -220,7 +294,7 @@ struct CmdSendx {
     #[argh(option, short = 't', default = "60")]
     timeout: u32,
 
-    /// seed phrase or path to the keys
+    /// path to the keys or stored keys name (`validator` or `depool`)
     #[argh(option, short = 's')]
     sign: Option<PathBuf>,
 
@@ -233,16 +307,8 @@ impl CmdSendx {
     async fn run(self, ctx: CliContext) -> Result<serde_json::Value> {
         let config = ctx.load_config()?;
 
-        // Prepare RPC clients
-        let node_tcp_rpc = NodeTcpRpc::new(config.control()?)
-            .await
-            .context("failed to build node TCP client")?;
-        let node_udp_rpc = NodeUdpRpc::new(config.adnl()?)
-            .await
-            .context("failed to build node UDP client")?;
-
         // Parse arguments
-        let address = parse_address(&self.destination)?;
+        let address = ctx.resolve_address(&self.destination)?;
 
         let abi = parse_contract_abi(&self.abi)?;
         let method = abi
@@ -251,14 +317,24 @@ impl CmdSendx {
             .with_context(|| format!("method `{}` not found", self.method))?;
 
         let input = nekoton_abi::parse_abi_tokens(&method.inputs, self.args)?;
-        let keys = self.sign.map(StoredKeys::load_as_keypair).transpose()?;
+        let keys = self
+            .sign
+            .map(|path| {
+                let path = match path.to_str() {
+                    Some("validator") => ctx.dirs.validator_keys.clone(),
+                    Some("depool") => ctx.dirs.depool_keys.clone(),
+                    _ => path,
+                };
+                StoredKeys::load_as_keypair(path)
+            })
+            .transpose()?;
         let state_init = parse_optional_state_init(self.state_init)?;
 
-        // Check whether the node is running
-        node_tcp_rpc.get_stats().await?.try_into_running()?;
-
-        // Create subscription
-        let subscription = Subscription::new(node_tcp_rpc, node_udp_rpc);
+        // Create subscription (falls back to the remote endpoint without the node)
+        let subscription = connect_subscription(&config, ctx.dirs(), ctx.cancellation()).await?;
+        if let Some(policy) = spending_policy {
+            subscription.set_spending_policy(policy);
+        }
         let signature_id = subscription.get_signature_id().await?;
 
         // Prepare external message
@@ -292,7 +368,7 @@ impl CmdSendx {
         } = subscription
             .send_message(&message, expire_at)
             .await?
-            .context("message expired")?;
+            .into_result()?;
 
         // Parse transaction
         let msg_hash = tx
@@ -344,19 +420,19 @@ struct CmdSend {
     #[argh(option, short = 'a', long = "abi")]
     abi: PathBuf,
 
-    /// destination address
+    /// destination address or name from the address book
     #[argh(option, short = 'd', long = "dest")]
     destination: String,
 
-    /// amount to send in tokens
+    /// amount to send (e.g. `1250.5` or `1e9 nano`)
     #[argh(option)]
-    amount: u128,
+    amount: String,
 
     /// send this message with a bounce flag set
     #[argh(switch)]
     bounce: bool,
 
-    /// interpret amount as amount in nano tokens
+    /// interpret amount without a unit as nano tokens
     #[argh(switch)]
     nano: bool,
 }
@@ -364,27 +440,16 @@ struct CmdSend {
 impl CmdSend {
     async fn run(self, ctx: CliContext) -> Result<serde_json::Value> {
         let mut config = ctx.load_config()?;
+        let spending_policy = ctx.load_spending_policy(&config)?;
         let validator = config
             .validator
             .take()
             .context("validator entry not found in the app config")?;
-        let currency = config.currency();
-
-        // Prepare RPC clients
-        let node_tcp_rpc = NodeTcpRpc::new(config.control()?)
-            .await
-            .context("failed to build node TCP client")?;
-        let node_udp_rpc = NodeUdpRpc::new(config.adnl()?)
-            .await
-            .context("failed to build node UDP client")?;
 
         // Parse arguments
-        let dest = parse_address(&self.destination)?;
+        let dest = ctx.resolve_address(&self.destination)?;
 
-        let mut amount = self.amount;
-        if !self.nano {
-            amount = amount.saturating_mul(ONE_EVER);
-        }
+        let amount = parse_tokens(&self.amount, self.nano)?;
 
         let abi = parse_contract_abi(&self.abi)?;
         let method = abi
@@ -395,11 +460,8 @@ impl CmdSend {
         let input = nekoton_abi::parse_abi_tokens(&method.inputs, self.args)?;
         let payload = method.encode_internal_input(&input)?.into_cell()?;
 
-        // Check whether the node is running
-        node_tcp_rpc.get_stats().await?.try_into_running()?;
-
-        // Create subscription
-        let subscription = Subscription::new(node_tcp_rpc, node_udp_rpc);
+        // Create subscription (falls back to the remote endpoint without the node)
+        let subscription = connect_subscription(&config, ctx.dirs(), ctx.cancellation()).await?;
 
         // Prepare wallet
         let keypair = StoredKeys::load(&ctx.dirs.validator_keys)
@@ -422,22 +484,24 @@ impl CmdSend {
         let wallet_balance = wallet.get_balance().await?.unwrap_or_default();
         anyhow::ensure!(
             amount < wallet_balance,
-            "wallet balance is not enough ({} {currency})",
+            "wallet balance is not enough ({:#})",
             Tokens(wallet_balance)
         );
 
+        let message = InternalMessage {
+            dst: dest,
+            amount,
+            payload,
+            bounce: self.bounce,
+        };
+        estimate_transfer(&wallet, &message).await?;
+        check_spending_policy(&wallet, &message).await?;
+
         // Send external message and wait until it is delivered
         let TransactionWithHash {
             hash: tx_hash,
             data: tx,
-        } = wallet
-            .transfer(InternalMessage {
-                dst: dest,
-                amount,
-                payload,
-                bounce: self.bounce,
-            })
-            .await?;
+        } = wallet.transfer(message).await?;
 
         // Parse transaction
         let msg_hash = tx.in_msg.context("inbound message not found")?.hash();
@@ -0,0 +1,237 @@
+use std::fmt::Write;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use argh::FromArgs;
+use dialoguer::console::{style, Term};
+
+use super::logs::read_last_lines;
+use super::CliContext;
+use crate::config::{AppConfig, AppConfigValidator};
+use crate::dirs::VALIDATOR_MANAGER_SERVICE;
+use crate::logging::log_file_path;
+use crate::network::{DataSource, NodeStats, NodeTcpRpc, ValidatorSetEntry};
+use crate::util::*;
+use crate::validator::IncidentHistory;
+
+#[derive(FromArgs)]
+/// Plain-text live view of the node and validator state
+#[argh(subcommand, name = "dashboard")]
+pub struct Cmd {
+    /// refresh interval (in seconds). 5 seconds default
+    #[argh(option, short = 'i', default = "5")]
+    interval: u64,
+
+    /// number of recent manager events. 10 by default
+    #[argh(option, short = 'n', default = "10")]
+    events: usize,
+}
+
+impl Cmd {
+    pub async fn run(self, ctx: CliContext) -> Result<()> {
+        anyhow::ensure!(is_terminal(), "dashboard requires a terminal");
+
+        let term = Term::stdout();
+        term.hide_cursor()?;
+        let _cursor = CursorGuard(term.clone());
+
+        let interval = Duration::from_secs(std::cmp::max(self.interval, 1));
+        let mut node_tcp_rpc = None;
+        let cancellation = ctx.cancellation();
+        loop {
+            let mut frame = String::new();
+            if let Err(e) = self.render(&ctx, &mut node_tcp_rpc, &mut frame).await {
+                writeln!(frame, "\n{}", style(format!("✘ {e:?}")).red())?;
+            }
+
+            term.move_cursor_to(0, 0)?;
+            term.clear_to_end_of_screen()?;
+            term.write_str(&frame)?;
+
+            tokio::select! {
+                _ = tokio::time::sleep(interval) => {}
+                _ = cancellation.cancelled() => return Ok(()),
+            }
+        }
+    }
+
+    async fn render(
+        &self,
+        ctx: &CliContext,
+        node_tcp_rpc: &mut Option<NodeTcpRpc>,
+        frame: &mut String,
+    ) -> Result<()> {
+        let now = broxus_util::now();
+        writeln!(
+            frame,
+            "{} {}\n",
+            style("nodekeeper dashboard").bold(),
+            note(format!("updated at {now}, press Ctrl+C to exit"))
+        )?;
+
+        // Recent events don't require the node
+        let mut events = Vec::new();
+        let config = ctx.load_config();
+        if let Ok(AppConfig {
+            logging: Some(logging),
+            ..
+        }) = &config
+        {
+            let path = log_file_path(&logging.logs_dir(ctx.dirs()), VALIDATOR_MANAGER_SERVICE);
+            events = read_last_lines(&path, self.events).unwrap_or_default();
+        }
+        let incidents = IncidentHistory::new(&ctx.dirs().incident_history)
+            .load()
+            .unwrap_or_default();
+
+        let result = async {
+            let config = config?;
+            let rpc = match node_tcp_rpc.take() {
+                Some(rpc) => rpc,
+                None => NodeTcpRpc::new(config.control()?)
+                    .await?
+                    .with_cancellation(ctx.cancellation()),
+            };
+            render_node(&rpc, &config, now, frame).await?;
+
+            // NOTE: reconnect only after errors
+            *node_tcp_rpc = Some(rpc);
+            Ok::<_, anyhow::Error>(())
+        }
+        .await;
+
+        panel(frame, "Recent manager events")?;
+        if events.is_empty() {
+            writeln!(frame, "  {}", note("logging to files is not configured"))?;
+        }
+        for line in &events {
+            writeln!(frame, "  {line}")?;
+        }
+
+        panel(frame, "Recent incidents")?;
+        if incidents.is_empty() {
+            writeln!(frame, "  {}", note("none"))?;
+        }
+        for incident in incidents.iter().rev().take(5) {
+            writeln!(frame, "  {} {}", incident.timestamp, incident.kind)?;
+        }
+
+        result
+    }
+}
+
+/// Restores the hidden cursor on any exit from the dashboard (including errors)
+struct CursorGuard(Term);
+
+impl Drop for CursorGuard {
+    fn drop(&mut self) {
+        self.0.show_cursor().ok();
+    }
+}
+
+async fn render_node(
+    node_tcp_rpc: &NodeTcpRpc,
+    config: &AppConfig,
+    now: u32,
+    frame: &mut String,
+) -> Result<()> {
+    // Sync status
+    panel(frame, "Node")?;
+    let stats = match node_tcp_rpc.get_stats().await? {
+        NodeStats::Running(stats) => stats,
+        NodeStats::NotReady(sync_status) => {
+            field(frame, "sync status", style(sync_status).yellow())?;
+            return Ok(());
+        }
+    };
+    let node_version = &stats.node_version;
+    field(
+        frame,
+        "version",
+        format!(
+            "{}.{}.{}",
+            node_version.major, node_version.minor, node_version.patch
+        ),
+    )?;
+    field(frame, "sync status", style(stats.sync_status).green())?;
+    field(frame, "mc seqno", stats.last_mc_block.seq_no)?;
+    field(frame, "mc time diff", format!("{}s", stats.mc_time_diff))?;
+    field(frame, "sc time diff", format!("{}s", stats.sc_time_diff))?;
+
+    // Validator set membership
+    panel(frame, "Validator set")?;
+    let vset_entry = |entry: &ValidatorSetEntry| match entry {
+        ValidatorSetEntry::None => style("no".to_owned()).dim(),
+        ValidatorSetEntry::Validator(adnl) => {
+            style(format!("yes, adnl {}", hex::encode(adnl))).green()
+        }
+    };
+    field(frame, "current", vset_entry(&stats.in_current_vset))?;
+    field(frame, "next", vset_entry(&stats.in_next_vset))?;
+
+    // Elections
+    panel(frame, "Elections")?;
+    let blockchain_config = node_tcp_rpc.get_blockchain_config().await?;
+    let timings = blockchain_config
+        .elector_params()
+        .context("invalid elector params")?;
+    let current_vset = blockchain_config
+        .validator_set()
+        .context("invalid validator set")?;
+    let round_end = current_vset.utime_until();
+    let elections_start = round_end.saturating_sub(timings.elections_start_before);
+    let elections_end = round_end.saturating_sub(timings.elections_end_before);
+    if now < elections_start {
+        field(frame, "starts in", format_duration(elections_start - now))?;
+    } else if now < elections_end {
+        field(frame, "status", style("open").green())?;
+        field(frame, "ends in", format_duration(elections_end - now))?;
+    } else {
+        field(frame, "status", "finished")?;
+        field(
+            frame,
+            "next round in",
+            format_duration(round_end.saturating_sub(now)),
+        )?;
+    }
+
+    // Balances
+    panel(frame, "Balances")?;
+    let mut accounts = Vec::new();
+    match &config.validator {
+        Some(AppConfigValidator::Single(single)) => accounts.push(("wallet", &single.address)),
+        Some(AppConfigValidator::DePool(depool)) => {
+            accounts.push(("wallet", &depool.owner));
+            accounts.push(("depool", &depool.depool));
+        }
+        None => writeln!(frame, "  {}", note("validation is not configured"))?,
+    }
+    for (name, address) in accounts {
+        let balance = node_tcp_rpc
+            .get_account_state(address)
+            .await?
+            .map(|state| state.storage.balance.grams.as_u128())
+            .unwrap_or_default();
+        field(frame, name, format!("{:#}", Tokens(balance)))?;
+    }
+
+    Ok(())
+}
+
+fn panel(frame: &mut String, title: &str) -> std::fmt::Result {
+    writeln!(frame, "\n{}", style(title).bold().underlined())
+}
+
+fn field(frame: &mut String, name: &str, value: impl std::fmt::Display) -> std::fmt::Result {
+    let name = format!("{name}:");
+    writeln!(frame, "  {}{value}", style(format!("{name:<14}")).dim())
+}
+
+fn format_duration(seconds: u32) -> String {
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if hours > 0 {
+        format!("{hours}h {minutes:02}m {seconds:02}s")
+    } else {
+        format!("{minutes}m {seconds:02}s")
+    }
+}
@@ -0,0 +1,208 @@
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+use anyhow::{Context, Result};
+use argh::FromArgs;
+
+use super::CliContext;
+use crate::config::NodeConfig;
+use crate::network::NodeTcpRpc;
+use crate::util::*;
+
+#[derive(FromArgs)]
+/// Node DB maintenance
+#[argh(subcommand, name = "db")]
+pub struct Cmd {
+    #[argh(subcommand)]
+    subcommand: SubCmd,
+}
+
+impl Cmd {
+    pub async fn run(self, ctx: CliContext) -> Result<()> {
+        let response = match self.subcommand {
+            SubCmd::Size(cmd) => cmd.run(ctx)?,
+            SubCmd::Gc(cmd) => cmd.run(ctx).await?,
+            SubCmd::PruneArchives(cmd) => cmd.run(ctx).await?,
+        };
+
+        print_output(response);
+        Ok(())
+    }
+}
+
+#[derive(FromArgs)]
+#[argh(subcommand)]
+enum SubCmd {
+    Size(CmdSize),
+    Gc(CmdGc),
+    PruneArchives(CmdPruneArchives),
+}
+
+#[derive(FromArgs)]
+/// Prints node DB size per column
+#[argh(subcommand, name = "size")]
+struct CmdSize {}
+
+impl CmdSize {
+    fn run(self, ctx: CliContext) -> Result<serde_json::Value> {
+        let db_path = node_db_path(&ctx)?;
+
+        let mut columns = Vec::new();
+        for entry in std::fs::read_dir(&db_path).context("failed to read node DB dir")? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let size = system::dir_size(entry.path())
+                .with_context(|| format!("failed to compute `{name}` size"))?;
+            columns.push((name, size));
+        }
+        columns.sort_unstable_by(|(_, a), (_, b)| b.cmp(a));
+
+        let total = columns.iter().map(|(_, size)| size).sum::<u64>();
+        let disk = system::statvfs(&db_path)?;
+
+        Ok(serde_json::json!({
+            "path": db_path,
+            "total": total,
+            "columns": columns
+                .into_iter()
+                .map(|(name, size)| serde_json::json!({ "name": name, "size": size }))
+                .collect::<Vec<_>>(),
+            "disk": {
+                "total": disk.total_space,
+                "available": disk.available_space,
+            },
+        }))
+    }
+}
+
+#[derive(FromArgs)]
+/// Forces states GC by temporarily reducing its interval
+#[argh(subcommand, name = "gc")]
+struct CmdGc {
+    /// temporary states GC interval (in milliseconds). 1000 ms default
+    #[argh(option, default = "1000")]
+    interval: u32,
+
+    /// how long to keep the reduced interval (in seconds). 60 seconds default
+    #[argh(option, default = "60")]
+    duration: u64,
+}
+
+impl CmdGc {
+    async fn run(self, ctx: CliContext) -> Result<serde_json::Value> {
+        const DEFAULT_GC_INTERVAL_SEC: u32 = 900;
+
+        // Restore the configured interval afterwards
+        let configured_interval_ms = NodeConfig::load(&ctx.dirs.node_config)
+            .ok()
+            .and_then(|config| config.get_gc().ok().flatten())
+            .map(|gc| gc.cells_gc_config.gc_interval_sec)
+            .unwrap_or(DEFAULT_GC_INTERVAL_SEC)
+            .saturating_mul(1000);
+
+        let rpc_node = create_rpc_node(&ctx).await?;
+        rpc_node
+            .set_states_gc_interval(self.interval)
+            .await
+            .context("states GC control is not supported by the node")?;
+
+        eprintln!(
+            "States GC interval reduced to {} ms for {} s",
+            self.interval, self.duration
+        );
+        tokio::time::sleep(Duration::from_secs(self.duration)).await;
+
+        rpc_node
+            .set_states_gc_interval(configured_interval_ms)
+            .await
+            .context("failed to restore states GC interval")?;
+
+        Ok(serde_json::json!({
+            "gc_interval_ms": configured_interval_ms,
+        }))
+    }
+}
+
+#[derive(FromArgs)]
+/// Removes old block archives
+#[argh(subcommand, name = "prune-archives")]
+struct CmdPruneArchives {
+    /// remove archives older than the specified number of hours
+    #[argh(option)]
+    older_than: Option<u64>,
+
+    /// remove the oldest archives until their total size fits (in GB)
+    #[argh(option)]
+    max_size: Option<u64>,
+
+    /// only print archives which will be removed
+    #[argh(switch)]
+    dry_run: bool,
+}
+
+impl CmdPruneArchives {
+    async fn run(self, ctx: CliContext) -> Result<serde_json::Value> {
+        anyhow::ensure!(
+            self.older_than.is_some() || self.max_size.is_some(),
+            "either `--older-than` or `--max-size` must be specified"
+        );
+
+        let archives = system::list_archive_packages(node_db_path(&ctx)?)?;
+
+        let now = SystemTime::now();
+        let mut remaining = archives.iter().map(|archive| archive.size).sum::<u64>();
+        let max_size = self.max_size.map(|gb| gb << 30);
+
+        let mut to_remove = Vec::new();
+        for archive in archives {
+            let expired = matches!(self.older_than, Some(hours)
+                if now.duration_since(archive.modified).unwrap_or_default()
+                    > Duration::from_secs(hours * 3600));
+            let oversized = matches!(max_size, Some(max_size) if remaining > max_size);
+            if !expired && !oversized {
+                break;
+            }
+            remaining -= archive.size;
+            to_remove.push(archive);
+        }
+
+        if !self.dry_run && !to_remove.is_empty() {
+            // Node may still use the removed archives, so it must be stopped first
+            if is_node_running(&ctx).await {
+                anyhow::bail!("node is running, stop it before pruning archives");
+            }
+
+            for archive in &to_remove {
+                std::fs::remove_file(&archive.path).with_context(|| {
+                    format!("failed to remove archive {}", archive.path.display())
+                })?;
+            }
+        }
+
+        Ok(serde_json::json!({
+            "dry_run": self.dry_run,
+            "removed_size": to_remove.iter().map(|archive| archive.size).sum::<u64>(),
+            "remaining_size": remaining,
+            "removed": to_remove,
+        }))
+    }
+}
+
+fn node_db_path(ctx: &CliContext) -> Result<PathBuf> {
+    NodeConfig::load(&ctx.dirs.node_config)?
+        .get_internal_db_path()?
+        .context("node DB path is not configured")
+}
+
+async fn create_rpc_node(ctx: &CliContext) -> Result<NodeTcpRpc> {
+    let config = ctx.load_config()?;
+    let rpc = NodeTcpRpc::new(config.control()?).await?;
+    Ok(rpc.with_cancellation(ctx.cancellation()))
+}
+
+async fn is_node_running(ctx: &CliContext) -> bool {
+    match create_rpc_node(ctx).await {
+        Ok(rpc_node) => matches!(rpc_node.ping().await, Ok(Some(_))),
+        Err(_) => false,
+    }
+}
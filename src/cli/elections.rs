@@ -0,0 +1,639 @@
+use anyhow::{Context, Result};
+use argh::FromArgs;
+use everscale_crypto::ed25519;
+use serde::Serialize;
+use ton_block::Serializable;
+
+use super::{find_validator_entry, parse_hash, CliContext};
+use crate::config::{AppConfigValidator, StoredKeys};
+use crate::contracts::{elector, wallet, Elector};
+use crate::network::{connect_data_source, connect_subscription, LocalNodeRequired};
+use crate::util::*;
+use crate::validator::{BidHistory, ManagerClient, RoundTimings, DEFAULT_STAKE_FACTOR};
+
+#[derive(FromArgs)]
+/// Elections management stuff
+#[argh(subcommand, name = "elections")]
+pub struct Cmd {
+    #[argh(subcommand)]
+    subcommand: SubCmd,
+}
+
+impl Cmd {
+    pub async fn run(self, ctx: CliContext) -> Result<()> {
+        match self.subcommand {
+            SubCmd::Complaints(cmd) => invoke_as_cli(cmd.run(ctx)).await,
+            SubCmd::Timeline(cmd) => cmd.run(ctx).await,
+            SubCmd::Analyze(cmd) => cmd.run(ctx).await,
+            SubCmd::History(cmd) => cmd.run(ctx).await,
+        }
+    }
+}
+
+#[derive(FromArgs)]
+#[argh(subcommand)]
+enum SubCmd {
+    Complaints(CmdComplaints),
+    Timeline(CmdTimeline),
+    Analyze(CmdAnalyze),
+    History(CmdHistory),
+}
+
+#[derive(FromArgs)]
+/// Lists complaints from the past elections and votes for them
+#[argh(subcommand, name = "complaints")]
+struct CmdComplaints {
+    /// hash of the complaint to vote for
+    #[argh(option)]
+    vote: Vec<String>,
+
+    /// vote for all complaints with the suggested approval
+    #[argh(switch)]
+    vote_suggested: bool,
+}
+
+impl CmdComplaints {
+    async fn run(self, ctx: CliContext) -> Result<()> {
+        // Parse arguments
+        let mut votes = self
+            .vote
+            .iter()
+            .map(|hash| parse_hash(hash).context("invalid complaint hash"))
+            .collect::<Result<Vec<_>>>()?;
+
+        // Load config
+        let mut config = ctx.load_config()?;
+        let validator = config.validator.take();
+
+        // Prepare RPC clients
+        let subscription = connect_subscription(&config, ctx.dirs(), ctx.cancellation()).await?;
+        if let Some(network) = config.network.take() {
+            subscription.set_network_params(network);
+        }
+        subscription.ensure_ready().await?;
+
+        // Find current validator set entry
+        let blockchain_config = subscription.get_blockchain_config().await?;
+        let elector_address = subscription.get_system_addresses().await?.elector;
+        let vset = blockchain_config
+            .config
+            .validator_set()
+            .context("invalid validator set")?;
+        let vset_hash = vset.serialize()?.repr_hash();
+
+        let validator_entry = find_validator_entry(&subscription, &vset).await?;
+
+        // Get complaints
+        let elector = Elector::new(elector_address, subscription.clone());
+        let complaints = elector
+            .get_complaints()
+            .await
+            .context("failed to get complaints")?;
+
+        let mut entries = Vec::with_capacity(complaints.len());
+        for complaint in &complaints {
+            let verdict = Verdict::suggest(complaint, &vset_hash, validator_entry.as_ref());
+            if self.vote_suggested && verdict == Verdict::Approve {
+                votes.push(complaint.hash);
+            }
+
+            entries.push(serde_json::json!({
+                "election_id": complaint.election_id,
+                "hash": complaint.hash.to_hex_string(),
+                "validator_pubkey": complaint.validator_pubkey.to_hex_string(),
+                "created_at": complaint.created_at,
+                "severity": complaint.severity,
+                "reward_address": format!("-1:{}", complaint.reward_addr.to_hex_string()),
+                "paid": complaint.paid.to_string(),
+                "suggested_fine": complaint.suggested_fine.to_string(),
+                "suggested_fine_part": complaint.suggested_fine_part,
+                "voters": complaint.voters.len(),
+                "weight_remaining": complaint.weight_remaining,
+                "verdict": verdict,
+            }));
+        }
+
+        // Send votes
+        votes.sort();
+        votes.dedup();
+        let mut sent_votes = Vec::with_capacity(votes.len());
+        if !votes.is_empty() {
+            if subscription.is_remote() {
+                return Err(LocalNodeRequired.into());
+            }
+
+            let (validator_idx, validator_pubkey) =
+                validator_entry.context("validator is not in the current validator set")?;
+            let validator_key_hash = tl_proto::hash(
+                ed25519::PublicKey::from_bytes(validator_pubkey)
+                    .context("invalid validator public key")?
+                    .as_tl(),
+            );
+
+            // Prepare wallet
+            let wallet_address = match validator {
+                Some(AppConfigValidator::Single(single)) => single.address,
+                Some(AppConfigValidator::DePool(depool)) => depool.owner,
+                None => anyhow::bail!("validator entry not found in the app config"),
+            };
+            let keypair = StoredKeys::load(&ctx.dirs.validator_keys)
+                .context("failed to load validator wallet keys")?
+                .as_keypair();
+            let wallet = wallet::Wallet::new(
+                wallet_address.workchain_id() as i8,
+                keypair,
+                subscription.clone(),
+            );
+            anyhow::ensure!(
+                wallet.address() == &wallet_address,
+                "validator wallet address mismatch"
+            );
+
+            let signature_id = subscription.get_signature_id().await?;
+
+            for hash in votes {
+                let complaint = complaints
+                    .iter()
+                    .find(|complaint| complaint.hash == hash)
+                    .with_context(|| format!("complaint {} not found", hash.to_hex_string()))?;
+
+                let verdict = Verdict::suggest(complaint, &vset_hash, validator_entry.as_ref());
+                anyhow::ensure!(
+                    verdict == Verdict::Approve,
+                    "unable to vote for complaint {} ({verdict:?})",
+                    hash.to_hex_string()
+                );
+
+                let message = elector
+                    .vote_for_complaint(
+                        validator_idx,
+                        &validator_key_hash,
+                        complaint.election_id,
+                        &complaint.hash,
+                        signature_id,
+                    )
+                    .await?;
+
+                let tx = wallet
+                    .call(message)
+                    .await
+                    .context("failed to vote for complaint")?;
+
+                sent_votes.push(serde_json::json!({
+                    "hash": hash.to_hex_string(),
+                    "tx_hash": tx.hash.to_hex_string(),
+                }));
+            }
+        }
+
+        print_output(serde_json::json!({
+            "complaints": entries,
+            "votes": sent_votes,
+        }));
+        Ok(())
+    }
+}
+
+#[derive(FromArgs)]
+/// Shows past and upcoming election windows with countdowns
+#[argh(subcommand, name = "timeline")]
+struct CmdTimeline {
+    /// elections start offset (in seconds). 600 seconds default
+    #[argh(option, default = "600")]
+    elections_start_offset: u32,
+
+    /// elections end offset (in seconds). 120 seconds default
+    #[argh(option, default = "120")]
+    elections_end_offset: u32,
+
+    /// bid window start (in percents of the elections duration). 0 default
+    #[argh(option, default = "0")]
+    bid_window_start: u8,
+
+    /// bid window end (in percents of the elections duration). 25 default
+    #[argh(option, default = "25")]
+    bid_window_end: u8,
+}
+
+impl CmdTimeline {
+    async fn run(self, ctx: CliContext) -> Result<()> {
+        anyhow::ensure!(
+            self.bid_window_start <= self.bid_window_end && self.bid_window_end <= 100,
+            "invalid bid window"
+        );
+
+        // Prefer the running manager which already has the blockchain config
+        let timings = match ManagerClient::connect(&ctx.dirs().manager_socket).await {
+            Some(mut client) => {
+                let mut elections = client.elections().await?;
+                serde_json::from_value::<Option<RoundTimings>>(elections["timings"].take())?
+            }
+            None => None,
+        };
+        let timings = match timings {
+            Some(timings) => timings,
+            None => {
+                let config = ctx.load_config()?;
+                let data_source = connect_data_source(&config).await?;
+                RoundTimings::new(&data_source.get_blockchain_config().await?)?
+            }
+        };
+
+        let now = broxus_util::now();
+        let mut events = Vec::new();
+        let mut push_event = |event: &'static str, at: u32, action: Option<&'static str>| {
+            events.push((at, event, action));
+        };
+
+        if let Some((prev_round_start, prev_round_end)) = timings.prev_round {
+            push_event("previous_round_start", prev_round_start, None);
+            push_event(
+                "previous_round_stake_unfreeze",
+                prev_round_end + timings.stake_held_for,
+                Some("stake becomes available for recovery"),
+            );
+        }
+
+        let (round_start, round_end) = timings.current_round;
+        push_event("current_round_start", round_start, None);
+
+        let elections_start = round_end.saturating_sub(timings.elections_start_before);
+        let elections_end = round_end.saturating_sub(timings.elections_end_before);
+        push_event("elections_start", elections_start, None);
+
+        let bid_from = elections_start + self.elections_start_offset;
+        let bid_to = elections_end.saturating_sub(self.elections_end_offset);
+        let duration = bid_to.saturating_sub(bid_from);
+        push_event(
+            "bid_window_start",
+            bid_from + duration * self.bid_window_start as u32 / 100,
+            Some("recover unfrozen stake and send election request at a random time"),
+        );
+        push_event(
+            "bid_window_end",
+            bid_from + duration * self.bid_window_end as u32 / 100,
+            Some("standby manager sends election request if primary missed the window"),
+        );
+        push_event(
+            "elections_end",
+            elections_end,
+            Some("no requests are sent after the end offset"),
+        );
+
+        push_event("next_round_start", round_end, None);
+        push_event(
+            "current_round_stake_unfreeze",
+            round_end + timings.stake_held_for,
+            Some("recover stake during the next elections"),
+        );
+        push_event(
+            "next_round_end",
+            round_end + timings.validators_elected_for,
+            None,
+        );
+
+        events.sort_by_key(|(at, ..)| *at);
+        let events = events
+            .into_iter()
+            .map(|(at, event, action)| {
+                serde_json::json!({
+                    "event": event,
+                    "at": at,
+                    "in": at as i64 - now as i64,
+                    "action": action,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        print_output(serde_json::json!({
+            "now": now,
+            "events": events,
+        }));
+        Ok(())
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum Verdict {
+    /// Complaint can be approved
+    Approve,
+    /// Complaint is for this validator
+    OwnValidator,
+    /// Vote was already sent
+    AlreadyVoted,
+    /// Complaint was already accepted
+    Accepted,
+    /// Validator is not in the set which can vote for this complaint
+    CannotVote,
+}
+
+impl Verdict {
+    fn suggest(
+        complaint: &elector::Complaint,
+        vset_hash: &ton_types::UInt256,
+        validator_entry: Option<&(u16, [u8; 32])>,
+    ) -> Self {
+        if complaint.is_accepted() {
+            return Self::Accepted;
+        }
+
+        let Some((idx, pubkey)) = validator_entry else {
+            return Self::CannotVote;
+        };
+        if &complaint.vset_id != vset_hash {
+            return Self::CannotVote;
+        }
+
+        if complaint.voters.contains(idx) {
+            Self::AlreadyVoted
+        } else if complaint.validator_pubkey.as_slice() == pubkey {
+            Self::OwnValidator
+        } else {
+            Self::Approve
+        }
+    }
+}
+
+#[derive(FromArgs)]
+/// Lists sent election bids with their outcomes
+#[argh(subcommand, name = "history")]
+struct CmdHistory {
+    /// max number of the latest bids to show. 20 default
+    #[argh(option, default = "20")]
+    limit: usize,
+}
+
+impl CmdHistory {
+    async fn run(self, ctx: CliContext) -> Result<()> {
+        let mut bids = BidHistory::new(&ctx.dirs().bid_history).load()?;
+        bids.reverse();
+        bids.truncate(self.limit);
+
+        let mut config = ctx.load_config()?;
+
+        // Prepare RPC clients
+        let subscription = connect_subscription(&config, ctx.dirs(), ctx.cancellation()).await?;
+        if let Some(network) = config.network.take() {
+            subscription.set_network_params(network);
+        }
+        subscription.ensure_ready().await?;
+
+        let elector_address = subscription.get_system_addresses().await?.elector;
+        let elector_data = Elector::new(elector_address, subscription.clone())
+            .get_data()
+            .await?;
+
+        let bids = bids
+            .into_iter()
+            .map(|bid| {
+                // NOTE: elector forgets the elections after the stake is unfrozen
+                let outcome = elector_data.bid_outcome(bid.election_id, &bid.participant);
+                serde_json::json!({
+                    "election_id": bid.election_id,
+                    "sent_at": bid.sent_at,
+                    "participant": bid.participant.to_string(),
+                    "stake": bid.stake.to_string(),
+                    "max_factor": bid.max_factor as f64 / 65536.0,
+                    "adnl_addr": bid.adnl_addr,
+                    "tx_hash": bid.tx_hash,
+                    "outcome": outcome,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        print_output(serde_json::json!({ "bids": bids }));
+        Ok(())
+    }
+}
+
+#[derive(FromArgs)]
+/// Simulates the current elections and suggests the stake and max factor
+#[argh(subcommand, name = "analyze")]
+struct CmdAnalyze {
+    /// stake amount (e.g. `1250.5` or `1e12 nano`). Stake per round from the config by default
+    #[argh(option)]
+    stake: Option<Tokens<u128>>,
+
+    /// max factor (e.g. `2.5`). Stake factor from the config by default
+    #[argh(option)]
+    max_factor: Option<f64>,
+}
+
+impl CmdAnalyze {
+    async fn run(self, ctx: CliContext) -> Result<()> {
+        const STAKE_FACTOR_ONE: f64 = 65536.0;
+
+        let mut config = ctx.load_config()?;
+        let validator = config.validator.take();
+
+        // Resolve what-if params
+        let (configured_stake, configured_factor, participant) = match &validator {
+            Some(AppConfigValidator::Single(single)) => (
+                Some(single.stake_per_round as u128),
+                single.stake_factor,
+                Some(split_address(&single.address)?.1),
+            ),
+            // NOTE: DePool stakes are sent from proxies and depend on the pool balance
+            Some(AppConfigValidator::DePool(depool)) => (None, depool.stake_factor, None),
+            None => (None, None, None),
+        };
+        let stake = match self.stake {
+            Some(stake) => stake.0,
+            None => configured_stake.context("`--stake` is required")?,
+        };
+        let max_factor = match self.max_factor {
+            Some(factor) => {
+                anyhow::ensure!(factor >= 1.0, "max factor must be at least 1.0");
+                (factor * STAKE_FACTOR_ONE) as u32
+            }
+            None => configured_factor.unwrap_or(DEFAULT_STAKE_FACTOR),
+        };
+
+        // Prepare RPC clients
+        let subscription = connect_subscription(&config, ctx.dirs(), ctx.cancellation()).await?;
+        if let Some(network) = config.network.take() {
+            subscription.set_network_params(network);
+        }
+        subscription.ensure_ready().await?;
+
+        let blockchain_config = subscription.get_blockchain_config().await?;
+        let elector_address = subscription.get_system_addresses().await?.elector;
+        let elector_data = Elector::new(elector_address, subscription.clone())
+            .get_data()
+            .await?;
+        let election_id = elector_data
+            .election_id()
+            .context("elections are not open")?;
+
+        let stakes_config = blockchain_config
+            .config
+            .stakes_config()
+            .context("invalid stakes config")?;
+        let validators_count = blockchain_config
+            .config
+            .validators_count()
+            .context("invalid validators count")?;
+        let params = ElectionParams {
+            min_validators: validators_count.min_validators.as_u16() as usize,
+            max_validators: validators_count.max_validators.as_u16() as usize,
+            min_stake: stakes_config.min_stake.as_u128(),
+            max_stake: stakes_config.max_stake.as_u128(),
+            min_total_stake: stakes_config.min_total_stake.as_u128(),
+            max_stake_factor: stakes_config.max_stake_factor,
+        };
+
+        // Our previous bid is replaced by the what-if bid
+        let bids = elector_data
+            .bids()
+            .into_iter()
+            .filter(|bid| Some(&bid.src_addr) != participant.as_ref())
+            .map(|bid| SimulatedBid {
+                stake: bid.stake as u128,
+                max_factor: bid.max_factor,
+            })
+            .collect::<Vec<_>>();
+
+        let factor = |value: u32| value as f64 / STAKE_FACTOR_ONE;
+        let describe = |stake: u128, max_factor: u32| {
+            let Some(outcome) = params.simulate(&bids, stake, max_factor) else {
+                return serde_json::json!({
+                    "stake": Tokens(stake).to_string(),
+                    "max_factor": factor(max_factor),
+                    "elections_failed": true,
+                });
+            };
+            let cut_off = outcome.own_stake.map(|s| Tokens(stake.saturating_sub(s)));
+            serde_json::json!({
+                "stake": Tokens(stake).to_string(),
+                "max_factor": factor(max_factor),
+                "elected": outcome.own_stake.is_some(),
+                "effective_stake": outcome.own_stake.map(|s| Tokens(s).to_string()),
+                "cut_off": cut_off.map(|s| s.to_string()),
+                "min_elected_stake": Tokens(outcome.min_stake).to_string(),
+                "total_stake": Tokens(outcome.total_stake).to_string(),
+                "validators": outcome.validators,
+            })
+        };
+
+        // Find the smallest factor at which the whole stake is used,
+        // or the largest stake which fits into the max allowed factor
+        let suggestion = params.simulate(&bids, stake, max_factor).map(|outcome| {
+            let min_stake = std::cmp::max(outcome.min_stake, 1);
+            if outcome.own_stake.is_none() {
+                // Outbid the smallest elected stake
+                return (min_stake + one_token(), max_factor);
+            }
+
+            let required_factor = (stake * 65536).div_ceil(min_stake);
+            if required_factor <= params.max_stake_factor as u128 {
+                let max_factor = std::cmp::max(required_factor as u32, 65536);
+                (stake, max_factor)
+            } else {
+                let max_factor = params.max_stake_factor;
+                let stake = std::cmp::max(min_stake * max_factor as u128 / 65536, min_stake);
+                (stake, max_factor)
+            }
+        });
+
+        print_output(serde_json::json!({
+            "election_id": election_id,
+            "participants": bids.len(),
+            "max_validators": params.max_validators,
+            "max_stake_factor": factor(params.max_stake_factor),
+            "what_if": describe(stake, max_factor),
+            "suggested": suggestion.map(|(stake, max_factor)| describe(stake, max_factor)),
+        }));
+        Ok(())
+    }
+}
+
+/// Elector params from the config params 16 and 17
+struct ElectionParams {
+    min_validators: usize,
+    max_validators: usize,
+    min_stake: u128,
+    max_stake: u128,
+    min_total_stake: u128,
+    max_stake_factor: u32,
+}
+
+#[derive(Clone, Copy)]
+struct SimulatedBid {
+    stake: u128,
+    max_factor: u32,
+}
+
+struct SimulatedOutcome {
+    validators: usize,
+    min_stake: u128,
+    total_stake: u128,
+    /// Effective stake of our bid if it was elected
+    own_stake: Option<u128>,
+}
+
+impl ElectionParams {
+    /// Replicates `try_elect` of the elector contract with an additional bid
+    fn simulate(
+        &self,
+        bids: &[SimulatedBid],
+        stake: u128,
+        max_factor: u32,
+    ) -> Option<SimulatedOutcome> {
+        let clamp = |bid: &SimulatedBid| SimulatedBid {
+            stake: std::cmp::min(bid.stake, self.max_stake),
+            max_factor: std::cmp::min(bid.max_factor, self.max_stake_factor),
+        };
+
+        // NOTE: the new bid is the latest one, so it goes after the bids with the same stake
+        let mut list = bids
+            .iter()
+            .map(clamp)
+            .map(|bid| (bid, false))
+            .collect::<Vec<_>>();
+        if stake >= self.min_stake {
+            list.push((clamp(&SimulatedBid { stake, max_factor }), true));
+        }
+        list.sort_by(|(a, _), (b, _)| b.stake.cmp(&a.stake));
+
+        let effective_stake = |bid: &SimulatedBid, min_stake: u128| {
+            std::cmp::min(bid.stake, (bid.max_factor as u128 * min_stake) >> 16)
+        };
+
+        let n = std::cmp::min(list.len(), self.max_validators);
+        let mut best: Option<(usize, u128, u128)> = None;
+        for count in std::cmp::max(self.min_validators, 1)..=n {
+            let min_stake = list[count - 1].0.stake;
+            if min_stake < self.min_stake {
+                break;
+            }
+            let total = list[..count]
+                .iter()
+                .map(|(bid, _)| effective_stake(bid, min_stake))
+                .sum::<u128>();
+            if best
+                .map(|(_, _, best_total)| total > best_total)
+                .unwrap_or(true)
+            {
+                best = Some((count, min_stake, total));
+            }
+        }
+
+        let (validators, min_stake, total_stake) = best?;
+        if total_stake < self.min_total_stake {
+            return None;
+        }
+
+        let own_stake = list[..validators]
+            .iter()
+            .find(|(_, own)| *own)
+            .map(|(bid, _)| effective_stake(bid, min_stake));
+
+        Some(SimulatedOutcome {
+            validators,
+            min_stake,
+            total_stake,
+            own_stake,
+        })
+    }
+}
@@ -0,0 +1,102 @@
+use serde::Serialize;
+
+use crate::network::{NodeRpcError, SendMessageError, StatsError, TcpAdnlError};
+use crate::util::*;
+
+/// App config can't be loaded
+#[derive(Debug, thiserror::Error)]
+#[error("invalid app config")]
+pub struct ConfigError;
+
+/// Stable failure category which scripts can rely on
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    /// Unclassified failure
+    Failed,
+    /// Operation was declined by the user
+    Declined,
+    /// App config is missing or invalid
+    Config,
+    /// Node or JRPC endpoint is not reachable
+    RpcUnreachable,
+    /// Node is not synced yet
+    NodeNotSynced,
+    /// Message was not executed successfully
+    OnChain,
+    /// Command was interrupted by a termination signal
+    Interrupted,
+}
+
+impl ErrorCode {
+    pub fn from_error(e: &anyhow::Error) -> Self {
+        // NOTE: contexts are checked first, sources are checked afterwards
+        if e.downcast_ref::<Declined>().is_some() {
+            return Self::Declined;
+        }
+        if e.downcast_ref::<ConfigError>().is_some() {
+            return Self::Config;
+        }
+
+        for cause in e.chain() {
+            if let Some(e) = cause.downcast_ref::<NodeRpcError>() {
+                match e {
+                    NodeRpcError::Cancelled => return Self::Interrupted,
+                    NodeRpcError::ConnectionFailed(_) | NodeRpcError::QueryTimeout => {
+                        return Self::RpcUnreachable
+                    }
+                    NodeRpcError::InvalidStats(StatsError::NotReady) => return Self::NodeNotSynced,
+                    _ => {}
+                }
+            } else if let Some(e) = cause.downcast_ref::<TcpAdnlError>() {
+                if matches!(
+                    e,
+                    TcpAdnlError::ConnectionTimeout | TcpAdnlError::ConnectionError(_)
+                ) {
+                    return Self::RpcUnreachable;
+                }
+            } else if let Some(e) = cause.downcast_ref::<reqwest::Error>() {
+                if e.is_connect() || e.is_timeout() {
+                    return Self::RpcUnreachable;
+                }
+            } else if let Some(StatsError::NotReady) = cause.downcast_ref::<StatsError>() {
+                return Self::NodeNotSynced;
+            } else if cause.downcast_ref::<SendMessageError>().is_some() {
+                return Self::OnChain;
+            }
+        }
+
+        Self::Failed
+    }
+
+    pub fn exit_code(self) -> i32 {
+        match self {
+            Self::Failed => 1,
+            Self::Declined => 2,
+            Self::Config => 3,
+            Self::RpcUnreachable => 4,
+            Self::NodeNotSynced => 5,
+            Self::OnChain => 6,
+            Self::Interrupted => 130,
+        }
+    }
+}
+
+/// Prints the error and returns the process exit code
+pub fn report_error(e: &anyhow::Error) -> i32 {
+    let code = ErrorCode::from_error(e);
+
+    if is_json_output() {
+        print_output(serde_json::json!({
+            "error": {
+                "code": code,
+                "message": e.to_string(),
+                "causes": e.chain().skip(1).map(ToString::to_string).collect::<Vec<_>>(),
+            }
+        }));
+    } else {
+        eprintln!("Error: {e:?}");
+    }
+
+    code.exit_code()
+}
@@ -6,9 +6,12 @@ use anyhow::Result;
 use argh::FromArgs;
 
 use super::CliContext;
+use crate::config::AppConfigExporter;
 use crate::exporter::{
-    Exporter, ExporterTarget, FileExporterTarget, HttpExporterTarget, StdoutExporterTarget,
+    Exporter, ExporterTarget, FileExporterTarget, HealthServer, HttpExporterTarget,
+    StdoutExporterTarget,
 };
+use crate::node_logs::NodeLogWatcher;
 
 #[derive(FromArgs)]
 /// Prometheus metrics exporter
@@ -29,12 +32,32 @@ pub struct Cmd {
     /// metrics collection interval (in seconds). 10 seconds default
     #[argh(option, short = 'i', default = "10")]
     interval: u32,
+
+    /// socket addr to host the `/healthz` endpoint
+    #[argh(option)]
+    health_addr: Option<SocketAddr>,
+
+    /// max timediff (in seconds) for the node to be healthy. 120 seconds default
+    #[argh(option, default = "120")]
+    health_max_time_diff: u16,
 }
 
 impl Cmd {
-    pub async fn run(self, ctx: CliContext) -> Result<()> {
+    pub async fn run(mut self, ctx: CliContext) -> Result<()> {
         let mut targets = Vec::<Box<dyn ExporterTarget>>::new();
 
+        // NOTE: invalid config is reported by the exporter itself
+        let mut config = ctx.load_config().ok();
+
+        // Use target from the config if no targets specified
+        if self.file.is_none() && self.addr.is_none() {
+            match config.as_mut().and_then(|config| config.exporter.take()) {
+                Some(AppConfigExporter::Http { addr }) => self.addr = Some(addr),
+                Some(AppConfigExporter::File { path }) => self.file = Some(path),
+                None => {}
+            }
+        }
+
         // Add file exporter if path specified
         if let Some(file) = self.file {
             targets.push(Box::new(FileExporterTarget::new(file)));
@@ -49,16 +72,39 @@ impl Cmd {
         }
 
         // Fallback to stdout exporter
-        if targets.is_empty() {
+        if targets.is_empty() && self.health_addr.is_none() {
             targets.push(Box::new(StdoutExporterTarget));
         }
 
-        let exporter = Exporter::new(ctx.dirs, targets);
+        let mut exporter = Exporter::new(ctx.dirs, targets);
+
+        // Add health check endpoint
+        if let Some(addr) = self.health_addr {
+            if self.once {
+                return Err(ExporterError::OnceNotSupported.into());
+            }
+            let server = HealthServer::bind(addr).await?;
+            exporter = exporter.with_health(server, self.health_max_time_diff as i32);
+        }
+
+        // Follow node logs
+        if let Some(source) = config.and_then(|config| config.node_logs) {
+            if !self.once {
+                exporter = exporter.with_node_logs(NodeLogWatcher::spawn(source));
+            }
+        }
+
         if self.once {
             exporter.once().await
         } else {
             let interval = Duration::from_secs(self.interval as u64);
-            exporter.serve(interval).await;
+            let signal_rx = broxus_util::any_signal(broxus_util::TERMINATION_SIGNALS);
+            tokio::select! {
+                _ = exporter.serve(interval) => {},
+                signal = signal_rx => {
+                    tracing::info!(?signal, "received termination signal");
+                }
+            }
             Ok(())
         }
     }
@@ -66,6 +112,6 @@ impl Cmd {
 
 #[derive(thiserror::Error, Debug)]
 enum ExporterError {
-    #[error("once flag is not supported by http exporter")]
+    #[error("once flag is not supported by http exporter and health endpoint")]
     OnceNotSupported,
 }
@@ -0,0 +1,375 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use argh::FromArgs;
+use futures_util::stream::{self, StreamExt};
+
+use super::CliContext;
+use crate::config::{FleetHost, FleetHostApi, FleetHostSsh, FleetInventory};
+use crate::util::*;
+use crate::validator::RoundTimings;
+
+#[derive(FromArgs)]
+/// Manages multiple remote validators
+#[argh(subcommand, name = "fleet")]
+pub struct Cmd {
+    /// path to the inventory file. `$ROOT/fleet.toml` default
+    #[argh(option)]
+    inventory: Option<PathBuf>,
+
+    /// only use hosts with this profile
+    #[argh(option)]
+    profile: Option<String>,
+
+    /// max number of hosts processed simultaneously. 8 default
+    #[argh(option, default = "8")]
+    parallel: usize,
+
+    #[argh(subcommand)]
+    subcommand: SubCmd,
+}
+
+impl Cmd {
+    pub async fn run(self, ctx: CliContext) -> Result<()> {
+        let path = self
+            .inventory
+            .unwrap_or_else(|| ctx.dirs().fleet_inventory.clone());
+        let hosts = FleetInventory::load(path)?
+            .hosts
+            .into_iter()
+            .filter(|host| match &self.profile {
+                Some(profile) => host.profile.as_ref() == Some(profile),
+                None => true,
+            })
+            .collect::<Vec<_>>();
+        anyhow::ensure!(!hosts.is_empty(), "no fleet hosts selected");
+
+        let fleet = Fleet {
+            client: reqwest::Client::builder()
+                .timeout(REQUEST_TIMEOUT)
+                .build()?,
+            parallel: std::cmp::max(self.parallel, 1),
+        };
+
+        match self.subcommand {
+            SubCmd::Status(_) => fleet.status(hosts).await,
+            SubCmd::Elections(_) => fleet.elections(hosts).await,
+            SubCmd::Upgrade(cmd) => fleet.upgrade(hosts, cmd).await,
+        }
+    }
+}
+
+#[derive(FromArgs)]
+#[argh(subcommand)]
+enum SubCmd {
+    Status(CmdStatus),
+    Elections(CmdElections),
+    Upgrade(CmdUpgrade),
+}
+
+#[derive(FromArgs)]
+/// Shows the status of each validator
+#[argh(subcommand, name = "status")]
+struct CmdStatus {}
+
+#[derive(FromArgs)]
+/// Shows the upcoming elections of each validator
+#[argh(subcommand, name = "elections")]
+struct CmdElections {}
+
+#[derive(FromArgs)]
+/// Rebuilds nodes which don't support the required global version (SSH only)
+#[argh(subcommand, name = "upgrade")]
+struct CmdUpgrade {
+    /// rebuild the node even if it supports the required version
+    #[argh(switch, short = 'f')]
+    force: bool,
+
+    /// restart the node immediately, even during its catchain session
+    #[argh(switch)]
+    now: bool,
+}
+
+struct Fleet {
+    client: reqwest::Client,
+    parallel: usize,
+}
+
+impl Fleet {
+    async fn status(&self, hosts: Vec<FleetHost>) -> Result<()> {
+        let results = self
+            .run(&hosts, |host| async move {
+                match (&host.api, &host.ssh) {
+                    (Some(api), _) => self.api_request(api, "v1/status").await,
+                    (_, Some(ssh)) => ssh_command(ssh, &["validator", "status"], None).await,
+                    _ => unreachable!(),
+                }
+            })
+            .await;
+
+        print_results(
+            &[
+                "HOST",
+                "CURRENT VSET",
+                "NEXT VSET",
+                "TIME DIFF",
+                "MAINTENANCE",
+            ],
+            &results,
+            |status| {
+                let flag = |value: &serde_json::Value, yes: &str, no: &str| {
+                    if value.is_null() { no } else { yes }.to_owned()
+                };
+                vec![
+                    flag(&status["in_current_vset"], "yes", "no"),
+                    flag(&status["in_next_vset"], "yes", "no"),
+                    format!("{}s", status["mc_time_diff"]),
+                    flag(&status["maintenance"], "on", "off"),
+                ]
+            },
+        )
+    }
+
+    async fn elections(&self, hosts: Vec<FleetHost>) -> Result<()> {
+        let results = self
+            .run(&hosts, |host| async move {
+                match (&host.api, &host.ssh) {
+                    (Some(api), _) => {
+                        let mut res = self.api_request(api, "v1/elections").await?;
+                        let timings =
+                            serde_json::from_value::<Option<RoundTimings>>(res["timings"].take())?
+                                .context("remote manager is not ready")?;
+
+                        let round_end = timings.current_round.1;
+                        Ok(serde_json::json!({
+                            "elections_start": round_end
+                                .saturating_sub(timings.elections_start_before),
+                            "elections_end": round_end
+                                .saturating_sub(timings.elections_end_before),
+                            "journal": res["journal"].take(),
+                        }))
+                    }
+                    (_, Some(ssh)) => {
+                        let timeline = ssh_command(ssh, &["elections", "timeline"], None).await?;
+                        let event_at = |name: &str| {
+                            timeline["events"]
+                                .as_array()
+                                .and_then(|events| events.iter().find(|e| e["event"] == name))
+                                .map(|event| event["at"].clone())
+                                .unwrap_or_default()
+                        };
+                        Ok(serde_json::json!({
+                            "elections_start": event_at("elections_start"),
+                            "elections_end": event_at("elections_end"),
+                            "journal": null,
+                        }))
+                    }
+                    _ => unreachable!(),
+                }
+            })
+            .await;
+
+        let now = broxus_util::now() as i64;
+        print_results(
+            &[
+                "HOST",
+                "ELECTIONS START",
+                "ELECTIONS END",
+                "ELECTION ID",
+                "STAKE",
+            ],
+            &results,
+            |elections| {
+                let countdown = |at: &serde_json::Value| match at.as_i64() {
+                    Some(at) => format!("{at} ({:+}s)", at - now),
+                    None => "-".to_owned(),
+                };
+                let journal = &elections["journal"];
+                let stake = if journal["stake_accepted"] == true {
+                    "accepted"
+                } else if !journal["request"].is_null() {
+                    "sent"
+                } else {
+                    "-"
+                };
+                vec![
+                    countdown(&elections["elections_start"]),
+                    countdown(&elections["elections_end"]),
+                    match journal["election_id"].as_u64() {
+                        Some(id) => id.to_string(),
+                        None => "-".to_owned(),
+                    },
+                    stake.to_owned(),
+                ]
+            },
+        )
+    }
+
+    async fn upgrade(&self, hosts: Vec<FleetHost>, cmd: CmdUpgrade) -> Result<()> {
+        const UPGRADE_TIMEOUT: Duration = Duration::from_secs(3600);
+
+        let mut args = vec!["--yes", "upgrade"];
+        if cmd.force {
+            args.push("--force");
+        }
+        if cmd.now {
+            args.push("--now");
+        }
+
+        let args = &args;
+        let results = self
+            .run(&hosts, |host| async move {
+                let ssh = host.ssh.as_ref().context("upgrade requires SSH access")?;
+                ssh_command(ssh, args, Some(UPGRADE_TIMEOUT)).await
+            })
+            .await;
+
+        print_results(&["HOST", "RESULT"], &results, |_| vec!["ok".to_owned()])
+    }
+
+    /// Runs the operation for all hosts with a limited concurrency
+    async fn run<'a, F, Fut>(
+        &'a self,
+        hosts: &'a [FleetHost],
+        f: F,
+    ) -> Vec<(String, Result<serde_json::Value>)>
+    where
+        F: Fn(&'a FleetHost) -> Fut,
+        Fut: std::future::Future<Output = Result<serde_json::Value>> + 'a,
+    {
+        stream::iter(hosts)
+            .map(|host| {
+                let fut = f(host);
+                async move { (host.name.clone(), fut.await) }
+            })
+            .buffered(self.parallel)
+            .collect()
+            .await
+    }
+
+    async fn api_request(&self, api: &FleetHostApi, path: &str) -> Result<serde_json::Value> {
+        let url = api.url.join(path).context("invalid API url")?;
+        let res = self
+            .client
+            .get(url)
+            .bearer_auth(api.token.as_str())
+            .send()
+            .await
+            .context("API request failed")?;
+
+        let status = res.status();
+        let body = serde_json::from_str::<serde_json::Value>(&res.text().await?)
+            .context("invalid API response")?;
+        if !status.is_success() {
+            anyhow::bail!(
+                "API request failed ({status}): {}",
+                body["error"].as_str().unwrap_or_default()
+            );
+        }
+        Ok(body)
+    }
+}
+
+/// Runs `nodekeeper` on the remote host and parses its JSON output
+async fn ssh_command(
+    ssh: &FleetHostSsh,
+    args: &[&str],
+    timeout: Option<Duration>,
+) -> Result<serde_json::Value> {
+    let mut remote = vec![shell_quote(&ssh.binary), "--output json".to_owned()];
+    if let Some(root) = &ssh.root {
+        remote.push(format!("--root {}", shell_quote(&root.to_string_lossy())));
+    }
+    remote.extend(args.iter().map(|arg| shell_quote(arg)));
+
+    let mut command = tokio::process::Command::new("ssh");
+    command.args(["-o", "BatchMode=yes"]);
+    if let Some(port) = ssh.port {
+        command.arg("-p").arg(port.to_string());
+    }
+    match &ssh.user {
+        Some(user) => command.arg(format!("{user}@{}", ssh.host)),
+        None => command.arg(&ssh.host),
+    };
+    command.arg(remote.join(" ")).kill_on_drop(true);
+
+    let output = tokio::time::timeout(timeout.unwrap_or(REQUEST_TIMEOUT), command.output())
+        .await
+        .context("SSH command timeout")?
+        .context("failed to run ssh")?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!(
+            "remote command failed ({}): {}",
+            output.status,
+            stderr.trim().lines().last().unwrap_or_default()
+        );
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    if stdout.trim().is_empty() {
+        return Ok(serde_json::Value::Null);
+    }
+    serde_json::from_str(stdout.trim()).context("invalid remote command output")
+}
+
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+/// Prints a table (or JSON) and fails if any host failed
+fn print_results<F>(
+    header: &[&str],
+    results: &[(String, Result<serde_json::Value>)],
+    columns: F,
+) -> Result<()>
+where
+    F: Fn(&serde_json::Value) -> Vec<String>,
+{
+    let failed = results.iter().filter(|(_, res)| res.is_err()).count();
+
+    if is_terminal() && !is_json_output() {
+        let mut rows = vec![header.iter().map(|s| s.to_string()).collect::<Vec<_>>()];
+        for (name, res) in results {
+            let mut row = vec![name.clone()];
+            match res {
+                Ok(value) => row.extend(columns(value)),
+                Err(e) => row.push(format!("error: {e:#}")),
+            }
+            rows.push(row);
+        }
+
+        let mut widths = vec![0; header.len()];
+        for row in &rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = std::cmp::max(*width, cell.chars().count());
+            }
+        }
+
+        for row in rows {
+            let line = row
+                .iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{cell:<width$}"))
+                .collect::<Vec<_>>()
+                .join("  ");
+            println!("{}", line.trim_end());
+        }
+    } else {
+        let hosts = results
+            .iter()
+            .map(|(name, res)| match res {
+                Ok(value) => serde_json::json!({ "host": name, "result": value }),
+                Err(e) => serde_json::json!({ "host": name, "error": format!("{e:#}") }),
+            })
+            .collect::<Vec<_>>();
+        print_output(serde_json::json!({ "hosts": hosts }));
+    }
+
+    anyhow::ensure!(failed == 0, "{failed} of {} hosts failed", results.len());
+    Ok(())
+}
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
@@ -0,0 +1,244 @@
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use argh::FromArgs;
+use everscale_crypto::ed25519;
+use ton_block::Serializable;
+
+use super::{find_validator_entry, parse_hash, CliContext};
+use crate::contracts::config_contract::ConfigProposal;
+use crate::contracts::ConfigContract;
+use crate::network::{connect_subscription, ConfigWithId, LocalNodeRequired, Subscription};
+use crate::util::*;
+
+#[derive(FromArgs)]
+/// Blockchain config proposals
+#[argh(subcommand, name = "governance")]
+pub struct Cmd {
+    #[argh(subcommand)]
+    subcommand: SubCmd,
+}
+
+impl Cmd {
+    pub async fn run(self, ctx: CliContext) -> Result<()> {
+        match self.subcommand {
+            SubCmd::Proposals(cmd) => cmd.run(ctx).await,
+            SubCmd::Vote(cmd) => invoke_as_cli(cmd.run(ctx)).await,
+        }
+    }
+}
+
+#[derive(FromArgs)]
+#[argh(subcommand)]
+enum SubCmd {
+    Proposals(CmdProposals),
+    Vote(CmdVote),
+}
+
+#[derive(FromArgs)]
+/// Lists active config proposals with the changes of the params
+#[argh(subcommand, name = "proposals")]
+struct CmdProposals {}
+
+impl CmdProposals {
+    async fn run(self, ctx: CliContext) -> Result<()> {
+        let governance = Governance::new(&ctx).await?;
+        let proposals = governance.config_contract.get_proposals().await?;
+
+        let proposals = proposals
+            .iter()
+            .map(|proposal| governance.describe(proposal))
+            .collect::<Vec<_>>();
+
+        print_output(serde_json::json!({
+            "validator_idx": governance.validator_entry.map(|(idx, _)| idx),
+            "proposals": proposals,
+        }));
+        Ok(())
+    }
+}
+
+#[derive(FromArgs)]
+/// Votes for the config proposals with the current validator key
+#[argh(subcommand, name = "vote")]
+struct CmdVote {
+    /// hash of the proposal to vote for
+    #[argh(positional)]
+    hashes: Vec<String>,
+
+    /// never prompt
+    #[argh(switch, short = 'f')]
+    force: bool,
+}
+
+impl CmdVote {
+    async fn run(self, ctx: CliContext) -> Result<()> {
+        let mut hashes = self
+            .hashes
+            .iter()
+            .map(|hash| parse_hash(hash).context("invalid proposal hash"))
+            .collect::<Result<Vec<_>>>()?;
+        hashes.sort();
+        hashes.dedup();
+        anyhow::ensure!(!hashes.is_empty(), "no proposals specified");
+
+        let governance = Governance::new(&ctx).await?;
+        if governance.subscription.is_remote() {
+            return Err(LocalNodeRequired.into());
+        }
+
+        let (validator_idx, validator_pubkey) = governance
+            .validator_entry
+            .context("validator is not in the current validator set")?;
+        let validator_key_hash = tl_proto::hash(
+            ed25519::PublicKey::from_bytes(validator_pubkey)
+                .context("invalid validator public key")?
+                .as_tl(),
+        );
+
+        let proposals = governance.config_contract.get_proposals().await?;
+        let signature_id = governance.subscription.get_signature_id().await?;
+
+        let mut votes = Vec::with_capacity(hashes.len());
+        for hash in hashes {
+            let proposal = proposals
+                .iter()
+                .find(|proposal| proposal.hash == hash)
+                .with_context(|| format!("proposal {} not found", hash.to_hex_string()))?;
+            anyhow::ensure!(
+                !governance.has_voted(proposal),
+                "already voted for proposal {}",
+                hash.to_hex_string()
+            );
+
+            let description = governance.describe(proposal);
+            if is_terminal() && !self.force {
+                eprintln!("{}", serde_json::to_string_pretty(&description)?);
+                if !confirm(
+                    &dialoguer::theme::ColorfulTheme::default(),
+                    false,
+                    "Do you really want to vote for this proposal?",
+                )? {
+                    return Err(Declined.into());
+                }
+            }
+
+            let (message, expire_at) = governance
+                .config_contract
+                .vote_for_proposal(validator_idx, &validator_key_hash, &hash, signature_id)
+                .await?;
+
+            let TransactionWithHash { hash: tx_hash, .. } = governance
+                .subscription
+                .send_message(&message, expire_at)
+                .await?
+                .into_result()
+                .context("failed to vote for proposal")?;
+
+            votes.push(serde_json::json!({
+                "hash": hash.to_hex_string(),
+                "tx_hash": tx_hash.to_hex_string(),
+            }));
+        }
+
+        print_output(serde_json::json!({
+            "validator_idx": validator_idx,
+            "votes": votes,
+        }));
+        Ok(())
+    }
+}
+
+struct Governance {
+    subscription: Arc<Subscription>,
+    config_contract: ConfigContract,
+    blockchain_config: Arc<ConfigWithId>,
+    vset_hash: ton_types::UInt256,
+    validator_entry: Option<(u16, [u8; 32])>,
+}
+
+impl Governance {
+    async fn new(ctx: &CliContext) -> Result<Self> {
+        let mut config = ctx.load_config()?;
+
+        let subscription = connect_subscription(&config, ctx.dirs(), ctx.cancellation()).await?;
+        if let Some(network) = config.network.take() {
+            subscription.set_network_params(network);
+        }
+        subscription.ensure_ready().await?;
+
+        let blockchain_config = subscription.get_blockchain_config().await?;
+        let config_address = subscription.get_system_addresses().await?.config;
+        let vset = blockchain_config
+            .config
+            .validator_set()
+            .context("invalid validator set")?;
+        let vset_hash = vset.serialize()?.repr_hash();
+        let validator_entry = find_validator_entry(&subscription, &vset).await?;
+
+        Ok(Self {
+            config_contract: ConfigContract::new(config_address, subscription.clone()),
+            subscription,
+            blockchain_config,
+            vset_hash,
+            validator_entry,
+        })
+    }
+
+    /// Whether the node validator already voted in the current round
+    fn has_voted(&self, proposal: &ConfigProposal) -> bool {
+        matches!(
+            self.validator_entry,
+            Some((idx, _)) if proposal.vset_id == self.vset_hash && proposal.voters.contains(&idx)
+        )
+    }
+
+    fn describe(&self, proposal: &ConfigProposal) -> serde_json::Value {
+        let changes = self.describe_changes(proposal).map(|changes| {
+            changes
+                .into_iter()
+                .map(|(field, current, proposed)| {
+                    serde_json::json!({
+                        "field": field,
+                        "current": current,
+                        "proposed": proposed,
+                    })
+                })
+                .collect::<Vec<_>>()
+        });
+
+        serde_json::json!({
+            "hash": proposal.hash.to_hex_string(),
+            "param": proposal.param_id,
+            "critical": proposal.is_critical,
+            "expires_at": proposal.expires_at,
+            "rounds_remaining": proposal.rounds_remaining,
+            "wins": proposal.wins,
+            "losses": proposal.losses,
+            "voters": proposal.voters.len(),
+            "weight_remaining": proposal.weight_remaining,
+            "voted": self.has_voted(proposal),
+            "if_hash_equal": proposal.if_hash_equal.as_ref().map(|hash| hash.to_hex_string()),
+            "changes": match changes {
+                Ok(changes) => serde_json::json!(changes),
+                Err(e) => serde_json::json!(format!("failed to decode param: {e:?}")),
+            },
+        })
+    }
+
+    /// Returns changed fields of the param as `(name, current, proposed)`
+    fn describe_changes(&self, proposal: &ConfigProposal) -> Result<Vec<(String, String, String)>> {
+        let current = match u32::try_from(proposal.param_id) {
+            Ok(param_id) => match self.blockchain_config.config.config(param_id)? {
+                Some(param) => describe_config_param(&param)?,
+                None => Vec::new(),
+            },
+            Err(_) => Vec::new(),
+        };
+        let proposed = match proposal.decode_value()? {
+            Some(param) => describe_config_param(&param)?,
+            None => Vec::new(),
+        };
+        Ok(diff_config_fields(&current, &proposed))
+    }
+}
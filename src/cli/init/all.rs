@@ -0,0 +1,132 @@
+use std::time::Duration;
+
+use anyhow::Result;
+use argh::FromArgs;
+use dialoguer::console::style;
+use dialoguer::theme::Theme;
+
+use super::{contracts, node, Template};
+use crate::cli::CliContext;
+use crate::util::*;
+use crate::validator::ManagerClient;
+
+#[derive(FromArgs)]
+/// Runs the whole setup: node configs, binary, services and contracts
+#[argh(subcommand, name = "all")]
+pub struct Cmd {
+    /// force download and build the latest node
+    #[argh(switch)]
+    rebuild: bool,
+    /// which user to use for systemd services
+    #[argh(option)]
+    user: Option<String>,
+    /// show the validator wallet address as a QR code
+    #[argh(switch)]
+    qr: bool,
+    /// copy the validator wallet address to the clipboard
+    #[argh(switch)]
+    copy: bool,
+}
+
+impl Cmd {
+    pub async fn run(
+        self,
+        theme: &dyn Theme,
+        ctx: &CliContext,
+        template: &Option<Template>,
+    ) -> Result<()> {
+        let mut steps = Steps::new(4);
+
+        steps.next("Preparing node");
+        let node = node::Cmd {
+            rebuild: self.rebuild,
+            step: None,
+        }
+        .run(theme, ctx, template)
+        .await?;
+
+        steps.next("Preparing services");
+        #[cfg(not(feature = "packaged"))]
+        {
+            let services = template.as_ref().and_then(|t| t.systemd.as_ref());
+            let user = self
+                .user
+                .clone()
+                .or_else(|| services.and_then(|s| s.user.clone()));
+            anyhow::ensure!(
+                user.is_some() || input_mode() == InputMode::Interactive,
+                "`user` param is required when prompts are disabled"
+            );
+
+            super::systemd::prepare_services(theme, ctx.dirs(), &user)?;
+            super::systemd::systemd_daemon_reload().await?;
+            super::systemd::start_services(
+                theme,
+                node.adopted_node.is_none(),
+                services.map(|s| s.enable),
+                services.map(|s| s.start),
+            )
+            .await?;
+        }
+        #[cfg(feature = "packaged")]
+        eprintln!("{}", style("Services are managed by the package").dim());
+
+        steps.next("Preparing validator");
+        let contracts = contracts::Cmd {
+            qr: self.qr,
+            copy: self.copy,
+        }
+        .run(theme, ctx, template)
+        .await?;
+
+        steps.next("Starting validator manager");
+        let manager_status = if contracts.is_some() {
+            // NOTE: restart to apply the new validator config immediately
+            system::systemd_restart_service(crate::dirs::VALIDATOR_MANAGER_SERVICE).await?;
+            wait_for_manager(ctx).await
+        } else {
+            None
+        };
+
+        if is_terminal() && !is_json_output() {
+            let state = match &manager_status {
+                Some(_) => style("running").green(),
+                None => style("not running").yellow(),
+            };
+            eprintln!(
+                "\n{} {state}\n{}",
+                style("Validator manager:").bold(),
+                style("Run `nodekeeper validator status` to check the validator").dim(),
+            );
+        } else {
+            print_output(super::with_steps(serde_json::json!({
+                "node": node,
+                "contracts": contracts,
+                "manager": manager_status,
+            })));
+        }
+        Ok(())
+    }
+}
+
+/// Waits until the manager control socket is ready and returns its status
+async fn wait_for_manager(ctx: &CliContext) -> Option<serde_json::Value> {
+    const ATTEMPTS: usize = 30;
+    const INTERVAL: Duration = Duration::from_secs(1);
+
+    let progress = Progress::spinner("Waiting for the validator manager");
+    for _ in 0..ATTEMPTS {
+        if let Some(mut client) = ManagerClient::connect(&ctx.dirs().manager_socket).await {
+            match client.status().await {
+                Ok(status) => {
+                    progress.finish();
+                    return Some(status);
+                }
+                Err(e) => tracing::debug!("manager is not ready: {e:?}"),
+            }
+        }
+        tokio::time::sleep(INTERVAL).await;
+    }
+    progress.finish();
+    None
+}
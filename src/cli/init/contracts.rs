@@ -1,5 +1,6 @@
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use std::time::Duration;
 
 use anyhow::{Context, Result};
 use argh::FromArgs;
@@ -15,6 +16,7 @@ use crate::cli::{CliContext, ProjectDirs};
 use crate::config::*;
 use crate::contracts::*;
 use crate::crypto;
+use crate::network::{connect_data_source, DataSource};
 use crate::util::*;
 
 const DEFAULT_STAKE_FACTOR: f64 = 3.0;
@@ -26,7 +28,14 @@ const DEFAULT_PARTICIPANT_REWARD_FRACTION: u8 = 95;
 #[derive(FromArgs)]
 /// Deploys contracts required for validation
 #[argh(subcommand, name = "contracts")]
-pub struct Cmd {}
+pub struct Cmd {
+    /// show the validator wallet address as a QR code
+    #[argh(switch)]
+    pub qr: bool,
+    /// copy the validator wallet address to the clipboard
+    #[argh(switch)]
+    pub copy: bool,
+}
 
 impl Cmd {
     pub async fn run(
@@ -46,8 +55,8 @@ impl Cmd {
             None => None,
         };
 
-        let mut config = ctx.load_config()?;
         let dirs = ctx.dirs();
+        let mut config = AppConfig::load_file(&dirs.app_config)?;
 
         // Create keys directory if it doesn't exist
         if !dirs.keys_dir.exists() {
@@ -66,7 +75,10 @@ impl Cmd {
                 )?,
             };
             if !overwrite {
-                return Ok(Some(Output::from_existing(dirs, validator)));
+                // NOTE: allows to resume waiting for the wallet refill
+                let output = Output::from_existing(dirs, validator);
+                self.show_wallet(&config, &output).await?;
+                return Ok(Some(output));
             }
 
             if template.is_some() && overwrite {
@@ -101,8 +113,114 @@ impl Cmd {
                 },
             };
 
+        self.show_wallet(&config, &output).await?;
+
         Ok(Some(output))
     }
+
+    /// Shows payment instructions and waits until the validator wallet is refilled
+    async fn show_wallet(&self, config: &AppConfig, output: &Output) -> Result<()> {
+        const POLL_INTERVAL: Duration = Duration::from_secs(10);
+        const REFILL_TIMEOUT: Duration = Duration::from_secs(3600);
+
+        if !is_terminal() {
+            return Ok(());
+        }
+
+        let wallet_address = output.validator_wallet();
+        let target_balance = output.target_balance();
+
+        let data_source = match connect_data_source(config).await {
+            Ok(data_source) => Some(data_source),
+            Err(e) => {
+                print_warning(format!("unable to check the wallet balance: {e:#}"));
+                None
+            }
+        };
+        let balance = match &data_source {
+            Some(data_source) => get_balance(data_source.as_ref(), wallet_address).await?,
+            None => 0,
+        };
+
+        let missing = target_balance.saturating_sub(balance);
+        let qr_data = if missing > 0 {
+            let payment_uri = format!("ton://transfer/{wallet_address}?amount={missing}");
+            eprintln!(
+                "\n{} {}\n{} {}",
+                style("Amount to send:").green().bold(),
+                style(format!("{:#}", Tokens(missing))).bold(),
+                style("Payment link:").green().bold(),
+                style(&payment_uri).bold(),
+            );
+            payment_uri
+        } else {
+            wallet_address.to_string()
+        };
+
+        if self.qr {
+            eprintln!("\n{}", render_qr(&qr_data)?);
+        }
+        if self.copy {
+            copy_to_clipboard(&wallet_address.to_string(), None)?;
+            eprintln!("{}", style("Wallet address copied to the clipboard").dim());
+        }
+
+        let Some(data_source) = data_source else {
+            return Ok(());
+        };
+        if missing == 0 {
+            eprintln!("{}", style("Validator wallet balance is enough").green());
+            return Ok(());
+        }
+
+        eprintln!(
+            "{}",
+            style("Press Ctrl+C to stop waiting, run `nodekeeper init contracts` to resume").dim()
+        );
+        let progress = Progress::spinner("Waiting for the wallet refill");
+        let wait_for_refill = async {
+            loop {
+                tokio::time::sleep(POLL_INTERVAL).await;
+                match get_balance(data_source.as_ref(), wallet_address).await {
+                    Ok(balance) if balance >= target_balance => break balance,
+                    Ok(balance) => progress.set_message(format!(
+                        "{:#} of {:#}",
+                        Tokens(balance),
+                        Tokens(target_balance)
+                    )),
+                    Err(e) => tracing::debug!("failed to get wallet balance: {e:?}"),
+                }
+            }
+        };
+        let result = tokio::time::timeout(REFILL_TIMEOUT, wait_for_refill).await;
+        progress.finish();
+
+        match result {
+            Ok(balance) => eprintln!(
+                "{} {}",
+                style("Validator wallet refilled:").green().bold(),
+                style(format!("{:#}", Tokens(balance))).bold(),
+            ),
+            Err(_) => print_warning(
+                "validator wallet is still not refilled, \
+                run `nodekeeper init contracts` to resume waiting",
+            ),
+        }
+        Ok(())
+    }
+}
+
+async fn get_balance(
+    data_source: &dyn DataSource,
+    address: &ton_block::MsgAddressInt,
+) -> Result<u128> {
+    let account = data_source
+        .get_account_state(address)
+        .await
+        .context("failed to get account state")?;
+    Ok(account
+        .map(|account| account.storage.balance.grams.as_u128())
+        .unwrap_or_default())
 }
 
 #[derive(Debug, Serialize)]
@@ -113,6 +231,20 @@ pub enum Output {
 }
 
 impl Output {
+    fn validator_wallet(&self) -> &ton_block::MsgAddressInt {
+        match self {
+            Self::Single(output) => &output.validator_wallet,
+            Self::DePool(output) => &output.validator_wallet,
+        }
+    }
+
+    fn target_balance(&self) -> u128 {
+        match self {
+            Self::Single(output) => output.target_balance,
+            Self::DePool(output) => output.target_balance,
+        }
+    }
+
     fn from_existing(dirs: &ProjectDirs, validator: &AppConfigValidator) -> Self {
         match validator {
             AppConfigValidator::Single(single) => Self::Single(OutputSingle {
@@ -170,7 +302,9 @@ fn prepare_single_validator(
     }
     .interact(template.map(|t| t.overwrite_validator_keys))?;
 
-    let wallet_address = wallet::compute_wallet_address(-1, &keypair.public);
+    // NOTE: elector accepts stakes only from the masterchain
+    let workchain = configure_wallet_workchain(theme, &keypair.public, Some(Some(-1)))?;
+    let wallet_address = wallet::compute_wallet_address(workchain, &keypair.public);
 
     // Configure stake params
     steps.next("Configuring the stake");
@@ -217,6 +351,8 @@ fn prepare_single_validator(
         address: wallet_address.clone(),
         stake_per_round,
         stake_factor: Some(stake_factor),
+        stake_strategy: None,
+        recovered_stake: None,
     }));
     dirs.store_app_config(app_config)?;
 
@@ -371,9 +507,12 @@ fn prepare_new_depool(
     }
     .interact(template.map(|t| t.overwrite_validator_keys))?;
 
-    // TODO: Select wallet type
-
-    let wallet_address = wallet::compute_wallet_address(0, &wallet_keypair.public);
+    let workchain = configure_wallet_workchain(
+        theme,
+        &wallet_keypair.public,
+        template.map(|t| t.wallet_workchain),
+    )?;
+    let wallet_address = wallet::compute_wallet_address(workchain, &wallet_keypair.public);
 
     // Create depool
     steps.next("Creating DePool");
@@ -489,6 +628,8 @@ fn prepare_new_depool(
             validator_assurance,
             participant_reward_fraction,
         }),
+        reactions: None,
+        proxy_top_up: None,
     };
 
     // Configure stEVER strategies stuff
@@ -530,9 +671,8 @@ fn prepare_existing_depool(
     }
     .interact(None)?;
 
-    // TODO: Select wallet type
-
-    let wallet_address = wallet::compute_wallet_address(0, &wallet_keypair.public);
+    let workchain = configure_wallet_workchain(theme, &wallet_keypair.public, None)?;
+    let wallet_address = wallet::compute_wallet_address(workchain, &wallet_keypair.public);
 
     // Prepare validator wallet
     steps.next("Creating DePool");
@@ -567,6 +707,8 @@ fn prepare_existing_depool(
         stake_factor: Some(stake_factor),
         cluster: None,
         deploy: None,
+        reactions: None,
+        proxy_top_up: None,
     };
 
     // Configure stEVER strategies stuff
@@ -595,6 +737,48 @@ impl DePoolType {
     }
 }
 
+fn configure_wallet_workchain(
+    theme: &dyn Theme,
+    public: &ed25519_dalek::PublicKey,
+    template: Option<Option<i8>>,
+) -> Result<i8> {
+    const WORKCHAINS: [i8; 2] = [0, -1];
+
+    let addresses = WORKCHAINS.map(|workchain| wallet::compute_wallet_address(workchain, public));
+
+    // Show wallet address preview
+    if is_terminal() {
+        eprintln!(
+            "{}\n{}\n{}\n{}\n{}",
+            style("Validator wallet state init hash:").green().bold(),
+            style(wallet::compute_wallet_state_init_hash(public).to_hex_string()).bold(),
+            style("Validator wallet addresses:").green().bold(),
+            style(format!("  basechain:   {}", addresses[0])).bold(),
+            style(format!("  masterchain: {}", addresses[1])).bold(),
+        );
+    }
+
+    Ok(match template {
+        Some(None) => 0,
+        Some(Some(workchain)) => {
+            anyhow::ensure!(
+                WORKCHAINS.contains(&workchain),
+                "Unsupported wallet workchain: {workchain}"
+            );
+            workchain
+        }
+        None => {
+            let selected = Select::with_theme(theme)
+                .with_prompt("Select validator wallet workchain")
+                .item(format!("Basechain ({})", addresses[0]))
+                .item(format!("Masterchain ({})", addresses[1]))
+                .default(0)
+                .interact()?;
+            WORKCHAINS[selected]
+        }
+    })
+}
+
 fn configure_stake_factor(theme: &dyn Theme, template: Option<Option<u32>>) -> Result<u32> {
     const MIN_STAKE_FACTOR: f64 = 1.0;
     const MAX_STAKE_FACTOR: f64 = 3.0;
@@ -0,0 +1,276 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use argh::FromArgs;
+use everscale_crypto::ed25519;
+
+use super::ProjectDirs;
+use crate::config::{
+    AppConfig, AppConfigAdnl, AppConfigControl, GlobalConfig, NodeConfig, NodeConsoleConfig,
+    NodeLogConfig, StoredKeys,
+};
+use crate::network::NodeTcpRpc;
+use crate::util::*;
+
+#[derive(FromArgs)]
+/// Imports the node setup created by other tooling (main.ton.dev scripts, ever-node-tools)
+#[argh(subcommand, name = "import")]
+pub struct Cmd {
+    /// directory with the existing setup (e.g. `/ton-node` or `~/ton-keys`)
+    #[argh(option)]
+    pub from: PathBuf,
+
+    /// overwrite existing nodekeeper configs
+    #[argh(switch)]
+    pub force: bool,
+}
+
+impl Cmd {
+    pub async fn run(self, dirs: &ProjectDirs) -> Result<()> {
+        print_output(self.import(dirs).await?);
+        Ok(())
+    }
+
+    /// Imports the setup and returns a summary of the imported files
+    pub async fn import(&self, dirs: &ProjectDirs) -> Result<serde_json::Value> {
+        let found = FoundFiles::detect(&self.from);
+        let node_config_path = found
+            .node_config
+            .as_ref()
+            .context("node config not found")?;
+        let console_config_path = found
+            .console_config
+            .as_ref()
+            .context("console config not found")?;
+        let global_config_path = found
+            .global_config
+            .as_ref()
+            .context("global config not found")?;
+
+        if !self.force {
+            for path in [&dirs.app_config, &dirs.node_config, &dirs.validator_keys] {
+                anyhow::ensure!(
+                    !path.exists(),
+                    "{} already exists, use `--force` to overwrite it",
+                    path.display()
+                );
+            }
+        }
+
+        let mut node_config = NodeConfig::load(node_config_path)?;
+        let console_config = NodeConsoleConfig::load(console_config_path)?;
+        let global_config = GlobalConfig::load(global_config_path)?;
+
+        // Ensure that the console keys are accepted by the node
+        let control_server = node_config
+            .get_control_server()?
+            .context("control server is not configured in the node config")?;
+        let console = console_config.config;
+        anyhow::ensure!(
+            ed25519::PublicKey::from(&control_server.server_key) == console.server_key,
+            "console server key doesn't match the node control server"
+        );
+        let client_pubkey = ed25519::PublicKey::from(&console.client_key);
+        if let Some(clients) = &control_server.clients {
+            anyhow::ensure!(
+                clients.contains(&client_pubkey),
+                "console client key is not allowed by the node control server"
+            );
+        }
+
+        let adnl_node = node_config
+            .get_adnl_node()?
+            .context("ADNL is not configured in the node config")?;
+
+        // Prepare app config
+        let mut app_config = if dirs.app_config.exists() {
+            AppConfig::load_file(&dirs.app_config)?
+        } else {
+            AppConfig::default()
+        };
+        app_config.control = Some(AppConfigControl::from_addr_and_keys(
+            console.server_address,
+            console.server_key,
+            console.client_key,
+        ));
+        app_config.adnl = Some(AppConfigAdnl {
+            client_port: AppConfigAdnl::AUTO_CLIENT_PORT,
+            server_address: adnl_node.ip_address,
+            server_pubkey: adnl_node.overlay_pubkey()?,
+            zerostate_file_hash: *global_config.zero_state.file_hash.as_array(),
+            trust_mode: false,
+        });
+
+        // Verify connectivity before writing anything
+        let node_tcp_rpc = NodeTcpRpc::new(app_config.control()?)
+            .await
+            .context("failed to build node TCP client")?;
+        let stats = node_tcp_rpc
+            .get_stats()
+            .await
+            .context("failed to connect to the node control server")?;
+
+        // Store configs in the nodekeeper layout
+        std::fs::create_dir_all(&dirs.node_configs_dir)
+            .context("failed to create node configs directory")?;
+        std::fs::copy(global_config_path, &dirs.global_config)
+            .context("failed to copy global config")?;
+        if !dirs.node_log_config.exists() {
+            NodeLogConfig::generate().store(&dirs.node_log_config)?;
+        }
+        node_config.set_global_config_path(&dirs.global_config)?;
+        node_config.store(&dirs.node_config)?;
+        app_config.store(&dirs.app_config)?;
+
+        let mut validator_keys = None;
+        if let Some(path) = &found.validator_keys {
+            let keys = StoredKeys::load(path).context("failed to load validator keys")?;
+            std::fs::create_dir_all(&dirs.keys_dir).context("failed to create keys directory")?;
+            keys.store(&dirs.validator_keys)?;
+            validator_keys = Some(hex::encode(keys.as_keypair().public.as_bytes()));
+        }
+
+        if is_terminal() {
+            eprintln!("Imported setup from {}", self.from.display());
+            if app_config.validator.is_none() {
+                eprintln!("Run `nodekeeper init contracts` to configure the validator wallet");
+            }
+        }
+
+        Ok(serde_json::json!({
+            "node_config": node_config_path,
+            "console_config": console_config_path,
+            "global_config": global_config_path,
+            "validator_keys": found.validator_keys,
+            "validator_pubkey": validator_keys,
+            "wallet_address": console_config.wallet_id,
+            "node_stats": stats,
+        }))
+    }
+}
+
+/// Node process which was started without nodekeeper (manually or in docker)
+pub struct RunningNode {
+    pub pid: u32,
+    /// Directory with the node setup (suitable for `import`)
+    pub setup_dir: PathBuf,
+}
+
+/// Finds a running node process which doesn't use the specified configs directory
+pub fn find_running_node(own_configs_dir: &Path) -> Option<RunningNode> {
+    const NODE_BINARIES: &[&str] = &["ton_node", "ton-node", "node"];
+
+    let own_configs_dir = std::fs::canonicalize(own_configs_dir).ok();
+
+    for entry in std::fs::read_dir("/proc").ok()?.flatten() {
+        let Some(pid) = entry
+            .file_name()
+            .to_str()
+            .and_then(|pid| pid.parse::<u32>().ok())
+        else {
+            continue;
+        };
+        let Ok(cmdline) = std::fs::read(entry.path().join("cmdline")) else {
+            continue;
+        };
+        let args = cmdline
+            .split(|&b| b == 0)
+            .map(|arg| String::from_utf8_lossy(arg).into_owned())
+            .collect::<Vec<_>>();
+
+        let Some(binary) = args.first().map(Path::new).and_then(Path::file_name) else {
+            continue;
+        };
+        if !NODE_BINARIES.iter().any(|name| binary == *name) {
+            continue;
+        }
+        let Some(configs_dir) = parse_configs_arg(&args[1..]) else {
+            continue;
+        };
+
+        // NOTE: paths are resolved through procfs to support containers
+        // (it is not canonicalized to keep the container root in the path)
+        let configs_dir = if configs_dir.is_absolute() {
+            let path = configs_dir.strip_prefix("/").unwrap_or(&configs_dir);
+            entry.path().join("root").join(path)
+        } else {
+            entry.path().join("cwd").join(configs_dir)
+        };
+        match std::fs::canonicalize(&configs_dir) {
+            Ok(path) if Some(&path) != own_configs_dir.as_ref() => {}
+            _ => continue,
+        }
+
+        // Keys are usually stored next to the configs directory
+        let setup_dir = [Some(configs_dir.as_path()), configs_dir.parent()]
+            .into_iter()
+            .flatten()
+            .find(|dir| {
+                let found = FoundFiles::detect(dir);
+                found.node_config.is_some() && found.console_config.is_some()
+            });
+        if let Some(setup_dir) = setup_dir {
+            return Some(RunningNode {
+                pid,
+                setup_dir: setup_dir.to_path_buf(),
+            });
+        }
+    }
+
+    None
+}
+
+fn parse_configs_arg(args: &[String]) -> Option<PathBuf> {
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--configs" || arg == "-c" {
+            return args.next().map(PathBuf::from);
+        }
+        if let Some(path) = arg.strip_prefix("--configs=") {
+            return Some(PathBuf::from(path));
+        }
+    }
+    None
+}
+
+/// Known files of the existing setup
+struct FoundFiles {
+    node_config: Option<PathBuf>,
+    console_config: Option<PathBuf>,
+    global_config: Option<PathBuf>,
+    validator_keys: Option<PathBuf>,
+}
+
+impl FoundFiles {
+    fn detect(root: &Path) -> Self {
+        // NOTE: ever-node-tools keep configs in `configs/`,
+        // main.ton.dev scripts keep keys in `ton-keys/`
+        const NODE_CONFIG: &[&str] = &["config.json", "configs/config.json"];
+        const CONSOLE_CONFIG: &[&str] = &[
+            "console.json",
+            "configs/console.json",
+            "ton-keys/console.json",
+        ];
+        const GLOBAL_CONFIG: &[&str] = &[
+            "ton-global.config.json",
+            "configs/ton-global.config.json",
+            "global-config.json",
+            "configs/global-config.json",
+        ];
+        const VALIDATOR_KEYS: &[&str] = &["msig.keys.json", "ton-keys/msig.keys.json"];
+
+        let find = |candidates: &[&str]| {
+            candidates
+                .iter()
+                .map(|name| root.join(name))
+                .find(|path| path.is_file())
+        };
+
+        Self {
+            node_config: find(NODE_CONFIG),
+            console_config: find(CONSOLE_CONFIG),
+            global_config: find(GLOBAL_CONFIG),
+            validator_keys: find(VALIDATOR_KEYS),
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use std::net::Ipv4Addr;
+use std::net::{Ipv4Addr, SocketAddrV4};
 use std::path::PathBuf;
 
 use anyhow::{Context, Result};
@@ -7,11 +7,18 @@ use broxus_util::{const_bool, serde_optional_string, serde_string_or_number};
 use serde::{Deserialize, Serialize};
 
 use super::{CliContext, ProjectDirs};
-use crate::config::{AppConfig, AppConfigDePoolDeploymentParams, DePoolType, NodeConfig};
+use crate::config::{
+    AppConfig, AppConfigDePoolDeploymentParams, DePoolType, NodeConfig, NodeLogLevel, NodeRole,
+};
 use crate::defaults;
-use crate::util::{is_terminal, print_output};
+use crate::util::{
+    input_mode, is_json_output, is_terminal, print_output, print_steps_summary, take_steps_log,
+    InputMode,
+};
 
+mod all;
 mod contracts;
+mod import;
 mod node;
 #[cfg(not(feature = "packaged"))]
 mod systemd;
@@ -28,12 +35,35 @@ pub struct Cmd {
     /// force download and build the latest node
     #[argh(switch)]
     rebuild: bool,
+    /// only run the specified step (`configs`, `control`, `adnl`, `binary` or `services`)
+    #[argh(option)]
+    step: Option<InitStep>,
+    /// show the validator wallet address as a QR code
+    #[argh(switch)]
+    qr: bool,
+    /// copy the validator wallet address to the clipboard
+    #[argh(switch)]
+    copy: bool,
 }
 
 impl Cmd {
     pub async fn run(self, ctx: CliContext) -> Result<()> {
+        let result = self.run_steps(ctx).await;
+
+        // NOTE: the summary is also useful when some step failed
+        if is_terminal() && !is_json_output() {
+            print_steps_summary(&take_steps_log());
+        }
+        result
+    }
+
+    async fn run_steps(self, ctx: CliContext) -> Result<()> {
         fn load_template(template: Option<PathBuf>) -> Result<Option<Template>> {
             let Some(path) = &template else {
+                anyhow::ensure!(
+                    input_mode() == InputMode::Interactive,
+                    "template is required when prompts are disabled"
+                );
                 return Ok(None);
             };
 
@@ -52,34 +82,80 @@ impl Cmd {
         }
 
         let theme = &dialoguer::theme::ColorfulTheme::default();
+        if let Some(step) = self.step {
+            anyhow::ensure!(
+                self.subcommand.is_none(),
+                "`--step` can't be used with subcommands"
+            );
+
+            #[cfg(not(feature = "packaged"))]
+            if step == InitStep::Services {
+                anyhow::ensure!(
+                    self.template.is_none(),
+                    "Template is not supported for `services` step"
+                );
+                return systemd::Cmd::default().run(theme, &ctx).await;
+            }
+            #[cfg(feature = "packaged")]
+            anyhow::ensure!(
+                step != InitStep::Services,
+                "Services are managed by the package"
+            );
+
+            let template = load_template(self.template)?;
+
+            let node = node::Cmd {
+                rebuild: self.rebuild,
+                step: Some(step),
+            }
+            .run(theme, &ctx, &template)
+            .await?;
+
+            if template.is_some() && !is_terminal() {
+                print_output(with_steps(serde_json::to_value(node).unwrap()));
+            }
+
+            return Ok(());
+        }
+
         match self.subcommand {
             None => {
                 let template = load_template(self.template)?;
 
                 let node = node::Cmd {
                     rebuild: self.rebuild,
+                    step: None,
                 }
                 .run(theme, &ctx, &template)
                 .await?;
 
-                let contracts = contracts::Cmd {}.run(theme, &ctx, &template).await?;
+                let contracts = contracts::Cmd {
+                    qr: self.qr,
+                    copy: self.copy,
+                }
+                .run(theme, &ctx, &template)
+                .await?;
 
                 if template.is_some() && !is_terminal() {
-                    print_output(serde_json::json!({
+                    print_output(with_steps(serde_json::json!({
                         "node": node,
                         "contracts": contracts,
-                    }));
+                    })));
                 }
 
                 Ok(())
             }
+            Some(SubCmd::All(cmd)) => {
+                let template = load_template(self.template)?;
+                cmd.run(theme, &ctx, &template).await
+            }
             Some(SubCmd::Node(cmd)) => {
                 let template = load_template(self.template)?;
 
                 let node = cmd.run(theme, &ctx, &template).await?;
 
                 if template.is_some() && !is_terminal() {
-                    print_output(serde_json::to_value(node).unwrap());
+                    print_output(with_steps(serde_json::to_value(node).unwrap()));
                 }
 
                 Ok(())
@@ -90,11 +166,18 @@ impl Cmd {
                 let contracts = cmd.run(theme, &ctx, &template).await?;
 
                 if template.is_some() && !is_terminal() {
-                    print_output(serde_json::to_value(contracts).unwrap());
+                    print_output(with_steps(serde_json::to_value(contracts).unwrap()));
                 }
 
                 Ok(())
             }
+            Some(SubCmd::Import(cmd)) => {
+                anyhow::ensure!(
+                    self.template.is_none(),
+                    "Template is not supported for `import` command"
+                );
+                cmd.run(&ctx.dirs).await
+            }
             #[cfg(not(feature = "packaged"))]
             Some(SubCmd::Systemd(cmd)) => {
                 anyhow::ensure!(
@@ -107,15 +190,57 @@ impl Cmd {
     }
 }
 
+/// Adds the recorded steps to the machine-readable output
+fn with_steps(mut output: serde_json::Value) -> serde_json::Value {
+    if let Some(output) = output.as_object_mut() {
+        output.insert("steps".to_owned(), serde_json::json!(take_steps_log()));
+    }
+    output
+}
+
 #[derive(FromArgs)]
 #[argh(subcommand)]
 enum SubCmd {
+    All(all::Cmd),
     Node(node::Cmd),
     Contracts(contracts::Cmd),
+    Import(import::Cmd),
     #[cfg(not(feature = "packaged"))]
     Systemd(systemd::Cmd),
 }
 
+/// Part of the node setup which can be re-run separately
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum InitStep {
+    /// Node config paths, role and sections
+    Configs,
+    /// Control server keys and address
+    Control,
+    /// ADNL keys and public address
+    Adnl,
+    /// Node binary
+    Binary,
+    /// Systemd services
+    Services,
+}
+
+impl std::str::FromStr for InitStep {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "configs" => Ok(Self::Configs),
+            "control" => Ok(Self::Control),
+            "adnl" => Ok(Self::Adnl),
+            "binary" => Ok(Self::Binary),
+            "services" => Ok(Self::Services),
+            _ => Err(anyhow::anyhow!(
+                "unknown step (neither `configs`, `control`, `adnl`, `binary` nor `services`)"
+            )),
+        }
+    }
+}
+
 impl ProjectDirs {
     fn store_app_config(&self, app_config: &AppConfig) -> Result<()> {
         app_config.store(&self.app_config)
@@ -140,9 +265,17 @@ pub struct Template {
     #[serde(default)]
     adnl: TemplateAdnl,
 
+    /// Node settings.
+    #[serde(default)]
+    node: TemplateNode,
+
     /// Optional validation params.
     #[serde(default)]
     validator: Option<TemplateValidator>,
+
+    /// Systemd services settings (used by `init all`).
+    #[serde(default)]
+    systemd: Option<TemplateSystemd>,
 }
 
 fn default_global_config() -> Option<String> {
@@ -155,7 +288,7 @@ struct TemplateGeneral {
     #[serde(default = "const_bool::<true>")]
     create_root_dir: bool,
 
-    /// Path, url or name of the global config. Default: `ever_mainnet`.
+    /// Path, url or network preset name of the global config. Default: `ever_mainnet`.
     ///
     /// NOTE: Tries to use the existing one if None.
     #[serde(default = "default_global_config")]
@@ -225,6 +358,10 @@ struct TemplateValidatorDePool {
     /// DePool type.
     depool_type: DePoolType,
 
+    /// Validator wallet workchain (`0` or `-1`). Default: `0`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    wallet_workchain: Option<i8>,
+
     /// Optional stake factor.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     stake_factor: Option<u32>,
@@ -242,6 +379,29 @@ struct TemplateValidatorDePool {
     deploy: AppConfigDePoolDeploymentParams,
 }
 
+#[derive(Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+struct TemplateSystemd {
+    /// Which user to use for systemd services. Default: `None` (current user).
+    user: Option<String>,
+
+    /// Whether to enable services for auto-start. Default: `true`.
+    enable: bool,
+
+    /// Whether to immediately start services. Default: `true`.
+    start: bool,
+}
+
+impl Default for TemplateSystemd {
+    fn default() -> Self {
+        Self {
+            user: None,
+            enable: true,
+            start: true,
+        }
+    }
+}
+
 #[derive(Deserialize, Serialize)]
 #[serde(default, deny_unknown_fields)]
 struct TemplateControl {
@@ -284,6 +444,29 @@ struct TemplateAdnl {
     public_ip: Option<Ipv4Addr>,
 }
 
+#[derive(Default, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+struct TemplateNode {
+    /// Node role: `validator`, `full` or `archive`. Default: `None` (keep the existing one).
+    role: Option<NodeRole>,
+
+    /// Block archives lifetime in hours, `0` to keep them forever. Default: depends on role.
+    ///
+    /// NOTE: Applied only to a new node config if not specified.
+    archives_life_time_hours: Option<u32>,
+
+    /// Cells cache size in bytes. Default: 1/8 of the total memory.
+    ///
+    /// NOTE: Applied only to a new node config if not specified.
+    cells_cache_size: Option<u64>,
+
+    /// Node metrics endpoint address. Default: `None` (disabled).
+    metrics_addr: Option<SocketAddrV4>,
+
+    /// Log level for the node modules. Default: `None` (keep the existing one).
+    log_level: Option<NodeLogLevel>,
+}
+
 struct TemplateNodeRepo {
     /// Node repository URL.
     url: reqwest::Url,
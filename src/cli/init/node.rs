@@ -13,7 +13,7 @@ use reqwest::Url;
 use serde::Serialize;
 use tokio::process::Command;
 
-use super::Template;
+use super::{InitStep, Template};
 use crate::cli::{CliContext, ProjectDirs};
 use crate::config::*;
 use crate::defaults;
@@ -26,6 +26,9 @@ pub struct Cmd {
     /// force download and build the latest node
     #[argh(switch)]
     pub rebuild: bool,
+    /// only run the specified step (`configs`, `control`, `adnl` or `binary`)
+    #[argh(option)]
+    pub step: Option<InitStep>,
 }
 
 impl Cmd {
@@ -35,10 +38,16 @@ impl Cmd {
         ctx: &CliContext,
         template: &Option<Template>,
     ) -> Result<Output> {
+        anyhow::ensure!(
+            self.step != Some(InitStep::Services),
+            "`services` step is not a part of the node setup"
+        );
+
         let dirs = ctx.dirs();
+        let runs = |step: InitStep| self.step.map_or(true, |selected| selected == step);
 
         // Compute steps len
-        let mut steps = Steps::new(2);
+        let mut steps = Steps::new(if runs(InitStep::Binary) { 2 } else { 1 });
 
         steps.next("Preparing configs");
 
@@ -49,6 +58,31 @@ impl Cmd {
             return Ok(output);
         }
 
+        // Offer to adopt the node which was started without nodekeeper
+        if self.step.is_none() && template.is_none() && !dirs.app_config.exists() {
+            if let Some(node) = super::import::find_running_node(&dirs.node_configs_dir) {
+                let prompt = format!(
+                    "Found a running node (pid {}) with configs at {}. Adopt it?",
+                    node.pid,
+                    node.setup_dir.display()
+                );
+                if confirm(theme, true, prompt)? {
+                    let import = super::import::Cmd {
+                        from: node.setup_dir,
+                        force: false,
+                    };
+                    output.adopted_node = Some(
+                        import
+                            .import(dirs)
+                            .await
+                            .context("failed to adopt the running node")?,
+                    );
+                    steps.next("Running node is adopted now. Great!");
+                    return Ok(output);
+                }
+            }
+        }
+
         // Ensure that global config exists
         let global_config = load_global_config(theme, dirs, template, &mut output).await?;
         // Ensure that node config exists
@@ -57,47 +91,71 @@ impl Cmd {
         let mut app_config = load_app_config(dirs, template, &mut output)?;
 
         // Configure control server
-        if !setup_control_server(
-            theme,
-            dirs,
-            template,
-            &mut app_config,
-            &mut node_config,
-            &mut output,
-        )? {
-            return Ok(output);
+        if runs(InitStep::Control) {
+            // Explicitly selected step allows regenerating existing client keys
+            if self.step.is_some()
+                && template.is_none()
+                && app_config.control.is_some()
+                && confirm(theme, false, "Regenerate control client keys?")?
+            {
+                app_config.control = None;
+            }
+
+            if !setup_control_server(
+                theme,
+                dirs,
+                template,
+                &mut app_config,
+                &mut node_config,
+                &mut output,
+            )? {
+                return Ok(output);
+            }
         }
 
         // Configure udp rpc
-        if !setup_adnl(
-            theme,
-            dirs,
-            template,
-            &mut app_config,
-            &mut node_config,
-            &global_config,
-            &mut output,
-        )
-        .await?
+        if runs(InitStep::Adnl)
+            && !setup_adnl(
+                theme,
+                dirs,
+                template,
+                &mut app_config,
+                &mut node_config,
+                &global_config,
+                &mut output,
+            )
+            .await?
         {
             return Ok(output);
         }
 
         // Configure node config
-        setup_node_config_paths(theme, dirs, template, &mut node_config, &mut output)?;
+        if runs(InitStep::Configs) {
+            setup_node_config_paths(theme, dirs, template, &mut node_config, &mut output)?;
+            let role = setup_node_role(
+                theme,
+                dirs,
+                template,
+                &mut app_config,
+                &mut node_config,
+                &mut output,
+            )?;
+            setup_node_config_sections(theme, dirs, template, role, &mut node_config, &mut output)?;
+        }
 
         // Clone and build the node
-        steps.next("Preparing binary");
-        if !setup_binary(
-            theme,
-            dirs,
-            &app_config,
-            template,
-            self.rebuild,
-            &mut output,
-        )
-        .await?
-        {
+        if runs(InitStep::Binary) {
+            steps.next("Preparing binary");
+
+            // Explicitly selected step always rebuilds the node
+            let rebuild = self.rebuild || self.step.is_some();
+            if !setup_binary(theme, dirs, &app_config, template, rebuild, &mut output).await? {
+                return Ok(output);
+            }
+        }
+
+        if self.step.is_some() {
+            steps.next("Step completed. Great!");
             return Ok(output);
         }
 
@@ -146,6 +204,9 @@ pub struct Output {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub node_db_path: Option<PathBuf>,
 
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub node_role: Option<NodeRole>,
+
     #[serde(skip_serializing_if = "Option::is_none")]
     pub node_binary_updated: Option<bool>,
 
@@ -154,6 +215,9 @@ pub struct Output {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub system_is_suitable: Option<bool>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub adopted_node: Option<serde_json::Value>,
 }
 
 fn prepare_root_dir(
@@ -256,8 +320,13 @@ async fn load_global_config(
             Some(template) => match template.general.global_config.as_deref() {
                 None | Some("ever_mainnet") => Cow::Borrowed(GlobalConfig::MAINNET),
                 Some("ever_testnet") => Cow::Borrowed(GlobalConfig::MAINNET),
-                Some(url) => {
-                    let url = url.parse().context("invalid global config URL")?;
+                Some(name_or_url) => {
+                    let url = match NetworksIndex::load(&dirs.networks_index)?
+                        .find_by_name(name_or_url)
+                    {
+                        Some(preset) => PathOrUrl::Url(preset.global_config_url.clone()),
+                        None => name_or_url.parse().context("invalid global config URL")?,
+                    };
                     download_config(url).await.map(Cow::Owned)?
                 }
             },
@@ -356,7 +425,7 @@ fn load_app_config(
     }
 
     // Load app config if it already exists
-    AppConfig::load(app_config)
+    AppConfig::load_file(app_config)
 }
 
 fn setup_control_server(
@@ -441,7 +510,7 @@ fn setup_control_server(
 
             // Ensure that node clients config has our app in it
             if let Some(clients) = &mut existing_server.clients {
-                let client_pubkey = ed25519::PublicKey::from(&existing_client.client_secret);
+                let client_pubkey = ed25519::PublicKey::from(&*existing_client.client_secret);
                 if !clients.contains(&client_pubkey) {
                     let append = if clients.is_empty() {
                         true
@@ -721,10 +790,11 @@ async fn setup_adnl(
         (None, Some(adnl_node)) => {
             // Create client config
             app_config.adnl = Some(AppConfigAdnl {
-                client_port: defaults::DEFAULT_LOCAL_ADNL_PORT,
+                client_port: AppConfigAdnl::AUTO_CLIENT_PORT,
                 server_address: adnl_node.ip_address,
                 server_pubkey: adnl_node.overlay_pubkey()?,
                 zerostate_file_hash,
+                trust_mode: false,
             });
 
             dirs.store_app_config(app_config)?;
@@ -765,10 +835,11 @@ async fn setup_adnl(
             node_config.set_adnl_node(&adnl_node)?;
 
             app_config.adnl = Some(AppConfigAdnl {
-                client_port: defaults::DEFAULT_LOCAL_ADNL_PORT,
+                client_port: AppConfigAdnl::AUTO_CLIENT_PORT,
                 server_address: adnl_node.ip_address,
                 server_pubkey: adnl_node.overlay_pubkey()?,
                 zerostate_file_hash,
+                trust_mode: false,
             });
 
             dirs.store_app_config(app_config)?;
@@ -851,47 +922,203 @@ fn setup_node_config_paths(
     Ok(())
 }
 
-async fn setup_binary(
+fn setup_node_role(
     theme: &dyn Theme,
     dirs: &ProjectDirs,
-    app_config: &AppConfig,
     template: &Option<Template>,
-    force: bool,
+    app_config: &mut AppConfig,
+    node_config: &mut NodeConfig,
     output: &mut Output,
-) -> Result<bool> {
-    async fn get_node_version<P: AsRef<Path>>(node: P) -> Result<String> {
-        use std::io::Write;
-
-        let child = Command::new(node.as_ref())
-            .arg("--version")
-            .output()
-            .await
-            .context("failed to run node binary")?;
-
-        if !child.status.success() {
-            std::io::stderr().write_all(&child.stdout)?;
-            anyhow::bail!("node finished with exit code {}", child.status);
+) -> Result<NodeRole> {
+    let is_new = output.node_config_reset == Some(true);
+    let old_role = app_config.node_role;
+
+    let role = match template {
+        Some(template) => template.node.role.unwrap_or(app_config.node_role()),
+        None => {
+            let roles = NodeRole::ALL;
+            let default_role = roles
+                .iter()
+                .position(|role| *role == app_config.node_role())
+                .unwrap_or_default();
+            roles[Select::with_theme(theme)
+                .with_prompt("Select node role")
+                .items(&roles)
+                .default(default_role)
+                .interact()?]
+        }
+    };
+
+    if is_new || old_role != Some(role) {
+        node_config.apply_role(role)?;
+        dirs.store_node_config(node_config)?;
+        output.node_config_updated = Some(true);
+
+        app_config.node_role = Some(role);
+        dirs.store_app_config(app_config)?;
+        output.app_config_updated = Some(true);
+
+        if template.is_some() && !is_new {
+            eprintln!("Node role updated");
+        }
+    }
+
+    output.node_role = Some(role);
+    Ok(role)
+}
+
+fn setup_node_config_sections(
+    theme: &dyn Theme,
+    dirs: &ProjectDirs,
+    template: &Option<Template>,
+    role: NodeRole,
+    node_config: &mut NodeConfig,
+    output: &mut Output,
+) -> Result<()> {
+    const DEFAULT_METRICS_ADDR: SocketAddrV4 = SocketAddrV4::new(Ipv4Addr::LOCALHOST, 9100);
+
+    fn suggested_cells_cache_size() -> u64 {
+        const MIN_CACHE_SIZE: u64 = 1 << 30;
+        const MAX_CACHE_SIZE: u64 = 16 << 30;
+
+        let mut system = sysinfo::System::new();
+        system.refresh_memory();
+        (system.total_memory() / 8).clamp(MIN_CACHE_SIZE, MAX_CACHE_SIZE)
+    }
+
+    // Only a newly generated node config has stub sections
+    let is_new = output.node_config_reset == Some(true);
+    let default_archives_life_time_hours = role.archives_life_time_hours().unwrap_or_default();
+
+    let (archives_life_time_hours, cells_cache_size, metrics_addr, log_level) = match template {
+        Some(template) => {
+            let node = &template.node;
+            let archives_life_time_hours = node
+                .archives_life_time_hours
+                .or(is_new.then_some(default_archives_life_time_hours));
+            let cells_cache_size = node
+                .cells_cache_size
+                .or_else(|| is_new.then(suggested_cells_cache_size));
+            let metrics_addr = node.metrics_addr.map(Some);
+            (
+                archives_life_time_hours,
+                cells_cache_size,
+                metrics_addr,
+                node.log_level,
+            )
         }
+        None if !is_new => return Ok(()),
+        None if !confirm(theme, false, "Customize node GC, cache, metrics and logs?")? => (
+            Some(default_archives_life_time_hours),
+            Some(suggested_cells_cache_size()),
+            None,
+            None,
+        ),
+        None => {
+            // Archive node keeps all blocks
+            let archives_life_time_hours = match role {
+                NodeRole::Archive => 0,
+                NodeRole::Validator | NodeRole::Full => Input::with_theme(theme)
+                    .with_prompt("Block archives lifetime in hours (0 to keep forever)")
+                    .default(default_archives_life_time_hours)
+                    .interact_text()?,
+            };
+
+            let cells_cache_size_gb = Input::with_theme(theme)
+                .with_prompt("Cells cache size in GB")
+                .default(suggested_cells_cache_size() >> 30)
+                .validate_with(|gb: &u64| match *gb {
+                    0 => Err("Cache size must be at least 1 GB"),
+                    _ => Ok(()),
+                })
+                .interact_text()?;
 
-        parse_node_version(&child.stdout)
-            .map(String::from)
-            .context("invalid node output during version check")
+            let metrics_addr = if confirm(theme, false, "Enable node metrics endpoint?")? {
+                let addr = node_config
+                    .get_metrics()?
+                    .map(|metrics| metrics.address)
+                    .unwrap_or(DEFAULT_METRICS_ADDR);
+                let addr: SocketAddrV4 = Input::with_theme(theme)
+                    .with_prompt("Metrics endpoint address")
+                    .default(addr)
+                    .interact_text()?;
+                Some(addr)
+            } else {
+                None
+            };
+
+            let levels = NodeLogLevel::ALL;
+            let default_level = levels
+                .iter()
+                .position(|level| *level == NodeLogLevel::default())
+                .unwrap_or_default();
+            let log_level = levels[Select::with_theme(theme)
+                .with_prompt("Node log level")
+                .items(&levels)
+                .default(default_level)
+                .interact()?];
+
+            (
+                Some(archives_life_time_hours),
+                Some(cells_cache_size_gb << 30),
+                Some(metrics_addr),
+                Some(log_level),
+            )
+        }
+    };
+
+    let mut updated = false;
+
+    if let Some(hours) = archives_life_time_hours {
+        let mut gc = node_config.get_gc()?.unwrap_or_default();
+        gc.set_archives_life_time((hours > 0).then_some(hours));
+        node_config.set_gc(&gc)?;
+        updated = true;
+    }
+
+    if let Some(bytes) = cells_cache_size {
+        if node_config.get_cells_cache_size()? != Some(bytes) {
+            node_config.set_cells_cache_size(bytes)?;
+            updated = true;
+        }
+    }
+
+    if let Some(addr) = metrics_addr {
+        let metrics = addr.map(|address| NodeConfigMetrics {
+            address,
+            global_labels: Default::default(),
+        });
+        node_config.set_metrics(metrics.as_ref())?;
+        updated = true;
     }
 
-    fn parse_node_version(output: &[u8]) -> Option<&str> {
-        const OLD_PREFIX: &[u8] = b"TON Node, version ";
-        const NEW_PREFIX: &[u8] = b"EVER Node, version ";
+    if let Some(level) = log_level {
+        dirs.store_node_log_config(&NodeLogConfig::generate_with_level(level))?;
+        output.logger_config_reset = Some(true);
+    }
 
-        output
-            .strip_prefix(NEW_PREFIX)
-            .or_else(|| output.strip_prefix(OLD_PREFIX))
-            .and_then(|output| output.split(|&ch| ch == b'\n').next())
-            .and_then(|output| std::str::from_utf8(output).ok())
+    if updated {
+        dirs.store_node_config(node_config)?;
+        output.node_config_updated = Some(true);
+        if template.is_some() && !is_new {
+            eprintln!("Node config sections updated");
+        }
     }
 
+    Ok(())
+}
+
+async fn setup_binary(
+    theme: &dyn Theme,
+    dirs: &ProjectDirs,
+    app_config: &AppConfig,
+    template: &Option<Template>,
+    force: bool,
+    output: &mut Output,
+) -> Result<bool> {
     if !force && dirs.node_binary.exists() {
         // Only check version if binary exists
-        let node_version = get_node_version(&dirs.node_binary).await?;
+        let node_version = system::get_node_version(&dirs.node_binary).await?;
         output.node_version = Some(node_version);
         return Ok(true);
     }
@@ -915,51 +1142,54 @@ async fn setup_binary(
                 .with_initial_text(app_config.node_repo())
                 .interact_text()?;
 
-            // Parse args:
-            // -b,--branch <branch>
-            // -f,--features <feature_name>+
-            let mut args = args.split(' ');
-            let repo = args.next().context("Url expected")?.parse::<Url>()?;
-
-            let mut branch = None;
-            let mut features = Vec::new();
-            'args: loop {
-                match args.next() {
-                    Some("-b" | "--branch") => {
-                        branch = Some(
-                            args.next()
-                                .map(ToOwned::to_owned)
-                                .context("Expected branch name")?,
-                        );
-                    }
-                    Some("-f" | "--features") => {
-                        for feature in args.by_ref() {
-                            if feature.starts_with('-') {
-                                continue 'args;
-                            }
-                            features.push(feature.to_owned());
-                        }
-                        anyhow::ensure!(!features.is_empty(), "Expected features list");
-                    }
-                    Some(name) => anyhow::bail!("Unknown argument: {name}"),
-                    None => break,
-                }
-            }
-
-            (repo, branch, features)
+            parse_node_repo(&args)?
         }
     };
 
     dirs.install_node_from_repo(&repo, &branch, &features)
         .await?;
 
-    let node_version = get_node_version(&dirs.node_binary).await?;
+    let node_version = system::get_node_version(&dirs.node_binary).await?;
     output.node_binary_updated = Some(true);
     output.node_version = Some(node_version);
 
     Ok(true)
 }
 
+/// Parses node repo URL with optional args:
+/// `-b,--branch <branch>`, `-f,--features <feature_name>+`
+fn parse_node_repo(args: &str) -> Result<(Url, Option<String>, Vec<String>)> {
+    let mut args = args.split(' ');
+    let repo = args.next().context("Url expected")?.parse::<Url>()?;
+
+    let mut branch = None;
+    let mut features = Vec::new();
+    'args: loop {
+        match args.next() {
+            Some("-b" | "--branch") => {
+                branch = Some(
+                    args.next()
+                        .map(ToOwned::to_owned)
+                        .context("Expected branch name")?,
+                );
+            }
+            Some("-f" | "--features") => {
+                for feature in args.by_ref() {
+                    if feature.starts_with('-') {
+                        continue 'args;
+                    }
+                    features.push(feature.to_owned());
+                }
+                anyhow::ensure!(!features.is_empty(), "Expected features list");
+            }
+            Some(name) => anyhow::bail!("Unknown argument: {name}"),
+            None => break,
+        }
+    }
+
+    Ok((repo, branch, features))
+}
+
 async fn clone_repo<P: AsRef<Path>>(url: &Url, branch: &Option<String>, target: P) -> Result<()> {
     // Remove old repo if it exists
     let target = target.as_ref();
@@ -979,9 +1209,12 @@ async fn clone_repo<P: AsRef<Path>>(url: &Url, branch: &Option<String>, target:
     }
 
     // git clone to the target folder
-    exec(command.arg(url.to_string()).arg(target))
+    let progress = Progress::spinner("Cloning node repo");
+    exec_with_progress(command.arg(url.to_string()).arg(target), &progress)
         .await
-        .context("failed to clone repo")
+        .context("failed to clone repo")?;
+    progress.finish();
+    Ok(())
 }
 
 async fn build_node<P: AsRef<Path>>(target: P, features: &[String]) -> Result<PathBuf> {
@@ -1009,7 +1242,11 @@ async fn build_node<P: AsRef<Path>>(target: P, features: &[String]) -> Result<Pa
     }
 
     // cargo build in the target folder
-    exec(&mut command).await.context("failed to build node")?;
+    let progress = Progress::spinner("Building node");
+    exec_with_progress(&mut command, &progress)
+        .await
+        .context("failed to build node")?;
+    progress.finish();
 
     // Return the path to the freshly built binary
     for binary_name in ["ton_node", "ever-node"] {
@@ -1135,6 +1372,12 @@ impl ProjectDirs {
         Ok(())
     }
 
+    /// Builds and installs the node from `<url> [-b <branch>] [-f <features>]`
+    pub async fn install_node(&self, node_repo: &str) -> Result<()> {
+        let (repo, branch, features) = parse_node_repo(node_repo)?;
+        self.install_node_from_repo(&repo, &branch, &features).await
+    }
+
     pub async fn install_node_from_repo(
         &self,
         repo: &Url,
@@ -10,10 +10,11 @@ use dialoguer::Select;
 use tokio::process::Command;
 
 use crate::cli::{CliContext, ProjectDirs};
+use crate::config::AppConfig;
 use crate::dirs::{VALIDATOR_EXPORTER_SERVICE, VALIDATOR_MANAGER_SERVICE, VALIDATOR_SERVICE};
 use crate::util::*;
 
-#[derive(FromArgs)]
+#[derive(Default, FromArgs)]
 /// Creates systemd services
 #[argh(subcommand, name = "systemd")]
 pub struct Cmd {
@@ -33,6 +34,9 @@ impl Cmd {
         if self.user.is_none() && !is_terminal() {
             anyhow::bail!("`user` param is required when running without tty");
         }
+        if self.user.is_none() && input_mode() != InputMode::Interactive {
+            anyhow::bail!("`user` param is required when prompts are disabled");
+        }
 
         let dirs = ctx.dirs();
         let mut steps = Steps::new(2);
@@ -47,7 +51,7 @@ impl Cmd {
 
         // Optionally start services
         steps.next("Systemd services are configured now. Great!");
-        start_services(theme, self.enable, self.start).await?;
+        start_services(theme, true, self.enable, self.start).await?;
 
         Ok(())
     }
@@ -127,6 +131,7 @@ pub fn prepare_services(
 
 pub async fn start_services(
     theme: &dyn Theme,
+    with_node: bool,
     enable: Option<bool>,
     start: Option<bool>,
 ) -> Result<()> {
@@ -135,20 +140,22 @@ pub async fn start_services(
         VALIDATOR_MANAGER_SERVICE,
         VALIDATOR_EXPORTER_SERVICE,
     ];
+    // NOTE: adopted nodes are managed outside of nodekeeper
+    let services = &services[if with_node { 0 } else { 1 }..];
 
     let enabled = match enable {
         Some(enable) => enable,
         None => confirm(theme, true, "Enable autostart services at system startup?")?,
     };
-    systemd_set_services_enabled(services, enabled).await?;
+    systemd_set_services_enabled(services.iter().copied(), enabled).await?;
 
     let start = match start {
         Some(start) => start,
         None => confirm(theme, true, "Restart systemd services?")?,
     };
     if start {
-        for service in services {
-            systemd_restart_service(service).await?;
+        for &service in services {
+            system::systemd_restart_service(service).await?;
         }
     }
 
@@ -167,7 +174,7 @@ Type=simple
 Restart=always
 RestartSec=1
 User={user}
-LimitNOFILE=2048000
+{limits}
 ExecStart={node_binary} --configs {configs_dir}
 
 [Install]
@@ -187,6 +194,7 @@ StartLimitIntervalSec=0
 Type=simple
 Restart=always
 RestartSec=1
+TimeoutStopSec=300
 User={user}
 ExecStart={nodekeeper_binary} --root {root_dir} validator run
 
@@ -227,9 +235,15 @@ impl ProjectDirs {
         let node_configs_dir = std::fs::canonicalize(&self.node_configs_dir)
             .context("failed to canonicalize node configs path")?;
 
+        // Resource limits depend on the node role
+        let role = AppConfig::load_file(&self.app_config)
+            .map(|config| config.node_role())
+            .unwrap_or_default();
+
         let validator_service = format!(
             validator_service!(),
             user = user,
+            limits = role.systemd_limits(),
             node_binary = node.display(),
             configs_dir = node_configs_dir.display()
         );
@@ -271,17 +285,6 @@ impl ProjectDirs {
     }
 }
 
-async fn systemd_restart_service(service: &str) -> Result<()> {
-    exec(
-        Command::new("systemctl")
-            .stdout(Stdio::piped())
-            .arg("restart")
-            .arg(service),
-    )
-    .await
-    .with_context(|| format!("failed to restart service {service}"))
-}
-
 async fn systemd_set_services_enabled<'a, I: IntoIterator<Item = &'a str>>(
     services: I,
     enabled: bool,
@@ -0,0 +1,124 @@
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use argh::FromArgs;
+
+use super::CliContext;
+use crate::dirs::{VALIDATOR_EXPORTER_SERVICE, VALIDATOR_MANAGER_SERVICE};
+use crate::logging::log_file_path;
+
+#[derive(FromArgs)]
+/// Prints logs of the validation manager or exporter
+#[argh(subcommand, name = "logs")]
+pub struct Cmd {
+    /// component name (`validator-manager` or `validator-exporter`)
+    #[argh(positional, default = "VALIDATOR_MANAGER_SERVICE.to_owned()")]
+    name: String,
+
+    /// number of last lines to print. 50 by default
+    #[argh(option, short = 'n', default = "50")]
+    lines: usize,
+
+    /// wait for new lines
+    #[argh(switch, short = 'f')]
+    follow: bool,
+}
+
+impl Cmd {
+    pub async fn run(self, ctx: CliContext) -> Result<()> {
+        anyhow::ensure!(
+            self.name == VALIDATOR_MANAGER_SERVICE || self.name == VALIDATOR_EXPORTER_SERVICE,
+            "unknown component: {}",
+            self.name
+        );
+
+        let logs_dir = match ctx.load_config()?.logging {
+            Some(logging) => logging.logs_dir(ctx.dirs()),
+            None => anyhow::bail!("logging to files is not configured"),
+        };
+        let path = log_file_path(&logs_dir, &self.name);
+
+        let mut file = std::fs::File::open(&path).context("failed to open log file")?;
+        let mut offset = tail_offset(&mut file, self.lines)?;
+
+        let mut stdout = std::io::stdout();
+        offset += copy_from(&mut file, offset, &mut stdout)?;
+        if !self.follow {
+            return Ok(());
+        }
+
+        let interval = Duration::from_millis(500);
+        let cancellation = ctx.cancellation();
+        loop {
+            tokio::select! {
+                _ = tokio::time::sleep(interval) => {}
+                _ = cancellation.cancelled() => return Ok(()),
+            }
+
+            // Reopen the file after rotation
+            let len = match std::fs::metadata(&path) {
+                Ok(metadata) => metadata.len(),
+                Err(_) => continue,
+            };
+            if len < offset {
+                file = std::fs::File::open(&path).context("failed to reopen log file")?;
+                offset = 0;
+            }
+
+            offset += copy_from(&mut file, offset, &mut stdout)?;
+        }
+    }
+}
+
+/// Reads the last `lines` lines of the log file
+pub fn read_last_lines(path: &std::path::Path, lines: usize) -> Result<Vec<String>> {
+    let mut file = std::fs::File::open(path).context("failed to open log file")?;
+    let offset = tail_offset(&mut file, lines)?;
+
+    let mut data = Vec::new();
+    copy_from(&mut file, offset, &mut data)?;
+    Ok(String::from_utf8_lossy(&data)
+        .lines()
+        .map(ToOwned::to_owned)
+        .collect())
+}
+
+/// Finds the offset of the last `lines` lines in the file
+fn tail_offset(file: &mut std::fs::File, lines: usize) -> Result<u64> {
+    const CHUNK_SIZE: u64 = 8192;
+
+    let len = file.metadata()?.len();
+    if lines == 0 {
+        return Ok(len);
+    }
+
+    let mut end = len;
+    let mut newlines = 0;
+    let mut chunk = vec![0; CHUNK_SIZE as usize];
+    while end > 0 {
+        let start = end.saturating_sub(CHUNK_SIZE);
+        let chunk = &mut chunk[..(end - start) as usize];
+        file.seek(SeekFrom::Start(start))?;
+        file.read_exact(chunk)?;
+
+        for (i, byte) in chunk.iter().enumerate().rev() {
+            // NOTE: skip the trailing newline of the last line
+            if *byte == b'\n' && start + i as u64 != len - 1 {
+                newlines += 1;
+                if newlines == lines {
+                    return Ok(start + i as u64 + 1);
+                }
+            }
+        }
+        end = start;
+    }
+    Ok(0)
+}
+
+fn copy_from<W: Write>(file: &mut std::fs::File, offset: u64, output: &mut W) -> Result<u64> {
+    file.seek(SeekFrom::Start(offset))?;
+    let copied = std::io::copy(file, output)?;
+    output.flush()?;
+    Ok(copied)
+}
@@ -0,0 +1,65 @@
+use anyhow::Result;
+use argh::FromArgs;
+
+use super::CliContext;
+use crate::config::AppConfig;
+use crate::util::*;
+use crate::validator::ManagerClient;
+
+#[derive(FromArgs)]
+/// Toggles maintenance mode (elections are skipped, stakes are still recovered)
+#[argh(subcommand, name = "maintenance")]
+pub struct Cmd {
+    #[argh(subcommand)]
+    subcommand: SubCmd,
+}
+
+impl Cmd {
+    pub async fn run(self, ctx: CliContext) -> Result<()> {
+        let dirs = ctx.dirs();
+
+        let (enabled, reason) = match self.subcommand {
+            SubCmd::On(cmd) => (true, cmd.reason),
+            SubCmd::Off(_) => (false, None),
+        };
+
+        // Let the running manager apply the change
+        if let Some(mut client) = ManagerClient::connect(&dirs.manager_socket).await {
+            print_output(client.set_maintenance(enabled, reason).await?);
+            return Ok(());
+        }
+
+        // NOTE: env overrides must not be stored back
+        let mut config = AppConfig::load_file(&dirs.app_config)?;
+        let previous = config.maintenance.is_some();
+        config.set_maintenance(enabled, reason);
+        config.store(&dirs.app_config)?;
+
+        print_output(serde_json::json!({
+            "previous": previous,
+            "maintenance": config.maintenance,
+        }));
+        Ok(())
+    }
+}
+
+#[derive(FromArgs)]
+#[argh(subcommand)]
+enum SubCmd {
+    On(CmdOn),
+    Off(CmdOff),
+}
+
+#[derive(FromArgs)]
+/// Enables maintenance mode
+#[argh(subcommand, name = "on")]
+struct CmdOn {
+    /// description of the planned works
+    #[argh(option)]
+    reason: Option<String>,
+}
+
+#[derive(FromArgs)]
+/// Disables maintenance mode
+#[argh(subcommand, name = "off")]
+struct CmdOff {}
@@ -0,0 +1,337 @@
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::net::Ipv4Addr;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use argh::FromArgs;
+use serde::{Deserialize, Serialize};
+
+use super::CliContext;
+use crate::config::{AppConfig, AppConfigMaintenance, NodeConfig};
+use crate::dirs::ProjectDirs;
+use crate::network::{NodeTcpRpc, NodeUdpRpc};
+use crate::util::*;
+
+#[derive(FromArgs)]
+/// Moves the validator to a new host
+#[argh(subcommand, name = "migrate")]
+pub struct Cmd {
+    #[argh(subcommand)]
+    subcommand: SubCmd,
+}
+
+impl Cmd {
+    pub async fn run(self, ctx: CliContext) -> Result<()> {
+        let response = match self.subcommand {
+            SubCmd::Export(cmd) => cmd.run(ctx)?,
+            SubCmd::Import(cmd) => cmd.run(ctx).await?,
+            SubCmd::Activate(cmd) => cmd.run(ctx).await?,
+        };
+
+        print_output(response);
+        Ok(())
+    }
+}
+
+#[derive(FromArgs)]
+#[argh(subcommand)]
+enum SubCmd {
+    Export(CmdExport),
+    Import(CmdImport),
+    Activate(CmdActivate),
+}
+
+#[derive(FromArgs)]
+/// Stops bidding on this host and packages keys and configs
+#[argh(subcommand, name = "export")]
+struct CmdExport {
+    /// path to the migration bundle
+    #[argh(positional)]
+    output: PathBuf,
+
+    /// overwrite the existing bundle
+    #[argh(switch, short = 'f')]
+    force: bool,
+}
+
+impl CmdExport {
+    fn run(self, ctx: CliContext) -> Result<serde_json::Value> {
+        let dirs = ctx.dirs();
+        anyhow::ensure!(
+            self.force || !self.output.exists(),
+            "migration bundle already exists, use `--force` to overwrite it"
+        );
+
+        // Stop bidding before the keys leave this host
+        let mut app_config = AppConfig::load_file(&dirs.app_config)?;
+        if app_config.maintenance.is_none() {
+            app_config.maintenance = Some(AppConfigMaintenance {
+                since: broxus_util::now(),
+                reason: Some(MIGRATION_REASON.to_owned()),
+            });
+            app_config.store(&dirs.app_config)?;
+        }
+
+        let bundle = MigrationBundle::collect(dirs)?;
+        let data = serde_json::to_vec_pretty(&bundle)?;
+        write_private(&self.output, &data).context("failed to write migration bundle")?;
+
+        let steps = [
+            "Copy the bundle to the new host over a secure channel (it contains private keys)"
+                .to_owned(),
+            "Run `nodekeeper migrate import <bundle>` on the new host".to_owned(),
+            "Run `nodekeeper init node --step binary` and `nodekeeper init systemd` there"
+                .to_owned(),
+            format!(
+                "Stop the node and the manager on this host: `systemctl stop {} {}`",
+                crate::dirs::VALIDATOR_SERVICE,
+                crate::dirs::VALIDATOR_MANAGER_SERVICE
+            ),
+            "Start the node on the new host and wait until it is synced".to_owned(),
+            "Run `nodekeeper migrate activate` on the new host to resume bidding".to_owned(),
+        ];
+        print_checklist(&steps);
+
+        Ok(serde_json::json!({
+            "path": self.output,
+            "files": bundle.files.keys().collect::<Vec<_>>(),
+            "maintenance": true,
+            "next_steps": steps,
+        }))
+    }
+}
+
+#[derive(FromArgs)]
+/// Restores keys and configs from the migration bundle
+#[argh(subcommand, name = "import")]
+struct CmdImport {
+    /// path to the migration bundle
+    #[argh(positional)]
+    input: PathBuf,
+
+    /// public IP of this host (resolved automatically by default)
+    #[argh(option)]
+    public_ip: Option<Ipv4Addr>,
+
+    /// overwrite existing configs without confirmation
+    #[argh(switch, short = 'f')]
+    force: bool,
+}
+
+impl CmdImport {
+    async fn run(self, ctx: CliContext) -> Result<serde_json::Value> {
+        let dirs = ctx.dirs();
+
+        let data = std::fs::read(&self.input).context("failed to read migration bundle")?;
+        let bundle =
+            serde_json::from_slice::<MigrationBundle>(&data).context("invalid migration bundle")?;
+        anyhow::ensure!(
+            bundle.version == MigrationBundle::VERSION,
+            "unsupported migration bundle version"
+        );
+
+        if dirs.app_config.exists()
+            && is_terminal()
+            && !self.force
+            && !confirm(
+                &dialoguer::theme::ColorfulTheme::default(),
+                false,
+                "Existing configs will be overwritten. Continue?",
+            )?
+        {
+            return Err(Declined.into());
+        }
+
+        let public_ip = match self.public_ip {
+            Some(ip) => ip,
+            None => public_ip::addr_v4()
+                .await
+                .context("failed to resolve public ip")?,
+        };
+
+        bundle.restore(dirs)?;
+
+        // Update the address of the node
+        let mut node_config = NodeConfig::load(&dirs.node_config)?;
+        let mut adnl_node = node_config
+            .get_adnl_node()?
+            .context("node ADNL config not found")?;
+        adnl_node.ip_address.set_ip(public_ip);
+        node_config.set_adnl_node(&adnl_node)?;
+        node_config.store(&dirs.node_config)?;
+
+        // Keep bidding paused until the node is synced
+        let mut app_config = AppConfig::load_file(&dirs.app_config)?;
+        if let Some(adnl) = &mut app_config.adnl {
+            adnl.server_address = adnl_node.ip_address;
+        }
+        if app_config.maintenance.is_none() {
+            app_config.maintenance = Some(AppConfigMaintenance {
+                since: broxus_util::now(),
+                reason: Some(MIGRATION_REASON.to_owned()),
+            });
+        }
+        app_config.store(&dirs.app_config)?;
+
+        let steps = [
+            "Run `nodekeeper init node --step binary` and `nodekeeper init systemd`",
+            "Make sure the node and the manager are stopped on the old host",
+            "Start the node and wait until it is synced",
+            "Run `nodekeeper migrate activate` to resume bidding",
+        ];
+        print_checklist(&steps);
+
+        Ok(serde_json::json!({
+            "exported_at": bundle.created_at,
+            "files": bundle.files.keys().collect::<Vec<_>>(),
+            "address": adnl_node.ip_address,
+            "next_steps": steps,
+        }))
+    }
+}
+
+#[derive(FromArgs)]
+/// Verifies that the new node is synced and resumes bidding
+#[argh(subcommand, name = "activate")]
+struct CmdActivate {
+    /// max timediff (in seconds). 120 seconds default
+    #[argh(option, default = "120")]
+    max_time_diff: i32,
+
+    /// skip confirmation
+    #[argh(switch, short = 'f')]
+    force: bool,
+}
+
+impl CmdActivate {
+    async fn run(self, ctx: CliContext) -> Result<serde_json::Value> {
+        let dirs = ctx.dirs();
+        let config = ctx.load_config()?;
+
+        let node_tcp_rpc = NodeTcpRpc::new(config.control()?)
+            .await?
+            .with_cancellation(ctx.cancellation());
+        let stats = node_tcp_rpc
+            .get_stats()
+            .await?
+            .try_into_running()
+            .context("node is not synced")?;
+        anyhow::ensure!(
+            stats.mc_time_diff < self.max_time_diff && stats.sc_time_diff < self.max_time_diff,
+            "node is not synced yet (mc_time_diff: {}, sc_time_diff: {})",
+            stats.mc_time_diff,
+            stats.sc_time_diff
+        );
+
+        // DHT record is updated by the node itself
+        let adnl = config.adnl()?;
+        let node_udp_rpc = NodeUdpRpc::new(adnl, dirs)
+            .await?
+            .with_cancellation(ctx.cancellation());
+        let announced = node_udp_rpc.find_address(node_udp_rpc.peer_id()).await.ok();
+        if announced != Some(adnl.server_address) {
+            print_warning("DHT doesn't point to this host yet");
+        }
+
+        if is_terminal()
+            && !self.force
+            && !confirm(
+                &dialoguer::theme::ColorfulTheme::default(),
+                false,
+                "Is the validator stopped on the old host?",
+            )?
+        {
+            return Err(Declined.into());
+        }
+
+        let mut app_config = AppConfig::load_file(&dirs.app_config)?;
+        let previous = app_config.maintenance.take();
+        app_config.store(&dirs.app_config)?;
+
+        Ok(serde_json::json!({
+            "mc_time_diff": stats.mc_time_diff,
+            "sc_time_diff": stats.sc_time_diff,
+            "announced_address": announced,
+            "maintenance_since": previous.map(|m| m.since),
+        }))
+    }
+}
+
+const MIGRATION_REASON: &str = "host migration";
+
+/// Keys and configs of the validator
+#[derive(Serialize, Deserialize)]
+struct MigrationBundle {
+    version: u32,
+    created_at: u32,
+    /// Base64 encoded files by the path relative to the root dir
+    files: BTreeMap<String, String>,
+}
+
+impl MigrationBundle {
+    const VERSION: u32 = 1;
+
+    fn collect(dirs: &ProjectDirs) -> Result<Self> {
+        let mut files = BTreeMap::new();
+        for path in dirs.state_files() {
+            if !path.exists() {
+                continue;
+            }
+
+            let name = path
+                .strip_prefix(&dirs.root)
+                .with_context(|| format!("{} is outside of the root dir", path.display()))?;
+            let data = std::fs::read(path)
+                .with_context(|| format!("failed to read {}", path.display()))?;
+            files.insert(name.display().to_string(), base64::encode(data));
+        }
+
+        Ok(Self {
+            version: Self::VERSION,
+            created_at: broxus_util::now(),
+            files,
+        })
+    }
+
+    fn restore(&self, dirs: &ProjectDirs) -> Result<()> {
+        let known = dirs.state_files();
+        for (name, data) in &self.files {
+            let path = dirs.root.join(name);
+            anyhow::ensure!(known.contains(&&path), "unexpected file: {name}");
+
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)
+                    .with_context(|| format!("failed to create {}", parent.display()))?;
+            }
+
+            let data = base64::decode(data).with_context(|| format!("invalid file: {name}"))?;
+            write_private(&path, &data)
+                .with_context(|| format!("failed to write {}", path.display()))?;
+        }
+        Ok(())
+    }
+}
+
+fn write_private(path: &Path, data: &[u8]) -> std::io::Result<()> {
+    let mut file = std::fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(path)?;
+    file.write_all(data)
+}
+
+fn print_checklist<T: AsRef<str>>(steps: &[T]) {
+    if !is_terminal() {
+        return;
+    }
+
+    eprintln!("Next steps:");
+    for (i, step) in steps.iter().enumerate() {
+        eprintln!("  {}. {}", i + 1, step.as_ref());
+    }
+    eprintln!();
+}
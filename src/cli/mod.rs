@@ -1,18 +1,48 @@
 use std::path::PathBuf;
+use std::time::Duration;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use argh::FromArgs;
+use dialoguer::console::style;
+use tokio_util::sync::CancellationToken;
 
 use crate::config::*;
+use crate::contracts::wallet::SpendingPolicy;
+use crate::contracts::{InternalMessage, Wallet};
 use crate::dirs::*;
+use crate::network::{Subscription, ValidatorSetEntry};
 use crate::util::*;
 
+pub use self::error::report_error;
+use self::error::{ConfigError, ErrorCode};
+
+pub mod addresses;
+pub mod approve;
+pub mod backup;
+pub mod config;
 pub mod contract;
+pub mod dashboard;
+pub mod db;
+pub mod elections;
+mod error;
 pub mod exporter;
+pub mod fleet;
+pub mod governance;
 pub mod init;
+pub mod logs;
+pub mod maintenance;
+pub mod migrate;
+pub mod net;
+pub mod networks;
 pub mod node;
+pub mod ping;
+pub mod rpc;
 pub mod seed;
+#[cfg(not(feature = "packaged"))]
+pub mod self_update;
+pub mod upgrade;
 pub mod validator;
+pub mod version;
 
 /// All-in-one node management tool
 #[derive(FromArgs)]
@@ -23,23 +53,90 @@ pub struct App {
     /// path to the root directory
     #[argh(option, default = "ProjectDirs::default_root_dir()")]
     root: PathBuf,
+
+    /// accept all confirmations
+    #[argh(switch, short = 'y')]
+    yes: bool,
+
+    /// never prompt, use default answers for confirmations
+    #[argh(switch)]
+    no_input: bool,
+
+    /// output format: `text` (default) or `json`
+    #[argh(option, default = "OutputFormat::Text")]
+    output: OutputFormat,
 }
 
 impl App {
     pub async fn run(self) -> Result<()> {
-        tracing::debug!("root dir {:?}", self.root);
+        set_json_output(self.output == OutputFormat::Json);
+
+        anyhow::ensure!(
+            !(self.yes && self.no_input),
+            "`--yes` and `--no-input` can't be used together"
+        );
+        if self.yes {
+            set_input_mode(InputMode::AssumeYes);
+        } else if self.no_input {
+            set_input_mode(InputMode::NoInput);
+        }
 
         let ctx = CliContext {
             dirs: ProjectDirs::new(self.root),
+            cancellation: Default::default(),
         };
 
+        // Only long-running commands write logs to files
+        let log_name = match &self.command {
+            Command::Validator(cmd) if cmd.is_manager() => Some(VALIDATOR_MANAGER_SERVICE),
+            Command::Exporter(_) => Some(VALIDATOR_EXPORTER_SERVICE),
+            _ => None,
+        };
+        crate::logging::init(&ctx.dirs, log_name)?;
+
+        // NOTE: long-running commands handle termination signals themselves
+        if log_name.is_none() {
+            spawn_interrupt_handler(ctx.cancellation.clone());
+        }
+
+        tracing::debug!("root dir {:?}", ctx.dirs.root);
+
+        if let Ok(Some(index)) = NetworksIndex::load_cached(&ctx.dirs.networks_index) {
+            crate::defaults::set_network_presets(index.networks);
+        }
+
+        if let Ok(config) = ctx.load_config() {
+            set_token_decimals(config.decimals());
+            set_token_currency(config.currency());
+        }
+
         match self.command {
             Command::Init(cmd) => invoke_as_cli(cmd.run(ctx)).await,
             Command::Validator(cmd) => cmd.run(ctx).await,
             Command::Contract(cmd) => invoke_as_cli(cmd.run(ctx)).await,
+            Command::Config(cmd) => cmd.run(ctx).await,
+            Command::Elections(cmd) => cmd.run(ctx).await,
             Command::Exporter(cmd) => cmd.run(ctx).await,
+            Command::Fleet(cmd) => cmd.run(ctx).await,
+            Command::Governance(cmd) => cmd.run(ctx).await,
+            Command::Addresses(cmd) => cmd.run(ctx).await,
+            Command::Approve(cmd) => cmd.run(),
+            Command::Backup(cmd) => cmd.run(ctx).await,
             Command::Node(cmd) => cmd.run(ctx).await,
+            Command::Logs(cmd) => cmd.run(ctx).await,
+            Command::Maintenance(cmd) => cmd.run(ctx).await,
+            Command::Migrate(cmd) => cmd.run(ctx).await,
+            Command::Dashboard(cmd) => invoke_as_cli(cmd.run(ctx)).await,
+            Command::Db(cmd) => cmd.run(ctx).await,
+            Command::Ping(cmd) => cmd.run(ctx).await,
+            Command::Rpc(cmd) => cmd.run(ctx).await,
+            Command::Net(cmd) => cmd.run(ctx).await,
+            Command::Networks(cmd) => cmd.run(ctx).await,
             Command::Seed(cmd) => cmd.run(),
+            #[cfg(not(feature = "packaged"))]
+            Command::SelfUpdate(cmd) => cmd.run(ctx).await,
+            Command::Version(cmd) => cmd.run(ctx).await,
+            Command::Upgrade(cmd) => cmd.run(ctx).await,
         }
     }
 }
@@ -50,21 +147,297 @@ enum Command {
     Init(init::Cmd),
     Validator(validator::Cmd),
     Contract(contract::Cmd),
+    Config(config::Cmd),
+    Elections(elections::Cmd),
     Exporter(exporter::Cmd),
+    Fleet(fleet::Cmd),
+    Governance(governance::Cmd),
+    Addresses(addresses::Cmd),
+    Approve(approve::Cmd),
+    Backup(backup::Cmd),
     Node(node::Cmd),
+    Logs(logs::Cmd),
+    Maintenance(maintenance::Cmd),
+    Migrate(migrate::Cmd),
+    Dashboard(dashboard::Cmd),
+    Db(db::Cmd),
+    Ping(ping::Cmd),
+    Rpc(rpc::Cmd),
+    Net(net::Cmd),
+    Networks(networks::Cmd),
     Seed(seed::Cmd),
+    #[cfg(not(feature = "packaged"))]
+    SelfUpdate(self_update::Cmd),
+    Version(version::Cmd),
+    Upgrade(upgrade::Cmd),
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum OutputFormat {
+    Text,
+    Json,
+}
+
+impl std::str::FromStr for OutputFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            _ => Err(anyhow::anyhow!(
+                "unknown output format (neither `text` nor `json`)"
+            )),
+        }
+    }
 }
 
 pub struct CliContext {
     dirs: ProjectDirs,
+    cancellation: CancellationToken,
 }
 
 impl CliContext {
     pub fn load_config(&self) -> Result<AppConfig> {
-        AppConfig::load(&self.dirs.app_config)
+        AppConfig::load(&self.dirs.app_config).context(ConfigError)
     }
 
     pub fn dirs(&self) -> &ProjectDirs {
         &self.dirs
     }
+
+    /// Token which is cancelled on Ctrl-C to abort in-flight node queries
+    pub fn cancellation(&self) -> CancellationToken {
+        self.cancellation.clone()
+    }
+
+    pub fn load_address_book(&self) -> Result<AddressBook> {
+        AddressBook::load(&self.dirs.address_book)
+    }
+
+    /// Parses a raw address or a name from the address book
+    pub fn resolve_address(&self, input: &str) -> Result<ton_block::MsgAddressInt> {
+        self.load_address_book()?.resolve(input)
+    }
+
+    pub fn load_spending_policy(&self, config: &AppConfig) -> Result<Option<SpendingPolicy>> {
+        SpendingPolicy::from_config(
+            config,
+            &self.load_address_book()?,
+            &self.dirs.spending_ledger,
+        )
+    }
+}
+
+/// Labels for addresses in the CLI output (address book names and known contracts)
+pub struct AddressLabels {
+    book: AddressBook,
+    known: Vec<(ton_block::MsgAddressInt, String)>,
+}
+
+impl AddressLabels {
+    pub fn new(book: AddressBook) -> Self {
+        Self {
+            book,
+            known: Vec::new(),
+        }
+    }
+
+    pub fn add(&mut self, address: &ton_block::MsgAddressInt, label: impl Into<String>) {
+        self.known.push((address.clone(), label.into()));
+    }
+
+    pub fn add_system(&mut self, addresses: &SystemAddresses) {
+        for (address, label) in [
+            (&addresses.elector, "elector"),
+            (&addresses.config, "config"),
+            (&addresses.minter, "minter"),
+        ] {
+            let address = ton_block::MsgAddressInt::AddrStd(ton_block::MsgAddrStd {
+                anycast: None,
+                workchain_id: -1,
+                address: (*address).into(),
+            });
+            self.add(&address, label);
+        }
+    }
+
+    /// Returns the address book name or the known contract name
+    pub fn get(&self, address: &ton_block::MsgAddressInt) -> Option<&str> {
+        self.book.label(address).or_else(|| {
+            self.known
+                .iter()
+                .find(|(known, _)| known == address)
+                .map(|(_, label)| label.as_str())
+        })
+    }
+
+    /// Address with its label, e.g. `0:abcd... (alice)`
+    pub fn display(&self, address: &ton_block::MsgAddressInt) -> String {
+        match self.get(address) {
+            Some(label) => format!("{address} ({label})"),
+            None => address.to_string(),
+        }
+    }
+}
+
+/// Executes the wallet transfer locally and prints the estimated fees.
+///
+/// Fails if any of the transactions is expected to fail.
+async fn estimate_transfer(wallet: &Wallet, message: &InternalMessage) -> Result<()> {
+    let estimate = match wallet.estimate_transfer(message.clone()).await {
+        Ok(estimate) => estimate,
+        Err(e) => {
+            print_error(format!("failed to estimate fees: {e:?}"));
+            return Ok(());
+        }
+    };
+
+    if is_terminal() {
+        eprintln!(
+            "{}\n{}\n",
+            style("Estimated fees:").green().bold(),
+            style(format!("{:#}", Tokens(estimate.total_fees()))).bold(),
+        );
+    }
+
+    estimate.ensure_success()
+}
+
+/// Checks the transfer against the spending policy.
+///
+/// Destinations outside the allowlist always require an explicit confirmation
+/// (even with `--force`).
+async fn check_spending_policy(wallet: &Wallet, message: &InternalMessage) -> Result<()> {
+    let Some(policy) = wallet.spending_policy() else {
+        return Ok(());
+    };
+
+    if policy.requires_confirmation(&message.dst) {
+        anyhow::ensure!(
+            is_terminal() && input_mode() == InputMode::Interactive,
+            "transfer to {} requires confirmation (not in the spending policy allowlist)",
+            message.dst
+        );
+        print_warning("destination is not in the spending policy allowlist");
+        if !confirm(
+            &dialoguer::theme::ColorfulTheme::default(),
+            false,
+            "Do you really want to send tokens to this address?",
+        )? {
+            return Err(Declined.into());
+        }
+        policy.confirm_destination(&message.dst);
+    }
+
+    if policy.requires_transfer_approval(&message.dst, message.amount) {
+        request_approval(
+            policy,
+            ApprovalOperation::Transfer {
+                wallet: wallet.address().clone(),
+                destination: message.dst.clone(),
+                amount: message.amount,
+            },
+        )?;
+    }
+
+    wallet.check_spending_policy(message).await.map(|_| ())
+}
+
+/// Asks for the second approval of the key rotation if it is required by the policy
+fn check_key_rotation_policy(ctx: &CliContext, config: &AppConfig, keys: &str) -> Result<()> {
+    match ctx.load_spending_policy(config)? {
+        Some(policy) if policy.requires_key_rotation_approval() => request_approval(
+            &policy,
+            ApprovalOperation::KeyRotation {
+                keys: keys.to_owned(),
+            },
+        ),
+        _ => Ok(()),
+    }
+}
+
+/// Waits for the approval signature from the second key holder
+fn request_approval(policy: &SpendingPolicy, operation: ApprovalOperation) -> Result<()> {
+    anyhow::ensure!(
+        is_terminal() && input_mode() == InputMode::Interactive,
+        "operation requires a second approval"
+    );
+
+    let token = ApprovalRequest::new(operation).encode();
+    eprintln!(
+        "{}\n{}\n{}\n",
+        style("This operation requires a second approval.")
+            .yellow()
+            .bold(),
+        style("Ask an approver to run:").dim(),
+        style(format!("nodekeeper approve --keys <path> {token}")).bold(),
+    );
+
+    let signature: String =
+        dialoguer::Input::with_theme(&dialoguer::theme::ColorfulTheme::default())
+            .with_prompt("Approval signature")
+            .interact_text()?;
+    let signature = parse_hex_or_base64(signature.trim())
+        .ok()
+        .and_then(|signature| <[u8; 64]>::try_from(signature).ok())
+        .context("invalid approval signature format")?;
+
+    policy.approve(&token, &signature)
+}
+
+/// Cancels in-flight node queries on the first termination signal.
+///
+/// The process is terminated if the command doesn't finish shortly after.
+fn spawn_interrupt_handler(cancellation: CancellationToken) {
+    const GRACE_PERIOD: Duration = Duration::from_secs(2);
+
+    let signal_rx = broxus_util::any_signal(broxus_util::TERMINATION_SIGNALS);
+    tokio::spawn(async move {
+        let Ok(signal) = signal_rx.await else {
+            return;
+        };
+        tracing::debug!(?signal, "received termination signal");
+
+        cancellation.cancel();
+        tokio::time::sleep(GRACE_PERIOD).await;
+        std::process::exit(ErrorCode::Interrupted.exit_code());
+    });
+}
+
+/// Finds the node in the current validator set.
+///
+/// Returns the validator index and its public key
+/// (always `None` without the local node).
+async fn find_validator_entry(
+    subscription: &Subscription,
+    vset: &ton_block::ValidatorSet,
+) -> Result<Option<(u16, [u8; 32])>> {
+    if subscription.is_remote() {
+        return Ok(None);
+    }
+
+    let stats = subscription
+        .tcp_rpc()?
+        .get_stats()
+        .await?
+        .try_into_running()?;
+
+    Ok(match stats.in_current_vset {
+        ValidatorSetEntry::Validator(adnl) => {
+            let adnl = ton_types::UInt256::from(adnl);
+            vset.list()
+                .iter()
+                .enumerate()
+                .find(|(_, descr)| descr.adnl_addr.as_ref() == Some(&adnl))
+                .map(|(idx, descr)| (idx as u16, *descr.public_key.as_slice()))
+        }
+        ValidatorSetEntry::None => None,
+    })
+}
+
+fn parse_hash(hash: &str) -> Result<ton_types::UInt256> {
+    let hash = hex::decode(hash.trim_start_matches("0x"))?;
+    anyhow::ensure!(hash.len() == 32, "hash must be 32 bytes long");
+    Ok(ton_types::UInt256::from_slice(&hash))
 }
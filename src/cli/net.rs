@@ -0,0 +1,268 @@
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use argh::FromArgs;
+use everscale_network::adnl;
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+
+use super::ping::PingStats;
+use super::CliContext;
+use crate::config::AppConfigAdnl;
+use crate::network::{NodeTcpRpc, NodeUdpRpc};
+use crate::util::*;
+
+#[derive(FromArgs)]
+/// Network diagnostics
+#[argh(subcommand, name = "net")]
+pub struct Cmd {
+    #[argh(subcommand)]
+    subcommand: SubCmd,
+}
+
+impl Cmd {
+    pub async fn run(self, ctx: CliContext) -> Result<()> {
+        let response = match self.subcommand {
+            SubCmd::Peers(cmd) => cmd.run(ctx).await?,
+            SubCmd::CheckExternal(cmd) => cmd.run(ctx).await?,
+        };
+
+        print_output(response);
+        Ok(())
+    }
+}
+
+#[derive(FromArgs)]
+#[argh(subcommand)]
+enum SubCmd {
+    Peers(CmdPeers),
+    CheckExternal(CmdCheckExternal),
+}
+
+#[derive(FromArgs)]
+/// Shows overlay neighbours of the node and their reachability
+#[argh(subcommand, name = "peers")]
+struct CmdPeers {
+    /// number of probes for each peer. 3 by default
+    #[argh(option, short = 'c', default = "3")]
+    count: u32,
+
+    /// probe timeout (in milliseconds). 1000 ms by default
+    #[argh(option, short = 't', default = "1000")]
+    timeout: u64,
+
+    /// max number of peers probed in parallel. 16 by default
+    #[argh(option, default = "16")]
+    concurrency: usize,
+}
+
+impl CmdPeers {
+    async fn run(self, ctx: CliContext) -> Result<serde_json::Value> {
+        const PROBE_INTERVAL: Duration = Duration::from_millis(200);
+
+        let config = ctx.load_config()?;
+        let count = std::cmp::max(self.count, 1);
+        let timeout = Duration::from_millis(self.timeout);
+
+        // Node state from the control server
+        let node = match NodeTcpRpc::new(config.control()?).await {
+            Ok(rpc_node) => match rpc_node
+                .with_cancellation(ctx.cancellation())
+                .get_stats()
+                .await
+            {
+                Ok(stats) => serde_json::to_value(stats)?,
+                Err(e) => serde_json::json!({ "error": format!("{e:?}") }),
+            },
+            Err(e) => serde_json::json!({ "error": format!("{e:?}") }),
+        };
+
+        let adnl_config = config.adnl()?;
+        let node_udp_rpc = NodeUdpRpc::new(adnl_config, ctx.dirs())
+            .await
+            .context("failed to build node UDP client")?
+            .with_cancellation(ctx.cancellation());
+
+        // Check that the node address is announced in DHT
+        let dht = match node_udp_rpc.find_address(node_udp_rpc.peer_id()).await {
+            Ok(addr) => serde_json::json!({
+                "address": addr,
+                "matches_config": addr == adnl_config.server_address,
+            }),
+            Err(e) => serde_json::json!({ "error": format!("{e:?}") }),
+        };
+
+        // Probe overlay neighbours
+        let neighbours = node_udp_rpc
+            .get_overlay_neighbours()
+            .await
+            .context("failed to get overlay neighbours")?;
+
+        let mut peers = futures_util::stream::iter(neighbours)
+            .map(|pubkey| {
+                let node_udp_rpc = &node_udp_rpc;
+                async move {
+                    let adnl_id = adnl::NodeIdFull::new(pubkey).compute_short_id();
+                    let probe = async {
+                        let addr = node_udp_rpc.find_address(&adnl_id).await?;
+                        let peer_id = node_udp_rpc.add_peer(pubkey, addr)?;
+                        let stats = PingStats::collect(count, PROBE_INTERVAL, || {
+                            node_udp_rpc.ping_peer(&peer_id, timeout)
+                        })
+                        .await;
+                        Ok::<_, anyhow::Error>((addr, stats))
+                    };
+
+                    match probe.await {
+                        Ok((addr, stats)) => serde_json::json!({
+                            "adnl_id": adnl_id.to_string(),
+                            "address": addr,
+                            "stats": stats,
+                        }),
+                        Err(e) => serde_json::json!({
+                            "adnl_id": adnl_id.to_string(),
+                            "error": format!("{e:?}"),
+                        }),
+                    }
+                }
+            })
+            .buffer_unordered(std::cmp::max(self.concurrency, 1))
+            .collect::<Vec<_>>()
+            .await;
+        peers.sort_unstable_by_key(|peer| peer["adnl_id"].as_str().map(str::to_owned));
+
+        let reachable = peers
+            .iter()
+            .filter(|peer| peer["stats"]["received"].as_u64().unwrap_or_default() > 0)
+            .count();
+
+        Ok(serde_json::json!({
+            "node": node,
+            "dht": dht,
+            "total_peers": peers.len(),
+            "reachable_peers": reachable,
+            "peers": peers,
+        }))
+    }
+}
+
+#[derive(FromArgs)]
+/// Checks that the node is reachable from outside on its advertised address
+#[argh(subcommand, name = "check-external")]
+struct CmdCheckExternal {
+    /// probe request timeout (in seconds). 10 seconds default
+    #[argh(option, short = 't', default = "10")]
+    timeout: u64,
+}
+
+impl CmdCheckExternal {
+    async fn run(self, ctx: CliContext) -> Result<serde_json::Value> {
+        let config = ctx.load_config()?;
+        let adnl_config = config.adnl()?;
+        let timeout = Duration::from_secs(self.timeout);
+
+        let mut output = serde_json::json!({
+            "address": adnl_config.server_address,
+            "pubkey": hex::encode(adnl_config.server_pubkey.as_bytes()),
+        });
+
+        let reachable = if config.external_probes.is_empty() {
+            // Fallback to the advertised DHT record and a direct probe of the public address
+            let node_udp_rpc = NodeUdpRpc::new(adnl_config, ctx.dirs())
+                .await
+                .context("failed to build node UDP client")?
+                .with_cancellation(ctx.cancellation());
+
+            let (dht, announced) = match node_udp_rpc.find_address(node_udp_rpc.peer_id()).await {
+                Ok(addr) => {
+                    let matches = addr == adnl_config.server_address;
+                    (
+                        serde_json::json!({ "address": addr, "matches_config": matches }),
+                        matches,
+                    )
+                }
+                Err(e) => (serde_json::json!({ "error": format!("{e:?}") }), false),
+            };
+
+            let direct =
+                PingStats::collect(3, Duration::from_millis(200), || node_udp_rpc.ping(timeout))
+                    .await;
+            let responded = direct.received > 0;
+
+            output["mode"] = "dht".into();
+            output["dht"] = dht;
+            output["direct"] = serde_json::to_value(direct)?;
+            announced && responded
+        } else {
+            let client = reqwest::Client::builder().timeout(timeout).build()?;
+
+            let probes = futures_util::future::join_all(
+                config
+                    .external_probes
+                    .iter()
+                    .map(|url| ExternalProbe::check(&client, url, adnl_config)),
+            )
+            .await;
+            let reachable = probes.iter().any(|probe| probe.reachable);
+
+            output["mode"] = "probes".into();
+            output["probes"] = serde_json::to_value(probes)?;
+            reachable
+        };
+
+        if !reachable {
+            print_warning("node is not reachable on its advertised address");
+        }
+        output["reachable"] = reachable.into();
+        Ok(output)
+    }
+}
+
+/// Result of the remote reachability check.
+///
+/// Probe receives `GET <url>?address=<ip:port>&pubkey=<hex>&zerostate_file_hash=<hex>`
+/// and responds with `{ "reachable": bool, "rtt_ms": number?, "error": string? }`.
+#[derive(Serialize, Deserialize)]
+struct ExternalProbe {
+    #[serde(default)]
+    probe: String,
+    reachable: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rtt_ms: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl ExternalProbe {
+    async fn check(client: &reqwest::Client, url: &reqwest::Url, adnl: &AppConfigAdnl) -> Self {
+        // NOTE: probe url can contain credentials, so only its host is shown
+        let probe = url.host_str().unwrap_or_default().to_owned();
+
+        let res = async {
+            let text = client
+                .get(url.clone())
+                .query(&[
+                    ("address", adnl.server_address.to_string()),
+                    ("pubkey", hex::encode(adnl.server_pubkey.as_bytes())),
+                    ("zerostate_file_hash", hex::encode(adnl.zerostate_file_hash)),
+                ])
+                .send()
+                .await?
+                .error_for_status()?
+                .text()
+                .await?;
+            serde_json::from_str::<Self>(&text).context("invalid probe response")
+        }
+        .await;
+
+        match res {
+            Ok(response) => Self { probe, ..response },
+            Err(e) => Self {
+                probe,
+                reachable: false,
+                rtt_ms: None,
+                error: Some(format!("{e:?}")),
+            },
+        }
+    }
+}
@@ -0,0 +1,121 @@
+use anyhow::{Context, Result};
+use argh::FromArgs;
+
+use super::CliContext;
+use crate::config::NetworksIndex;
+use crate::util::*;
+
+#[derive(FromArgs)]
+/// Network presets management
+#[argh(subcommand, name = "networks")]
+pub struct Cmd {
+    #[argh(subcommand)]
+    subcommand: SubCmd,
+}
+
+impl Cmd {
+    pub async fn run(self, ctx: CliContext) -> Result<()> {
+        let response = match self.subcommand {
+            SubCmd::List(cmd) => cmd.run(ctx)?,
+            SubCmd::Update(cmd) => cmd.run(ctx).await?,
+        };
+
+        print_output(response);
+        Ok(())
+    }
+}
+
+#[derive(FromArgs)]
+#[argh(subcommand)]
+enum SubCmd {
+    List(CmdList),
+    Update(CmdUpdate),
+}
+
+#[derive(FromArgs)]
+/// Lists known network presets
+#[argh(subcommand, name = "list")]
+struct CmdList {}
+
+impl CmdList {
+    fn run(self, ctx: CliContext) -> Result<serde_json::Value> {
+        let index = NetworksIndex::load(&ctx.dirs().networks_index)?;
+        Ok(serde_json::to_value(index)?)
+    }
+}
+
+#[derive(FromArgs)]
+/// Downloads the latest signed network presets index
+#[argh(subcommand, name = "update")]
+struct CmdUpdate {
+    /// allow replacing the index with an older version
+    #[argh(switch)]
+    allow_downgrade: bool,
+}
+
+impl CmdUpdate {
+    async fn run(self, ctx: CliContext) -> Result<serde_json::Value> {
+        let params = ctx
+            .load_config()?
+            .networks_index
+            .context("`networks_index` section with the index public key is not configured")?;
+        let dirs = ctx.dirs();
+
+        let client = reqwest::Client::builder()
+            .user_agent(concat!("nodekeeper/", env!("CARGO_PKG_VERSION")))
+            .build()?;
+
+        let download = |url: reqwest::Url| {
+            let request = client.get(url).send();
+            async move {
+                let data = request.await?.error_for_status()?.bytes().await?;
+                Ok::<_, anyhow::Error>(data)
+            }
+        };
+
+        let data = download(params.url.clone())
+            .await
+            .context("failed to download networks index")?;
+
+        let signature_url = format!("{}.sig", params.url).parse()?;
+        let signature = download(signature_url)
+            .await
+            .context("failed to download networks index signature")?;
+        let signature = std::str::from_utf8(&signature)
+            .ok()
+            .and_then(|signature| parse_hex_or_base64(signature.trim()).ok())
+            .and_then(|signature| <[u8; 64]>::try_from(signature).ok())
+            .context("invalid networks index signature format")?;
+
+        let index = NetworksIndex::from_signed(&data, &signature, &params.public_key)?;
+
+        let previous = NetworksIndex::load_cached(&dirs.networks_index)?;
+        let previous_version = previous.as_ref().map(|index| index.version);
+        if let Some(previous_version) = previous_version {
+            anyhow::ensure!(
+                index.version >= previous_version || self.allow_downgrade,
+                "downloaded index version {} is older than the current one ({previous_version})",
+                index.version
+            );
+        }
+
+        let added = index
+            .networks
+            .iter()
+            .filter(|preset| match &previous {
+                Some(previous) => previous.find_by_name(&preset.name).is_none(),
+                None => true,
+            })
+            .map(|preset| preset.name.as_str())
+            .collect::<Vec<_>>();
+
+        index.store(&dirs.networks_index)?;
+
+        Ok(serde_json::json!({
+            "previous_version": previous_version,
+            "version": index.version,
+            "networks": index.networks.len(),
+            "added_networks": added,
+        }))
+    }
+}
@@ -8,7 +8,8 @@ use ton_block::Serializable;
 
 use super::CliContext;
 use crate::config::NodeConfig;
-use crate::network::{ConfigParamWithId, ConfigWithId, NodeTcpRpc};
+use crate::dirs::VALIDATOR_SERVICE;
+use crate::network::{ConfigParamWithId, ConfigWithId, NodeStats, NodeTcpRpc, ValidatorSetEntry};
 use crate::util::*;
 
 #[derive(FromArgs)]
@@ -135,6 +136,7 @@ impl Cmd {
                 rpc_node.send_message(&data).await?;
                 serde_json::json!({})
             }
+            SubCmd::Restart(cmd) => cmd.run(ctx).await?,
             SubCmd::GenDht(cmd) => {
                 use everscale_crypto::ed25519;
                 use everscale_network::proto;
@@ -220,6 +222,7 @@ enum SubCmd {
     GetConfigParam(CmdGetConfigParam),
     GetAccount(CmdGetAccount),
     SendMessage(CmdSendMessage),
+    Restart(CmdRestart),
     GenDht(CmdNodeGenDht),
 }
 
@@ -339,6 +342,75 @@ struct CmdSendMessage {
     data: Option<String>,
 }
 
+#[derive(FromArgs)]
+/// Restarts the node service
+#[argh(subcommand, name = "restart")]
+struct CmdRestart {
+    /// wait until our validator is not signing blocks in the current catchain session
+    #[argh(switch)]
+    safe: bool,
+}
+
+impl CmdRestart {
+    async fn run(self, ctx: CliContext) -> Result<serde_json::Value> {
+        let mut delay = 0;
+        if self.safe {
+            let rpc_node = ctx.create_rpc_node().await?;
+            if let NodeStats::Running(stats) = rpc_node.get_stats().await? {
+                let config = rpc_node.get_config_all().await?.config;
+                delay = safe_restart_delay(
+                    matches!(stats.in_current_vset, ValidatorSetEntry::Validator(_)),
+                    matches!(stats.in_next_vset, ValidatorSetEntry::Validator(_)),
+                    &config,
+                    broxus_util::now(),
+                )?;
+            }
+        }
+
+        if delay > 0 {
+            let delay = std::time::Duration::from_secs(delay as u64);
+            sleep_with_progress("Waiting for the node restart", delay).await;
+        }
+
+        system::systemd_restart_service(VALIDATOR_SERVICE).await?;
+        Ok(serde_json::json!({
+            "delay": delay,
+        }))
+    }
+}
+
+/// Computes a delay (in seconds) until the node can be restarted without missing our signatures.
+///
+/// The restart is postponed until the end of the validation round if we are not elected
+/// for the next one, or until the next catchain session otherwise.
+pub(super) fn safe_restart_delay(
+    in_current_vset: bool,
+    in_next_vset: bool,
+    config: &ton_block::ConfigParams,
+    now: u32,
+) -> Result<u32> {
+    if !in_current_vset {
+        return Ok(0);
+    }
+
+    let vset = config.validator_set()?;
+    let round_end = vset.utime_until();
+    if !in_next_vset {
+        return Ok(round_end.saturating_sub(now));
+    }
+
+    let catchain_config = config.catchain_config()?;
+    let session_lifetime = std::cmp::min(
+        catchain_config.mc_catchain_lifetime,
+        catchain_config.shard_catchain_lifetime,
+    )
+    .max(1);
+
+    let elapsed = now.saturating_sub(vset.utime_since());
+    let next_session = vset.utime_since() + (elapsed / session_lifetime + 1) * session_lifetime;
+    Ok(std::cmp::min(next_session, round_end).saturating_sub(now))
+}
+
 #[derive(FromArgs)]
 /// Generates signed DHT entry for this node
 #[argh(subcommand, name = "gendht")]
@@ -357,6 +429,7 @@ struct CmdNodeGenDht {
 impl CliContext {
     async fn create_rpc_node(self) -> Result<NodeTcpRpc> {
         let config = self.load_config()?;
-        NodeTcpRpc::new(config.control()?).await
+        let rpc = NodeTcpRpc::new(config.control()?).await?;
+        Ok(rpc.with_cancellation(self.cancellation()))
     }
 }
@@ -0,0 +1,139 @@
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use argh::FromArgs;
+use everscale_crypto::ed25519;
+use serde::Serialize;
+
+use super::CliContext;
+use crate::network::{NodeTcpRpc, NodeUdpRpc};
+use crate::util::*;
+
+#[derive(FromArgs)]
+/// Checks connectivity with the node over the control and ADNL channels
+#[argh(subcommand, name = "ping")]
+pub struct Cmd {
+    /// number of echo queries for each channel. 5 by default
+    #[argh(option, short = 'c', default = "5")]
+    count: u32,
+
+    /// interval between queries (in milliseconds). 500 ms by default
+    #[argh(option, short = 'i', default = "500")]
+    interval: u64,
+
+    /// query timeout (in milliseconds). 2000 ms by default
+    #[argh(option, short = 't', default = "2000")]
+    timeout: u64,
+}
+
+impl Cmd {
+    pub async fn run(self, ctx: CliContext) -> Result<()> {
+        let config = ctx.load_config()?;
+        let count = std::cmp::max(self.count, 1);
+        let interval = Duration::from_millis(self.interval);
+        let timeout = Duration::from_millis(self.timeout);
+
+        // Check control channel
+        let control = config.control()?;
+
+        let mut control_config = control.clone();
+        control_config.query_timeout = timeout;
+
+        let started_at = Instant::now();
+        let node_tcp_rpc = NodeTcpRpc::new(&control_config)
+            .await
+            .context("failed to connect to the control server")?
+            .with_cancellation(ctx.cancellation());
+        let handshake_ms = as_millis(started_at.elapsed());
+
+        let control_stats = PingStats::collect(count, interval, || node_tcp_rpc.ping()).await;
+
+        // Check ADNL channel
+        let adnl = config.adnl()?;
+
+        let node_udp_rpc = NodeUdpRpc::new(adnl, ctx.dirs())
+            .await
+            .context("failed to build node UDP client")?
+            .with_cancellation(ctx.cancellation());
+
+        let adnl_stats = PingStats::collect(count, interval, || node_udp_rpc.ping(timeout)).await;
+
+        let client_pubkey = ed25519::PublicKey::from(&*control.client_secret);
+        print_output(serde_json::json!({
+            "control": {
+                "server_address": control.server_address,
+                "server_pubkey": key_fingerprint(&control.server_pubkey),
+                "client_pubkey": key_fingerprint(&client_pubkey),
+                "handshake_ms": handshake_ms,
+                "stats": control_stats,
+            },
+            "adnl": {
+                "server_address": adnl.server_address,
+                "server_pubkey": key_fingerprint(&adnl.server_pubkey),
+                "server_adnl_id": node_udp_rpc.peer_id().to_string(),
+                "stats": adnl_stats,
+            },
+        }));
+        Ok(())
+    }
+}
+
+#[derive(Default, Serialize)]
+pub(super) struct PingStats {
+    sent: u32,
+    pub(super) received: u32,
+    loss_percent: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    min_rtt_ms: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    avg_rtt_ms: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_rtt_ms: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    last_error: Option<String>,
+}
+
+impl PingStats {
+    pub(super) async fn collect<F, Fut>(count: u32, interval: Duration, mut f: F) -> Self
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<Option<Duration>>>,
+    {
+        let mut stats = Self::default();
+        let mut rtts = Vec::with_capacity(count as usize);
+
+        for i in 0..count {
+            if i > 0 {
+                tokio::time::sleep(interval).await;
+            }
+
+            stats.sent += 1;
+            match f().await {
+                Ok(Some(rtt)) => {
+                    stats.received += 1;
+                    rtts.push(as_millis(rtt));
+                }
+                Ok(None) => {}
+                Err(e) => stats.last_error = Some(format!("{e:?}")),
+            }
+        }
+
+        stats.loss_percent = (stats.sent - stats.received) as f64 * 100.0 / stats.sent as f64;
+        if !rtts.is_empty() {
+            stats.min_rtt_ms = rtts.iter().copied().reduce(f64::min);
+            stats.max_rtt_ms = rtts.iter().copied().reduce(f64::max);
+            stats.avg_rtt_ms = Some(rtts.iter().sum::<f64>() / rtts.len() as f64);
+        }
+
+        stats
+    }
+}
+
+fn key_fingerprint(key: &ed25519::PublicKey) -> String {
+    hex::encode(key.as_bytes())
+}
+
+fn as_millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
@@ -0,0 +1,94 @@
+use std::fmt::Write;
+
+use anyhow::{Context, Result};
+use argh::FromArgs;
+
+use super::CliContext;
+use crate::network::NodeTcpRpc;
+use crate::util::*;
+
+#[derive(FromArgs)]
+/// Low-level control server queries (for debugging)
+#[argh(subcommand, name = "rpc")]
+pub struct Cmd {
+    #[argh(subcommand)]
+    subcommand: SubCmd,
+}
+
+impl Cmd {
+    pub async fn run(self, ctx: CliContext) -> Result<()> {
+        match self.subcommand {
+            SubCmd::Raw(cmd) => cmd.run(ctx).await,
+        }
+    }
+}
+
+#[derive(FromArgs)]
+#[argh(subcommand)]
+enum SubCmd {
+    Raw(CmdRaw),
+}
+
+#[derive(FromArgs)]
+/// Sends a serialized boxed TL query to the control server
+#[argh(subcommand, name = "raw")]
+struct CmdRaw {
+    /// hex encoded query (including the constructor id)
+    #[argh(option)]
+    hex: String,
+
+    /// max number of the answer bytes in the hexdump. 4096 by default
+    #[argh(option, default = "4096")]
+    limit: usize,
+}
+
+impl CmdRaw {
+    async fn run(self, ctx: CliContext) -> Result<()> {
+        let config = ctx.load_config()?;
+
+        let hex = self.hex.trim();
+        let hex = hex.strip_prefix("0x").unwrap_or(hex);
+        let query = hex::decode(hex).context("invalid query hex")?;
+
+        let node_rpc = NodeTcpRpc::new(config.control()?)
+            .await?
+            .with_cancellation(ctx.cancellation());
+        let answer = node_rpc.raw_query(&query).await?;
+
+        if is_json_output() {
+            print_output(serde_json::json!({
+                "len": answer.len(),
+                "answer": hex::encode(&answer),
+            }));
+        } else {
+            print_output(hexdump(&answer, self.limit));
+        }
+        Ok(())
+    }
+}
+
+/// Formats bytes as `offset | hex | ascii` lines
+fn hexdump(data: &[u8], limit: usize) -> String {
+    const ROW_LEN: usize = 16;
+
+    let mut result = String::new();
+    for (i, row) in data[..data.len().min(limit)].chunks(ROW_LEN).enumerate() {
+        let mut ascii = String::with_capacity(ROW_LEN);
+        write!(result, "{:08x} ", i * ROW_LEN).unwrap();
+        for byte in row {
+            write!(result, " {byte:02x}").unwrap();
+            ascii.push(match byte {
+                0x20..=0x7e => *byte as char,
+                _ => '.',
+            });
+        }
+        let padding = (ROW_LEN - row.len()) * 3;
+        writeln!(result, "{:padding$}  |{ascii}|", "").unwrap();
+    }
+
+    if data.len() > limit {
+        writeln!(result, "... {} more bytes", data.len() - limit).unwrap();
+    }
+    write!(result, "{} bytes", data.len()).unwrap();
+    result
+}
@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use anyhow::Result;
 use argh::FromArgs;
 
@@ -37,12 +39,28 @@ struct CmdGenerate {
     /// mnemonic type
     #[argh(option, long = "type", short = 't', default = "MnemonicType::Bip39")]
     ty: MnemonicType,
+
+    /// copy the seed to the clipboard instead of printing it
+    #[argh(switch)]
+    copy: bool,
+
+    /// clear the clipboard after this number of seconds. 30 seconds default
+    #[argh(option, default = "30")]
+    clear_after: u64,
 }
 
 impl CmdGenerate {
     fn run(self) -> Result<()> {
         let seed = crypto::generate_seed(self.ty);
-        print_output(seed);
+        if self.copy {
+            let clear_after = (self.clear_after > 0).then(|| Duration::from_secs(self.clear_after));
+            copy_to_clipboard(&seed, clear_after)?;
+            if is_terminal() {
+                eprintln!("Seed copied to the clipboard");
+            }
+        } else {
+            print_output(seed);
+        }
         Ok(())
     }
 }
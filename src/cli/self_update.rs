@@ -0,0 +1,242 @@
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use argh::FromArgs;
+use serde::Deserialize;
+use sha2::Digest;
+
+use super::CliContext;
+use crate::config::AppConfigUpdates;
+use crate::dirs::{VALIDATOR_EXPORTER_SERVICE, VALIDATOR_MANAGER_SERVICE};
+use crate::util::*;
+
+#[derive(FromArgs)]
+/// Updates nodekeeper to the latest release
+#[argh(subcommand, name = "self-update")]
+pub struct Cmd {
+    /// only check for a new release
+    #[argh(switch)]
+    check: bool,
+
+    /// reinstall even if the current version is the latest
+    #[argh(switch)]
+    force: bool,
+
+    /// allow installing a release which is older than the current version
+    #[argh(switch)]
+    allow_downgrade: bool,
+
+    /// do not restart nodekeeper systemd services
+    #[argh(switch)]
+    no_restart: bool,
+}
+
+impl Cmd {
+    pub async fn run(self, ctx: CliContext) -> Result<()> {
+        let params = ctx
+            .load_config()?
+            .updates
+            .context("`updates` section with the release public key is not configured")?;
+
+        let client = reqwest::Client::builder()
+            .user_agent(concat!("nodekeeper/", env!("CARGO_PKG_VERSION")))
+            .build()?;
+
+        let current_version = env!("CARGO_PKG_VERSION");
+        let release = Release::fetch(&client, &params).await?;
+        let latest_version = release.version();
+        let update_available = is_newer(latest_version, current_version);
+
+        if self.check || (!update_available && !self.force && !self.allow_downgrade) {
+            print_output(serde_json::json!({
+                "current_version": current_version,
+                "latest_version": latest_version,
+                "update_available": update_available,
+            }));
+            return Ok(());
+        }
+
+        // Download and verify the signed manifest.
+        // NOTE: the tag name is not signed, so only the manifest version is trusted
+        let manifest = release.download(&client, MANIFEST_ASSET).await?;
+        let signature = release
+            .download(&client, &format!("{MANIFEST_ASSET}.sig"))
+            .await?;
+        verify_signature(&params, &manifest, &signature)?;
+        let manifest = serde_json::from_slice::<ReleaseManifest>(&manifest)
+            .context("invalid release manifest")?;
+
+        anyhow::ensure!(
+            manifest.version == latest_version,
+            "release manifest is for version {}, but the release is {latest_version}",
+            manifest.version
+        );
+        if !is_newer(&manifest.version, current_version) {
+            let reinstall = manifest.version == current_version && self.force;
+            anyhow::ensure!(
+                reinstall || self.allow_downgrade,
+                "release {} is older than the current version {current_version} \
+                (use `--allow-downgrade` to install it anyway)",
+                manifest.version
+            );
+        }
+
+        // Download the binary for the current target and check its hash
+        let target = current_target();
+        let expected_hash = manifest
+            .targets
+            .get(&target)
+            .with_context(|| format!("release manifest has no `{target}` target"))?;
+        let binary = release.download(&client, &target).await?;
+        anyhow::ensure!(
+            hex::encode(sha2::Sha256::digest(&binary)) == expected_hash.to_lowercase(),
+            "release binary hash mismatch"
+        );
+
+        // Replace the current executable
+        let current_exe = std::env::current_exe()
+            .and_then(std::fs::canonicalize)
+            .context("failed to get current executable path")?;
+        replace_binary(&current_exe, &binary)?;
+
+        // Restart services which use the replaced binary
+        let mut restarted = Vec::new();
+        if !self.no_restart {
+            let dirs = ctx.dirs();
+            for (service, path) in [
+                (VALIDATOR_MANAGER_SERVICE, &dirs.validator_manager_service),
+                (VALIDATOR_EXPORTER_SERVICE, &dirs.validator_exporter_service),
+            ] {
+                if path.exists() {
+                    system::systemd_restart_service(service).await?;
+                    restarted.push(service);
+                }
+            }
+        }
+
+        print_output(serde_json::json!({
+            "previous_version": current_version,
+            "version": latest_version,
+            "path": current_exe,
+            "restarted_services": restarted,
+        }));
+        Ok(())
+    }
+}
+
+#[derive(Deserialize)]
+struct Release {
+    tag_name: String,
+    assets: Vec<ReleaseAsset>,
+}
+
+/// Signed description of the release binaries
+#[derive(Deserialize)]
+struct ReleaseManifest {
+    version: String,
+    /// Hex encoded SHA-256 of the binary for each target
+    targets: std::collections::HashMap<String, String>,
+}
+
+const MANIFEST_ASSET: &str = "manifest.json";
+
+#[derive(Deserialize)]
+struct ReleaseAsset {
+    name: String,
+    browser_download_url: reqwest::Url,
+}
+
+impl Release {
+    async fn fetch(client: &reqwest::Client, params: &AppConfigUpdates) -> Result<Self> {
+        let text = client
+            .get(params.feed.clone())
+            .header(reqwest::header::ACCEPT, "application/json")
+            .send()
+            .await
+            .context("failed to fetch the latest release")?
+            .error_for_status()?
+            .text()
+            .await?;
+        serde_json::from_str(&text).context("invalid release info")
+    }
+
+    fn version(&self) -> &str {
+        self.tag_name.trim_start_matches('v')
+    }
+
+    async fn download(&self, client: &reqwest::Client, name: &str) -> Result<Vec<u8>> {
+        let asset = self
+            .assets
+            .iter()
+            .find(|asset| asset.name == name)
+            .with_context(|| format!("release {} has no `{name}` asset", self.tag_name))?;
+
+        let mut response = client
+            .get(asset.browser_download_url.clone())
+            .send()
+            .await
+            .with_context(|| format!("failed to download `{name}`"))?
+            .error_for_status()?;
+
+        let mut progress =
+            Progress::download(format!("Downloading {name}"), response.content_length());
+        let mut data = Vec::new();
+        while let Some(chunk) = response.chunk().await? {
+            data.extend_from_slice(&chunk);
+            progress.inc(chunk.len() as u64);
+        }
+        progress.finish();
+
+        Ok(data)
+    }
+}
+
+/// Release asset name for the current platform (e.g. `nodekeeper-x86_64-linux`)
+fn current_target() -> String {
+    format!(
+        "nodekeeper-{}-{}",
+        std::env::consts::ARCH,
+        std::env::consts::OS
+    )
+}
+
+fn verify_signature(params: &AppConfigUpdates, data: &[u8], signature: &[u8]) -> Result<()> {
+    let signature = std::str::from_utf8(signature)
+        .ok()
+        .and_then(|signature| parse_hex_or_base64(signature.trim()).ok())
+        .and_then(|signature| <[u8; 64]>::try_from(signature).ok())
+        .context("invalid release signature format")?;
+
+    anyhow::ensure!(
+        params.public_key.verify_raw(data, &signature),
+        "release signature mismatch"
+    );
+    Ok(())
+}
+
+/// Atomically replaces the executable with the new binary
+fn replace_binary(path: &Path, binary: &[u8]) -> Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".new");
+    let tmp = PathBuf::from(tmp);
+
+    // NOTE: temp file is created in the same dir to make rename atomic
+    std::fs::write(&tmp, binary).context("failed to write new binary")?;
+    std::fs::set_permissions(&tmp, std::fs::Permissions::from_mode(0o755))?;
+    if let Err(e) = std::fs::rename(&tmp, path) {
+        std::fs::remove_file(&tmp).ok();
+        return Err(e).context("failed to replace current binary");
+    }
+    Ok(())
+}
+
+fn is_newer(version: &str, current: &str) -> bool {
+    fn parse(version: &str) -> Vec<u64> {
+        version
+            .split(|c| c == '.' || c == '-')
+            .map_while(|part| part.parse().ok())
+            .collect()
+    }
+    parse(version) > parse(current)
+}
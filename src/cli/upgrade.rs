@@ -0,0 +1,114 @@
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use argh::FromArgs;
+use broxus_util::now;
+
+use super::node::safe_restart_delay;
+use super::CliContext;
+use crate::dirs::VALIDATOR_SERVICE;
+use crate::network::{NodeTcpRpc, NodeUdpRpc, ValidatorSetEntry};
+use crate::util::*;
+
+#[derive(FromArgs)]
+/// Rebuilds the node when the network requires a newer global version
+#[argh(subcommand, name = "upgrade")]
+pub struct Cmd {
+    /// rebuild the node even if it supports the required version, skip confirmations
+    #[argh(switch, short = 'f')]
+    force: bool,
+
+    /// do not restart the node service
+    #[argh(switch)]
+    no_restart: bool,
+
+    /// restart the node immediately, even during our catchain session
+    #[argh(switch)]
+    now: bool,
+}
+
+impl Cmd {
+    pub async fn run(self, ctx: CliContext) -> Result<()> {
+        let config = ctx.load_config()?;
+        let dirs = ctx.dirs();
+
+        // Compare the node capabilities with the network requirements
+        let node_tcp_rpc = NodeTcpRpc::new(config.control()?)
+            .await?
+            .with_cancellation(ctx.cancellation());
+        let node_udp_rpc = NodeUdpRpc::new(config.adnl()?, dirs)
+            .await?
+            .with_cancellation(ctx.cancellation());
+
+        let stats = node_tcp_rpc
+            .get_stats()
+            .await?
+            .try_into_running()
+            .context("node is not synced")?;
+        let node = node_udp_rpc
+            .get_capabilities()
+            .await
+            .context("failed to get node capabilities")?;
+        let blockchain_config = node_tcp_rpc.get_config_all().await?.config;
+        let required = blockchain_config.get_global_version()?;
+
+        let upgrade_required =
+            node.version < required.version || required.capabilities & !node.capabilities != 0;
+
+        if upgrade_required {
+            print_warning(format!(
+                "node supports global version {}, but the network requires {}",
+                node.version, required.version
+            ));
+        }
+
+        if !upgrade_required && !self.force {
+            print_output(serde_json::json!({
+                "upgrade_required": false,
+                "node_version": stats.node_version,
+            }));
+            return Ok(());
+        }
+
+        let theme = &dialoguer::theme::ColorfulTheme::default();
+        let node_repo = config.node_repo();
+        if is_terminal()
+            && !self.force
+            && !confirm(theme, true, format!("Build the node from {node_repo}?"))?
+        {
+            return Err(Declined.into());
+        }
+
+        // Build the latest node version
+        dirs.install_node(node_repo).await?;
+        let installed_version = system::get_node_version(&dirs.node_binary).await?;
+
+        // Schedule restart outside our validation round
+        let mut restarted = false;
+        if !self.no_restart {
+            if !self.now {
+                let delay = safe_restart_delay(
+                    matches!(stats.in_current_vset, ValidatorSetEntry::Validator(_)),
+                    matches!(stats.in_next_vset, ValidatorSetEntry::Validator(_)),
+                    &blockchain_config,
+                    now(),
+                )?;
+                if delay > 0 {
+                    let delay = Duration::from_secs(delay as u64);
+                    sleep_with_progress("Waiting for the node restart", delay).await;
+                }
+            }
+
+            system::systemd_restart_service(VALIDATOR_SERVICE).await?;
+            restarted = true;
+        }
+
+        print_output(serde_json::json!({
+            "upgrade_required": upgrade_required,
+            "previous_version": stats.node_version,
+            "installed_version": installed_version,
+            "restarted": restarted,
+        }));
+        Ok(())
+    }
+}
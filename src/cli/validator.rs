@@ -1,3 +1,6 @@
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use std::time::Duration;
 
 use anyhow::{Context, Result};
@@ -5,12 +8,19 @@ use argh::FromArgs;
 use dialoguer::console::style;
 use tokio_util::sync::CancellationToken;
 
-use super::CliContext;
+use super::{check_spending_policy, estimate_transfer, AddressLabels, CliContext};
 use crate::config::{AppConfigValidator, StoredKeys};
+use crate::contracts::wallet::tip3::TokenRoot;
 use crate::contracts::{depool, wallet, InternalMessage, ONE_EVER};
-use crate::network::{ConfigWithId, NodeTcpRpc, NodeUdpRpc, Subscription};
+use crate::network::{connect_data_source, connect_subscription, NodeTcpRpc};
+use crate::node_logs;
+use crate::node_metrics;
+use crate::notifications::Event;
 use crate::util::*;
-use crate::validator::{ValidationManager, ValidationParams};
+use crate::validator::{
+    node_status, IncidentHistory, ManagerClient, PerformanceHistory, RewardEntry, RewardSource,
+    RewardsLedger, RoundStats, ValidationManager, ValidationParams,
+};
 
 #[derive(FromArgs)]
 /// Validator management stuff
@@ -24,21 +34,37 @@ impl Cmd {
     pub async fn run(self, ctx: CliContext) -> Result<()> {
         match self.subcommand {
             SubCmd::Balance(cmd) => cmd.run(ctx).await,
+            SubCmd::Status(cmd) => cmd.run(ctx).await,
+            SubCmd::Report(cmd) => cmd.run(ctx).await,
+            SubCmd::Rewards(cmd) => cmd.run(ctx).await,
+            SubCmd::DePool(cmd) => cmd.run(ctx).await,
             SubCmd::Tick(cmd) => invoke_as_cli(cmd.run(ctx)).await,
             SubCmd::Withdraw(cmd) => invoke_as_cli(cmd.run(ctx)).await,
             SubCmd::Unstake(cmd) => invoke_as_cli(cmd.run(ctx)).await,
+            SubCmd::RewardFraction(cmd) => invoke_as_cli(cmd.run(ctx)).await,
+            SubCmd::Token(cmd) => invoke_as_cli(cmd.run(ctx)).await,
             SubCmd::Run(cmd) => cmd.run(ctx).await,
         }
     }
+
+    pub fn is_manager(&self) -> bool {
+        matches!(self.subcommand, SubCmd::Run(_))
+    }
 }
 
 #[derive(FromArgs)]
 #[argh(subcommand)]
 enum SubCmd {
     Balance(CmdBalance),
+    Status(CmdStatus),
+    Report(CmdReport),
+    Rewards(CmdRewards),
+    DePool(CmdDePool),
     Tick(CmdTick),
     Withdraw(CmdWithdraw),
     Unstake(CmdUnstake),
+    RewardFraction(CmdRewardFraction),
+    Token(CmdToken),
     Run(CmdRun),
 }
 
@@ -56,34 +82,29 @@ impl CmdBalance {
             .take()
             .context("validator entry not found in the app config")?;
 
-        // Prepare RPC client
-        let node_rpc = NodeTcpRpc::new(config.control()?).await?;
+        // Prepare data source (local node or fallback)
+        let data_source = connect_data_source(&config).await?;
+        let book = ctx.load_address_book()?;
 
         // Get current network config params
-        let ConfigWithId {
-            config: blockchain_config,
-            ..
-        } = node_rpc.get_config_all().await?;
+        let blockchain_config = data_source.get_blockchain_config().await?;
         let storage_prices = &StoragePrices::new(&blockchain_config)?;
 
         // Prepare helpers
         let get_account_balance = |address: &ton_block::MsgAddressInt| {
-            let node_rpc = &node_rpc;
+            let data_source = &data_source;
             let address = address.clone();
             async move {
-                let state = node_rpc.get_shard_account_state(&address).await?;
-                Ok::<_, anyhow::Error>(match state.read_account()? {
-                    ton_block::Account::Account(account) => {
-                        let storage_fee = storage_prices.compute_fee(
-                            &account.storage_stat,
-                            address.is_masterchain(),
-                            broxus_util::now(),
-                        );
-
-                        Some((account.storage.balance.grams, storage_fee))
-                    }
-                    ton_block::Account::AccountNone => None,
-                })
+                let account = data_source.get_account_state(&address).await?;
+                Ok::<_, anyhow::Error>(account.map(|account| {
+                    let storage_fee = storage_prices.compute_fee(
+                        &account.storage_stat,
+                        address.is_masterchain(),
+                        broxus_util::now(),
+                    );
+
+                    (account.storage.balance.grams, storage_fee)
+                }))
             }
         };
 
@@ -91,6 +112,7 @@ impl CmdBalance {
             |address: &ton_block::MsgAddressInt, balance: Option<(ton_block::Grams, u128)>| {
                 serde_json::json!({
                     "address": address.to_string(),
+                    "label": book.label(address),
                     "balance": balance.as_ref().map(|(b, _)| b.to_string()),
                     "storage_fee": balance.as_ref().map(|(_, f)| f.to_string()),
                 })
@@ -110,11 +132,11 @@ impl CmdBalance {
             AppConfigValidator::DePool(config) => {
                 let wallet_balance = get_account_balance(&config.owner).await?;
 
-                let depool = node_rpc.get_shard_account_state(&config.depool).await?;
+                let depool = data_source.get_account_state(&config.depool).await?;
 
                 let mut depool_balance = None;
                 let mut proxies = None;
-                if let ton_block::Account::Account(ref state) = depool.read_account()? {
+                if let Some(ref state) = depool {
                     depool_balance = {
                         let storage_fee = storage_prices.compute_fee(
                             &state.storage_stat,
@@ -156,6 +178,464 @@ impl CmdBalance {
     }
 }
 
+#[derive(FromArgs)]
+/// Shows the validator node status in the validator sets
+#[argh(subcommand, name = "status")]
+struct CmdStatus {
+    /// show the history of detected incidents
+    #[argh(switch)]
+    history: bool,
+
+    /// include metrics scraped from the node metrics endpoint
+    #[argh(switch, short = 'v')]
+    verbose: bool,
+}
+
+impl CmdStatus {
+    async fn run(self, ctx: CliContext) -> Result<()> {
+        const NODE_LOG_LINES: usize = 1000;
+
+        let history = if self.history {
+            let incidents = IncidentHistory::new(&ctx.dirs.incident_history).load()?;
+            Some(incidents)
+        } else {
+            None
+        };
+
+        let config = ctx.load_config()?;
+
+        // Prefer the running manager which already has a connection to the node
+        let mut status = match ManagerClient::connect(&ctx.dirs.manager_socket).await {
+            Some(mut client) => client.status().await?,
+            None => {
+                let node_tcp_rpc = NodeTcpRpc::new(config.control()?)
+                    .await
+                    .context("failed to build node TCP client")?
+                    .with_cancellation(ctx.cancellation());
+                let blockchain_config = node_tcp_rpc.get_config_all().await?;
+                node_status(&config, &node_tcp_rpc, &blockchain_config.config).await?
+            }
+        };
+
+        // Parse recent node logs if configured
+        let node_logs = match &config.node_logs {
+            Some(source) => Some(node_logs::read_recent(source, NODE_LOG_LINES).await?),
+            None => None,
+        };
+
+        let node_metrics = if self.verbose {
+            node_metrics::scrape_configured(&ctx.dirs.node_config).await?
+        } else {
+            None
+        };
+
+        if let Some(maintenance) = &config.maintenance {
+            print_warning(match &maintenance.reason {
+                Some(reason) => format!("maintenance mode is enabled: {reason}"),
+                None => "maintenance mode is enabled".to_owned(),
+            });
+        }
+
+        status["node_logs"] = serde_json::json!(node_logs);
+        status["node_metrics"] = serde_json::json!(node_metrics);
+        status["history"] = serde_json::json!(history);
+        print_output(status);
+        Ok(())
+    }
+}
+
+#[derive(FromArgs)]
+/// Prints aggregated validator performance for the recorded rounds
+#[argh(subcommand, name = "report")]
+struct CmdReport {
+    /// include rounds started at or after this unix timestamp
+    #[argh(option)]
+    from: Option<u32>,
+
+    /// include rounds started before this unix timestamp
+    #[argh(option)]
+    to: Option<u32>,
+
+    /// print rounds as CSV
+    #[argh(switch)]
+    csv: bool,
+}
+
+impl CmdReport {
+    async fn run(self, ctx: CliContext) -> Result<()> {
+        const SECONDS_PER_YEAR: f64 = 31536000.0;
+
+        let from = self.from.unwrap_or_default();
+        let to = self.to.unwrap_or(u32::MAX);
+        let rounds = PerformanceHistory::new(&ctx.dirs.performance_history)
+            .load()?
+            .into_values()
+            .filter(|round| (from..to).contains(&round.election_id))
+            .collect::<Vec<_>>();
+
+        if self.csv {
+            let mut output = std::io::stdout().lock();
+            writeln!(output, "election_id,round_end,stake,reward,banned,uptime")?;
+            for round in &rounds {
+                writeln!(
+                    output,
+                    "{},{},{},{},{},{}",
+                    round.election_id,
+                    round.round_end,
+                    Tokens(round.stake),
+                    Tokens(round.reward),
+                    round.banned,
+                    round.uptime().map(|u| u.to_string()).unwrap_or_default(),
+                )?;
+            }
+            return Ok(());
+        }
+
+        let total_stake = rounds.iter().map(|round| round.stake).sum::<u128>();
+        let total_reward = rounds.iter().map(|round| round.reward).sum::<u128>();
+        let avg_stake = total_stake.checked_div(rounds.len() as u128);
+
+        let uptimes = rounds
+            .iter()
+            .filter_map(RoundStats::uptime)
+            .collect::<Vec<_>>();
+        let avg_uptime =
+            (!uptimes.is_empty()).then(|| uptimes.iter().sum::<f64>() / uptimes.len() as f64);
+
+        // NOTE: APY is estimated from the total duration of the rounds
+        let duration = rounds
+            .iter()
+            .map(|round| round.round_end.saturating_sub(round.election_id) as f64)
+            .sum::<f64>();
+        let apy = match avg_stake {
+            Some(avg_stake) if avg_stake > 0 && duration > 0.0 => {
+                Some(total_reward as f64 / avg_stake as f64 * SECONDS_PER_YEAR / duration)
+            }
+            _ => None,
+        };
+
+        print_output(serde_json::json!({
+            "rounds": rounds.len(),
+            "banned_rounds": rounds.iter().filter(|round| round.banned).count(),
+            "total_stake": Tokens(total_stake).to_string(),
+            "avg_stake": avg_stake.map(|stake| Tokens(stake).to_string()),
+            "total_reward": Tokens(total_reward).to_string(),
+            "avg_uptime": avg_uptime,
+            "apy": apy,
+            "history": rounds,
+        }));
+        Ok(())
+    }
+}
+
+#[derive(FromArgs)]
+/// Exports received rewards as CSV
+#[argh(subcommand, name = "rewards")]
+struct CmdRewards {
+    /// include rewards received at or after this unix timestamp
+    #[argh(option)]
+    from: Option<u32>,
+
+    /// include rewards received before this unix timestamp
+    #[argh(option)]
+    to: Option<u32>,
+
+    /// aggregate rewards by period (`day`, `month` or `year`)
+    #[argh(option)]
+    period: Option<RewardsPeriod>,
+}
+
+impl CmdRewards {
+    async fn run(self, ctx: CliContext) -> Result<()> {
+        let from = self.from.unwrap_or_default();
+        let to = self.to.unwrap_or(u32::MAX);
+        let entries = RewardsLedger::new(&ctx.dirs.rewards_ledger)
+            .load()?
+            .into_iter()
+            .filter(|entry| (from..to).contains(&entry.timestamp));
+
+        // Reward value in the price feed currency
+        let reward_value = |entry: &RewardEntry| {
+            let price = entry.price.as_ref()?;
+            Some(entry.reward? as f64 / one_token() as f64 * price.value)
+        };
+
+        let mut output = std::io::stdout().lock();
+        let Some(period) = self.period else {
+            writeln!(
+                output,
+                "date,tx_hash,source,round,amount,reward,price,currency,reward_value"
+            )?;
+            for entry in entries {
+                let (source, round) = match &entry.source {
+                    RewardSource::Elector { election_ids } => (
+                        "elector",
+                        election_ids
+                            .iter()
+                            .map(ToString::to_string)
+                            .collect::<Vec<_>>()
+                            .join(" "),
+                    ),
+                    RewardSource::DePool { round_id } => ("depool", round_id.to_string()),
+                };
+                writeln!(
+                    output,
+                    "{},{},{source},{round},{},{},{},{},{}",
+                    format_date(entry.timestamp, RewardsPeriod::Second),
+                    entry.tx_hash,
+                    Tokens(entry.amount),
+                    entry
+                        .reward
+                        .map(|r| Tokens(r).to_string())
+                        .unwrap_or_default(),
+                    entry
+                        .price
+                        .as_ref()
+                        .map(|p| p.value.to_string())
+                        .unwrap_or_default(),
+                    entry
+                        .price
+                        .as_ref()
+                        .map(|p| p.currency.as_str())
+                        .unwrap_or_default(),
+                    reward_value(&entry)
+                        .map(|v| v.to_string())
+                        .unwrap_or_default(),
+                )?;
+            }
+            return Ok(());
+        };
+
+        // NOTE: entries are already sorted by time
+        let mut periods = Vec::<(String, usize, u128, u128, f64)>::new();
+        for entry in entries {
+            let date = format_date(entry.timestamp, period);
+            let row = match periods.last_mut() {
+                Some(row) if row.0 == date => row,
+                _ => {
+                    periods.push((date, 0, 0, 0, 0.0));
+                    periods.last_mut().unwrap()
+                }
+            };
+            row.1 += 1;
+            row.2 += entry.amount;
+            row.3 += entry.reward.unwrap_or_default();
+            row.4 += reward_value(&entry).unwrap_or_default();
+        }
+
+        writeln!(output, "period,transactions,amount,reward,reward_value")?;
+        for (date, count, amount, reward, value) in periods {
+            writeln!(
+                output,
+                "{date},{count},{},{},{value}",
+                Tokens(amount),
+                Tokens(reward)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Copy, Clone)]
+enum RewardsPeriod {
+    Second,
+    Day,
+    Month,
+    Year,
+}
+
+impl std::str::FromStr for RewardsPeriod {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "day" => Ok(Self::Day),
+            "month" => Ok(Self::Month),
+            "year" => Ok(Self::Year),
+            _ => Err(anyhow::anyhow!(
+                "unknown period (neither `day`, `month` nor `year`)"
+            )),
+        }
+    }
+}
+
+/// Formats the unix timestamp as a UTC date truncated to the period
+fn format_date(timestamp: u32, period: RewardsPeriod) -> String {
+    let (year, month, day) = civil_from_days((timestamp / 86400) as i64);
+
+    let seconds = timestamp % 86400;
+    match period {
+        RewardsPeriod::Second => format!(
+            "{year}-{month:02}-{day:02} {:02}:{:02}:{:02}",
+            seconds / 3600,
+            seconds / 60 % 60,
+            seconds % 60
+        ),
+        RewardsPeriod::Day => format!("{year}-{month:02}-{day:02}"),
+        RewardsPeriod::Month => format!("{year}-{month:02}"),
+        RewardsPeriod::Year => year.to_string(),
+    }
+}
+
+#[derive(FromArgs)]
+/// Shows DePool rounds and participants
+#[argh(subcommand, name = "depool")]
+struct CmdDePool {}
+
+impl CmdDePool {
+    async fn run(self, ctx: CliContext) -> Result<()> {
+        let DePoolCmdContext { depool, .. } = DePoolCmdContext::new(&ctx).await?;
+
+        let state = depool.get_state().await?;
+        let info = depool.get_info(&state)?;
+        let rounds = depool.get_rounds(&state)?;
+        let participants = depool.get_participants(&state)?;
+
+        let mut labels = AddressLabels::new(ctx.load_address_book()?);
+        labels.add(depool.address(), "depool");
+        labels.add(&info.validator_wallet, "validator wallet");
+        for (i, proxy) in info.proxies.iter().enumerate() {
+            labels.add(proxy, format!("proxy #{i}"));
+        }
+
+        let make_complex_stakes =
+            |stakes: &std::collections::BTreeMap<u64, depool::ComplexStake>| {
+                stakes
+                    .iter()
+                    .map(|(round, stake)| {
+                        serde_json::json!({
+                            "round": round,
+                            "owner": stake.owner.to_string(),
+                            "remaining_amount": stake.remaining_amount.to_string(),
+                            "withdrawal_value": stake.withdrawal_value.to_string(),
+                            "withdrawal_period": stake.withdrawal_period,
+                            "last_withdrawal_time": stake.last_withdrawal_time,
+                        })
+                    })
+                    .collect::<Vec<_>>()
+            };
+
+        let mut participant_entries = Vec::with_capacity(participants.len());
+        for address in participants {
+            let Some(participant) = depool.get_participant_info(&state, &address)? else {
+                continue;
+            };
+
+            participant_entries.push(serde_json::json!({
+                "address": address.to_string(),
+                "label": labels.get(&address),
+                "total": participant.total.to_string(),
+                "reward": participant.reward.to_string(),
+                "reinvest": participant.reinvest,
+                "withdraw_value": participant.withdraw_value.to_string(),
+                "stakes": participant
+                    .stakes
+                    .iter()
+                    .map(|(round, stake)| (round.to_string(), stake.to_string()))
+                    .collect::<std::collections::BTreeMap<_, _>>(),
+                "vestings": make_complex_stakes(&participant.vestings),
+                "locks": make_complex_stakes(&participant.locks),
+            }));
+        }
+
+        print_output(serde_json::json!({
+            "address": depool.address().to_string(),
+            "pool_closed": info.pool_closed,
+            "min_stake": info.min_stake.to_string(),
+            "validator_assurance": info.validator_assurance.to_string(),
+            "participant_reward_fraction": info.participant_reward_fraction,
+            "validator_reward_fraction": info.validator_reward_fraction,
+            "balance_threshold": info.balance_threshold.to_string(),
+            "validator_wallet": info.validator_wallet.to_string(),
+            "proxies": info.proxies.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            "rounds": rounds
+                .values()
+                .map(|round| {
+                    serde_json::json!({
+                        "id": round.id,
+                        "step": format!("{:?}", round.step),
+                        "completion_reason": format!("{:?}", round.completion_reason),
+                        "supposed_elected_at": round.supposed_elected_at,
+                        "unfreeze": round.unfreeze,
+                        "stake_held_for": round.stake_held_for,
+                        "stake": round.stake.to_string(),
+                        "recovered_stake": round.recovered_stake.to_string(),
+                        "validator_stake": round.validator_stake.to_string(),
+                        "participant_reward": round.participant_reward.to_string(),
+                        "participant_qty": round.participant_qty,
+                    })
+                })
+                .collect::<Vec<_>>(),
+            "participants": participant_entries,
+        }));
+        Ok(())
+    }
+}
+
+#[derive(FromArgs)]
+/// Decreases the validator reward fraction in the DePool
+#[argh(subcommand, name = "reward-fraction")]
+struct CmdRewardFraction {
+    /// new validator reward fraction (in percents)
+    #[argh(positional)]
+    fraction: u8,
+
+    /// never prompt
+    #[argh(switch, short = 'f')]
+    force: bool,
+}
+
+impl CmdRewardFraction {
+    async fn run(self, ctx: CliContext) -> Result<()> {
+        let DePoolCmdContext { mut depool, .. } = DePoolCmdContext::new(&ctx).await?;
+
+        let depool_keys = StoredKeys::load(&ctx.dirs.depool_keys)
+            .context("failed to load DePool keys")?
+            .as_keypair();
+        depool.set_keypair(depool_keys)?;
+
+        // Validator reward fraction can only be decreased
+        let state = depool.get_state().await?;
+        let info = depool.get_info(&state)?;
+        anyhow::ensure!(
+            self.fraction > 0 && self.fraction < info.validator_reward_fraction,
+            "new fraction must be in range (0, {})",
+            info.validator_reward_fraction
+        );
+
+        if is_terminal() {
+            eprintln!(
+                "{}\n{}\n{}\n{}\n",
+                style("DePool address:").green().bold(),
+                style(depool.address()).bold(),
+                style("Validator reward fraction:").green().bold(),
+                style(format!(
+                    "{}% -> {}%",
+                    info.validator_reward_fraction, self.fraction
+                ))
+                .bold(),
+            );
+
+            if !self.force
+                && !confirm(
+                    &dialoguer::theme::ColorfulTheme::default(),
+                    false,
+                    "Do you really want to change the validator reward fraction?",
+                )?
+            {
+                return Err(Declined.into());
+            }
+        }
+
+        depool.set_validator_reward_fraction(self.fraction).await?;
+
+        print_output(serde_json::json!({
+            "validator_reward_fraction": self.fraction,
+        }));
+        Ok(())
+    }
+}
+
 #[derive(FromArgs)]
 /// Ticktock depool.
 #[argh(subcommand, name = "tick")]
@@ -163,25 +643,24 @@ struct CmdTick {}
 
 impl CmdTick {
     async fn run(self, ctx: CliContext) -> Result<()> {
-        let DePoolCmdContext {
-            currency,
-            wallet,
-            depool,
-        } = DePoolCmdContext::new(&ctx).await?;
+        let DePoolCmdContext { wallet, depool } = DePoolCmdContext::new(&ctx).await?;
 
         // Check wallet balance
         let wallet_balance = wallet.get_balance().await?.unwrap_or_default();
         anyhow::ensure!(
             ONE_EVER * 2 < wallet_balance,
-            "wallet balance is not enough ({} {currency})",
+            "wallet balance is not enough ({:#})",
             Tokens(wallet_balance)
         );
 
+        let message = depool.ticktock()?;
+        estimate_transfer(&wallet, &message).await?;
+
         // Send external message and wait until it is delivered
         let TransactionWithHash {
             hash: tx_hash,
             data: tx,
-        } = wallet.transfer(depool.ticktock()?).await?;
+        } = wallet.transfer(message).await?;
 
         // Parse transaction
         let msg_hash = tx
@@ -202,15 +681,15 @@ impl CmdTick {
 /// Reduce the validator stake in the depool.
 #[argh(subcommand, name = "unstake")]
 struct CmdUnstake {
-    /// amount to unstake in tokens
+    /// amount to unstake (e.g. `1250.5` or `1e9 nano`)
     #[argh(positional)]
-    amount: u128,
+    amount: String,
 
     /// never prompt
     #[argh(switch, short = 'f')]
     force: bool,
 
-    /// interpret amount as amount in nano tokens
+    /// interpret amount without a unit as nano tokens
     #[argh(switch)]
     nano: bool,
 
@@ -221,17 +700,10 @@ struct CmdUnstake {
 
 impl CmdUnstake {
     async fn run(self, ctx: CliContext) -> Result<()> {
-        let DePoolCmdContext {
-            currency,
-            wallet,
-            depool,
-        } = DePoolCmdContext::new(&ctx).await?;
+        let DePoolCmdContext { wallet, depool } = DePoolCmdContext::new(&ctx).await?;
 
         // Parse arguments
-        let mut amount = self.amount;
-        if !self.nano {
-            amount = amount.saturating_mul(ONE_EVER);
-        }
+        let amount = parse_tokens(&self.amount, self.nano)?;
 
         // Get participant info
         let depool_state = depool.get_state().await?;
@@ -244,7 +716,7 @@ impl CmdUnstake {
         // Check participant info
         anyhow::ensure!(
             amount <= participant_info.total as u128,
-            "participant stake is not enough ({} {currency})",
+            "participant stake is not enough ({:#})",
             Tokens(participant_info.total),
         );
 
@@ -252,10 +724,12 @@ impl CmdUnstake {
         let wallet_balance = wallet.get_balance().await?.unwrap_or_default();
         anyhow::ensure!(
             ONE_EVER * 2 < wallet_balance,
-            "wallet balance is not enough ({} {currency})",
+            "wallet balance is not enough ({:#})",
             Tokens(wallet_balance)
         );
 
+        let message = depool.withdraw_part(amount as u64, self.from_pooling)?;
+
         if is_terminal() {
             eprintln!(
                 "{}\n{}\n{}\n{}\n\n{}\n{}\n{}\n{}\n",
@@ -264,29 +738,30 @@ impl CmdUnstake {
                 style("DePool address:").green().bold(),
                 style(depool.address()).bold(),
                 style("Total stake:").green().bold(),
-                style(format!("{} {currency}", Tokens(participant_info.total))).bold(),
+                style(format!("{:#}", Tokens(participant_info.total))).bold(),
                 style("Amount to unstake:").green().bold(),
-                style(format!("{} {currency}", Tokens(amount))).bold()
+                style(format!("{:#}", Tokens(amount))).bold()
             );
+        }
 
-            if !self.force
-                && !confirm(
-                    &dialoguer::theme::ColorfulTheme::default(),
-                    false,
-                    "Do you really want to unstake tokens?",
-                )?
-            {
-                return Ok(());
-            }
+        estimate_transfer(&wallet, &message).await?;
+
+        if is_terminal()
+            && !self.force
+            && !confirm(
+                &dialoguer::theme::ColorfulTheme::default(),
+                false,
+                "Do you really want to unstake tokens?",
+            )?
+        {
+            return Err(Declined.into());
         }
 
         // Send external message and wait until it is delivered
         let TransactionWithHash {
             hash: tx_hash,
             data: tx,
-        } = wallet
-            .transfer(depool.withdraw_part(amount as u64, self.from_pooling)?)
-            .await?;
+        } = wallet.transfer(message).await?;
 
         // Parse transaction
         let msg_hash = tx
@@ -307,19 +782,19 @@ impl CmdUnstake {
 /// Withdraws tokens from the validator wallet
 #[argh(subcommand, name = "withdraw")]
 struct CmdWithdraw {
-    /// destination account address
+    /// destination account address or name from the address book
     #[argh(positional)]
     dest: String,
 
-    /// amount to withdraw in tokens
+    /// amount to withdraw (e.g. `1250.5` or `1e9 nano`)
     #[argh(positional)]
-    amount: u128,
+    amount: String,
 
     /// never prompt
     #[argh(switch, short = 'f')]
     force: bool,
 
-    /// interpret amount as amount in nano tokens
+    /// interpret amount without a unit as nano tokens
     #[argh(switch)]
     nano: bool,
 }
@@ -328,29 +803,25 @@ impl CmdWithdraw {
     async fn run(self, ctx: CliContext) -> Result<()> {
         // Load config
         let mut config = ctx.load_config()?;
+        let spending_policy = ctx.load_spending_policy(&config)?;
         let validator = config
             .validator
             .take()
             .context("validator entry not found in the app config")?;
-        let currency = config.currency();
 
         // Prepare RPC clients
-        let node_tcp_rpc = NodeTcpRpc::new(config.control()?)
-            .await
-            .context("failed to build node TCP client")?;
-        let node_udp_rpc = NodeUdpRpc::new(config.adnl()?)
-            .await
-            .context("failed to build node UDP client")?;
-
-        let subscription = Subscription::new(node_tcp_rpc, node_udp_rpc);
+        let subscription = connect_subscription(&config, ctx.dirs(), ctx.cancellation()).await?;
+        if let Some(policy) = spending_policy {
+            subscription.set_spending_policy(policy);
+        }
         subscription.ensure_ready().await?;
 
         // Parse arguments
-        let dest = parse_address(&self.dest)?;
-        let mut amount = self.amount;
-        if !self.nano {
-            amount = amount.saturating_mul(ONE_EVER);
-        }
+        let dest = ctx.resolve_address(&self.dest)?;
+        let amount = parse_tokens(&self.amount, self.nano)?;
+
+        let mut labels = AddressLabels::new(ctx.load_address_book()?);
+        labels.add_system(&subscription.get_system_addresses().await?);
 
         // Prepare wallet
         let keypair = StoredKeys::load(&ctx.dirs.validator_keys)
@@ -373,46 +844,50 @@ impl CmdWithdraw {
         let wallet_balance = wallet.get_balance().await?.unwrap_or_default();
         anyhow::ensure!(
             amount < wallet_balance,
-            "wallet balance is not enough ({} {currency})",
+            "wallet balance is not enough ({:#})",
             Tokens(wallet_balance)
         );
 
+        let message = InternalMessage {
+            dst: dest,
+            amount,
+            payload: Default::default(),
+            bounce: false,
+        };
+
         if is_terminal() {
             eprintln!(
                 "{}\n{}\n{}\n{}\n\n{}\n{}\n{}\n{}\n",
                 style("Wallet address:").green().bold(),
                 style(wallet.address()).bold(),
                 style("Wallet balance:").green().bold(),
-                style(format!("{} {currency}", Tokens(wallet_balance))).bold(),
+                style(format!("{:#}", Tokens(wallet_balance))).bold(),
                 style("Target address:").green().bold(),
-                style(&dest).bold(),
+                style(labels.display(&message.dst)).bold(),
                 style("Amount to send:").green().bold(),
-                style(format!("{} {currency}", Tokens(amount))).bold()
+                style(format!("{:#}", Tokens(amount))).bold()
             );
+        }
 
-            if !self.force
-                && !confirm(
-                    &dialoguer::theme::ColorfulTheme::default(),
-                    false,
-                    "Do you really want to send tokens?",
-                )?
-            {
-                return Ok(());
-            }
+        estimate_transfer(&wallet, &message).await?;
+        check_spending_policy(&wallet, &message).await?;
+
+        if is_terminal()
+            && !self.force
+            && !confirm(
+                &dialoguer::theme::ColorfulTheme::default(),
+                false,
+                "Do you really want to send tokens?",
+            )?
+        {
+            return Err(Declined.into());
         }
 
         // Send external message and wait until it is delivered
         let TransactionWithHash {
             hash: tx_hash,
             data: tx,
-        } = wallet
-            .transfer(InternalMessage {
-                dst: dest,
-                amount,
-                payload: Default::default(),
-                bounce: false,
-            })
-            .await?;
+        } = wallet.transfer(message).await?;
 
         // Parse transaction
         let msg_hash = tx
@@ -429,6 +904,213 @@ impl CmdWithdraw {
     }
 }
 
+#[derive(FromArgs)]
+/// TIP-3 tokens of the validator wallet
+#[argh(subcommand, name = "token")]
+struct CmdToken {
+    #[argh(subcommand)]
+    subcommand: TokenSubCmd,
+}
+
+impl CmdToken {
+    async fn run(self, ctx: CliContext) -> Result<()> {
+        match self.subcommand {
+            TokenSubCmd::Balance(cmd) => cmd.run(ctx).await,
+            TokenSubCmd::Transfer(cmd) => cmd.run(ctx).await,
+        }
+    }
+}
+
+#[derive(FromArgs)]
+#[argh(subcommand)]
+enum TokenSubCmd {
+    Balance(CmdTokenBalance),
+    Transfer(CmdTokenTransfer),
+}
+
+#[derive(FromArgs)]
+/// Fetches the token balance of the validator wallet
+#[argh(subcommand, name = "balance")]
+struct CmdTokenBalance {
+    /// token root address or name
+    #[argh(positional)]
+    root: String,
+}
+
+impl CmdTokenBalance {
+    async fn run(self, ctx: CliContext) -> Result<()> {
+        let root = ctx.resolve_address(&self.root)?;
+
+        let TokenCmdContext { wallet, root, .. } = TokenCmdContext::new(&ctx, root).await?;
+
+        let details = root.get_details().await?;
+        let token_wallet = root.wallet_of(wallet.address()).await?;
+        let balance = token_wallet.get_balance().await?;
+
+        print_output(serde_json::json!({
+            "root": root.address().to_string(),
+            "symbol": details.symbol,
+            "decimals": details.decimals,
+            "token_wallet": token_wallet.address().to_string(),
+            "balance": balance.unwrap_or_default().to_string(),
+        }));
+        Ok(())
+    }
+}
+
+#[derive(FromArgs)]
+/// Transfers tokens from the validator wallet
+#[argh(subcommand, name = "transfer")]
+struct CmdTokenTransfer {
+    /// token root address or name
+    #[argh(positional)]
+    root: String,
+
+    /// recipient address or name (token wallet owner)
+    #[argh(positional)]
+    dest: String,
+
+    /// amount of tokens to transfer (with decimals)
+    #[argh(positional)]
+    amount: String,
+
+    /// never prompt
+    #[argh(switch, short = 'f')]
+    force: bool,
+
+    /// interpret amount as amount in the smallest token units
+    #[argh(switch)]
+    raw: bool,
+}
+
+impl CmdTokenTransfer {
+    async fn run(self, ctx: CliContext) -> Result<()> {
+        let root = ctx.resolve_address(&self.root)?;
+        let dest = ctx.resolve_address(&self.dest)?;
+
+        let TokenCmdContext { wallet, root } = TokenCmdContext::new(&ctx, root).await?;
+
+        // Parse amount
+        let details = root.get_details().await?;
+        let amount = if self.raw {
+            self.amount.parse().context("invalid amount")?
+        } else {
+            parse_token_amount(&self.amount, details.decimals)?
+        };
+        anyhow::ensure!(amount > 0, "amount must be greater than zero");
+
+        // Check token balance
+        let token_wallet = root.wallet_of(wallet.address()).await?;
+        let balance = token_wallet.get_balance().await?.unwrap_or_default();
+        anyhow::ensure!(
+            amount <= balance,
+            "token balance is not enough ({balance} < {amount})"
+        );
+
+        let message = token_wallet.transfer(amount, &dest, wallet.address())?;
+
+        // Check wallet balance
+        let wallet_balance = wallet.get_balance().await?.unwrap_or_default();
+        anyhow::ensure!(
+            message.amount + ONE_EVER < wallet_balance,
+            "wallet balance is not enough ({:#})",
+            Tokens(wallet_balance)
+        );
+
+        if is_terminal() {
+            eprintln!(
+                "{}\n{}\n{}\n{}\n\n{}\n{}\n{}\n{}\n",
+                style("Wallet address:").green().bold(),
+                style(wallet.address()).bold(),
+                style("Token balance:").green().bold(),
+                style(format!("{balance} ({})", details.symbol)).bold(),
+                style("Recipient address:").green().bold(),
+                style(AddressLabels::new(ctx.load_address_book()?).display(&dest)).bold(),
+                style("Amount to transfer:").green().bold(),
+                style(format!("{amount} ({})", details.symbol)).bold()
+            );
+        }
+
+        estimate_transfer(&wallet, &message).await?;
+        check_spending_policy(&wallet, &message).await?;
+
+        if is_terminal()
+            && !self.force
+            && !confirm(
+                &dialoguer::theme::ColorfulTheme::default(),
+                false,
+                "Do you really want to transfer tokens?",
+            )?
+        {
+            return Err(Declined.into());
+        }
+
+        // Send external message and wait until it is delivered
+        let TransactionWithHash {
+            hash: tx_hash,
+            data: tx,
+        } = wallet.transfer(message).await?;
+
+        // Parse transaction
+        let msg_hash = tx
+            .in_msg
+            .context("external inbound message not found")?
+            .hash();
+
+        // Done
+        print_output(serde_json::json!({
+            "tx_hash": tx_hash.to_hex_string(),
+            "msg_hash": msg_hash.to_hex_string(),
+        }));
+        Ok(())
+    }
+}
+
+struct TokenCmdContext {
+    wallet: wallet::Wallet,
+    root: TokenRoot,
+}
+
+impl TokenCmdContext {
+    async fn new(ctx: &CliContext, root: ton_block::MsgAddressInt) -> Result<Self> {
+        // Load config
+        let mut config = ctx.load_config()?;
+        let spending_policy = ctx.load_spending_policy(&config)?;
+        let wallet_address = match config.validator.take() {
+            Some(AppConfigValidator::Single(single)) => single.address,
+            Some(AppConfigValidator::DePool(depool)) => depool.owner,
+            None => anyhow::bail!("validator entry not found in the app config"),
+        };
+
+        // Prepare RPC clients
+        let subscription = connect_subscription(&config, ctx.dirs(), ctx.cancellation()).await?;
+        if let Some(policy) = spending_policy {
+            subscription.set_spending_policy(policy);
+        }
+        subscription.ensure_ready().await?;
+
+        // Prepare wallet
+        let keypair = StoredKeys::load(&ctx.dirs.validator_keys)
+            .context("failed to load validator wallet keys")?
+            .as_keypair();
+
+        let wallet = wallet::Wallet::new(
+            wallet_address.workchain_id() as i8,
+            keypair,
+            subscription.clone(),
+        );
+        anyhow::ensure!(
+            wallet.address() == &wallet_address,
+            "validator wallet address mismatch"
+        );
+
+        Ok(Self {
+            wallet,
+            root: TokenRoot::new(root, subscription),
+        })
+    }
+}
+
 #[derive(FromArgs)]
 /// Starts managing validation
 #[argh(subcommand, name = "run")]
@@ -461,7 +1143,15 @@ struct CmdRun {
     #[argh(option, default = "2.0")]
     retry_interval_multiplier: f64,
 
-    /// forces stakes to be sent right after the start of each election
+    /// bid window start (in percents of the elections duration). 0 default
+    #[argh(option, default = "0")]
+    bid_window_start: u8,
+
+    /// bid window end (in percents of the elections duration). 25 default
+    #[argh(option, default = "25")]
+    bid_window_end: u8,
+
+    /// forces stakes to be sent right at the start of the bid window
     #[argh(switch)]
     disable_random_shift: bool,
 
@@ -472,13 +1162,30 @@ struct CmdRun {
     /// force elect
     #[argh(switch)]
     force: bool,
+
+    /// send election requests despite conflicting bids from other validator nodes
+    #[argh(switch)]
+    allow_conflicting_bids: bool,
+
+    /// only log messages which would be sent
+    #[argh(switch)]
+    dry_run: bool,
 }
 
 impl CmdRun {
     async fn run(mut self, ctx: CliContext) -> Result<()> {
+        anyhow::ensure!(
+            self.bid_window_start <= self.bid_window_end && self.bid_window_end <= 100,
+            "invalid bid window"
+        );
+
         // Start listening termination signals
         let signal_rx = broxus_util::any_signal(broxus_util::TERMINATION_SIGNALS);
 
+        let heartbeat_path = ctx.dirs.manager_heartbeat.clone();
+
+        let config = ctx.load_config().ok();
+
         // Create validation manager
         let mut manager = ValidationManager::new(
             ctx.dirs,
@@ -487,32 +1194,60 @@ impl CmdRun {
                 stake_unfreeze_offset: self.stake_unfreeze_offset,
                 elections_start_offset: self.elections_start_offset,
                 elections_end_offset: self.elections_end_offset,
+                bid_window: (self.bid_window_start, self.bid_window_end),
                 disable_random_shift: self.disable_random_shift,
                 ignore_deploy: self.ignore_deploy,
+                dry_run: self.dry_run,
+                allow_conflicting_bids: self.allow_conflicting_bids,
             },
         );
 
-        if self.force {
-            return manager.force_elect().await;
+        // NOTE: notifications config is reloaded by the manager on each iteration
+        let notifier = manager.notifier().clone();
+        if let Some(config) = config {
+            notifier.update(config.notifications);
+            notifier.update_hooks(config.hooks);
         }
+        notifier.notify(Event::ManagerStarted);
 
         // Spawn cancellation future
         let cancellation_token = CancellationToken::new();
         let cancelled = cancellation_token.cancelled();
+        let forced = Arc::new(AtomicBool::new(false));
 
         tokio::spawn({
             let guard = manager.guard().clone();
             let cancellation_token = cancellation_token.clone();
+            let forced = forced.clone();
 
             async move {
-                if let Ok(signal) = signal_rx.await {
-                    tracing::warn!(?signal, "received termination signal");
-                    let _guard = guard.lock().await;
-                    cancellation_token.cancel();
+                let Ok(signal) = signal_rx.await else {
+                    return;
+                };
+                tracing::warn!(?signal, "received termination signal");
+
+                // Wait for the current on-chain operation unless the signal is repeated
+                let repeated_signal = broxus_util::any_signal(broxus_util::TERMINATION_SIGNALS);
+                tokio::select! {
+                    _guard = guard.lock() => {
+                        tracing::info!("no pending operations, shutting down");
+                    }
+                    _ = repeated_signal => {
+                        tracing::error!("received repeated termination signal, aborting");
+                        forced.store(true, Ordering::Release);
+                    }
                 }
+                cancellation_token.cancel();
             }
         });
 
+        if self.force {
+            return tokio::select! {
+                res = manager.force_elect() => res,
+                _ = cancelled => shutdown_result(&forced),
+            };
+        }
+
         // Prepare validation future
         let validation_fut = async {
             self.min_retry_interval = std::cmp::max(self.min_retry_interval, 1);
@@ -524,6 +1259,9 @@ impl CmdRun {
             loop {
                 if let Err(e) = manager.try_validate().await {
                     tracing::error!("error occurred: {e:?}");
+                    notifier.notify(Event::Error {
+                        message: format!("{e:#}"),
+                    });
                 }
 
                 tracing::info!("retrying in {interval} seconds");
@@ -536,18 +1274,36 @@ impl CmdRun {
             }
         };
 
+        // Report that the manager is alive (used by the health check)
+        let heartbeat_fut = async {
+            loop {
+                if let Err(e) = std::fs::write(&heartbeat_path, broxus_util::now().to_string()) {
+                    tracing::warn!("failed to write heartbeat: {e:?}");
+                }
+                tokio::time::sleep(Duration::from_secs(30)).await;
+            }
+        };
+
         // Cancellable main loop
         tokio::select! {
             _ = validation_fut => {},
+            _ = heartbeat_fut => {},
             _ = cancelled => {},
         };
 
-        Ok(())
+        shutdown_result(&forced)
     }
 }
 
+fn shutdown_result(forced: &AtomicBool) -> Result<()> {
+    if forced.load(Ordering::Acquire) {
+        anyhow::bail!("validation manager was stopped during an on-chain operation");
+    }
+    tracing::info!("validation manager stopped");
+    Ok(())
+}
+
 struct DePoolCmdContext {
-    currency: &'static str,
     wallet: wallet::Wallet,
     depool: depool::DePool,
 }
@@ -567,14 +1323,7 @@ impl DePoolCmdContext {
         };
 
         // Prepare RPC clients
-        let node_tcp_rpc = NodeTcpRpc::new(config.control()?)
-            .await
-            .context("failed to build node TCP client")?;
-        let node_udp_rpc = NodeUdpRpc::new(config.adnl()?)
-            .await
-            .context("failed to build node UDP client")?;
-
-        let subscription = Subscription::new(node_tcp_rpc, node_udp_rpc);
+        let subscription = connect_subscription(&config, ctx.dirs(), ctx.cancellation()).await?;
         subscription.ensure_ready().await?;
 
         // Prepare wallet
@@ -595,10 +1344,6 @@ impl DePoolCmdContext {
         // Prepare depool
         let depool = depool::DePool::new(validator.depool_type, validator.depool, subscription);
 
-        Ok(Self {
-            currency: config.currency(),
-            wallet,
-            depool,
-        })
+        Ok(Self { wallet, depool })
     }
 }
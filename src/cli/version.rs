@@ -0,0 +1,97 @@
+use anyhow::{Context, Result};
+use argh::FromArgs;
+
+use super::CliContext;
+use crate::network::{NodeTcpRpc, NodeUdpRpc};
+use crate::util::*;
+
+#[derive(FromArgs)]
+/// Prints versions of nodekeeper and the node
+#[argh(subcommand, name = "version")]
+pub struct Cmd {
+    /// check compatibility of the running node with the network
+    #[argh(switch)]
+    check: bool,
+}
+
+impl Cmd {
+    pub async fn run(self, ctx: CliContext) -> Result<()> {
+        let dirs = ctx.dirs();
+
+        let installed_node_version = if dirs.node_binary.exists() {
+            Some(system::get_node_version(&dirs.node_binary).await?)
+        } else {
+            None
+        };
+
+        let mut output = serde_json::json!({
+            "nodekeeper_version": env!("CARGO_PKG_VERSION"),
+            "installed_node_version": installed_node_version,
+        });
+
+        if self.check {
+            output["compatibility"] = check_compatibility(&ctx).await?;
+        }
+
+        print_output(output);
+        Ok(())
+    }
+}
+
+async fn check_compatibility(ctx: &CliContext) -> Result<serde_json::Value> {
+    let config = ctx.load_config()?;
+
+    let node_tcp_rpc = NodeTcpRpc::new(config.control()?)
+        .await
+        .context("failed to create node rpc client")?
+        .with_cancellation(ctx.cancellation());
+    let node_udp_rpc = NodeUdpRpc::new(config.adnl()?, ctx.dirs())
+        .await
+        .context("failed to create node udp client")?
+        .with_cancellation(ctx.cancellation());
+
+    let stats = node_tcp_rpc
+        .get_stats()
+        .await?
+        .try_into_running()
+        .context("node is not synced")?;
+    let node = node_udp_rpc
+        .get_capabilities()
+        .await
+        .context("failed to get node capabilities")?;
+
+    // Network requirements are stored in the config param 8
+    let blockchain_config = node_tcp_rpc.get_config_all().await?.config;
+    let network = blockchain_config
+        .get_global_version()
+        .context("failed to get global version")?;
+
+    let missing_capabilities = network.capabilities & !node.capabilities;
+    let compatible = node.version >= network.version && missing_capabilities == 0;
+
+    if node.version < network.version {
+        print_warning(format!(
+            "node supports global version {}, but the network requires {}",
+            node.version, network.version
+        ));
+    }
+    if missing_capabilities != 0 {
+        print_warning(format!(
+            "node does not support required capabilities: 0x{missing_capabilities:016x}"
+        ));
+    }
+
+    Ok(serde_json::json!({
+        "node_version": stats.node_version,
+        "node": {
+            "global_version": node.version,
+            "capabilities": format!("0x{:016x}", node.capabilities),
+        },
+        "network": {
+            "global_version": network.version,
+            "capabilities": format!("0x{:016x}", network.capabilities),
+        },
+        "missing_capabilities": format!("0x{missing_capabilities:016x}"),
+        "compatible": compatible,
+    }))
+}
@@ -0,0 +1,61 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use broxus_util::serde_string;
+use serde::{Deserialize, Serialize};
+
+/// Named recipients which can be used instead of raw addresses
+#[derive(Default, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AddressBook {
+    #[serde(default)]
+    pub entries: BTreeMap<String, AddressBookEntry>,
+}
+
+impl AddressBook {
+    /// Loads the address book, returns an empty one if the file doesn't exist
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read address book {}", path.display()))?;
+        toml::from_str(&content).context("failed to deserialize address book")
+    }
+
+    pub fn store<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let data = toml::to_string_pretty(self).context("failed to serialize address book")?;
+        std::fs::write(path, data).context("failed to save address book")
+    }
+
+    /// Parses a raw address or finds an entry with the specified name
+    pub fn resolve(&self, input: &str) -> Result<ton_block::MsgAddressInt> {
+        let input = input.trim();
+        if let Some(entry) = self.entries.get(input) {
+            return Ok(entry.address.clone());
+        }
+        input
+            .parse()
+            .map_err(|_| anyhow::anyhow!("invalid address or unknown name `{input}`"))
+    }
+
+    /// Finds the name of the address
+    pub fn label(&self, address: &ton_block::MsgAddressInt) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|(_, entry)| &entry.address == address)
+            .map(|(name, _)| name.as_str())
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AddressBookEntry {
+    #[serde(with = "serde_string")]
+    pub address: ton_block::MsgAddressInt,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
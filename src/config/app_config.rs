@@ -1,39 +1,157 @@
-use std::net::SocketAddrV4;
-use std::path::Path;
+use std::collections::HashMap;
+use std::net::{SocketAddr, SocketAddrV4};
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 
 use anyhow::{Context, Result};
 use broxus_util::{
-    const_duration_ms, serde_duration_ms, serde_hex_array, serde_optional_string, serde_string,
-    serde_string_or_number,
+    const_duration_ms, serde_duration_ms, serde_hex_array, serde_optional_hex_array,
+    serde_optional_string, serde_string, serde_string_or_number,
 };
 use everscale_crypto::ed25519;
 use serde::{Deserialize, Serialize};
 
+use super::{migrations, Secret};
 use crate::defaults;
-use crate::util::{serde_mc_address, serde_public_key, serde_secret_key};
+use crate::util::{serde_mc_address, serde_public_key, CronSchedule};
 
 /// Tool config
 #[derive(Default, Clone, Serialize, Deserialize)]
 #[serde(default, deny_unknown_fields)]
 pub struct AppConfig {
+    /// Config layout version (older layouts are migrated on load)
+    pub version: u32,
+    /// Node role selected during init
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub node_role: Option<NodeRole>,
     /// Control config
     pub control: Option<AppConfigControl>,
     /// ADNL config
     pub adnl: Option<AppConfigAdnl>,
     /// Validation config
     pub validator: Option<AppConfigValidator>,
+    /// Fallback JRPC endpoint which is used when the local node is not available
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fallback_jrpc: Option<reqwest::Url>,
+    /// Coordination between redundant validation managers
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub failover: Option<AppConfigFailover>,
+    /// Default metrics exporter target (when not specified in args)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exporter: Option<AppConfigExporter>,
+    /// Notification channels
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub notifications: Option<AppConfigNotifications>,
+    /// User scripts which are executed on events
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub hooks: Vec<AppConfigHook>,
+    /// Validator wallet and DePool balance thresholds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub balance_alerts: Option<AppConfigBalanceAlerts>,
+    /// Logging of the validation manager and exporter
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub logging: Option<AppConfigLogging>,
+    /// Trace export of the validation manager and exporter
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub telemetry: Option<AppConfigTelemetry>,
+    /// Source of the node logs for the log parser
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub node_logs: Option<AppConfigNodeLogs>,
+    /// Automatic restart of the stuck node
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub watchdog: Option<AppConfigWatchdog>,
+    /// Free space monitoring of the node DB filesystem
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub disk_watchdog: Option<AppConfigDiskWatchdog>,
+    /// Recurring tasks of the validation manager
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub schedule: Vec<AppConfigScheduledTask>,
+    /// Network specific parameters
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub network: Option<AppConfigNetwork>,
+    /// Token price source for the rewards accounting
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub price_feed: Option<AppConfigPriceFeed>,
+    /// Release source for the `self-update` command
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub updates: Option<AppConfigUpdates>,
+    /// Source of the refreshed network presets for the `networks update` command
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub networks_index: Option<AppConfigNetworksIndex>,
+    /// Remote services which check reachability of the node ADNL address
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub external_probes: Vec<reqwest::Url>,
+    /// Planned maintenance, elections are skipped while it is set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub maintenance: Option<AppConfigMaintenance>,
+    /// Management API of the validation manager
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api: Option<AppConfigApi>,
+    /// Limits for transfers from the validator wallet
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub spending_policy: Option<AppConfigSpendingPolicy>,
+    /// Encryption and remote destinations of the backups
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub backup: Option<AppConfigBackup>,
 }
 
 impl AppConfig {
+    /// Loads the app config with `NODEKEEPER_*` env overrides applied
+    /// (e.g. `NODEKEEPER_CONTROL__SERVER_ADDRESS` for `control.server_address`).
+    ///
+    /// NOTE: the config file can be omitted when overrides are specified.
     pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
-        let content = std::fs::read_to_string(path).context("failed to read app config")?;
+        let overrides = env_overrides(std::env::vars())?;
+        if overrides.is_empty() {
+            return Self::load_file(path);
+        }
+
+        let path = path.as_ref();
+        let mut config = if path.exists() {
+            read_table(path)?.1
+        } else {
+            toml::value::Table::new()
+        };
+
+        for (name, keys, value) in overrides {
+            let (key, parents) = keys.split_last().context("empty env override")?;
+            let mut table = &mut config;
+            for parent in parents {
+                table = match table
+                    .entry(parent.clone())
+                    .or_insert_with(|| toml::Value::Table(Default::default()))
+                {
+                    toml::Value::Table(table) => table,
+                    _ => anyhow::bail!("env override `{name}` conflicts with a non-table value"),
+                };
+            }
+            table.insert(key.clone(), value);
+        }
+
+        toml::Value::Table(config)
+            .try_into()
+            .context("failed to deserialize app config with env overrides")
+    }
+
+    /// Loads the app config without env overrides (e.g. to modify and store it back)
+    pub fn load_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        // NOTE: the content is parsed again to keep error positions
+        let (content, _) = read_table(path.as_ref())?;
         toml::from_str(&content).context("failed to deserialize app config")
     }
 
     pub fn store<P: AsRef<Path>>(&self, path: P) -> Result<()> {
-        let data = toml::to_string_pretty(self).context("failed to serialize config")?;
-        std::fs::write(path, data).context("failed to save config")
+        let mut config = match toml::Value::try_from(self).context("failed to serialize config")? {
+            toml::Value::Table(config) => config,
+            _ => anyhow::bail!("unexpected app config structure"),
+        };
+        config.insert(
+            "version".to_owned(),
+            toml::Value::Integer(migrations::APP_CONFIG_VERSION as i64),
+        );
+        let data = toml::to_string_pretty(&toml::Value::Table(config))
+            .context("failed to serialize config")?;
+        write_atomic(path.as_ref(), &data).context("failed to save config")
     }
 
     pub fn currency(&self) -> &'static str {
@@ -42,7 +160,7 @@ impl AppConfig {
         }
 
         if let Some(adnl) = &self.adnl {
-            if let Some(defaults) = defaults::detect_custom_defaults(&adnl.zerostate_file_hash) {
+            if let Some(defaults) = defaults::detect_network_defaults(&adnl.zerostate_file_hash) {
                 return defaults.currency;
             }
         }
@@ -50,13 +168,46 @@ impl AppConfig {
         defaults::DEFAULT_CURRENCY
     }
 
+    pub fn node_role(&self) -> NodeRole {
+        self.node_role.unwrap_or_default()
+    }
+
+    /// Enables or disables maintenance mode (keeps the original start time)
+    pub fn set_maintenance(&mut self, enabled: bool, reason: Option<String>) {
+        self.maintenance = match (enabled, self.maintenance.take()) {
+            (false, _) => None,
+            (true, Some(maintenance)) => Some(AppConfigMaintenance {
+                reason: reason.or(maintenance.reason),
+                ..maintenance
+            }),
+            (true, None) => Some(AppConfigMaintenance {
+                since: broxus_util::now(),
+                reason,
+            }),
+        };
+    }
+
+    pub fn decimals(&self) -> u8 {
+        if let Some(decimals) = self.network.as_ref().and_then(|network| network.decimals) {
+            return decimals;
+        }
+
+        if let Some(adnl) = &self.adnl {
+            if let Some(defaults) = defaults::detect_network_defaults(&adnl.zerostate_file_hash) {
+                return defaults.decimals;
+            }
+        }
+
+        defaults::DEFAULT_DECIMALS
+    }
+
     pub fn node_repo(&self) -> &str {
         if let Some(node_repo) = defaults::node_repo_from_env() {
             return node_repo;
         }
 
         if let Some(adnl) = &self.adnl {
-            if let Some(defaults) = defaults::detect_custom_defaults(&adnl.zerostate_file_hash) {
+            if let Some(defaults) = defaults::detect_network_defaults(&adnl.zerostate_file_hash) {
                 return defaults.node_repo;
             }
         }
@@ -73,6 +224,138 @@ impl AppConfig {
     }
 }
 
+/// Reads the app config as a TOML table, migrating it to the latest layout
+fn read_table(path: &Path) -> Result<(String, toml::value::Table)> {
+    let content = std::fs::read_to_string(path).context("failed to read app config")?;
+    let mut config: toml::value::Table =
+        toml::from_str(&content).context("failed to deserialize app config")?;
+
+    let original = config.clone();
+    let Some(version) = migrations::migrate_app_config(&mut config)? else {
+        return Ok((content, config));
+    };
+
+    // NOTE: rewriting drops comments and keys order, so the file is left as is
+    // when only the `version` field was added (it is written on the next store)
+    let mut migrated = config.clone();
+    migrated.remove("version");
+    if migrated == original {
+        return Ok((content, config));
+    }
+
+    let content = toml::to_string_pretty(&toml::Value::Table(config.clone()))
+        .context("failed to serialize config")?;
+
+    // NOTE: the config is still usable when it can't be rewritten (e.g. on a read-only mount)
+    let to = migrations::APP_CONFIG_VERSION;
+    match migrations::backup(path, version).and_then(|backup| {
+        write_atomic(path, &content).context("failed to save config")?;
+        Ok(backup)
+    }) {
+        Ok(backup) => {
+            tracing::warn!(from = version, to, backup = %backup.display(), "migrated app config");
+        }
+        Err(e) => tracing::warn!(
+            from = version,
+            to,
+            "failed to store migrated app config: {e:?}"
+        ),
+    }
+    Ok((content, config))
+}
+
+/// Writes the file through a temp file, so concurrent readers never see a partial content
+pub(crate) fn write_atomic(path: &Path, data: &str) -> std::io::Result<()> {
+    let tmp = path.with_extension(format!("{}.tmp", std::process::id()));
+    std::fs::write(&tmp, data)?;
+    // NOTE: the config may contain secrets, so the original permissions are kept
+    if let Ok(metadata) = std::fs::metadata(path) {
+        std::fs::set_permissions(&tmp, metadata.permissions())?;
+    }
+    std::fs::rename(&tmp, path).map_err(|e| {
+        std::fs::remove_file(&tmp).ok();
+        e
+    })
+}
+
+const ENV_PREFIX: &str = "NODEKEEPER_";
+
+/// Returns a list of (env variable name, config keys path, value)
+/// from the `(name, value)` pairs of the environment.
+///
+/// NOTE: only variables for the known config sections are overrides,
+/// others (e.g. `NODEKEEPER_ROOT` or `NODEKEEPER_EVENT` for hooks) are skipped
+fn env_overrides<I>(vars: I) -> Result<Vec<(String, Vec<String>, toml::Value)>>
+where
+    I: IntoIterator<Item = (String, String)>,
+{
+    let sections = config_sections();
+
+    let mut overrides = Vec::new();
+    for (name, value) in vars {
+        let Some(path) = name.strip_prefix(ENV_PREFIX) else {
+            continue;
+        };
+
+        let keys = path.split("__").map(str::to_lowercase).collect::<Vec<_>>();
+        if !sections.contains(&keys[0].as_str()) {
+            tracing::debug!(name, "env variable is not a config override");
+            continue;
+        }
+        anyhow::ensure!(
+            keys.iter().all(|key| !key.is_empty()),
+            "invalid env override `{name}`"
+        );
+
+        // NOTE: values which are not valid TOML are treated as strings
+        let value = toml::from_str::<toml::value::Table>(&format!("value = {value}"))
+            .ok()
+            .and_then(|mut table| table.remove("value"))
+            .unwrap_or(toml::Value::String(value));
+
+        overrides.push((name, keys, value));
+    }
+
+    // Apply overrides in a stable order
+    overrides.sort_unstable_by(|(a, ..), (b, ..)| a.cmp(b));
+    Ok(overrides)
+}
+
+/// Names of the top-level app config fields (taken from its `Deserialize` impl)
+fn config_sections() -> &'static [&'static str] {
+    use serde::de::{self, Visitor};
+
+    struct FieldsCollector<'a>(&'a mut &'static [&'static str]);
+
+    impl<'de> de::Deserializer<'de> for FieldsCollector<'_> {
+        type Error = de::value::Error;
+
+        fn deserialize_any<V: Visitor<'de>>(self, _: V) -> Result<V::Value, Self::Error> {
+            Err(de::Error::custom("expected a struct"))
+        }
+
+        fn deserialize_struct<V: Visitor<'de>>(
+            self,
+            _: &'static str,
+            fields: &'static [&'static str],
+            _: V,
+        ) -> Result<V::Value, Self::Error> {
+            *self.0 = fields;
+            Err(de::Error::custom("fields collected"))
+        }
+
+        serde::forward_to_deserialize_any! {
+            bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+            bytes byte_buf option unit unit_struct newtype_struct seq tuple
+            tuple_struct map enum identifier ignored_any
+        }
+    }
+
+    let mut fields: &'static [&'static str] = &[];
+    AppConfig::deserialize(FieldsCollector(&mut fields)).ok();
+    fields
+}
+
 #[derive(Clone, Serialize, Deserialize)]
 #[serde(deny_unknown_fields)]
 pub struct AppConfigControl {
@@ -83,9 +366,8 @@ pub struct AppConfigControl {
     #[serde(with = "serde_public_key")]
     pub server_pubkey: ed25519::PublicKey,
 
-    /// Control client pubkey
-    #[serde(with = "serde_secret_key")]
-    pub client_secret: ed25519::SecretKey,
+    /// Control client secret key (inline or `env:`/`file:` reference)
+    pub client_secret: Secret<ed25519::SecretKey>,
 
     /// Control server connection timeout
     #[serde(with = "serde_duration_ms", default = "const_duration_ms::<2000>")]
@@ -94,6 +376,14 @@ pub struct AppConfigControl {
     /// Control server query timeout
     #[serde(with = "serde_duration_ms", default = "const_duration_ms::<10000>")]
     pub query_timeout: Duration,
+
+    /// Ping the idle control connection after this period (`0` to disable)
+    #[serde(with = "serde_duration_ms", default = "const_duration_ms::<15000>")]
+    pub keepalive_interval: Duration,
+
+    /// Reconnect if nothing was received from the control server for this period
+    #[serde(with = "serde_duration_ms", default = "const_duration_ms::<45000>")]
+    pub idle_timeout: Duration,
 }
 
 impl AppConfigControl {
@@ -105,9 +395,11 @@ impl AppConfigControl {
         Self {
             server_address: addr,
             server_pubkey: server_key,
-            client_secret: client_key,
+            client_secret: Secret::new(client_key),
             connection_timeout: Duration::from_millis(2000),
             query_timeout: Duration::from_millis(10000),
+            keepalive_interval: Duration::from_millis(15000),
+            idle_timeout: Duration::from_millis(45000),
         }
     }
 }
@@ -115,7 +407,8 @@ impl AppConfigControl {
 #[derive(Clone, Serialize, Deserialize)]
 #[serde(deny_unknown_fields)]
 pub struct AppConfigAdnl {
-    /// Local ADNL port
+    /// Local ADNL port (`0` to select a free port at runtime)
+    #[serde(default)]
     pub client_port: u16,
 
     /// Server ADNL address
@@ -128,6 +421,734 @@ pub struct AppConfigAdnl {
     /// Zerostate file hash from the global config
     #[serde(with = "serde_hex_array")]
     pub zerostate_file_hash: [u8; 32],
+
+    /// Skip verification of the received blocks (only for local setups)
+    #[serde(default)]
+    pub trust_mode: bool,
+}
+
+impl AppConfigAdnl {
+    /// New configs select the local port at runtime, a fixed port is opt-in
+    pub const AUTO_CLIENT_PORT: u16 = 0;
+}
+
+/// Metrics exporter target
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "lowercase", tag = "mode")]
+pub enum AppConfigExporter {
+    /// Serve metrics over HTTP
+    Http { addr: SocketAddr },
+    /// Periodically replace the textfile (e.g. for the node_exporter textfile collector)
+    File { path: PathBuf },
+}
+
+#[derive(Default, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AppConfigNotifications {
+    pub channels: Vec<AppConfigNotificationChannel>,
+    /// Custom message templates by event name (e.g. `election_submitted`)
+    pub templates: HashMap<String, String>,
+}
+
+// NOTE: `deny_unknown_fields` is not supported with `flatten`
+#[derive(Clone, Serialize, Deserialize)]
+pub struct AppConfigNotificationChannel {
+    #[serde(flatten)]
+    pub target: NotificationTarget,
+    /// Min severity of the sent events
+    #[serde(default = "default_min_severity")]
+    pub min_severity: NotificationSeverity,
+    /// Names of the sent events (all events if empty)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub events: Vec<String>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase", tag = "type")]
+pub enum NotificationTarget {
+    Telegram {
+        bot_token: Secret<String>,
+        chat_id: String,
+    },
+    Slack {
+        webhook_url: Secret<reqwest::Url>,
+    },
+    Discord {
+        webhook_url: Secret<reqwest::Url>,
+    },
+    /// Sends emails using the local `sendmail` binary
+    Email {
+        to: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        from: Option<String>,
+        #[serde(default = "default_sendmail_path")]
+        sendmail: PathBuf,
+    },
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NotificationSeverity {
+    Info,
+    Warning,
+    Error,
+}
+
+fn default_min_severity() -> NotificationSeverity {
+    NotificationSeverity::Warning
+}
+
+fn default_sendmail_path() -> PathBuf {
+    PathBuf::from("/usr/sbin/sendmail")
+}
+
+/// Script which receives the event as JSON on stdin
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AppConfigHook {
+    /// Event name (e.g. `election_submitted`)
+    pub event: String,
+    /// Path to the executable
+    pub command: PathBuf,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+    /// The script is killed when it runs longer
+    #[serde(with = "serde_duration_ms", default = "const_duration_ms::<30000>")]
+    pub timeout: Duration,
+}
+
+/// Active/standby mode of the validation manager
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AppConfigFailover {
+    pub role: FailoverRole,
+    /// Shared lock file (e.g. on a network storage).
+    ///
+    /// When not specified, only the bids in the elector are used to detect the primary.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lock_path: Option<PathBuf>,
+    /// How long the lock is held without updates
+    #[serde(with = "serde_duration_ms", default = "const_duration_ms::<10800000>")]
+    pub lease: Duration,
+}
+
+/// Restarts the node service when it stops applying masterchain blocks
+/// or the control server stops answering.
+///
+/// NOTE: the manager user must be allowed to restart the service.
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AppConfigWatchdog {
+    /// Node systemd service name
+    #[serde(default = "default_node_unit")]
+    pub service: String,
+    /// The node is considered stuck after this timeout without progress
+    #[serde(with = "serde_duration_ms", default = "const_duration_ms::<600000>")]
+    pub stuck_timeout: Duration,
+    /// Node status check interval
+    #[serde(with = "serde_duration_ms", default = "const_duration_ms::<60000>")]
+    pub interval: Duration,
+    /// Min interval between restarts
+    #[serde(with = "serde_duration_ms", default = "const_duration_ms::<1800000>")]
+    pub restart_cooldown: Duration,
+    /// Max number of restarts during a day
+    #[serde(default = "default_max_daily_restarts")]
+    pub max_daily_restarts: usize,
+}
+
+fn default_max_daily_restarts() -> usize {
+    3
+}
+
+/// Runs cleanup actions when free space on the node DB filesystem
+/// is below the threshold.
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AppConfigDiskWatchdog {
+    /// Min free space (in GB)
+    #[serde(default = "default_min_free_space_gb")]
+    pub min_free_space_gb: u64,
+    /// Free space check interval
+    #[serde(with = "serde_duration_ms", default = "const_duration_ms::<60000>")]
+    pub interval: Duration,
+    /// Actions to run when free space is low
+    #[serde(default = "default_disk_watchdog_actions")]
+    pub actions: Vec<DiskWatchdogAction>,
+    /// Archives newer than this are never pruned
+    #[serde(with = "serde_duration_ms", default = "const_duration_ms::<172800000>")]
+    pub min_archive_age: Duration,
+}
+
+impl AppConfigDiskWatchdog {
+    pub fn has_action(&self, action: DiskWatchdogAction) -> bool {
+        self.actions.contains(&action)
+    }
+}
+
+fn default_min_free_space_gb() -> u64 {
+    20
+}
+
+fn default_disk_watchdog_actions() -> Vec<DiskWatchdogAction> {
+    vec![DiskWatchdogAction::Alert]
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiskWatchdogAction {
+    /// Remove the oldest block archives
+    PruneArchives,
+    /// Send the `low_disk_space` notification
+    Alert,
+    /// Skip elections until there is enough space
+    StopBidding,
+}
+
+// NOTE: `deny_unknown_fields` is not supported with `flatten`
+/// Task which is run by the validation manager on a cron schedule
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct AppConfigScheduledTask {
+    /// Cron expression in UTC (e.g. `0 9 * * *` or `@daily`)
+    #[serde(with = "serde_string")]
+    pub cron: CronSchedule,
+    /// Max random delay after the scheduled time
+    #[serde(with = "serde_duration_ms", default)]
+    pub jitter: Duration,
+    #[serde(flatten)]
+    pub task: ScheduledTask,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "task")]
+pub enum ScheduledTask {
+    /// Send the `balance_report` notification with the wallet and DePool balances
+    BalanceReport,
+    /// Log the node DB size and send the `large_db` notification above the threshold
+    DbSizeCheck {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        max_size_gb: Option<u64>,
+    },
+    /// Send ticktock to the DePool
+    Ticktock,
+    /// Copy keys and configs into a timestamped directory
+    Backup {
+        /// Backups directory (`backups` in the root directory by default)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        dir: Option<PathBuf>,
+        /// How many backups to keep
+        #[serde(default = "default_backups_to_keep")]
+        keep: usize,
+        /// Name of the remote destination from the `backup` section (used instead of `dir`)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        destination: Option<String>,
+    },
+}
+
+impl ScheduledTask {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::BalanceReport => "balance_report",
+            Self::DbSizeCheck { .. } => "db_size_check",
+            Self::Ticktock => "ticktock",
+            Self::Backup { .. } => "backup",
+        }
+    }
+}
+
+fn default_backups_to_keep() -> usize {
+    7
+}
+
+/// Encryption and remote destinations of the backups
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AppConfigBackup {
+    /// Passphrase for the client-side encryption (required for remote destinations)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub passphrase: Option<Secret<String>>,
+    /// Named remote destinations
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub destinations: Vec<BackupDestination>,
+}
+
+impl AppConfigBackup {
+    pub fn destination(&self, name: &str) -> Result<&BackupDestination> {
+        self.destinations
+            .iter()
+            .find(|destination| destination.name() == name)
+            .with_context(|| format!("backup destination `{name}` not found"))
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase", tag = "type")]
+pub enum BackupDestination {
+    /// S3-compatible object storage
+    S3 {
+        name: String,
+        /// Storage endpoint (e.g. `https://s3.eu-central-1.amazonaws.com`)
+        endpoint: reqwest::Url,
+        region: String,
+        bucket: String,
+        /// Objects key prefix (e.g. `validator-1/`)
+        #[serde(default, skip_serializing_if = "String::is_empty")]
+        prefix: String,
+        access_key_id: String,
+        secret_access_key: Secret<String>,
+    },
+    /// Remote directory which is accessed using the local `sftp` binary
+    Sftp {
+        name: String,
+        /// Remote directory (e.g. `backup@host:/backups/validator-1`)
+        target: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        port: Option<u16>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        identity_file: Option<PathBuf>,
+    },
+}
+
+impl BackupDestination {
+    pub fn name(&self) -> &str {
+        match self {
+            Self::S3 { name, .. } | Self::Sftp { name, .. } => name,
+        }
+    }
+}
+
+/// Purpose of the node, affects its GC settings and resource limits
+#[derive(Default, Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NodeRole {
+    /// Participates in elections, keeps only recent data
+    #[default]
+    Validator,
+    /// Serves recent blocks and states to other nodes
+    Full,
+    /// Keeps all blocks since the zerostate
+    Archive,
+}
+
+impl NodeRole {
+    pub const ALL: [Self; 3] = [Self::Validator, Self::Full, Self::Archive];
+
+    /// Block archives lifetime, `None` means that archives are kept forever
+    pub fn archives_life_time_hours(&self) -> Option<u32> {
+        match self {
+            Self::Validator => Some(48),
+            Self::Full => Some(168),
+            Self::Archive => None,
+        }
+    }
+
+    /// How long unused cells are kept in the states DB
+    pub fn cells_lifetime_sec(&self) -> u32 {
+        match self {
+            Self::Validator | Self::Full => 1800,
+            Self::Archive => 86400,
+        }
+    }
+
+    /// Additional `[Service]` entries for the node systemd service
+    pub fn systemd_limits(&self) -> &'static str {
+        match self {
+            Self::Validator | Self::Full => "LimitNOFILE=2048000",
+            Self::Archive => "LimitNOFILE=4096000\nTimeoutStopSec=600",
+        }
+    }
+}
+
+impl std::fmt::Display for NodeRole {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Validator => "validator",
+            Self::Full => "full",
+            Self::Archive => "archive",
+        })
+    }
+}
+
+/// Overrides for networks with non-standard system contracts.
+///
+/// Addresses are taken from the blockchain config when not specified.
+#[derive(Default, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AppConfigNetwork {
+    /// Elector contract address (masterchain account id)
+    #[serde(
+        with = "serde_optional_hex_array",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub elector_address: Option<[u8; 32]>,
+    /// Config contract address (masterchain account id)
+    #[serde(
+        with = "serde_optional_hex_array",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub config_address: Option<[u8; 32]>,
+    /// Minter contract address (masterchain account id)
+    #[serde(
+        with = "serde_optional_hex_array",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub minter_address: Option<[u8; 32]>,
+    /// Number of decimals of the native currency
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub decimals: Option<u8>,
+}
+
+impl AppConfigNetwork {
+    /// Returns system contract addresses with overrides applied
+    pub fn system_addresses(&self, config: &ton_block::ConfigParams) -> Result<SystemAddresses> {
+        let elector = match self.elector_address {
+            Some(address) => address.into(),
+            None => config
+                .elector_address()
+                .context("invalid elector address")?,
+        };
+        let config_address = match self.config_address {
+            Some(address) => address.into(),
+            None => config.config_address().context("invalid config address")?,
+        };
+        let minter = match self.minter_address {
+            Some(address) => address.into(),
+            None => config.minter_address().context("invalid minter address")?,
+        };
+
+        Ok(SystemAddresses {
+            elector,
+            config: config_address,
+            minter,
+        })
+    }
+}
+
+/// Masterchain account ids of the system contracts
+pub struct SystemAddresses {
+    pub elector: ton_types::UInt256,
+    pub config: ton_types::UInt256,
+    pub minter: ton_types::UInt256,
+}
+
+/// Maintenance mode state
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AppConfigMaintenance {
+    /// Unix timestamp when the maintenance mode was enabled
+    pub since: u32,
+    /// Optional description of the planned works
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+/// Management API server which is started by the validation manager
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AppConfigApi {
+    /// TCP address to listen on (only loopback addresses are allowed)
+    #[serde(default = "default_api_listen_addr")]
+    pub listen_addr: SocketAddr,
+    /// Unix socket path (used instead of the TCP address)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unix_socket: Option<PathBuf>,
+    /// Bearer token with the operator role
+    pub token: Secret<String>,
+    /// Additional named tokens (e.g. read-only tokens for monitoring)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tokens: Vec<AppConfigApiToken>,
+}
+
+impl AppConfigApi {
+    /// Tokens with their roles, the main token is named `default`
+    pub fn all_tokens(&self) -> impl Iterator<Item = (&str, &str, ApiRole)> {
+        std::iter::once(("default", self.token.as_str(), ApiRole::Operator)).chain(
+            self.tokens
+                .iter()
+                .map(|item| (item.name.as_str(), item.token.as_str(), item.role)),
+        )
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AppConfigApiToken {
+    /// Unique token name which is used in logs
+    pub name: String,
+    pub token: Secret<String>,
+    pub role: ApiRole,
+}
+
+/// Allowed management API actions
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ApiRole {
+    /// Only status queries
+    ReadOnly,
+    /// Status queries and actions (maintenance, stake recovery)
+    Operator,
+}
+
+impl std::str::FromStr for ApiRole {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "read_only" => Ok(Self::ReadOnly),
+            "operator" => Ok(Self::Operator),
+            _ => Err(anyhow::anyhow!(
+                "unknown API role (neither `read_only` nor `operator`)"
+            )),
+        }
+    }
+}
+
+fn default_api_listen_addr() -> SocketAddr {
+    SocketAddr::from(([127, 0, 0, 1], 10600))
+}
+
+/// Source of the signed nodekeeper releases
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AppConfigUpdates {
+    /// Latest release endpoint (GitHub releases API format)
+    #[serde(default = "default_release_feed")]
+    pub feed: reqwest::Url,
+    /// Public key which is used to verify release binaries
+    #[serde(with = "serde_public_key")]
+    pub public_key: ed25519::PublicKey,
+}
+
+fn default_release_feed() -> reqwest::Url {
+    defaults::DEFAULT_RELEASE_FEED.parse().unwrap()
+}
+
+/// Signed network presets index
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AppConfigNetworksIndex {
+    /// Index JSON URL, its signature is downloaded from `<url>.sig`
+    pub url: reqwest::Url,
+    /// Public key which is used to verify the index
+    #[serde(with = "serde_public_key")]
+    pub public_key: ed25519::PublicKey,
+}
+
+/// HTTP endpoint which returns the token price as JSON
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AppConfigPriceFeed {
+    pub url: reqwest::Url,
+    /// JSON pointer to the price value (e.g. `/everscale/usd`), the whole response by default
+    #[serde(default)]
+    pub pointer: String,
+    /// Currency of the price
+    #[serde(default = "default_price_currency")]
+    pub currency: String,
+}
+
+fn default_price_currency() -> String {
+    "USD".to_owned()
+}
+
+/// Where the node writes its logs
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "lowercase", tag = "source")]
+pub enum AppConfigNodeLogs {
+    /// Systemd journal of the node service
+    Journald {
+        #[serde(default = "default_node_unit")]
+        unit: String,
+    },
+    /// Plain log file
+    File { path: PathBuf },
+}
+
+fn default_node_unit() -> String {
+    crate::dirs::VALIDATOR_SERVICE.to_owned()
+}
+
+/// Structured logs with file rotation
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AppConfigLogging {
+    /// Log filter (e.g. `info` or `info,nodekeeper::network=debug`)
+    #[serde(default = "default_log_level")]
+    pub level: String,
+    #[serde(default)]
+    pub format: LogFormat,
+    /// Log files directory (`logs` in the root directory by default)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dir: Option<PathBuf>,
+    /// Log file is rotated when it exceeds this size (in bytes)
+    #[serde(with = "serde_string_or_number", default = "default_max_log_file_size")]
+    pub max_file_size: u64,
+    /// Log file is also rotated at the start of each period
+    #[serde(default)]
+    pub rotation: LogRotation,
+    /// How many rotated files to keep
+    #[serde(default = "default_max_log_files")]
+    pub max_files: usize,
+    /// Also write logs to stderr
+    #[serde(default = "const_bool::<true>")]
+    pub stderr: bool,
+}
+
+fn default_log_level() -> String {
+    "info".to_owned()
+}
+
+fn default_max_log_file_size() -> u64 {
+    100 << 20
+}
+
+fn default_max_log_files() -> usize {
+    10
+}
+
+/// Spans export over OTLP/HTTP (e.g. to Grafana Tempo or Jaeger)
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AppConfigTelemetry {
+    /// OTLP/HTTP traces endpoint (e.g. `http://127.0.0.1:4318/v1/traces`)
+    pub endpoint: reqwest::Url,
+    /// Spans filter
+    #[serde(default = "default_telemetry_level")]
+    pub level: String,
+    /// `service.name` of the exported spans (log name by default)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub service_name: Option<String>,
+    /// Additional HTTP headers (e.g. for authorization)
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub headers: HashMap<String, String>,
+    /// Max number of spans in one request
+    #[serde(default = "default_telemetry_batch_size")]
+    pub batch_size: usize,
+    /// Max delay before the collected spans are exported
+    #[serde(with = "serde_duration_ms", default = "const_duration_ms::<5000>")]
+    pub export_interval: Duration,
+}
+
+fn default_telemetry_level() -> String {
+    "nodekeeper=debug".to_owned()
+}
+
+fn default_telemetry_batch_size() -> usize {
+    512
+}
+
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    #[default]
+    Pretty,
+    Json,
+}
+
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogRotation {
+    Never,
+    Hourly,
+    #[default]
+    Daily,
+}
+
+impl LogRotation {
+    /// Returns the rotation period (in seconds)
+    pub fn period(&self) -> Option<u32> {
+        match self {
+            Self::Never => None,
+            Self::Hourly => Some(3600),
+            Self::Daily => Some(86400),
+        }
+    }
+}
+
+/// Low balance alerts for the validator wallet and DePool
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AppConfigBalanceAlerts {
+    /// Validator wallet balance threshold (in nano tokens)
+    #[serde(
+        with = "serde_string_or_number",
+        default = "default_min_wallet_balance"
+    )]
+    pub wallet_min_balance: u64,
+    /// DePool balance threshold (in nano tokens)
+    #[serde(
+        with = "serde_string_or_number",
+        default = "default_min_depool_balance"
+    )]
+    pub depool_min_balance: u64,
+    /// Don't send election requests while any balance is below the threshold
+    #[serde(default)]
+    pub pause_bidding: bool,
+    /// Balances check interval
+    #[serde(with = "serde_duration_ms", default = "const_duration_ms::<600000>")]
+    pub interval: Duration,
+}
+
+/// Limits for transfers from the validator wallet
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AppConfigSpendingPolicy {
+    /// Max amount of a single transfer (in nano tokens)
+    #[serde(
+        default,
+        with = "serde_optional_string",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub max_transfer: Option<u64>,
+    /// Max total amount of transfers within 24 hours (in nano tokens)
+    #[serde(
+        default,
+        with = "serde_optional_string",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub daily_limit: Option<u64>,
+    /// Allowed destinations (raw addresses or address book names).
+    /// Transfers to other addresses must be confirmed in the CLI
+    /// and are rejected by the validation manager
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allowlist: Vec<String>,
+    /// Operations which require a second approval
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub approvals: Option<AppConfigApprovals>,
+}
+
+/// Second approval (two-man rule) for dangerous operations
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AppConfigApprovals {
+    /// Hex encoded public keys which can approve operations
+    pub approvers: Vec<String>,
+    /// Transfers above this amount require an approval (in nano tokens)
+    #[serde(
+        default,
+        with = "serde_optional_string",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub transfer_threshold: Option<u64>,
+    /// Control and ADNL key rotations require an approval
+    #[serde(default)]
+    pub key_rotation: bool,
+}
+
+fn default_min_wallet_balance() -> u64 {
+    10_000_000_000
+}
+
+fn default_min_depool_balance() -> u64 {
+    20_000_000_000
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FailoverRole {
+    /// Sends election requests at the scheduled time
+    Primary,
+    /// Sends election requests only if the primary missed the bid window
+    Standby,
 }
 
 #[derive(Clone, Eq, PartialEq, Serialize, Deserialize)]
@@ -141,6 +1162,14 @@ impl AppConfigValidator {
     pub fn is_single(&self) -> bool {
         matches!(self, Self::Single(_))
     }
+
+    /// Validator wallet address (DePool owner for DePool validators)
+    pub fn wallet_address(&self) -> &ton_block::MsgAddressInt {
+        match self {
+            Self::Single(single) => &single.address,
+            Self::DePool(depool) => &depool.owner,
+        }
+    }
 }
 
 #[derive(Clone, Eq, PartialEq, Serialize, Deserialize)]
@@ -152,6 +1181,40 @@ pub struct AppConfigValidatorSingle {
     pub stake_per_round: u64,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub stake_factor: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stake_strategy: Option<AppConfigStakeStrategy>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recovered_stake: Option<AppConfigRecoveredStake>,
+}
+
+/// What to do with the stake recovered from the elector
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AppConfigRecoveredStake {
+    /// Keep the recovered stake on the validator wallet
+    Return,
+    /// Add the recovered stake to the next election bid
+    Rollover,
+}
+
+/// How the stake for each elections is computed
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "snake_case", tag = "type")]
+pub enum AppConfigStakeStrategy {
+    /// Always use `stake_per_round`
+    Fixed,
+    /// Use the percent of the wallet balance, keeping the reserve
+    Percent {
+        percent: u8,
+        #[serde(with = "serde_string_or_number")]
+        reserve: u64,
+    },
+    /// Use the whole balance (except the reserve), but not more than
+    /// the stake which is limited by the stake factor
+    MaxFactorAware {
+        #[serde(with = "serde_string_or_number")]
+        reserve: u64,
+    },
 }
 
 #[derive(Clone, Eq, PartialEq, Serialize, Deserialize)]
@@ -172,6 +1235,68 @@ pub struct AppConfigValidatorDePool {
     pub cluster: Option<ton_block::MsgAddressInt>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub deploy: Option<AppConfigDePoolDeploymentParams>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reactions: Option<AppConfigDePoolReactions>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy_top_up: Option<AppConfigProxyTopUp>,
+}
+
+/// Automatic reactions on DePool events
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AppConfigDePoolReactions {
+    /// Start the elections flow when the DePool requests the stake signing
+    #[serde(default = "const_bool::<true>")]
+    pub participate_on_request: bool,
+    /// Replenish the DePool balance when it is too low
+    #[serde(default)]
+    pub top_up_on_low_balance: bool,
+}
+
+impl Default for AppConfigDePoolReactions {
+    fn default() -> Self {
+        Self {
+            participate_on_request: true,
+            top_up_on_low_balance: false,
+        }
+    }
+}
+
+/// Periodic replenishment of the DePool proxies
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AppConfigProxyTopUp {
+    /// Proxy balance threshold (in nano tokens, without the storage fee)
+    #[serde(with = "serde_string_or_number", default = "default_min_proxy_balance")]
+    pub min_balance: u64,
+    /// Max amount (in nano tokens) which can be sent to proxies during a day
+    #[serde(with = "serde_string_or_number", default = "default_max_proxy_top_up")]
+    pub daily_limit: u64,
+    /// Proxy balances check interval
+    #[serde(with = "serde_duration_ms", default = "const_duration_ms::<600000>")]
+    pub interval: Duration,
+}
+
+impl Default for AppConfigProxyTopUp {
+    fn default() -> Self {
+        Self {
+            min_balance: default_min_proxy_balance(),
+            daily_limit: default_max_proxy_top_up(),
+            interval: Duration::from_secs(600),
+        }
+    }
+}
+
+fn default_min_proxy_balance() -> u64 {
+    2_000_000_000
+}
+
+fn default_max_proxy_top_up() -> u64 {
+    50_000_000_000
+}
+
+const fn const_bool<const N: bool>() -> bool {
+    N
 }
 
 #[derive(Clone, Eq, PartialEq, Serialize, Deserialize)]
@@ -204,3 +1329,90 @@ impl DePoolType {
         !self.is_default()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn config_sections_are_known() {
+        let sections = config_sections();
+        assert!(sections.contains(&"control"));
+        assert!(sections.contains(&"validator"));
+        assert!(!sections.contains(&"root"));
+    }
+
+    fn vars(vars: &[(&str, &str)]) -> Vec<(String, String)> {
+        vars.iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn hook_event_env_is_not_an_override() {
+        // Hooks are executed with these variables and can call nodekeeper
+        let overrides = env_overrides(vars(&[
+            ("NODEKEEPER_EVENT", "election_submitted"),
+            ("NODEKEEPER_ROOT", "/var/ever"),
+            ("PATH", "/usr/bin"),
+        ]))
+        .unwrap();
+        assert!(overrides.is_empty());
+    }
+
+    #[test]
+    fn env_overrides_are_parsed() {
+        let overrides = env_overrides(vars(&[
+            ("NODEKEEPER_CONTROL__SERVER_ADDRESS", "127.0.0.1:5031"),
+            ("NODEKEEPER_ADNL__TRUST_MODE", "true"),
+        ]))
+        .unwrap();
+
+        // Sorted by the variable name
+        assert_eq!(overrides.len(), 2);
+        let (name, keys, value) = &overrides[0];
+        assert_eq!(name, "NODEKEEPER_ADNL__TRUST_MODE");
+        assert_eq!(keys, &["adnl", "trust_mode"]);
+        assert_eq!(value, &toml::Value::Boolean(true));
+
+        // Values which are not valid TOML are strings
+        let (_, keys, value) = &overrides[1];
+        assert_eq!(keys, &["control", "server_address"]);
+        assert_eq!(value, &toml::Value::String("127.0.0.1:5031".to_owned()));
+    }
+
+    #[test]
+    fn version_only_migration_is_not_rewritten() {
+        let path = std::env::temp_dir().join(format!(
+            "nodekeeper-version-only-{}.toml",
+            std::process::id()
+        ));
+        // NOTE: comments and keys order would be lost on rewrite
+        let content = "# local node\n[control]\nserver_address = \"127.0.0.1:5031\"\n";
+        std::fs::write(&path, content).unwrap();
+
+        let result = read_table(&path);
+        let stored = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let (_, config) = result.unwrap();
+        assert_eq!(
+            config.get("version"),
+            Some(&toml::Value::Integer(migrations::APP_CONFIG_VERSION as i64))
+        );
+        assert_eq!(stored, content);
+
+        // Backup is only created when the file is rewritten
+        assert!(!std::env::temp_dir()
+            .join(format!(
+                "nodekeeper-version-only-{}.toml.v0.bak",
+                std::process::id()
+            ))
+            .exists());
+    }
+
+    #[test]
+    fn empty_env_override_key_is_rejected() {
+        assert!(env_overrides(vars(&[("NODEKEEPER_CONTROL__", "1")])).is_err());
+    }
+}
@@ -0,0 +1,88 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+use super::Secret;
+
+/// Remote validators which are managed by the `fleet` commands
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FleetInventory {
+    #[serde(default)]
+    pub hosts: Vec<FleetHost>,
+}
+
+impl FleetInventory {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read fleet inventory {}", path.display()))?;
+        let inventory: Self =
+            toml::from_str(&content).context("failed to deserialize fleet inventory")?;
+
+        let mut names = HashSet::new();
+        for host in &inventory.hosts {
+            anyhow::ensure!(
+                names.insert(host.name.as_str()),
+                "duplicate fleet host `{}`",
+                host.name
+            );
+            anyhow::ensure!(
+                host.api.is_some() != host.ssh.is_some(),
+                "fleet host `{}` must have either `api` or `ssh` access",
+                host.name
+            );
+        }
+
+        Ok(inventory)
+    }
+}
+
+/// Remote validator
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FleetHost {
+    /// Unique name which is used in the output
+    pub name: String,
+    /// Arbitrary label to select a group of hosts (e.g. `mainnet`)
+    #[serde(default)]
+    pub profile: Option<String>,
+    /// Access via the management API
+    #[serde(default)]
+    pub api: Option<FleetHostApi>,
+    /// Access via SSH
+    #[serde(default)]
+    pub ssh: Option<FleetHostSsh>,
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FleetHostApi {
+    /// Base URL of the management API (e.g. behind a reverse proxy or an SSH tunnel)
+    pub url: reqwest::Url,
+    /// Bearer token
+    pub token: Secret<String>,
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FleetHostSsh {
+    /// SSH destination (hostname, address or an alias from `~/.ssh/config`)
+    pub host: String,
+    #[serde(default)]
+    pub user: Option<String>,
+    #[serde(default)]
+    pub port: Option<u16>,
+    /// Path to the remote `nodekeeper` binary
+    #[serde(default = "default_binary")]
+    pub binary: String,
+    /// Remote root directory
+    #[serde(default)]
+    pub root: Option<PathBuf>,
+}
+
+fn default_binary() -> String {
+    "nodekeeper".to_owned()
+}
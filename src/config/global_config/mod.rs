@@ -4,7 +4,7 @@ use std::path::Path;
 use anyhow::{anyhow, Context, Result};
 use broxus_util::serde_base64_array;
 use everscale_network::proto;
-use serde::{Deserialize, Deserializer};
+use serde::{Deserialize, Deserializer, Serialize};
 
 #[derive(Clone)]
 pub struct GlobalConfig {
@@ -16,6 +16,11 @@ impl GlobalConfig {
     pub const MAINNET: &'static str = include_str!("mainnet.json");
     pub const TESTNET: &'static str = include_str!("testnet.json");
 
+    pub const MAINNET_URL: &'static str =
+        "https://raw.githubusercontent.com/tonlabs/main.ton.dev/master/configs/ton-global.config.json";
+    pub const TESTNET_URL: &'static str =
+        "https://raw.githubusercontent.com/tonlabs/net.ton.dev/master/configs/ton-global.config.json";
+
     pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
         let file = std::fs::File::open(path).context("failed to open global config")?;
         let config = serde_json::from_reader(std::io::BufReader::new(file))
@@ -131,6 +136,117 @@ impl TryFrom<BlockIdJson> for ton_block::BlockIdExt {
     }
 }
 
+/// Changes between the stored global config and the latest one
+#[derive(Default, Serialize)]
+pub struct GlobalConfigUpdate {
+    /// Static DHT nodes which are missing in the stored config
+    pub added_dht_nodes: Vec<String>,
+    /// Static DHT nodes which are present only in the stored config (kept)
+    pub local_dht_nodes: Vec<String>,
+    /// Hardfork seqnos which are missing in the stored config
+    pub added_hardforks: Vec<i64>,
+    /// Whether the init block differs
+    pub init_block_changed: bool,
+}
+
+impl GlobalConfigUpdate {
+    pub fn is_empty(&self) -> bool {
+        self.added_dht_nodes.is_empty()
+            && self.added_hardforks.is_empty()
+            && !self.init_block_changed
+    }
+}
+
+/// Merges the latest global config into the stored one.
+///
+/// New static DHT nodes and hardforks are appended, custom local entries are preserved.
+pub fn merge_global_config(current: &str, latest: &str) -> Result<(String, GlobalConfigUpdate)> {
+    use serde_json::Value;
+
+    fn entries<'a>(config: &'a mut Value, path: &str) -> Result<&'a mut Vec<Value>> {
+        let entry = config
+            .pointer_mut(path)
+            .with_context(|| format!("`{path}` not found"))?;
+        if entry.is_null() {
+            *entry = Value::Array(Vec::new());
+        }
+        entry
+            .as_array_mut()
+            .with_context(|| format!("`{path}` is not an array"))
+    }
+
+    fn dht_node_id(node: &Value) -> String {
+        node.pointer("/id/key")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_owned()
+    }
+
+    // Ensure that both configs are valid and belong to the same network
+    let current_config = serde_json::from_str::<GlobalConfig>(current)?;
+    let latest_config = serde_json::from_str::<GlobalConfig>(latest)?;
+    anyhow::ensure!(
+        current_config.zero_state == latest_config.zero_state,
+        "latest global config is for a different network"
+    );
+
+    let mut config = serde_json::from_str::<Value>(current)?;
+    let mut latest = serde_json::from_str::<Value>(latest)?;
+    let mut update = GlobalConfigUpdate::default();
+
+    // Merge static DHT nodes
+    const DHT_NODES: &str = "/dht/static_nodes/nodes";
+    let latest_nodes = std::mem::take(entries(&mut latest, DHT_NODES)?);
+    let nodes = entries(&mut config, DHT_NODES)?;
+    for node in &*nodes {
+        let id = dht_node_id(node);
+        if !latest_nodes.iter().any(|latest| dht_node_id(latest) == id) {
+            update.local_dht_nodes.push(id);
+        }
+    }
+    for node in latest_nodes {
+        let id = dht_node_id(&node);
+        if !nodes.iter().any(|node| dht_node_id(node) == id) {
+            update.added_dht_nodes.push(id);
+            nodes.push(node);
+        }
+    }
+
+    // Merge hardforks
+    const HARDFORKS: &str = "/validator/hardforks";
+    if latest.pointer(HARDFORKS).is_some() {
+        let latest_hardforks = std::mem::take(entries(&mut latest, HARDFORKS)?);
+        if config.pointer(HARDFORKS).is_none() {
+            if let Some(validator) = config.get_mut("validator").and_then(Value::as_object_mut) {
+                validator.insert("hardforks".to_owned(), Value::Array(Vec::new()));
+            }
+        }
+        let hardforks = entries(&mut config, HARDFORKS)?;
+        for hardfork in latest_hardforks {
+            if !hardforks.contains(&hardfork) {
+                update
+                    .added_hardforks
+                    .push(hardfork["seqno"].as_i64().unwrap_or_default());
+                hardforks.push(hardfork);
+            }
+        }
+    }
+
+    // Update init block
+    const INIT_BLOCK: &str = "/validator/init_block";
+    if let Some(init_block) = latest.pointer(INIT_BLOCK) {
+        if config.pointer(INIT_BLOCK) != Some(init_block) {
+            if let Some(validator) = config.get_mut("validator").and_then(Value::as_object_mut) {
+                validator.insert("init_block".to_owned(), init_block.clone());
+                update.init_block_changed = true;
+            }
+        }
+    }
+
+    let config = serde_json::to_string_pretty(&config)?;
+    Ok((config, update))
+}
+
 fn require_type(ty: String, required: &'static str) -> Result<()> {
     if ty == required {
         Ok(())
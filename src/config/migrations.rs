@@ -0,0 +1,144 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+/// Current app config layout version
+pub const APP_CONFIG_VERSION: u32 = APP_CONFIG_MIGRATIONS.len() as u32;
+/// Current stored keys layout version
+pub const STORED_KEYS_VERSION: u32 = STORED_KEYS_MIGRATIONS.len() as u32;
+
+type Migration<T> = fn(&mut T) -> Result<()>;
+
+/// `i`-th migration upgrades the app config layout from version `i` to `i + 1`
+const APP_CONFIG_MIGRATIONS: &[Migration<toml::value::Table>] = &[
+    // 0 -> 1: `version` field introduced
+    |_| Ok(()),
+];
+
+/// `i`-th migration upgrades the keys file layout from version `i` to `i + 1`
+const STORED_KEYS_MIGRATIONS: &[Migration<serde_json::Map<String, serde_json::Value>>] = &[
+    // 0 -> 1: `version` field introduced
+    |_| Ok(()),
+];
+
+/// Upgrades the app config layout. Returns the original version if it was changed
+pub fn migrate_app_config(config: &mut toml::value::Table) -> Result<Option<u32>> {
+    let version = match config.get("version") {
+        None => 0,
+        Some(toml::Value::Integer(version)) => {
+            u32::try_from(*version).context("invalid app config version")?
+        }
+        Some(_) => anyhow::bail!("invalid app config version"),
+    };
+
+    if !apply("app config", config, version, APP_CONFIG_MIGRATIONS)? {
+        return Ok(None);
+    }
+    config.insert(
+        "version".to_owned(),
+        toml::Value::Integer(APP_CONFIG_VERSION as i64),
+    );
+    Ok(Some(version))
+}
+
+/// Upgrades the keys file layout. Returns the original version if it was changed
+pub fn migrate_stored_keys(
+    keys: &mut serde_json::Map<String, serde_json::Value>,
+) -> Result<Option<u32>> {
+    let version = match keys.get("version") {
+        None => 0,
+        Some(version) => version
+            .as_u64()
+            .and_then(|version| u32::try_from(version).ok())
+            .context("invalid keys file version")?,
+    };
+
+    if !apply("keys file", keys, version, STORED_KEYS_MIGRATIONS)? {
+        return Ok(None);
+    }
+    keys.insert("version".to_owned(), STORED_KEYS_VERSION.into());
+    Ok(Some(version))
+}
+
+/// Copies the original file next to it before rewriting
+pub fn backup(path: &Path, version: u32) -> Result<PathBuf> {
+    let mut backup = path.as_os_str().to_owned();
+    backup.push(format!(".v{version}.bak"));
+    let backup = PathBuf::from(backup);
+
+    std::fs::copy(path, &backup).context("failed to backup the original file")?;
+    Ok(backup)
+}
+
+fn apply<T>(name: &str, data: &mut T, version: u32, migrations: &[Migration<T>]) -> Result<bool> {
+    let latest = migrations.len() as u32;
+    anyhow::ensure!(
+        version <= latest,
+        "{name} version {version} is not supported (latest is {latest}), please update nodekeeper"
+    );
+
+    for (i, migration) in migrations.iter().enumerate().skip(version as usize) {
+        migration(data)
+            .with_context(|| format!("failed to migrate {name} to version {}", i + 1))?;
+    }
+    Ok(version < latest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn old_app_config_is_migrated() {
+        let mut config = toml::from_str::<toml::value::Table>(
+            r#"
+            [control]
+            server_address = "127.0.0.1:5031"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(migrate_app_config(&mut config).unwrap(), Some(0));
+        assert_eq!(
+            config.get("version"),
+            Some(&toml::Value::Integer(APP_CONFIG_VERSION as i64))
+        );
+        assert!(config.contains_key("control"));
+    }
+
+    #[test]
+    fn current_app_config_is_not_migrated() {
+        let mut config = toml::value::Table::new();
+        config.insert(
+            "version".to_owned(),
+            toml::Value::Integer(APP_CONFIG_VERSION as i64),
+        );
+
+        let original = config.clone();
+        assert_eq!(migrate_app_config(&mut config).unwrap(), None);
+        assert_eq!(config, original);
+    }
+
+    #[test]
+    fn newer_app_config_is_rejected() {
+        let mut config = toml::value::Table::new();
+        config.insert(
+            "version".to_owned(),
+            toml::Value::Integer(APP_CONFIG_VERSION as i64 + 1),
+        );
+        assert!(migrate_app_config(&mut config).is_err());
+
+        config.insert("version".to_owned(), toml::Value::Integer(-1));
+        assert!(migrate_app_config(&mut config).is_err());
+    }
+
+    #[test]
+    fn old_stored_keys_are_migrated() {
+        let mut keys = serde_json::Map::new();
+        keys.insert("secret".to_owned(), "00".repeat(32).into());
+
+        assert_eq!(migrate_stored_keys(&mut keys).unwrap(), Some(0));
+        assert_eq!(keys.get("version"), Some(&STORED_KEYS_VERSION.into()));
+        assert_eq!(migrate_stored_keys(&mut keys).unwrap(), None);
+    }
+}
@@ -1,12 +1,33 @@
+pub use self::address_book::{AddressBook, AddressBookEntry};
+pub(crate) use self::app_config::write_atomic;
 pub use self::app_config::{
-    AppConfig, AppConfigAdnl, AppConfigControl, AppConfigDePoolDeploymentParams,
-    AppConfigValidator, AppConfigValidatorDePool, AppConfigValidatorSingle, DePoolType,
+    ApiRole, AppConfig, AppConfigAdnl, AppConfigApi, AppConfigApiToken, AppConfigBackup,
+    AppConfigBalanceAlerts, AppConfigControl, AppConfigDePoolDeploymentParams,
+    AppConfigDePoolReactions, AppConfigDiskWatchdog, AppConfigExporter, AppConfigFailover,
+    AppConfigHook, AppConfigLogging, AppConfigMaintenance, AppConfigNetwork,
+    AppConfigNetworksIndex, AppConfigNodeLogs, AppConfigNotificationChannel,
+    AppConfigNotifications, AppConfigPriceFeed, AppConfigProxyTopUp, AppConfigRecoveredStake,
+    AppConfigScheduledTask, AppConfigStakeStrategy, AppConfigTelemetry, AppConfigUpdates,
+    AppConfigValidator, AppConfigValidatorDePool, AppConfigValidatorSingle, AppConfigWatchdog,
+    BackupDestination, DePoolType, DiskWatchdogAction, FailoverRole, LogFormat, LogRotation,
+    NodeRole, NotificationSeverity, NotificationTarget, ScheduledTask, SystemAddresses,
 };
-pub use self::global_config::GlobalConfig;
-pub use self::node_config::{NodeConfig, NodeConfigAdnl, NodeConfigControlServer, NodeLogConfig};
+pub use self::fleet::{FleetHost, FleetHostApi, FleetHostSsh, FleetInventory};
+pub use self::global_config::{merge_global_config, GlobalConfig, GlobalConfigUpdate};
+pub use self::networks::{NetworkPreset, NetworksIndex};
+pub use self::node_config::{
+    NodeConfig, NodeConfigAdnl, NodeConfigControlServer, NodeConfigGc, NodeConfigMetrics,
+    NodeConsoleConfig, NodeConsoleControl, NodeLogConfig, NodeLogLevel,
+};
+pub use self::secret::Secret;
 pub use self::stored_keys::StoredKeys;
 
+mod address_book;
 mod app_config;
+mod fleet;
 mod global_config;
+mod migrations;
+mod networks;
 mod node_config;
+mod secret;
 mod stored_keys;
@@ -0,0 +1,135 @@
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use broxus_util::serde_hex_array;
+use everscale_crypto::ed25519;
+use serde::{Deserialize, Serialize};
+
+use super::GlobalConfig;
+
+/// Known networks metadata
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NetworksIndex {
+    /// Index version, older indices are rejected on update
+    pub version: u32,
+    pub networks: Vec<NetworkPreset>,
+}
+
+/// Network preset
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NetworkPreset {
+    /// Preset name which is used in the init template (e.g. `ever_mainnet`)
+    pub name: String,
+    /// Human readable network name
+    pub description: String,
+    #[serde(with = "serde_hex_array")]
+    pub zerostate_file_hash: [u8; 32],
+    /// Latest global config URL
+    pub global_config_url: reqwest::Url,
+    /// Recommended node repo (`<url> [-b <branch>] [-f <features>]`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub node_repo: Option<String>,
+    /// Native currency name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub currency: Option<String>,
+    /// Number of decimals of the native currency
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub decimals: Option<u8>,
+    /// Elector contract ABI URL
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub elector_abi_url: Option<reqwest::Url>,
+}
+
+impl NetworksIndex {
+    /// Presets which are embedded into the binary
+    pub fn builtin() -> Self {
+        fn preset(name: &str, description: &str, config: &str, url: &str) -> NetworkPreset {
+            let config = serde_json::from_str::<GlobalConfig>(config).unwrap();
+            NetworkPreset {
+                name: name.to_owned(),
+                description: description.to_owned(),
+                zerostate_file_hash: *config.zero_state.file_hash.as_array(),
+                global_config_url: url.parse().unwrap(),
+                node_repo: None,
+                currency: None,
+                decimals: None,
+                elector_abi_url: None,
+            }
+        }
+
+        Self {
+            version: 0,
+            networks: vec![
+                preset(
+                    "ever_mainnet",
+                    "Everscale mainnet",
+                    GlobalConfig::MAINNET,
+                    GlobalConfig::MAINNET_URL,
+                ),
+                preset(
+                    "ever_testnet",
+                    "Everscale testnet",
+                    GlobalConfig::TESTNET,
+                    GlobalConfig::TESTNET_URL,
+                ),
+            ],
+        }
+    }
+
+    /// Loads the refreshed index merged with the builtin presets
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let mut index = match Self::load_cached(path)? {
+            Some(index) => index,
+            None => return Ok(Self::builtin()),
+        };
+
+        for preset in Self::builtin().networks {
+            if index.find_by_name(&preset.name).is_none() {
+                index.networks.push(preset);
+            }
+        }
+        Ok(index)
+    }
+
+    /// Loads the refreshed index if it exists
+    pub fn load_cached<P: AsRef<Path>>(path: P) -> Result<Option<Self>> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let data = std::fs::read_to_string(path).context("failed to read networks index")?;
+        let index = serde_json::from_str(&data).context("invalid networks index")?;
+        Ok(Some(index))
+    }
+
+    /// Parses the downloaded index and checks its signature
+    pub fn from_signed(
+        data: &[u8],
+        signature: &[u8; 64],
+        public_key: &ed25519::PublicKey,
+    ) -> Result<Self> {
+        anyhow::ensure!(
+            public_key.verify_raw(data, signature),
+            "networks index signature mismatch"
+        );
+        serde_json::from_slice(data).context("invalid networks index")
+    }
+
+    pub fn store<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let data = serde_json::to_string_pretty(self).context("failed to serialize index")?;
+        std::fs::write(path, data).context("failed to save networks index")
+    }
+
+    pub fn find_by_name(&self, name: &str) -> Option<&NetworkPreset> {
+        self.networks.iter().find(|preset| preset.name == name)
+    }
+
+    pub fn find_by_zerostate(&self, zerostate_file_hash: &[u8; 32]) -> Option<&NetworkPreset> {
+        self.networks
+            .iter()
+            .find(|preset| &preset.zerostate_file_hash == zerostate_file_hash)
+    }
+}
@@ -1,3 +1,4 @@
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::net::SocketAddrV4;
 use std::path::{Path, PathBuf};
@@ -8,17 +9,61 @@ use broxus_util::serde_base64_array;
 use everscale_crypto::ed25519;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
-pub struct NodeLogConfig(&'static str);
+use super::NodeRole;
+
+pub struct NodeLogConfig(Cow<'static, str>);
 
 impl NodeLogConfig {
     const TEMPLATE: &'static str = include_str!("log_cfg.yml");
 
     pub fn generate() -> Self {
-        Self(Self::TEMPLATE)
+        Self(Cow::Borrowed(Self::TEMPLATE))
+    }
+
+    /// Generates log config with the specified level for the node modules
+    pub fn generate_with_level(level: NodeLogLevel) -> Self {
+        if level == NodeLogLevel::Info {
+            return Self::generate();
+        }
+        let config = Self::TEMPLATE.replace("level: info", &format!("level: {level}"));
+        Self(Cow::Owned(config))
     }
 
     pub fn store<P: AsRef<Path>>(&self, path: P) -> Result<()> {
-        std::fs::write(path, self.0).context("failed to write node log config")
+        std::fs::write(path, self.0.as_ref()).context("failed to write node log config")
+    }
+}
+
+#[derive(Default, Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NodeLogLevel {
+    Error,
+    Warn,
+    #[default]
+    Info,
+    Debug,
+    Trace,
+}
+
+impl NodeLogLevel {
+    pub const ALL: [Self; 5] = [
+        Self::Error,
+        Self::Warn,
+        Self::Info,
+        Self::Debug,
+        Self::Trace,
+    ];
+}
+
+impl std::fmt::Display for NodeLogLevel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Error => "error",
+            Self::Warn => "warn",
+            Self::Info => "info",
+            Self::Debug => "debug",
+            Self::Trace => "trace",
+        })
     }
 }
 
@@ -33,6 +78,11 @@ impl NodeConfig {
     const ADNL_NODE: &'static str = "adnl_node";
     const GLOBAL_CONFIG_PATH: &'static str = "ton_global_config_name";
     const INTERNAL_DB_PATH: &'static str = "internal_db_path";
+    const GC: &'static str = "gc";
+    const CELLS_DB_CONFIG: &'static str = "cells_db_config";
+    const CELLS_CACHE_SIZE: &'static str = "cache_size_bytes";
+    const METRICS: &'static str = "metrics";
+    const INIT_MC_BLOCK: &'static str = "init_mc_block";
 
     const TEMPLATE: &'static str = include_str!("default_config.json");
 
@@ -91,6 +141,78 @@ impl NodeConfig {
         self.set_field(Self::CONTROL_SERVER, node)
     }
 
+    pub fn get_gc(&self) -> Result<Option<NodeConfigGc>> {
+        self.get_field(Self::GC)
+    }
+
+    pub fn set_gc(&mut self, gc: &NodeConfigGc) -> Result<()> {
+        self.set_field(Self::GC, gc)
+    }
+
+    pub fn get_cells_cache_size(&self) -> Result<Option<u64>> {
+        match self.0.get(Self::CELLS_DB_CONFIG) {
+            Some(section) => match section.get(Self::CELLS_CACHE_SIZE).cloned() {
+                Some(value) => Ok(serde_json::from_value(value)?),
+                None => Ok(None),
+            },
+            None => Ok(None),
+        }
+    }
+
+    pub fn set_cells_cache_size(&mut self, bytes: u64) -> Result<()> {
+        let config = self
+            .0
+            .as_object_mut()
+            .ok_or(NodeConfigError::InvalidConfig)?;
+        let section = config
+            .entry(Self::CELLS_DB_CONFIG)
+            .or_insert_with(|| serde_json::Value::Object(Default::default()))
+            .as_object_mut()
+            .ok_or(NodeConfigError::InvalidConfig)?;
+        section.insert(Self::CELLS_CACHE_SIZE.to_owned(), bytes.into());
+        Ok(())
+    }
+
+    pub fn get_metrics(&self) -> Result<Option<NodeConfigMetrics>> {
+        self.get_field(Self::METRICS)
+    }
+
+    /// Enables or disables (if `None`) the node metrics endpoint
+    pub fn set_metrics(&mut self, metrics: Option<&NodeConfigMetrics>) -> Result<()> {
+        match metrics {
+            Some(metrics) => self.set_field(Self::METRICS, metrics),
+            None => {
+                let config = self
+                    .0
+                    .as_object_mut()
+                    .ok_or(NodeConfigError::InvalidConfig)?;
+                config.remove(Self::METRICS);
+                Ok(())
+            }
+        }
+    }
+
+    /// Adjusts GC and sync settings for the specified node role
+    pub fn apply_role(&mut self, role: NodeRole) -> Result<()> {
+        let mut gc = self.get_gc()?.unwrap_or_default();
+        gc.set_archives_life_time(role.archives_life_time_hours());
+        gc.enable_for_shard_state_persistent = role != NodeRole::Archive;
+        gc.cells_gc_config.cells_lifetime_sec = role.cells_lifetime_sec();
+        self.set_gc(&gc)?;
+
+        let config = self
+            .0
+            .as_object_mut()
+            .ok_or(NodeConfigError::InvalidConfig)?;
+        match role {
+            // Archive node must sync all blocks since the zerostate
+            NodeRole::Archive => config.insert(Self::INIT_MC_BLOCK.to_owned(), 0.into()),
+            // Other nodes start from the latest key block
+            NodeRole::Validator | NodeRole::Full => config.remove(Self::INIT_MC_BLOCK),
+        };
+        Ok(())
+    }
+
     fn get_field<D>(&self, field: &str) -> Result<Option<D>>
     where
         for<'de> D: Deserialize<'de>,
@@ -164,6 +286,9 @@ pub struct NodeConfigAdnl {
 impl NodeConfigAdnl {
     pub const DHT_TAG: usize = 1;
     pub const OVERLAY_TAG: usize = 2;
+    /// Tags of the previous keys which are still served after rotation
+    pub const RETIRED_DHT_TAG: usize = 101;
+    pub const RETIRED_OVERLAY_TAG: usize = 102;
 
     pub fn from_addr_and_keys(addr: SocketAddrV4, keys: Keys) -> Self {
         Self {
@@ -194,10 +319,130 @@ impl NodeConfigAdnl {
             .context("overlay key not found")
             .context("invalid ADNL node config")
     }
+
+    pub fn has_retired_keys(&self) -> bool {
+        self.keys.contains_key(&Self::RETIRED_DHT_TAG)
+            || self.keys.contains_key(&Self::RETIRED_OVERLAY_TAG)
+    }
+
+    /// Generates new keys and keeps the previous ones under the retired tags
+    pub fn rotate_keys(&mut self) {
+        let mut keys = Self::generate_keys();
+        for (tag, retired_tag) in [
+            (Self::DHT_TAG, Self::RETIRED_DHT_TAG),
+            (Self::OVERLAY_TAG, Self::RETIRED_OVERLAY_TAG),
+        ] {
+            if let Some(key) = self.keys.remove(&tag) {
+                keys.insert(retired_tag, key);
+            }
+        }
+        self.keys = keys;
+    }
+
+    /// Removes the previous keys. Returns whether there were any
+    pub fn remove_retired_keys(&mut self) -> bool {
+        let dht = self.keys.remove(&Self::RETIRED_DHT_TAG);
+        let overlay = self.keys.remove(&Self::RETIRED_OVERLAY_TAG);
+        dht.is_some() || overlay.is_some()
+    }
 }
 
 pub type Keys = HashMap<usize, ed25519::SecretKey>;
 
+#[derive(Clone, Serialize, Deserialize)]
+pub struct NodeConfigGc {
+    pub enable_for_archives: bool,
+    /// `None` means that archives are kept forever
+    pub archives_life_time_hours: Option<u32>,
+    pub enable_for_shard_state_persistent: bool,
+    pub cells_gc_config: NodeConfigCellsGc,
+}
+
+impl NodeConfigGc {
+    /// Sets archives lifetime, `None` disables archives GC
+    pub fn set_archives_life_time(&mut self, hours: Option<u32>) {
+        self.enable_for_archives = hours.is_some();
+        self.archives_life_time_hours = hours;
+    }
+}
+
+impl Default for NodeConfigGc {
+    fn default() -> Self {
+        Self {
+            enable_for_archives: true,
+            archives_life_time_hours: None,
+            enable_for_shard_state_persistent: true,
+            cells_gc_config: NodeConfigCellsGc::default(),
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct NodeConfigCellsGc {
+    pub gc_interval_sec: u32,
+    pub cells_lifetime_sec: u32,
+}
+
+impl Default for NodeConfigCellsGc {
+    fn default() -> Self {
+        Self {
+            gc_interval_sec: 900,
+            cells_lifetime_sec: 1800,
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct NodeConfigMetrics {
+    pub address: SocketAddrV4,
+    #[serde(default)]
+    pub global_labels: HashMap<String, String>,
+}
+
+/// Config of the console tool which is shipped with the node
+#[derive(Serialize, Deserialize)]
+pub struct NodeConsoleConfig {
+    pub config: NodeConsoleControl,
+    /// Validator wallet address
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wallet_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_factor: Option<f64>,
+}
+
+impl NodeConsoleConfig {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let data = std::fs::read_to_string(path).context("failed to read console config")?;
+        serde_json::from_str(&data).context("failed to deserialize console config")
+    }
+
+    /// Stores the config which is readable only by the current user
+    pub fn store<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        use std::io::Write;
+        use std::os::unix::fs::OpenOptionsExt;
+
+        let data =
+            serde_json::to_string_pretty(self).context("failed to serialize console config")?;
+        std::fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .mode(0o600)
+            .open(path)
+            .and_then(|mut file| file.write_all(data.as_bytes()))
+            .context("failed to write console config")
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct NodeConsoleControl {
+    pub server_address: SocketAddrV4,
+    #[serde(with = "serde_node_public_key")]
+    pub server_key: ed25519::PublicKey,
+    #[serde(with = "serde_node_secret_key")]
+    pub client_key: ed25519::SecretKey,
+}
+
 mod serde_control_clients {
     use super::*;
 
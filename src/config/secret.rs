@@ -0,0 +1,115 @@
+use anyhow::{Context, Result};
+use everscale_crypto::ed25519;
+use serde::de::IntoDeserializer;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::util::serde_secret_key;
+
+/// Config value which can be specified inline or as a reference
+/// to an env variable (`env:NAME`) or a file (`file:/run/secrets/name`).
+///
+/// References are resolved on load and preserved on store.
+#[derive(Clone)]
+pub struct Secret<T> {
+    value: T,
+    reference: Option<String>,
+}
+
+impl<T> Secret<T> {
+    pub fn new(value: T) -> Self {
+        Self {
+            value,
+            reference: None,
+        }
+    }
+
+    /// Whether the value is loaded from an `env:` or `file:` reference
+    pub fn is_reference(&self) -> bool {
+        self.reference.is_some()
+    }
+}
+
+impl<T> std::ops::Deref for Secret<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.value
+    }
+}
+
+impl<T: SecretValue> Serialize for Secret<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match &self.reference {
+            Some(reference) => serializer.serialize_str(reference),
+            None => serializer.serialize_str(&self.value.to_secret_string()),
+        }
+    }
+}
+
+impl<'de, T: SecretValue> Deserialize<'de> for Secret<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error;
+
+        let value = String::deserialize(deserializer)?;
+        let (value, reference) = match resolve_reference(&value).map_err(Error::custom)? {
+            Some(resolved) => (resolved, Some(value)),
+            None => (value, None),
+        };
+
+        Ok(Self {
+            value: T::from_secret_str(&value).map_err(Error::custom)?,
+            reference,
+        })
+    }
+}
+
+fn resolve_reference(value: &str) -> Result<Option<String>> {
+    if let Some(name) = value.strip_prefix("env:") {
+        let value = std::env::var(name)
+            .with_context(|| format!("secret env variable `{name}` not found"))?;
+        Ok(Some(value))
+    } else if let Some(path) = value.strip_prefix("file:") {
+        let value = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read secret file `{path}`"))?;
+        Ok(Some(value.trim_end().to_owned()))
+    } else {
+        Ok(None)
+    }
+}
+
+pub trait SecretValue: Sized {
+    fn from_secret_str(s: &str) -> Result<Self>;
+
+    fn to_secret_string(&self) -> String;
+}
+
+impl SecretValue for String {
+    fn from_secret_str(s: &str) -> Result<Self> {
+        Ok(s.to_owned())
+    }
+
+    fn to_secret_string(&self) -> String {
+        self.clone()
+    }
+}
+
+impl SecretValue for reqwest::Url {
+    fn from_secret_str(s: &str) -> Result<Self> {
+        s.parse().context("invalid url")
+    }
+
+    fn to_secret_string(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl SecretValue for ed25519::SecretKey {
+    fn from_secret_str(s: &str) -> Result<Self> {
+        serde_secret_key::deserialize(s.into_deserializer())
+            .map_err(|e: serde::de::value::Error| anyhow::anyhow!("{e}"))
+    }
+
+    fn to_secret_string(&self) -> String {
+        hex::encode(self.as_bytes())
+    }
+}
@@ -4,6 +4,7 @@ use anyhow::{Context, Result};
 use broxus_util::{serde_hex_array, serde_optional_hex_array};
 use serde::{Deserialize, Serialize};
 
+use super::migrations;
 use crate::crypto::*;
 
 #[derive(Serialize)]
@@ -70,13 +71,40 @@ impl StoredKeys {
                 pub public: Option<[u8; 32]>,
                 #[serde(default)]
                 pub seed: Option<String>,
+                #[serde(default, rename = "version")]
+                pub _version: u32,
             }
 
             let file = std::fs::File::open(path).context("failed to open keys file")?;
-            let mut deserializer =
-                serde_json::Deserializer::from_reader(std::io::BufReader::new(file));
-            let data: StoredKeysHelper = serde_path_to_error::deserialize(&mut deserializer)
-                .context("failed to parse keys")?;
+            let mut keys: serde_json::Map<String, serde_json::Value> =
+                serde_json::from_reader(std::io::BufReader::new(file))
+                    .context("failed to parse keys")?;
+
+            if let Some(version) = migrations::migrate_stored_keys(&mut keys)? {
+                // NOTE: migrated keys are still usable when the file can't be rewritten
+                let to = migrations::STORED_KEYS_VERSION;
+                match migrations::backup(path, version)
+                    .and_then(|backup| write_keys(path, keys.clone()).map(|_| backup))
+                {
+                    Ok(backup) => tracing::warn!(
+                        path = %path.display(),
+                        from = version,
+                        to,
+                        backup = %backup.display(),
+                        "migrated keys file"
+                    ),
+                    Err(e) => tracing::warn!(
+                        path = %path.display(),
+                        from = version,
+                        to,
+                        "failed to store migrated keys file: {e:?}"
+                    ),
+                }
+            }
+
+            let data: StoredKeysHelper =
+                serde_path_to_error::deserialize(serde_json::Value::Object(keys))
+                    .context("failed to parse keys")?;
 
             if let Some(secret) = data.secret {
                 Ok(StoredKeys {
@@ -95,8 +123,12 @@ impl StoredKeys {
     }
 
     pub fn store<P: AsRef<Path>>(&self, path: P) -> Result<()> {
-        let data = serde_json::to_string_pretty(self).context("failed to serialize keys")?;
-        std::fs::write(path, data).context("failed to save keys")
+        let mut keys = match serde_json::to_value(self).context("failed to serialize keys")? {
+            serde_json::Value::Object(keys) => keys,
+            _ => anyhow::bail!("unexpected keys structure"),
+        };
+        keys.insert("version".to_owned(), migrations::STORED_KEYS_VERSION.into());
+        write_keys(path.as_ref(), keys)
     }
 
     pub fn as_secret(&self) -> ed25519_dalek::SecretKey {
@@ -109,3 +141,8 @@ impl StoredKeys {
         ed25519_dalek::Keypair { secret, public }
     }
 }
+
+fn write_keys(path: &Path, keys: serde_json::Map<String, serde_json::Value>) -> Result<()> {
+    let data = serde_json::to_string_pretty(&keys).context("failed to serialize keys")?;
+    std::fs::write(path, data).context("failed to save keys")
+}
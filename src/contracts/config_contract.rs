@@ -0,0 +1,198 @@
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use broxus_util::now;
+
+use crate::network::Subscription;
+
+/// Blockchain config contract (proposals and votes)
+pub struct ConfigContract {
+    address: ton_block::MsgAddressInt,
+    subscription: Arc<Subscription>,
+}
+
+impl ConfigContract {
+    pub fn new(address: ton_types::UInt256, subscription: Arc<Subscription>) -> Self {
+        let address = ton_block::MsgAddressInt::AddrStd(ton_block::MsgAddrStd {
+            anycast: None,
+            workchain_id: -1,
+            address: address.into(),
+        });
+
+        Self {
+            address,
+            subscription,
+        }
+    }
+
+    /// Returns active config proposals
+    pub async fn get_proposals(&self) -> Result<Vec<ConfigProposal>> {
+        let state = self
+            .subscription
+            .get_account_state(&self.address)
+            .await
+            .context("failed to get config contract state")?
+            .context("config contract not found")?;
+        parse_proposals(&state)
+    }
+
+    /// Prepares an external message with the vote for the proposal signed with the validator key.
+    ///
+    /// Returns the message and the time until which it is waited for.
+    pub async fn vote_for_proposal(
+        &self,
+        validator_idx: u16,
+        validator_key_hash: &[u8; 32],
+        proposal_hash: &ton_types::UInt256,
+        signature_id: Option<i32>,
+    ) -> Result<(ton_block::Message, u32)> {
+        use ton_types::IBitstring;
+
+        const OP_VOTE_FOR_PROPOSAL: u32 = 0x566f7445;
+        const TIMEOUT: u32 = 60;
+
+        let mut data = Vec::with_capacity(4 + 2 + 32);
+        data.extend_from_slice(&OP_VOTE_FOR_PROPOSAL.to_be_bytes());
+        data.extend_from_slice(&validator_idx.to_be_bytes());
+        data.extend_from_slice(proposal_hash.as_slice());
+
+        let data_to_sign = ton_abi::extend_signature_with_id(&data, signature_id);
+        let signature = self
+            .subscription
+            .tcp_rpc()?
+            .sign(validator_key_hash, &data_to_sign)
+            .await
+            .context("failed to sign proposal vote")?;
+
+        let mut body = ton_types::BuilderData::new();
+        body.append_raw(&signature, signature.len() * 8)?
+            .append_raw(&data, data.len() * 8)?;
+
+        let mut message =
+            ton_block::Message::with_ext_in_header(ton_block::ExternalInboundMessageHeader {
+                dst: self.address.clone(),
+                ..Default::default()
+            });
+        message.set_body(ton_types::SliceData::load_builder(body)?);
+
+        Ok((message, now() + TIMEOUT))
+    }
+}
+
+/// Active config proposal
+pub struct ConfigProposal {
+    /// Proposal cell hash
+    pub hash: ton_types::UInt256,
+    pub expires_at: u32,
+    pub param_id: i32,
+    /// New param value (`None` removes the param)
+    pub param_value: Option<ton_types::Cell>,
+    /// Expected hash of the current param value
+    pub if_hash_equal: Option<ton_types::UInt256>,
+    pub is_critical: bool,
+    /// Indices of the validators which voted in the current round
+    pub voters: Vec<u16>,
+    pub weight_remaining: i64,
+    /// Hash of the validator set of the current voting round
+    pub vset_id: ton_types::UInt256,
+    pub rounds_remaining: u8,
+    pub wins: u8,
+    pub losses: u8,
+}
+
+impl ConfigProposal {
+    /// Decodes the proposed param value
+    pub fn decode_value(&self) -> Result<Option<ton_block::ConfigParamEnum>> {
+        let (Some(value), Ok(param_id)) = (&self.param_value, u32::try_from(self.param_id)) else {
+            return Ok(None);
+        };
+
+        let mut slice = ton_types::SliceData::load_cell(value.clone())?;
+        ton_block::ConfigParamEnum::construct_from_slice_and_number(&mut slice, param_id)
+            .map(Some)
+            .context("failed to decode proposed param value")
+    }
+}
+
+fn parse_proposals(state: &ton_block::AccountStuff) -> Result<Vec<ConfigProposal>> {
+    use ton_types::HashmapType;
+
+    const PROPOSAL_STATUS_TAG: u8 = 0xce;
+    const PROPOSAL_TAG: u8 = 0xf3;
+
+    let ton_block::AccountState::AccountActive { state_init } = &state.storage.state else {
+        anyhow::bail!("config contract is not active");
+    };
+    let data = state_init
+        .data
+        .clone()
+        .context("config contract data is empty")?;
+    let mut data = ton_types::SliceData::load_cell(data)?;
+
+    // cfg_dict:^Cell stored_seqno:uint32 public_key:uint256
+    data.checked_drain_reference()?;
+    data.move_by(32 + 256)?;
+    // vote_dict:(HashmapE 256 ConfigProposalStatus)
+    let vote_dict = ton_types::HashmapE::with_hashmap(256, data.get_next_dictionary()?);
+
+    let mut statuses = Vec::new();
+    vote_dict.iterate_slices(|mut key, value| {
+        statuses.push((key.get_next_hash()?, value));
+        Ok(true)
+    })?;
+
+    let mut result = Vec::with_capacity(statuses.len());
+    for (hash, mut status) in statuses {
+        anyhow::ensure!(
+            status.get_next_byte()? == PROPOSAL_STATUS_TAG,
+            "invalid proposal status"
+        );
+        let expires_at = status.get_next_u32()?;
+        let mut proposal = ton_types::SliceData::load_cell(status.checked_drain_reference()?)?;
+        let is_critical = status.get_next_bit()?;
+        let voters = ton_types::HashmapE::with_hashmap(16, status.get_next_dictionary()?);
+        let weight_remaining = status.get_next_i64()?;
+        let vset_id = status.get_next_hash()?;
+        let rounds_remaining = status.get_next_byte()?;
+        let wins = status.get_next_byte()?;
+        let losses = status.get_next_byte()?;
+
+        let mut voter_ids = Vec::new();
+        voters.iterate_slices(|mut key, _| {
+            voter_ids.push(key.get_next_u16()?);
+            Ok(true)
+        })?;
+
+        anyhow::ensure!(
+            proposal.get_next_byte()? == PROPOSAL_TAG,
+            "invalid proposal"
+        );
+        let param_id = proposal.get_next_i32()?;
+        let param_value = match proposal.get_next_bit()? {
+            true => Some(proposal.checked_drain_reference()?),
+            false => None,
+        };
+        let if_hash_equal = match proposal.get_next_bit()? {
+            true => Some(proposal.get_next_hash()?),
+            false => None,
+        };
+
+        result.push(ConfigProposal {
+            hash,
+            expires_at,
+            param_id,
+            param_value,
+            if_hash_equal,
+            is_critical,
+            voters: voter_ids,
+            weight_remaining,
+            vset_id,
+            rounds_remaining,
+            wins,
+            losses,
+        });
+    }
+
+    result.sort_by_key(|proposal| proposal.expires_at);
+    Ok(result)
+}
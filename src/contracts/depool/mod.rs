@@ -3,8 +3,8 @@ use std::sync::Arc;
 
 use anyhow::{Context, Result};
 use nekoton_abi::{
-    BuildTokenValue, FunctionBuilder, FunctionExt, KnownParamType, KnownParamTypePlain,
-    PackAbiPlain, TokenValueExt, UnpackAbi, UnpackAbiPlain, UnpackFirst,
+    BuildTokenValue, EventBuilder, FunctionBuilder, FunctionExt, KnownParamType,
+    KnownParamTypePlain, PackAbiPlain, TokenValueExt, UnpackAbi, UnpackAbiPlain, UnpackFirst,
 };
 use nekoton_utils::SimpleClock;
 use num::ToPrimitive;
@@ -98,7 +98,7 @@ impl DePool {
         .pack();
 
         self.subscription
-            .send_message_with_retires(move |timeout, signature_id| {
+            .send_message_reliable(move |timeout, signature_id| {
                 let (expire_at, header) = make_default_headers(None, timeout);
 
                 let mut message = self.external_message_to_self(
@@ -123,6 +123,41 @@ impl DePool {
         Ok(())
     }
 
+    /// Decreases the validator reward fraction (signed with DePool keys)
+    pub async fn set_validator_reward_fraction(&self, fraction: u8) -> Result<()> {
+        let keypair = self.keypair.as_ref().context("DePool keypair not set")?;
+
+        let inputs = [fraction.token_value().named("fraction")];
+
+        self.subscription
+            .send_message_reliable(move |timeout, signature_id| {
+                let (expire_at, header) = make_default_headers(None, timeout);
+
+                let message = self.external_message_to_self(
+                    common::set_validator_reward_fraction()
+                        .encode_input(
+                            &header,
+                            &inputs,
+                            false,
+                            Some((keypair, signature_id)),
+                            Some(self.address.clone()),
+                        )
+                        .context("failed to encode setValidatorRewardFraction")?,
+                )?;
+
+                Ok((message, expire_at))
+            })
+            .await
+            .context("failed to set validator reward fraction")?;
+
+        Ok(())
+    }
+
+    /// Replenishes the DePool balance
+    pub fn receive_funds(&self, amount: u128) -> Result<InternalMessage> {
+        self.internal_message_to_self(amount, common::receive_funds().encode_internal_input(&[])?)
+    }
+
     pub fn ticktock(&self) -> Result<InternalMessage> {
         self.internal_message_to_self(ONE_EVER, common::ticktock().encode_internal_input(&[])?)
     }
@@ -155,18 +190,59 @@ impl DePool {
         if depool_balance <= critical_balance {
             let remaining = num::BigInt::from(Self::INITIAL_BALANCE) - depool_balance;
             if let Some(remaining) = remaining.to_u128() {
-                messages.push(self.internal_message_to_self(
-                    remaining,
-                    common::receive_funds().encode_internal_input(&[])?,
-                )?);
+                messages.push(self.receive_funds(remaining)?);
             }
         }
 
         // Check proxies
-        for proxy in depool_info.proxies {
+        messages.extend(
+            self.top_up_proxies(
+                &depool_info.proxies,
+                &storage_prices,
+                Self::MIN_PROXY_BALANCE,
+            )
+            .await?,
+        );
+
+        Ok(messages)
+    }
+
+    /// Prepares messages to replenish proxies with balance below `min_balance`
+    /// (plus the storage fee).
+    pub async fn get_proxies_top_up(
+        &self,
+        config: &ton_block::ConfigParams,
+        min_balance: u128,
+    ) -> Result<Vec<InternalMessage>> {
+        let storage_prices = StoragePrices::new(config)?;
+
+        let account = self
+            .subscription
+            .get_account_state(&self.address)
+            .await?
+            .context("DePool not deployed")?;
+
+        let depool_info = DePoolState {
+            state: &account,
+            ty: self.ty,
+        }
+        .get_info()?;
+
+        self.top_up_proxies(&depool_info.proxies, &storage_prices, min_balance)
+            .await
+    }
+
+    async fn top_up_proxies(
+        &self,
+        proxies: &[ton_block::MsgAddressInt],
+        storage_prices: &StoragePrices,
+        min_balance: u128,
+    ) -> Result<Vec<InternalMessage>> {
+        let mut messages = Vec::new();
+        for proxy in proxies {
             let account = self
                 .subscription
-                .get_account_state(&proxy)
+                .get_account_state(proxy)
                 .await
                 .context("failed to get proxy state")?
                 .context("proxy not deployed")?;
@@ -176,7 +252,7 @@ impl DePool {
                 proxy.is_masterchain(),
                 broxus_util::now(),
             );
-            let target_balance = Self::MIN_PROXY_BALANCE + fee;
+            let target_balance = min_balance + fee;
 
             let proxy_balance = match account.storage.state {
                 ton_block::AccountState::AccountActive { .. } => {
@@ -192,10 +268,10 @@ impl DePool {
 
             if let Some(mut required_amount) = target_balance.checked_sub(proxy_balance) {
                 // Topup a twice more to reduce the number of messages
-                required_amount += Self::MIN_PROXY_BALANCE;
+                required_amount += min_balance;
                 messages.push(InternalMessage {
                     amount: required_amount,
-                    dst: proxy,
+                    dst: proxy.clone(),
                     payload: Default::default(),
                     bounce: false,
                 });
@@ -254,6 +330,13 @@ impl DePool {
         DePoolState { state, ty: self.ty }.get_rounds()
     }
 
+    pub fn get_participants(
+        &self,
+        state: &ton_block::AccountStuff,
+    ) -> Result<Vec<ton_block::MsgAddressInt>> {
+        DePoolState { state, ty: self.ty }.get_participants()
+    }
+
     pub fn get_allowed_participants(
         &self,
         state: &ton_block::AccountStuff,
@@ -377,6 +460,25 @@ impl DePoolState<'_> {
         Ok(rounds)
     }
 
+    pub fn get_participants(&self) -> Result<Vec<ton_block::MsgAddressInt>> {
+        let tokens = self.run_local(common::get_participants(), &[])?;
+        match tokens.into_iter().next() {
+            Some(ton_abi::Token {
+                value: ton_abi::TokenValue::Array(_, items),
+                ..
+            }) => items
+                .into_iter()
+                .map(|item| match item {
+                    ton_abi::TokenValue::Address(ton_block::MsgAddress::AddrStd(addr)) => {
+                        Ok(ton_block::MsgAddressInt::AddrStd(addr))
+                    }
+                    _ => Err(nekoton_abi::UnpackerError::InvalidAbi.into()),
+                })
+                .collect(),
+            _ => Err(nekoton_abi::UnpackerError::InvalidAbi.into()),
+        }
+    }
+
     pub fn get_allowed_participants(&self) -> Result<Vec<ton_block::MsgAddressInt>> {
         self.ensure_stever()?;
         let addresses: stever::ParticipantsMap = self
@@ -604,6 +706,119 @@ pub enum CompletionReason {
     NoValidatorRequest = 8,
 }
 
+/// Known DePool events
+#[derive(Debug)]
+pub enum DePoolEvent {
+    /// DePool waits for the election request from the validator
+    StakeSigningRequested {
+        election_id: u32,
+        proxy: ton_block::MsgAddressInt,
+    },
+    /// DePool balance is not enough to continue operation
+    TooLowDePoolBalance { replenishment: u128 },
+    /// Round was completed
+    RoundCompleted(Round),
+}
+
+impl DePoolEvent {
+    /// Parses known events from the transaction outgoing messages
+    pub fn parse_transaction(tx: &ton_block::Transaction) -> Result<Vec<Self>> {
+        let mut result = Vec::new();
+        tx.out_msgs.iterate(|ton_block::InRefValue(msg)| {
+            match Self::parse_message(&msg) {
+                Ok(Some(event)) => result.push(event),
+                Ok(None) => {}
+                Err(e) => tracing::warn!("failed to parse DePool event: {e:?}"),
+            }
+            Ok(true)
+        })?;
+        Ok(result)
+    }
+
+    fn parse_message(msg: &ton_block::Message) -> Result<Option<Self>> {
+        if !matches!(msg.header(), ton_block::CommonMsgInfo::ExtOutMsgInfo(_)) {
+            return Ok(None);
+        }
+
+        let Some(mut body) = msg.body() else {
+            return Ok(None);
+        };
+
+        let Ok(event_id) = body.get_next_u32() else {
+            return Ok(None);
+        };
+
+        let decode = |event: &ton_abi::Event| {
+            ton_abi::TokenValue::decode_params(
+                &event.inputs,
+                body.clone(),
+                &event.abi_version,
+                false,
+            )
+        };
+
+        let event = events::stake_signing_requested();
+        if event_id == event.id {
+            let events::StakeSigningRequested { election_id, proxy } = decode(event)?.unpack()?;
+            return Ok(Some(Self::StakeSigningRequested { election_id, proxy }));
+        }
+
+        let event = events::too_low_depool_balance();
+        if event_id == event.id {
+            let replenishment: num::BigUint = decode(event)?.unpack_first()?;
+            let replenishment = replenishment.to_u128().unwrap_or(u128::MAX);
+            return Ok(Some(Self::TooLowDePoolBalance { replenishment }));
+        }
+
+        let event = events::round_completed();
+        if event_id == event.id {
+            let round: Round = decode(event)?.unpack_first()?;
+            return Ok(Some(Self::RoundCompleted(round)));
+        }
+
+        Ok(None)
+    }
+}
+
+mod events {
+    use super::*;
+
+    #[derive(Clone, UnpackAbiPlain, KnownParamTypePlain)]
+    pub struct StakeSigningRequested {
+        #[abi(uint32)]
+        pub election_id: u32,
+        #[abi(address)]
+        pub proxy: ton_block::MsgAddressInt,
+    }
+
+    pub fn stake_signing_requested() -> &'static ton_abi::Event {
+        once!(ton_abi::Event, || {
+            EventBuilder::new("StakeSigningRequested")
+                .abi_version(ton_abi::contract::ABI_VERSION_2_0)
+                .inputs(StakeSigningRequested::param_type())
+                .build()
+        })
+    }
+
+    pub fn too_low_depool_balance() -> &'static ton_abi::Event {
+        once!(ton_abi::Event, || {
+            EventBuilder::new("TooLowDePoolBalance")
+                .abi_version(ton_abi::contract::ABI_VERSION_2_0)
+                .input("replenishment", ton_abi::ParamType::Uint(256))
+                .build()
+        })
+    }
+
+    pub fn round_completed() -> &'static ton_abi::Event {
+        once!(ton_abi::Event, || {
+            EventBuilder::new("RoundCompleted")
+                .abi_version(ton_abi::contract::ABI_VERSION_2_0)
+                .input("round", Round::param_type())
+                .build()
+        })
+    }
+}
+
 mod common {
     use super::*;
 
@@ -647,6 +862,16 @@ mod common {
         })
     }
 
+    pub fn set_validator_reward_fraction() -> &'static ton_abi::Function {
+        once!(ton_abi::Function, || {
+            FunctionBuilder::new("setValidatorRewardFraction")
+                .time_header()
+                .expire_header()
+                .input("fraction", u8::param_type())
+                .build()
+        })
+    }
+
     pub fn receive_funds() -> &'static ton_abi::Function {
         once!(ton_abi::Function, || {
             FunctionBuilder::new("receiveFunds").build()
@@ -695,6 +920,19 @@ mod common {
                 .build()
         })
     }
+
+    pub fn get_participants() -> &'static ton_abi::Function {
+        once!(ton_abi::Function, || {
+            FunctionBuilder::new("getParticipants")
+                .output(
+                    "participants",
+                    ton_abi::ParamType::Array(Box::new(ton_abi::ParamType::Address)),
+                )
+                .time_header()
+                .expire_header()
+                .build()
+        })
+    }
 }
 
 mod stever {
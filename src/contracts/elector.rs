@@ -2,11 +2,12 @@ use std::collections::BTreeMap;
 use std::sync::Arc;
 
 use anyhow::{Context, Result};
-use broxus_util::now;
+use broxus_util::{now, serde_hex_array};
 use nekoton_abi::{
     BuildTokenValue, FunctionBuilder, KnownParamType, KnownParamTypePlain, MaybeRef, PackAbiPlain,
     TokenValueExt, UnpackAbi, UnpackAbiPlain,
 };
+use serde::{Deserialize, Serialize};
 
 use super::{InternalMessage, ONE_EVER};
 use crate::network::Subscription;
@@ -48,24 +49,15 @@ impl Elector {
     }
 
     /// Prepares validator node and generates elector payload
-    pub async fn participate_in_elections(
+    /// Generates new validator keys in the node and registers them for the elections
+    pub async fn generate_validator_keys(
         &self,
         election_id: u32,
-        address: &ton_block::MsgAddressInt,
-        stake_factor: u32,
         timings: &ton_block::ConfigParam15,
-        signature_id: Option<i32>,
-    ) -> Result<ton_types::Cell> {
+    ) -> Result<ValidatorKeys> {
         const TTL_OFFSET: u32 = 1000;
 
-        anyhow::ensure!(
-            address.is_masterchain(),
-            "participant address not in masterchain"
-        );
-
-        let (_, address) = split_address(address)?;
-
-        let rpc = self.subscription.tcp_rpc();
+        let rpc = self.subscription.tcp_rpc()?;
 
         // Generate new key
         let permanent_key_hash = rpc
@@ -73,12 +65,6 @@ impl Elector {
             .await
             .context("failed to generate validator keys")?;
 
-        // Export its public key
-        let perm_pubkey = rpc
-            .export_public_key(&permanent_key_hash)
-            .await
-            .context("failed to export validator public key")?;
-
         // Add this key as a validator key
         let ttl = election_id
             + timings.validators_elected_for
@@ -102,20 +88,50 @@ impl Elector {
             .await
             .context("failed to add validator adnl address")?;
 
+        Ok(ValidatorKeys {
+            permanent_key_hash,
+            adnl_addr,
+        })
+    }
+
+    /// Builds a signed elections request using the registered validator keys
+    pub async fn make_election_payload(
+        &self,
+        keys: &ValidatorKeys,
+        election_id: u32,
+        address: &ton_block::MsgAddressInt,
+        stake_factor: u32,
+        signature_id: Option<i32>,
+    ) -> Result<ton_types::Cell> {
+        anyhow::ensure!(
+            address.is_masterchain(),
+            "participant address not in masterchain"
+        );
+
+        let (_, address) = split_address(address)?;
+
+        let rpc = self.subscription.tcp_rpc()?;
+
+        // Export its public key
+        let perm_pubkey = rpc
+            .export_public_key(&keys.permanent_key_hash)
+            .await
+            .context("failed to export validator public key")?;
+
         // Sign data
         let unsigned = UnsignedParticipantData {
             election_id,
             address,
             max_factor: stake_factor,
             public_key: ton_types::UInt256::from(perm_pubkey.to_bytes()),
-            adnl_addr: ton_types::UInt256::from(adnl_addr),
+            adnl_addr: ton_types::UInt256::from(keys.adnl_addr),
         };
 
         let data_to_sign = unsigned.build_data_to_sign();
         let data_to_sign = ton_abi::extend_signature_with_id(&data_to_sign, signature_id);
 
         let signature = rpc
-            .sign(&permanent_key_hash, &data_to_sign)
+            .sign(&keys.permanent_key_hash, &data_to_sign)
             .await
             .context("failed to sign election data")?;
 
@@ -127,22 +143,57 @@ impl Elector {
 
     pub async fn get_data(&self) -> Result<ElectorData> {
         let state = self.get_state().await?;
+        let inner = parse_data(&state)?;
+        Ok(ElectorData { inner })
+    }
 
-        let ton_block::AccountState::AccountActive { state_init } = state.storage.state else {
-            anyhow::bail!("elector account is not active");
-        };
+    /// Returns complaints from the past elections
+    pub async fn get_complaints(&self) -> Result<Vec<Complaint>> {
+        let state = self.get_state().await?;
+        parse_complaints(&state)
+    }
 
-        let data = state_init.data.context("elector data is empty")?;
-        let inner: data::PartialElectorData = ton_abi::TokenValue::decode_params(
-            data::layout(),
-            ton_types::SliceData::load_cell(data)?,
-            &ton_abi::contract::ABI_VERSION_2_1,
-            true,
-        )
-        .context("failed to parse elector data")?
-        .unpack()?;
+    /// Prepares a vote for the complaint signed with the validator key
+    pub async fn vote_for_complaint(
+        &self,
+        validator_idx: u16,
+        validator_key_hash: &[u8; 32],
+        election_id: u32,
+        complaint_hash: &ton_types::UInt256,
+        signature_id: Option<i32>,
+    ) -> Result<InternalMessage> {
+        use ton_types::IBitstring;
+
+        const OP_VOTE_FOR_COMPLAINT: u32 = 0x56744370;
+        const SIGN_TAG: u32 = 0x56744350;
+
+        let mut data = Vec::with_capacity(4 + 2 + 4 + 32);
+        data.extend_from_slice(&SIGN_TAG.to_be_bytes());
+        data.extend_from_slice(&validator_idx.to_be_bytes());
+        data.extend_from_slice(&election_id.to_be_bytes());
+        data.extend_from_slice(complaint_hash.as_slice());
+
+        let data_to_sign = ton_abi::extend_signature_with_id(&data, signature_id);
+        let signature = self
+            .subscription
+            .tcp_rpc()?
+            .sign(validator_key_hash, &data_to_sign)
+            .await
+            .context("failed to sign complaint vote")?;
 
-        Ok(ElectorData { inner })
+        let mut payload = ton_types::BuilderData::new();
+        payload
+            .append_u32(OP_VOTE_FOR_COMPLAINT)?
+            .append_u64(now() as u64)?
+            .append_raw(&signature, signature.len() * 8)?
+            .append_raw(&data, data.len() * 8)?;
+
+        Ok(InternalMessage {
+            amount: ONE_EVER,
+            dst: self.address.clone(),
+            payload: payload.into_cell()?,
+            bounce: true,
+        })
     }
 
     async fn get_state(&self) -> Result<ton_block::AccountStuff> {
@@ -154,6 +205,141 @@ impl Elector {
     }
 }
 
+fn parse_data(state: &ton_block::AccountStuff) -> Result<data::PartialElectorData> {
+    let ton_block::AccountState::AccountActive { state_init } = &state.storage.state else {
+        anyhow::bail!("elector account is not active");
+    };
+
+    let data = state_init.data.clone().context("elector data is empty")?;
+    ton_abi::TokenValue::decode_params(
+        data::layout(),
+        ton_types::SliceData::load_cell(data)?,
+        &ton_abi::contract::ABI_VERSION_2_1,
+        true,
+    )
+    .context("failed to parse elector data")?
+    .unpack()
+}
+
+/// Complaint for the validator from the past elections
+pub struct Complaint {
+    pub election_id: u32,
+    pub hash: ton_types::UInt256,
+    pub vset_id: ton_types::UInt256,
+    pub validator_pubkey: ton_types::UInt256,
+    pub created_at: u32,
+    pub severity: u8,
+    pub reward_addr: ton_types::UInt256,
+    pub paid: u128,
+    pub suggested_fine: u128,
+    pub suggested_fine_part: u32,
+    pub voters: Vec<u16>,
+    pub weight_remaining: i64,
+}
+
+impl Complaint {
+    /// Complaint is accepted when enough validators voted for it
+    pub fn is_accepted(&self) -> bool {
+        self.weight_remaining < 0
+    }
+}
+
+fn parse_complaints(state: &ton_block::AccountStuff) -> Result<Vec<Complaint>> {
+    use ton_block::Deserializable;
+    use ton_types::HashmapType;
+
+    const COMPLAINT_STATUS_TAG: u8 = 0x2d;
+    const COMPLAINT_TAG: u8 = 0xbc;
+
+    let ton_block::AccountState::AccountActive { state_init } = &state.storage.state else {
+        anyhow::bail!("elector account is not active");
+    };
+    let data = state_init.data.clone().context("elector data is empty")?;
+    let mut data = ton_types::SliceData::load_cell(data)?;
+
+    // elect:(Maybe ^Elect)
+    if data.get_next_bit()? {
+        data.checked_drain_reference()?;
+    }
+    // credits:(HashmapE 256 Grams)
+    data.get_next_dictionary()?;
+    // past_elections:(HashmapE 32 PastElection)
+    let past_elections = ton_types::HashmapE::with_hashmap(32, data.get_next_dictionary()?);
+
+    let mut elections = Vec::new();
+    past_elections.iterate_slices(|mut key, value| {
+        elections.push((key.get_next_u32()?, value));
+        Ok(true)
+    })?;
+
+    let mut result = Vec::new();
+    for (election_id, mut past_election) in elections {
+        // unfreeze_at:uint32 stake_held:uint32 vset_hash:uint256
+        past_election.move_by(32 + 32 + 256)?;
+        // frozen_dict:(HashmapE 256 FrozenStake)
+        past_election.get_next_dictionary()?;
+        // total_stake:Grams bonuses:Grams
+        ton_block::Grams::construct_from(&mut past_election)?;
+        ton_block::Grams::construct_from(&mut past_election)?;
+        // complaints:(HashmapE 256 ComplaintStatus)
+        let complaints =
+            ton_types::HashmapE::with_hashmap(256, past_election.get_next_dictionary()?);
+
+        let mut statuses = Vec::new();
+        complaints.iterate_slices(|mut key, value| {
+            statuses.push((key.get_next_hash()?, value));
+            Ok(true)
+        })?;
+
+        for (hash, mut status) in statuses {
+            anyhow::ensure!(
+                status.get_next_byte()? == COMPLAINT_STATUS_TAG,
+                "invalid complaint status"
+            );
+            let mut complaint = ton_types::SliceData::load_cell(status.checked_drain_reference()?)?;
+            let voters = ton_types::HashmapE::with_hashmap(16, status.get_next_dictionary()?);
+            let vset_id = status.get_next_hash()?;
+            let weight_remaining = status.get_next_i64()?;
+
+            let mut voter_ids = Vec::new();
+            voters.iterate_slices(|mut key, _| {
+                voter_ids.push(key.get_next_u16()?);
+                Ok(true)
+            })?;
+
+            anyhow::ensure!(
+                complaint.get_next_byte()? == COMPLAINT_TAG,
+                "invalid complaint"
+            );
+            let validator_pubkey = complaint.get_next_hash()?;
+            complaint.checked_drain_reference()?; // description
+            let created_at = complaint.get_next_u32()?;
+            let severity = complaint.get_next_byte()?;
+            let reward_addr = complaint.get_next_hash()?;
+            let paid = ton_block::Grams::construct_from(&mut complaint)?;
+            let suggested_fine = ton_block::Grams::construct_from(&mut complaint)?;
+            let suggested_fine_part = complaint.get_next_u32()?;
+
+            result.push(Complaint {
+                election_id,
+                hash,
+                vset_id,
+                validator_pubkey,
+                created_at,
+                severity,
+                reward_addr,
+                paid: paid.as_u128(),
+                suggested_fine: suggested_fine.as_u128(),
+                suggested_fine_part,
+                voters: voter_ids,
+                weight_remaining,
+            });
+        }
+    }
+
+    Ok(result)
+}
+
 pub struct ElectorData {
     inner: data::PartialElectorData,
 }
@@ -164,6 +350,37 @@ impl ElectorData {
         Some(election_id)
     }
 
+    /// Returns the stake of the participant in the current elections
+    pub fn stake(&self, address: &ton_block::MsgAddressInt) -> Option<u64> {
+        if !address.is_masterchain() {
+            return None;
+        }
+
+        let current_election = self.inner.current_election.0.as_ref()?;
+        let (_, address) = split_address(address).ok()?;
+
+        current_election
+            .members
+            .values()
+            .find(|entry| entry.src_addr == address)
+            .map(|entry| entry.msg_value)
+    }
+
+    /// Returns stakes of other participants in the current elections
+    pub fn participant_stakes(&self, except: &ton_block::MsgAddressInt) -> Vec<u128> {
+        let Some(current_election) = &self.inner.current_election.0 else {
+            return Vec::new();
+        };
+        let except = split_address(except).ok().map(|(_, address)| address);
+
+        current_election
+            .members
+            .values()
+            .filter(|entry| Some(&entry.src_addr) != except.as_ref())
+            .map(|entry| entry.msg_value as u128)
+            .collect()
+    }
+
     pub fn nearest_unfreeze_at(&self, election_id: u32) -> Option<u32> {
         self.inner
             .past_elections
@@ -185,25 +402,187 @@ impl ElectorData {
         self.inner.credits.get(&address).copied()
     }
 
-    pub fn elected(&self, address: &ton_block::MsgAddressInt) -> bool {
-        if !address.is_masterchain() {
-            return false;
+    /// Returns stakes of the participant which are still frozen in the elector
+    pub fn frozen_stakes(&self, address: &ton_block::MsgAddressInt) -> Vec<FrozenStake> {
+        let Some((_, address)) = split_address(address).ok() else {
+            return Vec::new();
+        };
+
+        let mut result = Vec::new();
+        for (election_id, election) in &self.inner.past_elections {
+            for frozen in election.frozen_dict.values() {
+                if frozen.addr == address {
+                    // NOTE: bonuses are distributed proportionally to the stake
+                    let reward = match election.total_stake {
+                        0 => 0,
+                        total_stake => {
+                            let reward =
+                                num::BigUint::from(election.bonuses) * frozen.stake / total_stake;
+                            num::ToPrimitive::to_u128(&reward).unwrap_or_default()
+                        }
+                    };
+
+                    result.push(FrozenStake {
+                        election_id: *election_id,
+                        unfreeze_at: election.unfreeze_at,
+                        stake_held: election.stake_held,
+                        stake: frozen.stake,
+                        reward,
+                        banned: frozen.banned,
+                    });
+                }
+            }
         }
+        result
+    }
+
+    pub fn elected(&self, address: &ton_block::MsgAddressInt) -> bool {
+        self.stake(address).is_some()
+    }
+
+    /// Finds a bid in the current elections which was sent either from the participant
+    /// with other validator keys or with the same validator keys from another participant
+    pub fn find_conflicting_bid(
+        &self,
+        address: &ton_block::MsgAddressInt,
+        public_key: &ton_types::UInt256,
+        adnl_addr: &ton_types::UInt256,
+    ) -> Option<ElectionBid> {
+        let current_election = self.inner.current_election.0.as_ref()?;
+        let (_, address) = split_address(address).ok()?;
 
+        current_election
+            .members
+            .iter()
+            .find(|(key, entry)| {
+                let same_keys = *key == public_key && entry.adnl_addr == *adnl_addr;
+                let same_key_or_adnl = *key == public_key || entry.adnl_addr == *adnl_addr;
+                if entry.src_addr == address {
+                    !same_keys
+                } else {
+                    same_key_or_adnl
+                }
+            })
+            .map(|(key, entry)| ElectionBid {
+                public_key: *key,
+                adnl_addr: entry.adnl_addr,
+                src_addr: entry.src_addr,
+                stake: entry.msg_value,
+                max_factor: entry.max_factor,
+            })
+    }
+
+    /// Returns all bids in the current elections
+    pub fn bids(&self) -> Vec<ElectionBid> {
         let Some(current_election) = &self.inner.current_election.0 else {
-            return false;
-        };
-        let Ok((_, address)) = split_address(address) else {
-            return false;
+            return Vec::new();
         };
 
         current_election
             .members
-            .values()
-            .any(|entry| entry.src_addr == address)
+            .iter()
+            .map(|(key, entry)| ElectionBid {
+                public_key: *key,
+                adnl_addr: entry.adnl_addr,
+                src_addr: entry.src_addr,
+                stake: entry.msg_value,
+                max_factor: entry.max_factor,
+            })
+            .collect()
+    }
+
+    /// Returns the bid of the participant in the current elections
+    pub fn bid(&self, address: &ton_block::MsgAddressInt) -> Option<ElectionBid> {
+        let (_, address) = split_address(address).ok()?;
+        self.bids().into_iter().find(|bid| bid.src_addr == address)
+    }
+
+    /// Resolves the outcome of the participant bid in the specified elections.
+    ///
+    /// Returns `None` if the elector no longer has info about these elections.
+    pub fn bid_outcome(
+        &self,
+        election_id: u32,
+        address: &ton_block::MsgAddressInt,
+    ) -> Option<BidOutcome> {
+        let (_, address) = split_address(address).ok()?;
+
+        if let Some(current_election) = &self.inner.current_election.0 {
+            if current_election.elect_at == election_id {
+                let accepted = current_election
+                    .members
+                    .values()
+                    .any(|entry| entry.src_addr == address);
+                return Some(if accepted {
+                    BidOutcome::Accepted
+                } else {
+                    BidOutcome::Refunded
+                });
+            }
+        }
+
+        let election = self.inner.past_elections.get(&election_id)?;
+        Some(
+            match election
+                .frozen_dict
+                .values()
+                .find(|frozen| frozen.addr == address)
+            {
+                Some(frozen) if frozen.banned => BidOutcome::Banned,
+                Some(_) => BidOutcome::Won,
+                // NOTE: stakes which were not elected are returned immediately
+                None => BidOutcome::Refunded,
+            },
+        )
     }
 }
 
+/// Outcome of the election bid
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BidOutcome {
+    /// Bid is in the current elections
+    Accepted,
+    /// Bid was not accepted or not elected
+    Refunded,
+    /// Validator was elected, the stake is frozen
+    Won,
+    /// Validator was elected and then banned
+    Banned,
+}
+
+/// Stake from the past elections which is frozen in the elector
+#[derive(Debug, Clone, Copy)]
+pub struct FrozenStake {
+    pub election_id: u32,
+    pub unfreeze_at: u32,
+    pub stake_held: u32,
+    pub stake: u128,
+    /// Current share of the round bonuses
+    pub reward: u128,
+    pub banned: bool,
+}
+
+/// Election request accepted by the elector
+#[derive(Debug, Clone)]
+pub struct ElectionBid {
+    pub public_key: ton_types::UInt256,
+    pub adnl_addr: ton_types::UInt256,
+    pub src_addr: ton_types::UInt256,
+    pub stake: u64,
+    /// Max factor of the bid (16.16 fixed point)
+    pub max_factor: u32,
+}
+
+/// Validator keys registered in the node for the elections
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ValidatorKeys {
+    #[serde(with = "serde_hex_array")]
+    pub permanent_key_hash: [u8; 32],
+    #[serde(with = "serde_hex_array")]
+    pub adnl_addr: [u8; 32],
+}
+
 struct UnsignedParticipantData {
     election_id: u32,
     address: ton_types::UInt256,
@@ -295,6 +674,28 @@ mod data {
     pub struct PastElectionData {
         #[abi(uint32)]
         pub unfreeze_at: u32,
+        #[abi(uint32)]
+        pub stake_held: u32,
+        #[abi(uint256)]
+        pub vset_hash: ton_types::UInt256,
+        #[abi]
+        pub frozen_dict: BTreeMap<ton_types::UInt256, FrozenStakeData>,
+        #[abi(gram)]
+        pub total_stake: u128,
+        #[abi(gram)]
+        pub bonuses: u128,
+    }
+
+    #[derive(Debug, UnpackAbi, KnownParamType)]
+    pub struct FrozenStakeData {
+        #[abi(uint256)]
+        pub addr: ton_types::UInt256,
+        #[abi(uint64)]
+        pub weight: u64,
+        #[abi(gram)]
+        pub stake: u128,
+        #[abi(bool)]
+        pub banned: bool,
     }
 }
 
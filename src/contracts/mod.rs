@@ -1,10 +1,12 @@
 pub use cluster::Cluster;
+pub use config_contract::ConfigContract;
 pub use depool::DePool;
 pub use elector::Elector;
 pub use strategy::Strategy;
 pub use wallet::Wallet;
 
 pub mod cluster;
+pub mod config_contract;
 pub mod depool;
 pub mod elector;
 pub mod strategy;
@@ -1,14 +1,20 @@
 use std::sync::Arc;
 
 use anyhow::{Context, Result};
+use broxus_util::now;
 use nekoton_abi::{FunctionBuilder, KnownParamTypePlain, PackAbiPlain};
 use ton_abi::contract::ABI_VERSION_2_3;
 use ton_block::{Deserializable, GetRepresentationHash};
 
 use super::{InternalMessage, ONE_EVER};
-use crate::network::Subscription;
+use crate::network::{AccountStatesRx, Subscription, TransactionOutcome};
 use crate::util::{make_default_headers, TransactionWithHash};
 
+pub use self::policy::SpendingPolicy;
+
+pub mod policy;
+pub mod tip3;
+
 pub struct Wallet {
     keypair: ed25519_dalek::Keypair,
     address: ton_block::MsgAddressInt,
@@ -39,10 +45,19 @@ impl Wallet {
         Ok(account.map(|state| state.storage.balance.grams.as_u128()))
     }
 
+    /// Subscribes to the wallet state changes
+    pub fn subscribe_state(&self) -> AccountStatesRx {
+        self.subscription.subscribe_account(&self.address)
+    }
+
     /// Sends the internal message to the recipient, returns the destination transaction
     pub async fn call(&self, internal_message: InternalMessage) -> Result<TransactionWithHash> {
+        const REMOTE_TIMEOUT: u32 = 60;
+
         let dst = internal_message.dst.clone();
-        let mut dst_transactions = self.subscription.subscribe(&dst);
+        // NOTE: blocks are not processed without the local node
+        let dst_transactions =
+            (!self.subscription.is_remote()).then(|| self.subscription.subscribe(&dst));
 
         let src_tx = self.transfer(internal_message).await?;
         tracing::debug!(source_tx_hash = ?src_tx.hash, "message sent from wallet");
@@ -72,6 +87,14 @@ impl Wallet {
             .context("failed to find outgoing message")?;
         let out_msg_hash = out_msg_hash.context("outgoing message not found")?;
 
+        let Some(mut dst_transactions) = dst_transactions else {
+            return self
+                .subscription
+                .poll_transaction(&out_msg_hash, now() + REMOTE_TIMEOUT)
+                .await?
+                .context("destination transaction was not found");
+        };
+
         while let Some(tx) = dst_transactions.recv().await {
             tracing::debug!(source_tx_hash = ?src_tx.hash, tx_hash = ?tx.hash, "new transaction found");
             let Some(msg) = tx.data.in_msg_cell() else {
@@ -84,65 +107,134 @@ impl Wallet {
         anyhow::bail!("destination transaction was not found")
     }
 
+    /// Spending policy which is applied to all transfers
+    pub fn spending_policy(&self) -> Option<&SpendingPolicy> {
+        self.subscription.spending_policy()
+    }
+
+    /// Checks the transfer against the spending policy (if configured).
+    ///
+    /// Returns whether the transfer must be recorded to the spending ledger.
+    pub async fn check_spending_policy(&self, internal_message: &InternalMessage) -> Result<bool> {
+        let Some(policy) = self.spending_policy() else {
+            return Ok(false);
+        };
+        let elector = self.subscription.get_system_addresses().await?.elector;
+        policy.check(&internal_message.dst, internal_message.amount, &elector)
+    }
+
     /// Sends the internal message to the recipient, returns the source transaction
     pub async fn transfer(&self, internal_message: InternalMessage) -> Result<TransactionWithHash> {
-        let account = self.get_account_state().await?;
+        let limited = self.check_spending_policy(&internal_message).await?;
+        let (dst, amount) = (internal_message.dst.clone(), internal_message.amount);
+
+        let state_init = self.get_state_init().await?;
+        let inputs = make_transfer_inputs(internal_message);
+
+        let tx = self
+            .subscription
+            .send_message_reliable(|timeout, signature_id| {
+                self.make_transfer_message(&inputs, state_init.clone(), timeout, signature_id)
+            })
+            .await?;
 
-        let state_init = match account {
+        if let Some(policy) = self.spending_policy().filter(|_| limited) {
+            if let Err(e) = policy.record(&dst, amount, &tx.hash) {
+                tracing::error!(tx_hash = ?tx.hash, "failed to record spending: {e:?}");
+            }
+        }
+
+        Ok(tx)
+    }
+
+    /// Executes the transfer locally to estimate fees and exit codes
+    /// of the wallet and the destination transactions
+    pub async fn estimate_transfer(
+        &self,
+        internal_message: InternalMessage,
+    ) -> Result<TransferEstimate> {
+        const TIMEOUT: u32 = 60;
+
+        let dst = internal_message.dst.clone();
+        let state_init = self.get_state_init().await?;
+        let inputs = make_transfer_inputs(internal_message);
+
+        let signature_id = self.subscription.get_signature_id().await?;
+        let (message, _) =
+            self.make_transfer_message(&inputs, state_init, TIMEOUT, signature_id)?;
+
+        let wallet = self.subscription.emulate_message(&message).await?;
+
+        let mut destination = None;
+        if let TransactionOutcome::Accepted(tx) = &wallet {
+            let mut out_msg = None;
+            tx.data.out_msgs.iterate(|ton_block::InRefValue(msg)| {
+                if matches!(msg.int_header(), Some(header) if header.dst == dst) {
+                    out_msg = Some(msg);
+                    Ok(false)
+                } else {
+                    Ok(true)
+                }
+            })?;
+
+            if let Some(msg) = out_msg {
+                destination = Some(self.subscription.emulate_message(&msg).await?);
+            }
+        }
+
+        Ok(TransferEstimate {
+            wallet,
+            destination,
+        })
+    }
+
+    fn make_transfer_message(
+        &self,
+        inputs: &[ton_abi::Token],
+        state_init: Option<ton_block::StateInit>,
+        timeout: u32,
+        signature_id: Option<i32>,
+    ) -> Result<(ton_block::Message, u32)> {
+        let (expire_at, headers) = make_default_headers(Some(self.keypair.public), timeout);
+
+        let mut message =
+            ton_block::Message::with_ext_in_header(ton_block::ExternalInboundMessageHeader {
+                dst: self.address.clone(),
+                ..Default::default()
+            });
+
+        message.set_body(
+            ever_wallet::send_transaction()
+                .encode_input(
+                    &headers,
+                    inputs,
+                    false,
+                    Some((&self.keypair, signature_id)),
+                    Some(self.address.clone()),
+                )
+                .and_then(ton_types::SliceData::load_builder)?,
+        );
+
+        if let Some(state_init) = state_init {
+            message.set_state_init(state_init);
+        }
+
+        Ok((message, expire_at))
+    }
+
+    async fn get_state_init(&self) -> Result<Option<ton_block::StateInit>> {
+        match self.get_account_state().await? {
             Some(account) => match account.storage.state {
-                ton_block::AccountState::AccountActive { .. } => None,
+                ton_block::AccountState::AccountActive { .. } => Ok(None),
                 ton_block::AccountState::AccountFrozen { .. } => {
                     anyhow::bail!("account frozen");
                 }
-                ton_block::AccountState::AccountUninit => Some(
+                ton_block::AccountState::AccountUninit => Ok(Some(
                     make_state_init(&self.keypair.public).context("failed to make state init")?,
-                ),
+                )),
             },
             None => anyhow::bail!("account not deployed"),
-        };
-
-        let inputs = ever_wallet::SendTransactionInputs {
-            dest: internal_message.dst,
-            value: internal_message.amount,
-            bounce: internal_message.bounce,
-            flags: 3,
-            payload: internal_message.payload,
         }
-        .pack();
-
-        let tx = self
-            .subscription
-            .send_message_with_retires(|timeout, signature_id| {
-                let (expire_at, headers) = make_default_headers(Some(self.keypair.public), timeout);
-
-                let mut message = ton_block::Message::with_ext_in_header(
-                    ton_block::ExternalInboundMessageHeader {
-                        dst: self.address.clone(),
-                        ..Default::default()
-                    },
-                );
-
-                message.set_body(
-                    ever_wallet::send_transaction()
-                        .encode_input(
-                            &headers,
-                            &inputs,
-                            false,
-                            Some((&self.keypair, signature_id)),
-                            Some(self.address.clone()),
-                        )
-                        .and_then(ton_types::SliceData::load_builder)?,
-                );
-
-                if let Some(state_init) = state_init.clone() {
-                    message.set_state_init(state_init);
-                }
-
-                Ok((message, expire_at))
-            })
-            .await?;
-
-        Ok(tx)
     }
 
     async fn get_account_state(&self) -> Result<Option<ton_block::AccountStuff>> {
@@ -153,13 +245,46 @@ impl Wallet {
     }
 }
 
+/// Locally executed wallet transfer
+pub struct TransferEstimate {
+    /// Wallet transaction outcome
+    pub wallet: TransactionOutcome,
+    /// Destination transaction outcome (if the message was sent)
+    pub destination: Option<TransactionOutcome>,
+}
+
+impl TransferEstimate {
+    /// Total fees of the wallet and the destination transactions
+    pub fn total_fees(&self) -> u128 {
+        let fees = |outcome: &TransactionOutcome| {
+            outcome
+                .transaction()
+                .map(|tx| tx.data.total_fees.grams.as_u128())
+                .unwrap_or_default()
+        };
+        fees(&self.wallet) + self.destination.as_ref().map(fees).unwrap_or_default()
+    }
+
+    /// Returns an error if any of the transactions is expected to fail
+    pub fn ensure_success(&self) -> Result<()> {
+        if let Some(e) = self.wallet.error() {
+            anyhow::bail!("wallet transaction is expected to fail: {e}");
+        }
+        match &self.destination {
+            Some(outcome) => match outcome.error() {
+                Some(e) => anyhow::bail!("destination transaction is expected to fail: {e}"),
+                None => Ok(()),
+            },
+            None => anyhow::bail!("wallet is not expected to send the message"),
+        }
+    }
+}
+
 pub fn compute_wallet_address(
     workchain_id: i8,
     pubkey: &ed25519_dalek::PublicKey,
 ) -> ton_block::MsgAddressInt {
-    let hash = make_state_init(pubkey)
-        .and_then(|state| state.hash())
-        .unwrap();
+    let hash = compute_wallet_state_init_hash(pubkey);
     ton_block::MsgAddressInt::AddrStd(ton_block::MsgAddrStd::with_address(
         None,
         workchain_id,
@@ -167,6 +292,24 @@ pub fn compute_wallet_address(
     ))
 }
 
+/// Computes the hash of the wallet state init (same for all workchains)
+pub fn compute_wallet_state_init_hash(pubkey: &ed25519_dalek::PublicKey) -> ton_types::UInt256 {
+    make_state_init(pubkey)
+        .and_then(|state| state.hash())
+        .unwrap()
+}
+
+fn make_transfer_inputs(internal_message: InternalMessage) -> Vec<ton_abi::Token> {
+    ever_wallet::SendTransactionInputs {
+        dest: internal_message.dst,
+        value: internal_message.amount,
+        bounce: internal_message.bounce,
+        flags: 3,
+        payload: internal_message.payload,
+    }
+    .pack()
+}
+
 fn make_state_init(public_key: &ed25519_dalek::PublicKey) -> Result<ton_block::StateInit> {
     use ton_types::IBitstring;
 
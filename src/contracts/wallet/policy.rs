@@ -0,0 +1,234 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use broxus_util::serde_string;
+use everscale_crypto::ed25519;
+use serde::{Deserialize, Serialize};
+
+use crate::config::{AddressBook, AppConfig, AppConfigValidator};
+use crate::util::{split_address, verify_approval, ApprovalOperation, ApprovalRequest, Tokens};
+
+const DAY: u32 = 86400;
+
+/// Limits and allowed destinations for transfers from the validator wallet.
+///
+/// Transfers to the elector and to the configured DePool (and its cluster)
+/// are always allowed and not limited since the funds stay under validator control.
+pub struct SpendingPolicy {
+    max_transfer: Option<u128>,
+    daily_limit: Option<u128>,
+    allowlist: Vec<ton_block::MsgAddressInt>,
+    trusted: Vec<ton_block::MsgAddressInt>,
+    /// Destinations which were explicitly confirmed by the user
+    confirmed: parking_lot::Mutex<Vec<ton_block::MsgAddressInt>>,
+    approvers: Vec<ed25519::PublicKey>,
+    approval_threshold: Option<u128>,
+    approve_key_rotation: bool,
+    /// Transfers which were approved by the second key
+    approved: parking_lot::Mutex<Vec<(ton_block::MsgAddressInt, u128)>>,
+    ledger: PathBuf,
+}
+
+impl SpendingPolicy {
+    /// Builds the policy from the app config, returns `None` if it is not configured
+    pub fn from_config<P: AsRef<Path>>(
+        config: &AppConfig,
+        address_book: &AddressBook,
+        ledger: P,
+    ) -> Result<Option<Self>> {
+        let Some(policy) = &config.spending_policy else {
+            return Ok(None);
+        };
+
+        let allowlist = policy
+            .allowlist
+            .iter()
+            .map(|item| address_book.resolve(item))
+            .collect::<Result<Vec<_>>>()
+            .context("invalid spending policy allowlist")?;
+
+        let approvals = policy.approvals.as_ref();
+        let approvers = approvals
+            .map(|approvals| approvals.approvers.as_slice())
+            .unwrap_or_default()
+            .iter()
+            .map(|key| {
+                let key = hex::decode(key.trim()).ok()?;
+                ed25519::PublicKey::from_bytes(key.try_into().ok()?)
+            })
+            .collect::<Option<Vec<_>>>()
+            .context("invalid approver public key")?;
+        anyhow::ensure!(
+            approvals.is_none() || !approvers.is_empty(),
+            "no approvers specified in the spending policy"
+        );
+
+        let mut trusted = Vec::new();
+        if let Some(AppConfigValidator::DePool(depool)) = &config.validator {
+            trusted.push(depool.depool.clone());
+            trusted.extend(depool.cluster.clone());
+        }
+
+        Ok(Some(Self {
+            max_transfer: policy.max_transfer.map(u128::from),
+            daily_limit: policy.daily_limit.map(u128::from),
+            allowlist,
+            trusted,
+            confirmed: Default::default(),
+            approvers,
+            approval_threshold: approvals
+                .and_then(|approvals| approvals.transfer_threshold)
+                .map(u128::from),
+            approve_key_rotation: approvals.map_or(false, |approvals| approvals.key_rotation),
+            approved: Default::default(),
+            ledger: ledger.as_ref().to_path_buf(),
+        }))
+    }
+
+    /// Whether the transfer to the destination must be confirmed by the user
+    pub fn requires_confirmation(&self, dst: &ton_block::MsgAddressInt) -> bool {
+        !self.trusted.contains(dst)
+            && !self.allowlist.contains(dst)
+            && !self.confirmed.lock().contains(dst)
+    }
+
+    /// Allows transfers to the destination outside the allowlist
+    pub fn confirm_destination(&self, dst: &ton_block::MsgAddressInt) {
+        self.confirmed.lock().push(dst.clone());
+    }
+
+    /// Whether the transfer must be approved by the second key
+    pub fn requires_transfer_approval(&self, dst: &ton_block::MsgAddressInt, amount: u128) -> bool {
+        matches!(self.approval_threshold, Some(threshold) if amount > threshold)
+            && !self.trusted.contains(dst)
+            && !self.approved.lock().contains(&(dst.clone(), amount))
+    }
+
+    /// Whether control and ADNL key rotations must be approved by the second key
+    pub fn requires_key_rotation_approval(&self) -> bool {
+        self.approve_key_rotation
+    }
+
+    /// Checks the approval signature of the encoded request
+    pub fn approve(&self, token: &str, signature: &[u8; 64]) -> Result<()> {
+        let request = ApprovalRequest::decode(token)?;
+        anyhow::ensure!(!request.is_expired(), "approval request expired");
+        anyhow::ensure!(
+            verify_approval(token, signature, &self.approvers),
+            "approval signature mismatch"
+        );
+
+        if let ApprovalOperation::Transfer {
+            destination,
+            amount,
+            ..
+        } = request.operation
+        {
+            self.approved.lock().push((destination, amount));
+        }
+        Ok(())
+    }
+
+    /// Checks the transfer against the policy.
+    ///
+    /// Returns whether the transfer is counted towards the daily limit.
+    pub fn check(
+        &self,
+        dst: &ton_block::MsgAddressInt,
+        amount: u128,
+        elector: &ton_types::UInt256,
+    ) -> Result<bool> {
+        if self.trusted.contains(dst)
+            || matches!(split_address(dst), Ok((-1, address)) if &address == elector)
+        {
+            return Ok(false);
+        }
+
+        anyhow::ensure!(
+            !self.requires_confirmation(dst),
+            "transfer to {dst} requires confirmation (not in the spending policy allowlist)"
+        );
+
+        anyhow::ensure!(
+            !self.requires_transfer_approval(dst, amount),
+            "transfer of {:#} requires a second approval",
+            Tokens(amount)
+        );
+
+        if let Some(max_transfer) = self.max_transfer {
+            anyhow::ensure!(
+                amount <= max_transfer,
+                "transfer amount exceeds the spending policy limit ({:#})",
+                Tokens(max_transfer)
+            );
+        }
+
+        if let Some(daily_limit) = self.daily_limit {
+            let spent = self.spent_since(broxus_util::now().saturating_sub(DAY))?;
+            anyhow::ensure!(
+                spent.saturating_add(amount) <= daily_limit,
+                "transfer exceeds the daily spending limit ({:#} of {:#} already spent)",
+                Tokens(spent),
+                Tokens(daily_limit)
+            );
+        }
+
+        Ok(true)
+    }
+
+    /// Appends the sent transfer to the spending ledger
+    pub fn record(
+        &self,
+        dst: &ton_block::MsgAddressInt,
+        amount: u128,
+        tx_hash: &ton_types::UInt256,
+    ) -> Result<()> {
+        let entry = SpendingEntry {
+            timestamp: broxus_util::now(),
+            destination: dst.clone(),
+            amount,
+            tx_hash: tx_hash.to_hex_string(),
+        };
+
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.ledger)
+            .context("failed to open spending ledger")?;
+        writeln!(file, "{}", serde_json::to_string(&entry)?)?;
+        Ok(())
+    }
+
+    fn spent_since(&self, since: u32) -> Result<u128> {
+        if !self.ledger.exists() {
+            return Ok(0);
+        }
+
+        let data =
+            std::fs::read_to_string(&self.ledger).context("failed to read spending ledger")?;
+        data.lines()
+            .filter(|line| !line.trim().is_empty())
+            .try_fold(0u128, |spent, line| {
+                let entry: SpendingEntry =
+                    serde_json::from_str(line).context("invalid spending ledger entry")?;
+                Ok(if entry.timestamp >= since {
+                    spent.saturating_add(entry.amount)
+                } else {
+                    spent
+                })
+            })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct SpendingEntry {
+    /// Unix timestamp when the transfer was sent
+    timestamp: u32,
+    #[serde(with = "serde_string")]
+    destination: ton_block::MsgAddressInt,
+    #[serde(with = "serde_string")]
+    amount: u128,
+    /// Hex encoded hash of the wallet transaction
+    tx_hash: String,
+}
@@ -0,0 +1,223 @@
+//! TIP-3.1 token root and token wallet bindings.
+
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use nekoton_abi::{
+    BuildTokenValue, FunctionBuilder, FunctionExt, KnownParamType, TokenValueExt, UnpackFirst,
+};
+
+use crate::contracts::{InternalMessage, ONE_EVER};
+use crate::network::Subscription;
+
+pub struct TokenRoot {
+    address: ton_block::MsgAddressInt,
+    subscription: Arc<Subscription>,
+}
+
+impl TokenRoot {
+    pub fn new(address: ton_block::MsgAddressInt, subscription: Arc<Subscription>) -> Self {
+        Self {
+            address,
+            subscription,
+        }
+    }
+
+    pub fn address(&self) -> &ton_block::MsgAddressInt {
+        &self.address
+    }
+
+    pub async fn get_details(&self) -> Result<TokenRootDetails> {
+        let account = self
+            .subscription
+            .get_account_state(&self.address)
+            .await?
+            .context("token root not deployed")?;
+
+        let run = |function: &ton_abi::Function| {
+            function
+                .run_local(&nekoton_utils::SimpleClock, account.clone(), &[answer_id()])
+                .and_then(|output| {
+                    output.tokens.with_context(|| {
+                        format!("getter failed (exit code: {})", output.result_code)
+                    })
+                })
+        };
+
+        Ok(TokenRootDetails {
+            name: run(root_methods::name())?.unpack_first()?,
+            symbol: run(root_methods::symbol())?.unpack_first()?,
+            decimals: run(root_methods::decimals())?.unpack_first()?,
+        })
+    }
+
+    /// Computes the token wallet address of the specified owner
+    pub async fn wallet_of(&self, owner: &ton_block::MsgAddressInt) -> Result<TokenWallet> {
+        let address = self
+            .subscription
+            .run_local(
+                &self.address,
+                root_methods::wallet_of(),
+                &[
+                    answer_id(),
+                    owner.clone().token_value().named("walletOwner"),
+                ],
+            )
+            .await?
+            .unpack_first()?;
+
+        Ok(TokenWallet {
+            address,
+            subscription: self.subscription.clone(),
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TokenRootDetails {
+    pub name: String,
+    pub symbol: String,
+    pub decimals: u8,
+}
+
+pub struct TokenWallet {
+    address: ton_block::MsgAddressInt,
+    subscription: Arc<Subscription>,
+}
+
+impl TokenWallet {
+    /// Attached amount for the token transfer
+    pub const TRANSFER_AMOUNT: u128 = ONE_EVER / 2;
+    /// Attached amount for the recipient token wallet deployment
+    pub const DEPLOY_WALLET_AMOUNT: u128 = ONE_EVER / 10;
+
+    pub fn address(&self) -> &ton_block::MsgAddressInt {
+        &self.address
+    }
+
+    /// Returns token balance or `None` if the token wallet is not deployed
+    pub async fn get_balance(&self) -> Result<Option<u128>> {
+        let is_deployed = matches!(
+            self.subscription.get_account_state(&self.address).await?,
+            Some(account) if matches!(account.storage.state, ton_block::AccountState::AccountActive { .. })
+        );
+        if !is_deployed {
+            return Ok(None);
+        }
+
+        let balance = self
+            .subscription
+            .run_local(&self.address, wallet_methods::balance(), &[answer_id()])
+            .await?
+            .unpack_first()?;
+        Ok(Some(balance))
+    }
+
+    /// Prepares an internal message from the owner to transfer tokens.
+    ///
+    /// Recipient token wallet is deployed if needed and the remaining gas
+    /// is returned to the `remaining_gas_to` address.
+    pub fn transfer(
+        &self,
+        amount: u128,
+        recipient: &ton_block::MsgAddressInt,
+        remaining_gas_to: &ton_block::MsgAddressInt,
+    ) -> Result<InternalMessage> {
+        let payload = wallet_methods::transfer()
+            .encode_internal_input(&[
+                amount.token_value().named("amount"),
+                recipient.clone().token_value().named("recipient"),
+                Self::DEPLOY_WALLET_AMOUNT
+                    .token_value()
+                    .named("deployWalletValue"),
+                remaining_gas_to
+                    .clone()
+                    .token_value()
+                    .named("remainingGasTo"),
+                false.token_value().named("notify"),
+                ton_types::Cell::default().token_value().named("payload"),
+            ])?
+            .into_cell()?;
+
+        Ok(InternalMessage {
+            dst: self.address.clone(),
+            amount: Self::TRANSFER_AMOUNT + Self::DEPLOY_WALLET_AMOUNT,
+            payload,
+            bounce: true,
+        })
+    }
+}
+
+fn answer_id() -> ton_abi::Token {
+    0u32.token_value().named("answerId")
+}
+
+mod root_methods {
+    use super::*;
+
+    pub fn name() -> &'static ton_abi::Function {
+        once!(ton_abi::Function, || {
+            make_getter("name", String::param_type())
+        })
+    }
+
+    pub fn symbol() -> &'static ton_abi::Function {
+        once!(ton_abi::Function, || {
+            make_getter("symbol", String::param_type())
+        })
+    }
+
+    pub fn decimals() -> &'static ton_abi::Function {
+        once!(ton_abi::Function, || {
+            make_getter("decimals", u8::param_type())
+        })
+    }
+
+    pub fn wallet_of() -> &'static ton_abi::Function {
+        once!(ton_abi::Function, || {
+            FunctionBuilder::new("walletOf")
+                .abi_version(ABI_VERSION)
+                .default_headers()
+                .input("answerId", u32::param_type())
+                .input("walletOwner", ton_block::MsgAddressInt::param_type())
+                .output("value0", ton_block::MsgAddressInt::param_type())
+                .build()
+        })
+    }
+}
+
+mod wallet_methods {
+    use super::*;
+
+    pub fn balance() -> &'static ton_abi::Function {
+        once!(ton_abi::Function, || {
+            make_getter("balance", u128::param_type())
+        })
+    }
+
+    pub fn transfer() -> &'static ton_abi::Function {
+        once!(ton_abi::Function, || {
+            FunctionBuilder::new("transfer")
+                .abi_version(ABI_VERSION)
+                .default_headers()
+                .input("amount", u128::param_type())
+                .input("recipient", ton_block::MsgAddressInt::param_type())
+                .input("deployWalletValue", u128::param_type())
+                .input("remainingGasTo", ton_block::MsgAddressInt::param_type())
+                .input("notify", bool::param_type())
+                .input("payload", ton_types::Cell::param_type())
+                .build()
+        })
+    }
+}
+
+fn make_getter(name: &str, output: ton_abi::ParamType) -> ton_abi::Function {
+    FunctionBuilder::new(name)
+        .abi_version(ABI_VERSION)
+        .default_headers()
+        .input("answerId", u32::param_type())
+        .output("value0", output)
+        .build()
+}
+
+const ABI_VERSION: ton_abi::contract::AbiVersion = ton_abi::contract::ABI_VERSION_2_2;
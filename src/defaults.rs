@@ -2,14 +2,18 @@ use std::collections::HashMap;
 
 use once_cell::race::OnceBox;
 
+use crate::config::NetworkPreset;
 use crate::util::parse_hex_or_base64;
 
 pub const DEFAULT_CURRENCY: &str = "EVER";
 
+pub const DEFAULT_DECIMALS: u8 = 9;
+
 pub const DEFAULT_NODE_REPO: &str = "https://github.com/everx-labs/ever-node.git";
+pub const DEFAULT_RELEASE_FEED: &str =
+    "https://api.github.com/repos/broxus/nodekeeper/releases/latest";
 
 pub const DEFAULT_CONTROL_PORT: u16 = 5031;
-pub const DEFAULT_LOCAL_ADNL_PORT: u16 = 0;
 pub const DEFAULT_ADNL_PORT: u16 = 30100;
 
 const ENV_CURRENCY: &str = "NODEKEEPER_CURRENCY";
@@ -18,6 +22,7 @@ const ENV_NODE_REPO: &str = "NODEKEEPER_NODE_REPO";
 #[derive(Copy, Clone)]
 pub struct Values {
     pub currency: &'static str,
+    pub decimals: u8,
     pub node_repo: &'static str,
 }
 
@@ -35,13 +40,48 @@ pub fn node_repo_from_env() -> Option<&'static str> {
         .as_deref()
 }
 
+/// Sets presets from the refreshed networks index
+pub fn set_network_presets(presets: Vec<NetworkPreset>) {
+    NETWORK_PRESETS.set(Box::new(presets)).ok();
+}
+
+/// Returns defaults of the network, refreshed presets take precedence over the builtin ones
+pub fn detect_network_defaults(zerostate_file_hash: &[u8; 32]) -> Option<Values> {
+    let builtin = detect_custom_defaults(zerostate_file_hash);
+    let Some(preset) = NETWORK_PRESETS.get().and_then(|presets| {
+        presets
+            .iter()
+            .find(|p| &p.zerostate_file_hash == zerostate_file_hash)
+    }) else {
+        return builtin;
+    };
+
+    let builtin = builtin.unwrap_or(Values {
+        currency: DEFAULT_CURRENCY,
+        decimals: DEFAULT_DECIMALS,
+        node_repo: DEFAULT_NODE_REPO,
+    });
+    Some(Values {
+        currency: preset.currency.as_deref().unwrap_or(builtin.currency),
+        decimals: preset.decimals.unwrap_or(builtin.decimals),
+        node_repo: preset.node_repo.as_deref().unwrap_or(builtin.node_repo),
+    })
+}
+
+static NETWORK_PRESETS: OnceBox<Vec<NetworkPreset>> = OnceBox::new();
+
 macro_rules! decl_known_networks {
-    ($ident:ident, { $($file_hash:literal => { currency: $currency:expr, node_repo: $node_repo:expr, }),*$(,)? }) => {
+    ($ident:ident, { $($file_hash:literal => {
+        currency: $currency:expr,
+        decimals: $decimals:expr,
+        node_repo: $node_repo:expr,
+    }),*$(,)? }) => {
         pub fn $ident(zerostate_file_hash: &[u8; 32]) -> Option<Values> {
             static KNOWN_NETWORKS: OnceBox<HashMap<[u8; 32], Values>> = OnceBox::new();
             KNOWN_NETWORKS.get_or_init(|| Box::new(HashMap::from([
                 $((parse_hex_or_base64($file_hash).unwrap().try_into().unwrap(), Values {
                     currency: $currency,
+                    decimals: $decimals,
                     node_repo: $node_repo,
                 })),*
             ])))
@@ -55,6 +95,7 @@ decl_known_networks! {
     detect_custom_defaults, {
         "ywj7H75tJ3PgbEeX+UNP3j0iR1x9imIIJJuQgrlCr8s=" => {
             currency: "VENOM",
+            decimals: 9,
             node_repo: "https://github.com/everx-labs/ever-node.git -f with_signature_id",
         },
     }
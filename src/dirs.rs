@@ -6,6 +6,7 @@ pub const VALIDATOR_SERVICE: &str = "validator";
 pub const VALIDATOR_MANAGER_SERVICE: &str = "validator-manager";
 pub const VALIDATOR_EXPORTER_SERVICE: &str = "validator-exporter";
 
+#[derive(Clone)]
 pub struct ProjectDirs {
     pub app_config: PathBuf,
     pub node_config: PathBuf,
@@ -19,6 +20,20 @@ pub struct ProjectDirs {
     pub keys_dir: PathBuf,
     pub validator_keys: PathBuf,
     pub depool_keys: PathBuf,
+    pub peers_cache: PathBuf,
+    pub subscription_state: PathBuf,
+    pub validation_journal: PathBuf,
+    pub bid_history: PathBuf,
+    pub incident_history: PathBuf,
+    pub performance_history: PathBuf,
+    pub rewards_ledger: PathBuf,
+    pub spending_ledger: PathBuf,
+    pub manager_heartbeat: PathBuf,
+    pub manager_socket: PathBuf,
+    pub networks_index: PathBuf,
+    pub fleet_inventory: PathBuf,
+    pub address_book: PathBuf,
+    pub logs_dir: PathBuf,
     pub root: PathBuf,
     pub validator_service: PathBuf,
     pub validator_manager_service: PathBuf,
@@ -63,6 +78,20 @@ impl ProjectDirs {
             keys_dir,
             validator_keys,
             depool_keys,
+            peers_cache: root.join("peers.json"),
+            subscription_state: root.join("subscription.json"),
+            validation_journal: root.join("journal.json"),
+            bid_history: root.join("bids.jsonl"),
+            incident_history: root.join("incidents.jsonl"),
+            performance_history: root.join("performance.json"),
+            rewards_ledger: root.join("rewards.jsonl"),
+            spending_ledger: root.join("spending.jsonl"),
+            manager_heartbeat: root.join("manager.heartbeat"),
+            manager_socket: root.join("manager.sock"),
+            networks_index: root.join("networks.json"),
+            fleet_inventory: root.join("fleet.toml"),
+            address_book: root.join("addresses.toml"),
+            logs_dir: root.join("logs"),
             root,
             validator_service,
             validator_manager_service,
@@ -70,6 +99,20 @@ impl ProjectDirs {
         }
     }
 
+    /// Keys and configs which are required to restore the validator
+    pub fn state_files(&self) -> [&PathBuf; 8] {
+        [
+            &self.app_config,
+            &self.node_config,
+            &self.node_log_config,
+            &self.global_config,
+            &self.validator_keys,
+            &self.depool_keys,
+            &self.validation_journal,
+            &self.networks_index,
+        ]
+    }
+
     pub fn default_root_dir() -> PathBuf {
         if let Ok(path) = std::env::var(ENV) {
             PathBuf::from(path)
@@ -0,0 +1,166 @@
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::Result;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio_util::sync::{CancellationToken, DropGuard};
+
+use crate::config::AppConfig;
+use crate::network::NodeStats;
+
+/// Serves the latest health report on `/healthz`
+pub struct HealthServer {
+    state: Arc<parking_lot::RwLock<HealthReport>>,
+    _cancellation_guard: DropGuard,
+}
+
+impl HealthServer {
+    pub async fn bind(addr: SocketAddr) -> Result<Self> {
+        let listener = tokio::net::TcpListener::bind(addr).await?;
+        let state = Arc::new(parking_lot::RwLock::new(HealthReport::default()));
+        let cancellation_token = CancellationToken::new();
+
+        tokio::spawn({
+            let state = state.clone();
+            let cancellation_token = cancellation_token.clone();
+            async move {
+                loop {
+                    let (mut stream, _) = tokio::select! {
+                        res = listener.accept() => match res {
+                            Ok(connection) => connection,
+                            Err(e) => {
+                                tracing::warn!("failed to accept health check connection: {e:?}");
+                                continue;
+                            }
+                        },
+                        _ = cancellation_token.cancelled() => break,
+                    };
+
+                    let state = state.clone();
+                    tokio::spawn(async move {
+                        let mut buffer = [0; 1024];
+                        let Ok(n) = stream.read(&mut buffer).await else {
+                            return;
+                        };
+                        let request = String::from_utf8_lossy(&buffer[..n]);
+                        let path = request.split_whitespace().nth(1).unwrap_or_default();
+
+                        let (status, body) = if path == "/healthz" {
+                            let report = state.read();
+                            let status = if report.healthy {
+                                "200 OK"
+                            } else {
+                                "503 Service Unavailable"
+                            };
+                            (status, report.body.clone())
+                        } else {
+                            ("404 Not Found", String::new())
+                        };
+
+                        let response = format!(
+                            "HTTP/1.1 {status}\r\n\
+                            Content-Type: application/json\r\n\
+                            Content-Length: {}\r\n\
+                            Connection: close\r\n\r\n{body}",
+                            body.len()
+                        );
+                        if let Err(e) = stream.write_all(response.as_bytes()).await {
+                            tracing::debug!("failed to send health check response: {e:?}");
+                        }
+                    });
+                }
+            }
+        });
+
+        Ok(Self {
+            state,
+            _cancellation_guard: cancellation_token.drop_guard(),
+        })
+    }
+
+    pub fn update(&self, report: HealthReport) {
+        *self.state.write() = report;
+    }
+}
+
+pub struct HealthReport {
+    healthy: bool,
+    body: String,
+}
+
+impl Default for HealthReport {
+    fn default() -> Self {
+        Self::failed("health was not checked yet")
+    }
+}
+
+impl HealthReport {
+    /// Checks the node status and the validation manager heartbeat
+    pub fn compute(
+        config: &AppConfig,
+        stats: &NodeStats,
+        heartbeat_path: &Path,
+        max_time_diff: i32,
+    ) -> Self {
+        const MAX_HEARTBEAT_AGE: u32 = 180;
+
+        let now = broxus_util::now();
+
+        let node_running = matches!(stats, NodeStats::Running(_));
+        let node_synced = match stats {
+            NodeStats::Running(stats) => {
+                stats.mc_time_diff <= max_time_diff && stats.sc_time_diff <= max_time_diff
+            }
+            NodeStats::NotReady(_) => false,
+        };
+
+        let last_heartbeat = std::fs::read_to_string(heartbeat_path)
+            .ok()
+            .and_then(|data| data.trim().parse::<u32>().ok());
+        let manager_alive = match (&config.validator, last_heartbeat) {
+            (None, _) => true,
+            (Some(_), Some(heartbeat)) => now.saturating_sub(heartbeat) <= MAX_HEARTBEAT_AGE,
+            (Some(_), None) => false,
+        };
+
+        let healthy = node_running && node_synced && manager_alive;
+        let body = serde_json::json!({
+            "healthy": healthy,
+            "checked_at": now,
+            "checks": {
+                "node_running": node_running,
+                "node_synced": {
+                    "ok": node_synced,
+                    "max_time_diff": max_time_diff,
+                    "mc_time_diff": match stats {
+                        NodeStats::Running(stats) => Some(stats.mc_time_diff),
+                        NodeStats::NotReady(_) => None,
+                    },
+                },
+                "manager_alive": {
+                    "ok": manager_alive,
+                    "validation_enabled": config.validator.is_some(),
+                    "last_heartbeat": last_heartbeat,
+                },
+            },
+        });
+
+        Self {
+            healthy,
+            body: body.to_string(),
+        }
+    }
+
+    pub fn failed(error: impl std::fmt::Display) -> Self {
+        let body = serde_json::json!({
+            "healthy": false,
+            "checked_at": broxus_util::now(),
+            "error": error.to_string(),
+        });
+        Self {
+            healthy: false,
+            body: body.to_string(),
+        }
+    }
+}
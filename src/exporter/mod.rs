@@ -4,28 +4,51 @@ use anyhow::Result;
 use pomfrit::formatter::DisplayPrometheusExt;
 
 pub use self::file_target::FileExporterTarget;
+pub use self::health::{HealthReport, HealthServer};
 pub use self::http_target::HttpExporterTarget;
 pub use self::stdout_target::StdoutExporterTarget;
 use crate::config::{AppConfig, AppConfigValidator, DePoolType};
 use crate::dirs::ProjectDirs;
-use crate::network::{NodeStats, NodeTcpRpc, ValidatorSetEntry};
+use crate::network::{NodeStats, NodeTcpRpc, SessionStats, TcpAdnlCounters, ValidatorSetEntry};
+use crate::node_logs::{NodeLogStats, NodeLogWatcher};
+use crate::node_metrics::{self, NodeMetrics};
 
 mod file_target;
+mod health;
 mod http_target;
 mod stdout_target;
 
 pub struct Exporter {
     dirs: ProjectDirs,
     targets: Vec<Box<dyn ExporterTarget>>,
+    health: Option<(HealthServer, i32)>,
+    node_logs: Option<NodeLogWatcher>,
 }
 
 impl Exporter {
     pub fn new(dirs: ProjectDirs, targets: Vec<Box<dyn ExporterTarget>>) -> Self {
-        Self { dirs, targets }
+        Self {
+            dirs,
+            targets,
+            health: None,
+            node_logs: None,
+        }
+    }
+
+    /// Exports info parsed from the node logs
+    pub fn with_node_logs(mut self, watcher: NodeLogWatcher) -> Self {
+        self.node_logs = Some(watcher);
+        self
+    }
+
+    /// Updates the health report after each metrics collection
+    pub fn with_health(mut self, server: HealthServer, max_time_diff: i32) -> Self {
+        self.health = Some((server, max_time_diff));
+        self
     }
 
     pub async fn serve(self, interval: Duration) {
-        if self.targets.is_empty() {
+        if self.targets.is_empty() && self.health.is_none() {
             return;
         }
 
@@ -38,12 +61,14 @@ impl Exporter {
                 Err((e, fallback)) => {
                     tracing::error!("failed to prepare exporter: {e:?}");
                     self.export(&fallback);
+                    self.update_health(|| HealthReport::failed(&e));
                     continue;
                 }
             };
 
             if let Err(e) = self.collect(&config, &node_rpc).await {
                 tracing::error!("failed to collect metrics: {e:?}");
+                self.update_health(|| HealthReport::failed(&e));
             }
         }
     }
@@ -61,16 +86,51 @@ impl Exporter {
 
         tracing::debug!("collected node stats");
 
+        let node_logs = self.node_logs.as_ref().map(NodeLogWatcher::stats);
+
+        // NOTE: node metrics are optional and must not break the exporter
+        let node_metrics = match node_metrics::scrape_configured(&self.dirs.node_config).await {
+            Ok(metrics) => metrics,
+            Err(e) => {
+                tracing::debug!("failed to scrape node metrics: {e:?}");
+                None
+            }
+        };
+
+        // NOTE: session stats are not supported by all node versions
+        let sessions = match node_rpc.get_sessions_stats().await {
+            Ok(sessions) => Some(sessions),
+            Err(e) => {
+                tracing::debug!("failed to get validator sessions stats: {e:?}");
+                None
+            }
+        };
+
         let metrics = Metrics {
             collected_at,
             config,
             stats: &stats,
+            sessions: sessions.as_deref(),
+            node_logs: node_logs.as_ref(),
+            node_metrics: node_metrics.as_ref(),
+            control_counters: TcpAdnlCounters::get(),
         };
         self.export(&metrics);
 
+        if let Some((_, max_time_diff)) = &self.health {
+            let heartbeat = &self.dirs.manager_heartbeat;
+            self.update_health(|| HealthReport::compute(config, &stats, heartbeat, *max_time_diff));
+        }
+
         Ok(())
     }
 
+    fn update_health<F: FnOnce() -> HealthReport>(&self, f: F) {
+        if let Some((server, _)) = &self.health {
+            server.update(f());
+        }
+    }
+
     fn export(&self, metrics: &dyn std::fmt::Display) {
         for target in &self.targets {
             if let Err(e) = target.write(metrics) {
@@ -119,6 +179,10 @@ struct Metrics<'a> {
     collected_at: u32,
     config: &'a AppConfig,
     stats: &'a NodeStats,
+    sessions: Option<&'a [SessionStats]>,
+    node_logs: Option<&'a NodeLogStats>,
+    node_metrics: Option<&'a NodeMetrics>,
+    control_counters: TcpAdnlCounters,
 }
 
 impl std::fmt::Display for Metrics<'_> {
@@ -131,6 +195,22 @@ impl std::fmt::Display for Metrics<'_> {
 
         f.begin_metric("collected_at").value(self.collected_at)?;
 
+        let counters = &self.control_counters;
+        f.begin_metric("control_invalid_packets")
+            .value(counters.invalid_packets)?;
+        f.begin_metric("control_late_answers")
+            .value(counters.late_answers)?;
+        f.begin_metric("control_unknown_answers")
+            .value(counters.unknown_answers)?;
+
+        if let Some(node_logs) = self.node_logs {
+            write_node_logs_metrics(f, node_logs)?;
+        }
+
+        if let Some(node_metrics) = self.node_metrics {
+            node_metrics.write_prometheus(f)?;
+        }
+
         let stats = match self.stats {
             NodeStats::NotReady(sync_status) => {
                 return f
@@ -196,6 +276,10 @@ impl std::fmt::Display for Metrics<'_> {
                 .value(1)?,
         };
 
+        if let Some(sessions) = self.sessions {
+            write_sessions_metrics(f, sessions)?;
+        }
+
         const VALIDATION_ENABLED: &str = "validation_enabled";
         const VALIDATOR_TYPE: &str = "validator_type";
 
@@ -224,6 +308,55 @@ impl std::fmt::Display for Metrics<'_> {
     }
 }
 
+fn write_node_logs_metrics(
+    f: &mut std::fmt::Formatter<'_>,
+    node_logs: &NodeLogStats,
+) -> std::fmt::Result {
+    f.begin_metric("node_log_lines").value(node_logs.lines)?;
+    if let Some(progress) = node_logs.sync_progress {
+        f.begin_metric("node_log_sync_progress").value(progress)?;
+    }
+    if let Some(seqno) = node_logs.last_applied_mc_seqno {
+        f.begin_metric("node_log_applied_mc_seqno").value(seqno)?;
+    }
+    if let Some(seqno) = node_logs.last_applied_sc_seqno {
+        f.begin_metric("node_log_applied_sc_seqno").value(seqno)?;
+    }
+    for (kind, count) in &node_logs.errors {
+        f.begin_metric("node_log_errors")
+            .label("kind", kind)
+            .value(count)?;
+    }
+    Ok(())
+}
+
+fn write_sessions_metrics(
+    f: &mut std::fmt::Formatter<'_>,
+    sessions: &[SessionStats],
+) -> std::fmt::Result {
+    f.begin_metric("validator_sessions").value(sessions.len())?;
+    for session in sessions {
+        let session_id = hex::encode(session.session_id);
+        let metrics = [
+            ("validator_session_rounds", session.rounds),
+            ("validator_session_blocks_approved", session.blocks_approved),
+            ("validator_session_blocks_signed", session.blocks_signed),
+            ("validator_session_blocks_collated", session.blocks_collated),
+            (
+                "validator_session_collation_failures",
+                session.collation_failures,
+            ),
+        ];
+        for (name, value) in metrics {
+            f.begin_metric(name)
+                .label("session_id", &session_id)
+                .label("shard", &session.shard)
+                .value(value)?;
+        }
+    }
+    Ok(())
+}
+
 impl DePoolType {
     fn into_u8(self) -> u8 {
         match self {
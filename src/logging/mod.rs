@@ -0,0 +1,111 @@
+use anyhow::{Context, Result};
+use dialoguer::console;
+use tracing::Subscriber;
+use tracing_subscriber::filter::{Filtered, LevelFilter, Targets};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::Layer;
+
+use self::otlp::OtlpLayer;
+pub use self::rotating_file::log_file_path;
+use self::rotating_file::RotatingFile;
+use crate::config::{AppConfig, AppConfigLogging, AppConfigTelemetry, LogFormat};
+use crate::dirs::ProjectDirs;
+
+mod json;
+mod otlp;
+mod rotating_file;
+
+/// Initializes logger for the command.
+///
+/// Long-running commands (with `log_name`) also use the `logging` and `telemetry`
+/// sections from the app config.
+pub fn init(dirs: &ProjectDirs, log_name: Option<&str>) -> Result<()> {
+    let config = match log_name {
+        // NOTE: invalid config is reported by the command itself
+        Some(name) => AppConfig::load(&dirs.app_config)
+            .ok()
+            .map(|config| (name, config)),
+        None => None,
+    };
+
+    let Some((name, config)) = config else {
+        init_default();
+        return Ok(());
+    };
+
+    let telemetry = config.telemetry;
+    let Some(config) = config.logging else {
+        match telemetry {
+            Some(telemetry) => tracing_subscriber::registry()
+                .with(tracing_subscriber::fmt::layer().with_filter(LevelFilter::INFO))
+                .with(otlp_layer(name, &telemetry)?)
+                .init(),
+            None => init_default(),
+        }
+        return Ok(());
+    };
+
+    let filter = config
+        .level
+        .parse::<Targets>()
+        .context("invalid log level")?;
+
+    let file = RotatingFile::new(config.logs_dir(dirs), name, &config)?;
+
+    let file_layer = match config.format {
+        LogFormat::Pretty => tracing_subscriber::fmt::layer()
+            .with_ansi(false)
+            .with_writer(file)
+            .boxed(),
+        LogFormat::Json => tracing_subscriber::fmt::layer()
+            .event_format(json::JsonFormat)
+            .with_writer(file)
+            .boxed(),
+    };
+    let stderr_layer = config.stderr.then(|| {
+        tracing_subscriber::fmt::layer()
+            .with_writer(std::io::stderr)
+            .boxed()
+    });
+
+    let otlp_layer = match telemetry {
+        Some(telemetry) => Some(otlp_layer(name, &telemetry)?),
+        None => None,
+    };
+
+    tracing_subscriber::registry()
+        .with(file_layer.and_then(stderr_layer).with_filter(filter))
+        .with(otlp_layer)
+        .init();
+
+    tracing::info!(name, format = ?config.format, "started logging to file");
+    Ok(())
+}
+
+fn otlp_layer<S>(name: &str, config: &AppConfigTelemetry) -> Result<Filtered<OtlpLayer, Targets, S>>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    let filter = config
+        .level
+        .parse::<Targets>()
+        .context("invalid telemetry level")?;
+    Ok(OtlpLayer::spawn(name, config)?.with_filter(filter))
+}
+
+fn init_default() {
+    if console::user_attended() {
+        tracing_subscriber::fmt::init();
+    } else {
+        tracing_subscriber::fmt::fmt().without_time().init();
+    }
+}
+
+impl AppConfigLogging {
+    /// Log files directory
+    pub fn logs_dir(&self, dirs: &ProjectDirs) -> std::path::PathBuf {
+        self.dir.clone().unwrap_or_else(|| dirs.logs_dir.clone())
+    }
+}
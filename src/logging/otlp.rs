@@ -0,0 +1,312 @@
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context as _, Result};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, CONTENT_TYPE};
+use tokio::sync::mpsc;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::Layer;
+
+use crate::config::AppConfigTelemetry;
+
+/// Exports closed spans over OTLP/HTTP (JSON encoding).
+///
+/// Spans are sent by the background task in batches, new spans are dropped
+/// when the queue is full.
+pub struct OtlpLayer {
+    tx: mpsc::Sender<serde_json::Value>,
+}
+
+impl OtlpLayer {
+    pub fn spawn(name: &str, config: &AppConfigTelemetry) -> Result<Self> {
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        for (key, value) in &config.headers {
+            let key = HeaderName::try_from(key).context("invalid telemetry header name")?;
+            let value = HeaderValue::try_from(value).context("invalid telemetry header value")?;
+            headers.insert(key, value);
+        }
+
+        let client = reqwest::Client::builder()
+            .user_agent(concat!("nodekeeper/", env!("CARGO_PKG_VERSION")))
+            .default_headers(headers)
+            .build()?;
+
+        let resource = serde_json::json!({
+            "attributes": [
+                attribute("service.name", config.service_name.as_deref().unwrap_or(name)),
+                attribute("service.version", env!("CARGO_PKG_VERSION")),
+            ]
+        });
+
+        let batch_size = std::cmp::max(config.batch_size, 1);
+        let (tx, rx) = mpsc::channel(batch_size * QUEUE_BATCHES);
+        tokio::spawn(export_spans(
+            rx,
+            client,
+            config.endpoint.clone(),
+            resource,
+            batch_size,
+            config.export_interval,
+        ));
+
+        Ok(Self { tx })
+    }
+}
+
+impl<S> Layer<S> for OtlpLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+
+        // Continue the trace of the parent span
+        let parent = span.parent().and_then(|parent| {
+            let extensions = parent.extensions();
+            let parent = extensions.get::<SpanState>()?;
+            Some((parent.trace_id, parent.span_id))
+        });
+        let (trace_id, parent_span_id) = match parent {
+            Some((trace_id, span_id)) => (trace_id, Some(span_id)),
+            None => (rand::random(), None),
+        };
+
+        let mut attributes = AttributesVisitor::default();
+        attrs.record(&mut attributes);
+
+        span.extensions_mut().insert(SpanState {
+            trace_id,
+            span_id: rand::random(),
+            parent_span_id,
+            start_time: unix_nanos(),
+            attributes: attributes.attributes,
+            events: Vec::new(),
+            error: None,
+        });
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        if let Some(state) = span.extensions_mut().get_mut::<SpanState>() {
+            let mut attributes = AttributesVisitor {
+                attributes: std::mem::take(&mut state.attributes),
+                message: None,
+            };
+            values.record(&mut attributes);
+            state.attributes = attributes.attributes;
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let Some(span) = ctx.event_span(event) else {
+            return;
+        };
+        let mut extensions = span.extensions_mut();
+        let Some(state) = extensions.get_mut::<SpanState>() else {
+            return;
+        };
+
+        let level = *event.metadata().level();
+        let mut attributes = AttributesVisitor {
+            attributes: vec![attribute("level", level.as_str())],
+            message: None,
+        };
+        event.record(&mut attributes);
+
+        let name = attributes.message.unwrap_or_default();
+        if level == Level::ERROR {
+            state.error = Some(name.clone());
+        }
+        if state.events.len() < MAX_EVENTS {
+            state.events.push(serde_json::json!({
+                "timeUnixNano": unix_nanos().to_string(),
+                "name": name,
+                "attributes": attributes.attributes,
+            }));
+        }
+    }
+
+    fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(&id) else {
+            return;
+        };
+        let Some(state) = span.extensions_mut().remove::<SpanState>() else {
+            return;
+        };
+
+        let metadata = span.metadata();
+        let mut attributes = state.attributes;
+        attributes.push(attribute("code.namespace", metadata.target()));
+
+        let status = match state.error {
+            Some(message) => serde_json::json!({ "code": 2, "message": message }),
+            None => serde_json::json!({}),
+        };
+
+        let span = serde_json::json!({
+            "traceId": hex::encode(state.trace_id),
+            "spanId": hex::encode(state.span_id),
+            "parentSpanId": state.parent_span_id.map(hex::encode).unwrap_or_default(),
+            "name": metadata.name(),
+            "kind": 1,
+            "startTimeUnixNano": state.start_time.to_string(),
+            "endTimeUnixNano": unix_nanos().to_string(),
+            "attributes": attributes,
+            "events": state.events,
+            "status": status,
+        });
+
+        // NOTE: spans are dropped while the collector is unavailable
+        self.tx.try_send(span).ok();
+    }
+}
+
+async fn export_spans(
+    mut rx: mpsc::Receiver<serde_json::Value>,
+    client: reqwest::Client,
+    endpoint: reqwest::Url,
+    resource: serde_json::Value,
+    batch_size: usize,
+    export_interval: std::time::Duration,
+) {
+    let mut interval = tokio::time::interval(export_interval);
+    let mut batch = Vec::with_capacity(batch_size);
+    let mut last_failed = false;
+
+    loop {
+        tokio::select! {
+            span = rx.recv() => match span {
+                Some(span) => {
+                    batch.push(span);
+                    if batch.len() < batch_size {
+                        continue;
+                    }
+                }
+                None => break,
+            },
+            _ = interval.tick() => {},
+        }
+
+        if batch.is_empty() {
+            continue;
+        }
+
+        let body = serde_json::json!({
+            "resourceSpans": [{
+                "resource": resource,
+                "scopeSpans": [{
+                    "scope": {
+                        "name": env!("CARGO_PKG_NAME"),
+                        "version": env!("CARGO_PKG_VERSION"),
+                    },
+                    "spans": std::mem::take(&mut batch),
+                }]
+            }]
+        });
+
+        let res = client
+            .post(endpoint.clone())
+            .body(body.to_string())
+            .send()
+            .await
+            .and_then(|res| res.error_for_status());
+
+        // Report only the first failure in a row
+        match res {
+            Ok(_) => last_failed = false,
+            Err(e) if !last_failed => {
+                last_failed = true;
+                tracing::warn!("failed to export spans: {e:?}");
+            }
+            Err(_) => {}
+        }
+    }
+}
+
+/// How many full batches can be queued
+const QUEUE_BATCHES: usize = 4;
+/// Max number of events in one span
+const MAX_EVENTS: usize = 128;
+
+struct SpanState {
+    trace_id: [u8; 16],
+    span_id: [u8; 8],
+    parent_span_id: Option<[u8; 8]>,
+    start_time: u128,
+    attributes: Vec<serde_json::Value>,
+    events: Vec<serde_json::Value>,
+    error: Option<String>,
+}
+
+#[derive(Default)]
+struct AttributesVisitor {
+    attributes: Vec<serde_json::Value>,
+    message: Option<String>,
+}
+
+impl AttributesVisitor {
+    fn push(&mut self, field: &Field, value: serde_json::Value) {
+        self.attributes.push(serde_json::json!({
+            "key": field.name(),
+            "value": value,
+        }));
+    }
+}
+
+impl Visit for AttributesVisitor {
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.push(field, serde_json::json!({ "doubleValue": value }));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.push(field, serde_json::json!({ "intValue": value.to_string() }));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.push(field, serde_json::json!({ "intValue": value.to_string() }));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.push(field, serde_json::json!({ "boolValue": value }));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message = Some(value.to_owned());
+        } else {
+            self.push(field, serde_json::json!({ "stringValue": value }));
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        let value = format!("{value:?}");
+        if field.name() == "message" {
+            self.message = Some(value);
+        } else {
+            self.push(field, serde_json::json!({ "stringValue": value }));
+        }
+    }
+}
+
+fn attribute(key: &str, value: &str) -> serde_json::Value {
+    serde_json::json!({
+        "key": key,
+        "value": { "stringValue": value },
+    })
+}
+
+fn unix_nanos() -> u128 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos()
+}
@@ -0,0 +1,166 @@
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use broxus_util::now;
+use tracing_subscriber::fmt::MakeWriter;
+
+use crate::config::AppConfigLogging;
+
+/// Log file which is rotated by size and time
+pub struct RotatingFile {
+    state: parking_lot::Mutex<State>,
+}
+
+struct State {
+    dir: PathBuf,
+    name: String,
+    max_file_size: u64,
+    period: Option<u32>,
+    max_files: usize,
+    file: Option<File>,
+    size: u64,
+    current_period: u32,
+}
+
+impl RotatingFile {
+    pub fn new(dir: PathBuf, name: &str, params: &AppConfigLogging) -> Result<Self> {
+        std::fs::create_dir_all(&dir).context("failed to create logs directory")?;
+
+        let path = log_file_path(&dir, name);
+        let (file, size) = open_log_file(&path)?;
+
+        let period = params.rotation.period();
+        Ok(Self {
+            state: parking_lot::Mutex::new(State {
+                dir,
+                name: name.to_owned(),
+                max_file_size: params.max_file_size,
+                period,
+                max_files: params.max_files,
+                file: Some(file),
+                size,
+                current_period: period.map(|period| now() / period).unwrap_or_default(),
+            }),
+        })
+    }
+}
+
+/// Returns the path of the current log file
+pub fn log_file_path(dir: &Path, name: &str) -> PathBuf {
+    dir.join(format!("{name}.log"))
+}
+
+impl<'a> MakeWriter<'a> for RotatingFile {
+    type Writer = &'a RotatingFile;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        self
+    }
+}
+
+impl Write for &RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let mut state = self.state.lock();
+        state.rotate_if_needed(buf.len() as u64)?;
+
+        let file = match &mut state.file {
+            Some(file) => file,
+            None => return Ok(buf.len()),
+        };
+        let n = file.write(buf)?;
+        state.size += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match &mut self.state.lock().file {
+            Some(file) => file.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+impl State {
+    fn rotate_if_needed(&mut self, incoming: u64) -> std::io::Result<()> {
+        let now = now();
+
+        let period_changed = match self.period {
+            Some(period) => now / period != self.current_period,
+            None => false,
+        };
+        let size_exceeded = self.size > 0 && self.size + incoming > self.max_file_size;
+        if !period_changed && !size_exceeded {
+            return Ok(());
+        }
+
+        if let Some(period) = self.period {
+            self.current_period = now / period;
+        }
+
+        // Close the current file before renaming
+        self.file = None;
+
+        let path = log_file_path(&self.dir, &self.name);
+        let mut rotated = self.dir.join(format!("{}.log.{now}", self.name));
+        let mut i = 1;
+        while rotated.exists() {
+            rotated = self.dir.join(format!("{}.log.{now}-{i}", self.name));
+            i += 1;
+        }
+        let res = std::fs::rename(&path, rotated);
+        if res.is_ok() {
+            self.remove_old_files();
+        }
+
+        // NOTE: reopen the file even if the rename failed
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?;
+        self.size = file.metadata()?.len();
+        self.file = Some(file);
+        res
+    }
+
+    fn remove_old_files(&self) {
+        let prefix = format!("{}.log.", self.name);
+
+        let mut rotated = match std::fs::read_dir(&self.dir) {
+            Ok(entries) => entries
+                .filter_map(|entry| {
+                    let path = entry.ok()?.path();
+                    let name = path.file_name()?.to_str()?;
+                    name.starts_with(&prefix).then(|| path.clone())
+                })
+                .collect::<Vec<_>>(),
+            Err(_) => return,
+        };
+        if rotated.len() <= self.max_files {
+            return;
+        }
+
+        // NOTE: file names contain rotation timestamps
+        rotated.sort();
+        for path in &rotated[..rotated.len() - self.max_files] {
+            // NOTE: tracing can't be used here
+            if let Err(e) = std::fs::remove_file(path) {
+                eprintln!("failed to remove old log file {}: {e:?}", path.display());
+            }
+        }
+    }
+}
+
+fn open_log_file(path: &Path) -> Result<(File, u64)> {
+    let file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .context("failed to open log file")?;
+    let size = file
+        .metadata()
+        .context("failed to get log file size")?
+        .len();
+    Ok((file, size))
+}
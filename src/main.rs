@@ -1,6 +1,3 @@
-use anyhow::Result;
-use dialoguer::console;
-
 #[macro_export]
 macro_rules! once {
     ($ty:path, || $expr:expr) => {{
@@ -40,19 +37,19 @@ mod crypto;
 mod defaults;
 mod dirs;
 mod exporter;
+mod logging;
 mod network;
+mod node_logs;
+mod node_metrics;
+mod notifications;
 mod util;
 mod validator;
 
 #[tokio::main]
-async fn main() -> Result<()> {
-    if console::user_attended() {
-        tracing_subscriber::fmt::init();
-    } else {
-        tracing_subscriber::fmt::fmt().without_time().init();
+async fn main() {
+    if let Err(e) = argh::from_env::<ArgsOrVersion<cli::App>>().0.run().await {
+        std::process::exit(cli::report_error(&e));
     }
-
-    argh::from_env::<ArgsOrVersion<cli::App>>().0.run().await
 }
 
 struct ArgsOrVersion<T: argh::FromArgs>(T);
@@ -0,0 +1,306 @@
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use tokio_util::sync::CancellationToken;
+use ton_block::{Deserializable, Serializable};
+
+use super::node_tcp_rpc::{ConfigWithId, NodeTcpRpc};
+use super::node_udp_rpc::NodeUdpRpc;
+use super::subscription::Subscription;
+use crate::config::AppConfig;
+use crate::dirs::ProjectDirs;
+use crate::util::TransactionWithHash;
+
+/// Read-only access to the blockchain state
+#[async_trait::async_trait]
+pub trait DataSource: Send + Sync {
+    async fn get_account_state(
+        &self,
+        address: &ton_block::MsgAddressInt,
+    ) -> Result<Option<ton_block::AccountStuff>>;
+
+    async fn get_blockchain_config(&self) -> Result<ton_block::ConfigParams>;
+}
+
+/// Connects to the local node or to the fallback JRPC endpoint
+/// if the node is not available or not synced.
+pub async fn connect_data_source(config: &AppConfig) -> Result<Box<dyn DataSource>> {
+    let node_tcp_rpc = async {
+        let node_tcp_rpc = NodeTcpRpc::new(config.control()?).await?;
+        node_tcp_rpc.get_stats().await?.try_into_running()?;
+        Ok::<_, anyhow::Error>(node_tcp_rpc)
+    };
+
+    match (node_tcp_rpc.await, &config.fallback_jrpc) {
+        (Ok(node_tcp_rpc), _) => Ok(Box::new(node_tcp_rpc)),
+        (Err(e), Some(endpoint)) => {
+            tracing::warn!(%endpoint, "local node is not available, using fallback JRPC: {e:?}");
+            Ok(Box::new(JrpcClient::new(endpoint.clone())))
+        }
+        (Err(e), None) => Err(e),
+    }
+}
+
+/// Creates a subscription over the local node or over the fallback JRPC endpoint
+/// if the node control server is not available.
+///
+/// NOTE: operations which require the node itself (e.g. signing with
+/// the validator keys) fail in the fallback mode.
+pub async fn connect_subscription(
+    config: &AppConfig,
+    dirs: &ProjectDirs,
+    cancellation: CancellationToken,
+) -> Result<Arc<Subscription>> {
+    let node_rpc = async {
+        let node_tcp_rpc = NodeTcpRpc::new(config.control()?)
+            .await
+            .context("failed to build node TCP client")?
+            .with_cancellation(cancellation.clone());
+        node_tcp_rpc.get_stats().await?.try_into_running()?;
+        let node_udp_rpc = NodeUdpRpc::new(config.adnl()?, dirs)
+            .await
+            .context("failed to build node UDP client")?
+            .with_cancellation(cancellation);
+        Ok::<_, anyhow::Error>((node_tcp_rpc, node_udp_rpc))
+    };
+
+    match (node_rpc.await, &config.fallback_jrpc) {
+        (Ok((node_tcp_rpc, node_udp_rpc)), _) => Ok(Subscription::new(node_tcp_rpc, node_udp_rpc)),
+        (Err(e), Some(endpoint)) => {
+            tracing::warn!(%endpoint, "local node is not available, using fallback JRPC: {e:?}");
+            Ok(Subscription::new_remote(JrpcClient::new(endpoint.clone())))
+        }
+        (Err(e), None) => Err(e),
+    }
+}
+
+#[async_trait::async_trait]
+impl DataSource for NodeTcpRpc {
+    async fn get_account_state(
+        &self,
+        address: &ton_block::MsgAddressInt,
+    ) -> Result<Option<ton_block::AccountStuff>> {
+        let state = self
+            .get_shard_account_state(address)
+            .await
+            .context("failed to get shard account state")?;
+        match state
+            .read_account()
+            .context("failed to read account state")?
+        {
+            ton_block::Account::Account(state) => Ok(Some(state)),
+            ton_block::Account::AccountNone => Ok(None),
+        }
+    }
+
+    async fn get_blockchain_config(&self) -> Result<ton_block::ConfigParams> {
+        let ConfigWithId { config, .. } = self.get_config_all().await?;
+        Ok(config)
+    }
+}
+
+/// Minimal ever-jrpc client
+pub struct JrpcClient {
+    client: reqwest::Client,
+    endpoint: reqwest::Url,
+}
+
+impl JrpcClient {
+    pub fn new(endpoint: reqwest::Url) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            endpoint,
+        }
+    }
+
+    /// Broadcasts an external message
+    pub async fn send_message(&self, message: &ton_block::Message) -> Result<()> {
+        let message = base64::encode(ton_types::serialize_toc(&message.serialize()?)?);
+        self.request_optional::<serde_json::Value>(
+            "sendMessage",
+            serde_json::json!({ "message": message }),
+        )
+        .await
+        .map(|_| ())
+    }
+
+    /// Returns the transaction which was produced by the inbound message
+    pub async fn get_dst_transaction(
+        &self,
+        msg_hash: &ton_types::UInt256,
+    ) -> Result<Option<TransactionWithHash>> {
+        let transaction: Option<String> = self
+            .request_optional(
+                "getDstTransaction",
+                serde_json::json!({ "messageHash": msg_hash.to_hex_string() }),
+            )
+            .await?;
+
+        let Some(transaction) = transaction else {
+            return Ok(None);
+        };
+        let cell = base64::decode(transaction)
+            .ok()
+            .and_then(|data| ton_types::deserialize_tree_of_cells(&mut data.as_slice()).ok())
+            .ok_or(JrpcError::InvalidTransaction)?;
+        let hash = cell.repr_hash();
+        let data = ton_block::Transaction::construct_from_cell(cell)
+            .map_err(|_| JrpcError::InvalidTransaction)?;
+        Ok(Some(TransactionWithHash { hash, data }))
+    }
+
+    /// Returns the blockchain config with the id of the key block
+    /// (only `seqno` is known, hashes are zero) and the network global id
+    pub async fn get_blockchain_config_with_id(&self) -> Result<(ConfigWithId, i32)> {
+        let BlockchainConfig {
+            global_id,
+            config,
+            seqno,
+        } = self
+            .request("getBlockchainConfig", serde_json::json!({}))
+            .await?;
+
+        let config = ton_block::ConfigParams::construct_from_base64(&config)
+            .map_err(|_| JrpcError::InvalidBlockchainConfig)?;
+        let block_id = ton_block::BlockIdExt {
+            shard_id: ton_block::ShardIdent::masterchain(),
+            seq_no: seqno,
+            root_hash: Default::default(),
+            file_hash: Default::default(),
+        };
+        let global_id = global_id.context("global id not found in the JRPC response")?;
+        Ok((ConfigWithId { block_id, config }, global_id))
+    }
+
+    async fn request<T>(&self, method: &str, params: serde_json::Value) -> Result<T>
+    where
+        for<'de> T: Deserialize<'de>,
+    {
+        match self.request_optional(method, params).await? {
+            Some(result) => Ok(result),
+            None => Err(JrpcError::EmptyResponse.into()),
+        }
+    }
+
+    #[tracing::instrument(level = "debug", skip_all, fields(method = method))]
+    async fn request_optional<T>(
+        &self,
+        method: &str,
+        params: serde_json::Value,
+    ) -> Result<Option<T>>
+    where
+        for<'de> T: Deserialize<'de>,
+    {
+        #[derive(Deserialize)]
+        struct Response<T> {
+            result: Option<T>,
+            error: Option<ResponseError>,
+        }
+
+        #[derive(Deserialize)]
+        struct ResponseError {
+            code: i32,
+            message: String,
+        }
+
+        let body = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": method,
+            "params": params,
+        });
+
+        let response = self
+            .client
+            .post(self.endpoint.clone())
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(body.to_string())
+            .send()
+            .await
+            .context("failed to send JRPC request")?
+            .error_for_status()?
+            .bytes()
+            .await
+            .context("failed to read JRPC response")?;
+
+        match serde_json::from_slice::<Response<T>>(&response).context("invalid JRPC response")? {
+            Response {
+                error: Some(error), ..
+            } => Err(JrpcError::RequestFailed {
+                code: error.code,
+                message: error.message,
+            }
+            .into()),
+            Response { result, .. } => Ok(result),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl DataSource for JrpcClient {
+    async fn get_account_state(
+        &self,
+        address: &ton_block::MsgAddressInt,
+    ) -> Result<Option<ton_block::AccountStuff>> {
+        #[derive(Deserialize)]
+        #[serde(tag = "type", rename_all = "camelCase")]
+        enum ContractState {
+            NotExists,
+            Exists { account: String },
+        }
+
+        let state: ContractState = self
+            .request(
+                "getContractState",
+                serde_json::json!({ "address": address.to_string() }),
+            )
+            .await?;
+
+        match state {
+            ContractState::NotExists => Ok(None),
+            ContractState::Exists { account } => {
+                match ton_block::Account::construct_from_base64(&account)
+                    .map_err(|_| JrpcError::InvalidAccountState)?
+                {
+                    ton_block::Account::Account(state) => Ok(Some(state)),
+                    ton_block::Account::AccountNone => Ok(None),
+                }
+            }
+        }
+    }
+
+    async fn get_blockchain_config(&self) -> Result<ton_block::ConfigParams> {
+        let BlockchainConfig { config, .. } = self
+            .request("getBlockchainConfig", serde_json::json!({}))
+            .await?;
+
+        ton_block::ConfigParams::construct_from_base64(&config)
+            .map_err(|_| JrpcError::InvalidBlockchainConfig.into())
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BlockchainConfig {
+    #[serde(default)]
+    global_id: Option<i32>,
+    config: String,
+    /// Seqno of the key block with this config
+    #[serde(default)]
+    seqno: u32,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum JrpcError {
+    #[error("JRPC request failed with code {code}: {message}")]
+    RequestFailed { code: i32, message: String },
+    #[error("empty JRPC response")]
+    EmptyResponse,
+    #[error("invalid account state")]
+    InvalidAccountState,
+    #[error("invalid blockchain config")]
+    InvalidBlockchainConfig,
+    #[error("invalid transaction")]
+    InvalidTransaction,
+}
@@ -1,7 +1,12 @@
+pub use self::data_source::{connect_data_source, connect_subscription, DataSource};
 pub use self::node_tcp_rpc::*;
-pub use self::node_udp_rpc::NodeUdpRpc;
-pub use self::subscription::Subscription;
+pub use self::node_udp_rpc::{announce_address, NodeUdpRpc};
+pub use self::subscription::{
+    AccountStatesRx, ConfigChangedEvent, LocalNodeRequired, SendMessageError, Subscription,
+    TransactionOutcome,
+};
 
+mod data_source;
 mod node_tcp_rpc;
 mod node_udp_rpc;
 mod subscription;
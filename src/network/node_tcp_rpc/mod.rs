@@ -1,12 +1,15 @@
+use std::sync::Arc;
 use std::time::Duration;
 
 use anyhow::Result;
 use everscale_crypto::ed25519;
 use tl_proto::{IntermediateBytes, TlRead, TlWrite};
+use tokio_util::sync::CancellationToken;
 
-use self::stats::StatsError;
-pub use self::stats::{NodeStats, ValidatorSetEntry};
-use self::tcp_adnl::{TcpAdnl, TcpAdnlConfig, TcpAdnlError};
+use self::stats::NodeVersion;
+pub use self::stats::{NodeStats, SessionStats, StatsError, ValidatorSetEntry};
+use self::tcp_adnl::{TcpAdnl, TcpAdnlConfig};
+pub use self::tcp_adnl::{TcpAdnlCounters, TcpAdnlError};
 use crate::config::AppConfigControl;
 
 mod proto;
@@ -15,29 +18,55 @@ mod tcp_adnl;
 
 #[derive(Clone)]
 pub struct NodeTcpRpc {
-    tcp_adnl: TcpAdnl,
+    connection: Arc<Connection>,
     query_timeout: Duration,
+    cancellation: CancellationToken,
 }
 
 impl NodeTcpRpc {
     pub async fn new(config: &AppConfigControl) -> Result<Self> {
-        let tcp_adnl = TcpAdnl::connect(TcpAdnlConfig {
-            server_address: config.server_address.into(),
-            server_pubkey: config.server_pubkey,
-            client_secret: config.client_secret,
-            connection_timeout: config.connection_timeout,
-        })
-        .await
-        .map_err(NodeRpcError::ConnectionFailed)?;
-
         let query_timeout = config.query_timeout;
 
+        let connection = Connection::establish(
+            TcpAdnlConfig {
+                server_address: config.server_address.into(),
+                server_pubkey: config.server_pubkey,
+                client_secret: *config.client_secret,
+                connection_timeout: config.connection_timeout,
+                keepalive_interval: config.keepalive_interval,
+                idle_timeout: config.idle_timeout,
+            },
+            query_timeout,
+        )
+        .await?;
+
         Ok(Self {
-            tcp_adnl,
+            connection: Arc::new(connection),
             query_timeout,
+            cancellation: Default::default(),
         })
     }
 
+    /// Aborts all in-flight and future queries when the token is cancelled
+    pub fn with_cancellation(mut self, cancellation: CancellationToken) -> Self {
+        self.cancellation = cancellation;
+        self
+    }
+
+    /// Returns roundtrip time or `None` if the server didn't respond in time
+    pub async fn ping(&self) -> Result<Option<Duration>> {
+        let started_at = std::time::Instant::now();
+        let received = self
+            .connection
+            .get(&self.cancellation)
+            .await?
+            .tcp_adnl
+            .ping(self.query_timeout, &self.cancellation)
+            .await
+            .map_err(NodeRpcError::from_query_error)?;
+        Ok(received.then(|| started_at.elapsed()))
+    }
+
     pub async fn generate_key_pair(&self) -> Result<[u8; 32]> {
         let proto::KeyHash { key_hash } = self.query(proto::GenerateKeyPair).await?;
         Ok(key_hash)
@@ -107,7 +136,19 @@ impl NodeTcpRpc {
         Ok(serde_json::Value::Object(result))
     }
 
+    /// Returns stats of the active validator sessions.
+    ///
+    /// NOTE: not all node versions support this query
+    pub async fn get_sessions_stats(&self) -> Result<Vec<SessionStats>> {
+        self.ensure_supported(RpcFeature::SessionsStats).await?;
+        let stats = self
+            .query::<_, proto::SessionsStats>(proto::GetSessionsStats)
+            .await?;
+        Ok(stats.sessions.into_iter().map(SessionStats::from).collect())
+    }
+
     pub async fn set_states_gc_interval(&self, interval_ms: u32) -> Result<()> {
+        self.ensure_supported(RpcFeature::StatesGcInterval).await?;
         self.query(proto::SetStatesGcInterval { interval_ms })
             .await
             .map(expect_success)
@@ -179,49 +220,219 @@ impl NodeTcpRpc {
         }
     }
 
+    /// Sends an already serialized boxed query and returns the serialized answer.
+    ///
+    /// NOTE: used for the control queries which are not wrapped by the typed methods yet
+    pub async fn raw_query(&self, query: &[u8]) -> Result<Vec<u8>> {
+        if query.len() < 4 {
+            return Err(NodeRpcError::InvalidRawQuery.into());
+        }
+        if query.len() > MAX_RAW_QUERY_LEN {
+            return Err(NodeRpcError::RawQueryTooLarge {
+                len: query.len(),
+                max: MAX_RAW_QUERY_LEN,
+            }
+            .into());
+        }
+
+        let proto::RawAnswer(answer) = self.query(proto::RawQuery(query)).await?;
+        Ok(answer)
+    }
+
+    /// Fails with [`NodeRpcError::NodeTooOld`] if the node doesn't support the feature
+    async fn ensure_supported(&self, feature: RpcFeature) -> Result<()> {
+        let Some(node_version) = self.connection.get(&self.cancellation).await?.node_version else {
+            // NOTE: unknown versions are not restricted
+            return Ok(());
+        };
+
+        let required = feature.min_node_version();
+        if node_version < required {
+            return Err(NodeRpcError::NodeTooOld {
+                feature: feature.name(),
+                required,
+                actual: node_version,
+            }
+            .into());
+        }
+        Ok(())
+    }
+
+    #[tracing::instrument(level = "debug", skip_all, fields(query = std::any::type_name::<Q>()))]
     async fn query<Q, R>(&self, query: Q) -> Result<R>
     where
         Q: TlWrite<Repr = tl_proto::Boxed>,
         for<'a> R: TlRead<'a>,
     {
-        enum QueryResponse<T> {
-            Ok(T),
-            Err(String),
+        // NOTE: queries are not retried after the connection was closed in flight,
+        // since some of them are not idempotent (e.g. `send_message`)
+        let ConnectionState { tcp_adnl, .. } = self.connection.get(&self.cancellation).await?;
+        control_query(&tcp_adnl, query, self.query_timeout, &self.cancellation).await
+    }
+}
+
+/// Optional control queries which are not supported by older nodes
+#[derive(Debug, Copy, Clone)]
+enum RpcFeature {
+    SessionsStats,
+    StatesGcInterval,
+}
+
+impl RpcFeature {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::SessionsStats => "sessions stats",
+            Self::StatesGcInterval => "states gc interval",
         }
+    }
 
-        impl<'a, R> tl_proto::TlRead<'a> for QueryResponse<R>
-        where
-            R: TlRead<'a>,
-        {
-            type Repr = tl_proto::Boxed;
-
-            fn read_from(packet: &'a [u8], offset: &mut usize) -> tl_proto::TlResult<Self> {
-                let constructor = {
-                    let mut offset: usize = *offset;
-                    <u32 as TlRead>::read_from(packet, &mut offset)?
-                };
-                if constructor == proto::ControlQueryError::TL_ID {
-                    let proto::ControlQueryError { message, .. } = <_>::read_from(packet, offset)?;
-                    Ok(QueryResponse::Err(message))
-                } else {
-                    <R>::read_from(packet, offset).map(QueryResponse::Ok)
-                }
+    /// The first node version with the corresponding query
+    fn min_node_version(&self) -> NodeVersion {
+        match self {
+            Self::SessionsStats => NodeVersion::new(0, 55, 0),
+            Self::StatesGcInterval => NodeVersion::new(0, 52, 0),
+        }
+    }
+}
+
+async fn control_query<Q, R>(
+    tcp_adnl: &TcpAdnl,
+    query: Q,
+    timeout: Duration,
+    cancellation: &CancellationToken,
+) -> Result<R>
+where
+    Q: TlWrite<Repr = tl_proto::Boxed>,
+    for<'a> R: TlRead<'a>,
+{
+    enum QueryResponse<T> {
+        Ok(T),
+        Err(String),
+    }
+
+    impl<'a, R> tl_proto::TlRead<'a> for QueryResponse<R>
+    where
+        R: TlRead<'a>,
+    {
+        type Repr = tl_proto::Boxed;
+
+        fn read_from(packet: &'a [u8], offset: &mut usize) -> tl_proto::TlResult<Self> {
+            let constructor = {
+                let mut offset: usize = *offset;
+                <u32 as TlRead>::read_from(packet, &mut offset)?
+            };
+            if constructor == proto::ControlQueryError::TL_ID {
+                let proto::ControlQueryError { message, .. } = <_>::read_from(packet, offset)?;
+                Ok(QueryResponse::Err(message))
+            } else {
+                <R>::read_from(packet, offset).map(QueryResponse::Ok)
             }
         }
+    }
 
-        match self
-            .tcp_adnl
-            .query(
-                proto::ControlQuery(IntermediateBytes(query)),
-                self.query_timeout,
-            )
-            .await
-        {
-            Ok(Some(QueryResponse::Ok(data))) => Ok(data),
-            Ok(Some(QueryResponse::Err(message))) => Err(anyhow::Error::msg(message)),
-            Ok(None) => Err(NodeRpcError::QueryTimeout.into()),
-            Err(e) => Err(NodeRpcError::QueryFailed(e).into()),
+    match tcp_adnl
+        .query(
+            proto::ControlQuery(IntermediateBytes(query)),
+            timeout,
+            cancellation,
+        )
+        .await
+    {
+        Ok(Some(QueryResponse::Ok(data))) => Ok(data),
+        Ok(Some(QueryResponse::Err(message))) => Err(anyhow::Error::msg(message)),
+        Ok(None) => Err(NodeRpcError::QueryTimeout.into()),
+        Err(e) => Err(NodeRpcError::from_query_error(e).into()),
+    }
+}
+
+/// Control server connection which is reestablished after it was closed
+/// (e.g. by the keep-alive after the server stopped responding).
+struct Connection {
+    config: TcpAdnlConfig,
+    query_timeout: Duration,
+    state: parking_lot::Mutex<ConnectionState>,
+    reconnect_lock: tokio::sync::Mutex<()>,
+}
+
+#[derive(Clone)]
+struct ConnectionState {
+    tcp_adnl: TcpAdnl,
+    /// Node version reported right after the connection was established
+    node_version: Option<NodeVersion>,
+}
+
+impl Connection {
+    async fn establish(config: TcpAdnlConfig, query_timeout: Duration) -> Result<Self> {
+        let state = Self::connect(&config, query_timeout, &Default::default()).await?;
+
+        Ok(Self {
+            config,
+            query_timeout,
+            state: parking_lot::Mutex::new(state),
+            reconnect_lock: Default::default(),
+        })
+    }
+
+    async fn get(&self, cancellation: &CancellationToken) -> Result<ConnectionState> {
+        let state = self.state.lock().clone();
+        if !state.tcp_adnl.is_closed() {
+            return Ok(state);
         }
+
+        let _guard = self.reconnect_lock.lock().await;
+
+        // Connection could have been reestablished while waiting for the lock
+        let state = self.state.lock().clone();
+        if !state.tcp_adnl.is_closed() {
+            return Ok(state);
+        }
+
+        tracing::info!("reconnecting to the control server");
+        let state = Self::connect(&self.config, self.query_timeout, cancellation).await?;
+        *self.state.lock() = state.clone();
+        Ok(state)
+    }
+
+    async fn connect(
+        config: &TcpAdnlConfig,
+        query_timeout: Duration,
+        cancellation: &CancellationToken,
+    ) -> Result<ConnectionState> {
+        let tcp_adnl = TcpAdnl::connect(config.clone())
+            .await
+            .map_err(NodeRpcError::ConnectionFailed)?;
+
+        // NOTE: `getStats` is supported by all nodes and contains the version
+        // even if the node is not synced yet
+        let stats = control_query::<_, proto::Stats>(
+            &tcp_adnl,
+            proto::GetStats,
+            query_timeout,
+            cancellation,
+        )
+        .await;
+
+        // NOTE: a node which is starting up or slow to answer is still usable,
+        // so only the broken connection is an error here
+        let node_version = match stats {
+            Ok(stats) => NodeVersion::from_stats(&stats),
+            Err(e) => match e.downcast_ref::<NodeRpcError>() {
+                Some(NodeRpcError::QueryFailed(TcpAdnlError::SocketClosed)) => {
+                    return Err(NodeRpcError::ConnectionFailed(TcpAdnlError::SocketClosed).into())
+                }
+                Some(NodeRpcError::Cancelled) => return Err(e),
+                _ => {
+                    tracing::warn!("failed to get node version: {e:?}");
+                    None
+                }
+            },
+        };
+        tracing::debug!(?node_version, "connected to the control server");
+
+        Ok(ConnectionState {
+            tcp_adnl,
+            node_version,
+        })
     }
 }
 
@@ -249,6 +460,8 @@ pub struct ConfigParamWithId {
 
 fn expect_success(_: proto::Success) {}
 
+const MAX_RAW_QUERY_LEN: usize = 64 << 10;
+
 #[derive(thiserror::Error, Debug)]
 pub enum NodeRpcError {
     #[error("connection failed")]
@@ -257,6 +470,8 @@ pub enum NodeRpcError {
     QueryFailed(#[source] TcpAdnlError),
     #[error("query timeout")]
     QueryTimeout,
+    #[error("query cancelled")]
+    Cancelled,
     #[error("invalid stats")]
     InvalidStats(#[source] StatsError),
     #[error("invalid pubkey")]
@@ -271,4 +486,23 @@ pub enum NodeRpcError {
     InvalidBlockId,
     #[error("invalid blockchain config")]
     InvalidBlockchainConfig,
+    #[error("raw query must contain at least the constructor id")]
+    InvalidRawQuery,
+    #[error("raw query is too large ({len} bytes, max {max})")]
+    RawQueryTooLarge { len: usize, max: usize },
+    #[error("node too old for this operation ({feature} requires {required}, running {actual})")]
+    NodeTooOld {
+        feature: &'static str,
+        required: NodeVersion,
+        actual: NodeVersion,
+    },
+}
+
+impl NodeRpcError {
+    fn from_query_error(e: TcpAdnlError) -> Self {
+        match e {
+            TcpAdnlError::Cancelled => Self::Cancelled,
+            e => Self::QueryFailed(e),
+        }
+    }
 }
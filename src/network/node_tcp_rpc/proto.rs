@@ -65,6 +65,10 @@ pub struct AddValidatorAdnlAddress<'tl> {
 #[tl(boxed, id = "engine.validator.getStats", scheme = "proto.tl")]
 pub struct GetStats;
 
+#[derive(Copy, Clone, TlWrite)]
+#[tl(boxed, id = "engine.validator.getSessionsStats", scheme = "proto.tl")]
+pub struct GetSessionsStats;
+
 #[derive(Copy, Clone, TlWrite)]
 #[tl(
     boxed,
@@ -162,6 +166,25 @@ pub struct OneState {
     pub value: Vec<u8>,
 }
 
+#[derive(Clone, TlRead)]
+#[tl(boxed, id = "engine.validator.sessionsStats", scheme = "proto.tl")]
+pub struct SessionsStats {
+    pub sessions: Vec<SessionStats>,
+}
+
+#[derive(Clone, TlRead)]
+pub struct SessionStats {
+    pub session_id: [u8; 32],
+    pub workchain: i32,
+    pub shard: u64,
+    pub catchain_seqno: u32,
+    pub rounds: u32,
+    pub blocks_approved: u32,
+    pub blocks_signed: u32,
+    pub blocks_collated: u32,
+    pub collation_failures: u32,
+}
+
 #[derive(Clone, Debug, TlRead)]
 #[tl(boxed, id = "liteServer.configInfo", scheme = "proto.tl")]
 pub struct ConfigInfo {
@@ -180,6 +203,36 @@ pub enum ShardAccount {
     Empty,
 }
 
+/// Already serialized boxed query
+#[derive(Copy, Clone)]
+pub struct RawQuery<'tl>(pub &'tl [u8]);
+
+impl TlWrite for RawQuery<'_> {
+    type Repr = tl_proto::Boxed;
+
+    fn max_size_hint(&self) -> usize {
+        self.0.len()
+    }
+
+    fn write_to<P: tl_proto::TlPacket>(&self, packet: &mut P) {
+        packet.write_raw_slice(self.0);
+    }
+}
+
+/// Serialized answer as is
+#[derive(Clone)]
+pub struct RawAnswer(pub Vec<u8>);
+
+impl TlRead<'_> for RawAnswer {
+    type Repr = tl_proto::Boxed;
+
+    fn read_from(packet: &[u8], offset: &mut usize) -> tl_proto::TlResult<Self> {
+        let data = packet.get(*offset..).unwrap_or_default().to_vec();
+        *offset = packet.len();
+        Ok(Self(data))
+    }
+}
+
 pub type HashRef<'tl> = &'tl [u8; 32];
 
 mod tl_string {
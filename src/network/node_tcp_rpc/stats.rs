@@ -183,13 +183,39 @@ impl TryFrom<proto::Stats> for NodeStats {
     }
 }
 
-#[derive(Copy, Clone, Debug, Serialize)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Serialize)]
 pub struct NodeVersion {
     pub major: u32,
     pub minor: u32,
     pub patch: u32,
 }
 
+impl NodeVersion {
+    pub const fn new(major: u32, minor: u32, patch: u32) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+        }
+    }
+
+    /// Finds the node version among the raw stats (regardless of the sync status)
+    pub fn from_stats(stats: &proto::Stats) -> Option<Self> {
+        let item = stats
+            .items
+            .iter()
+            .find(|item| item.key == STATS_NODE_VERSION)?;
+        let str = serde_json::from_slice::<String>(&item.value).ok()?;
+        Self::from_str(&str).ok()
+    }
+}
+
+impl std::fmt::Display for NodeVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
 impl FromStr for NodeVersion {
     type Err = StatsError;
 
@@ -226,6 +252,38 @@ impl Serialize for ValidatorSetEntry {
     }
 }
 
+/// Validator session stats since the session start
+#[derive(Clone, Debug, Serialize)]
+pub struct SessionStats {
+    #[serde(with = "serde_hex_array")]
+    pub session_id: [u8; 32],
+    /// Shard in the `wc:prefix` format (e.g. `0:8000000000000000`)
+    pub shard: String,
+    pub catchain_seqno: u32,
+    /// Rounds in which the validator participated
+    pub rounds: u32,
+    pub blocks_approved: u32,
+    pub blocks_signed: u32,
+    /// Blocks collated by this validator
+    pub blocks_collated: u32,
+    pub collation_failures: u32,
+}
+
+impl From<proto::SessionStats> for SessionStats {
+    fn from(stats: proto::SessionStats) -> Self {
+        Self {
+            session_id: stats.session_id,
+            shard: format!("{}:{:016x}", stats.workchain, stats.shard),
+            catchain_seqno: stats.catchain_seqno,
+            rounds: stats.rounds,
+            blocks_approved: stats.blocks_approved,
+            blocks_signed: stats.blocks_signed,
+            blocks_collated: stats.blocks_collated,
+            collation_failures: stats.collation_failures,
+        }
+    }
+}
+
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum SyncStatus {
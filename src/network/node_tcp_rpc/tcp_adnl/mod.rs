@@ -1,7 +1,7 @@
 use std::net::SocketAddr;
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use ctr::cipher::{KeyIvInit, StreamCipher};
 use everscale_crypto::ed25519;
@@ -13,15 +13,20 @@ use tokio::net::TcpStream;
 use tokio::sync::mpsc;
 use tokio_util::sync::CancellationToken;
 
-use self::queries_cache::QueriesCache;
+use self::queries_cache::{AnswerStatus, QueriesCache};
 
 mod queries_cache;
 
+#[derive(Clone)]
 pub struct TcpAdnlConfig {
     pub server_address: SocketAddr,
     pub server_pubkey: ed25519::PublicKey,
     pub client_secret: ed25519::SecretKey,
     pub connection_timeout: Duration,
+    /// Ping the server after this period without received packets (zero to disable)
+    pub keepalive_interval: Duration,
+    /// Close the connection after this period without received packets
+    pub idle_timeout: Duration,
 }
 
 #[derive(Clone)]
@@ -61,7 +66,7 @@ impl TcpAdnl {
             queries_cache: Arc::new(Default::default()),
             cancellation_token: Default::default(),
             packets_tx: tx,
-            query_id: Default::default(),
+            activity: Arc::new(Activity::new()),
         });
 
         tokio::spawn(socket_writer(
@@ -74,8 +79,19 @@ impl TcpAdnl {
             socket_rx,
             cipher_receive,
             state.queries_cache.clone(),
+            state.activity.clone(),
             state.cancellation_token.clone(),
         ));
+        if !config.keepalive_interval.is_zero() {
+            tokio::spawn(keepalive(
+                config.keepalive_interval,
+                config.idle_timeout,
+                state.queries_cache.clone(),
+                state.packets_tx.clone(),
+                state.activity.clone(),
+                state.cancellation_token.clone(),
+            ));
+        }
 
         build_handshake_packet(
             &config.server_pubkey,
@@ -91,7 +107,50 @@ impl TcpAdnl {
         Ok(Self { state })
     }
 
-    pub async fn query<Q, R>(&self, query: Q, timeout: Duration) -> Result<Option<R>, TcpAdnlError>
+    /// Whether the connection was closed (by an error or by the keep-alive)
+    pub fn is_closed(&self) -> bool {
+        self.state.cancellation_token.is_cancelled()
+    }
+
+    /// Sends `tcp.ping` and waits for the `tcp.pong`.
+    /// Returns `false` if no answer was received in time.
+    pub async fn ping(
+        &self,
+        timeout: Duration,
+        cancellation: &CancellationToken,
+    ) -> Result<bool, TcpAdnlError> {
+        let cancelled = self.state.cancellation_token.cancelled();
+        if self.state.cancellation_token.is_cancelled() {
+            return Err(TcpAdnlError::SocketClosed);
+        }
+
+        let random_id = rand::thread_rng().gen();
+        let data = tl_proto::serialize(TcpPing { random_id });
+
+        let mut pending_query = self.state.queries_cache.add_query(ping_query_id(random_id));
+        if self.state.packets_tx.send(Packet::encrypted(data)).is_err() {
+            return Err(TcpAdnlError::SocketClosed);
+        }
+
+        tokio::select! {
+            res = tokio::time::timeout(timeout, pending_query.wait()) => {
+                Ok(matches!(res, Ok(Some(_))))
+            }
+            _  = cancelled => Err(TcpAdnlError::SocketClosed),
+            _ = cancellation.cancelled() => Err(TcpAdnlError::Cancelled),
+        }
+    }
+
+    /// Sends the query and waits for the answer.
+    ///
+    /// Returns `None` if no answer was received in time. The pending query
+    /// is dropped as soon as the `cancellation` token is cancelled.
+    pub async fn query<Q, R>(
+        &self,
+        query: Q,
+        timeout: Duration,
+        cancellation: &CancellationToken,
+    ) -> Result<Option<R>, TcpAdnlError>
     where
         Q: TlWrite<Repr = tl_proto::Boxed>,
         for<'a> R: TlRead<'a>,
@@ -101,21 +160,16 @@ impl TcpAdnl {
             return Err(TcpAdnlError::SocketClosed);
         }
 
-        let mut query_id = [0; 32];
-        query_id[..std::mem::size_of::<usize>()].copy_from_slice(
-            &self
-                .state
-                .query_id
-                .fetch_add(1, Ordering::AcqRel)
-                .to_le_bytes(),
-        );
+        // NOTE: random ids can't be guessed to spoof answers
+        let mut query_id: [u8; 32] = rand::thread_rng().gen();
+        query_id[31] = QUERY_MARKER;
 
         let data = tl_proto::serialize(AdnlMessageQuery {
             query_id: &query_id,
             query: IntermediateBytes(query),
         });
 
-        let pending_query = self.state.queries_cache.add_query(query_id);
+        let mut pending_query = self.state.queries_cache.add_query(query_id);
         if self.state.packets_tx.send(Packet::encrypted(data)).is_err() {
             return Err(TcpAdnlError::SocketClosed);
         }
@@ -125,6 +179,7 @@ impl TcpAdnl {
                 res.ok().flatten()
             }
             _  = cancelled => return Err(TcpAdnlError::SocketClosed),
+            _ = cancellation.cancelled() => return Err(TcpAdnlError::Cancelled),
         };
 
         Ok(match answer {
@@ -140,7 +195,7 @@ struct SharedState {
     queries_cache: Arc<QueriesCache>,
     cancellation_token: CancellationToken,
     packets_tx: PacketsTx,
-    query_id: AtomicUsize,
+    activity: Arc<Activity>,
 }
 
 impl Drop for SharedState {
@@ -200,6 +255,7 @@ async fn socket_reader<T>(
     mut socket: T,
     mut cipher: Aes256Ctr,
     queries_cache: Arc<QueriesCache>,
+    activity: Arc<Activity>,
     cancellation_token: CancellationToken,
 ) where
     T: AsyncRead + Unpin,
@@ -222,9 +278,13 @@ async fn socket_reader<T>(
             _ = &mut cancelled => break,
         }
 
+        // NOTE: the stream can't be resynchronized after an invalid length
         let length = u32::from_le_bytes(length) as usize;
-        if length < 64 {
-            continue;
+        if !(MIN_PACKET_LEN..=MAX_PACKET_LEN).contains(&length) {
+            COUNTERS.invalid_packets.fetch_add(1, Ordering::Relaxed);
+            tracing::error!(length, "invalid packet length, closing connection");
+            cancellation_token.cancel();
+            break;
         }
 
         let mut buffer = vec![0; length];
@@ -246,10 +306,13 @@ async fn socket_reader<T>(
             .as_slice()
             .eq(&buffer[length - 32..length])
         {
+            COUNTERS.invalid_packets.fetch_add(1, Ordering::Relaxed);
             tracing::warn!("packet checksum mismatch");
             continue;
         }
 
+        activity.touch();
+
         buffer.truncate(length - 32);
         buffer.drain(..32);
 
@@ -257,17 +320,105 @@ async fn socket_reader<T>(
             continue;
         }
 
-        match tl_proto::deserialize::<AdnlMessageAnswer>(&buffer) {
-            Ok(AdnlMessageAnswer { query_id, data }) => {
-                queries_cache.update_query(query_id, data);
-            }
-            Err(e) => tracing::warn!("invalid response: {e:?}"),
+        let status = match tl_proto::deserialize::<AdnlMessageAnswer>(&buffer) {
+            Ok(AdnlMessageAnswer { query_id, data }) => queries_cache.update_query(query_id, data),
+            Err(e) => match tl_proto::deserialize::<TcpPong>(&buffer) {
+                Ok(TcpPong { random_id }) => {
+                    queries_cache.update_query(&ping_query_id(random_id), &[])
+                }
+                Err(_) => {
+                    COUNTERS.invalid_packets.fetch_add(1, Ordering::Relaxed);
+                    tracing::warn!("invalid response: {e:?}");
+                    continue;
+                }
+            },
         };
+
+        match status {
+            AnswerStatus::Delivered => {}
+            AnswerStatus::Late => {
+                COUNTERS.late_answers.fetch_add(1, Ordering::Relaxed);
+                tracing::debug!("received duplicate or late answer");
+            }
+            AnswerStatus::Unknown => {
+                COUNTERS.unknown_answers.fetch_add(1, Ordering::Relaxed);
+                tracing::warn!("received answer for an unknown query");
+            }
+        }
     }
 
     tracing::debug!("receiver loop finished");
 }
 
+/// Pings the idle connection and closes it if the server stopped responding,
+/// so that a stale connection is not used for the next query.
+async fn keepalive(
+    interval: Duration,
+    idle_timeout: Duration,
+    queries_cache: Arc<QueriesCache>,
+    packets_tx: PacketsTx,
+    activity: Arc<Activity>,
+    cancellation_token: CancellationToken,
+) {
+    tokio::pin!(let cancelled = cancellation_token.cancelled(););
+
+    loop {
+        let idle = activity.idle();
+        if idle >= idle_timeout {
+            tracing::warn!(?idle, "control connection is idle, closing");
+            cancellation_token.cancel();
+            break;
+        }
+
+        if idle >= interval {
+            let random_id = rand::thread_rng().gen();
+            let data = tl_proto::serialize(TcpPing { random_id });
+
+            let mut pending_query = queries_cache.add_query(ping_query_id(random_id));
+            if packets_tx.send(Packet::encrypted(data)).is_err() {
+                break;
+            }
+
+            tokio::select! {
+                _ = tokio::time::timeout(idle_timeout - idle, pending_query.wait()) => continue,
+                _ = &mut cancelled => break,
+            }
+        }
+
+        tokio::select! {
+            _ = tokio::time::sleep(interval - idle) => {},
+            _ = &mut cancelled => break,
+        }
+    }
+
+    tracing::debug!("keepalive loop finished");
+}
+
+/// Time of the last received packet
+struct Activity {
+    started_at: Instant,
+    last_received_ms: AtomicU64,
+}
+
+impl Activity {
+    fn new() -> Self {
+        Self {
+            started_at: Instant::now(),
+            last_received_ms: AtomicU64::new(0),
+        }
+    }
+
+    fn touch(&self) {
+        let elapsed = self.started_at.elapsed().as_millis() as u64;
+        self.last_received_ms.store(elapsed, Ordering::Release);
+    }
+
+    fn idle(&self) -> Duration {
+        let last_received = Duration::from_millis(self.last_received_ms.load(Ordering::Acquire));
+        self.started_at.elapsed().saturating_sub(last_received)
+    }
+}
+
 struct Packet {
     data: Vec<u8>,
     encrypt: bool,
@@ -329,6 +480,67 @@ pub fn build_packet_cipher(shared_secret: &[u8; 32], checksum: &[u8; 32]) -> Aes
     )
 }
 
+/// Pings share the queries cache with regular queries,
+/// so their ids are marked to never collide with query ids.
+fn ping_query_id(random_id: u64) -> [u8; 32] {
+    let mut query_id = [0; 32];
+    query_id[..8].copy_from_slice(&random_id.to_le_bytes());
+    query_id[31] = PING_MARKER;
+    query_id
+}
+
+const QUERY_MARKER: u8 = 0x00;
+const PING_MARKER: u8 = 0xff;
+
+/// Nonce and checksum
+const MIN_PACKET_LEN: usize = 64;
+const MAX_PACKET_LEN: usize = 16 << 20;
+
+/// Anomalies of the received packets since the process start
+#[derive(Debug, Default, Copy, Clone)]
+pub struct TcpAdnlCounters {
+    /// Packets with an invalid length, checksum or content
+    pub invalid_packets: u64,
+    /// Duplicate answers or answers after the query timeout
+    pub late_answers: u64,
+    /// Answers for the queries which were never sent (possibly spoofed)
+    pub unknown_answers: u64,
+}
+
+impl TcpAdnlCounters {
+    pub fn get() -> Self {
+        Self {
+            invalid_packets: COUNTERS.invalid_packets.load(Ordering::Relaxed),
+            late_answers: COUNTERS.late_answers.load(Ordering::Relaxed),
+            unknown_answers: COUNTERS.unknown_answers.load(Ordering::Relaxed),
+        }
+    }
+}
+
+struct AtomicCounters {
+    invalid_packets: AtomicU64,
+    late_answers: AtomicU64,
+    unknown_answers: AtomicU64,
+}
+
+static COUNTERS: AtomicCounters = AtomicCounters {
+    invalid_packets: AtomicU64::new(0),
+    late_answers: AtomicU64::new(0),
+    unknown_answers: AtomicU64::new(0),
+};
+
+#[derive(Copy, Clone, TlWrite)]
+#[tl(boxed, id = "tcp.ping", size_hint = 8, scheme = "proto.tl")]
+struct TcpPing {
+    random_id: u64,
+}
+
+#[derive(Copy, Clone, TlRead)]
+#[tl(boxed, id = "tcp.pong", size_hint = 8, scheme = "proto.tl")]
+struct TcpPong {
+    random_id: u64,
+}
+
 #[derive(Clone, TlWrite)]
 #[tl(boxed, id = "adnl.message.query", scheme = "proto.tl")]
 struct AdnlMessageQuery<'tl, T> {
@@ -353,6 +565,8 @@ pub enum TcpAdnlError {
     ConnectionError(#[source] std::io::Error),
     #[error("socket closed")]
     SocketClosed,
+    #[error("query cancelled")]
+    Cancelled,
     #[error("invalid answer")]
     InvalidAnswer(#[source] tl_proto::TlError),
 }
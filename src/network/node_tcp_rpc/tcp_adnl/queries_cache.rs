@@ -1,5 +1,7 @@
+use std::collections::VecDeque;
 use std::sync::{Arc, Weak};
 
+use parking_lot::Mutex;
 use tokio::sync::oneshot;
 
 use crate::util::FxDashMap;
@@ -7,6 +9,8 @@ use crate::util::FxDashMap;
 #[derive(Default)]
 pub struct QueriesCache {
     queries: FxDashMap<[u8; 32], DataTx>,
+    /// Ids of the recently finished queries (to distinguish late answers from unknown ones)
+    finished: Mutex<VecDeque<[u8; 32]>>,
 }
 
 impl QueriesCache {
@@ -17,47 +21,140 @@ impl QueriesCache {
 
         PendingAdnlQuery {
             query_id,
-            data_rx: Some(rx),
+            data_rx: rx,
             cache: Arc::downgrade(self),
-            finished: false,
         }
     }
 
-    pub fn update_query(&self, query_id: &[u8; 32], answer: &[u8]) {
-        if let Some((_, tx)) = self.queries.remove(query_id) {
-            tx.send(answer.to_vec()).ok();
+    /// Delivers the answer to the pending query
+    pub fn update_query(&self, query_id: &[u8; 32], answer: &[u8]) -> AnswerStatus {
+        // NOTE: the lock is held while the query is removed, so a concurrent
+        // answer or drop can't observe it as neither pending nor finished
+        let mut finished = self.finished.lock();
+        match self.queries.remove(query_id) {
+            Some((_, tx)) => {
+                mark_finished(&mut finished, query_id);
+                drop(finished);
+                match tx.send(answer.to_vec()) {
+                    Ok(()) => AnswerStatus::Delivered,
+                    // Query was dropped right before the answer was received
+                    Err(_) => AnswerStatus::Late,
+                }
+            }
+            None if finished.contains(query_id) => AnswerStatus::Late,
+            None => AnswerStatus::Unknown,
         }
     }
+
+    fn remove_query(&self, query_id: &[u8; 32]) {
+        let mut finished = self.finished.lock();
+        if self.queries.remove(query_id).is_some() {
+            mark_finished(&mut finished, query_id);
+        }
+    }
+}
+
+fn mark_finished(finished: &mut VecDeque<[u8; 32]>, query_id: &[u8; 32]) {
+    const MAX_FINISHED: usize = 256;
+
+    if finished.len() >= MAX_FINISHED {
+        finished.pop_front();
+    }
+    finished.push_back(*query_id);
 }
 
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum AnswerStatus {
+    Delivered,
+    /// Duplicate answer or answer after the query timeout
+    Late,
+    /// Answer for the query which was never sent
+    Unknown,
+}
+
+/// Query waiting for the answer.
+///
+/// The query is removed from the cache when it is answered or dropped
+/// (e.g. on timeout or cancellation), so the late answer is ignored.
 pub struct PendingAdnlQuery {
     query_id: [u8; 32],
-    data_rx: Option<DataRx>,
+    data_rx: DataRx,
     cache: Weak<QueriesCache>,
-    finished: bool,
 }
 
 impl PendingAdnlQuery {
-    pub async fn wait(mut self) -> Option<Vec<u8>> {
-        // SAFETY: `data_rx` is guaranteed to be `Some`
-        let data_rx = unsafe { self.data_rx.take().unwrap_unchecked() };
-        let data = data_rx.await.ok();
-        self.finished = true;
-        data
+    pub async fn wait(&mut self) -> Option<Vec<u8>> {
+        (&mut self.data_rx).await.ok()
     }
 }
 
 impl Drop for PendingAdnlQuery {
     fn drop(&mut self) {
-        if self.finished {
-            return;
-        }
-
+        // NOTE: query ids are never reused, so it is a no-op for the answered query
         if let Some(cache) = self.cache.upgrade() {
-            cache.queries.remove(&self.query_id);
+            cache.remove_query(&self.query_id);
         }
     }
 }
 
 type DataTx = oneshot::Sender<Vec<u8>>;
 type DataRx = oneshot::Receiver<Vec<u8>>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const QUERY_ID: [u8; 32] = [1; 32];
+
+    #[test]
+    fn answer_after_drop_is_late() {
+        let cache = Arc::new(QueriesCache::default());
+        drop(cache.add_query(QUERY_ID));
+
+        assert_eq!(cache.update_query(&QUERY_ID, b"answer"), AnswerStatus::Late);
+    }
+
+    #[test]
+    fn duplicate_answer_is_not_delivered() {
+        let cache = Arc::new(QueriesCache::default());
+        let mut query = cache.add_query(QUERY_ID);
+
+        assert_eq!(
+            cache.update_query(&QUERY_ID, b"first"),
+            AnswerStatus::Delivered
+        );
+        assert_eq!(cache.update_query(&QUERY_ID, b"second"), AnswerStatus::Late);
+        assert_eq!(query.data_rx.try_recv().unwrap(), b"first");
+
+        drop(query);
+        assert_eq!(cache.update_query(&QUERY_ID, b"third"), AnswerStatus::Late);
+    }
+
+    #[test]
+    fn unknown_answer() {
+        let cache = Arc::new(QueriesCache::default());
+        let _query = cache.add_query(QUERY_ID);
+
+        assert_eq!(
+            cache.update_query(&[2; 32], b"answer"),
+            AnswerStatus::Unknown
+        );
+    }
+
+    #[test]
+    fn answer_races_with_drop() {
+        let cache = Arc::new(QueriesCache::default());
+        for i in 0..1000u32 {
+            let mut query_id = [0; 32];
+            query_id[..4].copy_from_slice(&i.to_le_bytes());
+
+            let query = cache.add_query(query_id);
+            let dropper = std::thread::spawn(move || drop(query));
+            let status = cache.update_query(&query_id, b"answer");
+            dropper.join().unwrap();
+
+            assert_ne!(status, AnswerStatus::Unknown);
+            assert_eq!(cache.update_query(&query_id, b"answer"), AnswerStatus::Late);
+        }
+    }
+}
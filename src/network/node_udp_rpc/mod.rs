@@ -3,49 +3,101 @@ use std::sync::Arc;
 use std::time::Duration;
 
 use anyhow::{Context, Result};
-use everscale_network::{adnl, overlay, rldp, NetworkBuilder};
+use everscale_crypto::ed25519;
+use everscale_network::{adnl, dht, overlay, rldp, NetworkBuilder};
+use futures_util::StreamExt;
 use parking_lot::Mutex;
 use rand::Rng;
 use tl_proto::{TlRead, TlWrite};
-
-use crate::config::AppConfigAdnl;
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
+
+use self::peers_cache::PeersCache;
+use self::verifier::BlockVerifier;
+use super::node_tcp_rpc::NodeRpcError;
+use crate::config::{AppConfigAdnl, GlobalConfig};
+use crate::dirs::ProjectDirs;
 use crate::util::BlockStuff;
 
+mod peers_cache;
 mod proto;
+mod verifier;
+
 #[derive(Clone)]
 pub struct NodeUdpRpc {
     inner: Arc<NodeInner>,
+    cancellation: CancellationToken,
 }
 
 impl NodeUdpRpc {
-    pub async fn new(config: &AppConfigAdnl) -> Result<Self> {
+    pub async fn new(config: &AppConfigAdnl, dirs: &ProjectDirs) -> Result<Self> {
         // Resolve public ip
         let ip_addr = public_ip::addr_v4()
             .await
             .context("failed to resolve public ip")?;
 
-        // Build keystore
-        let keystore = adnl::Keystore::builder()
-            .with_tagged_key(*session_keys(), KEY_TAG)?
-            .build();
-
         // Build network
-        let rldp_options = rldp::NodeOptions {
-            force_compression: true,
-            ..Default::default()
-        };
+        let build_network = |port: u16| {
+            let keystore = adnl::Keystore::builder()
+                .with_tagged_key(*session_keys(), KEY_TAG)?
+                .build();
 
-        let (adnl, rldp) = NetworkBuilder::with_adnl(
-            SocketAddrV4::new(ip_addr, config.client_port),
-            keystore,
-            adnl::NodeOptions {
-                use_loopback_for_neighbours: true,
+            let rldp_options = rldp::NodeOptions {
+                force_compression: true,
                 ..Default::default()
-            },
-        )
-        .with_rldp(rldp_options)
-        .build()
-        .context("failed to build network stack")?;
+            };
+
+            NetworkBuilder::with_adnl(
+                SocketAddrV4::new(ip_addr, port),
+                keystore,
+                adnl::NodeOptions {
+                    use_loopback_for_neighbours: true,
+                    ..Default::default()
+                },
+            )
+            .with_dht(KEY_TAG, Default::default())
+            .with_rldp(rldp_options)
+            .build()
+        };
+
+        // NOTE: the port can be taken by another tool between the selection and the bind,
+        // so binding is retried with a new port (or after a delay for the pinned one)
+        let mut attempt = 0;
+        let (adnl, dht, rldp) = loop {
+            let port = match config.client_port {
+                AppConfigAdnl::AUTO_CLIENT_PORT => find_free_udp_port()?,
+                port => port,
+            };
+
+            match build_network(port) {
+                Ok(network) => {
+                    tracing::debug!(port, "local ADNL port bound");
+                    break network;
+                }
+                Err(e) if is_addr_in_use(&e) && attempt < BIND_ATTEMPTS => {
+                    tracing::warn!(port, attempt, "local ADNL port is already in use");
+                    attempt += 1;
+                    if config.client_port != AppConfigAdnl::AUTO_CLIENT_PORT {
+                        tokio::time::sleep(BIND_RETRY_INTERVAL).await;
+                    }
+                }
+                Err(e) if is_addr_in_use(&e) => {
+                    return Err(e)
+                        .with_context(|| format!("local ADNL port {port} is already in use"))
+                }
+                Err(e) => return Err(e).context("failed to build network stack"),
+            }
+        };
+
+        // Add static DHT nodes and the ones known from the previous runs
+        // NOTE: static nodes can be unreachable, so discovered nodes are used as a fallback
+        let mut peers_cache = PeersCache::load(&dirs.peers_cache)?;
+        add_static_dht_nodes(&dht, dirs);
+        for peer in peers_cache.dht_nodes() {
+            if let Err(e) = dht.add_dht_peer(peer) {
+                tracing::debug!("failed to add cached DHT node: {e:?}");
+            }
+        }
 
         // Prepare overlay prefix
         let overlay_id_full = overlay::IdFull::for_workchain_overlay(
@@ -58,31 +110,142 @@ impl NodeUdpRpc {
             overlay: overlay_id.as_slice(),
         });
 
+        // Prepare block verifier
+        let verifier = if config.trust_mode {
+            tracing::warn!("trust mode enabled, blocks will not be verified");
+            None
+        } else {
+            Some(BlockVerifier::new(&config.zerostate_file_hash))
+        };
+
         // Add server as peer
         let peer_id_full = adnl::NodeIdFull::new(config.server_pubkey);
         let peer_id = peer_id_full.compute_short_id();
 
         let local_id = *adnl.key_by_tag(KEY_TAG)?.id();
-        adnl.add_peer(
-            adnl::NewPeerContext::Dht,
-            &local_id,
-            &peer_id,
-            config.server_address,
-            peer_id_full,
-        )
-        .context("failed to add server as a peer")?;
 
-        // Done
-        Ok(NodeUdpRpc {
+        let rpc = NodeUdpRpc {
             inner: Arc::new(NodeInner {
                 local_id,
                 peer_id,
                 query_prefix,
                 adnl,
+                dht,
                 rldp,
                 roundtrip: Default::default(),
+                verifier,
             }),
-        })
+            cancellation: Default::default(),
+        };
+
+        let addr = rpc
+            .resolve_server_address(config.server_address, &peers_cache, peer_id_full)
+            .await?;
+        if let Err(e) = peers_cache.update(&peer_id, addr) {
+            tracing::warn!("failed to update peers cache: {e:?}");
+        }
+
+        let dht_nodes = rpc.inner.dht.get_known_nodes(PeersCache::MAX_DHT_NODES);
+        if let Err(e) = dht_nodes.and_then(|nodes| peers_cache.update_dht_nodes(&nodes)) {
+            tracing::warn!("failed to update cached DHT nodes: {e:?}");
+        }
+
+        // Done
+        Ok(rpc)
+    }
+
+    /// Aborts all in-flight and future queries when the token is cancelled
+    pub fn with_cancellation(mut self, cancellation: CancellationToken) -> Self {
+        self.cancellation = cancellation;
+        self
+    }
+
+    /// Updates the validator set used for the blocks verification.
+    ///
+    /// NOTE: config must be received from the trusted source
+    pub fn set_trusted_config(&self, config: &ton_block::ConfigParams) -> Result<()> {
+        match &self.inner.verifier {
+            Some(verifier) => verifier.set_trusted_config(config),
+            None => Ok(()),
+        }
+    }
+
+    pub fn peer_id(&self) -> &adnl::NodeIdShort {
+        &self.inner.peer_id
+    }
+
+    /// Sends a single capabilities query and returns its roundtrip time
+    /// or `None` if the server didn't respond in time
+    pub async fn ping(&self, timeout: Duration) -> Result<Option<Duration>> {
+        self.ping_peer(&self.inner.peer_id, timeout).await
+    }
+
+    /// Same as [`ping`] but for an arbitrary overlay peer added with [`add_peer`]
+    ///
+    /// [`ping`]: Self::ping
+    /// [`add_peer`]: Self::add_peer
+    pub async fn ping_peer(
+        &self,
+        peer_id: &adnl::NodeIdShort,
+        timeout: Duration,
+    ) -> Result<Option<Duration>> {
+        let inner = &self.inner;
+        let started_at = std::time::Instant::now();
+        let query = inner.adnl.query_with_prefix::<_, proto::Capabilities>(
+            &inner.local_id,
+            peer_id,
+            &inner.query_prefix,
+            proto::GetCapabilities,
+            Some(timeout.as_millis() as u64),
+        );
+        let answer = cancellable(&self.cancellation, query).await?;
+        Ok(answer.map(|_| started_at.elapsed()))
+    }
+
+    /// Returns overlay neighbours known to the server
+    pub async fn get_overlay_neighbours(&self) -> Result<Vec<ed25519::PublicKey>> {
+        let query = proto::GetRandomPeers {
+            peers: proto::OverlayNodes { nodes: Vec::new() },
+        };
+        let proto::OverlayNodes { nodes } = self
+            .inner
+            .adnl_query(query, 2000, &self.cancellation)
+            .await?;
+
+        Ok(nodes
+            .iter()
+            .filter_map(|node| ed25519::PublicKey::from_tl(node.id.as_equivalent_ref()))
+            .collect())
+    }
+
+    /// Searches the peer address in DHT
+    pub async fn find_address(&self, peer_id: &adnl::NodeIdShort) -> Result<SocketAddrV4> {
+        let (addr, _) = cancellable(&self.cancellation, self.inner.dht.find_address(peer_id))
+            .await
+            .context("failed to find address in DHT")?;
+        Ok(addr)
+    }
+
+    /// Adds an overlay peer to send queries to it
+    pub fn add_peer(
+        &self,
+        pubkey: ed25519::PublicKey,
+        addr: SocketAddrV4,
+    ) -> Result<adnl::NodeIdShort> {
+        let inner = &self.inner;
+        let peer_id_full = adnl::NodeIdFull::new(pubkey);
+        let peer_id = peer_id_full.compute_short_id();
+        inner
+            .adnl
+            .add_peer(
+                adnl::NewPeerContext::Dht,
+                &inner.local_id,
+                &peer_id,
+                addr,
+                peer_id_full,
+            )
+            .context("failed to add peer")?;
+        Ok(peer_id)
     }
 
     pub async fn get_capabilities(&self) -> Result<proto::Capabilities> {
@@ -90,7 +253,10 @@ impl NodeUdpRpc {
 
         let mut attempt = 0;
         loop {
-            let res = self.inner.adnl_query(proto::GetCapabilities, 1000).await;
+            let res = self
+                .inner
+                .adnl_query(proto::GetCapabilities, 1000, &self.cancellation)
+                .await;
             attempt += 1;
             if res.is_ok() || attempt >= MAX_ATTEMPTS {
                 break res;
@@ -98,7 +264,7 @@ impl NodeUdpRpc {
         }
     }
 
-    /// Waits for the next block
+    /// Waits for the next masterchain block
     pub async fn get_next_block(
         &self,
         prev_block_id: &ton_block::BlockIdExt,
@@ -109,15 +275,26 @@ impl NodeUdpRpc {
         loop {
             let data = self
                 .inner
-                .rldp_query(proto::DownloadNextBlockFull { prev_block_id }, attempt)
+                .rldp_query(
+                    proto::DownloadNextBlockFull { prev_block_id },
+                    attempt,
+                    &self.cancellation,
+                )
                 .await
                 .context("rldp query failed")?;
 
             match data.as_deref().map(tl_proto::deserialize) {
                 // Received valid block
                 Some(Ok(proto::DataFull::Found {
-                    block_id, block, ..
-                })) => break BlockStuff::new(block, block_id),
+                    block_id,
+                    block,
+                    proof,
+                    is_link,
+                })) => {
+                    let block = BlockStuff::new(block, block_id)?;
+                    self.verify_next_block(prev_block_id, &block, proof, is_link)?;
+                    break Ok(block);
+                }
                 // Received invalid response
                 Some(Err(e)) => break Err(e.into()),
                 // Received empty response or nothing (due to timeout)
@@ -130,13 +307,81 @@ impl NodeUdpRpc {
         }
     }
 
+    /// Downloads up to `count` masterchain blocks after the specified one.
+    ///
+    /// Block ids are resolved sequentially while the blocks themselves
+    /// are downloaded in parallel (at most `concurrency` at a time).
+    /// Blocks are delivered in order, the stream ends after the first error.
+    pub fn get_blocks(
+        &self,
+        prev_block_id: ton_block::BlockIdExt,
+        count: u32,
+        concurrency: usize,
+    ) -> mpsc::Receiver<Result<BlockStuff>> {
+        let concurrency = std::cmp::max(concurrency, 1);
+        let (tx, rx) = mpsc::channel(concurrency);
+
+        let rpc = self.clone();
+        tokio::spawn(async move {
+            // Sequentially resolve next block ids
+            let ids = futures_util::stream::unfold(
+                (rpc.clone(), prev_block_id.clone(), count),
+                |(rpc, prev_block_id, remaining)| async move {
+                    if remaining == 0 {
+                        return None;
+                    }
+                    let res = rpc.get_next_block_id(&prev_block_id).await;
+                    let next = match &res {
+                        Ok(id) => id.clone(),
+                        Err(_) => prev_block_id,
+                    };
+                    // NOTE: stream is stopped by the consumer after the first error
+                    Some((res, (rpc, next, remaining - 1)))
+                },
+            );
+
+            // Download blocks in parallel preserving the order
+            let mut blocks = std::pin::pin!(ids
+                .map(|id| {
+                    let rpc = rpc.clone();
+                    async move { rpc.get_full_block(&id?).await }
+                })
+                .buffered(concurrency));
+
+            let mut prev_block_id = prev_block_id;
+            while let Some(res) = blocks.next().await {
+                let res = res.and_then(|(block, proof, is_link)| {
+                    rpc.verify_next_block(&prev_block_id, &block, &proof, is_link)?;
+                    Ok(block)
+                });
+
+                let is_err = match &res {
+                    Ok(block) => {
+                        prev_block_id = block.id().clone();
+                        false
+                    }
+                    Err(_) => true,
+                };
+
+                if tx.send(res).await.is_err() || is_err {
+                    break;
+                }
+            }
+        });
+
+        rx
+    }
+
     /// Polls the server for the specified block
+    ///
+    /// NOTE: block id must be received from the trusted source,
+    /// only the block data is checked against it.
     pub async fn get_block(&self, block_id: &ton_block::BlockIdExt) -> Result<BlockStuff> {
         let mut timeouts = BLOCK_TIMEOUTS;
         loop {
             match self
                 .inner
-                .adnl_query(proto::PrepareBlock { block_id }, 1000)
+                .adnl_query(proto::PrepareBlock { block_id }, 1000, &self.cancellation)
                 .await?
             {
                 proto::Prepared::Found => break,
@@ -152,7 +397,11 @@ impl NodeUdpRpc {
         loop {
             let data = self
                 .inner
-                .rldp_query(proto::RpcDownloadBlock { block_id }, attempt)
+                .rldp_query(
+                    proto::RpcDownloadBlock { block_id },
+                    attempt,
+                    &self.cancellation,
+                )
                 .await?;
 
             match data {
@@ -165,6 +414,151 @@ impl NodeUdpRpc {
             }
         }
     }
+
+    async fn get_next_block_id(
+        &self,
+        prev_block_id: &ton_block::BlockIdExt,
+    ) -> Result<ton_block::BlockIdExt> {
+        let mut timeouts = BLOCK_TIMEOUTS;
+        loop {
+            match self
+                .inner
+                .adnl_query(
+                    proto::GetNextBlockDescription { prev_block_id },
+                    1000,
+                    &self.cancellation,
+                )
+                .await?
+            {
+                proto::BlockDescription::Found { id } => break Ok(id),
+                proto::BlockDescription::Empty => {
+                    tracing::debug!("next block description not found");
+                    timeouts.sleep_and_update().await;
+                }
+            }
+        }
+    }
+
+    async fn get_full_block(
+        &self,
+        block_id: &ton_block::BlockIdExt,
+    ) -> Result<(BlockStuff, Vec<u8>, bool)> {
+        let mut timeouts = BLOCK_TIMEOUTS;
+
+        let mut attempt = 0;
+        loop {
+            let data = self
+                .inner
+                .rldp_query(
+                    proto::DownloadBlockFull { block_id },
+                    attempt,
+                    &self.cancellation,
+                )
+                .await
+                .context("rldp query failed")?;
+
+            match data.as_deref().map(tl_proto::deserialize) {
+                Some(Ok(proto::DataFull::Found {
+                    block_id: received_block_id,
+                    block,
+                    proof,
+                    is_link,
+                })) => {
+                    anyhow::ensure!(&received_block_id == block_id, "received block id mismatch");
+                    let block = BlockStuff::new(block, received_block_id)?;
+                    break Ok((block, proof.to_vec(), is_link));
+                }
+                Some(Err(e)) => break Err(e.into()),
+                Some(Ok(proto::DataFull::Empty)) | None => {
+                    tracing::debug!("full block not found");
+                    timeouts.sleep_and_update().await;
+                    attempt += 1;
+                }
+            }
+        }
+    }
+
+    /// Tries the configured address, then the last known address
+    /// and then searches the server in DHT.
+    async fn resolve_server_address(
+        &self,
+        configured_addr: SocketAddrV4,
+        peers_cache: &PeersCache,
+        peer_id_full: adnl::NodeIdFull,
+    ) -> Result<SocketAddrV4> {
+        let inner = &self.inner;
+
+        let mut candidates = vec![configured_addr];
+        if let Some(cached_addr) = peers_cache.get(&inner.peer_id) {
+            if cached_addr != configured_addr {
+                candidates.push(cached_addr);
+            }
+        }
+
+        for addr in candidates {
+            inner
+                .adnl
+                .add_peer(
+                    adnl::NewPeerContext::Dht,
+                    &inner.local_id,
+                    &inner.peer_id,
+                    addr,
+                    peer_id_full,
+                )
+                .context("failed to add server as a peer")?;
+
+            if self.get_capabilities().await.is_ok() {
+                return Ok(addr);
+            }
+            tracing::warn!(%addr, "server is not reachable");
+        }
+
+        tracing::info!(peer_id = %inner.peer_id, "searching server address in DHT");
+        let (addr, peer_id_full) = inner
+            .dht
+            .find_address(&inner.peer_id)
+            .await
+            .context("failed to find server address in DHT")?;
+
+        inner
+            .adnl
+            .add_peer(
+                adnl::NewPeerContext::Dht,
+                &inner.local_id,
+                &inner.peer_id,
+                addr,
+                peer_id_full,
+            )
+            .context("failed to add server as a peer")?;
+
+        tracing::info!(%addr, "found server address in DHT");
+        Ok(addr)
+    }
+
+    fn verify_next_block(
+        &self,
+        prev_block_id: &ton_block::BlockIdExt,
+        block: &BlockStuff,
+        proof: &[u8],
+        is_link: bool,
+    ) -> Result<()> {
+        let verifier = match &self.inner.verifier {
+            Some(verifier) => verifier,
+            None => return Ok(()),
+        };
+
+        let info = block.read_brief_info()?;
+        anyhow::ensure!(
+            &info.prev1 == prev_block_id && info.prev2.is_none(),
+            "next block {} doesn't reference the previous block {prev_block_id}",
+            block.id()
+        );
+        anyhow::ensure!(!is_link, "expected full proof for {}", block.id());
+
+        verifier
+            .verify(block, proof)
+            .with_context(|| format!("failed to verify block {}", block.id()))
+    }
 }
 
 struct NodeInner {
@@ -172,29 +566,45 @@ struct NodeInner {
     peer_id: adnl::NodeIdShort,
     query_prefix: Vec<u8>,
     adnl: Arc<adnl::Node>,
+    dht: Arc<dht::Node>,
     rldp: Arc<rldp::Node>,
     roundtrip: Mutex<u64>,
+    verifier: Option<BlockVerifier>,
 }
 
 impl NodeInner {
-    async fn adnl_query<Q, R>(&self, query: Q, timeout: u64) -> Result<R>
+    #[tracing::instrument(level = "debug", skip_all, fields(query = std::any::type_name::<Q>()))]
+    async fn adnl_query<Q, R>(
+        &self,
+        query: Q,
+        timeout: u64,
+        cancellation: &CancellationToken,
+    ) -> Result<R>
     where
         Q: TlWrite,
         for<'a> R: TlRead<'a, Repr = tl_proto::Boxed> + 'static,
     {
-        self.adnl
-            .query_with_prefix(
-                &self.local_id,
-                &self.peer_id,
-                &self.query_prefix,
-                query,
-                Some(timeout),
-            )
-            .await?
-            .context("timeout")
+        let query = self.adnl.query_with_prefix(
+            &self.local_id,
+            &self.peer_id,
+            &self.query_prefix,
+            query,
+            Some(timeout),
+        );
+        cancellable(cancellation, query).await?.context("timeout")
     }
 
-    async fn rldp_query<Q>(&self, query: Q, attempt: u64) -> Result<Option<Vec<u8>>>
+    #[tracing::instrument(
+        level = "debug",
+        skip_all,
+        fields(query = std::any::type_name::<Q>(), attempt = attempt)
+    )]
+    async fn rldp_query<Q>(
+        &self,
+        query: Q,
+        attempt: u64,
+        cancellation: &CancellationToken,
+    ) -> Result<Option<Vec<u8>>>
     where
         Q: TlWrite,
     {
@@ -214,10 +624,10 @@ impl NodeInner {
             }
         };
 
-        let (answer, roundtrip) = self
+        let query = self
             .rldp
-            .query(&self.local_id, &self.peer_id, query_data, roundtrip)
-            .await?;
+            .query(&self.local_id, &self.peer_id, query_data, roundtrip);
+        let (answer, roundtrip) = cancellable(cancellation, query).await?;
 
         if answer.is_some() {
             let mut current_roundtrip = self.roundtrip.lock();
@@ -266,6 +676,81 @@ impl DownloaderTimeouts {
     }
 }
 
+/// Resolves the query unless the token is cancelled first
+async fn cancellable<T>(
+    cancellation: &CancellationToken,
+    query: impl std::future::Future<Output = Result<T>>,
+) -> Result<T> {
+    tokio::select! {
+        res = query => res,
+        _ = cancellation.cancelled() => Err(NodeRpcError::Cancelled.into()),
+    }
+}
+
+/// Stores the server address in DHT signed with the specified ADNL key
+pub async fn announce_address(
+    dirs: &ProjectDirs,
+    key: &ed25519::SecretKey,
+    server_address: SocketAddrV4,
+) -> Result<bool> {
+    let ip_addr = public_ip::addr_v4()
+        .await
+        .context("failed to resolve public ip")?;
+
+    // NOTE: client port can be already used by the running manager
+    let port = find_free_udp_port()?;
+
+    let keystore = adnl::Keystore::builder()
+        .with_tagged_key(key.to_bytes(), KEY_TAG)?
+        .build();
+
+    let (adnl, dht) = NetworkBuilder::with_adnl(
+        SocketAddrV4::new(ip_addr, port),
+        keystore,
+        Default::default(),
+    )
+    .with_dht(KEY_TAG, Default::default())
+    .build()
+    .context("failed to build network stack")?;
+
+    add_static_dht_nodes(&dht, dirs);
+
+    dht.store_address(adnl.key_by_tag(KEY_TAG)?, server_address)
+        .await
+        .context("failed to store address in DHT")
+}
+
+fn add_static_dht_nodes(dht: &Arc<dht::Node>, dirs: &ProjectDirs) {
+    match GlobalConfig::load(&dirs.global_config) {
+        Ok(global_config) => {
+            for peer in global_config.dht_nodes {
+                if let Err(e) = dht.add_dht_peer(peer) {
+                    tracing::debug!("failed to add static DHT node: {e:?}");
+                }
+            }
+        }
+        Err(e) => tracing::warn!("DHT bootstrap is not available: {e:?}"),
+    }
+}
+
+/// Asks the OS for a currently unused local UDP port
+fn find_free_udp_port() -> Result<u16> {
+    let port = std::net::UdpSocket::bind((std::net::Ipv4Addr::UNSPECIFIED, 0))
+        .and_then(|socket| socket.local_addr())
+        .context("failed to find a free UDP port")?
+        .port();
+    Ok(port)
+}
+
+fn is_addr_in_use(e: &anyhow::Error) -> bool {
+    e.chain().any(|cause| {
+        matches!(
+            cause.downcast_ref::<std::io::Error>(),
+            Some(e) if e.kind() == std::io::ErrorKind::AddrInUse
+        )
+    })
+}
+
 fn session_keys() -> &'static [u8; 32] {
     use once_cell::sync::OnceCell;
 
@@ -274,3 +759,6 @@ fn session_keys() -> &'static [u8; 32] {
 }
 
 const KEY_TAG: usize = 0;
+
+const BIND_ATTEMPTS: usize = 5;
+const BIND_RETRY_INTERVAL: Duration = Duration::from_secs(1);
@@ -0,0 +1,165 @@
+use std::collections::HashMap;
+use std::net::SocketAddrV4;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use everscale_network::{adnl, proto};
+use serde::{Deserialize, Serialize};
+
+use crate::config::write_atomic;
+
+/// Last known addresses of the ADNL peers and discovered DHT nodes
+pub struct PeersCache {
+    path: PathBuf,
+    entries: PeersCacheEntries,
+}
+
+impl PeersCache {
+    pub const MAX_DHT_NODES: usize = 64;
+
+    /// Loads peers cache or creates an empty one if the file doesn't exist
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+
+        let entries = if path.exists() {
+            let data = std::fs::read_to_string(&path).context("failed to read peers cache")?;
+            match serde_json::from_str(&data) {
+                Ok(entries) => entries,
+                Err(e) => {
+                    tracing::warn!("failed to parse peers cache, ignoring: {e:?}");
+                    Default::default()
+                }
+            }
+        } else {
+            Default::default()
+        };
+
+        Ok(Self { path, entries })
+    }
+
+    pub fn get(&self, peer_id: &adnl::NodeIdShort) -> Option<SocketAddrV4> {
+        self.entries
+            .peers
+            .get(&hex::encode(peer_id.as_slice()))
+            .copied()
+    }
+
+    /// Updates peer address and stores the cache if it was changed
+    pub fn update(&mut self, peer_id: &adnl::NodeIdShort, addr: SocketAddrV4) -> Result<()> {
+        let prev = self
+            .entries
+            .peers
+            .insert(hex::encode(peer_id.as_slice()), addr);
+        if prev == Some(addr) {
+            return Ok(());
+        }
+        self.save()
+    }
+
+    /// Returns the DHT nodes known from the previous runs.
+    ///
+    /// NOTE: nodes are signed by themselves, so the signature is checked when they are added
+    pub fn dht_nodes(&self) -> Vec<proto::dht::NodeOwned> {
+        self.entries
+            .dht_nodes
+            .iter()
+            .filter_map(|node| {
+                let data = base64::decode(node).ok()?;
+                tl_proto::deserialize(&data).ok()
+            })
+            .collect()
+    }
+
+    /// Replaces the known DHT nodes and stores the cache if they were changed
+    pub fn update_dht_nodes(&mut self, nodes: &[proto::dht::NodeOwned]) -> Result<()> {
+        let mut nodes = nodes
+            .iter()
+            .take(Self::MAX_DHT_NODES)
+            .map(|node| base64::encode(tl_proto::serialize(node)))
+            .collect::<Vec<_>>();
+        nodes.sort_unstable();
+
+        if nodes.is_empty() || nodes == self.entries.dht_nodes {
+            return Ok(());
+        }
+        self.entries.dht_nodes = nodes;
+        self.save()
+    }
+
+    fn save(&self) -> Result<()> {
+        let data = serde_json::to_string_pretty(&self.entries)?;
+        write_atomic(&self.path, &data).context("failed to save peers cache")
+    }
+}
+
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+struct PeersCacheEntries {
+    peers: HashMap<String, SocketAddrV4>,
+    /// TL-serialized DHT nodes, encoded as base64
+    dht_nodes: Vec<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_dht_node(key: u8, port: u32) -> proto::dht::NodeOwned {
+        proto::dht::NodeOwned {
+            id: everscale_crypto::tl::PublicKeyOwned::Ed25519 { key: [key; 32] },
+            addr_list: proto::adnl::AddressList {
+                address: Some(proto::adnl::Address {
+                    ip: 0x7f000001,
+                    port,
+                }),
+                version: 1,
+                reinit_date: 1,
+                expire_at: 0,
+            },
+            version: 1,
+            signature: vec![key; 64].into(),
+        }
+    }
+
+    #[test]
+    fn entries_are_persisted() {
+        let path = std::env::temp_dir().join(format!(
+            "nodekeeper-peers-cache-{}.json",
+            std::process::id()
+        ));
+
+        let peer_id = adnl::NodeIdShort::new([1; 32]);
+        let addr = "127.0.0.1:30303".parse().unwrap();
+        let nodes = [make_dht_node(2, 30310), make_dht_node(3, 30311)];
+
+        let mut cache = PeersCache::load(&path).unwrap();
+        assert!(cache.get(&peer_id).is_none());
+        assert!(cache.dht_nodes().is_empty());
+        cache.update(&peer_id, addr).unwrap();
+        cache.update_dht_nodes(&nodes).unwrap();
+
+        let result = PeersCache::load(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        let cache = result.unwrap();
+        assert_eq!(cache.get(&peer_id), Some(addr));
+
+        let mut stored = cache
+            .dht_nodes()
+            .iter()
+            .map(tl_proto::serialize)
+            .collect::<Vec<_>>();
+        stored.sort_unstable();
+        let mut expected = nodes.iter().map(tl_proto::serialize).collect::<Vec<_>>();
+        expected.sort_unstable();
+        assert_eq!(stored, expected);
+    }
+
+    #[test]
+    fn cache_without_dht_nodes_is_accepted() {
+        let entries: PeersCacheEntries =
+            serde_json::from_str(r#"{"peers":{"00":"127.0.0.1:30303"}}"#).unwrap();
+        assert_eq!(entries.peers.len(), 1);
+        assert!(entries.dht_nodes.is_empty());
+    }
+}
@@ -21,6 +21,32 @@ pub struct DownloadNextBlockFull<'tl> {
     pub prev_block_id: &'tl ton_block::BlockIdExt,
 }
 
+#[derive(Copy, Clone, TlWrite)]
+#[tl(boxed, id = "tonNode.downloadBlockFull", scheme = "proto.tl")]
+pub struct DownloadBlockFull<'tl> {
+    #[tl(with = "tl_block_id")]
+    pub block_id: &'tl ton_block::BlockIdExt,
+}
+
+#[derive(Copy, Clone, TlWrite)]
+#[tl(boxed, id = "tonNode.getNextBlockDescription", scheme = "proto.tl")]
+pub struct GetNextBlockDescription<'tl> {
+    #[tl(with = "tl_block_id")]
+    pub prev_block_id: &'tl ton_block::BlockIdExt,
+}
+
+#[derive(Clone, TlRead)]
+#[tl(boxed, scheme = "proto.tl")]
+pub enum BlockDescription {
+    #[tl(id = "tonNode.blockDescriptionEmpty")]
+    Empty,
+    #[tl(id = "tonNode.blockDescription")]
+    Found {
+        #[tl(with = "tl_block_id")]
+        id: ton_block::BlockIdExt,
+    },
+}
+
 #[derive(Clone, TlRead)]
 #[tl(boxed, scheme = "proto.tl")]
 pub enum DataFull<'tl> {
@@ -61,6 +87,26 @@ pub struct Capabilities {
     pub capabilities: u64,
 }
 
+#[derive(TlWrite)]
+#[tl(boxed, id = "overlay.getRandomPeers", scheme = "proto.tl")]
+pub struct GetRandomPeers {
+    pub peers: OverlayNodes,
+}
+
+#[derive(Clone, TlWrite, TlRead)]
+#[tl(boxed, id = "overlay.nodes", scheme = "proto.tl")]
+pub struct OverlayNodes {
+    pub nodes: Vec<OverlayNode>,
+}
+
+#[derive(Clone, TlWrite, TlRead)]
+pub struct OverlayNode {
+    pub id: everscale_crypto::tl::PublicKeyOwned,
+    pub overlay: [u8; 32],
+    pub version: u32,
+    pub signature: Vec<u8>,
+}
+
 mod tl_block_id {
     use super::*;
 
@@ -0,0 +1,392 @@
+use anyhow::Result;
+use parking_lot::RwLock;
+use ton_block::Deserializable;
+
+use crate::util::BlockStuff;
+
+/// Checks that masterchain blocks received from the peer are signed
+/// by the current validator set.
+pub struct BlockVerifier {
+    zerostate_file_hash: ton_types::UInt256,
+    trusted_vset: RwLock<Option<TrustedValidatorSet>>,
+}
+
+impl BlockVerifier {
+    pub fn new(zerostate_file_hash: &[u8; 32]) -> Self {
+        Self {
+            zerostate_file_hash: ton_types::UInt256::from(*zerostate_file_hash),
+            trusted_vset: Default::default(),
+        }
+    }
+
+    /// Replaces the current validator set with the one from the trusted config
+    /// (e.g. received through the authenticated control channel).
+    pub fn set_trusted_config(&self, config: &ton_block::ConfigParams) -> Result<()> {
+        let vset = TrustedValidatorSet::from_config(config)?;
+        *self.trusted_vset.write() = Some(vset);
+        Ok(())
+    }
+
+    /// Verifies the masterchain block and its proof.
+    ///
+    /// NOTE: block data itself is already checked against the id in [`BlockStuff::new`]
+    pub fn verify(&self, block: &BlockStuff, proof: &[u8]) -> Result<(), VerificationError> {
+        let id = block.id();
+        if !id.shard_id.is_masterchain() {
+            return Err(VerificationError::NotMasterchainBlock);
+        }
+
+        if id.seq_no == 0 {
+            return if id.file_hash == self.zerostate_file_hash {
+                Ok(())
+            } else {
+                Err(VerificationError::ZerostateMismatch)
+            };
+        }
+
+        let proof = ton_block::BlockProof::construct_from_bytes(proof)
+            .map_err(|_| VerificationError::InvalidProof)?;
+        if &proof.proof_for != id {
+            return Err(VerificationError::ProofIdMismatch);
+        }
+
+        let merkle_proof = ton_block::MerkleProof::construct_from_cell(proof.root)
+            .map_err(|_| VerificationError::InvalidProof)?;
+        if merkle_proof.hash != id.root_hash {
+            return Err(VerificationError::ProofRootHashMismatch);
+        }
+
+        let signatures = proof
+            .signatures
+            .ok_or(VerificationError::SignaturesNotFound)?;
+
+        let info = block
+            .block()
+            .read_info()
+            .map_err(|_| VerificationError::InvalidBlock)?;
+
+        {
+            let trusted_vset = self.trusted_vset.read();
+            let trusted_vset = trusted_vset
+                .as_ref()
+                .ok_or(VerificationError::NoTrustedValidatorSet)?;
+
+            trusted_vset.check_signatures(
+                id,
+                info.gen_utime(),
+                &signatures.validator_info,
+                &signatures.pure_signatures,
+            )?;
+        }
+
+        // Switch to the new validator set on key blocks
+        if info.key_block() {
+            let config = block
+                .block()
+                .read_extra()
+                .and_then(|extra| extra.read_custom())
+                .ok()
+                .flatten()
+                .and_then(|custom| custom.config().cloned())
+                .ok_or(VerificationError::InvalidBlock)?;
+
+            let vset = TrustedValidatorSet::from_config(&config)
+                .map_err(|_| VerificationError::InvalidBlock)?;
+            *self.trusted_vset.write() = Some(vset);
+
+            tracing::debug!(block_id = %id, "switched to the new validator set");
+        }
+
+        Ok(())
+    }
+}
+
+struct TrustedValidatorSet {
+    vset: ton_block::ValidatorSet,
+    prev_vset: Option<ton_block::ValidatorSet>,
+    catchain_config: ton_block::CatchainConfig,
+}
+
+impl TrustedValidatorSet {
+    fn from_config(config: &ton_block::ConfigParams) -> Result<Self> {
+        Ok(Self {
+            vset: config.validator_set()?,
+            // NOTE: previous set is used to verify blocks before the last key block
+            prev_vset: config.prev_validator_set().ok(),
+            catchain_config: config.catchain_config()?,
+        })
+    }
+
+    fn check_signatures(
+        &self,
+        id: &ton_block::BlockIdExt,
+        gen_utime: ton_block::UnixTime32,
+        validator_info: &ton_block::ValidatorBaseInfo,
+        signatures: &ton_block::BlockSignaturesPure,
+    ) -> Result<(), VerificationError> {
+        let mut validators = None;
+        for vset in std::iter::once(&self.vset).chain(&self.prev_vset) {
+            let (subset, hash_short) = vset
+                .calc_subset(
+                    &self.catchain_config,
+                    ton_block::SHARD_FULL,
+                    ton_block::MASTERCHAIN_ID,
+                    validator_info.catchain_seqno,
+                    gen_utime,
+                )
+                .map_err(|_| VerificationError::InvalidValidatorSet)?;
+
+            if hash_short == validator_info.validator_list_hash_short {
+                validators = Some(subset);
+                break;
+            }
+        }
+        let validators = validators.ok_or(VerificationError::ValidatorSetMismatch)?;
+
+        let total_weight: u64 = validators.iter().map(|item| item.weight).sum();
+
+        let data = ton_block::Block::build_data_for_sign(&id.root_hash, &id.file_hash);
+        let weight = signatures
+            .check_signatures(&validators, &data)
+            .map_err(|_| VerificationError::InvalidSignatures)?;
+
+        if weight * 3 <= total_weight * 2 {
+            return Err(VerificationError::TooSmallSignaturesWeight);
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum VerificationError {
+    #[error("not a masterchain block")]
+    NotMasterchainBlock,
+    #[error("zerostate file hash mismatch")]
+    ZerostateMismatch,
+    #[error("invalid block")]
+    InvalidBlock,
+    #[error("invalid block proof")]
+    InvalidProof,
+    #[error("block proof is for another block")]
+    ProofIdMismatch,
+    #[error("block proof root hash mismatch")]
+    ProofRootHashMismatch,
+    #[error("block proof has no signatures")]
+    SignaturesNotFound,
+    #[error("no trusted validator set")]
+    NoTrustedValidatorSet,
+    #[error("invalid validator set")]
+    InvalidValidatorSet,
+    #[error("validator set mismatch")]
+    ValidatorSetMismatch,
+    #[error("invalid block signatures")]
+    InvalidSignatures,
+    #[error("too small signatures weight")]
+    TooSmallSignaturesWeight,
+}
+
+#[cfg(test)]
+mod tests {
+    use everscale_crypto::ed25519;
+
+    use super::*;
+
+    struct Validator {
+        secret: ed25519::SecretKey,
+        descr: ton_block::ValidatorDescr,
+    }
+
+    impl Validator {
+        fn generate(weight: u64) -> Self {
+            let secret = ed25519::SecretKey::generate(&mut rand::thread_rng());
+            let public = ed25519::PublicKey::from(&secret);
+            let public_key = ton_block::SigPubKey::from_bytes(&public.to_bytes()).unwrap();
+            Self {
+                secret,
+                descr: ton_block::ValidatorDescr::with_params(public_key, weight, None),
+            }
+        }
+
+        fn sign(&self, id: &ton_block::BlockIdExt) -> ton_block::CryptoSignaturePair {
+            self.sign_as(self.descr.compute_node_id_short(), id)
+        }
+
+        fn sign_as(
+            &self,
+            node_id_short: ton_types::UInt256,
+            id: &ton_block::BlockIdExt,
+        ) -> ton_block::CryptoSignaturePair {
+            let public = ed25519::PublicKey::from(&self.secret);
+            let data = ton_block::Block::build_data_for_sign(&id.root_hash, &id.file_hash);
+            let signature = self.secret.expand().sign_raw(&data, &public);
+            ton_block::CryptoSignaturePair::with_params(
+                node_id_short,
+                ton_block::CryptoSignature::from_bytes(&signature).unwrap(),
+            )
+        }
+    }
+
+    fn make_vset(validators: &[Validator]) -> ton_block::ValidatorSet {
+        let list = validators.iter().map(|v| v.descr.clone()).collect();
+        ton_block::ValidatorSet::new(0, u32::MAX, validators.len() as u16, list).unwrap()
+    }
+
+    fn make_block_id(seq_no: u32, hash: u8) -> ton_block::BlockIdExt {
+        ton_block::BlockIdExt::with_params(
+            ton_block::ShardIdent::masterchain(),
+            seq_no,
+            ton_types::UInt256::from([hash; 32]),
+            ton_types::UInt256::from([hash.wrapping_add(1); 32]),
+        )
+    }
+
+    fn check(
+        trusted: &TrustedValidatorSet,
+        signed_by: &ton_block::ValidatorSet,
+        id: &ton_block::BlockIdExt,
+        signatures: Vec<ton_block::CryptoSignaturePair>,
+    ) -> Result<(), VerificationError> {
+        let (_, hash_short) = signed_by
+            .calc_subset(
+                &trusted.catchain_config,
+                ton_block::SHARD_FULL,
+                ton_block::MASTERCHAIN_ID,
+                0,
+                Default::default(),
+            )
+            .unwrap();
+        let validator_info = ton_block::ValidatorBaseInfo::with_params(hash_short, 0);
+
+        let mut pure_signatures = ton_block::BlockSignaturesPure::new();
+        for signature in signatures {
+            pure_signatures.add_sigpair(signature);
+        }
+
+        trusted.check_signatures(id, Default::default(), &validator_info, &pure_signatures)
+    }
+
+    fn make_trusted(vset: ton_block::ValidatorSet) -> TrustedValidatorSet {
+        TrustedValidatorSet {
+            vset,
+            prev_vset: None,
+            catchain_config: Default::default(),
+        }
+    }
+
+    // NOTE: validator sets are generated, so no captured network data is required
+
+    #[test]
+    fn enough_signatures_are_accepted() {
+        let validators = (0..4).map(|_| Validator::generate(10)).collect::<Vec<_>>();
+        let vset = make_vset(&validators);
+        let trusted = make_trusted(vset.clone());
+
+        let id = make_block_id(100, 1);
+        let signatures = validators[..3].iter().map(|v| v.sign(&id)).collect();
+        check(&trusted, &vset, &id, signatures).unwrap();
+    }
+
+    #[test]
+    fn signatures_for_another_root_hash_are_rejected() {
+        let validators = (0..4).map(|_| Validator::generate(10)).collect::<Vec<_>>();
+        let vset = make_vset(&validators);
+        let trusted = make_trusted(vset.clone());
+
+        let id = make_block_id(100, 1);
+        let other_id = make_block_id(100, 2);
+        let signatures = validators.iter().map(|v| v.sign(&other_id)).collect();
+        assert!(matches!(
+            check(&trusted, &vset, &id, signatures),
+            Err(VerificationError::InvalidSignatures)
+        ));
+    }
+
+    #[test]
+    fn two_thirds_of_weight_is_not_enough() {
+        let validators = (0..3).map(|_| Validator::generate(10)).collect::<Vec<_>>();
+        let vset = make_vset(&validators);
+        let trusted = make_trusted(vset.clone());
+
+        let id = make_block_id(100, 1);
+        let signatures = validators[..2].iter().map(|v| v.sign(&id)).collect();
+        assert!(matches!(
+            check(&trusted, &vset, &id, signatures),
+            Err(VerificationError::TooSmallSignaturesWeight)
+        ));
+
+        // Weight is counted, not the number of signatures
+        let validators = [10, 10, 40].map(Validator::generate);
+        let vset = make_vset(&validators);
+        let trusted = make_trusted(vset.clone());
+        let signatures = validators[..2].iter().map(|v| v.sign(&id)).collect();
+        assert!(matches!(
+            check(&trusted, &vset, &id, signatures),
+            Err(VerificationError::TooSmallSignaturesWeight)
+        ));
+    }
+
+    #[test]
+    fn non_member_signatures_are_not_counted() {
+        let validators = (0..3).map(|_| Validator::generate(10)).collect::<Vec<_>>();
+        let vset = make_vset(&validators);
+        let trusted = make_trusted(vset.clone());
+
+        let id = make_block_id(100, 1);
+        let outsider = Validator::generate(10);
+
+        // Signature under its own id is ignored
+        let mut signatures = validators[..2]
+            .iter()
+            .map(|v| v.sign(&id))
+            .collect::<Vec<_>>();
+        signatures.push(outsider.sign(&id));
+        assert!(matches!(
+            check(&trusted, &vset, &id, signatures),
+            Err(VerificationError::TooSmallSignaturesWeight)
+        ));
+
+        // Signature under the id of the member is invalid
+        let mut signatures = validators[..2]
+            .iter()
+            .map(|v| v.sign(&id))
+            .collect::<Vec<_>>();
+        signatures.push(outsider.sign_as(validators[2].descr.compute_node_id_short(), &id));
+        assert!(matches!(
+            check(&trusted, &vset, &id, signatures),
+            Err(VerificationError::InvalidSignatures)
+        ));
+    }
+
+    #[test]
+    fn unknown_validator_set_is_rejected() {
+        let validators = (0..3).map(|_| Validator::generate(10)).collect::<Vec<_>>();
+        let trusted = make_trusted(make_vset(&validators));
+
+        let others = (0..3).map(|_| Validator::generate(10)).collect::<Vec<_>>();
+        let other_vset = make_vset(&others);
+
+        let id = make_block_id(100, 1);
+        let signatures = others.iter().map(|v| v.sign(&id)).collect();
+        assert!(matches!(
+            check(&trusted, &other_vset, &id, signatures),
+            Err(VerificationError::ValidatorSetMismatch)
+        ));
+    }
+
+    #[test]
+    fn previous_validator_set_is_accepted() {
+        let prev = (0..3).map(|_| Validator::generate(10)).collect::<Vec<_>>();
+        let prev_vset = make_vset(&prev);
+        let current = (0..3).map(|_| Validator::generate(10)).collect::<Vec<_>>();
+        let trusted = TrustedValidatorSet {
+            vset: make_vset(&current),
+            prev_vset: Some(prev_vset.clone()),
+            catchain_config: Default::default(),
+        };
+
+        let id = make_block_id(100, 1);
+        let signatures = prev.iter().map(|v| v.sign(&id)).collect();
+        check(&trusted, &prev_vset, &id, signatures).unwrap();
+    }
+}
@@ -1,4 +1,5 @@
 use std::collections::hash_map;
+use std::path::PathBuf;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, Weak};
 use std::time::Duration;
@@ -7,18 +8,24 @@ use anyhow::{Context, Result};
 use arc_swap::ArcSwapOption;
 use nekoton_abi::FunctionExt;
 use nekoton_utils::SimpleClock;
+use once_cell::sync::OnceCell;
 use rustc_hash::FxHashMap;
-use tokio::sync::{mpsc, oneshot, Notify};
+use serde::{Deserialize, Serialize};
+use tokio::sync::{broadcast, mpsc, oneshot, Notify};
 use tokio_util::sync::{CancellationToken, DropGuard};
 use ton_block::{Deserializable, Serializable};
 
+use super::data_source::{DataSource, JrpcClient};
 use super::node_tcp_rpc::{ConfigWithId, NodeTcpRpc};
 use super::node_udp_rpc::NodeUdpRpc;
-use crate::util::{split_address, BlockStuff, FxDashMap, TransactionWithHash};
+use crate::config::{AppConfigNetwork, SystemAddresses};
+use crate::contracts::wallet::SpendingPolicy;
+use crate::util::{
+    serde_block_id, split_address, BlockStuff, Emulator, FxDashMap, TransactionWithHash,
+};
 
 pub struct Subscription {
-    node_tcp_rpc: NodeTcpRpc,
-    node_udp_rpc: NodeUdpRpc,
+    transport: Transport,
     last_mc_block: ArcSwapOption<StoredMcBlock>,
     subscription_count: AtomicUsize,
     subscriptions_changed: Arc<Notify>,
@@ -26,26 +33,23 @@ pub struct Subscription {
     mc_subscriptions: AccountSubscriptions,
     sc_subscriptions: AccountSubscriptions,
     global_id: tokio::sync::Mutex<Option<i32>>,
+    blockchain_config: ArcSwapOption<ConfigWithId>,
+    state_file: OnceCell<PathBuf>,
+    network_params: OnceCell<AppConfigNetwork>,
+    spending_policy: OnceCell<SpendingPolicy>,
+    config_events_tx: broadcast::Sender<ConfigChangedEvent>,
+    cancellation: CancellationToken,
     _cancellation: DropGuard,
 }
 
 impl Subscription {
     pub fn new(node_tcp_rpc: NodeTcpRpc, node_udp_rpc: NodeUdpRpc) -> Arc<Self> {
-        let cancellation = CancellationToken::new();
-
-        let subscription = Arc::new(Self {
-            node_tcp_rpc,
-            node_udp_rpc,
-            last_mc_block: Default::default(),
-            subscription_count: Default::default(),
-            subscriptions_changed: Default::default(),
-            subscription_loop_step: Default::default(),
-            mc_subscriptions: Default::default(),
-            sc_subscriptions: Default::default(),
-            global_id: Default::default(),
-            _cancellation: cancellation.clone().drop_guard(),
+        let subscription = Self::with_transport(Transport::Node {
+            tcp: node_tcp_rpc,
+            udp: node_udp_rpc,
         });
 
+        let cancellation = subscription.cancellation.clone();
         let walk_fut = walk_blocks(Arc::downgrade(&subscription));
 
         tokio::spawn(async move {
@@ -58,12 +62,51 @@ impl Subscription {
         subscription
     }
 
+    /// Creates a subscription which works only through the remote JRPC endpoint
+    /// (without the local node control server).
+    ///
+    /// NOTE: blocks are not processed in this mode, so transactions and account
+    /// subscriptions are not available. External messages are tracked by polling.
+    pub fn new_remote(client: JrpcClient) -> Arc<Self> {
+        Self::with_transport(Transport::Remote(client))
+    }
+
+    fn with_transport(transport: Transport) -> Arc<Self> {
+        let cancellation = CancellationToken::new();
+
+        Arc::new(Self {
+            transport,
+            last_mc_block: Default::default(),
+            subscription_count: Default::default(),
+            subscriptions_changed: Default::default(),
+            subscription_loop_step: Default::default(),
+            mc_subscriptions: Default::default(),
+            sc_subscriptions: Default::default(),
+            global_id: Default::default(),
+            blockchain_config: Default::default(),
+            state_file: Default::default(),
+            network_params: Default::default(),
+            spending_policy: Default::default(),
+            config_events_tx: broadcast::channel(CONFIG_EVENTS_CAPACITY).0,
+            cancellation: cancellation.clone(),
+            _cancellation: cancellation.drop_guard(),
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
     pub async fn ensure_ready(&self) -> Result<()> {
-        let (stats, capabilities) = futures_util::future::join(
-            self.node_tcp_rpc.get_stats(),
-            self.node_udp_rpc.get_capabilities(),
-        )
-        .await;
+        let (tcp, udp) = match &self.transport {
+            Transport::Node { tcp, udp } => (tcp, udp),
+            Transport::Remote(_) => {
+                self.refresh_blockchain_config()
+                    .await
+                    .context("failed to get blockchain config")?;
+                return Ok(());
+            }
+        };
+
+        let (stats, capabilities) =
+            futures_util::future::join(tcp.get_stats(), udp.get_capabilities()).await;
 
         stats
             .context("failed to get node stats")?
@@ -73,32 +116,36 @@ impl Subscription {
         Ok(())
     }
 
-    pub fn tcp_rpc(&self) -> &NodeTcpRpc {
-        &self.node_tcp_rpc
+    /// Whether the subscription works without the local node
+    pub fn is_remote(&self) -> bool {
+        matches!(&self.transport, Transport::Remote(_))
     }
 
-    pub fn udp_rpc(&self) -> &NodeUdpRpc {
-        &self.node_udp_rpc
+    pub fn tcp_rpc(&self) -> Result<&NodeTcpRpc, LocalNodeRequired> {
+        match &self.transport {
+            Transport::Node { tcp, .. } => Ok(tcp),
+            Transport::Remote(_) => Err(LocalNodeRequired),
+        }
+    }
+
+    pub fn udp_rpc(&self) -> Result<&NodeUdpRpc, LocalNodeRequired> {
+        match &self.transport {
+            Transport::Node { udp, .. } => Ok(udp),
+            Transport::Remote(_) => Err(LocalNodeRequired),
+        }
     }
 
     pub async fn get_account_state(
         &self,
         address: &ton_block::MsgAddressInt,
     ) -> Result<Option<ton_block::AccountStuff>> {
-        let state = self
-            .node_tcp_rpc
-            .get_shard_account_state(address)
-            .await
-            .context("failed to get shard account state")?;
-        match state
-            .read_account()
-            .context("failed to read account state")?
-        {
-            ton_block::Account::Account(state) => Ok(Some(state)),
-            ton_block::Account::AccountNone => Ok(None),
+        match &self.transport {
+            Transport::Node { tcp, .. } => DataSource::get_account_state(tcp, address).await,
+            Transport::Remote(client) => DataSource::get_account_state(client, address).await,
         }
     }
 
+    #[tracing::instrument(level = "debug", skip_all, fields(%address, function = %function.name))]
     pub async fn run_local(
         &self,
         address: &ton_block::MsgAddressInt,
@@ -116,121 +163,247 @@ impl Subscription {
         }
     }
 
-    pub async fn send_message_with_retires<F>(&self, mut f: F) -> Result<TransactionWithHash>
+    /// Sends external messages until one of them is delivered.
+    ///
+    /// `f` is called for each attempt to build a new message with the specified timeout.
+    /// Each message is rebroadcasted until it expires. Delivered transaction
+    /// is checked for the successful compute and action phases.
+    #[tracing::instrument(skip_all)]
+    pub async fn send_message_reliable<F>(&self, mut f: F) -> Result<TransactionWithHash>
     where
         F: FnMut(u32, Option<i32>) -> Result<(ton_block::Message, u32)>,
     {
+        const MAX_ATTEMPTS: usize = 5;
+        const TIMEOUT: u32 = 60;
+
         let signature_id = self.get_signature_id().await?;
 
-        let timeout = 60;
-        loop {
-            let (message, expire_at) = f(timeout, signature_id)?;
-            if let Some(tx) = self.send_message(&message, expire_at).await? {
-                break Ok(tx);
+        for attempt in 0..MAX_ATTEMPTS {
+            let (message, expire_at) = f(TIMEOUT, signature_id)?;
+            match self.send_message(&message, expire_at).await? {
+                TransactionOutcome::Expired => {
+                    tracing::warn!(attempt, "retrying with a new message");
+                }
+                outcome => return outcome.into_result().map_err(From::from),
             }
         }
+
+        Err(SendMessageError::Expired.into())
     }
 
+    /// Broadcasts an external message and waits until it is delivered or expired.
+    #[tracing::instrument(skip_all, fields(expire_at = expire_at))]
     pub async fn send_message(
         &self,
         message: &ton_block::Message,
         expire_at: u32,
-    ) -> Result<Option<TransactionWithHash>> {
+    ) -> Result<TransactionOutcome> {
         // Prepare dst address
         let raw_dst = match message.ext_in_header() {
             Some(header) => header.dst.clone(),
             None => anyhow::bail!("expected external message"),
         };
-        let (workchain, dst) = split_address(&raw_dst)?;
 
         // Get message hash
         let msg_cell = message.serialize()?;
         let msg_hash = msg_cell.repr_hash();
         let data = ton_types::serialize_toc(&msg_cell)?;
 
-        // Find pending messages map
-        let subscriptions = match workchain {
-            ton_block::MASTERCHAIN_ID => &self.mc_subscriptions,
-            ton_block::BASE_WORKCHAIN_ID => &self.sc_subscriptions,
-            _ => anyhow::bail!("unsupported workchain"),
+        let node_tcp_rpc = match &self.transport {
+            Transport::Node { tcp, .. } => tcp,
+            Transport::Remote(client) => {
+                // There are no blocks to wait for, so just poll the transaction
+                client.send_message(message).await?;
+                tracing::debug!(dst = %raw_dst, ?msg_hash, "external message broadcasted");
+
+                let tx = self.poll_transaction(&msg_hash, expire_at).await?;
+                return self.make_transaction_outcome(&raw_dst, msg_hash, tx).await;
+            }
         };
 
         // Insert pending message
-        let (subscription_loop_works, rx) = {
-            let mut subscription = subscriptions.entry(dst).or_default();
-
-            let rx = match subscription.pending_messages.entry(msg_hash) {
-                hash_map::Entry::Vacant(entry) => {
-                    let (tx, rx) = oneshot::channel();
-                    entry.insert(PendingMessage {
-                        expire_at,
-                        tx: Some(tx),
-                    });
-                    rx
-                }
-                hash_map::Entry::Occupied(_) => anyhow::bail!("message already sent"),
-            };
-
-            // Start waiting for the subscription loop to start
-            let subscription_loop_works = self.subscription_loop_step.notified();
-
-            // Notify waiters while pending messages is still acquired
-            self.subscription_count.fetch_add(1, Ordering::Release);
-            self.subscriptions_changed.notify_waiters();
-
-            // Drop the lock
-            (subscription_loop_works, rx)
-        };
+        let mut pending = self.add_pending_message(&raw_dst, msg_hash, expire_at)?;
 
         // Wait until subscription loop was definitely started
-        subscription_loop_works.await;
+        (&mut pending.subscription_loop_works).await;
 
         // Send the message
-        if let Err(e) = self.node_tcp_rpc.send_message(data).await {
+        if let Err(e) = node_tcp_rpc.send_message(&data).await {
             // Remove pending message from the map before returning an error
-            match subscriptions.entry(dst) {
-                dashmap::mapref::entry::Entry::Occupied(mut entry) => {
-                    let should_remove = {
-                        let subscription = entry.get_mut();
-                        subscription.pending_messages.remove(&msg_hash);
-                        self.subscription_count.fetch_sub(1, Ordering::Release);
-                        self.subscriptions_changed.notify_waiters();
-                        subscription.is_empty()
-                    };
+            self.remove_pending_message(&pending);
+            return Err(e);
+        }
+        tracing::debug!(dst = %raw_dst, ?msg_hash, "external message broadcasted");
 
-                    if should_remove {
-                        entry.remove();
+        // Wait for the message execution, rebroadcasting it until it expires
+        let mut rebroadcast = tokio::time::interval_at(
+            tokio::time::Instant::now() + REBROADCAST_INTERVAL,
+            REBROADCAST_INTERVAL,
+        );
+        let tx = loop {
+            tokio::select! {
+                tx = &mut pending.rx => break tx?,
+                _ = rebroadcast.tick() => {
+                    if broxus_util::now() > expire_at {
+                        continue;
+                    }
+                    match node_tcp_rpc.send_message(&data).await {
+                        Ok(()) => {
+                            tracing::debug!(dst = %raw_dst, ?msg_hash, "external message rebroadcasted");
+                        }
+                        Err(e) => {
+                            tracing::warn!(
+                                dst = %raw_dst,
+                                ?msg_hash,
+                                "failed to rebroadcast external message: {e:?}"
+                            );
+                        }
                     }
                 }
-                dashmap::mapref::entry::Entry::Vacant(_) => {
-                    tracing::warn!("pending messages entry not found");
-                }
-            };
-            return Err(e);
+            }
+        };
+
+        self.make_transaction_outcome(&raw_dst, msg_hash, tx).await
+    }
+
+    /// Waits for the transaction with the specified incoming message
+    /// (which is broadcasted elsewhere) until the deadline.
+    ///
+    /// NOTE: should be called before the message is broadcasted.
+    #[tracing::instrument(skip_all, fields(%dst, deadline = deadline))]
+    pub async fn wait_transaction(
+        &self,
+        dst: &ton_block::MsgAddressInt,
+        msg_hash: ton_types::UInt256,
+        deadline: u32,
+    ) -> Result<TransactionOutcome> {
+        if self.is_remote() {
+            let tx = self.poll_transaction(&msg_hash, deadline).await?;
+            return self.make_transaction_outcome(dst, msg_hash, tx).await;
         }
-        tracing::debug!(dst = %raw_dst, ?msg_hash, "external message broadcasted");
 
-        // Wait for the message execution
-        let tx = rx.await?;
-        match &tx {
-            Some(tx) => {
-                tracing::debug!(
-                    dst = %raw_dst,
-                    ?msg_hash,
-                    tx_hash = ?tx.hash,
-                    "external message delivered"
-                );
+        let pending = self.add_pending_message(dst, msg_hash, deadline)?;
+        pending.subscription_loop_works.await;
+        let tx = pending.rx.await?;
+        self.make_transaction_outcome(dst, msg_hash, tx).await
+    }
+
+    /// Polls the remote endpoint for the transaction with the specified
+    /// incoming message until the deadline.
+    ///
+    /// NOTE: only available without the local node, use [`Subscription::subscribe`] otherwise.
+    pub async fn poll_transaction(
+        &self,
+        msg_hash: &ton_types::UInt256,
+        deadline: u32,
+    ) -> Result<Option<TransactionWithHash>> {
+        let Transport::Remote(client) = &self.transport else {
+            anyhow::bail!("transactions polling is only available for the remote endpoint");
+        };
+
+        let mut interval = tokio::time::interval(POLL_INTERVAL);
+        loop {
+            interval.tick().await;
+            match client.get_dst_transaction(msg_hash).await {
+                Ok(Some(tx)) => return Ok(Some(tx)),
+                Ok(None) => {}
+                Err(e) => tracing::warn!(?msg_hash, "failed to get transaction: {e:?}"),
             }
-            None => {
-                tracing::warn!(
-                    dst = %raw_dst,
-                    ?msg_hash,
-                    "external message expired"
-                );
+            if broxus_util::now() > deadline {
+                return Ok(None);
             }
         }
+    }
+
+    fn add_pending_message(
+        &self,
+        raw_dst: &ton_block::MsgAddressInt,
+        msg_hash: ton_types::UInt256,
+        expire_at: u32,
+    ) -> Result<PendingMessageRx<'_>> {
+        let (workchain, dst) = split_address(raw_dst)?;
+
+        // Find pending messages map
+        let subscriptions = match workchain {
+            ton_block::MASTERCHAIN_ID => &self.mc_subscriptions,
+            ton_block::BASE_WORKCHAIN_ID => &self.sc_subscriptions,
+            _ => anyhow::bail!("unsupported workchain"),
+        };
 
-        Ok(tx)
+        let mut subscription = subscriptions.entry(dst).or_default();
+
+        let rx = match subscription.pending_messages.entry(msg_hash) {
+            hash_map::Entry::Vacant(entry) => {
+                let (tx, rx) = oneshot::channel();
+                entry.insert(PendingMessage {
+                    expire_at,
+                    tx: Some(tx),
+                });
+                rx
+            }
+            hash_map::Entry::Occupied(_) => anyhow::bail!("message already sent"),
+        };
+
+        // Start waiting for the subscription loop to start
+        let subscription_loop_works = self.subscription_loop_step.notified();
+
+        // Notify waiters while pending messages is still acquired
+        self.subscription_count.fetch_add(1, Ordering::Release);
+        self.subscriptions_changed.notify_waiters();
+
+        // Drop the lock
+        Ok(PendingMessageRx {
+            subscriptions,
+            dst,
+            msg_hash,
+            subscription_loop_works: Box::pin(subscription_loop_works),
+            rx,
+        })
+    }
+
+    fn remove_pending_message(&self, pending: &PendingMessageRx<'_>) {
+        match pending.subscriptions.entry(pending.dst) {
+            dashmap::mapref::entry::Entry::Occupied(mut entry) => {
+                let should_remove = {
+                    let subscription = entry.get_mut();
+                    subscription.pending_messages.remove(&pending.msg_hash);
+                    self.subscription_count.fetch_sub(1, Ordering::Release);
+                    self.subscriptions_changed.notify_waiters();
+                    subscription.is_empty()
+                };
+
+                if should_remove {
+                    entry.remove();
+                }
+            }
+            dashmap::mapref::entry::Entry::Vacant(_) => {
+                tracing::warn!("pending messages entry not found");
+            }
+        };
+    }
+
+    async fn make_transaction_outcome(
+        &self,
+        dst: &ton_block::MsgAddressInt,
+        msg_hash: ton_types::UInt256,
+        tx: Option<TransactionWithHash>,
+    ) -> Result<TransactionOutcome> {
+        let Some(tx) = tx else {
+            // Check whether the message expired due to the frozen account
+            let state = self.get_account_state(dst).await?;
+            if let Some(state) = state {
+                if let ton_block::AccountState::AccountFrozen { .. } = state.storage.state {
+                    tracing::warn!(%dst, ?msg_hash, "destination account is frozen");
+                    return Ok(TransactionOutcome::AccountFrozen);
+                }
+            }
+
+            tracing::warn!(%dst, ?msg_hash, "external message expired");
+            return Ok(TransactionOutcome::Expired);
+        };
+
+        tracing::debug!(%dst, ?msg_hash, tx_hash = ?tx.hash, "external message delivered");
+        TransactionOutcome::from_transaction(tx)
     }
 
     pub fn subscribe(&self, address: &ton_block::MsgAddressInt) -> TransactionsRx {
@@ -255,46 +428,138 @@ impl Subscription {
         rx
     }
 
+    /// Subscribes to the account state changes.
+    ///
+    /// The state is requested after each block with account transactions,
+    /// so intermediate states within one block are not reported.
+    pub fn subscribe_account(
+        self: &Arc<Self>,
+        address: &ton_block::MsgAddressInt,
+    ) -> AccountStatesRx {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let mut transactions = self.subscribe(address);
+
+        let address = address.clone();
+        let subscription = Arc::downgrade(self);
+        tokio::spawn(async move {
+            while let Some(mut last_transaction) = transactions.recv().await {
+                // Skip all transactions except the last one
+                while let Ok(transaction) = transactions.try_recv() {
+                    last_transaction = transaction;
+                }
+
+                let Some(subscription) = subscription.upgrade() else {
+                    break;
+                };
+
+                let state = match subscription.get_account_state(&address).await {
+                    Ok(state) => state,
+                    Err(e) => {
+                        tracing::error!(%address, "failed to get account state: {e:?}");
+                        continue;
+                    }
+                };
+                drop(subscription);
+
+                let update = AccountStateUpdate {
+                    state,
+                    last_transaction,
+                };
+                if tx.send(update).is_err() {
+                    break;
+                }
+            }
+        });
+
+        rx
+    }
+
+    /// Returns the latest known blockchain config.
+    ///
+    /// Config is fetched only once and then updated on key blocks
+    /// while the blocks loop is running.
+    pub async fn get_blockchain_config(&self) -> Result<Arc<ConfigWithId>> {
+        if let Some(config) = &*self.blockchain_config.load() {
+            return Ok(config.clone());
+        }
+        self.refresh_blockchain_config().await
+    }
+
+    /// Returns system contract addresses (with overrides from the network params)
+    pub async fn get_system_addresses(&self) -> Result<SystemAddresses> {
+        let config = self.get_blockchain_config().await?;
+        match self.network_params.get() {
+            Some(params) => params.system_addresses(&config.config),
+            None => AppConfigNetwork::default().system_addresses(&config.config),
+        }
+    }
+
+    /// Subscribes to the changes of the important config params
+    /// (see [`TRACKED_CONFIG_PARAMS`]).
+    pub fn subscribe_config_changes(&self) -> broadcast::Receiver<ConfigChangedEvent> {
+        self.config_events_tx.subscribe()
+    }
+
     pub async fn get_signature_id(&self) -> Result<Option<i32>> {
-        let ConfigWithId { block_id, config } = self
-            .node_tcp_rpc
-            .get_config_all()
-            .await
-            .context("failed to get blockchain config")?;
-        if !requires_signature_id(config.capabilities()) {
+        let config = self.get_blockchain_config().await?;
+        if !requires_signature_id(config.config.capabilities()) {
             return Ok(None);
         }
+        self.get_global_id().await.map(Some)
+    }
 
-        let global_id = {
-            let mut global_id = self.global_id.lock().await;
-            match *global_id {
-                // Once received, it will never change
-                Some(global_id) => global_id,
-                // Try to get the known masterchain block
-                None => {
-                    // TODO: replace with `global_id` from `getstats` when it will be available.
-                    const RETRIES: usize = 10;
-                    const INTERVAL: Duration = Duration::from_secs(1);
-
-                    let mut retries = 0;
-                    let block = loop {
-                        match self.node_udp_rpc.get_block(&block_id).await {
-                            Ok(block) => break block,
-                            Err(e) if retries < RETRIES => {
-                                tracing::error!("failed to get the latest mc block: {e:?}");
-                                tokio::time::sleep(INTERVAL).await;
-                                retries += 1;
-                            }
-                            Err(e) => return Err(e),
+    pub async fn get_global_id(&self) -> Result<i32> {
+        let mut global_id = self.global_id.lock().await;
+        match *global_id {
+            // Once received, it will never change
+            Some(global_id) => Ok(global_id),
+            // Try to get the known masterchain block
+            None => {
+                // Remote endpoint returns it with the config
+                if let Transport::Remote(client) = &self.transport {
+                    let (config, id) = client.get_blockchain_config_with_id().await?;
+                    self.update_blockchain_config(Arc::new(config));
+                    return Ok(*global_id.insert(id));
+                }
+
+                // TODO: replace with `global_id` from `getstats` when it will be available.
+                const RETRIES: usize = 10;
+                const INTERVAL: Duration = Duration::from_secs(1);
+
+                let config = self.get_blockchain_config().await?;
+
+                let mut retries = 0;
+                let block = loop {
+                    match self.udp_rpc()?.get_block(&config.block_id).await {
+                        Ok(block) => break block,
+                        Err(e) if retries < RETRIES => {
+                            tracing::error!("failed to get the latest mc block: {e:?}");
+                            tokio::time::sleep(INTERVAL).await;
+                            retries += 1;
                         }
-                    };
+                        Err(e) => return Err(e),
+                    }
+                };
 
-                    *global_id.insert(block.block().global_id)
-                }
+                Ok(*global_id.insert(block.block().global_id))
             }
-        };
+        }
+    }
+
+    /// Executes an external or internal message locally on the latest
+    /// state of the destination account without broadcasting it.
+    pub async fn emulate_message(
+        &self,
+        message: &ton_block::Message,
+    ) -> Result<TransactionOutcome> {
+        let dst = message.dst_ref().context("message without destination")?;
+        let account = self.get_account_state(dst).await?;
+
+        let config = self.get_blockchain_config().await?;
+        let global_id = self.get_global_id().await?;
 
-        Ok(Some(global_id))
+        let tx = Emulator::new(&config.config, global_id)?.execute(message, account)?;
+        TransactionOutcome::from_transaction(tx)
     }
 
     async fn make_blocks_step(&self) -> Result<()> {
@@ -308,17 +573,38 @@ impl Subscription {
 
         // Get next masterchain block
         let next_mc_block = self
-            .node_udp_rpc
+            .udp_rpc()?
             .get_next_block(last_mc_block.data.id())
             .await
             .context("failed to get next block")?;
+
+        self.subscription_loop_step.notify_waiters(); // messages barrier
+
+        self.process_next_mc_block(last_mc_block, next_mc_block)
+            .await
+            .map(|_| ())
+    }
+
+    /// Processes the next masterchain block with all new shard blocks
+    async fn process_next_mc_block(
+        &self,
+        last_mc_block: Arc<StoredMcBlock>,
+        next_mc_block: BlockStuff,
+    ) -> Result<Arc<StoredMcBlock>> {
         let next_shard_block_ids = next_mc_block.shard_blocks()?;
-        let next_mc_utime = {
+        let (next_mc_utime, is_key_block) = {
             let info = next_mc_block.block().read_info()?;
-            info.gen_utime().as_u32()
+            (info.gen_utime().as_u32(), info.key_block())
         };
 
-        self.subscription_loop_step.notify_waiters(); // messages barrier
+        let key_block_config = if is_key_block {
+            let config = self
+                .process_key_block(&next_mc_block)
+                .context("failed to process key block")?;
+            Arc::new(encode_config(&config)?)
+        } else {
+            last_mc_block.key_block_config.clone()
+        };
 
         tracing::debug!("next shard blocks: {next_shard_block_ids:#?}");
 
@@ -326,7 +612,7 @@ impl Subscription {
         let mut tasks = Vec::with_capacity(next_shard_block_ids.len());
         for id in next_shard_block_ids.values().cloned() {
             let last_mc_block = last_mc_block.clone();
-            let rpc = self.node_udp_rpc.clone();
+            let rpc = self.udp_rpc()?.clone();
             tasks.push(tokio::spawn(async move {
                 let edge = &last_mc_block.shards_edge;
                 let mut blocks = Vec::new();
@@ -375,13 +661,16 @@ impl Subscription {
                 .collect(),
         );
 
-        self.last_mc_block.store(Some(Arc::new(StoredMcBlock {
+        let next_mc_block = Arc::new(StoredMcBlock {
             data: next_mc_block,
             shards_edge,
-        })));
+            key_block_config,
+        });
+        self.last_mc_block.store(Some(next_mc_block.clone()));
+        self.store_state(&next_mc_block);
 
         // Done
-        Ok(())
+        Ok(next_mc_block)
     }
 
     async fn get_last_mc_block(&self) -> Result<Arc<StoredMcBlock>> {
@@ -393,17 +682,234 @@ impl Subscription {
     }
 
     async fn update_last_mc_block(&self) -> Result<Arc<StoredMcBlock>> {
-        let stats = self.node_tcp_rpc.get_stats().await?;
+        let stats = self.tcp_rpc()?.get_stats().await?;
         let last_mc_block = stats.try_into_running()?.last_mc_block;
-        let data = self.node_udp_rpc.get_block(&last_mc_block).await?;
+
+        // Try to continue from the last processed block.
+        // NOTE: blocks which were processed before the error are kept,
+        // so the next step continues from them instead of skipping the gap.
+        if let Some(block) = self
+            .catch_up(&last_mc_block)
+            .await
+            .context("failed to catch up")?
+        {
+            return Ok(block);
+        }
+
+        // Use the validator set from the control server to verify next blocks
+        let config = self.refresh_blockchain_config().await?;
+        self.udp_rpc()?.set_trusted_config(&config.config)?;
+
+        let data = self.udp_rpc()?.get_block(&last_mc_block).await?;
 
         let shards_edge = Edge(data.shard_blocks_seq_no()?);
 
-        let block = Arc::new(StoredMcBlock { data, shards_edge });
+        let block = Arc::new(StoredMcBlock {
+            data,
+            shards_edge,
+            key_block_config: Arc::new(encode_config(&config.config)?),
+        });
         self.last_mc_block.store(Some(block.clone()));
+        self.store_state(&block);
         Ok(block)
     }
 
+    /// Processes all masterchain blocks since the last processed one
+    /// up to the specified block.
+    #[tracing::instrument(skip_all, fields(target = target.seq_no))]
+    async fn catch_up(&self, target: &ton_block::BlockIdExt) -> Result<Option<Arc<StoredMcBlock>>> {
+        const MAX_CATCH_UP_BLOCKS: u32 = 10000;
+        const CATCH_UP_CONCURRENCY: usize = 8;
+
+        // Find the last processed block.
+        // NOTE: the verifier already follows the in-memory block
+        let mut last = match &*self.last_mc_block.load() {
+            Some(last) => last.clone(),
+            None => match self.load_state() {
+                Some(state) => match self.restore_state(state).await {
+                    Ok(last) => last,
+                    Err(e) => {
+                        tracing::warn!("failed to restore the last processed block: {e:?}");
+                        return Ok(None);
+                    }
+                },
+                None => return Ok(None),
+            },
+        };
+
+        let last_seqno = last.data.id().seq_no;
+        if last_seqno >= target.seq_no {
+            return Ok(None);
+        }
+
+        let count = target.seq_no - last_seqno;
+        if count > MAX_CATCH_UP_BLOCKS {
+            tracing::warn!(
+                count,
+                "too many blocks to catch up, starting from the latest block"
+            );
+            return Ok(None);
+        }
+        tracing::info!(count, from = last_seqno, "catching up masterchain blocks");
+
+        let mut blocks =
+            self.udp_rpc()?
+                .get_blocks(last.data.id().clone(), count, CATCH_UP_CONCURRENCY);
+        while let Some(block) = blocks.recv().await {
+            last = self.process_next_mc_block(last, block?).await?;
+        }
+
+        tracing::info!(seqno = last.data.id().seq_no, "caught up");
+        Ok(Some(last))
+    }
+
+    /// Loads the last processed block and prepares the verifier for the next blocks
+    async fn restore_state(&self, state: SubscriptionState) -> Result<Arc<StoredMcBlock>> {
+        let data = self.udp_rpc()?.get_block(&state.last_mc_block).await?;
+        let shards_edge = Edge(data.shard_blocks_seq_no()?);
+
+        // Next blocks are signed by the validator set from the last key block config,
+        // which can differ from the current one if the gap spans a validator set change
+        let key_block_config = match state.key_block_config {
+            Some(config) => config,
+            None => {
+                tracing::warn!("key block config not found in the state, using the latest config");
+                let config = self.refresh_blockchain_config().await?;
+                encode_config(&config.config)?
+            }
+        };
+        let config = ton_block::ConfigParams::construct_from_base64(&key_block_config)
+            .map_err(|_| anyhow::anyhow!("invalid key block config"))?;
+        self.udp_rpc()?.set_trusted_config(&config)?;
+
+        Ok(Arc::new(StoredMcBlock {
+            data,
+            shards_edge,
+            key_block_config: Arc::new(key_block_config),
+        }))
+    }
+
+    /// Enables persistence of the last processed masterchain block,
+    /// so that the blocks loop can continue from it after restart.
+    pub fn set_state_file(&self, path: PathBuf) {
+        self.state_file.set(path).ok();
+    }
+
+    /// Sets overrides for the network specific parameters (e.g. system contract addresses)
+    pub fn set_network_params(&self, params: AppConfigNetwork) {
+        self.network_params.set(params).ok();
+    }
+
+    /// Sets the policy which is checked by all wallets using this subscription
+    pub fn set_spending_policy(&self, policy: SpendingPolicy) {
+        self.spending_policy.set(policy).ok();
+    }
+
+    pub fn spending_policy(&self) -> Option<&SpendingPolicy> {
+        self.spending_policy.get()
+    }
+
+    fn load_state(&self) -> Option<SubscriptionState> {
+        let path = self.state_file.get()?;
+        if !path.exists() {
+            return None;
+        }
+
+        let state = std::fs::read_to_string(path)
+            .map_err(anyhow::Error::from)
+            .and_then(|data| {
+                serde_json::from_str::<SubscriptionState>(&data).map_err(anyhow::Error::from)
+            });
+        match state {
+            Ok(state) => Some(state),
+            Err(e) => {
+                tracing::warn!("failed to load subscription state: {e:?}");
+                None
+            }
+        }
+    }
+
+    fn store_state(&self, last_mc_block: &StoredMcBlock) {
+        let Some(path) = self.state_file.get() else {
+            return;
+        };
+
+        let state = SubscriptionState {
+            last_mc_block: last_mc_block.data.id().clone(),
+            key_block_config: Some(last_mc_block.key_block_config.as_ref().clone()),
+        };
+        let res = serde_json::to_string(&state)
+            .map_err(anyhow::Error::from)
+            .and_then(|data| std::fs::write(path, data).map_err(From::from));
+        if let Err(e) = res {
+            tracing::warn!("failed to store subscription state: {e:?}");
+        }
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn refresh_blockchain_config(&self) -> Result<Arc<ConfigWithId>> {
+        let config = match &self.transport {
+            Transport::Node { tcp, .. } => tcp.get_config_all().await,
+            Transport::Remote(client) => client
+                .get_blockchain_config_with_id()
+                .await
+                .map(|(config, _)| config),
+        }
+        .context("failed to get blockchain config")?;
+        let config = Arc::new(config);
+        self.update_blockchain_config(config.clone());
+        Ok(config)
+    }
+
+    fn update_blockchain_config(&self, new: Arc<ConfigWithId>) {
+        let old = self.blockchain_config.swap(Some(new.clone()));
+
+        let (changed_params, previous) = match old {
+            Some(old) => (
+                TRACKED_CONFIG_PARAMS
+                    .iter()
+                    .copied()
+                    .filter(|&param| {
+                        old.config.config(param).ok().flatten()
+                            != new.config.config(param).ok().flatten()
+                    })
+                    .collect::<Vec<_>>(),
+                old,
+            ),
+            None => return,
+        };
+
+        if !changed_params.is_empty() {
+            tracing::info!(
+                block_id = %new.block_id,
+                ?changed_params,
+                "blockchain config changed"
+            );
+            self.config_events_tx
+                .send(ConfigChangedEvent {
+                    changed_params,
+                    previous,
+                    config: new,
+                })
+                .ok();
+        }
+    }
+
+    fn process_key_block(&self, block: &BlockStuff) -> Result<ton_block::ConfigParams> {
+        let config = block
+            .block()
+            .read_extra()?
+            .read_custom()?
+            .and_then(|custom| custom.config().cloned())
+            .context("key block without config")?;
+
+        self.update_blockchain_config(Arc::new(ConfigWithId {
+            block_id: block.id().clone(),
+            config: config.clone(),
+        }));
+        Ok(config)
+    }
+
     fn process_block(
         &self,
         block: &ton_block::Block,
@@ -505,6 +1011,31 @@ type AccountSubscriptions = FxDashMap<ton_types::UInt256, AccountSubscription>;
 pub type TransactionsTx = mpsc::UnboundedSender<TransactionWithHash>;
 pub type TransactionsRx = mpsc::UnboundedReceiver<TransactionWithHash>;
 
+pub type AccountStatesRx = mpsc::UnboundedReceiver<AccountStateUpdate>;
+
+#[derive(Clone)]
+pub struct AccountStateUpdate {
+    /// Account state after the last transaction (`None` if it was deleted)
+    pub state: Option<ton_block::AccountStuff>,
+    pub last_transaction: TransactionWithHash,
+}
+
+impl AccountStateUpdate {
+    pub fn balance(&self) -> u128 {
+        match &self.state {
+            Some(state) => state.storage.balance.grams.as_u128(),
+            None => 0,
+        }
+    }
+}
+
+enum Transport {
+    /// Local node control server and ADNL liteserver
+    Node { tcp: NodeTcpRpc, udp: NodeUdpRpc },
+    /// Remote JRPC endpoint
+    Remote(JrpcClient),
+}
+
 async fn walk_blocks(subscription: Weak<Subscription>) {
     loop {
         let subscription = match subscription.upgrade() {
@@ -535,9 +1066,45 @@ async fn walk_blocks(subscription: Weak<Subscription>) {
     }
 }
 
+/// Config params which are tracked for changes:
+/// - `8` - global version
+/// - `11` - config proposals voting setup
+/// - `15` - elections timings
+/// - `17` - stakes config
+/// - `32` - previous validator set
+/// - `34` - current validator set
+/// - `36` - next validator set
+pub const TRACKED_CONFIG_PARAMS: [u32; 7] = [8, 11, 15, 17, 32, 34, 36];
+
+const CONFIG_EVENTS_CAPACITY: usize = 16;
+
+#[derive(Clone)]
+pub struct ConfigChangedEvent {
+    pub changed_params: Vec<u32>,
+    pub previous: Arc<ConfigWithId>,
+    pub config: Arc<ConfigWithId>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SubscriptionState {
+    #[serde(with = "serde_block_id")]
+    last_mc_block: ton_block::BlockIdExt,
+    /// Base64 encoded config of the last key block before `last_mc_block`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    key_block_config: Option<String>,
+}
+
 struct StoredMcBlock {
     data: BlockStuff,
     shards_edge: Edge,
+    /// Base64 encoded config of the last key block (to restore the verifier after restart)
+    key_block_config: Arc<String>,
+}
+
+fn encode_config(config: &ton_block::ConfigParams) -> Result<String> {
+    Ok(base64::encode(ton_types::serialize_toc(
+        &config.serialize()?,
+    )?))
 }
 
 struct Edge(FxHashMap<ton_block::ShardIdent, u32>);
@@ -569,6 +1136,143 @@ impl Drop for PendingMessage {
     }
 }
 
+const REBROADCAST_INTERVAL: Duration = Duration::from_secs(10);
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+struct PendingMessageRx<'a> {
+    subscriptions: &'a AccountSubscriptions,
+    dst: ton_types::UInt256,
+    msg_hash: ton_types::UInt256,
+    subscription_loop_works: std::pin::Pin<Box<tokio::sync::futures::Notified<'a>>>,
+    rx: oneshot::Receiver<Option<TransactionWithHash>>,
+}
+
+/// The result of the incoming message processing
+pub enum TransactionOutcome {
+    /// Message was successfully processed
+    Accepted(TransactionWithHash),
+    /// Message was not included into any block before it expired
+    Expired,
+    /// Message was not processed because the destination account is frozen
+    AccountFrozen,
+    /// Compute phase failed due to the insufficient gas
+    OutOfGas {
+        tx: TransactionWithHash,
+        exit_code: i32,
+    },
+    /// Compute phase failed with the specified exit code
+    ComputeFailed {
+        tx: TransactionWithHash,
+        exit_code: i32,
+    },
+    /// Action phase failed with the specified result code
+    ActionFailed {
+        tx: TransactionWithHash,
+        result_code: i32,
+    },
+}
+
+impl TransactionOutcome {
+    pub fn from_transaction(tx: TransactionWithHash) -> Result<Self> {
+        const OUT_OF_GAS_EXIT_CODES: [i32; 2] = [13, -14];
+
+        let descr = match tx.data.read_description()? {
+            ton_block::TransactionDescr::Ordinary(descr) => descr,
+            _ => anyhow::bail!("unexpected transaction type"),
+        };
+
+        match descr.compute_ph {
+            ton_block::TrComputePhase::Vm(phase) if !phase.success => {
+                let exit_code = phase.exit_code;
+                return Ok(if OUT_OF_GAS_EXIT_CODES.contains(&exit_code) {
+                    Self::OutOfGas { tx, exit_code }
+                } else {
+                    Self::ComputeFailed { tx, exit_code }
+                });
+            }
+            ton_block::TrComputePhase::Skipped(phase) => {
+                anyhow::bail!("compute phase skipped: {:?}", phase.reason);
+            }
+            _ => {}
+        }
+
+        if let Some(action) = &descr.action {
+            if !action.success {
+                let result_code = action.result_code;
+                return Ok(Self::ActionFailed { tx, result_code });
+            }
+        }
+
+        Ok(Self::Accepted(tx))
+    }
+
+    /// Returns the transaction if it was executed
+    pub fn transaction(&self) -> Option<&TransactionWithHash> {
+        match self {
+            Self::Accepted(tx)
+            | Self::OutOfGas { tx, .. }
+            | Self::ComputeFailed { tx, .. }
+            | Self::ActionFailed { tx, .. } => Some(tx),
+            Self::Expired | Self::AccountFrozen => None,
+        }
+    }
+
+    /// Returns a short description of the failure
+    pub fn error(&self) -> Option<String> {
+        match self {
+            Self::Accepted(_) => None,
+            Self::Expired => Some("message expired".to_owned()),
+            Self::AccountFrozen => Some("account is frozen".to_owned()),
+            Self::OutOfGas { exit_code, .. } => Some(format!("out of gas (exit code {exit_code})")),
+            Self::ComputeFailed { exit_code, .. } => {
+                Some(format!("compute phase failed with exit code {exit_code}"))
+            }
+            Self::ActionFailed { result_code, .. } => Some(format!(
+                "action phase failed with result code {result_code}"
+            )),
+        }
+    }
+
+    pub fn into_result(self) -> Result<TransactionWithHash, SendMessageError> {
+        let tx_hash = |tx: &TransactionWithHash| hex::encode(tx.hash.as_slice());
+        match self {
+            Self::Accepted(tx) => Ok(tx),
+            Self::Expired => Err(SendMessageError::Expired),
+            Self::AccountFrozen => Err(SendMessageError::AccountFrozen),
+            Self::OutOfGas { tx, exit_code } => Err(SendMessageError::OutOfGas {
+                tx_hash: tx_hash(&tx),
+                exit_code,
+            }),
+            Self::ComputeFailed { tx, exit_code } => Err(SendMessageError::ComputePhaseFailed {
+                tx_hash: tx_hash(&tx),
+                exit_code,
+            }),
+            Self::ActionFailed { tx, result_code } => Err(SendMessageError::ActionPhaseFailed {
+                tx_hash: tx_hash(&tx),
+                result_code,
+            }),
+        }
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+#[error("local node is required for this operation")]
+pub struct LocalNodeRequired;
+
+#[derive(thiserror::Error, Debug)]
+pub enum SendMessageError {
+    #[error("message expired")]
+    Expired,
+    #[error("destination account is frozen")]
+    AccountFrozen,
+    #[error("out of gas (exit code {exit_code}) in transaction {tx_hash}")]
+    OutOfGas { tx_hash: String, exit_code: i32 },
+    #[error("compute phase failed with exit code {exit_code} in transaction {tx_hash}")]
+    ComputePhaseFailed { tx_hash: String, exit_code: i32 },
+    #[error("action phase failed with result code {result_code} in transaction {tx_hash}")]
+    ActionPhaseFailed { tx_hash: String, result_code: i32 },
+}
+
 fn requires_signature_id(capabilities: u64) -> bool {
     const CAP_WITH_SIGNATURE_ID: u64 = 0x4000000;
 
@@ -0,0 +1,290 @@
+use std::collections::BTreeMap;
+use std::io::{BufRead, Read, Seek, SeekFrom};
+use std::path::Path;
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use tokio::io::AsyncBufReadExt;
+use tokio_util::sync::{CancellationToken, DropGuard};
+
+use crate::config::AppConfigNodeLogs;
+
+/// Reads the last lines of the node logs and parses them
+pub async fn read_recent(source: &AppConfigNodeLogs, lines: usize) -> Result<NodeLogStats> {
+    let mut stats = NodeLogStats::default();
+    match source {
+        AppConfigNodeLogs::Journald { unit } => {
+            let output = tokio::process::Command::new("journalctl")
+                .args(["-u", unit, "-o", "cat", "--no-pager", "-n"])
+                .arg(lines.to_string())
+                .stdin(Stdio::null())
+                .output()
+                .await
+                .context("failed to run journalctl")?;
+            anyhow::ensure!(
+                output.status.success(),
+                "journalctl exited with {}",
+                output.status
+            );
+            for line in String::from_utf8_lossy(&output.stdout).lines() {
+                stats.feed(line);
+            }
+        }
+        AppConfigNodeLogs::File { path } => {
+            for line in read_last_lines(path, lines)? {
+                stats.feed(&line);
+            }
+        }
+    }
+    Ok(stats)
+}
+
+/// Background task which follows the node logs
+pub struct NodeLogWatcher {
+    stats: Arc<parking_lot::Mutex<NodeLogStats>>,
+    _cancellation_guard: DropGuard,
+}
+
+impl NodeLogWatcher {
+    pub fn spawn(source: AppConfigNodeLogs) -> Self {
+        const RETRY_INTERVAL: Duration = Duration::from_secs(10);
+
+        let stats = Arc::new(parking_lot::Mutex::new(NodeLogStats::default()));
+        let cancellation_token = CancellationToken::new();
+
+        tokio::spawn({
+            let stats = stats.clone();
+            let cancellation_token = cancellation_token.clone();
+            async move {
+                let follow = async {
+                    loop {
+                        let res = match &source {
+                            AppConfigNodeLogs::Journald { unit } => {
+                                follow_journald(unit, &stats).await
+                            }
+                            AppConfigNodeLogs::File { path } => follow_file(path, &stats).await,
+                        };
+                        if let Err(e) = res {
+                            tracing::warn!("failed to read node logs: {e:?}");
+                        }
+                        tokio::time::sleep(RETRY_INTERVAL).await;
+                    }
+                };
+                tokio::select! {
+                    _ = follow => {},
+                    _ = cancellation_token.cancelled() => {},
+                }
+            }
+        });
+
+        tracing::info!("started node logs watcher");
+
+        Self {
+            stats,
+            _cancellation_guard: cancellation_token.drop_guard(),
+        }
+    }
+
+    pub fn stats(&self) -> NodeLogStats {
+        self.stats.lock().clone()
+    }
+}
+
+async fn follow_journald(unit: &str, stats: &parking_lot::Mutex<NodeLogStats>) -> Result<()> {
+    let mut child = tokio::process::Command::new("journalctl")
+        .args(["-u", unit, "-o", "cat", "--no-pager", "-f", "-n", "0"])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .context("failed to run journalctl")?;
+
+    let stdout = child
+        .stdout
+        .take()
+        .context("journalctl stdout not captured")?;
+    let mut lines = tokio::io::BufReader::new(stdout).lines();
+    while let Some(line) = lines.next_line().await? {
+        stats.lock().feed(&line);
+    }
+
+    let status = child.wait().await?;
+    anyhow::bail!("journalctl exited with {status}")
+}
+
+async fn follow_file(path: &Path, stats: &parking_lot::Mutex<NodeLogStats>) -> Result<()> {
+    const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+    let mut file = std::fs::File::open(path).context("failed to open node log file")?;
+    let mut offset = file.seek(SeekFrom::End(0))?;
+    let mut incomplete = String::new();
+    loop {
+        tokio::time::sleep(POLL_INTERVAL).await;
+
+        // Reopen the file after rotation
+        let len = std::fs::metadata(path)?.len();
+        if len < offset {
+            file = std::fs::File::open(path).context("failed to reopen node log file")?;
+            offset = 0;
+            incomplete.clear();
+        }
+
+        let mut data = Vec::new();
+        file.seek(SeekFrom::Start(offset))?;
+        offset += file.read_to_end(&mut data)? as u64;
+
+        incomplete.push_str(&String::from_utf8_lossy(&data));
+        let Some(last_newline) = incomplete.rfind('\n') else {
+            continue;
+        };
+
+        let mut stats = stats.lock();
+        for line in incomplete[..last_newline].lines() {
+            stats.feed(line);
+        }
+        incomplete.drain(..=last_newline);
+    }
+}
+
+fn read_last_lines(path: &Path, lines: usize) -> Result<Vec<String>> {
+    // NOTE: approximate max size of the requested lines
+    const MAX_LINE_LEN: u64 = 1024;
+
+    let mut file = std::fs::File::open(path).context("failed to open node log file")?;
+    let len = file.metadata()?.len();
+    let offset = len.saturating_sub(lines as u64 * MAX_LINE_LEN);
+    file.seek(SeekFrom::Start(offset))?;
+
+    let mut result = std::io::BufReader::new(file)
+        .lines()
+        // Skip the partial first line
+        .skip((offset > 0) as usize)
+        .collect::<std::io::Result<Vec<_>>>()?;
+
+    let skip = result.len().saturating_sub(lines);
+    result.drain(..skip);
+    Ok(result)
+}
+
+/// Info extracted from the node logs
+#[derive(Default, Debug, Clone, Serialize)]
+pub struct NodeLogStats {
+    /// Number of parsed lines
+    pub lines: u64,
+    /// Last reported sync progress (in percents)
+    pub sync_progress: Option<f64>,
+    /// Seqno of the last applied masterchain block
+    pub last_applied_mc_seqno: Option<u32>,
+    /// Seqno of the last applied shard block
+    pub last_applied_sc_seqno: Option<u32>,
+    /// Number of errors and warnings by kind
+    pub errors: BTreeMap<&'static str, u64>,
+    /// Last error message
+    pub last_error: Option<String>,
+}
+
+impl NodeLogStats {
+    pub fn feed(&mut self, line: &str) {
+        let Some(line) = LogLine::parse(line) else {
+            return;
+        };
+        self.lines += 1;
+
+        let message = line.message.to_lowercase();
+
+        if message.contains("applied") && message.contains("block") {
+            if let Some((workchain, seqno)) = parse_block_id(line.message) {
+                if workchain == -1 {
+                    self.last_applied_mc_seqno = Some(seqno);
+                } else {
+                    self.last_applied_sc_seqno = Some(seqno);
+                }
+            }
+        }
+
+        let is_sync = ["sync", "boot"]
+            .iter()
+            .any(|target| line.target.contains(target) || message.contains(target));
+        if is_sync {
+            if let Some(progress) = parse_percent(line.message) {
+                self.sync_progress = Some(progress);
+            }
+        }
+
+        if matches!(line.level, "ERROR" | "WARN") {
+            *self.errors.entry(classify_error(&message)).or_default() += 1;
+            if line.level == "ERROR" {
+                self.last_error = Some(line.message.to_owned());
+            }
+        }
+    }
+}
+
+/// Line in the node log format (`{l} [{t}] {I}: {m}`)
+struct LogLine<'a> {
+    level: &'a str,
+    target: &'a str,
+    message: &'a str,
+}
+
+impl<'a> LogLine<'a> {
+    fn parse(line: &'a str) -> Option<Self> {
+        let (level, rest) = line.trim().split_once(' ')?;
+        let rest = rest.trim_start().strip_prefix('[')?;
+        let (target, rest) = rest.split_once(']')?;
+        let (_thread, message) = rest.split_once(": ")?;
+        Some(Self {
+            level,
+            target,
+            message,
+        })
+    }
+}
+
+/// Parses the first block id like `(-1:8000000000000000, 123, ...)`
+fn parse_block_id(message: &str) -> Option<(i32, u32)> {
+    message.match_indices('(').find_map(|(i, _)| {
+        let mut parts = message[i + 1..].split(", ");
+        let (workchain, shard) = parts.next()?.split_once(':')?;
+        u64::from_str_radix(shard, 16).ok()?;
+        let seqno = parts.next()?.trim_end_matches(')').parse().ok()?;
+        Some((workchain.parse().ok()?, seqno))
+    })
+}
+
+fn parse_percent(message: &str) -> Option<f64> {
+    message.split_whitespace().find_map(|word| {
+        let value = word
+            .trim_matches(|c: char| c != '%' && c != '.' && !c.is_ascii_digit())
+            .strip_suffix('%')?
+            .parse::<f64>()
+            .ok()?;
+        (0.0..=100.0).contains(&value).then_some(value)
+    })
+}
+
+fn classify_error(message: &str) -> &'static str {
+    const SIGNATURES: &[(&str, &str)] = &[
+        ("no space left", "disk_full"),
+        ("too many open files", "too_many_open_files"),
+        ("out of memory", "out_of_memory"),
+        ("memory allocation", "out_of_memory"),
+        ("rocksdb", "database"),
+        ("database", "database"),
+        ("catchain", "catchain"),
+        ("timeout", "timeout"),
+        ("timed out", "timeout"),
+        ("download", "download"),
+        ("overlay", "network"),
+        ("adnl", "network"),
+    ];
+
+    SIGNATURES
+        .iter()
+        .find(|(signature, _)| message.contains(signature))
+        .map(|(_, kind)| *kind)
+        .unwrap_or("other")
+}
@@ -0,0 +1,197 @@
+use std::collections::HashSet;
+use std::net::{Ipv4Addr, SocketAddrV4};
+use std::path::Path;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use serde::ser::SerializeMap;
+use serde::{Serialize, Serializer};
+
+use crate::config::NodeConfig;
+
+/// Prefix of the node metrics in the exporter output
+const METRICS_PREFIX: &str = "node_";
+
+/// Scrapes the metrics endpoint of the node if it is configured in the node config
+pub async fn scrape_configured<P: AsRef<Path>>(node_config: P) -> Result<Option<NodeMetrics>> {
+    let Some(metrics) = NodeConfig::load(node_config)?.get_metrics()? else {
+        return Ok(None);
+    };
+
+    let mut address = metrics.address;
+    if address.ip().is_unspecified() {
+        address.set_ip(Ipv4Addr::LOCALHOST);
+    }
+    scrape(address).await.map(Some)
+}
+
+/// Scrapes the node metrics in Prometheus text format
+pub async fn scrape(address: SocketAddrV4) -> Result<NodeMetrics> {
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    let text = reqwest::Client::builder()
+        .timeout(TIMEOUT)
+        .build()?
+        .get(format!("http://{address}/metrics"))
+        .send()
+        .await
+        .context("failed to scrape node metrics")?
+        .error_for_status()?
+        .text()
+        .await?;
+
+    Ok(NodeMetrics::parse(&text))
+}
+
+/// Gauges and counters exposed by the node
+#[derive(Default, Clone)]
+pub struct NodeMetrics {
+    pub samples: Vec<NodeMetricSample>,
+}
+
+#[derive(Clone)]
+pub struct NodeMetricSample {
+    pub name: String,
+    pub labels: Vec<(String, String)>,
+    pub value: f64,
+}
+
+impl NodeMetrics {
+    /// Parses Prometheus text format, skipping histograms and summaries
+    pub fn parse(text: &str) -> Self {
+        let mut skipped = HashSet::new();
+        let mut samples = Vec::new();
+
+        for line in text.lines().map(str::trim) {
+            if let Some(comment) = line.strip_prefix('#') {
+                let mut parts = comment.split_whitespace();
+                if let (Some("TYPE"), Some(name), Some("histogram" | "summary")) =
+                    (parts.next(), parts.next(), parts.next())
+                {
+                    skipped.insert(name.to_owned());
+                }
+                continue;
+            }
+
+            let Some(sample) = parse_sample(line) else {
+                continue;
+            };
+
+            let family = ["_bucket", "_sum", "_count"]
+                .iter()
+                .find_map(|suffix| sample.name.strip_suffix(suffix))
+                .unwrap_or(&sample.name);
+            if skipped.contains(family) || skipped.contains(&sample.name) {
+                continue;
+            }
+
+            samples.push(sample);
+        }
+
+        Self { samples }
+    }
+
+    /// Writes samples in Prometheus text format with the `node_` prefix
+    pub fn write_prometheus(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for sample in &self.samples {
+            f.write_str(METRICS_PREFIX)?;
+            f.write_str(&sample.name)?;
+            write_labels(f, &sample.labels)?;
+            writeln!(f, " {}", sample.value)?;
+        }
+        Ok(())
+    }
+}
+
+impl Serialize for NodeMetrics {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        struct Series<'a>(&'a NodeMetricSample);
+
+        impl std::fmt::Display for Series<'_> {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str(&self.0.name)?;
+                write_labels(f, &self.0.labels)
+            }
+        }
+
+        let mut map = serializer.serialize_map(Some(self.samples.len()))?;
+        for sample in &self.samples {
+            map.serialize_entry(&Series(sample).to_string(), &sample.value)?;
+        }
+        map.end()
+    }
+}
+
+fn write_labels(f: &mut std::fmt::Formatter<'_>, labels: &[(String, String)]) -> std::fmt::Result {
+    if labels.is_empty() {
+        return Ok(());
+    }
+
+    f.write_str("{")?;
+    for (i, (name, value)) in labels.iter().enumerate() {
+        if i > 0 {
+            f.write_str(",")?;
+        }
+        let value = value
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\n', "\\n");
+        write!(f, "{name}=\"{value}\"")?;
+    }
+    f.write_str("}")
+}
+
+fn parse_sample(line: &str) -> Option<NodeMetricSample> {
+    if line.is_empty() {
+        return None;
+    }
+
+    let name_end = line
+        .find(|c: char| c == '{' || c.is_whitespace())
+        .unwrap_or(line.len());
+    let (name, mut rest) = line.split_at(name_end);
+    if name.is_empty() {
+        return None;
+    }
+
+    let mut labels = Vec::new();
+    if let Some(label_list) = rest.strip_prefix('{') {
+        let mut chars = label_list.char_indices();
+        loop {
+            // Label name
+            let (start, _) = chars.by_ref().find(|(_, c)| !matches!(c, ',' | ' '))?;
+            if label_list[start..].starts_with('}') {
+                rest = &label_list[start + 1..];
+                break;
+            }
+            let (eq, _) = chars.by_ref().find(|(_, c)| *c == '=')?;
+            let label_name = label_list[start..eq].trim().to_owned();
+
+            // Quoted label value
+            chars.next().filter(|(_, c)| *c == '"')?;
+            let mut value = String::new();
+            loop {
+                match chars.next()?.1 {
+                    '"' => break,
+                    '\\' => match chars.next()?.1 {
+                        'n' => value.push('\n'),
+                        c => value.push(c),
+                    },
+                    c => value.push(c),
+                }
+            }
+            labels.push((label_name, value));
+        }
+    }
+
+    let value = rest.split_whitespace().next()?.parse::<f64>().ok()?;
+    if !value.is_finite() {
+        return None;
+    }
+
+    Some(NodeMetricSample {
+        name: name.to_owned(),
+        labels,
+        value,
+    })
+}
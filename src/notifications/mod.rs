@@ -0,0 +1,494 @@
+use std::collections::VecDeque;
+use std::process::Stdio;
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use arc_swap::{ArcSwap, ArcSwapOption};
+use broxus_util::{serde_optional_string, serde_string};
+use serde::Serialize;
+use tokio::io::AsyncWriteExt;
+
+use crate::config::{
+    AppConfigHook, AppConfigNotificationChannel, AppConfigNotifications, NotificationSeverity,
+    NotificationTarget,
+};
+use crate::util::Tokens;
+
+/// Sends events to the configured notification channels and hook scripts
+#[derive(Default, Clone)]
+pub struct Notifier {
+    inner: Arc<ArcSwapOption<Inner>>,
+    hooks: Arc<ArcSwap<Vec<AppConfigHook>>>,
+    recent: Arc<parking_lot::Mutex<VecDeque<EventRecord>>>,
+}
+
+/// Event which was sent by the notifier
+#[derive(Debug, Clone)]
+pub struct EventRecord {
+    /// Sequence number since the manager start
+    pub id: u64,
+    /// Unix timestamp when the event was sent
+    pub timestamp: u32,
+    pub event: Event,
+}
+
+struct Inner {
+    config: AppConfigNotifications,
+    client: reqwest::Client,
+    host: String,
+}
+
+impl Notifier {
+    pub fn new(config: Option<AppConfigNotifications>) -> Self {
+        let notifier = Self::default();
+        notifier.update(config);
+        notifier
+    }
+
+    /// Replaces notification channels for this notifier and all its clones
+    pub fn update(&self, config: Option<AppConfigNotifications>) {
+        let inner = config
+            .filter(|config| !config.channels.is_empty())
+            .map(|config| {
+                Arc::new(Inner {
+                    config,
+                    client: reqwest::Client::new(),
+                    host: sysinfo::System::host_name().unwrap_or_default(),
+                })
+            });
+        self.inner.store(inner);
+    }
+
+    /// Replaces hook scripts for this notifier and all its clones
+    pub fn update_hooks(&self, hooks: Vec<AppConfigHook>) {
+        self.hooks.store(Arc::new(hooks));
+    }
+
+    /// Returns the latest sent events (oldest first)
+    pub fn recent_events(&self) -> Vec<EventRecord> {
+        self.recent.lock().iter().cloned().collect()
+    }
+
+    /// Renders the event text as it is sent to the notification channels
+    pub fn render(&self, event: &Event) -> String {
+        match self.inner.load_full() {
+            Some(inner) => inner.render(event),
+            None => render_template(
+                event.default_template(),
+                event,
+                &sysinfo::System::host_name().unwrap_or_default(),
+            ),
+        }
+    }
+
+    /// Sends the event in background
+    pub fn notify(&self, event: Event) {
+        self.remember(&event);
+        self.run_hooks(&event);
+
+        let Some(inner) = self.inner.load_full() else {
+            return;
+        };
+
+        tokio::spawn(async move {
+            let text = inner.render(&event);
+            for channel in &inner.config.channels {
+                if !channel.accepts(&event) {
+                    continue;
+                }
+                if let Err(e) = inner.send(channel, &event, &text).await {
+                    tracing::warn!(event = event.name(), "failed to send notification: {e:?}");
+                }
+            }
+        });
+    }
+
+    fn remember(&self, event: &Event) {
+        const MAX_RECENT_EVENTS: usize = 100;
+
+        let mut recent = self.recent.lock();
+        if recent.len() >= MAX_RECENT_EVENTS {
+            recent.pop_front();
+        }
+        let id = recent.back().map(|record| record.id + 1).unwrap_or(1);
+        recent.push_back(EventRecord {
+            id,
+            timestamp: broxus_util::now(),
+            event: event.clone(),
+        });
+    }
+
+    fn run_hooks(&self, event: &Event) {
+        let hooks = self.hooks.load_full();
+        if !hooks.iter().any(|hook| hook.event == event.name()) {
+            return;
+        }
+
+        let payload = match event.payload() {
+            Ok(payload) => payload,
+            Err(e) => {
+                tracing::warn!(
+                    event = event.name(),
+                    "failed to serialize hook payload: {e:?}"
+                );
+                return;
+            }
+        };
+
+        let event = event.name();
+        tokio::spawn(async move {
+            for hook in hooks.iter().filter(|hook| hook.event == event) {
+                if let Err(e) = run_hook(hook, event, &payload).await {
+                    tracing::warn!(event, command = %hook.command.display(), "hook failed: {e:?}");
+                }
+            }
+        });
+    }
+}
+
+async fn run_hook(hook: &AppConfigHook, event: &str, payload: &[u8]) -> Result<()> {
+    let mut child = tokio::process::Command::new(&hook.command)
+        .args(&hook.args)
+        .env("NODEKEEPER_EVENT", event)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .context("failed to start hook")?;
+
+    let stdin = child.stdin.take();
+    let output = tokio::time::timeout(hook.timeout, async move {
+        if let Some(mut stdin) = stdin {
+            // NOTE: the script is not required to read the payload
+            stdin.write_all(payload).await.ok();
+        }
+        child.wait_with_output().await
+    })
+    .await
+    .context("hook timed out")??;
+
+    anyhow::ensure!(
+        output.status.success(),
+        "hook exited with {}: {}",
+        output.status,
+        String::from_utf8_lossy(&output.stderr).trim()
+    );
+    Ok(())
+}
+
+impl Inner {
+    fn render(&self, event: &Event) -> String {
+        let template = match self.config.templates.get(event.name()) {
+            Some(template) => template.as_str(),
+            None => event.default_template(),
+        };
+
+        render_template(template, event, &self.host)
+    }
+
+    async fn send(
+        &self,
+        channel: &AppConfigNotificationChannel,
+        event: &Event,
+        text: &str,
+    ) -> Result<()> {
+        match &channel.target {
+            NotificationTarget::Telegram { bot_token, chat_id } => {
+                let url = format!(
+                    "https://api.telegram.org/bot{}/sendMessage",
+                    bot_token.as_str()
+                );
+                let body = serde_json::json!({ "chat_id": chat_id, "text": text });
+                self.post(url, body).await
+            }
+            NotificationTarget::Slack { webhook_url } => {
+                let body = serde_json::json!({ "text": text });
+                self.post(webhook_url.as_str(), body).await
+            }
+            NotificationTarget::Discord { webhook_url } => {
+                let body = serde_json::json!({ "content": text });
+                self.post(webhook_url.as_str(), body).await
+            }
+            NotificationTarget::Email { to, from, sendmail } => {
+                let mut mail = format!("To: {to}\n");
+                if let Some(from) = from {
+                    mail += &format!("From: {from}\n");
+                }
+                mail += &format!(
+                    "Subject: [nodekeeper] {} on {}\n\n{text}\n",
+                    event.name(),
+                    self.host
+                );
+
+                let mut child = tokio::process::Command::new(sendmail)
+                    .arg("-t")
+                    .stdin(Stdio::piped())
+                    .spawn()
+                    .context("failed to start sendmail")?;
+                if let Some(mut stdin) = child.stdin.take() {
+                    stdin.write_all(mail.as_bytes()).await?;
+                }
+                let status = child.wait().await?;
+                anyhow::ensure!(status.success(), "sendmail exited with {status}");
+                Ok(())
+            }
+        }
+    }
+
+    async fn post<U: reqwest::IntoUrl>(&self, url: U, body: serde_json::Value) -> Result<()> {
+        self.client
+            .post(url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(body.to_string())
+            .send()
+            .await
+            .context("failed to send request")?
+            .error_for_status()?;
+        Ok(())
+    }
+}
+
+fn render_template(template: &str, event: &Event, host: &str) -> String {
+    let mut text = template
+        .replace("{event}", event.name())
+        .replace("{severity}", event.severity().as_str())
+        .replace("{host}", host);
+    for (name, value) in event.params() {
+        text = text.replace(&format!("{{{name}}}"), &value);
+    }
+    text
+}
+
+impl AppConfigNotificationChannel {
+    fn accepts(&self, event: &Event) -> bool {
+        event.severity() >= self.min_severity
+            && (self.events.is_empty() || self.events.iter().any(|name| name == event.name()))
+    }
+}
+
+impl NotificationSeverity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Info => "info",
+            Self::Warning => "warning",
+            Self::Error => "error",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "snake_case", tag = "event", content = "data")]
+pub enum Event {
+    /// Validation manager (re)started
+    ManagerStarted,
+    /// Election request was accepted by the elector
+    ElectionSubmitted {
+        election_id: u32,
+        participant: String,
+        #[serde(with = "serde_string")]
+        stake: u128,
+    },
+    /// Stake was recovered from the elector
+    StakeRecovered {
+        #[serde(with = "serde_string")]
+        amount: u128,
+    },
+    /// Validator node is behind the network
+    NodeOutOfSync { mc_time_diff: i32 },
+    /// Wallet balance is not enough
+    LowBalance {
+        address: String,
+        #[serde(with = "serde_string")]
+        balance: u128,
+        #[serde(with = "serde_string")]
+        required: u128,
+    },
+    /// Validator incident was detected
+    Incident { description: String },
+    /// Stuck node was restarted by the watchdog
+    NodeRestarted { reason: String },
+    /// Free space on the node DB filesystem is low
+    LowDiskSpace { available: u64, required: u64 },
+    /// Global version in the blockchain config was changed
+    NetworkVersionChanged { version: u32, capabilities: u64 },
+    /// Critical blockchain config params were changed (elections, stakes or voting setup)
+    BlockchainConfigChanged { params: Vec<u32>, changes: String },
+    /// Node doesn't support the global version required by the network
+    UpgradeRequired {
+        node_version: u32,
+        required_version: u32,
+    },
+    /// Scheduled report of the validator balances
+    BalanceReport {
+        #[serde(with = "serde_string")]
+        wallet: u128,
+        #[serde(with = "serde_optional_string")]
+        depool: Option<u128>,
+    },
+    /// Node DB size exceeds the configured threshold
+    LargeDb { size: u64, max_size: u64 },
+    /// Unexpected error
+    Error { message: String },
+}
+
+impl Event {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::ManagerStarted => "manager_started",
+            Self::ElectionSubmitted { .. } => "election_submitted",
+            Self::StakeRecovered { .. } => "stake_recovered",
+            Self::NodeOutOfSync { .. } => "node_out_of_sync",
+            Self::LowBalance { .. } => "low_balance",
+            Self::Incident { .. } => "incident",
+            Self::NodeRestarted { .. } => "node_restarted",
+            Self::LowDiskSpace { .. } => "low_disk_space",
+            Self::NetworkVersionChanged { .. } => "network_version_changed",
+            Self::BlockchainConfigChanged { .. } => "blockchain_config_changed",
+            Self::UpgradeRequired { .. } => "upgrade_required",
+            Self::BalanceReport { .. } => "balance_report",
+            Self::LargeDb { .. } => "large_db",
+            Self::Error { .. } => "error",
+        }
+    }
+
+    pub fn severity(&self) -> NotificationSeverity {
+        match self {
+            Self::ManagerStarted
+            | Self::ElectionSubmitted { .. }
+            | Self::StakeRecovered { .. }
+            | Self::NetworkVersionChanged { .. }
+            | Self::BalanceReport { .. } => NotificationSeverity::Info,
+            Self::NodeOutOfSync { .. }
+            | Self::LowBalance { .. }
+            | Self::Incident { .. }
+            | Self::NodeRestarted { .. }
+            | Self::LowDiskSpace { .. }
+            | Self::BlockchainConfigChanged { .. }
+            | Self::LargeDb { .. } => NotificationSeverity::Warning,
+            Self::UpgradeRequired { .. } | Self::Error { .. } => NotificationSeverity::Error,
+        }
+    }
+
+    /// JSON which is passed to the hook scripts
+    fn payload(&self) -> Result<Vec<u8>> {
+        let mut payload = serde_json::to_value(self)?;
+        if let Some(payload) = payload.as_object_mut() {
+            payload.insert("severity".to_owned(), self.severity().as_str().into());
+            payload.insert("timestamp".to_owned(), broxus_util::now().into());
+        }
+        Ok(serde_json::to_vec(&payload)?)
+    }
+
+    fn default_template(&self) -> &'static str {
+        match self {
+            Self::ManagerStarted => "[{host}] validation manager started",
+            Self::ElectionSubmitted { .. } => {
+                "[{host}] elections {election_id}: stake {stake} from {participant} accepted"
+            }
+            Self::StakeRecovered { .. } => "[{host}] recovered stake {amount}",
+            Self::NodeOutOfSync { .. } => "[{host}] node is out of sync ({mc_time_diff}s behind)",
+            Self::LowBalance { .. } => {
+                "[{host}] low balance of {address}: {balance} (required {required})"
+            }
+            Self::Incident { .. } => "[{host}] {description}",
+            Self::NodeRestarted { .. } => "[{host}] node was restarted: {reason}",
+            Self::LowDiskSpace { .. } => {
+                "[{host}] low disk space: {available} available (required {required})"
+            }
+            Self::NetworkVersionChanged { .. } => {
+                "[{host}] network global version changed to {version} (capabilities {capabilities})"
+            }
+            Self::BlockchainConfigChanged { .. } => {
+                "[{host}] blockchain config params {params} changed: {changes}"
+            }
+            Self::UpgradeRequired { .. } => {
+                "[{host}] node supports global version {node_version}, \
+                but the network requires {required_version}. Run `nodekeeper upgrade`"
+            }
+            Self::BalanceReport { .. } => "[{host}] balances: wallet {wallet}, DePool {depool}",
+            Self::LargeDb { .. } => "[{host}] node DB size is {size} (max {max_size})",
+            Self::Error { .. } => "[{host}] error: {message}",
+        }
+    }
+
+    fn params(&self) -> Vec<(&'static str, String)> {
+        match self {
+            Self::ManagerStarted => Vec::new(),
+            Self::ElectionSubmitted {
+                election_id,
+                participant,
+                stake,
+            } => vec![
+                ("election_id", election_id.to_string()),
+                ("participant", participant.clone()),
+                ("stake", Tokens(*stake).to_string()),
+            ],
+            Self::StakeRecovered { amount } => vec![("amount", Tokens(*amount).to_string())],
+            Self::NodeOutOfSync { mc_time_diff } => {
+                vec![("mc_time_diff", mc_time_diff.to_string())]
+            }
+            Self::LowBalance {
+                address,
+                balance,
+                required,
+            } => vec![
+                ("address", address.clone()),
+                ("balance", Tokens(*balance).to_string()),
+                ("required", Tokens(*required).to_string()),
+            ],
+            Self::Incident { description } => vec![("description", description.clone())],
+            Self::NodeRestarted { reason } => vec![("reason", reason.clone())],
+            Self::LowDiskSpace {
+                available,
+                required,
+            } => vec![
+                ("available", format_gb(*available)),
+                ("required", format_gb(*required)),
+            ],
+            Self::NetworkVersionChanged {
+                version,
+                capabilities,
+            } => vec![
+                ("version", version.to_string()),
+                ("capabilities", format!("0x{capabilities:016x}")),
+            ],
+            Self::BlockchainConfigChanged { params, changes } => vec![
+                (
+                    "params",
+                    params
+                        .iter()
+                        .map(|param| param.to_string())
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                ),
+                ("changes", changes.clone()),
+            ],
+            Self::UpgradeRequired {
+                node_version,
+                required_version,
+            } => vec![
+                ("node_version", node_version.to_string()),
+                ("required_version", required_version.to_string()),
+            ],
+            Self::BalanceReport { wallet, depool } => vec![
+                ("wallet", Tokens(*wallet).to_string()),
+                (
+                    "depool",
+                    depool
+                        .map(|b| Tokens(b).to_string())
+                        .unwrap_or_else(|| "-".to_owned()),
+                ),
+            ],
+            Self::LargeDb { size, max_size } => vec![
+                ("size", format_gb(*size)),
+                ("max_size", format_gb(*max_size)),
+            ],
+            Self::Error { message } => vec![("message", message.clone())],
+        }
+    }
+}
+
+fn format_gb(bytes: u64) -> String {
+    format!("{:.1} GB", bytes as f64 / (1u64 << 30) as f64)
+}
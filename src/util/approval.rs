@@ -0,0 +1,72 @@
+use anyhow::{Context, Result};
+use broxus_util::serde_string;
+use everscale_crypto::ed25519;
+use serde::{Deserialize, Serialize};
+
+/// How long the approval request stays valid (in seconds)
+pub const APPROVAL_TTL: u32 = 600;
+
+/// Operation which requires a second approval (two-man rule)
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ApprovalOperation {
+    Transfer {
+        #[serde(with = "serde_string")]
+        wallet: ton_block::MsgAddressInt,
+        #[serde(with = "serde_string")]
+        destination: ton_block::MsgAddressInt,
+        #[serde(with = "serde_string")]
+        amount: u128,
+    },
+    KeyRotation {
+        /// Rotated keys (`control` or `adnl`)
+        keys: String,
+    },
+}
+
+/// Approval request which is shared with the approver as an opaque token
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApprovalRequest {
+    pub operation: ApprovalOperation,
+    /// Unix timestamp after which the approval is not accepted
+    pub expire_at: u32,
+}
+
+impl ApprovalRequest {
+    pub fn new(operation: ApprovalOperation) -> Self {
+        Self {
+            operation,
+            expire_at: broxus_util::now() + APPROVAL_TTL,
+        }
+    }
+
+    pub fn encode(&self) -> String {
+        base64::encode(serde_json::to_vec(self).unwrap())
+    }
+
+    pub fn decode(token: &str) -> Result<Self> {
+        let data = base64::decode(token.trim()).context("invalid approval request encoding")?;
+        serde_json::from_slice(&data).context("invalid approval request")
+    }
+
+    pub fn is_expired(&self) -> bool {
+        broxus_util::now() >= self.expire_at
+    }
+}
+
+/// Signs the encoded approval request
+pub fn sign_approval(token: &str, secret: &ed25519::SecretKey) -> [u8; 64] {
+    let public = ed25519::PublicKey::from(secret);
+    secret.expand().sign_raw(token.trim().as_bytes(), &public)
+}
+
+/// Checks that the approval was signed by one of the approvers
+pub fn verify_approval(
+    token: &str,
+    signature: &[u8; 64],
+    approvers: &[ed25519::PublicKey],
+) -> bool {
+    approvers
+        .iter()
+        .any(|approver| approver.verify_raw(token.trim().as_bytes(), signature))
+}
@@ -2,10 +2,14 @@ use std::future::Future;
 use std::io::{Read, Write};
 use std::path::Path;
 use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use std::sync::{Mutex, RwLock};
+use std::time::Instant;
 
 use anyhow::{Context, Result};
 use dialoguer::console;
 use dialoguer::theme::Theme;
+use serde::Serialize;
 use tokio::process::Command;
 use tokio_util::sync::CancellationToken;
 use ton_block::Deserializable;
@@ -25,20 +29,125 @@ pub async fn exec(command: &mut Command) -> Result<()> {
     Ok(())
 }
 
+static TOKEN_DECIMALS: AtomicU8 = AtomicU8::new(crate::defaults::DEFAULT_DECIMALS);
+static TOKEN_CURRENCY: RwLock<&'static str> = RwLock::new(crate::defaults::DEFAULT_CURRENCY);
+
+/// Sets the number of decimals used for token amounts in the CLI
+pub fn set_token_decimals(decimals: u8) {
+    TOKEN_DECIMALS.store(std::cmp::min(decimals, 18), Ordering::Relaxed);
+}
+
+/// Sets the ticker of the native currency used in the CLI
+pub fn set_token_currency(currency: &'static str) {
+    *TOKEN_CURRENCY.write().unwrap() = currency;
+}
+
+/// Ticker of the native currency
+pub fn token_currency() -> &'static str {
+    *TOKEN_CURRENCY.read().unwrap()
+}
+
+/// Amount of nano tokens in one token
+pub fn one_token() -> u128 {
+    10u128.pow(TOKEN_DECIMALS.load(Ordering::Relaxed) as u32)
+}
+
+/// Parses the amount of native tokens.
+///
+/// Accepts plain numbers (`1250.5`), exponents (`1_000e9`) and an optional unit
+/// (`nano` or the currency ticker). Numbers without a unit are nano tokens if `nano` is set.
+pub fn parse_tokens(amount: &str, nano: bool) -> Result<u128> {
+    let decimals = TOKEN_DECIMALS.load(Ordering::Relaxed);
+    parse_tokens_with(amount, nano, decimals, token_currency())
+}
+
+fn parse_tokens_with(amount: &str, nano: bool, decimals: u8, currency: &str) -> Result<u128> {
+    // NOTE: u128 has at most 39 digits, larger exponents can only overflow
+    const MAX_EXPONENT: usize = 38;
+
+    let amount = amount.trim().replace('_', "");
+
+    // Split into mantissa, exponent and unit
+    let number_len = amount
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(amount.len());
+    let (number, mut rest) = amount.split_at(number_len);
+    let mut exponent = 0;
+    if let Some(exp) = rest.strip_prefix(['e', 'E']) {
+        let exp_len = exp.find(|c: char| !c.is_ascii_digit()).unwrap_or(exp.len());
+        if exp_len > 0 {
+            exponent = exp[..exp_len]
+                .parse::<usize>()
+                .context("invalid exponent")?;
+            anyhow::ensure!(exponent <= MAX_EXPONENT, "exponent is too large");
+            rest = &exp[exp_len..];
+        }
+    }
+
+    let nano = match rest.trim() {
+        "" => nano,
+        unit if unit.eq_ignore_ascii_case("nano") => true,
+        unit if unit.eq_ignore_ascii_case(currency) => false,
+        unit => anyhow::bail!("unknown unit `{unit}`"),
+    };
+    let decimals = if nano { 0 } else { decimals as usize };
+
+    let (int, frac) = number.split_once('.').unwrap_or((number, ""));
+    anyhow::ensure!(!int.is_empty() || !frac.is_empty(), "invalid amount");
+
+    let frac = frac.trim_end_matches('0');
+    let shift = exponent + decimals;
+    anyhow::ensure!(
+        frac.len() <= shift,
+        "amount must be a whole number of nano tokens"
+    );
+
+    let digits = format!("{int}{frac}{:0<width$}", "", width = shift - frac.len());
+    digits.parse::<u128>().context("invalid amount")
+}
+
+pub fn parse_token_amount(amount: &str, decimals: u8) -> Result<u128> {
+    let (int, frac) = amount.split_once('.').unwrap_or((amount, ""));
+    anyhow::ensure!(
+        frac.len() <= decimals as usize,
+        "too many decimal places (max {decimals})"
+    );
+
+    // Pad the fractional part with zeros and parse the whole number at once
+    let digits = format!("{int}{frac:0<width$}", width = decimals as usize);
+    digits.parse::<u128>().context("invalid amount")
+}
+
+/// Amount of native tokens.
+///
+/// Displayed with the network decimals, the alternate form (`{:#}`) also adds the ticker.
+#[derive(Debug, Clone, Copy)]
 pub struct Tokens<T>(pub T);
 
+impl FromStr for Tokens<u128> {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_tokens(s, false).map(Self)
+    }
+}
+
 impl<T: Into<u128> + Copy> std::fmt::Display for Tokens<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let decimals = TOKEN_DECIMALS.load(Ordering::Relaxed) as usize;
+        let one = one_token();
+
         let num: u128 = self.0.into();
-        let int = num / 1000000000;
-        let mut frac = num % 1000000000;
+        let int = num / one;
+        let frac = num % one;
 
         int.fmt(f)?;
         if frac > 0 {
-            while frac % 10 == 0 && frac > 0 {
-                frac /= 10;
-            }
-            f.write_fmt(format_args!(".{frac}"))?;
+            let frac = format!("{frac:0decimals$}");
+            f.write_fmt(format_args!(".{}", frac.trim_end_matches('0')))?;
+        }
+        if f.alternate() {
+            f.write_fmt(format_args!(" {}", token_currency()))?;
         }
         Ok(())
     }
@@ -155,18 +264,63 @@ pub fn parse_hex_or_base64(data: &str) -> Result<Vec<u8>> {
     }
 }
 
+/// How confirmations are answered
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum InputMode {
+    /// Ask the user
+    Interactive,
+    /// Accept all confirmations (`--yes`)
+    AssumeYes,
+    /// Use default answers without prompting (`--no-input`)
+    NoInput,
+}
+
+static INPUT_MODE: AtomicU8 = AtomicU8::new(InputMode::Interactive as u8);
+
+pub fn set_input_mode(mode: InputMode) {
+    INPUT_MODE.store(mode as u8, Ordering::Relaxed);
+}
+
+pub fn input_mode() -> InputMode {
+    match INPUT_MODE.load(Ordering::Relaxed) {
+        1 => InputMode::AssumeYes,
+        2 => InputMode::NoInput,
+        _ => InputMode::Interactive,
+    }
+}
+
 pub fn confirm<T>(theme: &dyn Theme, default: bool, text: T) -> std::io::Result<bool>
 where
     T: Into<String>,
 {
-    dialoguer::Confirm::with_theme(theme)
-        .with_prompt(text)
-        .default(default)
-        .interact()
+    match input_mode() {
+        InputMode::Interactive => dialoguer::Confirm::with_theme(theme)
+            .with_prompt(text)
+            .default(default)
+            .interact(),
+        InputMode::AssumeYes => Ok(true),
+        InputMode::NoInput => Ok(default),
+    }
+}
+
+/// Error which is returned when the user declines the operation
+#[derive(Debug, thiserror::Error)]
+#[error("operation declined")]
+pub struct Declined;
+
+static JSON_OUTPUT: AtomicBool = AtomicBool::new(false);
+
+/// Forces compact JSON output and machine-readable errors
+pub fn set_json_output(enabled: bool) {
+    JSON_OUTPUT.store(enabled, Ordering::Relaxed);
+}
+
+pub fn is_json_output() -> bool {
+    JSON_OUTPUT.load(Ordering::Relaxed)
 }
 
 pub fn print_output<T: std::fmt::Display>(arg: T) {
-    if is_terminal() {
+    if is_terminal() && !is_json_output() {
         writeln!(std::io::stdout(), "{arg:#}")
     } else {
         write!(std::io::stdout(), "{arg}")
@@ -182,21 +336,40 @@ pub fn print_error(text: impl std::fmt::Display) {
     }
 }
 
+pub fn print_warning(text: impl std::fmt::Display) {
+    if is_terminal() {
+        eprintln!("{}", console::style(format!("⚠ {text}")).yellow().bold());
+    } else {
+        eprintln!("Warning: {text}");
+    }
+}
+
 pub fn note(text: impl std::fmt::Display) -> impl std::fmt::Display {
     console::style(format!("({text})")).dim()
 }
 
+/// Sequence of the setup steps.
+///
+/// Records the duration and outcome of each step to the global steps log.
+/// The last `next` call just prints the final message.
 pub struct Steps {
     total: usize,
     current: usize,
+    running: Option<(String, Instant)>,
 }
 
 impl Steps {
     pub fn new(total: usize) -> Self {
-        Self { total, current: 0 }
+        Self {
+            total,
+            current: 0,
+            running: None,
+        }
     }
 
     pub fn next(&mut self, text: impl std::fmt::Display) {
+        self.finish_running(StepOutcome::Done);
+
         if is_terminal() {
             eprintln!(
                 "{} {text}",
@@ -207,8 +380,88 @@ impl Steps {
         } else {
             eprintln!("[{}/{}] {text}", self.current, self.total);
         }
+
+        if self.current < self.total {
+            self.running = Some((text.to_string(), Instant::now()));
+        }
         self.current += 1;
     }
+
+    fn finish_running(&mut self, outcome: StepOutcome) {
+        if let Some((name, started_at)) = self.running.take() {
+            STEPS_LOG.lock().unwrap().push(StepRecord {
+                name,
+                duration_ms: started_at.elapsed().as_millis() as u64,
+                outcome,
+            });
+        }
+    }
+}
+
+impl Drop for Steps {
+    fn drop(&mut self) {
+        // NOTE: steps are dropped before the final message only on errors or early returns
+        self.finish_running(StepOutcome::Interrupted);
+    }
+}
+
+static STEPS_LOG: Mutex<Vec<StepRecord>> = Mutex::new(Vec::new());
+
+/// Finished setup step
+#[derive(Debug, Clone, Serialize)]
+pub struct StepRecord {
+    pub name: String,
+    pub duration_ms: u64,
+    pub outcome: StepOutcome,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StepOutcome {
+    Done,
+    Interrupted,
+}
+
+/// Takes all steps recorded since the last call
+pub fn take_steps_log() -> Vec<StepRecord> {
+    std::mem::take(&mut *STEPS_LOG.lock().unwrap())
+}
+
+/// Prints the table with durations and outcomes of the steps
+pub fn print_steps_summary(steps: &[StepRecord]) {
+    if steps.is_empty() {
+        return;
+    }
+
+    let width = steps.iter().map(|step| step.name.chars().count()).max();
+    let width = width.unwrap_or_default();
+
+    eprintln!("\n{}", console::style("Summary:").bold());
+    let mut total_ms = 0;
+    for step in steps {
+        total_ms += step.duration_ms;
+        let outcome = match step.outcome {
+            StepOutcome::Done => console::style("done").green(),
+            StepOutcome::Interrupted => console::style("interrupted").red(),
+        };
+        eprintln!(
+            "  {:<width$}  {:>8}  {outcome}",
+            step.name,
+            format_step_duration(step.duration_ms),
+        );
+    }
+    eprintln!(
+        "  {:<width$}  {:>8}",
+        "Total",
+        format_step_duration(total_ms)
+    );
+}
+
+fn format_step_duration(ms: u64) -> String {
+    match ms / 1000 {
+        secs @ 0..=59 => format!("{secs}.{}s", ms % 1000 / 100),
+        secs => format!("{}m {:02}s", secs / 60, secs % 60),
+    }
 }
 
 pub fn is_terminal() -> bool {
@@ -264,3 +517,71 @@ fn ignore_interrupt(e: anyhow::Error) -> Result<()> {
 
     Err(e)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(amount: &str, nano: bool) -> Result<u128> {
+        parse_tokens_with(amount, nano, 9, "EVER")
+    }
+
+    #[test]
+    fn fractional_amounts() {
+        assert_eq!(parse("1250.5", false).unwrap(), 1_250_500_000_000);
+        assert_eq!(parse(".5", false).unwrap(), 500_000_000);
+        assert_eq!(parse("0.123456789", false).unwrap(), 123_456_789);
+        // Trailing zeros don't count as precision
+        assert_eq!(parse("0.1234567890", false).unwrap(), 123_456_789);
+        assert!(parse("0.1234567891", false).is_err());
+
+        assert_eq!(
+            parse_tokens_with("1.5", false, 18, "EVER").unwrap(),
+            15 * 10u128.pow(17)
+        );
+        assert!(parse_tokens_with("1.5", false, 0, "EVER").is_err());
+    }
+
+    #[test]
+    fn exponents() {
+        assert_eq!(parse("1e9", true).unwrap(), 1_000_000_000);
+        assert_eq!(parse("1.5e1", false).unwrap(), 15_000_000_000);
+        assert_eq!(parse("1.5E1", true).unwrap(), 15);
+        assert!(parse("1.55e1", true).is_err());
+        assert!(parse("1e39", true).is_err());
+        assert!(parse("1e38", false).is_err());
+        assert!(parse("1e999999999", true).is_err());
+    }
+
+    #[test]
+    fn units() {
+        assert_eq!(parse("1_000e9 nano", false).unwrap(), 1_000_000_000_000);
+        assert_eq!(parse("42nano", false).unwrap(), 42);
+        assert_eq!(parse("2 EVER", true).unwrap(), 2_000_000_000);
+        assert_eq!(parse("2 ever", true).unwrap(), 2_000_000_000);
+        assert!(parse("1.5 nano", false).is_err());
+        assert!(parse("1 TON", false).is_err());
+        assert!(parse_tokens_with("1 EVER", false, 9, "VENOM").is_err());
+    }
+
+    #[test]
+    fn invalid_amounts() {
+        for amount in [
+            "",
+            " ",
+            ".",
+            "-1",
+            "+1",
+            "abc",
+            "e9",
+            "1e",
+            "1e-9",
+            "1..2",
+            "1.2.3",
+            "0x10",
+            "340282366920938463463374607431768211456",
+        ] {
+            assert!(parse(amount, true).is_err(), "{amount}");
+        }
+    }
+}
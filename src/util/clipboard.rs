@@ -0,0 +1,79 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+
+use super::{is_terminal, print_warning};
+
+/// Copies the text to the system clipboard.
+///
+/// Uses the first available clipboard tool and falls back to the OSC 52
+/// terminal escape sequence (works over SSH in most terminals).
+/// If `clear_after` is set, the clipboard is cleared by a detached process.
+pub fn copy_to_clipboard(text: &str, clear_after: Option<Duration>) -> Result<()> {
+    for tool in CLIPBOARD_TOOLS {
+        let child = Command::new(tool.copy[0])
+            .args(&tool.copy[1..])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn();
+        let Ok(mut child) = child else {
+            continue;
+        };
+
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin
+                .write_all(text.as_bytes())
+                .context("failed to write to clipboard")?;
+        }
+        if !child.wait()?.success() {
+            continue;
+        }
+
+        if let Some(timeout) = clear_after {
+            // NOTE: the clipboard is cleared even if the CLI exits earlier
+            Command::new("sh")
+                .arg("-c")
+                .arg(format!("sleep {}; {}", timeout.as_secs(), tool.clear))
+                .stdin(Stdio::null())
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .spawn()
+                .context("failed to schedule clipboard cleanup")?;
+        }
+        return Ok(());
+    }
+
+    anyhow::ensure!(is_terminal(), "no clipboard tool found");
+    eprint!("\x1b]52;c;{}\x07", base64::encode(text));
+    if clear_after.is_some() {
+        print_warning("clipboard will not be cleared automatically, no clipboard tool found");
+    }
+    Ok(())
+}
+
+struct ClipboardTool {
+    copy: &'static [&'static str],
+    clear: &'static str,
+}
+
+const CLIPBOARD_TOOLS: &[ClipboardTool] = &[
+    ClipboardTool {
+        copy: &["wl-copy"],
+        clear: "wl-copy --clear",
+    },
+    ClipboardTool {
+        copy: &["xclip", "-selection", "clipboard"],
+        clear: "printf '' | xclip -selection clipboard",
+    },
+    ClipboardTool {
+        copy: &["xsel", "--clipboard", "--input"],
+        clear: "xsel --clipboard --delete",
+    },
+    ClipboardTool {
+        copy: &["pbcopy"],
+        clear: "printf '' | pbcopy",
+    },
+];
@@ -0,0 +1,112 @@
+use anyhow::{Context, Result};
+
+use super::Tokens;
+
+/// Returns human-readable values of the config param fields.
+///
+/// Params without the known layout are described as a single `value` field.
+pub fn describe_config_param(param: &ton_block::ConfigParamEnum) -> Result<Vec<(String, String)>> {
+    use ton_block::ConfigParamEnum;
+
+    let seconds = |value: u32| format!("{value}s");
+
+    Ok(match param {
+        ConfigParamEnum::ConfigParam11(voting) => {
+            let normal = voting
+                .read_normal_params()
+                .context("invalid normal proposal params")?;
+            let critical = voting
+                .read_critical_params()
+                .context("invalid critical proposal params")?;
+
+            let mut fields = Vec::with_capacity(16);
+            for (prefix, setup) in [("normal", normal), ("critical", critical)] {
+                fields.extend([
+                    (
+                        format!("{prefix}.min_tot_rounds"),
+                        setup.min_tot_rounds.to_string(),
+                    ),
+                    (
+                        format!("{prefix}.max_tot_rounds"),
+                        setup.max_tot_rounds.to_string(),
+                    ),
+                    (format!("{prefix}.min_wins"), setup.min_wins.to_string()),
+                    (format!("{prefix}.max_losses"), setup.max_losses.to_string()),
+                    (
+                        format!("{prefix}.min_store_sec"),
+                        seconds(setup.min_store_sec),
+                    ),
+                    (
+                        format!("{prefix}.max_store_sec"),
+                        seconds(setup.max_store_sec),
+                    ),
+                    (format!("{prefix}.bit_price"), setup.bit_price.to_string()),
+                    (format!("{prefix}.cell_price"), setup.cell_price.to_string()),
+                ]);
+            }
+            fields
+        }
+        ConfigParamEnum::ConfigParam15(timings) => vec![
+            (
+                "validators_elected_for".to_owned(),
+                seconds(timings.validators_elected_for),
+            ),
+            (
+                "elections_start_before".to_owned(),
+                seconds(timings.elections_start_before),
+            ),
+            (
+                "elections_end_before".to_owned(),
+                seconds(timings.elections_end_before),
+            ),
+            ("stake_held_for".to_owned(), seconds(timings.stake_held_for)),
+        ],
+        ConfigParamEnum::ConfigParam17(stakes) => vec![
+            (
+                "min_stake".to_owned(),
+                Tokens(stakes.min_stake.as_u128()).to_string(),
+            ),
+            (
+                "max_stake".to_owned(),
+                Tokens(stakes.max_stake.as_u128()).to_string(),
+            ),
+            (
+                "min_total_stake".to_owned(),
+                Tokens(stakes.min_total_stake.as_u128()).to_string(),
+            ),
+            (
+                "max_stake_factor".to_owned(),
+                format!("{:.2}", stakes.max_stake_factor as f64 / 65536.0),
+            ),
+        ],
+        other => vec![("value".to_owned(), format!("{other:?}"))],
+    })
+}
+
+/// Returns changed fields as `(name, old, new)`, missing values are shown as `-`
+pub fn diff_config_fields(
+    old: &[(String, String)],
+    new: &[(String, String)],
+) -> Vec<(String, String, String)> {
+    let find = |fields: &[(String, String)], name: &str| {
+        fields
+            .iter()
+            .find(|(field, _)| field == name)
+            .map(|(_, value)| value.clone())
+            .unwrap_or_else(|| "-".to_owned())
+    };
+
+    let mut result = Vec::new();
+    for (name, value) in new {
+        let old_value = find(old, name);
+        if &old_value != value {
+            result.push((name.clone(), old_value, value.clone()));
+        }
+    }
+    for (name, value) in old {
+        if !new.iter().any(|(field, _)| field == name) {
+            result.push((name.clone(), value.clone(), "-".to_owned()));
+        }
+    }
+    result
+}
@@ -0,0 +1,233 @@
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::{Context, Result};
+
+/// Cron expression (`minute hour day-of-month month day-of-week`) evaluated in UTC.
+///
+/// Supports `*`, lists, ranges, steps and `@hourly`/`@daily`/`@weekly`/`@monthly` aliases.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct CronSchedule {
+    expr: String,
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    any_day: bool,
+    any_weekday: bool,
+}
+
+impl CronSchedule {
+    /// Returns the first matching time strictly after the specified timestamp
+    pub fn next_after(&self, timestamp: u32) -> Option<u32> {
+        const MINUTE: u32 = 60;
+        const HOUR: u32 = 3600;
+        const DAY: u32 = 86400;
+        // NOTE: enough for Feb 29 across the skipped leap year
+        const MAX_LOOKAHEAD: u32 = 8 * 366 * DAY;
+
+        let mut time = (timestamp / MINUTE + 1) * MINUTE;
+        let limit = time.saturating_add(MAX_LOOKAHEAD);
+        while time < limit {
+            let days = time / DAY;
+            let (_, month, day) = civil_from_days(days as i64);
+            // NOTE: 1970-01-01 was Thursday
+            let weekday = (days + 4) % 7;
+            if !has(self.months, month) || !self.matches_day(day, weekday) {
+                time = (days + 1) * DAY;
+                continue;
+            }
+
+            if !has(self.hours, time % DAY / HOUR) {
+                time = (time / HOUR + 1) * HOUR;
+                continue;
+            }
+
+            if !has(self.minutes, time % HOUR / MINUTE) {
+                time += MINUTE;
+                continue;
+            }
+
+            return Some(time);
+        }
+        None
+    }
+
+    fn matches_day(&self, day: u32, weekday: u32) -> bool {
+        // NOTE: when both fields are restricted, any of them can match (as in cron)
+        match (self.any_day, self.any_weekday) {
+            (true, true) => true,
+            (false, true) => has(self.days, day),
+            (true, false) => has(self.weekdays, weekday),
+            (false, false) => has(self.days, day) || has(self.weekdays, weekday),
+        }
+    }
+}
+
+impl FromStr for CronSchedule {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let expr = match s.trim() {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            expr => expr,
+        };
+
+        let fields = expr.split_whitespace().collect::<Vec<_>>();
+        let [minutes, hours, days, months, weekdays] = fields[..] else {
+            anyhow::bail!("cron expression must have 5 fields");
+        };
+
+        // NOTE: both 0 and 7 are Sunday
+        let mut weekdays_mask = parse_field(weekdays, 0, 7).context("invalid day of week")?;
+        if has(weekdays_mask, 7) {
+            weekdays_mask = (weekdays_mask | 1) & !(1 << 7);
+        }
+
+        Ok(Self {
+            expr: s.trim().to_owned(),
+            minutes: parse_field(minutes, 0, 59).context("invalid minute")?,
+            hours: parse_field(hours, 0, 23).context("invalid hour")?,
+            days: parse_field(days, 1, 31).context("invalid day of month")?,
+            months: parse_field(months, 1, 12).context("invalid month")?,
+            weekdays: weekdays_mask,
+            // NOTE: stepped wildcards (e.g. `*/2`) are unrestricted too (as in cron)
+            any_day: days.starts_with('*'),
+            any_weekday: weekdays.starts_with('*'),
+        })
+    }
+}
+
+impl fmt::Display for CronSchedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.expr)
+    }
+}
+
+/// Parses the cron field into a bit mask
+fn parse_field(field: &str, min: u32, max: u32) -> Result<u64> {
+    let mut mask = 0;
+    for item in field.split(',') {
+        let (range, step) = match item.split_once('/') {
+            Some((range, step)) => (range, step.parse::<u32>().context("invalid step")?),
+            None => (item, 1),
+        };
+        anyhow::ensure!(step > 0, "step must be greater than zero");
+
+        let (from, to) = match range {
+            "*" => (min, max),
+            range => match range.split_once('-') {
+                Some((from, to)) => (from.parse()?, to.parse()?),
+                // NOTE: `N/step` means `N-max/step`
+                None if step > 1 => (range.parse()?, max),
+                None => {
+                    let value = range.parse()?;
+                    (value, value)
+                }
+            },
+        };
+        anyhow::ensure!(
+            min <= from && from <= to && to <= max,
+            "value out of range {min}-{max}"
+        );
+
+        for value in (from..=to).step_by(step as usize) {
+            mask |= 1 << value;
+        }
+    }
+    Ok(mask)
+}
+
+fn has(mask: u64, value: u32) -> bool {
+    mask & (1 << value) != 0
+}
+
+/// Converts days since the unix epoch into the `(year, month, day)` date
+pub fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    // See http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let days = days + 719468;
+    let era = days / 146097;
+    let doe = days - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + (month <= 2) as i64;
+    (year, month as u32, day as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn next(expr: &str, timestamp: u32) -> Option<u32> {
+        expr.parse::<CronSchedule>().unwrap().next_after(timestamp)
+    }
+
+    #[test]
+    fn stepped_wildcard_day_is_unrestricted() {
+        // 2024-06-04 (Tue) -> 2024-06-10 (Mon), not 2024-06-05 (odd day)
+        assert_eq!(next("0 0 */2 * 1", 1717459200), Some(1717977600));
+        // Both fields are restricted, any of them matches
+        assert_eq!(next("0 0 1-31/2 * 1", 1717459200), Some(1717545600));
+    }
+
+    #[test]
+    fn sunday_is_both_0_and_7() {
+        // 2024-06-01 (Sat) -> 2024-06-02 (Sun)
+        assert_eq!(next("0 0 * * 7", 1717200000), Some(1717286400));
+        assert_eq!(next("0 0 * * 0", 1717200000), Some(1717286400));
+        assert_eq!(next("0 0 * * 5-7", 1717200000), Some(1717286400));
+    }
+
+    #[test]
+    fn start_with_step_runs_until_max() {
+        // 2024-01-01 00:00
+        let start = 1704067200;
+        assert_eq!(next("5/20 * * * *", start), Some(start + 5 * 60));
+        assert_eq!(next("5/20 * * * *", start + 5 * 60), Some(start + 25 * 60));
+        assert_eq!(next("5/20 * * * *", start + 45 * 60), Some(start + 65 * 60));
+    }
+
+    #[test]
+    fn next_after_crosses_month_and_year() {
+        // 2024-01-31 23:59 -> 2024-02-01 00:00
+        assert_eq!(next("0 0 1 * *", 1706745540), Some(1706745600));
+        // 2023-12-31 23:59:30 -> 2024-01-01 00:00
+        assert_eq!(next("@daily", 1704067170), Some(1704067200));
+        assert_eq!(next("* * * * *", 1704067170), Some(1704067200));
+        // 2024-02-01 -> 2024-03-01 (leap year)
+        assert_eq!(next("0 0 1 3 *", 1706745600), Some(1709251200));
+    }
+
+    #[test]
+    fn next_after_finds_leap_day() {
+        // 2021-03-01 -> 2024-02-29 -> 2028-02-29
+        assert_eq!(next("0 0 29 2 *", 1614556800), Some(1709164800));
+        assert_eq!(next("0 0 29 2 *", 1709164800), Some(1835395200));
+        // Never matches
+        assert_eq!(next("0 0 30 2 *", 1614556800), None);
+    }
+
+    #[test]
+    fn invalid_expressions() {
+        for expr in [
+            "0 0 * *",
+            "60 * * * *",
+            "* 24 * * *",
+            "* * 0 * *",
+            "* * * 13 *",
+            "* * * * 8",
+            "*/0 * * * *",
+            "5-1 * * * *",
+            "a * * * *",
+        ] {
+            assert!(expr.parse::<CronSchedule>().is_err(), "{expr}");
+        }
+    }
+}
@@ -0,0 +1,55 @@
+use std::sync::atomic::AtomicU64;
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use ton_block::{GetRepresentationHash, Serializable};
+use ton_executor::TransactionExecutor;
+
+use super::TransactionWithHash;
+
+/// Local transaction executor used to estimate fees and exit codes
+pub struct Emulator {
+    config: ton_executor::BlockchainConfig,
+}
+
+impl Emulator {
+    pub fn new(config: &ton_block::ConfigParams, global_id: i32) -> Result<Self> {
+        let config = ton_executor::BlockchainConfig::with_config(config.clone(), global_id)
+            .context("invalid blockchain config")?;
+        Ok(Self { config })
+    }
+
+    /// Executes the message on the specified account state
+    pub fn execute(
+        &self,
+        message: &ton_block::Message,
+        account: Option<ton_block::AccountStuff>,
+    ) -> Result<TransactionWithHash> {
+        let last_trans_lt = account
+            .as_ref()
+            .map(|account| account.storage.last_trans_lt)
+            .unwrap_or_default();
+        let lt = std::cmp::max(last_trans_lt, message.lt().unwrap_or_default()) + 1;
+
+        let mut account_root = match account {
+            Some(account) => ton_block::Account::Account(account),
+            None => ton_block::Account::AccountNone,
+        }
+        .serialize()?;
+
+        let params = ton_executor::ExecuteParams {
+            block_unixtime: broxus_util::now(),
+            block_lt: lt,
+            last_tr_lt: Arc::new(AtomicU64::new(lt)),
+            ..Default::default()
+        };
+
+        let executor = ton_executor::OrdinaryTransactionExecutor::new(self.config.clone());
+        let data = executor
+            .execute_with_libs_and_params(Some(message), &mut account_root, params)
+            .context("failed to execute message")?;
+        let hash = data.hash()?;
+
+        Ok(TransactionWithHash { hash, data })
+    }
+}
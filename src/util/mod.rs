@@ -2,13 +2,27 @@ use std::hash::BuildHasherDefault;
 
 use dashmap::DashMap;
 
+pub use self::approval::*;
 pub use self::block_stuff::*;
 pub use self::cli::*;
+pub use self::clipboard::*;
+pub use self::config_params::*;
+pub use self::cron::*;
+pub use self::emulator::*;
+pub use self::progress::*;
+pub use self::qr::*;
 pub use self::serde::*;
 pub use self::transaction::*;
 
+mod approval;
 mod block_stuff;
 mod cli;
+mod clipboard;
+mod config_params;
+mod cron;
+mod emulator;
+mod progress;
+mod qr;
 mod serde;
 pub mod system;
 mod transaction;
@@ -0,0 +1,194 @@
+use std::collections::VecDeque;
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use dialoguer::console;
+use indicatif::{ProgressBar, ProgressStyle};
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::process::Command;
+
+use super::is_terminal;
+
+/// Progress of a long operation.
+///
+/// Uses a spinner or a progress bar in the terminal and plain log lines otherwise.
+pub struct Progress {
+    bar: Option<ProgressBar>,
+    message: String,
+    total: Option<u64>,
+    position: u64,
+    logged_percent: u64,
+    started_at: Instant,
+}
+
+impl Progress {
+    /// Creates a spinner for the operation with unknown length
+    pub fn spinner(message: impl Into<String>) -> Self {
+        let message = message.into();
+        let bar = is_terminal().then(|| {
+            let bar = ProgressBar::new_spinner().with_style(
+                ProgressStyle::with_template("{spinner:.green} {prefix:.bold} {wide_msg:.dim}")
+                    .unwrap(),
+            );
+            bar.set_prefix(message.clone());
+            bar.enable_steady_tick(Duration::from_millis(100));
+            bar
+        });
+        Self::new(bar, message, None)
+    }
+
+    /// Creates a progress bar with ETA for the operation with the known length
+    pub fn bar(message: impl Into<String>, total: u64) -> Self {
+        let message = message.into();
+        let bar = is_terminal().then(|| {
+            let bar = ProgressBar::new(total).with_style(
+                ProgressStyle::with_template(
+                    "{prefix:.bold} [{bar:30.green}] {percent}% (eta {eta}) {wide_msg:.dim}",
+                )
+                .unwrap()
+                .progress_chars("=> "),
+            );
+            bar.set_prefix(message.clone());
+            bar
+        });
+        Self::new(bar, message, Some(total))
+    }
+
+    /// Creates a progress bar for the download with the optional content length
+    pub fn download(message: impl Into<String>, total: Option<u64>) -> Self {
+        match total {
+            Some(total) => {
+                let progress = Self::bar(message, total);
+                if let Some(bar) = &progress.bar {
+                    bar.set_style(
+                        ProgressStyle::with_template(
+                            "{prefix:.bold} [{bar:30.green}] {bytes}/{total_bytes} (eta {eta})",
+                        )
+                        .unwrap()
+                        .progress_chars("=> "),
+                    );
+                }
+                progress
+            }
+            None => Self::spinner(message),
+        }
+    }
+
+    fn new(bar: Option<ProgressBar>, message: String, total: Option<u64>) -> Self {
+        if bar.is_none() {
+            eprintln!("{message}...");
+        }
+        Self {
+            bar,
+            message,
+            total,
+            position: 0,
+            logged_percent: 0,
+            started_at: Instant::now(),
+        }
+    }
+
+    /// Updates the details of the current step (ignored without a terminal)
+    pub fn set_message(&self, message: impl Into<String>) {
+        if let Some(bar) = &self.bar {
+            bar.set_message(message.into());
+        }
+    }
+
+    pub fn set_position(&mut self, position: u64) {
+        self.position = position;
+        if let Some(bar) = &self.bar {
+            bar.set_position(position);
+            return;
+        }
+
+        // Log every 10 percent
+        if let Some(total) = self.total.filter(|total| *total > 0) {
+            let percent = std::cmp::min(position * 100 / total, 100) / 10 * 10;
+            if percent > self.logged_percent {
+                self.logged_percent = percent;
+                eprintln!("{}: {percent}%", self.message);
+            }
+        }
+    }
+
+    pub fn inc(&mut self, delta: u64) {
+        self.set_position(self.position + delta);
+    }
+
+    /// Marks the operation as completed
+    pub fn finish(self) {
+        let elapsed = self.started_at.elapsed().as_secs();
+        match &self.bar {
+            Some(bar) => {
+                bar.finish_and_clear();
+                eprintln!(
+                    "{} {} {}",
+                    console::style("✔").green(),
+                    self.message,
+                    console::style(format!("({elapsed}s)")).dim()
+                );
+            }
+            None => eprintln!("{}: done in {elapsed}s", self.message),
+        }
+    }
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        if let Some(bar) = &self.bar {
+            if !bar.is_finished() {
+                bar.finish_and_clear();
+            }
+        }
+    }
+}
+
+/// Sleeps for the specified duration showing the remaining time
+pub async fn sleep_with_progress(message: impl Into<String>, duration: Duration) {
+    const TICK: Duration = Duration::from_secs(1);
+
+    let mut progress = Progress::bar(message, duration.as_secs());
+    let deadline = tokio::time::Instant::now() + duration;
+    loop {
+        let remaining = deadline.saturating_duration_since(tokio::time::Instant::now());
+        progress.set_position((duration - remaining).as_secs());
+        if remaining.is_zero() {
+            break;
+        }
+        tokio::time::sleep(std::cmp::min(TICK, remaining)).await;
+    }
+    progress.finish();
+}
+
+/// Runs the command showing the last line of its stderr in the progress
+pub async fn exec_with_progress(command: &mut Command, progress: &Progress) -> Result<()> {
+    const TAIL_LINES: usize = 20;
+
+    let mut child = command.stderr(Stdio::piped()).spawn()?;
+    let stderr = child.stderr.take().context("failed to capture stderr")?;
+
+    // Keep the last lines to show them on failure
+    let mut tail = VecDeque::with_capacity(TAIL_LINES);
+    let mut lines = BufReader::new(stderr).lines();
+    while let Some(line) = lines.next_line().await? {
+        progress.set_message(line.trim());
+        if tail.len() == TAIL_LINES {
+            tail.pop_front();
+        }
+        tail.push_back(line);
+    }
+
+    let status = child
+        .wait()
+        .await
+        .context("child process encountered an error")?;
+
+    anyhow::ensure!(
+        status.success(),
+        "child process failed with exit code {status}\n{}",
+        tail.make_contiguous().join("\n")
+    );
+    Ok(())
+}
@@ -0,0 +1,724 @@
+use anyhow::Result;
+
+/// Renders the data as a QR code for the terminal.
+///
+/// Light modules are drawn with blocks (as `qrencode -t UTF8`), so the code
+/// is scannable on dark terminal backgrounds.
+pub fn render_qr(data: &str) -> Result<String> {
+    const QUIET_ZONE: usize = 2;
+
+    let qr = QrCode::encode(data.as_bytes())?;
+
+    let size = qr.size + QUIET_ZONE * 2;
+    let is_light = |x: usize, y: usize| {
+        let inner = QUIET_ZONE..QUIET_ZONE + qr.size;
+        !(inner.contains(&x) && inner.contains(&y) && qr.get(x - QUIET_ZONE, y - QUIET_ZONE))
+    };
+
+    let mut result = String::with_capacity(size * size);
+    for y in (0..size).step_by(2) {
+        for x in 0..size {
+            let top = is_light(x, y);
+            let bottom = y + 1 >= size || is_light(x, y + 1);
+            result.push(match (top, bottom) {
+                (true, true) => '█',
+                (true, false) => '▀',
+                (false, true) => '▄',
+                (false, false) => ' ',
+            });
+        }
+        result.push('\n');
+    }
+    Ok(result)
+}
+
+/// Byte mode QR code with the medium error correction level (versions 1 to 9)
+struct QrCode {
+    size: usize,
+    modules: Vec<bool>,
+    is_function: Vec<bool>,
+}
+
+impl QrCode {
+    fn encode(data: &[u8]) -> Result<Self> {
+        // Select the smallest version which fits the data
+        let version = (MIN_VERSION..=MAX_VERSION)
+            .find(|&version| 4 + 8 + data.len() * 8 <= num_data_codewords(version) * 8)
+            .ok_or_else(|| anyhow::anyhow!("data is too long for a QR code"))?;
+
+        // Build data codewords
+        let capacity = num_data_codewords(version);
+        let mut bits = BitBuffer::default();
+        bits.push(0b0100, 4);
+        bits.push(data.len() as u32, 8);
+        for &byte in data {
+            bits.push(byte as u32, 8);
+        }
+        bits.push(0, std::cmp::min(4, capacity * 8 - bits.len));
+        bits.push(0, (8 - bits.len % 8) % 8);
+        for pad in [0xec, 0x11].into_iter().cycle() {
+            if bits.len >= capacity * 8 {
+                break;
+            }
+            bits.push(pad, 8);
+        }
+
+        let mut qr = Self {
+            size: version * 4 + 17,
+            modules: Vec::new(),
+            is_function: Vec::new(),
+        };
+        qr.modules = vec![false; qr.size * qr.size];
+        qr.is_function = vec![false; qr.size * qr.size];
+
+        qr.draw_function_patterns(version);
+        qr.draw_codewords(&add_ecc_and_interleave(version, &bits.bytes));
+
+        // Select the mask with the lowest penalty
+        let mut best = None;
+        for mask in 0..8 {
+            qr.apply_mask(mask);
+            qr.draw_format_bits(mask);
+            let penalty = qr.penalty_score();
+            if best
+                .map(|(_, best_penalty)| penalty < best_penalty)
+                .unwrap_or(true)
+            {
+                best = Some((mask, penalty));
+            }
+            // NOTE: masks are XOR so applying it again restores the data
+            qr.apply_mask(mask);
+        }
+        let (mask, _) = best.unwrap_or_default();
+        qr.apply_mask(mask);
+        qr.draw_format_bits(mask);
+
+        Ok(qr)
+    }
+
+    fn get(&self, x: usize, y: usize) -> bool {
+        self.modules[y * self.size + x]
+    }
+
+    fn set_function(&mut self, x: usize, y: usize, dark: bool) {
+        self.modules[y * self.size + x] = dark;
+        self.is_function[y * self.size + x] = true;
+    }
+
+    fn draw_function_patterns(&mut self, version: usize) {
+        let size = self.size;
+
+        // Timing patterns
+        for i in 0..size {
+            self.set_function(6, i, i % 2 == 0);
+            self.set_function(i, 6, i % 2 == 0);
+        }
+
+        // Finder patterns with separators
+        for (cx, cy) in [(3, 3), (size - 4, 3), (3, size - 4)] {
+            for dy in -4i32..=4 {
+                for dx in -4i32..=4 {
+                    let (x, y) = (cx as i32 + dx, cy as i32 + dy);
+                    if (0..size as i32).contains(&x) && (0..size as i32).contains(&y) {
+                        let dist = std::cmp::max(dx.abs(), dy.abs());
+                        self.set_function(x as usize, y as usize, dist != 2 && dist != 4);
+                    }
+                }
+            }
+        }
+
+        // Alignment patterns
+        let positions = ALIGNMENT_POSITIONS[version - 1];
+        let last = positions.len().saturating_sub(1);
+        for (i, &cx) in positions.iter().enumerate() {
+            for (j, &cy) in positions.iter().enumerate() {
+                // Skip the corners with finder patterns
+                if (i == 0 && j == 0) || (i == 0 && j == last) || (i == last && j == 0) {
+                    continue;
+                }
+                for dy in -2i32..=2 {
+                    for dx in -2i32..=2 {
+                        let dark = std::cmp::max(dx.abs(), dy.abs()) != 1;
+                        let (x, y) = ((cx as i32 + dx) as usize, (cy as i32 + dy) as usize);
+                        self.set_function(x, y, dark);
+                    }
+                }
+            }
+        }
+
+        // Reserve the format bits area
+        self.draw_format_bits(0);
+
+        // Version information
+        if version >= 7 {
+            let mut rem = version as u32;
+            for _ in 0..12 {
+                rem = (rem << 1) ^ ((rem >> 11) * 0x1f25);
+            }
+            let bits = (version as u32) << 12 | rem;
+            for i in 0..18 {
+                let dark = (bits >> i) & 1 != 0;
+                let (a, b) = (size - 11 + i % 3, i / 3);
+                self.set_function(a, b, dark);
+                self.set_function(b, a, dark);
+            }
+        }
+    }
+
+    fn draw_format_bits(&mut self, mask: u32) {
+        // NOTE: `0` is the format value of the medium error correction level
+        let data = mask;
+        let mut rem = data;
+        for _ in 0..10 {
+            rem = (rem << 1) ^ ((rem >> 9) * 0x537);
+        }
+        let bits = (data << 10 | rem) ^ 0x5412;
+        let bit = |i: usize| (bits >> i) & 1 != 0;
+
+        let size = self.size;
+        for i in 0..6 {
+            self.set_function(8, i, bit(i));
+        }
+        self.set_function(8, 7, bit(6));
+        self.set_function(8, 8, bit(7));
+        self.set_function(7, 8, bit(8));
+        for i in 9..15 {
+            self.set_function(14 - i, 8, bit(i));
+        }
+
+        for i in 0..8 {
+            self.set_function(size - 1 - i, 8, bit(i));
+        }
+        for i in 8..15 {
+            self.set_function(8, size - 15 + i, bit(i));
+        }
+        self.set_function(8, size - 8, true);
+    }
+
+    fn draw_codewords(&mut self, data: &[u8]) {
+        let size = self.size;
+        let total_bits = data.len() * 8;
+
+        let mut i = 0;
+        let mut right = size - 1;
+        while right >= 1 {
+            // Skip the vertical timing pattern
+            if right == 6 {
+                right = 5;
+            }
+            for vert in 0..size {
+                for j in 0..2 {
+                    let x = right - j;
+                    let upward = (right + 1) & 2 == 0;
+                    let y = if upward { size - 1 - vert } else { vert };
+                    if !self.is_function[y * size + x] && i < total_bits {
+                        self.modules[y * size + x] = (data[i >> 3] >> (7 - (i & 7))) & 1 != 0;
+                        i += 1;
+                    }
+                }
+            }
+            if right < 2 {
+                break;
+            }
+            right -= 2;
+        }
+    }
+
+    fn apply_mask(&mut self, mask: u32) {
+        for y in 0..self.size {
+            for x in 0..self.size {
+                let invert = match mask {
+                    0 => (x + y) % 2 == 0,
+                    1 => y % 2 == 0,
+                    2 => x % 3 == 0,
+                    3 => (x + y) % 3 == 0,
+                    4 => (x / 3 + y / 2) % 2 == 0,
+                    5 => x * y % 2 + x * y % 3 == 0,
+                    6 => (x * y % 2 + x * y % 3) % 2 == 0,
+                    _ => ((x + y) % 2 + x * y % 3) % 2 == 0,
+                };
+                let idx = y * self.size + x;
+                if invert && !self.is_function[idx] {
+                    self.modules[idx] = !self.modules[idx];
+                }
+            }
+        }
+    }
+
+    fn penalty_score(&self) -> usize {
+        let size = self.size;
+        let mut penalty = 0;
+
+        for transposed in [false, true] {
+            let get = |a: usize, b: usize| {
+                if transposed {
+                    self.get(b, a)
+                } else {
+                    self.get(a, b)
+                }
+            };
+
+            for b in 0..size {
+                // Runs of the same color and finder-like patterns
+                let mut runs = FinderRuns::new(size);
+                let mut color = false;
+                let mut run = 0;
+                for a in 0..size {
+                    if get(a, b) == color {
+                        run += 1;
+                        if run == 5 {
+                            penalty += 3;
+                        } else if run > 5 {
+                            penalty += 1;
+                        }
+                    } else {
+                        runs.push(run);
+                        if !color {
+                            penalty += runs.count_patterns() * 40;
+                        }
+                        color = !color;
+                        run = 1;
+                    }
+                }
+                penalty += runs.finish(color, run) * 40;
+            }
+        }
+
+        // 2x2 blocks of the same color
+        for y in 1..size {
+            for x in 1..size {
+                let color = self.get(x, y);
+                if color == self.get(x - 1, y)
+                    && color == self.get(x, y - 1)
+                    && color == self.get(x - 1, y - 1)
+                {
+                    penalty += 3;
+                }
+            }
+        }
+
+        // Balance of dark and light modules
+        let total = size * size;
+        let dark = self.modules.iter().filter(|&&dark| dark).count();
+        let k = (dark * 20)
+            .abs_diff(total * 10)
+            .div_ceil(total)
+            .saturating_sub(1);
+        penalty + k * 10
+    }
+}
+
+/// Last runs of a line used to find `1:1:3:1:1` finder-like patterns
+/// (the light border around the symbol is counted as in the spec)
+struct FinderRuns {
+    size: usize,
+    history: [usize; 7],
+}
+
+impl FinderRuns {
+    fn new(size: usize) -> Self {
+        Self {
+            size,
+            history: [0; 7],
+        }
+    }
+
+    fn push(&mut self, mut run: usize) {
+        if self.history[0] == 0 {
+            run += self.size;
+        }
+        self.history.copy_within(0..6, 1);
+        self.history[0] = run;
+    }
+
+    /// Returns the number of patterns ending with the last light run
+    fn count_patterns(&self) -> usize {
+        let h = &self.history;
+        let n = h[1];
+        let core = n > 0 && h[2] == n && h[3] == n * 3 && h[4] == n && h[5] == n;
+        usize::from(core && h[0] >= n * 4 && h[6] >= n)
+            + usize::from(core && h[6] >= n * 4 && h[0] >= n)
+    }
+
+    fn finish(mut self, color: bool, mut run: usize) -> usize {
+        if color {
+            self.push(run);
+            run = 0;
+        }
+        self.push(run + self.size);
+        self.count_patterns()
+    }
+}
+
+#[derive(Default)]
+struct BitBuffer {
+    bytes: Vec<u8>,
+    len: usize,
+}
+
+impl BitBuffer {
+    fn push(&mut self, value: u32, bits: usize) {
+        for i in (0..bits).rev() {
+            if self.len % 8 == 0 {
+                self.bytes.push(0);
+            }
+            if (value >> i) & 1 != 0 {
+                *self.bytes.last_mut().unwrap() |= 0x80 >> (self.len % 8);
+            }
+            self.len += 1;
+        }
+    }
+}
+
+fn num_raw_data_modules(version: usize) -> usize {
+    let mut result = (16 * version + 128) * version + 64;
+    if version >= 2 {
+        let num_align = version / 7 + 2;
+        result -= (25 * num_align - 10) * num_align - 55;
+        if version >= 7 {
+            result -= 36;
+        }
+    }
+    result
+}
+
+fn num_data_codewords(version: usize) -> usize {
+    let idx = version - 1;
+    num_raw_data_modules(version) / 8 - ECC_CODEWORDS_PER_BLOCK[idx] * NUM_ECC_BLOCKS[idx]
+}
+
+/// Splits data into blocks, appends Reed-Solomon codes and interleaves them
+fn add_ecc_and_interleave(version: usize, data: &[u8]) -> Vec<u8> {
+    let idx = version - 1;
+    let num_blocks = NUM_ECC_BLOCKS[idx];
+    let ecc_len = ECC_CODEWORDS_PER_BLOCK[idx];
+    let raw_codewords = num_raw_data_modules(version) / 8;
+    let num_short_blocks = num_blocks - raw_codewords % num_blocks;
+    let short_block_len = raw_codewords / num_blocks;
+
+    let divisor = reed_solomon_divisor(ecc_len);
+    let mut blocks = Vec::with_capacity(num_blocks);
+    let mut offset = 0;
+    for i in 0..num_blocks {
+        let data_len = short_block_len - ecc_len + usize::from(i >= num_short_blocks);
+        let mut block = data[offset..offset + data_len].to_vec();
+        offset += data_len;
+
+        let ecc = reed_solomon_remainder(&block, &divisor);
+        if i < num_short_blocks {
+            block.push(0);
+        }
+        block.extend(ecc);
+        blocks.push(block);
+    }
+
+    let mut result = Vec::with_capacity(raw_codewords);
+    for i in 0..=short_block_len {
+        for (j, block) in blocks.iter().enumerate() {
+            // Skip the padding byte of short blocks
+            if i != short_block_len - ecc_len || j >= num_short_blocks {
+                result.push(block[i]);
+            }
+        }
+    }
+    result
+}
+
+fn reed_solomon_divisor(degree: usize) -> Vec<u8> {
+    let mut result = vec![0; degree];
+    result[degree - 1] = 1;
+    let mut root = 1;
+    for _ in 0..degree {
+        for j in 0..degree {
+            result[j] = gf_multiply(result[j], root);
+            if j + 1 < degree {
+                result[j] ^= result[j + 1];
+            }
+        }
+        root = gf_multiply(root, 0x02);
+    }
+    result
+}
+
+fn reed_solomon_remainder(data: &[u8], divisor: &[u8]) -> Vec<u8> {
+    let mut result = vec![0; divisor.len()];
+    for &byte in data {
+        let factor = byte ^ result.remove(0);
+        result.push(0);
+        for (x, &y) in result.iter_mut().zip(divisor) {
+            *x ^= gf_multiply(y, factor);
+        }
+    }
+    result
+}
+
+/// Multiplication in GF(2^8) modulo `x^8 + x^4 + x^3 + x^2 + 1`
+fn gf_multiply(x: u8, y: u8) -> u8 {
+    let mut z = 0u8;
+    for i in (0..8).rev() {
+        z = (z << 1) ^ ((z >> 7) * 0x1d);
+        z ^= ((y >> i) & 1) * x;
+    }
+    z
+}
+
+const MIN_VERSION: usize = 1;
+const MAX_VERSION: usize = 9;
+
+const ECC_CODEWORDS_PER_BLOCK: [usize; MAX_VERSION] = [10, 16, 26, 18, 24, 16, 18, 22, 22];
+const NUM_ECC_BLOCKS: [usize; MAX_VERSION] = [1, 1, 1, 2, 2, 4, 4, 4, 5];
+
+const ALIGNMENT_POSITIONS: [&[usize]; MAX_VERSION] = [
+    &[],
+    &[6, 18],
+    &[6, 22],
+    &[6, 26],
+    &[6, 30],
+    &[6, 34],
+    &[6, 22, 38],
+    &[6, 24, 42],
+    &[6, 26, 46],
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Module rows as hex (dark is `1`, padded to 4 bits)
+    fn rows(qr: &QrCode) -> Vec<String> {
+        (0..qr.size)
+            .map(|y| {
+                let mut bits = (0..qr.size).map(|x| qr.get(x, y)).collect::<Vec<_>>();
+                bits.resize(qr.size.next_multiple_of(4), false);
+                bits.chunks(4)
+                    .map(|chunk| {
+                        let value = chunk
+                            .iter()
+                            .fold(0, |acc, &dark| (acc << 1) | u32::from(dark));
+                        std::char::from_digit(value, 16).unwrap()
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn check(data: &str, version: usize, expected: &[&str]) {
+        let qr = QrCode::encode(data.as_bytes()).unwrap();
+        assert_eq!(qr.size, version * 4 + 17);
+        assert_eq!(rows(&qr), expected);
+    }
+
+    // NOTE: expected matrices are produced by the reference encoder
+    // (Nayuki's `qrcodegen`, byte mode, medium ECC, automatic mask)
+
+    #[test]
+    fn version_1() {
+        // mask 2
+        check(
+            "EVER",
+            1,
+            &[
+                "fe43f8", "824a08", "ba8ae8", "baaae8", "bafae8", "82ca08", "feabf8", "00b800",
+                "be6be0", "402970", "ab3490", "9521f0", "e3f4b0", "00be60", "fe6b70", "82be60",
+                "bae970", "baa920", "baf480", "8201a0", "fef4b0",
+            ],
+        );
+    }
+
+    #[test]
+    fn version_3() {
+        // mask 2
+        check(
+            "https://github.com/broxus/nodekeeper",
+            3,
+            &[
+                "fe6d5bf8", "82372a08", "bac43ae8", "bac3d2e8", "baf09ae8", "82b98208", "feaaabf8",
+                "00fb9000", "be694be0", "70569b88", "4fef6080", "702d1050", "cb7be060", "9838ff88",
+                "f2d9cce0", "18939f90", "b3d15560", "cda6f7a8", "a3dfcba0", "983dae10", "82b35fb8",
+                "00d0e8f8", "fe31dae0", "82e21890", "bac04fa0", "bafeb878", "bac99ff0", "8257ad50",
+                "febd53a0",
+            ],
+        );
+    }
+
+    #[test]
+    fn version_5() {
+        // mask 4
+        check(
+            "0:7237D7D9AABBFFFCF6D5C0B6CE9E9A6F58DFD6CA3F38C26B9096A2DDE46F757D",
+            5,
+            &[
+                "feb2986bf8",
+                "8273d58208",
+                "ba60c692e8",
+                "ba9b023ae8",
+                "ba94d26ae8",
+                "8282d8c208",
+                "feaaaaabf8",
+                "00f29d2000",
+                "8bd298a7c8",
+                "75ee32cd90",
+                "2265717910",
+                "44c7ff94f8",
+                "dec46243e0",
+                "eda08decd0",
+                "b3ff49bd90",
+                "2de2e20d78",
+                "ee9e32a278",
+                "41cad84db0",
+                "ba20cbdef0",
+                "e4b244a830",
+                "2a391ac608",
+                "b9f6f06b88",
+                "6b91affe40",
+                "7156058968",
+                "0304884be8",
+                "cc6c33f490",
+                "1bfd75f490",
+                "3c43dd9978",
+                "fb4e842fe8",
+                "00c72fc890",
+                "fea9c97a90",
+                "82596b88e0",
+                "baf2322fe8",
+                "ba5a9dd788",
+                "ba56affd40",
+                "821e4499f0",
+                "fef11ad0f8",
+            ],
+        );
+    }
+
+    #[test]
+    fn version_7() {
+        // mask 6
+        check(
+            concat!(
+                "ton://transfer/0:7237d7d9aabbfffcf6d5c0b6ce9e9a6f58dfd6ca3f38c26b9096a2dde46f757d",
+                "?amount=1000000000&text=top-up",
+            ),
+            7,
+            &[
+                "fe8b9a7f4bf8",
+                "82acb2919208",
+                "bab662a7d2e8",
+                "ba46a18fdae8",
+                "ba969fc6bae8",
+                "825ee8990208",
+                "feaaaaaaabf8",
+                "0036f8fe1800",
+                "9fc8afb934b8",
+                "0d566ea6eb60",
+                "7252e855e8d8",
+                "dd0b5f28bd20",
+                "9ed93478d7c8",
+                "39c581269e20",
+                "eece2bfd8b90",
+                "5ce5cb452b28",
+                "b614f841f308",
+                "709a185769f8",
+                "0f0c73c8d178",
+                "b87d2cf84ea0",
+                "5fc3af9e3fd0",
+                "18e0d8a7f8c0",
+                "4a8a0ae52a98",
+                "58b048d3d8a8",
+                "bf809f9ddfc8",
+                "410523bad720",
+                "6a587e6c0d40",
+                "1cf62df375f0",
+                "3791c026fdd0",
+                "2cea20df66b8",
+                "1b7ed0ccd358",
+                "18729afb58f8",
+                "371b9b9a6010",
+                "25cffcfa7ea0",
+                "0a8b0b01f778",
+                "78c2e8e782e0",
+                "9add7fcbff90",
+                "00a5688bc8d0",
+                "fe896acc0a80",
+                "82af68f638a0",
+                "baf22fb1bf98",
+                "ba9234867328",
+                "ba769e10dbc8",
+                "8218f03d0eb8",
+                "fe8843ee0bc0",
+            ],
+        );
+    }
+
+    #[test]
+    fn version_9() {
+        // mask 2
+        check(
+            concat!(
+                "ton://transfer/0:7237d7d9aabbfffcf6d5c0b6ce9e9a6f58dfd6ca3f38c26b9096a2dde46f757d",
+                "?amount=25000000000000",
+                "&text=nodekeeper+validator+wallet+refill+for+the+next+elections+round",
+            ),
+            9,
+            &[
+                "fe7b2d7bc463f8",
+                "826c6c342a3208",
+                "ba9733738192e8",
+                "badca8027a6ae8",
+                "bae487fa19e2e8",
+                "8280608d622208",
+                "feaaaaaaaaabf8",
+                "00d4f68a94b800",
+                "be1c89ff1c7be0",
+                "b8d42f8ed8f558",
+                "5e2229302e9740",
+                "215c75d494f050",
+                "9792a8354d6fa0",
+                "35ed6f9f85b0d8",
+                "731f57256a5f80",
+                "04b6f099b0f058",
+                "8e9a71e62c2fa0",
+                "11b5ee1e0c34e8",
+                "e62c6c007a53d0",
+                "e88d8199d7a850",
+                "4a9b74722829a0",
+                "75eb21b6d4f078",
+                "2637d9f13b8fe0",
+                "752caed2c6e750",
+                "7fe2d5fb3e6fe0",
+                "b89d3a8b88f8e8",
+                "3ae2ccad7fdaf0",
+                "f8b1108dc3c8d0",
+                "8fd794fb581fa0",
+                "4ded8b67cc7488",
+                "caa14008bad060",
+                "0c066047b79e90",
+                "2fe570872a6a68",
+                "4d0103c79dead8",
+                "2a998ac8b60840",
+                "a506f4b3a7d208",
+                "5b361856784d78",
+                "e16b77b605ebe8",
+                "6778caa83a0920",
+                "bd0d9ed295f650",
+                "c24d329d2d6838",
+                "2c214573856b98",
+                "dea49a9c624d20",
+                "61d77503d19b18",
+                "130e1cfa192fb0",
+                "00a28e8a51e8f8",
+                "fe1d04ad2f4aa0",
+                "82b1098cc5b898",
+                "bacaa5fa584fe0",
+                "bace4007dc6f18",
+                "bac7f970230298",
+                "8212c01cd4b0d0",
+                "fe8f1ba06977a0",
+            ],
+        );
+    }
+
+    #[test]
+    fn too_long_data() {
+        assert!(QrCode::encode(&[b'x'; 180]).is_ok());
+        assert!(QrCode::encode(&[b'x'; 181]).is_err());
+    }
+}
@@ -107,4 +107,41 @@ pub mod serde_block_id {
             block_id.file_hash
         ))
     }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<ton_block::BlockIdExt, D::Error> {
+        use serde::de::Error;
+
+        fn parse_hash(s: &str) -> Option<ton_types::UInt256> {
+            let bytes: [u8; 32] = hex::decode(s).ok()?.try_into().ok()?;
+            Some(ton_types::UInt256::from(bytes))
+        }
+
+        let str = String::deserialize(deserializer)?;
+        let mut parts = str.split(':');
+        let mut next = || {
+            parts
+                .next()
+                .ok_or_else(|| Error::custom("invalid block id"))
+        };
+
+        let workchain_id = next()?
+            .parse::<i32>()
+            .map_err(|_| Error::custom("invalid workchain id"))?;
+        let shard = u64::from_str_radix(next()?, 16).map_err(|_| Error::custom("invalid shard"))?;
+        let seq_no = next()?
+            .parse::<u32>()
+            .map_err(|_| Error::custom("invalid seqno"))?;
+        let root_hash = parse_hash(next()?).ok_or_else(|| Error::custom("invalid root hash"))?;
+        let file_hash = parse_hash(next()?).ok_or_else(|| Error::custom("invalid file hash"))?;
+
+        Ok(ton_block::BlockIdExt {
+            shard_id: ton_block::ShardIdent::with_tagged_prefix(workchain_id, shard)
+                .map_err(|_| Error::custom("invalid shard"))?,
+            seq_no,
+            root_hash,
+            file_hash,
+        })
+    }
 }
@@ -6,6 +6,20 @@ use std::path::{Path, PathBuf};
 use std::ptr;
 
 use anyhow::{Context, Result};
+use tokio::process::Command;
+
+use super::exec;
+
+pub async fn systemd_restart_service(service: &str) -> Result<()> {
+    exec(
+        Command::new("systemctl")
+            .stdout(std::process::Stdio::piped())
+            .arg("restart")
+            .arg(service),
+    )
+    .await
+    .with_context(|| format!("failed to restart service {service}"))
+}
 
 #[allow(unused)]
 pub fn get_sudo_uid() -> Result<Option<u32>> {
@@ -83,6 +97,106 @@ pub fn statvfs<P: AsRef<Path>>(path: P) -> Result<FsStats> {
     })
 }
 
+/// Computes the total size of all files in the directory (recursively)
+pub fn dir_size<P: AsRef<Path>>(path: P) -> Result<u64> {
+    let metadata = std::fs::symlink_metadata(path.as_ref())?;
+    if !metadata.is_dir() {
+        return Ok(metadata.len());
+    }
+
+    let mut total = 0;
+    for entry in std::fs::read_dir(path.as_ref())? {
+        let entry = entry?;
+        match dir_size(entry.path()) {
+            Ok(size) => total += size,
+            // Files can be removed by the node while iterating
+            Err(e) if is_not_found(&e) => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(total)
+}
+
+/// Block archive package of the node DB
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ArchivePackage {
+    pub path: PathBuf,
+    pub size: u64,
+    #[serde(skip)]
+    pub modified: std::time::SystemTime,
+}
+
+/// Lists block archive packages of the node DB (oldest first)
+pub fn list_archive_packages<P: AsRef<Path>>(db_path: P) -> Result<Vec<ArchivePackage>> {
+    /// Directory with block archive packages (relative to the node DB)
+    const ARCHIVES_DIR: &str = "archive";
+    /// Extension of the archive package files
+    const ARCHIVE_PACKAGE_EXT: &str = "pack";
+
+    fn collect(path: &Path, packages: &mut Vec<ArchivePackage>) -> Result<()> {
+        for entry in std::fs::read_dir(path)? {
+            let path = entry?.path();
+            let metadata = std::fs::metadata(&path)?;
+            if metadata.is_dir() {
+                collect(&path, packages)?;
+            } else if matches!(path.extension(), Some(ext) if ext == ARCHIVE_PACKAGE_EXT) {
+                packages.push(ArchivePackage {
+                    path,
+                    size: metadata.len(),
+                    modified: metadata.modified()?,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    let mut packages = Vec::new();
+    let archives_dir = db_path.as_ref().join(ARCHIVES_DIR);
+    if archives_dir.exists() {
+        collect(&archives_dir, &mut packages).context("failed to list archive packages")?;
+    }
+    packages.sort_unstable_by_key(|package| package.modified);
+    Ok(packages)
+}
+
+/// Runs `<node> --version` and returns the reported version
+pub async fn get_node_version<P: AsRef<Path>>(node: P) -> Result<String> {
+    use std::io::Write;
+
+    let child = Command::new(node.as_ref())
+        .arg("--version")
+        .output()
+        .await
+        .context("failed to run node binary")?;
+
+    if !child.status.success() {
+        std::io::stderr().write_all(&child.stdout)?;
+        anyhow::bail!("node finished with exit code {}", child.status);
+    }
+
+    parse_node_version(&child.stdout)
+        .map(String::from)
+        .context("invalid node output during version check")
+}
+
+fn parse_node_version(output: &[u8]) -> Option<&str> {
+    const OLD_PREFIX: &[u8] = b"TON Node, version ";
+    const NEW_PREFIX: &[u8] = b"EVER Node, version ";
+
+    output
+        .strip_prefix(NEW_PREFIX)
+        .or_else(|| output.strip_prefix(OLD_PREFIX))
+        .and_then(|output| output.split(|&ch| ch == b'\n').next())
+        .and_then(|output| std::str::from_utf8(output).ok())
+}
+
+fn is_not_found(e: &anyhow::Error) -> bool {
+    matches!(
+        e.downcast_ref::<std::io::Error>(),
+        Some(e) if e.kind() == std::io::ErrorKind::NotFound
+    )
+}
+
 unsafe fn get_passwd(uid: u32, buf: &mut Buffer) -> Option<libc::passwd> {
     let mut pwd: MaybeUninit<libc::passwd> = MaybeUninit::uninit();
     let mut pwdp = ptr::null_mut();
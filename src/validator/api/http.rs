@@ -0,0 +1,360 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use tokio::io::{
+    AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader,
+};
+use tokio_util::sync::{CancellationToken, DropGuard};
+
+use super::{ApiError, ApiState};
+use crate::config::{ApiRole, AppConfigApi};
+
+/// Minimal HTTP/1.1 server with the management API.
+///
+/// Each connection serves exactly one request.
+pub struct ApiServer {
+    params: AppConfigApi,
+    _cancellation_guard: DropGuard,
+}
+
+impl ApiServer {
+    pub fn spawn(params: &AppConfigApi, state: Arc<ApiState>) -> Result<Self> {
+        let cancellation_token = CancellationToken::new();
+        let tokens = Arc::new(
+            params
+                .all_tokens()
+                .map(|(name, token, role)| ApiToken {
+                    name: name.to_owned(),
+                    token: token.to_owned(),
+                    role,
+                })
+                .collect::<Vec<_>>(),
+        );
+
+        match &params.unix_socket {
+            Some(path) => {
+                let listener = bind_unix_socket(path).context("failed to bind API socket")?;
+                tracing::info!(path = %path.display(), "started API server");
+                spawn_server(listener, state, tokens.clone(), cancellation_token.clone());
+            }
+            None => {
+                let addr = params.listen_addr;
+                anyhow::ensure!(
+                    addr.ip().is_loopback(),
+                    "API server must listen on a loopback address"
+                );
+                let listener = std::net::TcpListener::bind(addr)
+                    .and_then(|listener| {
+                        listener.set_nonblocking(true)?;
+                        tokio::net::TcpListener::from_std(listener)
+                    })
+                    .context("failed to bind API address")?;
+                tracing::info!(%addr, "started API server");
+                spawn_server(listener, state, tokens.clone(), cancellation_token.clone());
+            }
+        }
+
+        Ok(Self {
+            params: params.clone(),
+            _cancellation_guard: cancellation_token.drop_guard(),
+        })
+    }
+
+    pub fn matches(&self, params: &AppConfigApi) -> bool {
+        self.params.listen_addr == params.listen_addr
+            && self.params.unix_socket == params.unix_socket
+            && self.params.all_tokens().eq(params.all_tokens())
+    }
+}
+
+/// Binds the unix socket which is accessible only by the current user
+pub fn bind_unix_socket(path: &std::path::Path) -> std::io::Result<tokio::net::UnixListener> {
+    use std::os::unix::fs::PermissionsExt;
+
+    // Remove the socket left after the previous run
+    if path.exists() {
+        std::fs::remove_file(path)?;
+    }
+    let listener = std::os::unix::net::UnixListener::bind(path)?;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+    listener.set_nonblocking(true)?;
+    tokio::net::UnixListener::from_std(listener)
+}
+
+#[async_trait::async_trait]
+pub trait Listener: Send + Sync + 'static {
+    type Stream: AsyncRead + AsyncWrite + Send + Unpin + 'static;
+
+    async fn accept_stream(&self) -> std::io::Result<Self::Stream>;
+}
+
+#[async_trait::async_trait]
+impl Listener for tokio::net::TcpListener {
+    type Stream = tokio::net::TcpStream;
+
+    async fn accept_stream(&self) -> std::io::Result<Self::Stream> {
+        self.accept().await.map(|(stream, _)| stream)
+    }
+}
+
+#[async_trait::async_trait]
+impl Listener for tokio::net::UnixListener {
+    type Stream = tokio::net::UnixStream;
+
+    async fn accept_stream(&self) -> std::io::Result<Self::Stream> {
+        self.accept().await.map(|(stream, _)| stream)
+    }
+}
+
+fn spawn_server<L: Listener>(
+    listener: L,
+    state: Arc<ApiState>,
+    tokens: Arc<Vec<ApiToken>>,
+    cancellation_token: CancellationToken,
+) {
+    tokio::spawn(async move {
+        let accept_loop = async {
+            loop {
+                let stream = match listener.accept_stream().await {
+                    Ok(stream) => stream,
+                    Err(e) => {
+                        tracing::warn!("failed to accept API connection: {e:?}");
+                        tokio::time::sleep(Duration::from_secs(1)).await;
+                        continue;
+                    }
+                };
+
+                let state = state.clone();
+                let tokens = tokens.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = handle_connection(stream, &state, &tokens).await {
+                        tracing::debug!("failed to handle API request: {e:?}");
+                    }
+                });
+            }
+        };
+
+        tokio::select! {
+            _ = accept_loop => {},
+            _ = cancellation_token.cancelled() => {},
+        }
+    });
+}
+
+async fn handle_connection<S>(stream: S, state: &ApiState, tokens: &[ApiToken]) -> Result<()>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+    let (reader, mut writer) = tokio::io::split(stream);
+    let mut reader = BufReader::new(reader);
+
+    let request = tokio::time::timeout(REQUEST_TIMEOUT, read_request(&mut reader))
+        .await
+        .context("request timeout")??;
+
+    let (status, body) = match route(state, tokens, &request).await {
+        Ok(body) => (200, body),
+        Err(e) => {
+            let status = status_code(&e);
+            if status == 500 {
+                tracing::warn!(path = request.path, "API request failed: {e:?}");
+            }
+            let body = serde_json::json!({ "error": format!("{e:#}") });
+            (status, Response::Json(body))
+        }
+    };
+
+    let (content_type, body) = match body {
+        Response::Json(body) => ("application/json", body.to_string()),
+        Response::Rss(body) => ("application/rss+xml; charset=utf-8", body),
+    };
+    let response = format!(
+        "HTTP/1.1 {status} {}\r\n\
+        Content-Type: {content_type}\r\n\
+        Content-Length: {}\r\n\
+        Connection: close\r\n\r\n{body}",
+        reason_phrase(status),
+        body.len(),
+    );
+    writer.write_all(response.as_bytes()).await?;
+    writer.shutdown().await?;
+    Ok(())
+}
+
+enum Response {
+    Json(serde_json::Value),
+    Rss(String),
+}
+
+async fn route(state: &ApiState, tokens: &[ApiToken], request: &Request) -> Result<Response> {
+    #[derive(Deserialize)]
+    struct MaintenanceRequest {
+        enabled: bool,
+        #[serde(default)]
+        reason: Option<String>,
+    }
+
+    let token = request
+        .authorization
+        .as_deref()
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .and_then(|value| {
+            let value = value.trim().as_bytes();
+            tokens
+                .iter()
+                .find(|token| constant_time_eq(value, token.token.as_bytes()))
+        })
+        .ok_or(HttpError::Unauthorized)?;
+
+    // NOTE: only queries are allowed for read-only tokens
+    let required_role = match request.method.as_str() {
+        "GET" => ApiRole::ReadOnly,
+        _ => ApiRole::Operator,
+    };
+    if token.role < required_role {
+        return Err(HttpError::Forbidden.into());
+    }
+    if required_role == ApiRole::Operator {
+        tracing::info!(
+            token = token.name,
+            path = request.path,
+            "API action requested"
+        );
+    }
+
+    let body = match (request.method.as_str(), request.path.as_str()) {
+        ("GET", "/v1/status") => state.status().await,
+        ("GET", "/v1/balances") => state.balances().await,
+        ("GET", "/v1/elections") => state.elections().await,
+        ("GET", "/v1/events") => Ok(state.events()),
+        ("GET", "/v1/events.rss") => return Ok(Response::Rss(state.events_rss())),
+        ("POST", "/v1/maintenance") => {
+            let MaintenanceRequest { enabled, reason } = serde_json::from_slice(&request.body)
+                .map_err(|e| HttpError::BadRequest(e.to_string()))?;
+            state.set_maintenance(enabled, reason)
+        }
+        ("POST", "/v1/recover-stake") => state.recover_stake().await,
+        _ => Err(HttpError::NotFound.into()),
+    }?;
+    Ok(Response::Json(body))
+}
+
+struct ApiToken {
+    name: String,
+    token: String,
+    role: ApiRole,
+}
+
+struct Request {
+    method: String,
+    path: String,
+    authorization: Option<String>,
+    body: Vec<u8>,
+}
+
+async fn read_request<R>(reader: &mut R) -> Result<Request>
+where
+    R: AsyncBufRead + Unpin,
+{
+    const MAX_LINE_LEN: u64 = 8 << 10;
+    const MAX_HEADERS: usize = 64;
+    const MAX_BODY_LEN: usize = 64 << 10;
+
+    async fn read_line<R: AsyncBufRead + Unpin>(reader: &mut R) -> Result<String> {
+        let mut line = String::new();
+        (&mut *reader)
+            .take(MAX_LINE_LEN)
+            .read_line(&mut line)
+            .await?;
+        anyhow::ensure!(line.ends_with('\n'), "invalid request line");
+        Ok(line.trim_end().to_owned())
+    }
+
+    let line = read_line(reader).await?;
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        anyhow::bail!("invalid request line");
+    };
+    let path = target.split('?').next().unwrap_or_default();
+
+    let mut request = Request {
+        method: method.to_owned(),
+        path: path.to_owned(),
+        authorization: None,
+        body: Vec::new(),
+    };
+
+    let mut content_length = 0;
+    for _ in 0..=MAX_HEADERS {
+        let line = read_line(reader).await?;
+        if line.is_empty() {
+            if content_length > 0 {
+                anyhow::ensure!(content_length <= MAX_BODY_LEN, "request body is too large");
+                request.body = vec![0; content_length];
+                reader.read_exact(&mut request.body).await?;
+            }
+            return Ok(request);
+        }
+
+        let Some((name, value)) = line.split_once(':') else {
+            anyhow::bail!("invalid header");
+        };
+        let value = value.trim();
+        if name.eq_ignore_ascii_case("content-length") {
+            content_length = value.parse().context("invalid content length")?;
+        } else if name.eq_ignore_ascii_case("authorization") {
+            request.authorization = Some(value.to_owned());
+        }
+    }
+
+    anyhow::bail!("too many headers")
+}
+
+fn status_code(e: &anyhow::Error) -> u16 {
+    if let Some(e) = e.downcast_ref::<HttpError>() {
+        return match e {
+            HttpError::BadRequest(_) => 400,
+            HttpError::Unauthorized => 401,
+            HttpError::Forbidden => 403,
+            HttpError::NotFound => 404,
+        };
+    }
+    match e.downcast_ref::<ApiError>() {
+        Some(ApiError::NotConfigured | ApiError::Maintenance) => 409,
+        Some(ApiError::NotReady) => 503,
+        None => 500,
+    }
+}
+
+fn reason_phrase(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        409 => "Conflict",
+        503 => "Service Unavailable",
+        _ => "Internal Server Error",
+    }
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+#[derive(Debug, thiserror::Error)]
+enum HttpError {
+    #[error("bad request: {0}")]
+    BadRequest(String),
+    #[error("unauthorized")]
+    Unauthorized,
+    #[error("forbidden")]
+    Forbidden,
+    #[error("not found")]
+    NotFound,
+}
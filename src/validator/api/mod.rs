@@ -0,0 +1,366 @@
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use arc_swap::ArcSwapOption;
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+pub use self::http::ApiServer;
+pub use self::rpc::{ControlSocket, ManagerClient};
+use super::journal::Journal;
+use super::scheduler::send_ticktock;
+use super::send_message;
+use crate::config::{AppConfig, AppConfigValidator};
+use crate::contracts::*;
+use crate::dirs::ProjectDirs;
+use crate::network::{connect_data_source, NodeTcpRpc, Subscription};
+use crate::notifications::{Event, Notifier};
+use crate::util::{civil_from_days, Tokens};
+
+mod http;
+mod rpc;
+
+/// Manager state which is shared with the API handlers
+pub struct ApiState {
+    dirs: ProjectDirs,
+    notifier: Notifier,
+    guard: Arc<Mutex<()>>,
+    subscription: ArcSwapOption<Subscription>,
+    dry_run: bool,
+}
+
+impl ApiState {
+    pub fn new(
+        dirs: ProjectDirs,
+        notifier: Notifier,
+        guard: Arc<Mutex<()>>,
+        dry_run: bool,
+    ) -> Self {
+        Self {
+            dirs,
+            notifier,
+            guard,
+            subscription: Default::default(),
+            dry_run,
+        }
+    }
+
+    /// Makes actions which send messages available
+    pub fn set_subscription(&self, subscription: &Arc<Subscription>) {
+        self.subscription.store(Some(subscription.clone()));
+    }
+
+    pub async fn status(&self) -> Result<serde_json::Value> {
+        let config = self.load_config()?;
+        match self.subscription.load_full() {
+            // Reuse the existing connection and the cached config
+            Some(subscription) => {
+                let blockchain_config = subscription.get_blockchain_config().await?;
+                node_status(&config, subscription.tcp_rpc()?, &blockchain_config.config).await
+            }
+            None => {
+                let node_tcp_rpc = NodeTcpRpc::new(config.control()?).await?;
+                let blockchain_config = node_tcp_rpc.get_config_all().await?;
+                node_status(&config, &node_tcp_rpc, &blockchain_config.config).await
+            }
+        }
+    }
+
+    pub async fn balances(&self) -> Result<serde_json::Value> {
+        let config = self.load_config()?;
+        let validator = config.validator.as_ref().context(ApiError::NotConfigured)?;
+
+        let mut accounts = vec![("wallet", validator.wallet_address())];
+        if let AppConfigValidator::DePool(depool) = validator {
+            accounts.push(("depool", &depool.depool));
+        }
+
+        let data_source = connect_data_source(&config).await?;
+        let mut balances = serde_json::Map::new();
+        for (name, address) in accounts {
+            let balance = data_source
+                .get_account_state(address)
+                .await?
+                .map(|state| state.storage.balance.grams.as_u128())
+                .unwrap_or_default();
+            balances.insert(
+                name.to_owned(),
+                serde_json::json!({
+                    "address": address.to_string(),
+                    "balance": Tokens(balance).to_string(),
+                }),
+            );
+        }
+        Ok(balances.into())
+    }
+
+    pub async fn elections(&self) -> Result<serde_json::Value> {
+        let timings = match self.subscription.load_full() {
+            Some(subscription) => {
+                let blockchain_config = subscription.get_blockchain_config().await?;
+                Some(RoundTimings::new(&blockchain_config.config)?)
+            }
+            None => None,
+        };
+
+        let journal = Journal::load(self.dirs.validation_journal.clone());
+        Ok(serde_json::json!({
+            "timings": timings,
+            "journal": journal.state(),
+        }))
+    }
+
+    pub fn set_maintenance(
+        &self,
+        enabled: bool,
+        reason: Option<String>,
+    ) -> Result<serde_json::Value> {
+        // NOTE: env overrides must not be stored back
+        let mut config = AppConfig::load_file(&self.dirs.app_config)?;
+        let previous = config.maintenance.is_some();
+        config.set_maintenance(enabled, reason);
+        config.store(&self.dirs.app_config)?;
+
+        tracing::info!(enabled, "maintenance mode changed via API");
+        Ok(serde_json::json!({
+            "previous": previous,
+            "maintenance": config.maintenance,
+        }))
+    }
+
+    /// Recovers the unfrozen stake from the elector
+    /// (sends ticktock for DePool, which recovers the stake itself).
+    ///
+    /// Follows the manager send path: messages are only logged in the dry-run mode
+    /// and are not sent at all in the maintenance mode.
+    pub async fn recover_stake(&self) -> Result<serde_json::Value> {
+        let subscription = self.subscription.load_full().context(ApiError::NotReady)?;
+        let config = self.load_config()?;
+        if config.maintenance.is_some() {
+            return Err(ApiError::Maintenance.into());
+        }
+
+        let params = match config.validator.context(ApiError::NotConfigured)? {
+            AppConfigValidator::Single(params) => params,
+            AppConfigValidator::DePool(params) => {
+                if self.dry_run {
+                    tracing::info!(depool = %params.depool, "dry run: ticktock was not sent");
+                } else {
+                    send_ticktock(&params, &self.dirs, &subscription, &self.guard).await?;
+                    tracing::info!("sent ticktock via API");
+                }
+                return Ok(serde_json::json!({
+                    "ticktock": !self.dry_run,
+                    "dry_run": self.dry_run,
+                }));
+            }
+        };
+
+        let elector_address = subscription.get_system_addresses().await?.elector;
+        let elector = Elector::new(elector_address, subscription.clone());
+        let elector_data = elector.get_data().await?;
+
+        let Some(stake) = elector_data.has_unfrozen_stake(&params.address) else {
+            return Ok(serde_json::json!({ "recovered": null }));
+        };
+        let stake = stake.as_u128();
+
+        let keypair = self.dirs.load_validator_keys()?;
+        let wallet = Wallet::new(
+            params.address.workchain_id() as i8,
+            keypair,
+            subscription.clone(),
+        );
+        anyhow::ensure!(
+            wallet.address() == &params.address,
+            "validator wallet address mismatch"
+        );
+
+        // Prevent shutdown during stake recovery
+        let _guard = self.guard.lock().await;
+        let message = elector.recover_stake()?;
+        send_message(&wallet, message, "recover stake", self.dry_run).await?;
+        if self.dry_run {
+            return Ok(serde_json::json!({
+                "recovered": null,
+                "stake": Tokens(stake).to_string(),
+                "dry_run": true,
+            }));
+        }
+
+        tracing::info!(stake = %Tokens(stake), "recovered stake via API");
+        self.notifier
+            .notify(Event::StakeRecovered { amount: stake });
+        Ok(serde_json::json!({
+            "recovered": Tokens(stake).to_string(),
+            "dry_run": false,
+        }))
+    }
+
+    /// Returns the latest manager events (newest first)
+    pub fn events(&self) -> serde_json::Value {
+        let events = self
+            .notifier
+            .recent_events()
+            .into_iter()
+            .rev()
+            .map(|record| {
+                // NOTE: event is serialized as `{ "event": name, "data": ... }`
+                let mut entry = serde_json::json!(record.event);
+                if let Some(entry) = entry.as_object_mut() {
+                    entry.insert("id".to_owned(), record.id.into());
+                    entry.insert("timestamp".to_owned(), record.timestamp.into());
+                    let severity = record.event.severity().as_str();
+                    entry.insert("severity".to_owned(), severity.into());
+                    let text = self.notifier.render(&record.event);
+                    entry.insert("text".to_owned(), text.into());
+                }
+                entry
+            })
+            .collect::<Vec<_>>();
+        serde_json::json!({ "events": events })
+    }
+
+    /// Renders the latest manager events as an RSS 2.0 feed
+    pub fn events_rss(&self) -> String {
+        let host = sysinfo::System::host_name().unwrap_or_default();
+
+        let mut items = String::new();
+        for record in self.notifier.recent_events().into_iter().rev() {
+            let event = &record.event;
+            items += &format!(
+                "<item><title>{}</title><description>{}</description>\
+                <category>{}</category><pubDate>{}</pubDate>\
+                <guid isPermaLink=\"false\">{}-{}-{}</guid></item>",
+                xml_escape(&format!("[{}] {}", event.severity().as_str(), event.name())),
+                xml_escape(&self.notifier.render(event)),
+                event.name(),
+                format_rfc2822(record.timestamp),
+                xml_escape(&host),
+                record.timestamp,
+                record.id,
+            );
+        }
+
+        format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\
+            <rss version=\"2.0\"><channel>\
+            <title>nodekeeper events on {}</title>\
+            <link>http://localhost/</link>\
+            <description>Validation manager events</description>\
+            {items}</channel></rss>",
+            xml_escape(&host),
+        )
+    }
+
+    fn load_config(&self) -> Result<AppConfig> {
+        AppConfig::load(&self.dirs.app_config)
+    }
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Formats the timestamp as an RFC 2822 date (e.g. `Thu, 01 Jan 1970 00:00:00 +0000`)
+fn format_rfc2822(timestamp: u32) -> String {
+    const WEEKDAYS: [&str; 7] = ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"];
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+
+    let days = timestamp / 86400;
+    let seconds = timestamp % 86400;
+    let (year, month, day) = civil_from_days(days as i64);
+    format!(
+        "{}, {day:02} {} {year} {:02}:{:02}:{:02} +0000",
+        WEEKDAYS[(days % 7) as usize],
+        MONTHS[month as usize - 1],
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60,
+    )
+}
+
+/// Collects the validator node status
+pub async fn node_status(
+    config: &AppConfig,
+    node_tcp_rpc: &NodeTcpRpc,
+    blockchain_config: &ton_block::ConfigParams,
+) -> Result<serde_json::Value> {
+    let stats = node_tcp_rpc.get_stats().await?.try_into_running()?;
+
+    // Resolve system contracts with the configured overrides
+    let network = config.network.clone().unwrap_or_default();
+    let addresses = network.system_addresses(blockchain_config)?;
+
+    // NOTE: session stats are optional and must not break the status
+    let sessions = match node_tcp_rpc.get_sessions_stats().await {
+        Ok(sessions) => Some(sessions),
+        Err(e) => {
+            tracing::debug!("failed to get validator sessions stats: {e:?}");
+            None
+        }
+    };
+
+    Ok(serde_json::json!({
+        "maintenance": config.maintenance,
+        "in_current_vset": stats.in_current_vset,
+        "in_next_vset": stats.in_next_vset,
+        "mc_time_diff": stats.mc_time_diff,
+        "sessions": sessions,
+        "node_role": config.node_role(),
+        "network": {
+            "elector": format!("-1:{}", addresses.elector.to_hex_string()),
+            "config": format!("-1:{}", addresses.config.to_hex_string()),
+            "minter": format!("-1:{}", addresses.minter.to_hex_string()),
+            "decimals": config.decimals(),
+        },
+    }))
+}
+
+/// Elections params and bounds of the validation rounds
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoundTimings {
+    pub elections_start_before: u32,
+    pub elections_end_before: u32,
+    pub stake_held_for: u32,
+    pub validators_elected_for: u32,
+    pub current_round: (u32, u32),
+    pub prev_round: Option<(u32, u32)>,
+}
+
+impl RoundTimings {
+    pub fn new(config: &ton_block::ConfigParams) -> Result<Self> {
+        let timings = config.elector_params().context("invalid elector params")?;
+        let current_vset = config.validator_set().context("invalid validator set")?;
+        let prev_round = if config.prev_validator_set_present()? {
+            let prev_vset = config.prev_validator_set()?;
+            Some((prev_vset.utime_since(), prev_vset.utime_until()))
+        } else {
+            None
+        };
+
+        Ok(Self {
+            elections_start_before: timings.elections_start_before,
+            elections_end_before: timings.elections_end_before,
+            stake_held_for: timings.stake_held_for,
+            validators_elected_for: timings.validators_elected_for,
+            current_round: (current_vset.utime_since(), current_vset.utime_until()),
+            prev_round,
+        })
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ApiError {
+    #[error("manager is not ready")]
+    NotReady,
+    #[error("validator is not configured")]
+    NotConfigured,
+    #[error("actions are disabled in the maintenance mode")]
+    Maintenance,
+}
@@ -0,0 +1,274 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::UnixStream;
+use tokio_util::sync::{CancellationToken, DropGuard};
+
+use super::http::bind_unix_socket;
+use super::ApiState;
+
+/// JSON-RPC over the unix socket for the local tooling.
+///
+/// Each line is a separate request or response.
+pub struct ControlSocket {
+    path: PathBuf,
+    _cancellation_guard: DropGuard,
+}
+
+impl ControlSocket {
+    pub fn spawn(path: PathBuf, state: Arc<ApiState>) -> Result<Self> {
+        let listener = bind_unix_socket(&path).context("failed to bind control socket")?;
+
+        let cancellation_token = CancellationToken::new();
+        tokio::spawn({
+            let cancellation_token = cancellation_token.clone();
+            async move {
+                let accept_loop = async {
+                    loop {
+                        let stream = match listener.accept().await {
+                            Ok((stream, _)) => stream,
+                            Err(e) => {
+                                tracing::warn!("failed to accept control connection: {e:?}");
+                                tokio::time::sleep(Duration::from_secs(1)).await;
+                                continue;
+                            }
+                        };
+
+                        let state = state.clone();
+                        tokio::spawn(async move {
+                            if let Err(e) = handle_connection(stream, &state).await {
+                                tracing::debug!("control connection failed: {e:?}");
+                            }
+                        });
+                    }
+                };
+
+                tokio::select! {
+                    _ = accept_loop => {},
+                    _ = cancellation_token.cancelled() => {},
+                }
+            }
+        });
+
+        tracing::info!(path = %path.display(), "started control socket");
+
+        Ok(Self {
+            path,
+            _cancellation_guard: cancellation_token.drop_guard(),
+        })
+    }
+}
+
+impl Drop for ControlSocket {
+    fn drop(&mut self) {
+        // NOTE: the stale socket makes the CLI wait for the connection timeout
+        std::fs::remove_file(&self.path).ok();
+    }
+}
+
+/// Client for the control socket of the running manager
+pub struct ManagerClient {
+    stream: BufReader<UnixStream>,
+    next_id: u64,
+}
+
+impl ManagerClient {
+    /// Connects to the running manager, returns `None` if it is not running
+    pub async fn connect(path: &Path) -> Option<Self> {
+        const CONNECT_TIMEOUT: Duration = Duration::from_secs(1);
+
+        if !path.exists() {
+            return None;
+        }
+
+        match tokio::time::timeout(CONNECT_TIMEOUT, UnixStream::connect(path)).await {
+            Ok(Ok(stream)) => Some(Self {
+                stream: BufReader::new(stream),
+                next_id: 0,
+            }),
+            Ok(Err(e)) => {
+                tracing::debug!("failed to connect to the manager: {e:?}");
+                None
+            }
+            Err(_) => {
+                tracing::debug!("manager connection timeout");
+                None
+            }
+        }
+    }
+
+    pub async fn status(&mut self) -> Result<serde_json::Value> {
+        self.call("status", serde_json::json!({})).await
+    }
+
+    pub async fn elections(&mut self) -> Result<serde_json::Value> {
+        self.call("elections", serde_json::json!({})).await
+    }
+
+    pub async fn set_maintenance(
+        &mut self,
+        enabled: bool,
+        reason: Option<String>,
+    ) -> Result<serde_json::Value> {
+        let params = MaintenanceParams { enabled, reason };
+        self.call("maintenance", serde_json::to_value(params)?)
+            .await
+    }
+
+    async fn call<T: DeserializeOwned>(
+        &mut self,
+        method: &str,
+        params: serde_json::Value,
+    ) -> Result<T> {
+        const CALL_TIMEOUT: Duration = Duration::from_secs(60);
+
+        self.next_id += 1;
+        let id = self.next_id;
+
+        let mut request = serde_json::to_vec(&serde_json::json!({
+            "jsonrpc": JSONRPC_VERSION,
+            "id": id,
+            "method": method,
+            "params": params,
+        }))?;
+        request.push(b'\n');
+
+        let response = tokio::time::timeout(CALL_TIMEOUT, async {
+            self.stream.get_mut().write_all(&request).await?;
+            let mut line = String::new();
+            self.stream.read_line(&mut line).await?;
+            anyhow::ensure!(!line.is_empty(), "manager closed the connection");
+            Ok::<_, anyhow::Error>(line)
+        })
+        .await
+        .context("manager request timeout")??;
+
+        let response =
+            serde_json::from_str::<Response>(&response).context("invalid manager response")?;
+        anyhow::ensure!(response.id == Some(id), "manager response id mismatch");
+
+        match (response.result, response.error) {
+            (_, Some(error)) => anyhow::bail!("manager request failed: {}", error.message),
+            (Some(result), None) => Ok(serde_json::from_value(result)?),
+            (None, None) => anyhow::bail!("invalid manager response"),
+        }
+    }
+}
+
+async fn handle_connection(stream: UnixStream, state: &ApiState) -> Result<()> {
+    const MAX_REQUEST_LEN: u64 = 64 << 10;
+
+    let (reader, mut writer) = stream.into_split();
+    let mut reader = BufReader::new(reader);
+
+    let mut line = String::new();
+    loop {
+        line.clear();
+        (&mut reader)
+            .take(MAX_REQUEST_LEN)
+            .read_line(&mut line)
+            .await?;
+        if line.is_empty() {
+            return Ok(());
+        }
+        anyhow::ensure!(line.ends_with('\n'), "request is too large");
+
+        let response = match serde_json::from_str::<Request>(&line) {
+            Ok(request) => {
+                let (result, error) =
+                    match handle_request(state, request.method, request.params).await {
+                        Ok(result) => (Some(result), None),
+                        Err(error) => (None, Some(error)),
+                    };
+                Response {
+                    jsonrpc: JSONRPC_VERSION.to_owned(),
+                    id: request.id,
+                    result,
+                    error,
+                }
+            }
+            Err(e) => Response {
+                jsonrpc: JSONRPC_VERSION.to_owned(),
+                id: None,
+                result: None,
+                error: Some(Error::new(PARSE_ERROR, e.to_string())),
+            },
+        };
+
+        let mut response = serde_json::to_vec(&response)?;
+        response.push(b'\n');
+        writer.write_all(&response).await?;
+    }
+}
+
+async fn handle_request(
+    state: &ApiState,
+    method: String,
+    params: serde_json::Value,
+) -> Result<serde_json::Value, Error> {
+    let res = match method.as_str() {
+        "status" => state.status().await,
+        "elections" => state.elections().await,
+        "maintenance" => {
+            let MaintenanceParams { enabled, reason } = serde_json::from_value(params)
+                .map_err(|e| Error::new(INVALID_PARAMS, e.to_string()))?;
+            state.set_maintenance(enabled, reason)
+        }
+        _ => return Err(Error::new(METHOD_NOT_FOUND, "method not found")),
+    };
+
+    res.map_err(|e| Error::new(INTERNAL_ERROR, format!("{e:#}")))
+}
+
+const JSONRPC_VERSION: &str = "2.0";
+
+const PARSE_ERROR: i32 = -32700;
+const METHOD_NOT_FOUND: i32 = -32601;
+const INVALID_PARAMS: i32 = -32602;
+const INTERNAL_ERROR: i32 = -32603;
+
+#[derive(Deserialize)]
+struct Request {
+    #[serde(default)]
+    id: Option<u64>,
+    method: String,
+    #[serde(default)]
+    params: serde_json::Value,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Response {
+    jsonrpc: String,
+    id: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    result: Option<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    error: Option<Error>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Error {
+    code: i32,
+    message: String,
+}
+
+impl Error {
+    fn new(code: i32, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct MaintenanceParams {
+    enabled: bool,
+    #[serde(default)]
+    reason: Option<String>,
+}
@@ -0,0 +1,325 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use broxus_util::now;
+use ctr::cipher::{KeyIvInit, StreamCipher};
+use hmac::{Mac, NewMac};
+use rand::Rng;
+use tokio::process::Command;
+
+use self::s3::S3Storage;
+use self::sftp::SftpStorage;
+use crate::config::{AppConfigBackup, BackupDestination};
+use crate::dirs::ProjectDirs;
+use crate::util::civil_from_days;
+
+mod s3;
+mod sftp;
+
+const PREFIX: &str = "backup-";
+const ENCRYPTED_EXTENSION: &str = ".tar.gz.enc";
+
+/// Remote storage for the encrypted backups
+#[async_trait::async_trait]
+trait BackupStorage: Send + Sync {
+    async fn upload(&self, name: &str, data: Vec<u8>) -> Result<()>;
+
+    /// Returns names of all stored objects
+    async fn list(&self) -> Result<Vec<String>>;
+
+    async fn remove(&self, name: &str) -> Result<()>;
+}
+
+/// Copies keys and configs into a new timestamped directory and removes old backups
+pub fn make_local_backup(dirs: &ProjectDirs, dir: &Path, keep: usize) -> Result<PathBuf> {
+    let target = dir.join(backup_name(now()));
+
+    create_private_dir(&target)?;
+    for path in dirs.state_files() {
+        if !path.exists() {
+            continue;
+        }
+
+        let name = path
+            .strip_prefix(&dirs.root)
+            .with_context(|| format!("{} is outside of the root dir", path.display()))?;
+        let dst = target.join(name);
+        if let Some(parent) = dst.parent() {
+            create_private_dir(parent)?;
+        }
+        std::fs::copy(path, &dst).with_context(|| format!("failed to copy {}", path.display()))?;
+    }
+    tracing::info!(path = %target.display(), "created backup");
+
+    // Remove the oldest backups
+    let backups = std::fs::read_dir(dir)
+        .context("failed to list backups")?
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            let name = path.file_name()?.to_str()?;
+            (name.starts_with(PREFIX) && path.is_dir()).then_some(path)
+        })
+        .collect::<Vec<PathBuf>>();
+
+    for path in outdated(backups, keep) {
+        std::fs::remove_dir_all(&path)
+            .with_context(|| format!("failed to remove backup {}", path.display()))?;
+        tracing::info!(path = %path.display(), "removed old backup");
+    }
+
+    Ok(target)
+}
+
+/// Uploads an encrypted archive with keys and configs and removes old backups.
+///
+/// Returns the name of the uploaded backup.
+pub async fn make_remote_backup(
+    dirs: &ProjectDirs,
+    config: &AppConfigBackup,
+    destination: &str,
+    keep: usize,
+) -> Result<String> {
+    let passphrase = config
+        .passphrase
+        .as_ref()
+        .context("backup passphrase is required for remote destinations")?;
+    let storage: Box<dyn BackupStorage> = match config.destination(destination)? {
+        BackupDestination::S3 {
+            endpoint,
+            region,
+            bucket,
+            prefix,
+            access_key_id,
+            secret_access_key,
+            ..
+        } => Box::new(S3Storage::new(
+            endpoint.clone(),
+            region.clone(),
+            bucket.clone(),
+            prefix.clone(),
+            access_key_id.clone(),
+            secret_access_key.as_str().to_owned(),
+        )),
+        BackupDestination::Sftp {
+            target,
+            port,
+            identity_file,
+            ..
+        } => Box::new(SftpStorage::new(
+            target,
+            *port,
+            identity_file.clone(),
+            dirs.root.clone(),
+        )?),
+    };
+
+    let archive = make_archive(dirs).await?;
+    let data = encrypt(&archive, passphrase);
+
+    let name = format!("{}{ENCRYPTED_EXTENSION}", backup_name(now()));
+    storage
+        .upload(&name, data)
+        .await
+        .context("failed to upload backup")?;
+    tracing::info!(destination, name, "uploaded backup");
+
+    // Remove the oldest backups
+    let backups = storage
+        .list()
+        .await
+        .context("failed to list backups")?
+        .into_iter()
+        .filter(|name| name.starts_with(PREFIX) && name.ends_with(ENCRYPTED_EXTENSION))
+        .collect();
+
+    for name in outdated(backups, keep) {
+        storage
+            .remove(&name)
+            .await
+            .with_context(|| format!("failed to remove backup {name}"))?;
+        tracing::info!(destination, name, "removed old backup");
+    }
+
+    Ok(name)
+}
+
+/// Encrypts the data with the key derived from the passphrase (AES-256-CTR + HMAC-SHA256)
+pub fn encrypt(data: &[u8], passphrase: &str) -> Vec<u8> {
+    let mut rng = rand::thread_rng();
+    let salt: [u8; SALT_LEN] = rng.gen();
+    let iv: [u8; IV_LEN] = rng.gen();
+    let (enc_key, mac_key) = derive_keys(passphrase, &salt);
+
+    let mut result = Vec::with_capacity(HEADER_LEN + data.len() + MAC_LEN);
+    result.extend_from_slice(MAGIC);
+    result.extend_from_slice(&salt);
+    result.extend_from_slice(&iv);
+    result.extend_from_slice(data);
+    make_cipher(&enc_key, &iv).apply_keystream(&mut result[HEADER_LEN..]);
+
+    let mac = make_mac(&mac_key, &result);
+    result.extend_from_slice(&mac);
+    result
+}
+
+/// Decrypts the data produced by [`encrypt`]
+pub fn decrypt(data: &[u8], passphrase: &str) -> Result<Vec<u8>> {
+    anyhow::ensure!(
+        data.len() >= HEADER_LEN + MAC_LEN && data.starts_with(MAGIC),
+        "invalid encrypted backup"
+    );
+
+    let (data, mac) = data.split_at(data.len() - MAC_LEN);
+    let salt = &data[MAGIC.len()..MAGIC.len() + SALT_LEN];
+    let iv: [u8; IV_LEN] = data[MAGIC.len() + SALT_LEN..HEADER_LEN].try_into().unwrap();
+    let (enc_key, mac_key) = derive_keys(passphrase, salt);
+
+    let mut verifier = hmac::Hmac::<sha2::Sha256>::new_from_slice(&mac_key).unwrap();
+    verifier.update(data);
+    verifier
+        .verify(mac)
+        .map_err(|_| anyhow::anyhow!("invalid passphrase or corrupted backup"))?;
+
+    let mut result = data[HEADER_LEN..].to_vec();
+    make_cipher(&enc_key, &iv).apply_keystream(&mut result);
+    Ok(result)
+}
+
+/// Packs state files into a gzipped tar archive using the local `tar` binary
+async fn make_archive(dirs: &ProjectDirs) -> Result<Vec<u8>> {
+    let mut command = Command::new("tar");
+    command.arg("-C").arg(&dirs.root).arg("-czf").arg("-");
+
+    let mut empty = true;
+    for path in dirs.state_files() {
+        if !path.exists() {
+            continue;
+        }
+        let name = path
+            .strip_prefix(&dirs.root)
+            .with_context(|| format!("{} is outside of the root dir", path.display()))?;
+        command.arg(name);
+        empty = false;
+    }
+    anyhow::ensure!(!empty, "nothing to backup");
+
+    let output = command.output().await.context("failed to run tar")?;
+    anyhow::ensure!(
+        output.status.success(),
+        "failed to create backup archive: {}",
+        String::from_utf8_lossy(&output.stderr).trim()
+    );
+    Ok(output.stdout)
+}
+
+/// Returns items which exceed the retention limit (sorted names are sorted by time)
+fn outdated<T: Ord>(mut backups: Vec<T>, keep: usize) -> Vec<T> {
+    backups.sort();
+    let outdated = backups.len().saturating_sub(std::cmp::max(keep, 1));
+    backups.truncate(outdated);
+    backups
+}
+
+fn backup_name(timestamp: u32) -> String {
+    let (year, month, day) = civil_from_days((timestamp / 86400) as i64);
+    let seconds = timestamp % 86400;
+    format!(
+        "{PREFIX}{year}{month:02}{day:02}-{:02}{:02}{:02}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+fn create_private_dir(path: &Path) -> Result<()> {
+    use std::os::unix::fs::DirBuilderExt;
+
+    std::fs::DirBuilder::new()
+        .recursive(true)
+        .mode(0o700)
+        .create(path)
+        .with_context(|| format!("failed to create {}", path.display()))
+}
+
+fn derive_keys(passphrase: &str, salt: &[u8]) -> ([u8; 32], [u8; 32]) {
+    let mut keys = [0; 64];
+    pbkdf2::pbkdf2::<hmac::Hmac<sha2::Sha256>>(
+        passphrase.as_bytes(),
+        salt,
+        PBKDF_ITERATIONS,
+        &mut keys,
+    );
+    (
+        keys[..32].try_into().unwrap(),
+        keys[32..].try_into().unwrap(),
+    )
+}
+
+fn make_cipher(key: &[u8; 32], iv: &[u8; IV_LEN]) -> Aes256Ctr {
+    Aes256Ctr::new(
+        &generic_array::GenericArray::from(*key),
+        &generic_array::GenericArray::from(*iv),
+    )
+}
+
+fn make_mac(key: &[u8; 32], data: &[u8]) -> [u8; MAC_LEN] {
+    let mut mac = hmac::Hmac::<sha2::Sha256>::new_from_slice(key).unwrap();
+    mac.update(data);
+    mac.finalize().into_bytes().into()
+}
+
+type Aes256Ctr = ctr::Ctr128BE<aes::Aes256>;
+
+const MAGIC: &[u8] = b"NKBACKUP1";
+const SALT_LEN: usize = 16;
+const IV_LEN: usize = 16;
+const MAC_LEN: usize = 32;
+const HEADER_LEN: usize = MAGIC.len() + SALT_LEN + IV_LEN;
+const PBKDF_ITERATIONS: u32 = 100_000;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PASSPHRASE: &str = "correct horse battery staple";
+
+    #[test]
+    fn encryption_round_trip() {
+        let data = b"validator keys and configs".repeat(100);
+        let encrypted = encrypt(&data, PASSPHRASE);
+        assert_eq!(encrypted.len(), HEADER_LEN + data.len() + MAC_LEN);
+        assert!(encrypted.starts_with(MAGIC));
+        assert_ne!(&encrypted[HEADER_LEN..HEADER_LEN + data.len()], &data[..]);
+        assert_eq!(decrypt(&encrypted, PASSPHRASE).unwrap(), data);
+
+        // Salt and IV are random
+        assert_ne!(encrypt(&data, PASSPHRASE), encrypted);
+
+        let empty = encrypt(&[], PASSPHRASE);
+        assert!(decrypt(&empty, PASSPHRASE).unwrap().is_empty());
+    }
+
+    #[test]
+    fn tampered_backup_is_rejected() {
+        let encrypted = encrypt(b"validator keys", PASSPHRASE);
+
+        assert!(decrypt(&encrypted, "wrong passphrase").is_err());
+
+        // Ciphertext, header and MAC are authenticated
+        for offset in [
+            0,
+            HEADER_LEN,
+            MAGIC.len(),
+            MAGIC.len() + SALT_LEN,
+            encrypted.len() - 1,
+        ] {
+            let mut tampered = encrypted.clone();
+            tampered[offset] ^= 0x01;
+            assert!(decrypt(&tampered, PASSPHRASE).is_err(), "offset {offset}");
+        }
+
+        assert!(decrypt(&encrypted[..encrypted.len() - 1], PASSPHRASE).is_err());
+        assert!(decrypt(&encrypted[..HEADER_LEN], PASSPHRASE).is_err());
+        assert!(decrypt(b"", PASSPHRASE).is_err());
+    }
+}
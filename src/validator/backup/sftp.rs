@@ -0,0 +1,119 @@
+use std::path::PathBuf;
+use std::process::Stdio;
+
+use anyhow::{Context, Result};
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+use super::BackupStorage;
+
+/// Remote directory accessed with the local `sftp` binary in batch mode
+pub struct SftpStorage {
+    host: String,
+    dir: String,
+    port: Option<u16>,
+    identity_file: Option<PathBuf>,
+    tmp_dir: PathBuf,
+}
+
+impl SftpStorage {
+    /// Creates storage for the `user@host:/path` target
+    pub fn new(
+        target: &str,
+        port: Option<u16>,
+        identity_file: Option<PathBuf>,
+        tmp_dir: PathBuf,
+    ) -> Result<Self> {
+        let (host, dir) = target
+            .split_once(':')
+            .context("invalid sftp target, expected `user@host:/path`")?;
+        anyhow::ensure!(!host.is_empty(), "sftp host is empty");
+
+        let dir = dir.trim_end_matches('/');
+        Ok(Self {
+            host: host.to_owned(),
+            dir: if dir.is_empty() { "." } else { dir }.to_owned(),
+            port,
+            identity_file,
+            tmp_dir,
+        })
+    }
+
+    /// Runs the batch of sftp commands and returns its stdout
+    async fn run(&self, batch: String) -> Result<String> {
+        let mut command = Command::new("sftp");
+        command.args(["-b", "-", "-o", "BatchMode=yes"]);
+        if let Some(port) = self.port {
+            command.arg("-P").arg(port.to_string());
+        }
+        if let Some(identity_file) = &self.identity_file {
+            command.arg("-i").arg(identity_file);
+        }
+
+        let mut child = command
+            .arg(&self.host)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .context("failed to run sftp")?;
+
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(batch.as_bytes()).await?;
+        }
+
+        let output = child.wait_with_output().await?;
+        anyhow::ensure!(
+            output.status.success(),
+            "sftp failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+}
+
+#[async_trait::async_trait]
+impl BackupStorage for SftpStorage {
+    async fn upload(&self, name: &str, data: Vec<u8>) -> Result<()> {
+        use std::io::Write;
+        use std::os::unix::fs::OpenOptionsExt;
+
+        let tmp = self.tmp_dir.join(format!(".{name}"));
+        std::fs::OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .mode(0o600)
+            .open(&tmp)
+            .and_then(|mut file| file.write_all(&data))
+            .context("failed to write temp backup file")?;
+
+        let result = self
+            .run(format!(
+                "put \"{}\" \"{}/{name}\"\n",
+                tmp.display(),
+                self.dir
+            ))
+            .await;
+        std::fs::remove_file(&tmp).ok();
+        result.map(|_| ())
+    }
+
+    async fn list(&self) -> Result<Vec<String>> {
+        let output = self.run(format!("ls -1 \"{}\"\n", self.dir)).await?;
+        Ok(output
+            .lines()
+            // Skip echoed commands
+            .filter(|line| !line.starts_with("sftp>"))
+            .filter_map(|line| line.trim().rsplit('/').next())
+            .filter(|name| !name.is_empty())
+            .map(ToOwned::to_owned)
+            .collect())
+    }
+
+    async fn remove(&self, name: &str) -> Result<()> {
+        self.run(format!("rm \"{}/{name}\"\n", self.dir))
+            .await
+            .map(|_| ())
+    }
+}
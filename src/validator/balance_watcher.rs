@@ -0,0 +1,164 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use anyhow::Result;
+use tokio_util::sync::{CancellationToken, DropGuard};
+
+use crate::config::{AppConfigBalanceAlerts, AppConfigValidator};
+use crate::network::Subscription;
+use crate::notifications::{Event, Notifier};
+use crate::util::Tokens;
+
+/// Background task which watches the validator wallet and DePool balances
+pub struct BalanceWatcher {
+    params: AppConfigBalanceAlerts,
+    validator: AppConfigValidator,
+    checker: Arc<BalanceChecker>,
+    _cancellation_guard: DropGuard,
+}
+
+impl BalanceWatcher {
+    pub fn spawn(
+        params: &AppConfigBalanceAlerts,
+        validator: &AppConfigValidator,
+        subscription: Arc<Subscription>,
+        notifier: Notifier,
+    ) -> Self {
+        let mut accounts = Vec::with_capacity(2);
+        match validator {
+            AppConfigValidator::Single(single) => {
+                accounts.push(WatchedAccount::new(
+                    "wallet",
+                    single.address.clone(),
+                    params.wallet_min_balance,
+                ));
+            }
+            AppConfigValidator::DePool(depool) => {
+                accounts.push(WatchedAccount::new(
+                    "wallet",
+                    depool.owner.clone(),
+                    params.wallet_min_balance,
+                ));
+                accounts.push(WatchedAccount::new(
+                    "depool",
+                    depool.depool.clone(),
+                    params.depool_min_balance,
+                ));
+            }
+        }
+
+        let checker = Arc::new(BalanceChecker {
+            subscription,
+            notifier,
+            accounts,
+        });
+
+        let cancellation_token = CancellationToken::new();
+
+        tokio::spawn({
+            let checker = checker.clone();
+            let interval = params.interval;
+            let cancellation_token = cancellation_token.clone();
+            async move {
+                let mut interval = tokio::time::interval(interval);
+                let run = async {
+                    loop {
+                        interval.tick().await;
+                        if let Err(e) = checker.check().await {
+                            tracing::warn!("failed to check balances: {e:?}");
+                        }
+                    }
+                };
+                tokio::select! {
+                    _ = run => {},
+                    _ = cancellation_token.cancelled() => {},
+                }
+            }
+        });
+
+        tracing::info!("started balance watcher");
+
+        Self {
+            params: params.clone(),
+            validator: validator.clone(),
+            checker,
+            _cancellation_guard: cancellation_token.drop_guard(),
+        }
+    }
+
+    pub fn matches(&self, params: &AppConfigBalanceAlerts, validator: &AppConfigValidator) -> bool {
+        &self.params == params && &self.validator == validator
+    }
+
+    /// Returns `true` if bidding must be paused due to low balances
+    pub async fn should_pause_bidding(&self) -> Result<bool> {
+        Ok(self.params.pause_bidding && self.checker.check().await?)
+    }
+}
+
+struct BalanceChecker {
+    subscription: Arc<Subscription>,
+    notifier: Notifier,
+    accounts: Vec<WatchedAccount>,
+}
+
+impl BalanceChecker {
+    /// Returns `true` if any of the balances is below the threshold
+    async fn check(&self) -> Result<bool> {
+        let mut any_low = false;
+        for account in &self.accounts {
+            let balance = self
+                .subscription
+                .get_account_state(&account.address)
+                .await?
+                .map(|state| state.storage.balance.grams.as_u128())
+                .unwrap_or_default();
+
+            let is_low = balance < account.min_balance;
+            any_low |= is_low;
+
+            // NOTE: alert only once until the balance is replenished
+            let was_low = account.is_low.swap(is_low, Ordering::Relaxed);
+            if is_low && !was_low {
+                tracing::warn!(
+                    account = account.name,
+                    address = %account.address,
+                    balance = %Tokens(balance),
+                    min_balance = %Tokens(account.min_balance),
+                    "balance is below the threshold"
+                );
+                self.notifier.notify(Event::LowBalance {
+                    address: account.address.to_string(),
+                    balance,
+                    required: account.min_balance,
+                });
+            } else if !is_low && was_low {
+                tracing::info!(
+                    account = account.name,
+                    address = %account.address,
+                    balance = %Tokens(balance),
+                    "balance was replenished"
+                );
+            }
+        }
+        Ok(any_low)
+    }
+}
+
+struct WatchedAccount {
+    name: &'static str,
+    address: ton_block::MsgAddressInt,
+    min_balance: u128,
+    is_low: AtomicBool,
+}
+
+impl WatchedAccount {
+    fn new(name: &'static str, address: ton_block::MsgAddressInt, min_balance: u64) -> Self {
+        Self {
+            name,
+            address,
+            min_balance: min_balance as u128,
+            is_low: Default::default(),
+        }
+    }
+}
@@ -0,0 +1,63 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use broxus_util::serde_string;
+use serde::{Deserialize, Serialize};
+
+/// Election bids sent by the manager
+pub struct BidHistory {
+    path: PathBuf,
+}
+
+impl BidHistory {
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+        }
+    }
+
+    /// Reads all recorded bids
+    pub fn load(&self) -> Result<Vec<BidEntry>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let data = std::fs::read_to_string(&self.path).context("failed to read bids")?;
+        data.lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| serde_json::from_str(line).context("invalid bid entry"))
+            .collect()
+    }
+
+    /// Appends a new bid to the history
+    pub fn append(&self, entry: &BidEntry) -> Result<()> {
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .context("failed to open bids file")?;
+        writeln!(file, "{}", serde_json::to_string(entry)?)?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BidEntry {
+    /// Elections the bid was sent to
+    pub election_id: u32,
+    /// Unix timestamp when the bid was sent
+    pub sent_at: u32,
+    /// Address of the elections participant (wallet or proxy)
+    #[serde(with = "serde_string")]
+    pub participant: ton_block::MsgAddressInt,
+    /// Stake accepted by the elector (zero if the bid was not accepted)
+    #[serde(with = "serde_string")]
+    pub stake: u128,
+    /// Max factor of the bid (16.16 fixed point)
+    pub max_factor: u32,
+    /// Hex encoded validator ADNL address
+    pub adnl_addr: String,
+    /// Hex encoded hash of the wallet transaction with the bid
+    pub tx_hash: String,
+}
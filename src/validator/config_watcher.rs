@@ -0,0 +1,186 @@
+use std::ffi::CString;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use tokio::sync::Notify;
+
+use crate::config::AppConfig;
+
+/// Config sections which are applied only on the manager start
+const RESTART_REQUIRED: &[&str] = &["logging", "telemetry"];
+
+/// Background thread which tracks the app config changes
+pub struct ConfigWatcher {
+    stopped: Arc<AtomicBool>,
+}
+
+impl ConfigWatcher {
+    pub fn spawn(path: PathBuf, changed: Arc<Notify>) -> Result<Self> {
+        let inotify = Inotify::new(&path)?;
+        let stopped = Arc::new(AtomicBool::new(false));
+
+        std::thread::Builder::new()
+            .name("config-watcher".to_owned())
+            .spawn({
+                let stopped = stopped.clone();
+                move || {
+                    let mut sections = load_sections(&path).unwrap_or_default();
+                    while !stopped.load(Ordering::Acquire) {
+                        match inotify.wait() {
+                            Ok(true) => {}
+                            Ok(false) => continue,
+                            Err(e) => {
+                                tracing::error!("failed to watch app config: {e:?}");
+                                break;
+                            }
+                        }
+
+                        let new_sections = match load_sections(&path) {
+                            Ok(sections) => sections,
+                            Err(e) => {
+                                tracing::error!("invalid app config: {e:?}");
+                                continue;
+                            }
+                        };
+
+                        let changes = diff_sections(&sections, &new_sections);
+                        sections = new_sections;
+                        if changes.is_empty() {
+                            continue;
+                        }
+
+                        tracing::info!(?changes, "app config changed");
+                        for section in changes {
+                            if RESTART_REQUIRED.contains(&section.as_str()) {
+                                tracing::warn!(
+                                    %section,
+                                    "app config section changes require the manager restart"
+                                );
+                            }
+                        }
+                        changed.notify_one();
+                    }
+                }
+            })
+            .context("failed to spawn config watcher")?;
+
+        tracing::info!("started app config watcher");
+
+        Ok(Self { stopped })
+    }
+}
+
+impl Drop for ConfigWatcher {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::Release);
+    }
+}
+
+type Sections = serde_json::Map<String, serde_json::Value>;
+
+fn load_sections(path: &Path) -> Result<Sections> {
+    let config = AppConfig::load(path)?;
+    match serde_json::to_value(config)? {
+        serde_json::Value::Object(sections) => Ok(sections),
+        _ => anyhow::bail!("unexpected app config structure"),
+    }
+}
+
+fn diff_sections(old: &Sections, new: &Sections) -> Vec<String> {
+    let mut changes = old
+        .keys()
+        .chain(new.keys())
+        .filter(|name| old.get(*name) != new.get(*name))
+        .cloned()
+        .collect::<Vec<_>>();
+    changes.sort_unstable();
+    changes.dedup();
+    changes
+}
+
+/// Inotify watch for the file directory (to also handle file replacements)
+struct Inotify {
+    fd: OwnedFd,
+    file_name: Vec<u8>,
+}
+
+impl Inotify {
+    /// Max time to wait for events (in milliseconds)
+    const POLL_TIMEOUT: i32 = 1000;
+
+    fn new(path: &Path) -> Result<Self> {
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        let file_name = path
+            .file_name()
+            .context("invalid app config path")?
+            .as_bytes()
+            .to_vec();
+        let dir = CString::new(dir.as_os_str().as_bytes())?;
+
+        let fd = unsafe { libc::inotify_init1(libc::IN_NONBLOCK | libc::IN_CLOEXEC) };
+        if fd < 0 {
+            return Err(std::io::Error::last_os_error()).context("failed to init inotify");
+        }
+        let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+
+        let mask = libc::IN_CLOSE_WRITE | libc::IN_MOVED_TO | libc::IN_CREATE;
+        if unsafe { libc::inotify_add_watch(fd.as_raw_fd(), dir.as_ptr(), mask) } < 0 {
+            return Err(std::io::Error::last_os_error()).context("failed to watch app config");
+        }
+
+        Ok(Self { fd, file_name })
+    }
+
+    /// Waits for the next events, returns whether the file was changed
+    fn wait(&self) -> Result<bool> {
+        const EVENT_SIZE: usize = std::mem::size_of::<libc::inotify_event>();
+
+        let mut pollfd = libc::pollfd {
+            fd: self.fd.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        if unsafe { libc::poll(&mut pollfd, 1, Self::POLL_TIMEOUT) } <= 0 {
+            // NOTE: errors are most likely caused by signals
+            return Ok(false);
+        }
+
+        let mut buffer = [0u8; 4096];
+        let len = unsafe {
+            libc::read(
+                self.fd.as_raw_fd(),
+                buffer.as_mut_ptr() as *mut libc::c_void,
+                buffer.len(),
+            )
+        };
+        if len < 0 {
+            let e = std::io::Error::last_os_error();
+            return match e.kind() {
+                std::io::ErrorKind::WouldBlock | std::io::ErrorKind::Interrupted => Ok(false),
+                _ => Err(e).context("failed to read inotify events"),
+            };
+        }
+
+        let buffer = &buffer[..len as usize];
+        let mut changed = false;
+        let mut offset = 0;
+        while offset + EVENT_SIZE <= buffer.len() {
+            let event = unsafe {
+                std::ptr::read_unaligned(buffer.as_ptr().add(offset) as *const libc::inotify_event)
+            };
+            let name_start = offset + EVENT_SIZE;
+            let name_end = std::cmp::min(name_start + event.len as usize, buffer.len());
+            let name = buffer[name_start..name_end].split(|&b| b == 0).next();
+            changed |= name == Some(self.file_name.as_slice());
+            offset = name_end;
+        }
+        Ok(changed)
+    }
+}
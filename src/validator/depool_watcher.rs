@@ -0,0 +1,250 @@
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use tokio::sync::{Mutex, Notify};
+use tokio_util::sync::{CancellationToken, DropGuard};
+
+use crate::config::{AppConfigDePoolReactions, AppConfigProxyTopUp, AppConfigValidatorDePool};
+use crate::contracts::depool::DePoolEvent;
+use crate::contracts::{DePool, Wallet, ONE_EVER};
+use crate::dirs::ProjectDirs;
+use crate::network::Subscription;
+use crate::util::Tokens;
+
+/// Background task which reacts on DePool events and keeps proxies replenished
+pub struct DePoolWatcher {
+    params: AppConfigValidatorDePool,
+    _cancellation_guard: DropGuard,
+}
+
+impl DePoolWatcher {
+    pub fn spawn(
+        params: &AppConfigValidatorDePool,
+        subscription: Arc<Subscription>,
+        dirs: &ProjectDirs,
+        wakeup: Arc<Notify>,
+        guard: Arc<Mutex<()>>,
+    ) -> Result<Self> {
+        let wallet = Wallet::new(
+            params.owner.workchain_id() as i8,
+            dirs.load_validator_keys()?,
+            subscription.clone(),
+        );
+        anyhow::ensure!(
+            wallet.address() == &params.owner,
+            "validator wallet address mismatch"
+        );
+
+        let depool = DePool::new(
+            params.depool_type,
+            params.depool.clone(),
+            subscription.clone(),
+        );
+
+        let cancellation_token = CancellationToken::new();
+
+        let handler = EventsHandler {
+            reactions: params.reactions.clone().unwrap_or_default(),
+            proxy_top_up: params.proxy_top_up.clone(),
+            subscription,
+            wallet,
+            depool,
+            wakeup,
+            guard,
+        };
+
+        tokio::spawn({
+            let cancellation_token = cancellation_token.clone();
+            async move {
+                tokio::select! {
+                    _ = handler.run() => {},
+                    _ = handler.watch_proxies() => {},
+                    _ = cancellation_token.cancelled() => {},
+                }
+            }
+        });
+
+        tracing::info!(depool = %params.depool, "started DePool events watcher");
+
+        Ok(Self {
+            params: params.clone(),
+            _cancellation_guard: cancellation_token.drop_guard(),
+        })
+    }
+
+    pub fn params(&self) -> &AppConfigValidatorDePool {
+        &self.params
+    }
+}
+
+struct EventsHandler {
+    reactions: AppConfigDePoolReactions,
+    proxy_top_up: Option<AppConfigProxyTopUp>,
+    subscription: Arc<Subscription>,
+    wallet: Wallet,
+    depool: DePool,
+    wakeup: Arc<Notify>,
+    guard: Arc<Mutex<()>>,
+}
+
+impl EventsHandler {
+    async fn run(&self) {
+        let mut transactions = self.subscription.subscribe(self.depool.address());
+
+        while let Some(tx) = transactions.recv().await {
+            let events = match DePoolEvent::parse_transaction(&tx.data) {
+                Ok(events) => events,
+                Err(e) => {
+                    tracing::warn!(tx_hash = ?tx.hash, "failed to parse DePool transaction: {e:?}");
+                    continue;
+                }
+            };
+
+            for event in events {
+                if let Err(e) = self.handle_event(event).await {
+                    tracing::error!(tx_hash = ?tx.hash, "failed to handle DePool event: {e:?}");
+                }
+            }
+        }
+    }
+
+    async fn handle_event(&self, event: DePoolEvent) -> Result<()> {
+        match event {
+            DePoolEvent::StakeSigningRequested { election_id, proxy } => {
+                tracing::info!(election_id, %proxy, "DePool requested the stake signing");
+                if self.reactions.participate_on_request {
+                    self.wakeup.notify_one();
+                }
+            }
+            DePoolEvent::TooLowDePoolBalance { replenishment } => {
+                tracing::warn!(
+                    replenishment = %Tokens(replenishment),
+                    "DePool balance is too low"
+                );
+                if self.reactions.top_up_on_low_balance {
+                    self.top_up(replenishment).await?;
+                }
+            }
+            DePoolEvent::RoundCompleted(round) => {
+                tracing::info!(
+                    round_id = round.id,
+                    completion_reason = ?round.completion_reason,
+                    stake = %Tokens(round.stake),
+                    recovered_stake = %Tokens(round.recovered_stake),
+                    participant_reward = %Tokens(round.participant_reward),
+                    "DePool round completed"
+                );
+            }
+        }
+        Ok(())
+    }
+
+    async fn watch_proxies(&self) {
+        let Some(params) = &self.proxy_top_up else {
+            return futures_util::future::pending().await;
+        };
+
+        let mut limiter = DailyLimit::new(params.daily_limit as u128);
+        let mut interval = tokio::time::interval(params.interval);
+        loop {
+            interval.tick().await;
+            if let Err(e) = self.top_up_proxies(params, &mut limiter).await {
+                tracing::error!("failed to replenish DePool proxies: {e:?}");
+            }
+        }
+    }
+
+    async fn top_up_proxies(
+        &self,
+        params: &AppConfigProxyTopUp,
+        limiter: &mut DailyLimit,
+    ) -> Result<()> {
+        let config = self.subscription.get_blockchain_config().await?;
+        let messages = self
+            .depool
+            .get_proxies_top_up(&config.config, params.min_balance as u128)
+            .await?;
+
+        for message in messages {
+            if !limiter.try_spend(message.amount) {
+                tracing::warn!(
+                    proxy = %message.dst,
+                    amount = %Tokens(message.amount),
+                    spent = %Tokens(limiter.spent()),
+                    daily_limit = %Tokens(params.daily_limit as u128),
+                    "proxy top-up daily limit exceeded"
+                );
+                continue;
+            }
+
+            self.wallet
+                .wait_for_balance(message.amount + ONE_EVER)
+                .await?;
+
+            // Prevent shutdown during the operation
+            let _guard = self.guard.lock().await;
+
+            tracing::info!(
+                proxy = %message.dst,
+                amount = %Tokens(message.amount),
+                "replenishing DePool proxy balance"
+            );
+            self.wallet
+                .call(message)
+                .await
+                .context("failed to replenish proxy balance")?;
+        }
+
+        Ok(())
+    }
+
+    async fn top_up(&self, replenishment: u128) -> Result<()> {
+        let amount = replenishment.saturating_add(ONE_EVER);
+        self.wallet.wait_for_balance(amount + ONE_EVER).await?;
+
+        // Prevent shutdown during the operation
+        let _guard = self.guard.lock().await;
+
+        tracing::info!(amount = %Tokens(amount), "replenishing DePool balance");
+        self.wallet
+            .call(self.depool.receive_funds(amount)?)
+            .await
+            .context("failed to replenish DePool balance")?;
+        Ok(())
+    }
+}
+
+/// Limits the amount spent during the last 24 hours
+struct DailyLimit {
+    limit: u128,
+    history: VecDeque<(u32, u128)>,
+}
+
+impl DailyLimit {
+    const WINDOW: u32 = 86400;
+
+    fn new(limit: u128) -> Self {
+        Self {
+            limit,
+            history: Default::default(),
+        }
+    }
+
+    fn spent(&self) -> u128 {
+        self.history.iter().map(|(_, amount)| amount).sum()
+    }
+
+    fn try_spend(&mut self, amount: u128) -> bool {
+        let now = broxus_util::now();
+        while matches!(self.history.front(), Some((at, _)) if at + Self::WINDOW < now) {
+            self.history.pop_front();
+        }
+
+        if self.spent().saturating_add(amount) > self.limit {
+            return false;
+        }
+        self.history.push_back((now, amount));
+        true
+    }
+}
@@ -0,0 +1,161 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use anyhow::{Context, Result};
+use tokio_util::sync::{CancellationToken, DropGuard};
+
+use crate::config::{AppConfigDiskWatchdog, DiskWatchdogAction, NodeConfig};
+use crate::notifications::{Event, Notifier};
+use crate::util::system;
+
+/// Background task which keeps enough free space for the node DB
+pub struct DiskWatchdog {
+    params: AppConfigDiskWatchdog,
+    low_space: Arc<AtomicBool>,
+    _cancellation_guard: DropGuard,
+}
+
+impl DiskWatchdog {
+    pub fn spawn(
+        params: &AppConfigDiskWatchdog,
+        node_config: PathBuf,
+        notifier: Notifier,
+        dry_run: bool,
+    ) -> Self {
+        let cancellation_token = CancellationToken::new();
+        let low_space = Arc::new(AtomicBool::new(false));
+
+        let mut state = DiskWatchdogState {
+            params: params.clone(),
+            node_config,
+            notifier,
+            dry_run,
+            low_space: low_space.clone(),
+            alerted: false,
+        };
+
+        tokio::spawn({
+            let cancellation_token = cancellation_token.clone();
+            async move {
+                tokio::select! {
+                    _ = state.run() => {},
+                    _ = cancellation_token.cancelled() => {},
+                }
+            }
+        });
+
+        tracing::info!(
+            min_free_space_gb = params.min_free_space_gb,
+            "started disk watchdog"
+        );
+
+        Self {
+            params: params.clone(),
+            low_space,
+            _cancellation_guard: cancellation_token.drop_guard(),
+        }
+    }
+
+    pub fn params(&self) -> &AppConfigDiskWatchdog {
+        &self.params
+    }
+
+    /// Whether elections must be skipped due to low free space
+    pub fn should_pause_bidding(&self) -> bool {
+        self.params.has_action(DiskWatchdogAction::StopBidding)
+            && self.low_space.load(Ordering::Acquire)
+    }
+}
+
+struct DiskWatchdogState {
+    params: AppConfigDiskWatchdog,
+    node_config: PathBuf,
+    notifier: Notifier,
+    dry_run: bool,
+    low_space: Arc<AtomicBool>,
+    alerted: bool,
+}
+
+impl DiskWatchdogState {
+    async fn run(&mut self) {
+        let mut interval = tokio::time::interval(self.params.interval);
+        loop {
+            interval.tick().await;
+            if let Err(e) = self.check() {
+                tracing::error!("failed to check disk space: {e:?}");
+            }
+        }
+    }
+
+    fn check(&mut self) -> Result<()> {
+        let required = self.params.min_free_space_gb << 30;
+
+        let db_path = NodeConfig::load(&self.node_config)?
+            .get_internal_db_path()?
+            .context("node DB path is not configured")?;
+
+        let mut available = system::statvfs(&db_path)?.available_space;
+        if available >= required {
+            if self.low_space.swap(false, Ordering::AcqRel) {
+                tracing::info!(available, "free disk space restored");
+            }
+            self.alerted = false;
+            return Ok(());
+        }
+
+        tracing::warn!(available, required, "free disk space is low");
+
+        if self.params.has_action(DiskWatchdogAction::PruneArchives) {
+            let pruned = self.prune_archives(&db_path, required - available)?;
+            if pruned > 0 && !self.dry_run {
+                available = system::statvfs(&db_path)?.available_space;
+            }
+        }
+
+        let low_space = available < required;
+        self.low_space.store(low_space, Ordering::Release);
+
+        if low_space && !self.alerted && self.params.has_action(DiskWatchdogAction::Alert) {
+            self.notifier.notify(Event::LowDiskSpace {
+                available,
+                required,
+            });
+            self.alerted = true;
+        }
+
+        Ok(())
+    }
+
+    /// Removes the oldest archives to free the specified amount of bytes.
+    /// Returns the size of removed archives.
+    fn prune_archives(&self, db_path: &Path, to_free: u64) -> Result<u64> {
+        let now = SystemTime::now();
+
+        let mut pruned = 0;
+        for package in system::list_archive_packages(db_path)? {
+            if pruned >= to_free {
+                break;
+            }
+
+            // Recent archives are still used by the node
+            let age = now.duration_since(package.modified).unwrap_or_default();
+            if age < self.params.min_archive_age {
+                break;
+            }
+
+            if self.dry_run {
+                tracing::info!(path = %package.path.display(), "archive will be removed (dry run)");
+            } else {
+                std::fs::remove_file(&package.path).with_context(|| {
+                    format!("failed to remove archive {}", package.path.display())
+                })?;
+                tracing::info!(path = %package.path.display(), "removed archive");
+            }
+            pruned += package.size;
+        }
+
+        Ok(pruned)
+    }
+}
@@ -0,0 +1,155 @@
+use std::os::unix::io::AsRawFd;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use broxus_util::now;
+use serde::{Deserialize, Serialize};
+
+use crate::config::{AppConfigFailover, AppConfigValidator, FailoverRole};
+use crate::contracts::{elector, DePool};
+use crate::network::Subscription;
+
+/// Coordinates redundant validation managers
+pub struct Failover {
+    owner: String,
+}
+
+impl Default for Failover {
+    fn default() -> Self {
+        let host = sysinfo::System::host_name().unwrap_or_default();
+        Self {
+            owner: format!("{host}:{}", std::process::id()),
+        }
+    }
+}
+
+impl Failover {
+    /// Tries to acquire (or prolong) the shared lock.
+    ///
+    /// Returns `false` if the lock is held by another instance.
+    pub fn try_lock(&self, params: &AppConfigFailover) -> Result<bool> {
+        let Some(path) = &params.lock_path else {
+            return Ok(true);
+        };
+
+        // NOTE: the guard makes the whole read-check-write sequence atomic,
+        // so two instances can't both take over an expired lock
+        let Some(_guard) = LockGuard::try_acquire(path)? else {
+            tracing::info!("failover lock is being updated by another instance");
+            return Ok(false);
+        };
+
+        let now = now();
+        if let Some(lock) = FailoverLock::load(path)? {
+            let expired = lock.expires_at <= now;
+            let preempted =
+                params.role == FailoverRole::Primary && lock.role == FailoverRole::Standby;
+            if lock.owner != self.owner && !expired && !preempted {
+                tracing::info!(
+                    owner = %lock.owner,
+                    role = ?lock.role,
+                    expires_at = lock.expires_at,
+                    "failover lock is held by another instance"
+                );
+                return Ok(false);
+            }
+        }
+
+        FailoverLock {
+            owner: self.owner.clone(),
+            role: params.role,
+            expires_at: now + params.lease.as_secs() as u32,
+        }
+        .store(path)?;
+        Ok(true)
+    }
+}
+
+/// Returns `true` if any of the participant addresses already sent a bid
+pub async fn has_active_bid(
+    validator: &AppConfigValidator,
+    subscription: &std::sync::Arc<Subscription>,
+    elector_data: &elector::ElectorData,
+) -> Result<bool> {
+    Ok(match validator {
+        AppConfigValidator::Single(single) => elector_data.elected(&single.address),
+        AppConfigValidator::DePool(params) => {
+            let depool = DePool::new(
+                params.depool_type,
+                params.depool.clone(),
+                subscription.clone(),
+            );
+            let state = depool
+                .get_state()
+                .await
+                .context("failed to get DePool state")?;
+            let info = depool
+                .get_info(&state)
+                .context("failed to get DePool info")?;
+            info.proxies.iter().any(|proxy| elector_data.elected(proxy))
+        }
+    })
+}
+
+#[derive(Serialize, Deserialize)]
+struct FailoverLock {
+    owner: String,
+    role: FailoverRole,
+    expires_at: u32,
+}
+
+impl FailoverLock {
+    fn load(path: &Path) -> Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let data = std::fs::read_to_string(path).context("failed to read failover lock")?;
+        match serde_json::from_str(&data) {
+            Ok(lock) => Ok(Some(lock)),
+            Err(e) => {
+                tracing::warn!("invalid failover lock: {e:?}");
+                Ok(None)
+            }
+        }
+    }
+
+    fn store(&self, path: &Path) -> Result<()> {
+        let tmp = path.with_extension(format!("{}.tmp", std::process::id()));
+        std::fs::write(&tmp, serde_json::to_string(self)?)
+            .and_then(|_| std::fs::rename(tmp, path))
+            .context("failed to store failover lock")
+    }
+}
+
+/// Exclusive `flock` on the file next to the failover lock.
+///
+/// Released when the file is closed (also when the process dies).
+struct LockGuard {
+    _file: std::fs::File,
+}
+
+impl LockGuard {
+    /// Returns `None` if the guard is held by another instance
+    fn try_acquire(path: &Path) -> Result<Option<Self>> {
+        let mut guard_path = path.as_os_str().to_owned();
+        guard_path.push(".guard");
+
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .write(true)
+            .open(PathBuf::from(guard_path))
+            .context("failed to open failover lock guard")?;
+
+        let res = unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) };
+        if res == 0 {
+            return Ok(Some(Self { _file: file }));
+        }
+
+        let e = std::io::Error::last_os_error();
+        if e.kind() == std::io::ErrorKind::WouldBlock {
+            Ok(None)
+        } else {
+            Err(e).context("failed to lock failover lock guard")
+        }
+    }
+}
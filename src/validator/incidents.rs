@@ -0,0 +1,283 @@
+use std::collections::HashSet;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use broxus_util::now;
+use serde::{Deserialize, Serialize};
+use tokio::sync::watch;
+use tokio_util::sync::{CancellationToken, DropGuard};
+use ton_block::Serializable;
+
+use crate::contracts::Elector;
+use crate::network::{NodeStats, Subscription, ValidatorSetEntry};
+use crate::notifications::{Event, Notifier};
+
+/// Validator incidents stored in the local history file
+pub struct IncidentHistory {
+    path: PathBuf,
+}
+
+impl IncidentHistory {
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+        }
+    }
+
+    /// Reads all recorded incidents
+    pub fn load(&self) -> Result<Vec<Incident>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let data = std::fs::read_to_string(&self.path).context("failed to read incidents")?;
+        data.lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| serde_json::from_str(line).context("invalid incident entry"))
+            .collect()
+    }
+
+    /// Appends a new incident to the history
+    pub fn append(&self, incident: &Incident) -> Result<()> {
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .context("failed to open incidents file")?;
+        writeln!(file, "{}", serde_json::to_string(incident)?)?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Incident {
+    /// Unix timestamp when the incident was detected
+    pub timestamp: u32,
+    /// Validator set hash
+    pub vset_hash: String,
+    #[serde(flatten)]
+    pub kind: IncidentKind,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "kind")]
+pub enum IncidentKind {
+    /// Validator node was lagging behind for too long
+    OutOfSync { mc_time_diff: i32 },
+    /// Validator node stopped validating in the middle of the round
+    LeftValidatorSet,
+    /// There is a complaint for our validator in the elector
+    Complaint {
+        election_id: u32,
+        hash: String,
+        suggested_fine: String,
+    },
+}
+
+impl std::fmt::Display for IncidentKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::OutOfSync { mc_time_diff } => {
+                write!(f, "validator was out of sync (time diff {mc_time_diff}s)")
+            }
+            Self::LeftValidatorSet => f.write_str("validator stopped validating"),
+            Self::Complaint {
+                election_id, hash, ..
+            } => write!(f, "complaint {hash} for elections {election_id}"),
+        }
+    }
+}
+
+/// Background task which watches the validator in the current validator set
+pub struct IncidentMonitor {
+    subscription_tx: watch::Sender<Arc<Subscription>>,
+    _cancellation_guard: DropGuard,
+}
+
+impl IncidentMonitor {
+    pub fn spawn(
+        subscription: Arc<Subscription>,
+        history: IncidentHistory,
+        notifier: Notifier,
+        max_time_diff: i32,
+    ) -> Self {
+        let cancellation_token = CancellationToken::new();
+
+        // NOTE: complaints are recorded only once
+        let recorded = match history.load() {
+            Ok(incidents) => incidents
+                .into_iter()
+                .filter_map(|incident| match incident.kind {
+                    IncidentKind::Complaint { hash, .. } => Some(hash),
+                    _ => None,
+                })
+                .collect(),
+            Err(e) => {
+                tracing::warn!("failed to load incidents history: {e:?}");
+                HashSet::new()
+            }
+        };
+
+        let (subscription_tx, subscription) = watch::channel(subscription);
+        let mut monitor = Monitor {
+            subscription,
+            history,
+            notifier,
+            max_time_diff,
+            vset_hash: None,
+            validating: false,
+            lagging_checks: 0,
+            recorded,
+        };
+
+        tokio::spawn({
+            let cancellation_token = cancellation_token.clone();
+            async move {
+                tokio::select! {
+                    _ = monitor.run() => {},
+                    _ = cancellation_token.cancelled() => {},
+                }
+            }
+        });
+
+        tracing::info!("started incidents monitor");
+
+        Self {
+            subscription_tx,
+            _cancellation_guard: cancellation_token.drop_guard(),
+        }
+    }
+
+    /// Switches the monitor to the new subscription
+    pub fn set_subscription(&self, subscription: &Arc<Subscription>) {
+        self.subscription_tx.send_if_modified(|current| {
+            let modified = !Arc::ptr_eq(current, subscription);
+            *current = subscription.clone();
+            modified
+        });
+    }
+}
+
+struct Monitor {
+    subscription: watch::Receiver<Arc<Subscription>>,
+    history: IncidentHistory,
+    notifier: Notifier,
+    max_time_diff: i32,
+    vset_hash: Option<ton_types::UInt256>,
+    validating: bool,
+    lagging_checks: usize,
+    recorded: HashSet<String>,
+}
+
+impl Monitor {
+    const INTERVAL: Duration = Duration::from_secs(60);
+    const MAX_LAGGING_CHECKS: usize = 3;
+
+    async fn run(&mut self) {
+        loop {
+            if let Err(e) = self.check().await {
+                tracing::warn!("failed to check validator incidents: {e:?}");
+            }
+            tokio::time::sleep(Self::INTERVAL).await;
+        }
+    }
+
+    async fn check(&mut self) -> Result<()> {
+        let subscription = self.subscription.borrow().clone();
+        let stats = match subscription.tcp_rpc()?.get_stats().await? {
+            NodeStats::Running(stats) => stats,
+            NodeStats::NotReady(_) => return Ok(()),
+        };
+
+        let config = subscription.get_blockchain_config().await?;
+        let vset = config
+            .config
+            .validator_set()
+            .context("invalid validator set")?;
+        let vset_hash = vset.serialize()?.repr_hash();
+
+        // Reset state on the new round
+        if self.vset_hash != Some(vset_hash) {
+            self.vset_hash = Some(vset_hash);
+            self.validating = false;
+            self.lagging_checks = 0;
+        }
+
+        let ValidatorSetEntry::Validator(adnl) = stats.in_current_vset else {
+            if std::mem::take(&mut self.validating) {
+                self.record(&vset_hash, IncidentKind::LeftValidatorSet)?;
+            }
+            return Ok(());
+        };
+        self.validating = true;
+
+        // Check sync status
+        if stats.mc_time_diff > self.max_time_diff {
+            self.lagging_checks += 1;
+            if self.lagging_checks == Self::MAX_LAGGING_CHECKS {
+                self.record(
+                    &vset_hash,
+                    IncidentKind::OutOfSync {
+                        mc_time_diff: stats.mc_time_diff,
+                    },
+                )?;
+            }
+        } else {
+            self.lagging_checks = 0;
+        }
+
+        // Check complaints
+        let adnl = ton_types::UInt256::from(adnl);
+        let Some(public_key) = vset
+            .list()
+            .iter()
+            .find(|descr| descr.adnl_addr.as_ref() == Some(&adnl))
+            .map(|descr| ton_types::UInt256::from(*descr.public_key.as_slice()))
+        else {
+            return Ok(());
+        };
+
+        let addresses = subscription.get_system_addresses().await?;
+        let complaints = Elector::new(addresses.elector, subscription.clone())
+            .get_complaints()
+            .await?;
+        for complaint in complaints {
+            if complaint.validator_pubkey != public_key {
+                continue;
+            }
+            let hash = complaint.hash.to_hex_string();
+            if self.recorded.insert(hash.clone()) {
+                self.record(
+                    &vset_hash,
+                    IncidentKind::Complaint {
+                        election_id: complaint.election_id,
+                        hash,
+                        suggested_fine: complaint.suggested_fine.to_string(),
+                    },
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn record(&self, vset_hash: &ton_types::UInt256, kind: IncidentKind) -> Result<()> {
+        tracing::warn!(%kind, "validator incident detected");
+        self.notifier.notify(match &kind {
+            IncidentKind::OutOfSync { mc_time_diff } => Event::NodeOutOfSync {
+                mc_time_diff: *mc_time_diff,
+            },
+            kind => Event::Incident {
+                description: kind.to_string(),
+            },
+        });
+        self.history.append(&Incident {
+            timestamp: now(),
+            vset_hash: vset_hash.to_hex_string(),
+            kind,
+        })
+    }
+}
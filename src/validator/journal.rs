@@ -0,0 +1,104 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+use broxus_util::serde_string;
+use serde::{Deserialize, Serialize};
+
+use crate::contracts::elector::ValidatorKeys;
+
+/// Validation progress which survives manager restarts
+pub struct Journal {
+    path: PathBuf,
+    state: JournalState,
+}
+
+impl Journal {
+    pub fn load(path: PathBuf) -> Self {
+        let state = if path.exists() {
+            match std::fs::read_to_string(&path)
+                .map_err(anyhow::Error::from)
+                .and_then(|data| serde_json::from_str(&data).map_err(anyhow::Error::from))
+            {
+                Ok(state) => state,
+                Err(e) => {
+                    tracing::warn!("failed to load validation journal: {e:?}");
+                    Default::default()
+                }
+            }
+        } else {
+            Default::default()
+        };
+
+        Self { path, state }
+    }
+
+    /// Returns the last stored state
+    pub fn state(&self) -> &JournalState {
+        &self.state
+    }
+
+    /// Returns the state for the specified elections (resets it if elections changed)
+    pub fn elections(&mut self, election_id: u32) -> &JournalState {
+        if self.state.election_id != Some(election_id) {
+            tracing::debug!(election_id, "starting new journal entry");
+            self.state = JournalState {
+                election_id: Some(election_id),
+                ..Default::default()
+            };
+            self.store();
+        }
+        &self.state
+    }
+
+    /// Applies changes to the current state and stores it
+    pub fn update<F>(&mut self, f: F)
+    where
+        F: FnOnce(&mut JournalState),
+    {
+        f(&mut self.state);
+        self.store();
+    }
+
+    fn store(&self) {
+        let res = serde_json::to_string_pretty(&self.state)
+            .map_err(anyhow::Error::from)
+            .and_then(|data| write_atomic(&self.path, data));
+        if let Err(e) = res {
+            tracing::warn!("failed to store validation journal: {e:?}");
+        }
+    }
+}
+
+#[derive(Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct JournalState {
+    /// Elections this state belongs to
+    pub election_id: Option<u32>,
+    /// Stake was recovered from the elector during these elections
+    pub stake_recovered: bool,
+    /// Amount of the recovered stake (in nano tokens)
+    #[serde(with = "serde_string")]
+    pub recovered_amount: u128,
+    /// Validator keys registered in the node for these elections
+    pub keys: Option<ValidatorKeys>,
+    /// Election request which was sent but not yet confirmed
+    pub request: Option<ElectionRequest>,
+    /// Elector accepted the stake
+    pub stake_accepted: bool,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ElectionRequest {
+    /// Address of the elections participant (wallet or proxy)
+    #[serde(with = "serde_string")]
+    pub participant: ton_block::MsgAddressInt,
+    /// Unix timestamp when the request was sent
+    pub sent_at: u32,
+}
+
+fn write_atomic(path: &PathBuf, data: String) -> Result<()> {
+    let tmp = path.with_extension("tmp");
+    std::fs::write(&tmp, data)?;
+    std::fs::rename(tmp, path)?;
+    Ok(())
+}
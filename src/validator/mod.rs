@@ -5,28 +5,117 @@ use anyhow::{Context, Result};
 use broxus_util::now;
 use futures_util::FutureExt;
 use rand::Rng;
-use tokio::sync::Mutex;
-
+use tokio::sync::{Mutex, Notify};
+use tracing::Instrument;
+
+pub use self::api::{node_status, ManagerClient, RoundTimings};
+use self::api::{ApiServer, ApiState, ControlSocket};
+pub use self::backup::{decrypt, make_local_backup, make_remote_backup};
+use self::balance_watcher::BalanceWatcher;
+pub use self::bids::{BidEntry, BidHistory};
+use self::config_watcher::ConfigWatcher;
+use self::depool_watcher::DePoolWatcher;
+use self::disk_watchdog::DiskWatchdog;
+use self::failover::Failover;
+use self::incidents::IncidentMonitor;
+pub use self::incidents::{Incident, IncidentHistory, IncidentKind};
+use self::journal::ElectionRequest;
+pub use self::journal::Journal;
+use self::params_monitor::ParamsMonitor;
+use self::performance::PerformanceMonitor;
+pub use self::performance::{PerformanceHistory, RoundStats};
+use self::rewards::RewardTracker;
+pub use self::rewards::{RewardEntry, RewardSource, RewardsLedger};
+use self::scheduler::Scheduler;
+use self::stake_strategy::{make_stake_strategy, StakeContext};
+use self::upgrade_monitor::UpgradeMonitor;
+use self::watchdog::Watchdog;
 use crate::config::*;
 use crate::contracts::*;
 use crate::dirs::ProjectDirs;
 use crate::network::{ConfigWithId, NodeStats, NodeTcpRpc, NodeUdpRpc, Subscription};
-use crate::util::Tokens;
+use crate::notifications::{Event, Notifier};
+use crate::util::{Tokens, TransactionWithHash};
+
+mod api;
+mod backup;
+mod balance_watcher;
+mod bids;
+mod config_watcher;
+mod depool_watcher;
+mod disk_watchdog;
+mod failover;
+mod incidents;
+mod journal;
+mod params_monitor;
+mod performance;
+mod rewards;
+mod scheduler;
+mod stake_strategy;
+mod upgrade_monitor;
+mod watchdog;
 
 pub struct ValidationManager {
     dirs: ProjectDirs,
     params: ValidationParams,
     last_params: parking_lot::Mutex<Option<AppConfigValidator>>,
     guard: Arc<Mutex<()>>,
+    wakeup: Arc<Notify>,
+    config_changed: Arc<Notify>,
+    config_watcher: Option<ConfigWatcher>,
+    depool_watcher: Option<DePoolWatcher>,
+    balance_watcher: Option<BalanceWatcher>,
+    incident_monitor: Option<IncidentMonitor>,
+    upgrade_monitor: Option<UpgradeMonitor>,
+    params_monitor: Option<ParamsMonitor>,
+    performance_monitor: Option<PerformanceMonitor>,
+    reward_tracker: Option<RewardTracker>,
+    scheduler: Option<Scheduler>,
+    watchdog: Option<Watchdog>,
+    disk_watchdog: Option<DiskWatchdog>,
+    api_state: Arc<ApiState>,
+    api_server: Option<ApiServer>,
+    control_socket: Option<ControlSocket>,
+    journal: parking_lot::Mutex<Journal>,
+    failover: Failover,
+    notifier: Notifier,
 }
 
 impl ValidationManager {
     pub fn new(dirs: ProjectDirs, params: ValidationParams) -> Self {
+        let journal = Journal::load(dirs.validation_journal.clone());
+        let guard = Arc::<Mutex<()>>::default();
+        let notifier = Notifier::default();
+        let api_state = Arc::new(ApiState::new(
+            dirs.clone(),
+            notifier.clone(),
+            guard.clone(),
+            params.dry_run,
+        ));
         Self {
             dirs,
             params,
             last_params: Default::default(),
-            guard: Default::default(),
+            guard,
+            wakeup: Default::default(),
+            config_changed: Default::default(),
+            config_watcher: None,
+            depool_watcher: None,
+            balance_watcher: None,
+            incident_monitor: None,
+            upgrade_monitor: None,
+            params_monitor: None,
+            performance_monitor: None,
+            reward_tracker: None,
+            scheduler: None,
+            watchdog: None,
+            disk_watchdog: None,
+            api_state,
+            api_server: None,
+            control_socket: None,
+            journal: parking_lot::Mutex::new(journal),
+            failover: Failover::default(),
+            notifier,
         }
     }
 
@@ -34,11 +123,35 @@ impl ValidationManager {
         &self.guard
     }
 
+    pub fn notifier(&self) -> &Notifier {
+        &self.notifier
+    }
+
     pub async fn try_validate(&mut self) -> Result<()> {
         const SYNC_CHECK_INTERVAL: u32 = 10;
+        const FAILOVER_RETRY_INTERVAL: u32 = 60;
+        const LOW_BALANCE_RETRY_INTERVAL: u32 = 60;
+        const LOW_DISK_SPACE_RETRY_INTERVAL: u32 = 60;
 
         tracing::info!("started validation loop");
 
+        // Apply config changes without waiting for the next iteration
+        if self.config_watcher.is_none() {
+            match ConfigWatcher::spawn(self.dirs.app_config.clone(), self.config_changed.clone()) {
+                Ok(watcher) => self.config_watcher = Some(watcher),
+                Err(e) => tracing::warn!("failed to watch app config changes: {e:?}"),
+            }
+        }
+
+        // Serve requests from the local CLI
+        if self.control_socket.is_none() {
+            let path = self.dirs.manager_socket.clone();
+            match ControlSocket::spawn(path, self.api_state.clone()) {
+                Ok(socket) => self.control_socket = Some(socket),
+                Err(e) => tracing::warn!("failed to start control socket: {e:?}"),
+            }
+        }
+
         let mut random_shift = None;
 
         let mut interval = 0u32;
@@ -46,11 +159,27 @@ impl ValidationManager {
             // Sleep with the requested interval
             if interval > 0 {
                 interval = std::cmp::max(interval, 10);
-                tokio::time::sleep(Duration::from_secs(interval as u64)).await;
+                tokio::select! {
+                    _ = tokio::time::sleep(Duration::from_secs(interval as u64)) => {},
+                    _ = self.wakeup.notified() => {
+                        tracing::info!("woken up by the DePool event");
+                    },
+                    _ = self.config_changed.notified() => {
+                        tracing::info!("reloading app config");
+                    },
+                }
             }
 
             // Read config
             let mut config = AppConfig::load(&self.dirs.app_config)?;
+            self.notifier.update(config.notifications.take());
+            self.notifier
+                .update_hooks(std::mem::take(&mut config.hooks));
+            self.update_watchdog(config.watchdog.take());
+            self.update_disk_watchdog(config.disk_watchdog.take());
+            self.update_api_server(config.api.take());
+            let spending_policy = self.load_spending_policy(&config)?;
+
             let validator = match config.validator.take() {
                 Some(validator) => validator,
                 None => {
@@ -58,6 +187,12 @@ impl ValidationManager {
                     continue;
                 }
             };
+            let failover = config.failover.take();
+            let balance_alerts = config.balance_alerts.take();
+            let price_feed = config.price_feed.take();
+            let maintenance = config.maintenance.take();
+            let schedule = std::mem::take(&mut config.schedule);
+            let is_standby = matches!(&failover, Some(f) if f.role == FailoverRole::Standby);
 
             // Create tcp rpc and wait until node is synced
             let node_tcp_rpc = NodeTcpRpc::new(config.control()?).await?;
@@ -65,27 +200,103 @@ impl ValidationManager {
                 interval = SYNC_CHECK_INTERVAL;
                 continue;
             }
-            let node_udp_rpc = NodeUdpRpc::new(config.adnl()?).await?;
+            let node_udp_rpc = NodeUdpRpc::new(config.adnl()?, &self.dirs).await?;
 
             // Create subscription
             let subscription = Subscription::new(node_tcp_rpc, node_udp_rpc);
+            subscription.set_state_file(self.dirs.subscription_state.clone());
+            if let Some(network) = config.network.take() {
+                subscription.set_network_params(network);
+            }
+            if let Some(policy) = spending_policy {
+                subscription.set_spending_policy(policy);
+            }
             subscription.ensure_ready().await?;
+            self.api_state.set_subscription(&subscription);
+
+            // Watch validator incidents
+            match &self.incident_monitor {
+                Some(monitor) => monitor.set_subscription(&subscription),
+                None => {
+                    self.incident_monitor = Some(IncidentMonitor::spawn(
+                        subscription.clone(),
+                        IncidentHistory::new(&self.dirs.incident_history),
+                        self.notifier.clone(),
+                        self.params.max_time_diff,
+                    ))
+                }
+            }
+
+            // Watch network global version changes
+            match &self.upgrade_monitor {
+                Some(monitor) => monitor.set_subscription(&subscription),
+                None => {
+                    self.upgrade_monitor = Some(UpgradeMonitor::spawn(
+                        subscription.clone(),
+                        self.notifier.clone(),
+                    ))
+                }
+            }
+
+            // Watch critical config params changes
+            match &self.params_monitor {
+                Some(monitor) => monitor.set_subscription(&subscription),
+                None => {
+                    self.params_monitor = Some(ParamsMonitor::spawn(
+                        subscription.clone(),
+                        self.notifier.clone(),
+                    ))
+                }
+            }
 
             // Get current network config params
+            let config = subscription.get_blockchain_config().await?;
             let ConfigWithId {
-                block_id: target_block,
+                block_id: ref target_block,
                 config: ref blockchain_config,
-            } = subscription.tcp_rpc().get_config_all().await?;
+            } = *config;
 
-            if !self.params.ignore_deploy && self.ensure_deployed(&validator, &subscription).await?
+            if !self.params.ignore_deploy
+                && !self.params.dry_run
+                && self.ensure_deployed(&validator, &subscription).await?
             {
                 // Proceed to the next iteration after contracts deployment
                 continue;
             }
 
-            let elector_address = blockchain_config
-                .elector_address()
-                .context("invalid elector address")?;
+            // Watch DePool events
+            if !self.params.dry_run {
+                self.update_depool_watcher(&validator, &subscription)?;
+            }
+
+            // Watch balances
+            self.update_balance_watcher(balance_alerts.as_ref(), &validator, &subscription);
+
+            // Run recurring tasks
+            self.update_scheduler(&schedule, &validator, &subscription);
+
+            // Record validator performance
+            if !matches!(&self.performance_monitor, Some(m) if m.validator() == &validator) {
+                self.performance_monitor = Some(PerformanceMonitor::spawn(
+                    &validator,
+                    subscription.clone(),
+                    PerformanceHistory::new(&self.dirs.performance_history),
+                    self.params.max_time_diff,
+                ));
+            }
+
+            // Record received rewards
+            let price_feed = price_feed.as_ref();
+            if !matches!(&self.reward_tracker, Some(t) if t.matches(&validator, price_feed)) {
+                self.reward_tracker = Some(RewardTracker::spawn(
+                    &validator,
+                    price_feed,
+                    subscription.clone(),
+                    &self.dirs,
+                ));
+            }
+
+            let elector_address = subscription.get_system_addresses().await?.elector;
             let timings = blockchain_config
                 .elector_params()
                 .context("invalid elector params")?;
@@ -95,11 +306,24 @@ impl ValidationManager {
 
             // Get block with the config
             tracing::info!("target block id: {target_block}");
-            let target_block = subscription.udp_rpc().get_block(&target_block).await?;
+            let target_block = subscription.udp_rpc()?.get_block(target_block).await?;
             let target_block_info = target_block
                 .read_brief_info()
                 .context("invalid target block")?;
 
+            // Elections are already finished if the next validator set is published (p36)
+            if blockchain_config
+                .next_validator_set_present()
+                .context("invalid next validator set")?
+            {
+                random_shift = None;
+                tracing::info!("next validator set is already published");
+                interval = current_vset
+                    .utime_until()
+                    .saturating_sub(target_block_info.gen_utime);
+                continue;
+            }
+
             // Compute where are we on the validation timeline
             let timeline = Timeline::compute(&timings, &current_vset, target_block_info.gen_utime);
             tracing::info!("timeline: {timeline}");
@@ -122,14 +346,31 @@ impl ValidationManager {
                 } => {
                     let random_shift = match random_shift {
                         Some(shift) => shift,
-                        None if self.params.disable_random_shift => *random_shift.insert(0),
                         None => {
-                            // Compute the random offset in the first 1/4 of elections
-                            let range = (since_elections_start + until_elections_end)
+                            // Compute the offset within the configured bid window
+                            let duration = (since_elections_start + until_elections_end)
                                 .saturating_sub(self.params.elections_end_offset)
-                                .saturating_sub(self.params.elections_start_offset)
-                                / 4;
-                            *random_shift.insert(rand::thread_rng().gen_range(0..range))
+                                .saturating_sub(self.params.elections_start_offset);
+                            let (from, to) = self.params.bid_window;
+                            let from = duration * from as u32 / 100;
+                            let to = duration * to as u32 / 100;
+
+                            // NOTE: standby waits until the end of the bid window
+                            let shift = if is_standby {
+                                to
+                            } else if self.params.disable_random_shift {
+                                from
+                            } else {
+                                rand::thread_rng().gen_range(from..=to)
+                            };
+                            let elections_start = elections_end
+                                .saturating_sub(until_elections_end + since_elections_start);
+                            tracing::info!(
+                                bid_at =
+                                    elections_start + self.params.elections_start_offset + shift,
+                                "scheduled election request"
+                            );
+                            *random_shift.insert(shift)
                         }
                     };
 
@@ -193,9 +434,41 @@ impl ValidationManager {
                 }
             }
 
+            // Coordinate with other instances
+            if let Some(failover) = &failover {
+                if is_standby
+                    && failover::has_active_bid(&validator, &subscription, &elector_data).await?
+                {
+                    tracing::info!("primary instance already participates in elections");
+                    interval = elections_end.saturating_sub(now());
+                    continue;
+                }
+
+                if !self.failover.try_lock(failover)? {
+                    interval = FAILOVER_RETRY_INTERVAL;
+                    continue;
+                }
+            }
+
+            // Check free disk space before bidding
+            if matches!(&self.disk_watchdog, Some(w) if w.should_pause_bidding()) {
+                tracing::warn!("bidding is paused due to low disk space");
+                interval = LOW_DISK_SPACE_RETRY_INTERVAL;
+                continue;
+            }
+
+            // Check balances before bidding
+            if let Some(watcher) = &self.balance_watcher {
+                if watcher.should_pause_bidding().await? {
+                    tracing::warn!("bidding is paused due to low balance");
+                    interval = LOW_BALANCE_RETRY_INTERVAL;
+                    continue;
+                }
+            }
+
             // Prepare context
             let keypair = self.dirs.load_validator_keys()?;
-            let ctx = ElectionsContext {
+            let mut ctx = ElectionsContext {
                 subscription,
                 elector,
                 elector_data,
@@ -203,13 +476,26 @@ impl ValidationManager {
                 timings,
                 blockchain_config,
                 guard: &self.guard,
+                journal: &self.journal,
+                bid_history: BidHistory::new(&self.dirs.bid_history),
+                dry_run: self.params.dry_run,
+                allow_conflicting_bids: self.params.allow_conflicting_bids,
+                maintenance: maintenance.is_some(),
+                notifier: &self.notifier,
             };
 
+            // Resume elections after restart
+            if !self.params.dry_run && ctx.resume_elections().await? {
+                interval = elections_end.saturating_sub(now());
+                continue;
+            }
+
             // Prepare election future
             let validation = match validator {
                 AppConfigValidator::Single(validation) => validation.elect(keypair, ctx).boxed(),
                 AppConfigValidator::DePool(validation) => validation.elect(keypair, ctx).boxed(),
-            };
+            }
+            .instrument(tracing::info_span!("elections", election_id));
 
             // Try elect
             let deadline = Duration::from_secs(
@@ -230,36 +516,41 @@ impl ValidationManager {
     pub async fn force_elect(&self) -> Result<()> {
         // Read config
         let mut config = AppConfig::load(&self.dirs.app_config)?;
+        let spending_policy = self.load_spending_policy(&config)?;
         let validator = config
             .validator
             .take()
             .context("validator entry not found in the app config")?;
+        let notifier = Notifier::new(config.notifications.take());
+        notifier.update_hooks(std::mem::take(&mut config.hooks));
 
         // Create tcp rpc and wait until node is synced
         let node_tcp_rpc = NodeTcpRpc::new(config.control()?).await?;
         if !self.is_synced(&node_tcp_rpc, validator.is_single()).await? {
             anyhow::bail!("node not syned");
         }
-        let node_udp_rpc = NodeUdpRpc::new(config.adnl()?).await?;
+        let node_udp_rpc = NodeUdpRpc::new(config.adnl()?, &self.dirs).await?;
 
         // Create subscription
         let subscription = Subscription::new(node_tcp_rpc, node_udp_rpc);
+        if let Some(network) = config.network.take() {
+            subscription.set_network_params(network);
+        }
+        if let Some(policy) = spending_policy {
+            subscription.set_spending_policy(policy);
+        }
         subscription.ensure_ready().await?;
 
         // Get current network config params
-        let ConfigWithId {
-            config: ref blockchain_config,
-            ..
-        } = subscription.tcp_rpc().get_config_all().await?;
+        let config = subscription.get_blockchain_config().await?;
+        let blockchain_config = &config.config;
 
-        if !self.params.ignore_deploy {
+        if !self.params.ignore_deploy && !self.params.dry_run {
             self.ensure_deployed(&validator, &subscription).await?;
         }
 
         // Get addresses
-        let elector_address = blockchain_config
-            .elector_address()
-            .context("invalid elector address")?;
+        let elector_address = subscription.get_system_addresses().await?.elector;
         let timings = blockchain_config
             .elector_params()
             .context("invalid elector params")?;
@@ -286,6 +577,13 @@ impl ValidationManager {
             timings,
             blockchain_config,
             guard: &self.guard,
+            journal: &self.journal,
+            bid_history: BidHistory::new(&self.dirs.bid_history),
+            dry_run: self.params.dry_run,
+            allow_conflicting_bids: self.params.allow_conflicting_bids,
+            // NOTE: explicit elections ignore the maintenance mode
+            maintenance: false,
+            notifier: &notifier,
         };
 
         // Prepare election future
@@ -295,6 +593,12 @@ impl ValidationManager {
         }
     }
 
+    fn load_spending_policy(&self, config: &AppConfig) -> Result<Option<wallet::SpendingPolicy>> {
+        let address_book = AddressBook::load(&self.dirs.address_book)?;
+        wallet::SpendingPolicy::from_config(config, &address_book, &self.dirs.spending_ledger)
+    }
+
+    #[tracing::instrument(skip_all)]
     async fn ensure_deployed(
         &self,
         validator: &AppConfigValidator,
@@ -318,6 +622,134 @@ impl ValidationManager {
         Ok(true)
     }
 
+    fn update_depool_watcher(
+        &mut self,
+        validator: &AppConfigValidator,
+        subscription: &Arc<Subscription>,
+    ) -> Result<()> {
+        let AppConfigValidator::DePool(params) = validator else {
+            self.depool_watcher = None;
+            return Ok(());
+        };
+
+        if matches!(&self.depool_watcher, Some(watcher) if watcher.params() == params.as_ref()) {
+            return Ok(());
+        }
+
+        self.depool_watcher = Some(DePoolWatcher::spawn(
+            params,
+            subscription.clone(),
+            &self.dirs,
+            self.wakeup.clone(),
+            self.guard.clone(),
+        )?);
+        Ok(())
+    }
+
+    fn update_watchdog(&mut self, params: Option<AppConfigWatchdog>) {
+        let Some(params) = params else {
+            self.watchdog = None;
+            return;
+        };
+
+        if matches!(&self.watchdog, Some(watchdog) if watchdog.params() == &params) {
+            return;
+        }
+
+        self.watchdog = Some(Watchdog::spawn(
+            &params,
+            self.dirs.app_config.clone(),
+            self.notifier.clone(),
+            self.params.dry_run,
+        ));
+    }
+
+    fn update_disk_watchdog(&mut self, params: Option<AppConfigDiskWatchdog>) {
+        let Some(params) = params else {
+            self.disk_watchdog = None;
+            return;
+        };
+
+        if matches!(&self.disk_watchdog, Some(watchdog) if watchdog.params() == &params) {
+            return;
+        }
+
+        self.disk_watchdog = Some(DiskWatchdog::spawn(
+            &params,
+            self.dirs.node_config.clone(),
+            self.notifier.clone(),
+            self.params.dry_run,
+        ));
+    }
+
+    fn update_api_server(&mut self, params: Option<AppConfigApi>) {
+        let Some(params) = params else {
+            self.api_server = None;
+            return;
+        };
+
+        if matches!(&self.api_server, Some(server) if server.matches(&params)) {
+            return;
+        }
+
+        // NOTE: drop the previous server first to release its address
+        self.api_server = None;
+        match ApiServer::spawn(&params, self.api_state.clone()) {
+            Ok(server) => self.api_server = Some(server),
+            Err(e) => tracing::warn!("failed to start API server: {e:?}"),
+        }
+    }
+
+    fn update_balance_watcher(
+        &mut self,
+        params: Option<&AppConfigBalanceAlerts>,
+        validator: &AppConfigValidator,
+        subscription: &Arc<Subscription>,
+    ) {
+        let Some(params) = params else {
+            self.balance_watcher = None;
+            return;
+        };
+
+        if matches!(&self.balance_watcher, Some(watcher) if watcher.matches(params, validator)) {
+            return;
+        }
+
+        self.balance_watcher = Some(BalanceWatcher::spawn(
+            params,
+            validator,
+            subscription.clone(),
+            self.notifier.clone(),
+        ));
+    }
+
+    fn update_scheduler(
+        &mut self,
+        tasks: &[AppConfigScheduledTask],
+        validator: &AppConfigValidator,
+        subscription: &Arc<Subscription>,
+    ) {
+        if tasks.is_empty() {
+            self.scheduler = None;
+            return;
+        }
+
+        if matches!(&self.scheduler, Some(scheduler) if scheduler.matches(tasks, validator)) {
+            return;
+        }
+
+        self.scheduler = Some(Scheduler::spawn(
+            tasks,
+            validator,
+            subscription.clone(),
+            &self.dirs,
+            self.notifier.clone(),
+            self.guard.clone(),
+            self.params.dry_run,
+        ));
+    }
+
+    #[tracing::instrument(level = "debug", skip_all)]
     async fn is_synced(&self, node_rpc: &NodeTcpRpc, only_mc: bool) -> Result<bool> {
         let interval = Duration::from_secs(10);
         let mut attempts = 6;
@@ -351,8 +783,14 @@ pub struct ValidationParams {
     pub stake_unfreeze_offset: u32,
     pub elections_start_offset: u32,
     pub elections_end_offset: u32,
+    /// Range (in percents of the elections duration) where the bid is sent
+    pub bid_window: (u8, u8),
     pub disable_random_shift: bool,
     pub ignore_deploy: bool,
+    /// Log messages instead of sending them
+    pub dry_run: bool,
+    /// Send election requests even if there are conflicting bids
+    pub allow_conflicting_bids: bool,
 }
 
 #[derive(Clone, Copy)]
@@ -370,9 +808,262 @@ struct ElectionsContext<'a> {
     timings: ton_block::ConfigParam15,
     blockchain_config: &'a ton_block::ConfigParams,
     guard: &'a Mutex<()>,
+    journal: &'a parking_lot::Mutex<Journal>,
+    bid_history: BidHistory,
+    dry_run: bool,
+    allow_conflicting_bids: bool,
+    /// Only recover stakes without new bids
+    maintenance: bool,
+    notifier: &'a Notifier,
 }
 
 impl ElectionsContext<'_> {
+    /// Sends the message from the wallet (only logs it in the dry-run mode)
+    async fn send(&self, wallet: &Wallet, message: InternalMessage, action: &str) -> Result<()> {
+        send_message(wallet, message, action, self.dry_run).await
+    }
+
+    /// Waits until the wallet balance is enough (only checks it in the dry-run mode)
+    async fn wait_for_balance(&self, wallet: &Wallet, target: u128) -> Result<u128> {
+        let balance = wallet.get_balance().await?.unwrap_or_default();
+        if !self.dry_run {
+            if balance < target {
+                self.notifier.notify(Event::LowBalance {
+                    address: wallet.address().to_string(),
+                    balance,
+                    required: target,
+                });
+            }
+            return wallet.wait_for_balance(target).await;
+        }
+
+        if balance < target {
+            tracing::warn!(
+                current_balance = %Tokens(balance),
+                target_balance = %Tokens(target),
+                "dry run: validator wallet balance is not enough"
+            );
+        }
+        Ok(balance)
+    }
+
+    /// Checks the journal for the progress made before the restart.
+    ///
+    /// Returns `true` if the stake was already accepted for these elections.
+    async fn resume_elections(&mut self) -> Result<bool> {
+        let state = self.journal.lock().elections(self.election_id).clone();
+        if state.stake_accepted {
+            tracing::info!(election_id = self.election_id, "stake was already accepted");
+            return Ok(true);
+        }
+
+        let Some(request) = state.request else {
+            return Ok(false);
+        };
+
+        tracing::info!(
+            election_id = self.election_id,
+            participant = %request.participant,
+            sent_at = request.sent_at,
+            "waiting for the previously sent election request"
+        );
+        match self.confirm_participation(&request.participant).await {
+            Ok(()) => {
+                self.journal
+                    .lock()
+                    .update(|state| state.stake_accepted = true);
+                Ok(true)
+            }
+            Err(e) => {
+                tracing::warn!("previous election request was not accepted: {e:?}");
+                self.journal.lock().update(|state| state.request = None);
+                Ok(false)
+            }
+        }
+    }
+
+    /// Builds the election request payload reusing the validator keys
+    /// which were registered before the restart.
+    #[tracing::instrument(skip_all, fields(stake_factor = stake_factor))]
+    async fn make_election_payload(
+        &self,
+        participant: &ton_block::MsgAddressInt,
+        stake_factor: u32,
+    ) -> Result<ton_types::Cell> {
+        if self.dry_run {
+            tracing::info!(
+                %participant,
+                stake_factor,
+                "dry run: validator keys were not generated"
+            );
+            return Ok(Default::default());
+        }
+
+        let keys = self.journal.lock().elections(self.election_id).keys;
+        let keys = match keys {
+            Some(keys) => {
+                tracing::info!("reusing registered validator keys");
+                keys
+            }
+            None => {
+                let keys = self
+                    .elector
+                    .generate_validator_keys(self.election_id, &self.timings)
+                    .await
+                    .context("failed to prepare new validator key")?;
+                self.journal.lock().update(|state| state.keys = Some(keys));
+                tracing::info!("registered new validator keys");
+                keys
+            }
+        };
+
+        let signature_id = self.subscription.get_signature_id().await?;
+        let payload = self
+            .elector
+            .make_election_payload(
+                &keys,
+                self.election_id,
+                participant,
+                stake_factor,
+                signature_id,
+            )
+            .await
+            .context("failed to prepare election payload")?;
+        tracing::info!("generated election payload");
+        Ok(payload)
+    }
+
+    /// Sends the election request and waits until the elector accepts the stake
+    #[tracing::instrument(skip_all, fields(%participant))]
+    async fn send_election_request(
+        &mut self,
+        wallet: &Wallet,
+        message: InternalMessage,
+        participant: &ton_block::MsgAddressInt,
+        stake_factor: u32,
+    ) -> Result<()> {
+        if self.dry_run {
+            // NOTE: conflicts are only reported in the dry-run mode
+            if let Err(e) = self.check_conflicting_bids(participant).await {
+                tracing::warn!("dry run: election request would fail: {e:?}");
+            }
+            tracing::info!(
+                dst = %message.dst,
+                amount = %Tokens(message.amount),
+                %participant,
+                "dry run: election request was not sent"
+            );
+            return Ok(());
+        }
+
+        self.check_conflicting_bids(participant).await?;
+        wallet.check_transfer(&message).await?;
+
+        // NOTE: the request is stored before sending to not send it twice after restart
+        self.journal.lock().update(|state| {
+            state.request = Some(ElectionRequest {
+                participant: participant.clone(),
+                sent_at: now(),
+            })
+        });
+
+        let tx = match wallet.call(message).await {
+            Ok(tx) => tx,
+            Err(e) => {
+                self.journal.lock().update(|state| state.request = None);
+                return Err(e.context("failed to participate in elections"));
+            }
+        };
+        tracing::info!("sent validator stake");
+
+        let confirmed = self.confirm_participation(participant).await;
+        self.record_bid(participant, stake_factor, &tx);
+        confirmed?;
+        self.journal
+            .lock()
+            .update(|state| state.stake_accepted = true);
+        self.notifier.notify(Event::ElectionSubmitted {
+            election_id: self.election_id,
+            participant: participant.to_string(),
+            stake: self.elector_data.stake(participant).unwrap_or_default() as u128,
+        });
+        Ok(())
+    }
+
+    /// Stores the sent bid in the history
+    fn record_bid(
+        &self,
+        participant: &ton_block::MsgAddressInt,
+        stake_factor: u32,
+        tx: &TransactionWithHash,
+    ) {
+        let bid = self.elector_data.bid(participant);
+        let keys = self.journal.lock().elections(self.election_id).keys;
+        let entry = BidEntry {
+            election_id: self.election_id,
+            sent_at: now(),
+            participant: participant.clone(),
+            stake: bid
+                .as_ref()
+                .map(|bid| bid.stake as u128)
+                .unwrap_or_default(),
+            max_factor: bid
+                .as_ref()
+                .map(|bid| bid.max_factor)
+                .unwrap_or(stake_factor),
+            adnl_addr: keys
+                .map(|keys| hex::encode(keys.adnl_addr))
+                .unwrap_or_default(),
+            tx_hash: tx.hash.to_hex_string(),
+        };
+        if let Err(e) = self.bid_history.append(&entry) {
+            tracing::warn!("failed to store election bid: {e:?}");
+        }
+    }
+
+    /// Ensures that there are no bids from the participant with other validator keys
+    /// (e.g. from the redundant manager on another machine) and no bids with our keys
+    async fn check_conflicting_bids(
+        &mut self,
+        participant: &ton_block::MsgAddressInt,
+    ) -> Result<()> {
+        let Some(keys) = self.journal.lock().elections(self.election_id).keys else {
+            return Ok(());
+        };
+
+        let public_key = self
+            .subscription
+            .tcp_rpc()?
+            .export_public_key(&keys.permanent_key_hash)
+            .await
+            .context("failed to export validator public key")?;
+        let public_key = ton_types::UInt256::from(public_key.to_bytes());
+        let adnl_addr = ton_types::UInt256::from(keys.adnl_addr);
+
+        self.elector_data = self.elector.get_data().await?;
+        let bid = self
+            .elector_data
+            .find_conflicting_bid(participant, &public_key, &adnl_addr);
+        let Some(bid) = bid else {
+            return Ok(());
+        };
+
+        tracing::warn!(
+            %participant,
+            bid_public_key = %bid.public_key,
+            bid_adnl_addr = %bid.adnl_addr,
+            bid_src_addr = %bid.src_addr,
+            bid_stake = %Tokens(bid.stake),
+            "found conflicting election bid"
+        );
+        anyhow::ensure!(
+            self.allow_conflicting_bids,
+            "found conflicting election bid from another validator node \
+            (use `--allow-conflicting-bids` to ignore)"
+        );
+        Ok(())
+    }
+
     async fn check_can_be_elected(&mut self, address: &ton_block::MsgAddressInt) -> Result<bool> {
         self.elector_data = self.elector.get_data().await?;
         let Some(current_election_id) = self.elector_data.election_id() else {
@@ -381,6 +1072,63 @@ impl ElectionsContext<'_> {
 
         Ok(current_election_id == self.election_id && !self.elector_data.elected(address))
     }
+
+    /// Waits until the elector accepts the stake from the participant
+    #[tracing::instrument(skip_all)]
+    async fn confirm_participation(&mut self, address: &ton_block::MsgAddressInt) -> Result<()> {
+        const ATTEMPTS: usize = 12;
+        const INTERVAL: Duration = Duration::from_secs(5);
+
+        for _ in 0..ATTEMPTS {
+            self.elector_data = self.elector.get_data().await?;
+            anyhow::ensure!(
+                self.elector_data.election_id() == Some(self.election_id),
+                "elections finished before the stake was accepted"
+            );
+
+            if let Some(stake) = self.elector_data.stake(address) {
+                tracing::info!(%address, stake = %Tokens(stake), "elector accepted the stake");
+                return Ok(());
+            }
+
+            tokio::time::sleep(INTERVAL).await;
+        }
+
+        anyhow::bail!("elector has not accepted the stake from {address}")
+    }
+}
+
+/// Sends the message from the wallet (only logs it in the dry-run mode)
+#[tracing::instrument(skip_all, fields(action = action))]
+async fn send_message(
+    wallet: &Wallet,
+    message: InternalMessage,
+    action: &str,
+    dry_run: bool,
+) -> Result<()> {
+    if dry_run {
+        let fees = match wallet.estimate_transfer(message.clone()).await {
+            Ok(estimate) => Some(Tokens(estimate.total_fees())),
+            Err(e) => {
+                tracing::warn!("failed to estimate transfer: {e:?}");
+                None
+            }
+        };
+        tracing::info!(
+            action,
+            dst = %message.dst,
+            amount = %Tokens(message.amount),
+            fees = ?fees,
+            "dry run: message was not sent"
+        );
+        return Ok(());
+    }
+
+    wallet
+        .call(message)
+        .await
+        .with_context(|| format!("failed to {action}"))?;
+    Ok(())
 }
 
 impl AppConfigValidatorSingle {
@@ -399,27 +1147,27 @@ impl AppConfigValidatorSingle {
             address = %self.address,
             stake = %Tokens(self.stake_per_round),
             stake_factor = ?self.stake_factor,
+            stake_strategy = ?self.stake_strategy,
+            recovered_stake = ?self.recovered_stake,
             "election as single"
         );
 
-        let wallet = Wallet::new(-1, keypair, ctx.subscription.clone());
+        let wallet = Wallet::new(
+            self.address.workchain_id() as i8,
+            keypair,
+            ctx.subscription.clone(),
+        );
         anyhow::ensure!(
             wallet.address() == &self.address,
             "validator wallet address mismatch"
         );
 
-        if let Some(stake) = ctx.elector_data.has_unfrozen_stake(wallet.address()) {
-            wallet.wait_for_balance(2 * ONE_EVER).await?;
-
-            // Prevent shutdown during stake recovery
-            let _guard = ctx.guard.lock().await;
+        // Recover the stake returned by the elector
+        let recovered_amount = self.recover_stake(&wallet, &ctx).await?;
 
-            // Send recover stake message
-            tracing::info!(stake = %Tokens(stake.as_u128()), "recovering stake");
-            wallet
-                .call(ctx.elector.recover_stake()?)
-                .await
-                .context("failed to recover stake")?;
+        if ctx.maintenance {
+            tracing::warn!("skipping elections in maintenance mode");
+            return Ok(());
         }
 
         // Check whether validator was already elected before waiting for balance
@@ -428,9 +1176,30 @@ impl AppConfigValidatorSingle {
             return Ok(());
         }
 
+        // Prepare stake strategy
+        let stake_factor = self.stake_factor.unwrap_or(DEFAULT_STAKE_FACTOR);
+        let strategy = make_stake_strategy(self.stake_strategy.as_ref(), self.stake_per_round);
+        let stakes_config = ctx
+            .blockchain_config
+            .stakes_config()
+            .context("invalid stakes config")?;
+        let max_validators = ctx
+            .blockchain_config
+            .validators_count()
+            .context("invalid validators count")?
+            .max_validators
+            .as_u16() as usize;
+
         // Wait until validator wallet balance is enough
-        let target_balance = self.stake_per_round as u128 + 2 * ONE_EVER;
-        wallet.wait_for_balance(target_balance).await?;
+        let participant_stakes = ctx.elector_data.participant_stakes(wallet.address());
+        let stake_ctx = StakeContext {
+            stake_factor,
+            max_validators,
+            min_stake: stakes_config.min_stake.as_u128(),
+            participant_stakes: &participant_stakes,
+        };
+        let target_balance = strategy.required_balance(&stake_ctx) + 2 * ONE_EVER;
+        ctx.wait_for_balance(&wallet, target_balance).await?;
 
         // Check whether validator was already elected after waiting for balance
         if !ctx.check_can_be_elected(wallet.address()).await? {
@@ -438,39 +1207,92 @@ impl AppConfigValidatorSingle {
             return Ok(());
         }
 
-        let signature_id = ctx.subscription.get_signature_id().await?;
+        // Compute stake using the latest elections state
+        let balance = wallet.get_balance().await?.unwrap_or_default();
+        let participant_stakes = ctx.elector_data.participant_stakes(wallet.address());
+        let stake_ctx = StakeContext {
+            participant_stakes: &participant_stakes,
+            ..stake_ctx
+        };
+        let mut stake = strategy.compute_stake(balance.saturating_sub(2 * ONE_EVER), &stake_ctx);
+        if self.recovered_stake == Some(AppConfigRecoveredStake::Rollover) && recovered_amount > 0 {
+            // Add the recovered stake to the bid
+            tracing::info!(recovered = %Tokens(recovered_amount), "rolling over recovered stake");
+            stake = std::cmp::min(
+                stake + recovered_amount,
+                balance.saturating_sub(2 * ONE_EVER),
+            );
+        }
+        anyhow::ensure!(
+            stake >= stake_ctx.min_stake,
+            "computed stake is too small ({} < {})",
+            Tokens(stake),
+            Tokens(stake_ctx.min_stake)
+        );
+        tracing::info!(stake = %Tokens(stake), "computed validator stake");
 
         // Prevent shutdown while electing
-        let _guard = ctx.guard.lock().await;
+        let guard = ctx.guard;
+        let _guard = guard.lock().await;
 
         // Prepare node for elections
         let payload = ctx
-            .elector
-            .participate_in_elections(
-                ctx.election_id,
-                wallet.address(),
-                self.stake_factor.unwrap_or(DEFAULT_STAKE_FACTOR),
-                &ctx.timings,
-                signature_id,
-            )
-            .await
-            .context("failed to prepare new validator key")?;
-        tracing::info!("generated election payload");
+            .make_election_payload(wallet.address(), stake_factor)
+            .await?;
 
         // Send election message
-        wallet
-            .call(InternalMessage {
-                dst: ctx.elector.address().clone(),
-                amount: self.stake_per_round as u128 + ONE_EVER,
-                payload,
-                bounce: false,
-            })
+        let message = InternalMessage {
+            dst: ctx.elector.address().clone(),
+            amount: stake + ONE_EVER,
+            payload,
+            bounce: false,
+        };
+        ctx.send_election_request(&wallet, message, wallet.address(), stake_factor)
             .await
-            .context("failed to participate in elections")?;
+    }
 
-        // Done
-        tracing::info!("sent validator stake");
-        Ok(())
+    /// Recovers the stake returned by the elector (if any).
+    ///
+    /// Returns the amount recovered during the current elections.
+    #[tracing::instrument(skip_all)]
+    async fn recover_stake(&self, wallet: &Wallet, ctx: &ElectionsContext<'_>) -> Result<u128> {
+        for frozen in ctx.elector_data.frozen_stakes(wallet.address()) {
+            tracing::info!(
+                election_id = frozen.election_id,
+                unfreeze_at = frozen.unfreeze_at,
+                stake = %Tokens(frozen.stake),
+                banned = frozen.banned,
+                "found frozen stake"
+            );
+        }
+
+        let state = ctx.journal.lock().elections(ctx.election_id).clone();
+        if state.stake_recovered {
+            return Ok(state.recovered_amount);
+        }
+
+        let Some(stake) = ctx.elector_data.has_unfrozen_stake(wallet.address()) else {
+            return Ok(0);
+        };
+        let stake = stake.as_u128();
+
+        ctx.wait_for_balance(wallet, 2 * ONE_EVER).await?;
+
+        // Prevent shutdown during stake recovery
+        let _guard = ctx.guard.lock().await;
+
+        // Send recover stake message
+        tracing::info!(stake = %Tokens(stake), "recovering stake");
+        ctx.send(wallet, ctx.elector.recover_stake()?, "recover stake")
+            .await?;
+        if !ctx.dry_run {
+            ctx.journal.lock().update(|state| {
+                state.stake_recovered = true;
+                state.recovered_amount = stake;
+            });
+            ctx.notifier.notify(Event::StakeRecovered { amount: stake });
+        }
+        Ok(stake)
     }
 }
 
@@ -488,7 +1310,11 @@ impl AppConfigValidatorDePool {
                     Some(wallet) => Ok(wallet),
                     state => {
                         let keypair = self.ctx.dirs.load_validator_keys()?;
-                        let res = Wallet::new(0, keypair, self.ctx.subscription.clone());
+                        let res = Wallet::new(
+                            self.target.workchain_id() as i8,
+                            keypair,
+                            self.ctx.subscription.clone(),
+                        );
                         anyhow::ensure!(
                             res.address() == self.target,
                             "validator wallet address mismatch"
@@ -553,7 +1379,7 @@ impl AppConfigValidatorDePool {
             // Transfer initial funds to the depool (if its balance is not enough)
             if let Some(balance) = depool_initial_balance {
                 // Prevent shutdown during the operation
-                let _guard = ctx.guard.lock();
+                let _guard = ctx.guard.lock().await;
 
                 tracing::info!("transferring initial funds to the DePool");
                 wallet
@@ -567,7 +1393,7 @@ impl AppConfigValidatorDePool {
             }
 
             // Prevent shutdown during the operation
-            let _guard = ctx.guard.lock();
+            let _guard = ctx.guard.lock().await;
 
             // Call depool constructor
             tracing::info!("deploying DePool contract");
@@ -605,7 +1431,7 @@ impl AppConfigValidatorDePool {
                 let wallet = wallet.get_or_init()?;
 
                 // Prevent shutdown during the operation
-                let _guard = ctx.guard.lock();
+                let _guard = ctx.guard.lock().await;
 
                 // Set strategy as an allowed participant
                 tracing::info!(%strategy, "setting DePool strategy");
@@ -635,7 +1461,11 @@ impl AppConfigValidatorDePool {
             "election as DePool"
         );
 
-        let wallet = Wallet::new(0, keypair, ctx.subscription.clone());
+        let wallet = Wallet::new(
+            self.owner.workchain_id() as i8,
+            keypair,
+            ctx.subscription.clone(),
+        );
         anyhow::ensure!(
             wallet.address() == &self.owner,
             "validator wallet address mismatch"
@@ -683,6 +1513,11 @@ impl AppConfigValidatorDePool {
             return Ok(());
         }
 
+        if ctx.maintenance {
+            tracing::warn!("skipping elections in maintenance mode");
+            return Ok(());
+        }
+
         let proxy = &depool_info.proxies[round_id as usize % 2];
 
         // Check whether proxy was already elected after waiting for balance
@@ -692,43 +1527,28 @@ impl AppConfigValidatorDePool {
         }
 
         // Wait until validator wallet balance is enough
-        wallet.wait_for_balance(2 * ONE_EVER).await?;
-
-        let signature_id = ctx.subscription.get_signature_id().await?;
+        ctx.wait_for_balance(&wallet, 2 * ONE_EVER).await?;
 
         // Prevent shutdown while electing
-        let _guard = ctx.guard.lock().await;
+        let guard = ctx.guard;
+        let _guard = guard.lock().await;
 
         // Prepare node for elections
-        let payload = ctx
-            .elector
-            .participate_in_elections(
-                ctx.election_id,
-                proxy,
-                self.stake_factor.unwrap_or(DEFAULT_STAKE_FACTOR),
-                &ctx.timings,
-                signature_id,
-            )
-            .await
-            .context("failed to prepare new validator key")?;
-        tracing::info!("generated election payload");
+        let stake_factor = self.stake_factor.unwrap_or(DEFAULT_STAKE_FACTOR);
+        let payload = ctx.make_election_payload(proxy, stake_factor).await?;
 
         // Send election message
-        wallet
-            .call(InternalMessage {
-                dst: depool.address().clone(),
-                amount: ONE_EVER,
-                payload,
-                bounce: false,
-            })
+        let message = InternalMessage {
+            dst: depool.address().clone(),
+            amount: ONE_EVER,
+            payload,
+            bounce: false,
+        };
+        ctx.send_election_request(&wallet, message, proxy, stake_factor)
             .await
-            .context("failed to participate in elections")?;
-
-        // Done
-        tracing::info!("sent validator stake");
-        Ok(())
     }
 
+    #[tracing::instrument(skip_all)]
     async fn maintain_balances(
         &self,
         wallet: &Wallet,
@@ -744,21 +1564,21 @@ impl AppConfigValidatorDePool {
                 "replenishing depool contracts"
             );
 
-            wallet.wait_for_balance(message.amount + ONE_EVER).await?;
+            ctx.wait_for_balance(wallet, message.amount + ONE_EVER)
+                .await?;
 
             // Prevent shutdown during operation
             let _guard = ctx.guard.lock().await;
 
             // Send some funds to depool contracts
-            wallet
-                .call(message)
-                .await
-                .context("failed to replenish depool contracts")?;
+            ctx.send(wallet, message, "replenish depool contracts")
+                .await?;
         }
 
         Ok(())
     }
 
+    #[tracing::instrument(skip_all)]
     async fn update_depool(
         &self,
         wallet: &Wallet,
@@ -810,8 +1630,7 @@ impl AppConfigValidatorDePool {
             {
                 if remaining_stake > 0 {
                     remaining_stake = std::cmp::max(remaining_stake, depool_info.min_stake);
-                    wallet
-                        .wait_for_balance(remaining_stake as u128 + ONE_EVER)
+                    ctx.wait_for_balance(wallet, remaining_stake as u128 + ONE_EVER)
                         .await?;
 
                     // Prevent shutdown during sending stake
@@ -819,10 +1638,11 @@ impl AppConfigValidatorDePool {
 
                     // Send recover stake message
                     tracing::info!(stake = %Tokens(remaining_stake), "adding ordinary stake");
-                    wallet
-                        .call(depool.add_ordinary_stake(remaining_stake)?)
-                        .await
-                        .context("failed to add ordinary stake")?;
+                    let message = depool.add_ordinary_stake(remaining_stake)?;
+                    if !ctx.dry_run {
+                        wallet.check_transfer(&message).await?;
+                    }
+                    ctx.send(wallet, message, "add ordinary stake").await?;
                 }
             }
 
@@ -841,13 +1661,15 @@ impl AppConfigValidatorDePool {
             }
 
             // Update rounds
-            wallet.wait_for_balance(2 * ONE_EVER).await?;
+            ctx.wait_for_balance(wallet, 2 * ONE_EVER).await?;
 
             tracing::info!("sending ticktock");
-            wallet
-                .call(depool.ticktock()?)
-                .await
-                .context("failed to send ticktock")?;
+            ctx.send(wallet, depool.ticktock()?, "send ticktock")
+                .await?;
+            if ctx.dry_run {
+                // DePool rounds will not be updated without ticktock
+                break Ok(None);
+            }
             sent_ticktock = true;
             tokio::time::sleep(TICKTOCK_INTERVAL).await;
 
@@ -929,29 +1751,48 @@ impl Timeline {
 
 impl Wallet {
     async fn wait_for_balance(&self, target: u128) -> Result<u128> {
-        let interval = Duration::from_secs(1);
-        let mut last_balance = None;
-        loop {
-            match self.get_balance().await?.unwrap_or_default() {
-                balance if balance >= target => {
-                    if last_balance.is_some() {
-                        tracing::info!(balance = %Tokens(balance), "fetched wallet balance");
-                    }
-                    break Ok(balance);
-                }
-                balance => {
-                    if !matches!(last_balance, Some(last_balance) if last_balance == balance) {
-                        tracing::info!(
-                            address = %self.address(),
-                            current_balance = %Tokens(balance),
-                            target_balance = %Tokens(target),
-                            "waiting until validator wallet balance is enough",
-                        );
-                    }
-                    last_balance = Some(balance);
-                }
+        // NOTE: subscribe before getting the balance to not miss any updates
+        let mut states = self.subscribe_state();
+
+        let mut balance = self.get_balance().await?.unwrap_or_default();
+        let mut waited = false;
+        while balance < target {
+            tracing::info!(
+                address = %self.address(),
+                current_balance = %Tokens(balance),
+                target_balance = %Tokens(target),
+                "waiting until validator wallet balance is enough",
+            );
+            waited = true;
+
+            let update = states
+                .recv()
+                .await
+                .context("wallet state subscription closed")?;
+            balance = update.balance();
+        }
+
+        if waited {
+            tracing::info!(balance = %Tokens(balance), "fetched wallet balance");
+        }
+        Ok(balance)
+    }
+
+    /// Executes the transfer locally and fails if it is expected to fail
+    async fn check_transfer(&self, message: &InternalMessage) -> Result<()> {
+        match self.estimate_transfer(message.clone()).await {
+            Ok(estimate) => {
+                tracing::info!(
+                    dst = %message.dst,
+                    fees = %Tokens(estimate.total_fees()),
+                    "estimated transfer fees"
+                );
+                estimate.ensure_success()
+            }
+            Err(e) => {
+                tracing::warn!(dst = %message.dst, "failed to estimate transfer: {e:?}");
+                Ok(())
             }
-            tokio::time::sleep(interval).await;
         }
     }
 }
@@ -995,4 +1836,5 @@ impl ProjectDirs {
     }
 }
 
-const DEFAULT_STAKE_FACTOR: u32 = 196608;
+/// Max factor 3.0 in the elector fixed-point format
+pub const DEFAULT_STAKE_FACTOR: u32 = 196608;
@@ -0,0 +1,128 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use tokio::sync::{broadcast, watch};
+use tokio_util::sync::{CancellationToken, DropGuard};
+
+use crate::network::{ConfigChangedEvent, Subscription};
+use crate::notifications::{Event, Notifier};
+use crate::util::{describe_config_param, diff_config_fields};
+
+/// Config params which usually require validator action when changed
+const MONITORED_PARAMS: [u32; 3] = [11, 15, 17];
+
+/// Background task which reports changes of the critical blockchain config params
+pub struct ParamsMonitor {
+    subscription_tx: watch::Sender<Arc<Subscription>>,
+    _cancellation_guard: DropGuard,
+}
+
+impl ParamsMonitor {
+    pub fn spawn(subscription: Arc<Subscription>, notifier: Notifier) -> Self {
+        let cancellation_token = CancellationToken::new();
+        let (subscription_tx, mut subscription_rx) = watch::channel(subscription);
+
+        tokio::spawn({
+            let cancellation_token = cancellation_token.clone();
+            async move {
+                let run = async {
+                    let mut config_events = subscription_rx
+                        .borrow_and_update()
+                        .subscribe_config_changes();
+                    loop {
+                        let changed = tokio::select! {
+                            event = config_events.recv() => match event {
+                                Ok(event) => {
+                                    check(&event, &notifier);
+                                    continue;
+                                }
+                                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                                // NOTE: subscription is closed only after it was replaced
+                                Err(broadcast::error::RecvError::Closed) => {
+                                    subscription_rx.changed().await
+                                }
+                            },
+                            changed = subscription_rx.changed() => changed,
+                        };
+                        if changed.is_err() {
+                            break;
+                        }
+                        config_events = subscription_rx
+                            .borrow_and_update()
+                            .subscribe_config_changes();
+                    }
+                };
+
+                tokio::select! {
+                    _ = run => {},
+                    _ = cancellation_token.cancelled() => {},
+                }
+            }
+        });
+
+        tracing::info!("started config params monitor");
+
+        Self {
+            subscription_tx,
+            _cancellation_guard: cancellation_token.drop_guard(),
+        }
+    }
+
+    /// Switches the monitor to the new subscription
+    pub fn set_subscription(&self, subscription: &Arc<Subscription>) {
+        self.subscription_tx.send_if_modified(|current| {
+            let modified = !Arc::ptr_eq(current, subscription);
+            *current = subscription.clone();
+            modified
+        });
+    }
+}
+
+fn check(event: &ConfigChangedEvent, notifier: &Notifier) {
+    let mut params = Vec::new();
+    let mut changes = Vec::new();
+    for param in MONITORED_PARAMS {
+        if !event.changed_params.contains(&param) {
+            continue;
+        }
+        params.push(param);
+
+        let diff = describe_param(&event.previous.config, param).and_then(|old| {
+            let new = describe_param(&event.config.config, param)?;
+            Ok(diff_config_fields(&old, &new))
+        });
+        match diff {
+            Ok(diff) => changes.extend(
+                diff.into_iter()
+                    .map(|(field, old, new)| format!("p{param}.{field}: {old} -> {new}")),
+            ),
+            Err(e) => {
+                tracing::error!(param, "failed to describe config param change: {e:?}");
+                changes.push(format!("p{param} changed"));
+            }
+        }
+    }
+
+    if params.is_empty() {
+        return;
+    }
+
+    tracing::warn!(
+        block_id = %event.config.block_id,
+        ?params,
+        ?changes,
+        "critical config params changed"
+    );
+    notifier.notify(Event::BlockchainConfigChanged {
+        params,
+        changes: changes.join("; "),
+    });
+}
+
+/// Returns human-readable values of the config param fields (empty if the param is not set)
+fn describe_param(config: &ton_block::ConfigParams, param: u32) -> Result<Vec<(String, String)>> {
+    match config.config(param)? {
+        Some(param) => describe_config_param(&param),
+        None => Ok(Vec::new()),
+    }
+}
@@ -0,0 +1,226 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use broxus_util::serde_string;
+use serde::{Deserialize, Serialize};
+use tokio_util::sync::{CancellationToken, DropGuard};
+
+use crate::config::AppConfigValidator;
+use crate::contracts::{DePool, Elector};
+use crate::network::{NodeStats, Subscription, ValidatorSetEntry};
+
+/// Per-round validator stats stored in the local history file
+pub struct PerformanceHistory {
+    path: PathBuf,
+}
+
+impl PerformanceHistory {
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+        }
+    }
+
+    /// Reads all recorded rounds
+    pub fn load(&self) -> Result<BTreeMap<u32, RoundStats>> {
+        if !self.path.exists() {
+            return Ok(BTreeMap::new());
+        }
+
+        let data = std::fs::read_to_string(&self.path).context("failed to read performance")?;
+        let rounds: Vec<RoundStats> =
+            serde_json::from_str(&data).context("invalid performance history")?;
+        Ok(rounds
+            .into_iter()
+            .map(|round| (round.election_id, round))
+            .collect())
+    }
+
+    fn store(&self, rounds: &BTreeMap<u32, RoundStats>) -> Result<()> {
+        let rounds = rounds.values().collect::<Vec<_>>();
+        let tmp = self.path.with_extension("tmp");
+        std::fs::write(&tmp, serde_json::to_string_pretty(&rounds)?)
+            .and_then(|_| std::fs::rename(tmp, &self.path))
+            .context("failed to store performance history")
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RoundStats {
+    /// Elections id (equals to the round start)
+    pub election_id: u32,
+    /// Unix timestamp of the round end
+    pub round_end: u32,
+    /// Total frozen stake of the validator
+    #[serde(with = "serde_string")]
+    pub stake: u128,
+    /// Share of the round bonuses
+    #[serde(with = "serde_string")]
+    pub reward: u128,
+    /// Whether the stake was banned
+    pub banned: bool,
+    /// Number of checks while the node was in the validator set
+    pub checks: u32,
+    /// Number of checks while the node was in the validator set and synced
+    pub synced_checks: u32,
+}
+
+impl RoundStats {
+    /// Fraction of time when the validator was synced
+    pub fn uptime(&self) -> Option<f64> {
+        (self.checks > 0).then(|| self.synced_checks as f64 / self.checks as f64)
+    }
+}
+
+/// Background task which records per-round validator stats
+pub struct PerformanceMonitor {
+    validator: AppConfigValidator,
+    _cancellation_guard: DropGuard,
+}
+
+impl PerformanceMonitor {
+    pub fn spawn(
+        validator: &AppConfigValidator,
+        subscription: Arc<Subscription>,
+        history: PerformanceHistory,
+        max_time_diff: i32,
+    ) -> Self {
+        let cancellation_token = CancellationToken::new();
+
+        let mut recorder = Recorder {
+            validator: validator.clone(),
+            subscription,
+            history,
+            max_time_diff,
+        };
+
+        tokio::spawn({
+            let cancellation_token = cancellation_token.clone();
+            async move {
+                tokio::select! {
+                    _ = recorder.run() => {},
+                    _ = cancellation_token.cancelled() => {},
+                }
+            }
+        });
+
+        tracing::info!("started performance monitor");
+
+        Self {
+            validator: validator.clone(),
+            _cancellation_guard: cancellation_token.drop_guard(),
+        }
+    }
+
+    pub fn validator(&self) -> &AppConfigValidator {
+        &self.validator
+    }
+}
+
+struct Recorder {
+    validator: AppConfigValidator,
+    subscription: Arc<Subscription>,
+    history: PerformanceHistory,
+    max_time_diff: i32,
+}
+
+impl Recorder {
+    const INTERVAL: Duration = Duration::from_secs(60);
+    const REWARDS_INTERVAL: usize = 10;
+
+    async fn run(&mut self) {
+        let mut rounds = match self.history.load() {
+            Ok(rounds) => rounds,
+            Err(e) => {
+                tracing::warn!("failed to load performance history: {e:?}");
+                BTreeMap::new()
+            }
+        };
+
+        let mut interval = tokio::time::interval(Self::INTERVAL);
+        for i in 0usize.. {
+            interval.tick().await;
+
+            let res = async {
+                self.check_uptime(&mut rounds).await?;
+                if i % Self::REWARDS_INTERVAL == 0 {
+                    self.update_rewards(&mut rounds).await?;
+                }
+                self.history.store(&rounds)
+            };
+            if let Err(e) = res.await {
+                tracing::warn!("failed to record validator performance: {e:?}");
+            }
+        }
+    }
+
+    async fn check_uptime(&self, rounds: &mut BTreeMap<u32, RoundStats>) -> Result<()> {
+        let stats = match self.subscription.tcp_rpc()?.get_stats().await? {
+            NodeStats::Running(stats) => stats,
+            NodeStats::NotReady(_) => return Ok(()),
+        };
+        if !matches!(stats.in_current_vset, ValidatorSetEntry::Validator(_)) {
+            return Ok(());
+        }
+
+        let config = self.subscription.get_blockchain_config().await?;
+        let vset = config
+            .config
+            .validator_set()
+            .context("invalid validator set")?;
+
+        let round = rounds.entry(vset.utime_since()).or_default();
+        round.election_id = vset.utime_since();
+        round.round_end = vset.utime_until();
+        round.checks += 1;
+        if stats.mc_time_diff <= self.max_time_diff {
+            round.synced_checks += 1;
+        }
+        Ok(())
+    }
+
+    async fn update_rewards(&self, rounds: &mut BTreeMap<u32, RoundStats>) -> Result<()> {
+        let addresses = match &self.validator {
+            AppConfigValidator::Single(single) => vec![single.address.clone()],
+            AppConfigValidator::DePool(params) => {
+                let depool = DePool::new(
+                    params.depool_type,
+                    params.depool.clone(),
+                    self.subscription.clone(),
+                );
+                let state = depool.get_state().await?;
+                depool.get_info(&state)?.proxies
+            }
+        };
+
+        let addresses = self.subscription.get_system_addresses().await?;
+        let elector_data = Elector::new(addresses.elector, self.subscription.clone())
+            .get_data()
+            .await?;
+
+        // NOTE: frozen stakes of all participant addresses are merged
+        let mut updated = BTreeMap::<u32, RoundStats>::new();
+        for address in &addresses {
+            for frozen in elector_data.frozen_stakes(address) {
+                let round = updated.entry(frozen.election_id).or_default();
+                round.round_end = frozen.unfreeze_at.saturating_sub(frozen.stake_held);
+                round.stake += frozen.stake;
+                round.reward += frozen.reward;
+                round.banned |= frozen.banned;
+            }
+        }
+
+        for (election_id, update) in updated {
+            let round = rounds.entry(election_id).or_default();
+            round.election_id = election_id;
+            round.round_end = update.round_end;
+            round.stake = update.stake;
+            round.reward = update.reward;
+            round.banned = update.banned;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,331 @@
+use std::collections::HashSet;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use broxus_util::{serde_optional_string, serde_string};
+use serde::{Deserialize, Serialize};
+use tokio_util::sync::{CancellationToken, DropGuard};
+
+use super::performance::PerformanceHistory;
+use crate::config::{AppConfigPriceFeed, AppConfigValidator};
+use crate::contracts::depool::DePoolEvent;
+use crate::contracts::Elector;
+use crate::dirs::ProjectDirs;
+use crate::network::Subscription;
+use crate::util::{Tokens, TransactionWithHash};
+
+/// Received validation rewards
+pub struct RewardsLedger {
+    path: PathBuf,
+}
+
+impl RewardsLedger {
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+        }
+    }
+
+    /// Reads all recorded rewards
+    pub fn load(&self) -> Result<Vec<RewardEntry>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let data = std::fs::read_to_string(&self.path).context("failed to read rewards")?;
+        data.lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| serde_json::from_str(line).context("invalid rewards entry"))
+            .collect()
+    }
+
+    fn append(&self, entry: &RewardEntry) -> Result<()> {
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .context("failed to open rewards file")?;
+        writeln!(file, "{}", serde_json::to_string(entry)?)?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RewardEntry {
+    /// Unix timestamp of the transaction
+    pub timestamp: u32,
+    /// Hex encoded transaction hash
+    pub tx_hash: String,
+    #[serde(flatten)]
+    pub source: RewardSource,
+    /// Received amount (stake with reward)
+    #[serde(with = "serde_string")]
+    pub amount: u128,
+    /// Reward part of the amount (if known)
+    #[serde(
+        with = "serde_optional_string",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub reward: Option<u128>,
+    /// Token price at the moment of the transaction
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub price: Option<TokenPrice>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "source")]
+pub enum RewardSource {
+    /// Stake recovered from the elector to the validator wallet
+    Elector { election_ids: Vec<u32> },
+    /// Completed DePool round
+    DePool { round_id: u64 },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenPrice {
+    pub value: f64,
+    pub currency: String,
+}
+
+/// Background task which records received rewards
+pub struct RewardTracker {
+    validator: AppConfigValidator,
+    price_feed: Option<AppConfigPriceFeed>,
+    _cancellation_guard: DropGuard,
+}
+
+impl RewardTracker {
+    pub fn spawn(
+        validator: &AppConfigValidator,
+        price_feed: Option<&AppConfigPriceFeed>,
+        subscription: Arc<Subscription>,
+        dirs: &ProjectDirs,
+    ) -> Self {
+        let cancellation_token = CancellationToken::new();
+
+        let ledger = RewardsLedger::new(&dirs.rewards_ledger);
+        let booked = match ledger.load() {
+            Ok(entries) => entries
+                .into_iter()
+                .filter_map(|entry| match entry.source {
+                    RewardSource::Elector { election_ids } => Some(election_ids),
+                    RewardSource::DePool { .. } => None,
+                })
+                .flatten()
+                .collect(),
+            Err(e) => {
+                tracing::warn!("failed to load rewards: {e:?}");
+                HashSet::new()
+            }
+        };
+
+        let mut accountant = Accountant {
+            subscription,
+            ledger,
+            performance: PerformanceHistory::new(&dirs.performance_history),
+            price_feed: price_feed.cloned().map(PriceFeed::new),
+            booked,
+        };
+
+        tokio::spawn({
+            let validator = validator.clone();
+            let cancellation_token = cancellation_token.clone();
+            async move {
+                tokio::select! {
+                    _ = accountant.run(&validator) => {},
+                    _ = cancellation_token.cancelled() => {},
+                }
+            }
+        });
+
+        tracing::info!("started rewards tracker");
+
+        Self {
+            validator: validator.clone(),
+            price_feed: price_feed.cloned(),
+            _cancellation_guard: cancellation_token.drop_guard(),
+        }
+    }
+
+    pub fn matches(
+        &self,
+        validator: &AppConfigValidator,
+        price_feed: Option<&AppConfigPriceFeed>,
+    ) -> bool {
+        &self.validator == validator && self.price_feed.as_ref() == price_feed
+    }
+}
+
+struct Accountant {
+    subscription: Arc<Subscription>,
+    ledger: RewardsLedger,
+    performance: PerformanceHistory,
+    price_feed: Option<PriceFeed>,
+    /// Elections with already recorded rewards
+    booked: HashSet<u32>,
+}
+
+impl Accountant {
+    async fn run(&mut self, validator: &AppConfigValidator) {
+        let address = match validator {
+            AppConfigValidator::Single(single) => &single.address,
+            AppConfigValidator::DePool(depool) => &depool.depool,
+        };
+
+        let mut transactions = self.subscription.subscribe(address);
+        while let Some(tx) = transactions.recv().await {
+            let res = match validator {
+                AppConfigValidator::Single(_) => self.handle_wallet_transaction(address, &tx).await,
+                AppConfigValidator::DePool(_) => self.handle_depool_transaction(&tx).await,
+            };
+            if let Err(e) = res {
+                tracing::error!(tx_hash = ?tx.hash, "failed to record rewards: {e:?}");
+            }
+        }
+    }
+
+    async fn handle_wallet_transaction(
+        &mut self,
+        address: &ton_block::MsgAddressInt,
+        tx: &TransactionWithHash,
+    ) -> Result<()> {
+        // Elector `recover_stake` answer
+        const RECOVER_STAKE_OK: u32 = 0xf96f7324;
+
+        let Some(msg) = tx.data.read_in_msg()? else {
+            return Ok(());
+        };
+        let Some(header) = msg.int_header() else {
+            return Ok(());
+        };
+        let Some(mut body) = msg.body() else {
+            return Ok(());
+        };
+        if header.bounced || body.get_next_u32().ok() != Some(RECOVER_STAKE_OK) {
+            return Ok(());
+        }
+
+        let addresses = self.subscription.get_system_addresses().await?;
+        let elector = Elector::new(addresses.elector, self.subscription.clone());
+        if header.src != ton_block::MsgAddressIntOrNone::Some(elector.address().clone()) {
+            return Ok(());
+        }
+        let amount = header.value.grams.as_u128();
+
+        // NOTE: recovered amount contains all unfrozen stakes with their bonuses
+        let frozen = elector
+            .get_data()
+            .await?
+            .frozen_stakes(address)
+            .into_iter()
+            .map(|frozen| frozen.election_id)
+            .collect::<HashSet<_>>();
+
+        let mut election_ids = Vec::new();
+        let mut stake = 0;
+        for round in self.performance.load()?.into_values() {
+            if round.stake > 0
+                && round.round_end <= tx.data.now
+                && !frozen.contains(&round.election_id)
+                && !self.booked.contains(&round.election_id)
+            {
+                election_ids.push(round.election_id);
+                stake += round.stake;
+            }
+        }
+        let reward = (!election_ids.is_empty()).then(|| amount.saturating_sub(stake));
+
+        self.booked.extend(election_ids.iter().copied());
+        self.record(tx, RewardSource::Elector { election_ids }, amount, reward)
+            .await
+    }
+
+    async fn handle_depool_transaction(&mut self, tx: &TransactionWithHash) -> Result<()> {
+        for event in DePoolEvent::parse_transaction(&tx.data)? {
+            if let DePoolEvent::RoundCompleted(round) = event {
+                let source = RewardSource::DePool { round_id: round.id };
+                let amount = round.recovered_stake as u128;
+                let reward = Some(round.participant_reward as u128);
+                self.record(tx, source, amount, reward).await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn record(
+        &self,
+        tx: &TransactionWithHash,
+        source: RewardSource,
+        amount: u128,
+        reward: Option<u128>,
+    ) -> Result<()> {
+        let price = match &self.price_feed {
+            Some(price_feed) => match price_feed.fetch().await {
+                Ok(price) => Some(price),
+                Err(e) => {
+                    tracing::warn!("failed to fetch token price: {e:?}");
+                    None
+                }
+            },
+            None => None,
+        };
+
+        tracing::info!(
+            amount = %Tokens(amount),
+            reward = ?reward.map(|reward| Tokens(reward).to_string()),
+            ?source,
+            "received rewards"
+        );
+
+        self.ledger.append(&RewardEntry {
+            timestamp: tx.data.now,
+            tx_hash: hex::encode(tx.hash.as_slice()),
+            source,
+            amount,
+            reward,
+            price,
+        })
+    }
+}
+
+struct PriceFeed {
+    params: AppConfigPriceFeed,
+    client: reqwest::Client,
+}
+
+impl PriceFeed {
+    fn new(params: AppConfigPriceFeed) -> Self {
+        Self {
+            params,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    async fn fetch(&self) -> Result<TokenPrice> {
+        let response = self
+            .client
+            .get(self.params.url.clone())
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+
+        let response = serde_json::from_str::<serde_json::Value>(&response)
+            .context("invalid price feed response")?;
+        let value = match response.pointer(&self.params.pointer) {
+            Some(serde_json::Value::Number(value)) => value.as_f64(),
+            Some(serde_json::Value::String(value)) => value.parse().ok(),
+            _ => None,
+        }
+        .context("price not found in the price feed response")?;
+
+        Ok(TokenPrice {
+            value,
+            currency: self.params.currency.clone(),
+        })
+    }
+}
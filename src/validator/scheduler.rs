@@ -0,0 +1,242 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use broxus_util::now;
+use rand::Rng;
+use tokio::sync::Mutex;
+use tokio_util::sync::{CancellationToken, DropGuard};
+
+use super::backup;
+use crate::config::{
+    AppConfig, AppConfigScheduledTask, AppConfigValidator, AppConfigValidatorDePool, NodeConfig,
+    ScheduledTask,
+};
+use crate::contracts::*;
+use crate::dirs::ProjectDirs;
+use crate::network::Subscription;
+use crate::notifications::{Event, Notifier};
+use crate::util::{system, Tokens};
+
+/// Background tasks which run the configured recurring tasks
+pub struct Scheduler {
+    tasks: Vec<AppConfigScheduledTask>,
+    validator: AppConfigValidator,
+    _cancellation_guard: DropGuard,
+}
+
+impl Scheduler {
+    pub fn spawn(
+        tasks: &[AppConfigScheduledTask],
+        validator: &AppConfigValidator,
+        subscription: Arc<Subscription>,
+        dirs: &ProjectDirs,
+        notifier: Notifier,
+        guard: Arc<Mutex<()>>,
+        dry_run: bool,
+    ) -> Self {
+        let cancellation_token = CancellationToken::new();
+
+        let runner = Arc::new(TaskRunner {
+            validator: validator.clone(),
+            subscription,
+            dirs: dirs.clone(),
+            notifier,
+            guard,
+            dry_run,
+        });
+
+        for task in tasks {
+            let task = task.clone();
+            let runner = runner.clone();
+            let cancellation_token = cancellation_token.clone();
+            tokio::spawn(async move {
+                tokio::select! {
+                    _ = runner.run_scheduled(&task) => {},
+                    _ = cancellation_token.cancelled() => {},
+                }
+            });
+        }
+
+        tracing::info!(tasks = tasks.len(), "started scheduler");
+
+        Self {
+            tasks: tasks.to_vec(),
+            validator: validator.clone(),
+            _cancellation_guard: cancellation_token.drop_guard(),
+        }
+    }
+
+    pub fn matches(
+        &self,
+        tasks: &[AppConfigScheduledTask],
+        validator: &AppConfigValidator,
+    ) -> bool {
+        self.tasks == tasks && &self.validator == validator
+    }
+}
+
+struct TaskRunner {
+    validator: AppConfigValidator,
+    subscription: Arc<Subscription>,
+    dirs: ProjectDirs,
+    notifier: Notifier,
+    guard: Arc<Mutex<()>>,
+    dry_run: bool,
+}
+
+impl TaskRunner {
+    async fn run_scheduled(&self, task: &AppConfigScheduledTask) {
+        let name = task.task.name();
+        loop {
+            let now = now();
+            let Some(next) = task.cron.next_after(now) else {
+                tracing::warn!(task = name, cron = %task.cron, "cron expression never matches");
+                return;
+            };
+
+            // NOTE: jitter spreads the load when many nodes use the same schedule
+            let jitter = if task.jitter.is_zero() {
+                Duration::ZERO
+            } else {
+                rand::thread_rng().gen_range(Duration::ZERO..=task.jitter)
+            };
+            tracing::debug!(task = name, next, ?jitter, "scheduled task");
+            tokio::time::sleep(Duration::from_secs((next - now) as u64) + jitter).await;
+
+            tracing::info!(task = name, "running scheduled task");
+            let started_at = Instant::now();
+            match self.run(&task.task).await {
+                Ok(()) => tracing::info!(
+                    task = name,
+                    elapsed_ms = started_at.elapsed().as_millis() as u64,
+                    "scheduled task completed"
+                ),
+                Err(e) => tracing::error!(task = name, "scheduled task failed: {e:?}"),
+            }
+        }
+    }
+
+    async fn run(&self, task: &ScheduledTask) -> Result<()> {
+        match task {
+            ScheduledTask::BalanceReport => self.report_balances().await,
+            ScheduledTask::DbSizeCheck { max_size_gb } => self.check_db_size(*max_size_gb).await,
+            ScheduledTask::Ticktock => self.send_ticktock().await,
+            ScheduledTask::Backup {
+                dir,
+                destination: Some(destination),
+                keep,
+            } => {
+                anyhow::ensure!(
+                    dir.is_none(),
+                    "backup `dir` and `destination` are exclusive"
+                );
+                let config = AppConfig::load(&self.dirs.app_config)?
+                    .backup
+                    .context("backup section not found in the app config")?;
+                backup::make_remote_backup(&self.dirs, &config, destination, *keep)
+                    .await
+                    .map(|_| ())
+            }
+            ScheduledTask::Backup {
+                dir,
+                destination: None,
+                keep,
+            } => {
+                let dir = dir
+                    .clone()
+                    .unwrap_or_else(|| self.dirs.root.join("backups"));
+                backup::make_local_backup(&self.dirs, &dir, *keep).map(|_| ())
+            }
+        }
+    }
+
+    async fn report_balances(&self) -> Result<()> {
+        let wallet = self.get_balance(self.validator.wallet_address()).await?;
+        let depool = match &self.validator {
+            AppConfigValidator::DePool(depool) => Some(self.get_balance(&depool.depool).await?),
+            AppConfigValidator::Single(_) => None,
+        };
+
+        match depool {
+            Some(depool) => {
+                tracing::info!(wallet = %Tokens(wallet), depool = %Tokens(depool), "balances")
+            }
+            None => tracing::info!(wallet = %Tokens(wallet), "balances"),
+        }
+        self.notifier
+            .notify(Event::BalanceReport { wallet, depool });
+        Ok(())
+    }
+
+    async fn get_balance(&self, address: &ton_block::MsgAddressInt) -> Result<u128> {
+        Ok(self
+            .subscription
+            .get_account_state(address)
+            .await?
+            .map(|state| state.storage.balance.grams.as_u128())
+            .unwrap_or_default())
+    }
+
+    async fn check_db_size(&self, max_size_gb: Option<u64>) -> Result<()> {
+        let db_path = NodeConfig::load(&self.dirs.node_config)?
+            .get_internal_db_path()?
+            .context("node DB path is not configured")?;
+
+        let size = tokio::task::spawn_blocking(move || system::dir_size(db_path))
+            .await?
+            .context("failed to compute node DB size")?;
+        tracing::info!(size, "node DB size");
+
+        if let Some(max_size) = max_size_gb.map(|gb| gb << 30) {
+            if size > max_size {
+                tracing::warn!(size, max_size, "node DB is too large");
+                self.notifier.notify(Event::LargeDb { size, max_size });
+            }
+        }
+        Ok(())
+    }
+
+    async fn send_ticktock(&self) -> Result<()> {
+        let AppConfigValidator::DePool(params) = &self.validator else {
+            anyhow::bail!("ticktock is only supported for DePool validators");
+        };
+
+        if self.dry_run {
+            tracing::info!(depool = %params.depool, "dry run: ticktock was not sent");
+            return Ok(());
+        }
+
+        send_ticktock(params, &self.dirs, &self.subscription, &self.guard).await
+    }
+}
+
+/// Sends ticktock to the DePool from the validator wallet
+pub(super) async fn send_ticktock(
+    params: &AppConfigValidatorDePool,
+    dirs: &ProjectDirs,
+    subscription: &Arc<Subscription>,
+    guard: &Mutex<()>,
+) -> Result<()> {
+    let depool = DePool::new(
+        params.depool_type,
+        params.depool.clone(),
+        subscription.clone(),
+    );
+
+    let keypair = dirs.load_validator_keys()?;
+    let wallet = Wallet::new(
+        params.owner.workchain_id() as i8,
+        keypair,
+        subscription.clone(),
+    );
+    anyhow::ensure!(
+        wallet.address() == &params.owner,
+        "validator wallet address mismatch"
+    );
+
+    // Prevent shutdown during sending ticktock
+    let _guard = guard.lock().await;
+    wallet.call(depool.ticktock()?).await?;
+    Ok(())
+}
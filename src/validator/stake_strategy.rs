@@ -0,0 +1,97 @@
+use crate::config::AppConfigStakeStrategy;
+
+/// Computes the stake for the elections
+pub trait StakeStrategy: Send + Sync {
+    /// Wallet balance which is required to compute the stake
+    fn required_balance(&self, ctx: &StakeContext<'_>) -> u128;
+
+    /// Stake for the current elections
+    fn compute_stake(&self, balance: u128, ctx: &StakeContext<'_>) -> u128;
+}
+
+pub struct StakeContext<'a> {
+    /// Stake factor of the validator (16.16 fixed point)
+    pub stake_factor: u32,
+    /// Max number of validators in the set (from the config param 16)
+    pub max_validators: usize,
+    /// Min validator stake (from the config param 17)
+    pub min_stake: u128,
+    /// Stakes of other participants in the current elections
+    pub participant_stakes: &'a [u128],
+}
+
+impl StakeContext<'_> {
+    /// Estimates the min stake among the elected validators
+    fn expected_min_stake(&self) -> u128 {
+        let mut stakes = self.participant_stakes.to_vec();
+        stakes.sort_unstable_by(|a, b| b.cmp(a));
+
+        // NOTE: current validator will take one place in the set
+        let last = self.max_validators.saturating_sub(2);
+        let stake = stakes.get(last).or(stakes.last()).copied();
+        std::cmp::max(stake.unwrap_or_default(), self.min_stake)
+    }
+}
+
+pub fn make_stake_strategy(
+    config: Option<&AppConfigStakeStrategy>,
+    stake_per_round: u64,
+) -> Box<dyn StakeStrategy> {
+    match config {
+        None | Some(AppConfigStakeStrategy::Fixed) => Box::new(FixedStake {
+            stake: stake_per_round as u128,
+        }),
+        Some(AppConfigStakeStrategy::Percent { percent, reserve }) => Box::new(PercentStake {
+            percent: std::cmp::min(*percent, 100) as u128,
+            reserve: *reserve as u128,
+        }),
+        Some(AppConfigStakeStrategy::MaxFactorAware { reserve }) => Box::new(MaxFactorAwareStake {
+            reserve: *reserve as u128,
+        }),
+    }
+}
+
+struct FixedStake {
+    stake: u128,
+}
+
+impl StakeStrategy for FixedStake {
+    fn required_balance(&self, _: &StakeContext<'_>) -> u128 {
+        self.stake
+    }
+
+    fn compute_stake(&self, _: u128, _: &StakeContext<'_>) -> u128 {
+        self.stake
+    }
+}
+
+struct PercentStake {
+    percent: u128,
+    reserve: u128,
+}
+
+impl StakeStrategy for PercentStake {
+    fn required_balance(&self, ctx: &StakeContext<'_>) -> u128 {
+        self.reserve + ctx.min_stake * 100 / std::cmp::max(self.percent, 1)
+    }
+
+    fn compute_stake(&self, balance: u128, _: &StakeContext<'_>) -> u128 {
+        balance.saturating_sub(self.reserve) * self.percent / 100
+    }
+}
+
+struct MaxFactorAwareStake {
+    reserve: u128,
+}
+
+impl StakeStrategy for MaxFactorAwareStake {
+    fn required_balance(&self, ctx: &StakeContext<'_>) -> u128 {
+        self.reserve + ctx.min_stake
+    }
+
+    fn compute_stake(&self, balance: u128, ctx: &StakeContext<'_>) -> u128 {
+        // Elector returns the part of the stake which exceeds `min_stake * max_factor`
+        let max_effective_stake = ctx.expected_min_stake() * ctx.stake_factor as u128 / 65536;
+        std::cmp::min(balance.saturating_sub(self.reserve), max_effective_stake)
+    }
+}
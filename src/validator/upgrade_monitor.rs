@@ -0,0 +1,150 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use tokio::sync::{broadcast, watch};
+use tokio_util::sync::{CancellationToken, DropGuard};
+
+use crate::network::{ConfigWithId, Subscription};
+use crate::notifications::{Event, Notifier};
+
+/// Config param with the global version and capabilities
+const GLOBAL_VERSION_PARAM: u32 = 8;
+
+/// Background task which checks that the node supports the network global version
+pub struct UpgradeMonitor {
+    subscription_tx: watch::Sender<Arc<Subscription>>,
+    _cancellation_guard: DropGuard,
+}
+
+impl UpgradeMonitor {
+    pub fn spawn(subscription: Arc<Subscription>, notifier: Notifier) -> Self {
+        let cancellation_token = CancellationToken::new();
+        let (subscription_tx, subscription) = watch::channel(subscription);
+
+        let mut state = UpgradeMonitorState {
+            subscription,
+            notifier,
+            alerted: None,
+        };
+
+        tokio::spawn({
+            let cancellation_token = cancellation_token.clone();
+            async move {
+                tokio::select! {
+                    _ = state.run() => {},
+                    _ = cancellation_token.cancelled() => {},
+                }
+            }
+        });
+
+        tracing::info!("started upgrade monitor");
+
+        Self {
+            subscription_tx,
+            _cancellation_guard: cancellation_token.drop_guard(),
+        }
+    }
+
+    /// Switches the monitor to the new subscription
+    pub fn set_subscription(&self, subscription: &Arc<Subscription>) {
+        self.subscription_tx.send_if_modified(|current| {
+            let modified = !Arc::ptr_eq(current, subscription);
+            *current = subscription.clone();
+            modified
+        });
+    }
+}
+
+struct UpgradeMonitorState {
+    subscription: watch::Receiver<Arc<Subscription>>,
+    notifier: Notifier,
+    /// Last global version and capabilities which were reported as unsupported
+    alerted: Option<(u32, u64)>,
+}
+
+impl UpgradeMonitorState {
+    async fn run(&mut self) {
+        loop {
+            let subscription = self.subscription.borrow_and_update().clone();
+            let mut config_events = subscription.subscribe_config_changes();
+
+            match subscription.get_blockchain_config().await {
+                Ok(config) => self.check(&config).await,
+                Err(e) => tracing::error!("failed to get blockchain config: {e:?}"),
+            }
+
+            loop {
+                let event = tokio::select! {
+                    event = config_events.recv() => event,
+                    changed = self.subscription.changed() => match changed {
+                        Ok(()) => break,
+                        Err(_) => return,
+                    },
+                };
+                let event = match event {
+                    Ok(event) => event,
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    // NOTE: subscription is closed only after it was replaced
+                    Err(broadcast::error::RecvError::Closed) => {
+                        match self.subscription.changed().await {
+                            Ok(()) => break,
+                            Err(_) => return,
+                        }
+                    }
+                };
+                if !event.changed_params.contains(&GLOBAL_VERSION_PARAM) {
+                    continue;
+                }
+
+                if let Ok(global) = event.config.config.get_global_version() {
+                    tracing::warn!(
+                        version = global.version,
+                        capabilities = global.capabilities,
+                        "network global version changed"
+                    );
+                    self.notifier.notify(Event::NetworkVersionChanged {
+                        version: global.version,
+                        capabilities: global.capabilities,
+                    });
+                }
+                self.check(&event.config).await;
+            }
+        }
+    }
+
+    async fn check(&mut self, config: &ConfigWithId) {
+        if let Err(e) = self.check_impl(config).await {
+            tracing::error!("failed to check node global version: {e:?}");
+        }
+    }
+
+    async fn check_impl(&mut self, config: &ConfigWithId) -> Result<()> {
+        let required = config.config.get_global_version()?;
+        let subscription = self.subscription.borrow().clone();
+        let node = subscription.udp_rpc()?.get_capabilities().await?;
+
+        let supported =
+            node.version >= required.version && required.capabilities & !node.capabilities == 0;
+        if supported {
+            self.alerted = None;
+            return Ok(());
+        }
+
+        tracing::error!(
+            node_version = node.version,
+            required_version = required.version,
+            missing_capabilities = required.capabilities & !node.capabilities,
+            "node doesn't support the network global version"
+        );
+
+        let key = (required.version, required.capabilities);
+        if self.alerted != Some(key) {
+            self.notifier.notify(Event::UpgradeRequired {
+                node_version: node.version,
+                required_version: required.version,
+            });
+            self.alerted = Some(key);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,166 @@
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use tokio_util::sync::{CancellationToken, DropGuard};
+
+use crate::config::{AppConfig, AppConfigWatchdog};
+use crate::network::{NodeStats, NodeTcpRpc};
+use crate::notifications::{Event, Notifier};
+use crate::util::system;
+
+/// Background task which restarts the stuck node
+pub struct Watchdog {
+    params: AppConfigWatchdog,
+    _cancellation_guard: DropGuard,
+}
+
+impl Watchdog {
+    pub fn spawn(
+        params: &AppConfigWatchdog,
+        app_config: PathBuf,
+        notifier: Notifier,
+        dry_run: bool,
+    ) -> Self {
+        let cancellation_token = CancellationToken::new();
+
+        let mut state = WatchdogState {
+            params: params.clone(),
+            app_config,
+            notifier,
+            dry_run,
+            node_tcp_rpc: None,
+            last_mc_seqno: 0,
+            last_progress_at: Instant::now(),
+            restarts: VecDeque::new(),
+            limit_reached: false,
+        };
+
+        tokio::spawn({
+            let cancellation_token = cancellation_token.clone();
+            async move {
+                tokio::select! {
+                    _ = state.run() => {},
+                    _ = cancellation_token.cancelled() => {},
+                }
+            }
+        });
+
+        tracing::info!(service = %params.service, "started node watchdog");
+
+        Self {
+            params: params.clone(),
+            _cancellation_guard: cancellation_token.drop_guard(),
+        }
+    }
+
+    pub fn params(&self) -> &AppConfigWatchdog {
+        &self.params
+    }
+}
+
+struct WatchdogState {
+    params: AppConfigWatchdog,
+    app_config: PathBuf,
+    notifier: Notifier,
+    dry_run: bool,
+    node_tcp_rpc: Option<NodeTcpRpc>,
+    last_mc_seqno: u32,
+    last_progress_at: Instant,
+    restarts: VecDeque<Instant>,
+    limit_reached: bool,
+}
+
+impl WatchdogState {
+    async fn run(&mut self) {
+        let mut interval = tokio::time::interval(self.params.interval);
+        loop {
+            interval.tick().await;
+
+            let reason = match self.check().await {
+                Ok(()) => "node stopped applying masterchain blocks",
+                Err(e) => {
+                    tracing::warn!("node control server is not available: {e:?}");
+                    self.node_tcp_rpc = None;
+                    "node control server stopped answering"
+                }
+            };
+
+            if self.last_progress_at.elapsed() < self.params.stuck_timeout {
+                continue;
+            }
+
+            if let Err(e) = self.restart(reason).await {
+                tracing::error!("failed to restart node: {e:?}");
+                self.notifier.notify(Event::Error {
+                    message: format!("failed to restart node: {e:#}"),
+                });
+            }
+        }
+    }
+
+    async fn check(&mut self) -> Result<()> {
+        let node_tcp_rpc = match self.node_tcp_rpc.take() {
+            Some(node_tcp_rpc) => node_tcp_rpc,
+            None => {
+                let config = AppConfig::load(&self.app_config)?;
+                NodeTcpRpc::new(config.control()?).await?
+            }
+        };
+
+        match node_tcp_rpc.get_stats().await? {
+            NodeStats::Running(stats) => {
+                if stats.last_mc_block.seq_no != self.last_mc_seqno {
+                    self.last_mc_seqno = stats.last_mc_block.seq_no;
+                    self.last_progress_at = Instant::now();
+                }
+            }
+            // NOTE: only control server availability is checked during sync
+            NodeStats::NotReady(_) => self.last_progress_at = Instant::now(),
+        }
+
+        self.node_tcp_rpc = Some(node_tcp_rpc);
+        Ok(())
+    }
+
+    async fn restart(&mut self, reason: &'static str) -> Result<()> {
+        const DAY: Duration = Duration::from_secs(86400);
+
+        let now = Instant::now();
+        while matches!(self.restarts.front(), Some(at) if now.duration_since(*at) > DAY) {
+            self.restarts.pop_front();
+        }
+
+        if let Some(last_restart) = self.restarts.back() {
+            if now.duration_since(*last_restart) < self.params.restart_cooldown {
+                tracing::debug!(reason, "node restart is delayed by cooldown");
+                return Ok(());
+            }
+        }
+
+        if self.restarts.len() >= self.params.max_daily_restarts {
+            tracing::warn!(reason, "node restarts limit reached");
+            if !std::mem::replace(&mut self.limit_reached, true) {
+                self.notifier.notify(Event::Error {
+                    message: format!("node restarts limit reached ({reason})"),
+                });
+            }
+            return Ok(());
+        }
+        self.limit_reached = false;
+
+        tracing::warn!(reason, service = %self.params.service, "restarting stuck node");
+        if !self.dry_run {
+            system::systemd_restart_service(&self.params.service).await?;
+        }
+
+        self.restarts.push_back(now);
+        self.node_tcp_rpc = None;
+        self.last_progress_at = now;
+        self.notifier.notify(Event::NodeRestarted {
+            reason: reason.to_owned(),
+        });
+        Ok(())
+    }
+}